@@ -0,0 +1,12 @@
+-- Anonymous usage statistics submitted by opted-in CLI installations
+
+CREATE TABLE telemetry_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    installation_id TEXT NOT NULL,
+    version TEXT NOT NULL,
+    os TEXT NOT NULL,
+    counters JSONB NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_telemetry_reports_received_at ON telemetry_reports(received_at);
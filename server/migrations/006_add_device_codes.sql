@@ -0,0 +1,18 @@
+-- Device authorization requests for the CLI login (OAuth 2.0 device-code flow)
+-- The CLI polls with the device code while the user approves the user code
+-- in a browser; once approved, a CLI access token is issued.
+
+CREATE TABLE IF NOT EXISTS device_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    device_code TEXT NOT NULL,
+    user_code TEXT NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    approved_at TIMESTAMPTZ,
+    consumed BOOLEAN NOT NULL DEFAULT FALSE,
+    CONSTRAINT unique_device_code UNIQUE (device_code),
+    CONSTRAINT unique_user_code UNIQUE (user_code)
+);
+
+CREATE INDEX IF NOT EXISTS device_codes_expires_at_idx ON device_codes(expires_at);
@@ -0,0 +1,272 @@
+//! CLI device login API
+//!
+//! Implements the OAuth 2.0 device authorization flow (RFC 8628) for the CLI:
+//! 1. The CLI requests a device code and shows the user code and verification URL
+//! 2. The user opens `/device`, signs in and approves the user code
+//! 3. The CLI polls the token endpoint until it receives an access token
+//!
+//! The access token identifies the user to `/api/cli/session`, which returns
+//! the subscription claims the CLI uses to unlock paid features.
+
+use crate::api::AppState;
+use crate::auth::session::get_user_from_session;
+use crate::db::models::{SubscriptionStatus, User};
+use crate::db::operations::{CliTokenOps, DeviceCodeOps, SubscriptionOps, UserOps};
+use crate::errors::ServerError;
+use axum::{
+    extract::{Query, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tower_cookies::Cookies;
+use uuid::Uuid;
+
+/// How long a device code can be approved
+const DEVICE_CODE_LIFETIME_SECS: i64 = 15 * 60;
+
+/// Minimum seconds between polls of the token endpoint
+const POLL_INTERVAL_SECS: u64 = 5;
+
+/// Lifetime of issued CLI access tokens
+const ACCESS_TOKEN_LIFETIME_DAYS: i64 = 30;
+
+/// Characters of user codes (no vowels or look-alike characters)
+const USER_CODE_ALPHABET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+
+/// Response to a device code request
+#[derive(Debug, Serialize)]
+pub struct DeviceCodeResponse {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: String,
+    pub expires_in: u64,
+    pub interval: u64,
+}
+
+/// Token request sent by the polling CLI
+#[derive(Debug, Deserialize)]
+pub struct TokenRequest {
+    pub device_code: String,
+}
+
+/// Subscription claims of a logged-in user
+#[derive(Debug, Serialize)]
+pub struct SessionClaims {
+    pub email: String,
+    pub name: Option<String>,
+    /// Active plan: "free", "plus" or "pro"
+    pub subscription: String,
+    /// Unix timestamp when the access token expires
+    pub expires_at: i64,
+}
+
+/// Generate a user code like `BCDF-GHJK`
+fn generate_user_code() -> String {
+    let bytes = Uuid::new_v4().into_bytes();
+    let chars: String = bytes[..8]
+        .iter()
+        .map(|b| USER_CODE_ALPHABET[*b as usize % USER_CODE_ALPHABET.len()] as char)
+        .collect();
+    format!("{}-{}", &chars[..4], &chars[4..])
+}
+
+/// Generate an unguessable opaque token
+fn generate_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Normalize a user code typed by the user
+fn normalize_user_code(code: &str) -> String {
+    let chars: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if chars.len() == 8 {
+        format!("{}-{}", &chars[..4], &chars[4..])
+    } else {
+        chars
+    }
+}
+
+/// Public base URL of the server, derived from the request
+fn base_url(headers: &HeaderMap) -> String {
+    let host = headers
+        .get("host")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("termineer.io");
+    let scheme = if host.starts_with("localhost") || host.starts_with("127.0.0.1") {
+        "http"
+    } else {
+        "https"
+    };
+    format!("{scheme}://{host}")
+}
+
+/// The plan of a user's active subscription
+async fn subscription_plan(state: &AppState, user: &User) -> Result<String, ServerError> {
+    let subscription = SubscriptionOps::find_by_user_id(&state.db_pool, user.id).await?;
+    Ok(match subscription {
+        Some(subscription)
+            if matches!(
+                subscription.status,
+                SubscriptionStatus::Active | SubscriptionStatus::Trialing
+            ) =>
+        {
+            subscription.plan_id
+        }
+        _ => "free".to_string(),
+    })
+}
+
+/// OAuth-style error response used while polling
+fn oauth_error(status: StatusCode, error: &str) -> Response {
+    (status, Json(json!({ "error": error }))).into_response()
+}
+
+/// Start a device login
+pub async fn request_code(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<DeviceCodeResponse>, ServerError> {
+    let device_code = generate_token();
+    let user_code = generate_user_code();
+    let expires_at = Utc::now() + Duration::seconds(DEVICE_CODE_LIFETIME_SECS);
+    DeviceCodeOps::create(&state.db_pool, &device_code, &user_code, expires_at).await?;
+
+    let verification_uri = format!("{}/device", base_url(&headers));
+    Ok(Json(DeviceCodeResponse {
+        verification_uri_complete: format!("{verification_uri}?code={user_code}"),
+        verification_uri,
+        device_code,
+        user_code,
+        expires_in: DEVICE_CODE_LIFETIME_SECS as u64,
+        interval: POLL_INTERVAL_SECS,
+    }))
+}
+
+/// Exchange an approved device code for an access token
+pub async fn poll_token(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<TokenRequest>,
+) -> Result<Response, ServerError> {
+    let Some(device) =
+        DeviceCodeOps::find_by_device_code(&state.db_pool, &request.device_code).await?
+    else {
+        return Ok(oauth_error(StatusCode::BAD_REQUEST, "invalid_grant"));
+    };
+
+    if device.consumed {
+        return Ok(oauth_error(StatusCode::BAD_REQUEST, "invalid_grant"));
+    }
+    let Some(user_id) = device.user_id.filter(|_| device.approved_at.is_some()) else {
+        if device.expires_at < Utc::now() {
+            return Ok(oauth_error(StatusCode::BAD_REQUEST, "expired_token"));
+        }
+        return Ok(oauth_error(StatusCode::BAD_REQUEST, "authorization_pending"));
+    };
+    if !DeviceCodeOps::consume(&state.db_pool, device.id).await? {
+        return Ok(oauth_error(StatusCode::BAD_REQUEST, "invalid_grant"));
+    }
+
+    let user = UserOps::find_by_id(&state.db_pool, user_id)
+        .await?
+        .ok_or_else(|| ServerError::NotFound("User not found".to_string()))?;
+    let token = generate_token();
+    let expires_at = Utc::now() + Duration::days(ACCESS_TOKEN_LIFETIME_DAYS);
+    CliTokenOps::create(&state.db_pool, user.id, &token, expires_at).await?;
+
+    let claims = SessionClaims {
+        subscription: subscription_plan(&state, &user).await?,
+        email: user.email,
+        name: user.name,
+        expires_at: expires_at.timestamp(),
+    };
+    Ok(Json(json!({
+        "access_token": token,
+        "token_type": "Bearer",
+        "claims": claims,
+    }))
+    .into_response())
+}
+
+/// Current claims of the user owning a CLI access token
+pub async fn get_session(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response, ServerError> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let Some(token) = token else {
+        return Ok(oauth_error(StatusCode::UNAUTHORIZED, "invalid_token"));
+    };
+    let Some(access_token) = CliTokenOps::find_valid(&state.db_pool, token).await? else {
+        return Ok(oauth_error(StatusCode::UNAUTHORIZED, "invalid_token"));
+    };
+    let Some(user) = UserOps::find_by_id(&state.db_pool, access_token.user_id).await? else {
+        return Ok(oauth_error(StatusCode::UNAUTHORIZED, "invalid_token"));
+    };
+
+    Ok(Json(SessionClaims {
+        subscription: subscription_plan(&state, &user).await?,
+        email: user.email,
+        name: user.name,
+        expires_at: access_token.expires_at.timestamp(),
+    })
+    .into_response())
+}
+
+/// Query parameters of the approval page
+#[derive(Debug, Deserialize)]
+pub struct ApproveParams {
+    #[serde(default)]
+    code: Option<String>,
+}
+
+/// Browser page where a signed-in user approves a CLI login
+pub async fn approve_page(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ApproveParams>,
+    cookies: Cookies,
+) -> Result<Response, ServerError> {
+    let Some(session_user) = get_user_from_session(&cookies) else {
+        return Ok(Redirect::to("/auth/google/login").into_response());
+    };
+
+    let Some(code) = params.code.filter(|code| !code.trim().is_empty()) else {
+        return Ok(Html(
+            r#"<h1>Log in to Termineer CLI</h1>
+<form method="get" action="/device">
+  <label>Enter the code shown in your terminal: <input name="code" autofocus></label>
+  <button type="submit">Approve</button>
+</form>"#,
+        )
+        .into_response());
+    };
+
+    let user = UserOps::find_or_create_from_email(
+        &state.db_pool,
+        &session_user.email,
+        session_user.name.clone(),
+    )
+    .await?;
+    let approved = DeviceCodeOps::approve(&state.db_pool, &normalize_user_code(&code), user.id)
+        .await?
+        .is_some();
+
+    let body = if approved {
+        "<h1>CLI login approved</h1><p>You can close this page and return to your terminal.</p>"
+    } else {
+        "<h1>Invalid or expired code</h1><p>Run <code>termineer login</code> again to get a new code.</p>"
+    };
+    Ok(Html(body).into_response())
+}
+
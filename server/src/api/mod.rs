@@ -3,6 +3,8 @@
 //! Handles API endpoints for the application.
 
 pub mod auth;
+pub mod device;
+pub mod telemetry;
 
 use crate::config::Config;
 use sqlx::PgPool;
@@ -0,0 +1,57 @@
+//! Telemetry API
+//!
+//! Receives anonymous usage counters from CLI installations that opted in.
+
+use crate::api::AppState;
+use crate::db::operations::TelemetryOps;
+use crate::errors::ServerError;
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Maximum number of counters accepted in one report
+const MAX_COUNTERS: usize = 500;
+
+/// Maximum length of identifiers and counter names
+const MAX_FIELD_LENGTH: usize = 128;
+
+/// A batch of counters sent by the CLI
+#[derive(Debug, Deserialize)]
+pub struct TelemetryReport {
+    pub installation_id: String,
+    pub version: String,
+    pub os: String,
+    pub counters: BTreeMap<String, u64>,
+}
+
+/// Store a telemetry report
+pub async fn submit_report(
+    State(state): State<Arc<AppState>>,
+    Json(report): Json<TelemetryReport>,
+) -> Result<StatusCode, ServerError> {
+    if report.counters.len() > MAX_COUNTERS {
+        return Err(ServerError::Validation("Too many counters".to_string()));
+    }
+    let fields = [&report.installation_id, &report.version, &report.os];
+    if fields
+        .into_iter()
+        .chain(report.counters.keys())
+        .any(|field| field.is_empty() || field.len() > MAX_FIELD_LENGTH)
+    {
+        return Err(ServerError::Validation("Invalid field length".to_string()));
+    }
+
+    let counters = serde_json::to_value(&report.counters)
+        .map_err(|e| ServerError::Internal(e.to_string()))?;
+    TelemetryOps::insert_report(
+        &state.db_pool,
+        &report.installation_id,
+        &report.version,
+        &report.os,
+        counters,
+    )
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -50,6 +50,30 @@ pub struct LicenseKey {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Pending or approved CLI device authorization
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct DeviceCode {
+    pub id: Uuid,
+    pub device_code: String,
+    pub user_code: String,
+    pub user_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub approved_at: Option<DateTime<Utc>>,
+    pub consumed: bool,
+}
+
+/// Access token used by the CLI after logging in
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct CliAccessToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub token: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used: bool,
+}
+
 /// Usage statistics for analytics
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct UsageStat {
@@ -3,7 +3,9 @@ use log::{error, info};
 use sqlx::{Error as SqlxError, PgPool};
 use uuid::Uuid;
 
-use crate::db::models::{LicenseKey, Subscription, SubscriptionStatus, User};
+use crate::db::models::{
+    CliAccessToken, DeviceCode, LicenseKey, Subscription, SubscriptionStatus, User,
+};
 use crate::errors::ServerError;
 
 /// Operations for User model
@@ -616,3 +618,177 @@ impl LicenseOps {
         Ok(license)
     }
 }
+
+/// Operations for anonymous telemetry reports
+pub struct TelemetryOps;
+
+impl TelemetryOps {
+    /// Store a telemetry report
+    pub async fn insert_report(
+        pool: &PgPool,
+        installation_id: &str,
+        version: &str,
+        os: &str,
+        counters: serde_json::Value,
+    ) -> Result<(), ServerError> {
+        sqlx::query(
+            r#"
+            INSERT INTO telemetry_reports (installation_id, version, os, counters)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(installation_id)
+        .bind(version)
+        .bind(os)
+        .bind(counters)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("Database error storing telemetry report: {}", e);
+            ServerError::Database(e.to_string())
+        })?;
+
+        Ok(())
+    }
+}
+
+/// Operations for CLI device authorization
+pub struct DeviceCodeOps;
+
+impl DeviceCodeOps {
+    /// Create a new pending device authorization
+    pub async fn create(
+        pool: &PgPool,
+        device_code: &str,
+        user_code: &str,
+        expires_at: chrono::DateTime<Utc>,
+    ) -> Result<DeviceCode, ServerError> {
+        sqlx::query_as::<_, DeviceCode>(
+            r#"
+            INSERT INTO device_codes (device_code, user_code, expires_at)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#,
+        )
+        .bind(device_code)
+        .bind(user_code)
+        .bind(expires_at)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("Database error creating device code: {}", e);
+            ServerError::Database(e.to_string())
+        })
+    }
+
+    /// Find a device authorization by the code the CLI polls with
+    pub async fn find_by_device_code(
+        pool: &PgPool,
+        device_code: &str,
+    ) -> Result<Option<DeviceCode>, ServerError> {
+        sqlx::query_as::<_, DeviceCode>("SELECT * FROM device_codes WHERE device_code = $1")
+            .bind(device_code)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                error!("Database error finding device code: {}", e);
+                ServerError::Database(e.to_string())
+            })
+    }
+
+    /// Approve a pending, unexpired device authorization for a user
+    ///
+    /// Returns None when no such authorization exists.
+    pub async fn approve(
+        pool: &PgPool,
+        user_code: &str,
+        user_id: Uuid,
+    ) -> Result<Option<DeviceCode>, ServerError> {
+        let device = sqlx::query_as::<_, DeviceCode>(
+            r#"
+            UPDATE device_codes
+            SET user_id = $1, approved_at = $2
+            WHERE user_code = $3 AND approved_at IS NULL AND expires_at > $2
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(Utc::now())
+        .bind(user_code)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("Database error approving device code: {}", e);
+            ServerError::Database(e.to_string())
+        })?;
+
+        if device.is_some() {
+            info!("Approved CLI device login for user: {}", user_id);
+        }
+        Ok(device)
+    }
+
+    /// Mark an approved authorization as exchanged for a token
+    pub async fn consume(pool: &PgPool, id: Uuid) -> Result<bool, ServerError> {
+        let result = sqlx::query(
+            "UPDATE device_codes SET consumed = true WHERE id = $1 AND consumed = false",
+        )
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("Database error consuming device code: {}", e);
+            ServerError::Database(e.to_string())
+        })?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+/// Operations for CLI access tokens
+pub struct CliTokenOps;
+
+impl CliTokenOps {
+    /// Issue a new access token
+    pub async fn create(
+        pool: &PgPool,
+        user_id: Uuid,
+        token: &str,
+        expires_at: chrono::DateTime<Utc>,
+    ) -> Result<CliAccessToken, ServerError> {
+        sqlx::query_as::<_, CliAccessToken>(
+            r#"
+            INSERT INTO cli_access_tokens (user_id, token, expires_at)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(token)
+        .bind(expires_at)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("Database error creating CLI token: {}", e);
+            ServerError::Database(e.to_string())
+        })
+    }
+
+    /// Find an unexpired access token
+    pub async fn find_valid(
+        pool: &PgPool,
+        token: &str,
+    ) -> Result<Option<CliAccessToken>, ServerError> {
+        sqlx::query_as::<_, CliAccessToken>(
+            "SELECT * FROM cli_access_tokens WHERE token = $1 AND expires_at > $2",
+        )
+        .bind(token)
+        .bind(Utc::now())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("Database error finding CLI token: {}", e);
+            ServerError::Database(e.to_string())
+        })
+    }
+}
@@ -13,11 +13,16 @@ mod templates;
 // Re-export AppState from api module to make it available at crate root
 pub use api::AppState;
 
-use axum::{response::IntoResponse, routing::get, Router};
+use axum::{
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
 use config::Config;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use templates::{IndexTemplate, ManualTemplate};
+use tower_cookies::CookieManagerLayer;
 use tower_http::services::ServeDir;
 
 // Handle root route - serve index page
@@ -67,10 +72,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .merge(auth::auth_routes())
         // API routes
         .route("/api/auth/status", get(api::auth::get_status))
+        .route("/api/telemetry", post(api::telemetry::submit_report))
+        // CLI device login
+        .route("/device", get(api::device::approve_page))
+        .route("/api/device/code", post(api::device::request_code))
+        .route("/api/device/token", post(api::device::poll_token))
+        .route("/api/cli/session", get(api::device::get_session))
         // Serve static files
         .nest_service("/static", ServeDir::new(static_dir))
         // Health check
         .route("/health", get(|| async { "OK" }))
+        // Session cookies used by the auth routes
+        .layer(CookieManagerLayer::new())
         // Apply state to the router
         .with_state(state);
 
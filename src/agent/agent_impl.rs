@@ -3,23 +3,34 @@
 //! This module contains the Agent struct and related functionality for
 //! managing conversations, tool execution, and interactions with LLM backends.
 
+use super::budget::{BudgetUsage, SharedCost, SubAgentCost};
+use super::file_cache::FileCache;
+use super::file_watch::FileWatch;
 use super::interrupt::{spawn_interrupt_monitor, InterruptCoordinator};
+use super::output_monitor::{OutputMonitor, Verdict};
+use super::smart_context::SmartContext;
+use super::timings::{SessionTimings, TurnTimings};
 use super::types::{
     AgentCommand, AgentId, AgentMessage, AgentReceiver, AgentState, InterruptReceiver, StateSender,
 };
 use crate::ansi_converter::strip_ansi_sequences;
 use crate::config::Config;
 use crate::conversation::{sanitize_conversation, TruncationConfig};
+use crate::llm::sampling::SamplingTask;
 use crate::llm::{Backend, Content, Message, MessageInfo, TokenUsage};
 use crate::prompts::Grammar;
-use crate::tools::shell::{execute_shell, ShellOutput};
+use crate::tools::plan::ActionPlan;
+use crate::tools::progress::ProgressSender;
+use crate::tools::shell::{execute_shell, ShellExit, ShellOutput};
 use crate::tools::InterruptData;
 use crate::tools::ToolExecutor;
+use crate::workflow::export::SessionRecord;
 use std::collections::BTreeSet;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::sync::{mpsc, watch};
 use tokio::sync::mpsc::error::TryRecvError;
 
 /// Result of sending a message, including whether further processing is needed
@@ -31,6 +42,38 @@ pub struct MessageResult {
     pub token_usage: Option<TokenUsage>,
 }
 
+/// Malformed tool calls in a row answered with a tool error before the
+/// response is treated as a regular answer
+const MAX_MALFORMED_TOOL_CALLS: usize = 3;
+
+/// Request for the summary a handed-off conversation continues from
+const HANDOFF_SUMMARY_PROMPT: &str = "The conversation is being handed off to another agent. \
+Summarize it for that agent: what was done, what was learned (files, commands, findings), \
+decisions made and what remains to be done. Be concise and specific, and do not call any tools.";
+
+/// Maximum length of the handoff summary, in tokens
+const HANDOFF_SUMMARY_MAX_TOKENS: usize = 2000;
+
+/// Request for the commit message of a checkpoint
+const CHECKPOINT_MESSAGE_PROMPT: &str = "Write a git commit message for the diff below: a \
+summary line of at most 72 characters in the imperative mood, optionally followed by a blank \
+line and a short body. Answer with the commit message only.";
+
+/// Smallest thinking budget providers accept, in tokens
+const MIN_THINKING_BUDGET: usize = 1024;
+
+/// Tools without side effects, after which the next request is speculative
+const SPECULATIVE_TOOLS: &[&str] = &["read", "fetch", "search", "screenshot", "screendump"];
+
+/// How often the output of a streaming shell command is judged locally
+const OUTPUT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Shortest time between asking a model whether to interrupt a shell command
+const ESCALATION_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Characters from the end of the output sent with an interruption check
+const INTERRUPT_CHECK_OUTPUT_CHARS: usize = 8000;
+
 /// Result of checking if the LLM wants to interrupt a streaming command
 struct InterruptionCheck {
     pub interrupted: bool,
@@ -78,6 +121,62 @@ pub struct Agent {
 
     /// Counter for tool invocations, used for indexing tool results
     tool_invocation_counter: usize,
+
+    /// Actions recorded instead of executed while in plan mode
+    planned_actions: ActionPlan,
+
+    /// Resources consumed so far, checked against the configured budget
+    budget_usage: BudgetUsage,
+
+    /// Cost spent by sub-agents since it was last added to `budget_usage`
+    sub_agent_cost: SharedCost,
+
+    /// Malformed tool calls in a row, reported back to the model for a retry
+    malformed_tool_calls: usize,
+
+    /// Text the model wrote after the pending tool call, replayed after its result
+    trailing_text: Option<String>,
+
+    /// Count of user inputs sent to this agent, updated by the agent manager
+    interjections: Option<watch::Receiver<u64>>,
+
+    /// Reports the tokens used so far to the agent manager
+    usage_reporter: Option<watch::Sender<TokenUsage>>,
+
+    /// Receives the progress reports of the agent's tool calls
+    progress_reporter: Option<ProgressSender>,
+
+    /// Whether the next request follows a read-only tool and may be cancelled
+    /// by new user input
+    speculative_next: bool,
+
+    /// First user input of the conversation, passed on when handing off
+    task: Option<String>,
+
+    /// Files written or patched since the last checkpoint commit
+    checkpoint_paths: BTreeSet<String>,
+
+    /// Code from the semantic index selected for the current request
+    smart_context: Option<SmartContext>,
+
+    /// Files whose content is in the conversation, to answer repeated reads
+    file_cache: FileCache,
+
+    /// Files read or written, checked for changes made outside the agent
+    file_watch: FileWatch,
+
+    /// Requests and successful tool calls, for `/export-workflow`
+    session_record: SessionRecord,
+
+    /// Time spent in the phases of the current turn
+    turn_timings: TurnTimings,
+
+    /// Timings of the finished turns, for `/timings`
+    timings: SessionTimings,
+
+    /// Whether the system prompt was generated from the kind and tools, so it
+    /// is generated again when tools are enabled or disabled
+    generated_prompt: bool,
 }
 
 impl Agent {
@@ -96,27 +195,10 @@ impl Agent {
             None => crate::prompts::select_grammar_for_model(&config.model),
         };
         // Initialize system prompt if not already set
-        if config.system_prompt.is_none() {
-            // Convert enabled_tools setting to the appropriate list of tool names
-            let mut enabled_tools = if config.enable_tools {
-                crate::prompts::ALL_TOOLS.to_vec()
-            } else {
-                crate::prompts::READONLY_TOOLS.to_vec()
-            };
-
-            // Filter out specifically disabled tools
+        let generated_prompt = config.system_prompt.is_none();
+        if generated_prompt {
+            // Log which tools were disabled
             if !config.disabled_tools.is_empty() {
-                // Create a set of disabled tools for efficient lookups
-                let disabled_set: std::collections::HashSet<String> = config
-                    .disabled_tools
-                    .iter()
-                    .map(|s| s.to_lowercase())
-                    .collect();
-
-                // Filter out disabled tools
-                enabled_tools.retain(|&tool| !disabled_set.contains(&tool.to_lowercase()));
-
-                // Log which tools were disabled
                 bprintln!(
                     "ℹ️ {}{} tools disabled{}: {}",
                     crate::constants::FORMAT_BOLD,
@@ -126,26 +208,13 @@ impl Agent {
                 );
             }
 
-            // Generate the system prompt based on kind or minimal flag
-            match crate::prompts::generate_system_prompt(
-                &enabled_tools,
-                config.use_minimal_prompt,
-                config.kind.as_deref(),
-                grammar.clone(),
-                Some(&config.disabled_tools),
-            ) {
-                Ok(prompt) => {
-                    // Set the system prompt in the config's system_prompt field
-                    config.system_prompt = Some(prompt);
-                }
-                Err(e) => {
-                    // Return the error with additional context about the agent
-                    return Err(Box::<dyn std::error::Error + Send + Sync>::from(format!(
-                        "Failed to generate system prompt for agent '{}': {}",
-                        name, e
-                    )));
-                }
-            }
+            Self::generate_system_prompt(&mut config, &grammar).map_err(|e| {
+                // Return the error with additional context about the agent
+                Box::<dyn std::error::Error + Send + Sync>::from(format!(
+                    "Failed to generate system prompt for agent '{}': {}",
+                    name, e
+                ))
+            })?;
         }
 
         // Create LLM backend using factory
@@ -155,13 +224,13 @@ impl Agent {
                 e
             ))
         })?;
+        crate::telemetry::record_model(&config.model);
 
-        // Initialize tool executor (not readonly, not silent)
+        // Initialize tool executor (not silent)
         // Note: Agent manager will be set later in the run method
-        let mut tool_executor = ToolExecutor::new(false, false);
-
-        // Set the list of disabled tools in the tool executor
-        tool_executor.set_disabled_tools(config.disabled_tools.clone());
+        let tool_executor = Self::create_tool_executor(&config, None);
+        let stop_sequences = Self::stop_sequences_for(grammar.as_ref(), &config);
+        let smart_context = config.smart_context.map(SmartContext::new);
 
         Ok(Self {
             id,
@@ -170,18 +239,176 @@ impl Agent {
             llm,
             tool_executor,
             conversation: Vec::new(),
-            stop_sequences: Some(vec![
-                grammar.stop_sequences().done_stop_sequence.to_string(),
-                grammar.stop_sequences().error_stop_sequence.to_string(),
-            ]),
+            stop_sequences: Some(stop_sequences),
             cache_points: BTreeSet::new(),
             truncation_config: TruncationConfig::default(),
             sender,
             state: AgentState::Idle,
             tool_invocation_counter: 0,
+            planned_actions: ActionPlan::new(),
+            budget_usage: BudgetUsage::default(),
+            sub_agent_cost: SharedCost::default(),
+            malformed_tool_calls: 0,
+            trailing_text: None,
+            interjections: None,
+            usage_reporter: None,
+            progress_reporter: None,
+            speculative_next: false,
+            task: None,
+            checkpoint_paths: BTreeSet::new(),
+            smart_context,
+            file_cache: FileCache::default(),
+            file_watch: FileWatch::default(),
+            session_record: SessionRecord::default(),
+            turn_timings: TurnTimings::default(),
+            timings: SessionTimings::default(),
+            generated_prompt,
             grammar,
         })
     }
+
+    /// Create a copy of this agent with the same conversation and configuration
+    ///
+    /// The copy gets its own LLM backend and starts idle; pending tool state
+    /// (malformed call count, trailing text) is not carried over.
+    pub fn fork(
+        &self,
+        id: AgentId,
+        name: String,
+        sender: StateSender,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let llm = crate::llm::create_backend(&self.config).map_err(|e| {
+            Box::<dyn std::error::Error + Send + Sync>::from(format!(
+                "Failed to create LLM backend: {}",
+                e
+            ))
+        })?;
+
+        Ok(Self {
+            id,
+            name,
+            config: self.config.clone(),
+            llm,
+            tool_executor: Self::create_tool_executor(&self.config, None),
+            conversation: self.conversation.clone(),
+            stop_sequences: self.stop_sequences.clone(),
+            cache_points: self.cache_points.clone(),
+            truncation_config: TruncationConfig::default(),
+            sender,
+            state: AgentState::Idle,
+            tool_invocation_counter: self.tool_invocation_counter,
+            planned_actions: self.planned_actions.clone(),
+            budget_usage: self.budget_usage.clone(),
+            sub_agent_cost: SharedCost::default(),
+            malformed_tool_calls: 0,
+            trailing_text: None,
+            interjections: None,
+            usage_reporter: None,
+            progress_reporter: None,
+            speculative_next: false,
+            task: self.task.clone(),
+            checkpoint_paths: BTreeSet::new(),
+            smart_context: self.smart_context.clone(),
+            file_cache: self.file_cache.clone(),
+            file_watch: self.file_watch.clone(),
+            session_record: self.session_record.clone(),
+            turn_timings: TurnTimings::default(),
+            timings: self.timings.clone(),
+            generated_prompt: self.generated_prompt,
+            grammar: self.grammar.clone(),
+        })
+    }
+
+    /// The grammar's stop sequences followed by the configured extra ones
+    fn stop_sequences_for(grammar: &dyn Grammar, config: &Config) -> Vec<String> {
+        let mut stop_sequences = grammar.stop_sequences().to_vec();
+        for sequence in &config.stop_sequences {
+            if !stop_sequences.contains(sequence) {
+                stop_sequences.push(sequence.clone());
+            }
+        }
+        stop_sequences
+    }
+
+    /// Create a tool executor matching the agent's configuration
+    /// Tools the agent may use before disabled tools are removed: the kind's
+    /// default tools, or the read-only tools
+    fn available_tools(config: &Config) -> Vec<&'static str> {
+        let mut tools = if config.enable_tools && !config.readonly {
+            crate::prompts::kinds::default_tools(config.kind.as_deref())
+        } else {
+            crate::prompts::READONLY_TOOLS.to_vec()
+        };
+
+        // Propose mode keeps write and patch available, collecting them as a patch file
+        if config.propose_changes {
+            tools.extend(["write", "patch"]);
+        }
+        tools
+    }
+
+    /// Generate the system prompt for the configured kind and enabled tools
+    fn generate_system_prompt(
+        config: &mut Config,
+        grammar: &Arc<dyn Grammar>,
+    ) -> Result<(), String> {
+        let mut enabled_tools = Self::available_tools(config);
+
+        // Filter out specifically disabled tools
+        let disabled: std::collections::HashSet<String> = config
+            .disabled_tools
+            .iter()
+            .map(|s| s.to_lowercase())
+            .collect();
+        enabled_tools.retain(|&tool| !disabled.contains(&tool.to_lowercase()));
+
+        // Generate the system prompt based on kind or minimal flag
+        let system_prompt = crate::prompts::generate_system_prompt(
+            &enabled_tools,
+            config.use_minimal_prompt,
+            config.kind.as_deref(),
+            grammar.clone(),
+            Some(&config.disabled_tools),
+        )
+        .map_err(|e| e.to_string())?;
+
+        let mut prompt = system_prompt.text;
+        for sequence in system_prompt.stop_sequences {
+            if !config.stop_sequences.contains(&sequence) {
+                config.stop_sequences.push(sequence);
+            }
+        }
+
+        // Explain plan-only semantics to the model
+        if config.plan_mode {
+            prompt.push_str(crate::tools::plan::PLAN_MODE_PROMPT);
+        }
+
+        // Explain that write/patch only produce proposals
+        if config.propose_changes {
+            prompt.push_str(crate::tools::proposals::PROPOSE_MODE_PROMPT);
+        }
+
+        config.system_prompt = Some(prompt);
+        Ok(())
+    }
+
+    fn create_tool_executor(config: &Config, agent_id: Option<AgentId>) -> ToolExecutor {
+        let mut tool_executor = match agent_id {
+            Some(id) => ToolExecutor::with_agent_id(config.readonly, false, id),
+            None => ToolExecutor::new(config.readonly, false),
+        };
+
+        // Set the list of disabled tools in the tool executor
+        tool_executor.set_disabled_tools(config.disabled_tools.clone());
+
+        if config.propose_changes {
+            tool_executor.enable_proposals();
+        }
+
+        tool_executor
+    }
+
     fn set_state(&mut self, state: AgentState) {
         self.state = state.clone();
         self.sender.send(self.state.clone()).unwrap()
@@ -207,13 +434,10 @@ impl Agent {
         let _interrupt_monitor = spawn_interrupt_monitor(coordinator.clone(), interrupt_receiver);
 
         // Set up the tool executor with this agent's ID
-        let mut new_tool_executor = ToolExecutor::with_agent_id(false, false, self.id);
-
-        // Transfer the disabled tools list to the new executor
-        new_tool_executor.set_disabled_tools(self.config.disabled_tools.clone());
-
-        // Replace the tool executor
-        self.tool_executor = new_tool_executor;
+        self.tool_executor = Self::create_tool_executor(&self.config, Some(self.id));
+        if let Some(reporter) = &self.progress_reporter {
+            self.tool_executor.set_progress_reporter(reporter.clone());
+        }
 
         // MCP connections are now initialized at application startup before any agents
         // We don't add MCP server information to the conversation context anymore
@@ -222,12 +446,17 @@ impl Agent {
         // No session loading - sessions are disabled
 
         // Load project information and autoinclude files at startup for every agent
-        if let Err(e) = self.load_project_info(None, false).await {
-            bprintln !(error:"Failed to load project information: {}", e);
-        }
+        // Forked agents already carry this context in their conversation
+        if self.conversation.is_empty() {
+            if let Err(e) = self.load_project_info(None, false).await {
+                bprintln !(error:"Failed to load project information: {}", e);
+            }
 
-        if let Err(e) = self.load_autoinclude_files(false).await {
-            bprintln !(error:"Failed to load autoinclude files: {}", e);
+            if let Err(e) = self.load_autoinclude_files(false).await {
+                bprintln !(error:"Failed to load autoinclude files: {}", e);
+            }
+
+            self.load_scratchpad_head();
         }
 
         // Main agent loop
@@ -264,7 +493,8 @@ impl Agent {
                                 bprintln !("✅ {}Agent{} has completed its task.",
                                     crate::constants::FORMAT_BOLD,
                                     crate::constants::FORMAT_RESET);
-                                self.set_state(AgentState::Done(Some(result.response)))
+                                let response = self.annotate_budget_enforcement(result.response);
+                                self.set_state(AgentState::Done(Some(response)))
                             }
                         },
                         Err(e) => {
@@ -272,6 +502,7 @@ impl Agent {
                             self.set_state(AgentState::Idle);
                         }
                     }
+                    self.finish_turn_timing();
 
                     // Process any pending messages that arrived during LLM processing
                     'queue: loop {
@@ -365,24 +596,43 @@ impl Agent {
         );
     }
 
+    /// Add user input to the conversation and start processing it
+    ///
+    /// Input from a participant of a shared session is attributed by name.
+    fn receive_user_input(&mut self, author: Option<&str>, input: String) {
+        self.speculative_next = false;
+        self.task.get_or_insert_with(|| input.clone());
+        if let Some(smart_context) = &mut self.smart_context {
+            smart_context.request(&input);
+        }
+        // Add message to conversation and start processing
+        let text = match author {
+            Some(author) => crate::share::attributed(author, &input),
+            None => input.clone(),
+        };
+        self.session_record.request(&text);
+        self.conversation
+            .push(Message::text("user", text, MessageInfo::User));
+        self.set_state(AgentState::Processing);
+        // Display user input with chevron and dark blue color
+        bprintln!(
+            "{}{}{}>{} {}{}{}",
+            crate::constants::FORMAT_BLUE,
+            crate::constants::FORMAT_BOLD,
+            author.unwrap_or(""),
+            crate::constants::FORMAT_RESET,
+            crate::constants::FORMAT_BLUE,
+            input,
+            crate::constants::FORMAT_RESET
+        );
+    }
+
     /// Handle incoming messages and commands
     async fn handle_message(&mut self, msg: AgentMessage) {
         match msg {
-            AgentMessage::UserInput(input) => {
-                // Add message to conversation and start processing
-                self.conversation
-                    .push(Message::text("user", input.clone(), MessageInfo::User));
-                self.set_state(AgentState::Processing);
-                // Display user input with chevron and dark blue color
-                bprintln!(
-                    "{}{}>{} {}{}{}",
-                    crate::constants::FORMAT_BLUE,
-                    crate::constants::FORMAT_BOLD,
-                    crate::constants::FORMAT_RESET,
-                    crate::constants::FORMAT_BLUE,
-                    input,
-                    crate::constants::FORMAT_RESET
-                );
+            AgentMessage::UserInput(input) => self.receive_user_input(None, input),
+            AgentMessage::ParticipantInput { author, content } => {
+                self.receive_user_input(Some(&author), content)
             }
             AgentMessage::AgentInput {
                 content,
@@ -453,9 +703,245 @@ impl Agent {
             }
             AgentCommand::SetThinkingBudget(budget) => {
                 self.set_thinking_budget(budget);
-                bprintln!("Thinking budget set to {} tokens", budget);
+                if budget == 0 {
+                    bprintln!("Thinking disabled");
+                } else {
+                    bprintln!("Thinking budget set to {} tokens", budget);
+                }
+            }
+            AgentCommand::SetMaxTokens(max_tokens) => {
+                self.set_max_tokens(max_tokens);
+                match max_tokens {
+                    Some(max_tokens) => {
+                        bprintln!("Responses limited to {} tokens", max_tokens);
+                        let budget = self.config.thinking_budget;
+                        let thinking = thinking_budget_within(budget, Some(max_tokens));
+                        if thinking == 0 && budget > 0 {
+                            bprintln!(info: "Thinking is off while the limit is this short");
+                        } else if thinking < budget {
+                            bprintln!(info: "Thinking budget reduced to {} tokens to fit", thinking);
+                        }
+                    }
+                    None => bprintln!("Response length limit removed"),
+                }
             }
+            AgentCommand::SetTemperature { task, temperature } => {
+                self.config.sampling.set_temperature(task, temperature);
+                let target = task.map_or("all requests", SamplingTask::name);
+                match temperature {
+                    Some(temperature) => {
+                        bprintln!("Temperature of {} set to {}", target, temperature)
+                    }
+                    None => bprintln!("Temperature of {} reset to the default", target),
+                }
+                let conversation = matches!(task, None | Some(SamplingTask::Conversation));
+                if conversation && self.config.thinking_budget > 0 && self.llm.name() == "anthropic"
+                {
+                    bprintln!(info: "Anthropic models ignore the temperature while thinking is on");
+                }
+                for line in self.config.sampling.describe().lines() {
+                    bprintln!(info: "  {}", line);
+                }
+            }
+            AgentCommand::SetToolEnabled { tool, enabled } => {
+                match self.set_tool_enabled(&tool, enabled) {
+                    Ok(()) if self.generated_prompt => bprintln!(
+                        "Tool {} {}abled; system prompt updated",
+                        tool,
+                        if enabled { "en" } else { "dis" }
+                    ),
+                    Ok(()) => {
+                        bprintln!("Tool {} {}abled", tool, if enabled { "en" } else { "dis" })
+                    }
+                    Err(e) => bprintln!(error:"{}", e),
+                }
+            }
+            AgentCommand::Fork(name) => match super::fork_agent(self, name.clone()) {
+                Ok(id) => bprintln!("Conversation forked into agent '{}' [{}]", name, id),
+                Err(e) => bprintln!(error:"Failed to fork agent: {}", e),
+            },
+            AgentCommand::Handoff { name, kind, model } => {
+                match self.handoff(name.clone(), kind, model).await {
+                    Ok(id) => {
+                        bprintln!("Conversation handed off to agent '{}' [{}]", name, id);
+                        self.set_state(AgentState::Done(Some(format!(
+                            "Handed off to agent '{name}'"
+                        ))));
+                    }
+                    Err(e) => bprintln!(error:"Handoff failed: {}", e),
+                }
+            }
+            AgentCommand::Start {
+                name,
+                kind,
+                model,
+                prompt,
+            } => {
+                let mut config = self.config.clone();
+                config.kind = Some(kind);
+                config.system_prompt = None;
+                if let Some(model) = model {
+                    config.model = model;
+                }
+                let started = super::create_agent(name.clone(), config).and_then(|id| {
+                    super::send_message(id, AgentMessage::UserInput(prompt))?;
+                    Ok(id)
+                });
+                match started {
+                    Ok(id) => bprintln!("Started agent '{}' [{}]", name, id),
+                    Err(e) => bprintln!(error:"Failed to start agent '{}': {}", name, e),
+                }
+            }
+            AgentCommand::ExportWorkflow(name) => {
+                let kind = self.config.kind.as_deref();
+                match self
+                    .session_record
+                    .to_yaml(&name, kind)
+                    .and_then(|yaml| crate::workflow::export::save(&name, &yaml))
+                {
+                    Ok(path) => bprintln!(
+                        info: "Workflow draft saved to {}; review it, then run `termineer workflow {}`",
+                        path.display(),
+                        name
+                    ),
+                    Err(e) => bprintln!(error:"Failed to export workflow: {}", e),
+                }
+            }
+            AgentCommand::ExportConversation(path) => match self.export_conversation(&path) {
+                Ok(()) => bprintln!(info: "Conversation saved to {}", path),
+                Err(e) => bprintln!(error:"Failed to save conversation to {}: {}", path, e),
+            },
+            AgentCommand::ShowTimings => {
+                bprintln!("{}", self.timings.summary());
+            }
+        }
+    }
+
+    /// Write the conversation to a file, one JSON message per line
+    fn export_conversation(&self, path: &str) -> std::io::Result<()> {
+        let mut lines = String::new();
+        for message in &self.conversation {
+            lines.push_str(&serde_json::to_string(message)?);
+            lines.push('\n');
+        }
+        std::fs::write(path, lines)
+    }
+
+    /// Commit the files edited since the last checkpoint to the session branch
+    async fn commit_checkpoint(&mut self) {
+        if self.checkpoint_paths.is_empty() {
+            return;
+        }
+        let paths = std::mem::take(&mut self.checkpoint_paths);
+
+        let prepared = match super::checkpoint::prepare(&paths).await {
+            Ok(Some(prepared)) => prepared,
+            Ok(None) => return,
+            Err(e) => {
+                bprintln!(warn: "Checkpoint skipped: {}", e);
+                return;
+            }
+        };
+
+        let message = self
+            .checkpoint_message(&prepared.diff)
+            .await
+            .unwrap_or_else(|| super::checkpoint::fallback_message(&paths));
+        match super::checkpoint::commit(&prepared, &message).await {
+            Ok(commit) => bprintln!(
+                info: "Checkpoint {} on {}: {}",
+                commit,
+                super::checkpoint::branch(),
+                message.lines().next().unwrap_or("")
+            ),
+            Err(e) => bprintln!(warn: "Failed to commit checkpoint: {}", e),
+        }
+    }
+
+    /// Ask the model for a commit message describing a checkpoint diff
+    async fn checkpoint_message(&mut self, diff: &str) -> Option<String> {
+        let request = format!(
+            "{CHECKPOINT_MESSAGE_PROMPT}\n\n```diff\n{}\n```",
+            crate::git::truncate_diff(diff, super::checkpoint::MAX_DIFF_CHARS)
+        );
+        let messages = [Message::text("user", request, MessageInfo::User)];
+        let response = self
+            .llm
+            .send_message(
+                &messages,
+                None,
+                None,
+                None,
+                None,
+                Some(200),
+                self.config.sampling.for_task(SamplingTask::Summary),
+            )
+            .await
+            .ok()?;
+        if let Some(usage) = &response.usage {
+            self.record_usage(usage);
         }
+
+        let message = response.text().trim().trim_matches('`').trim().to_string();
+        (!message.is_empty()).then_some(message)
+    }
+
+    /// Continue the conversation in a new agent of another kind (and model)
+    ///
+    /// The new agent starts from a summary of this conversation written by the
+    /// current model, together with the original task.
+    async fn handoff(
+        &mut self,
+        name: String,
+        kind: String,
+        model: Option<String>,
+    ) -> Result<AgentId, Box<dyn std::error::Error + Send + Sync>> {
+        bprintln!("Summarizing the conversation for agent '{}'...", name);
+
+        let mut messages = self.conversation.clone();
+        messages.push(Message::text(
+            "user",
+            HANDOFF_SUMMARY_PROMPT.to_string(),
+            MessageInfo::User,
+        ));
+        let response = self
+            .llm
+            .send_message(
+                &messages,
+                self.config.system_prompt.as_deref(),
+                None,
+                None,
+                None,
+                Some(HANDOFF_SUMMARY_MAX_TOKENS),
+                self.config.sampling.for_task(SamplingTask::Summary),
+            )
+            .await
+            .map_err(|e| format!("Failed to summarize the conversation: {e}"))?;
+        if let Some(usage) = &response.usage {
+            self.record_usage(usage);
+        }
+        let summary = response.text();
+
+        let mut config = self.config.clone();
+        config.kind = Some(kind);
+        config.system_prompt = None;
+        if let Some(model) = model {
+            config.model = model;
+        }
+
+        let id = super::create_agent(name, config)?;
+        let task = self.task.as_deref().unwrap_or("(no task was given)");
+        let seed = format!(
+            "You are taking over a task from agent '{}'.\n\n\
+             Original task:\n{}\n\n\
+             Summary of the work so far:\n{}\n\n\
+             Continue the task from here.",
+            self.name,
+            task,
+            summary.trim()
+        );
+        super::send_message(id, AgentMessage::UserInput(seed))?;
+        Ok(id)
     }
 
     /// Execute a shell command with streaming output and interruption capability
@@ -468,12 +954,18 @@ impl Agent {
         // Update state to running tool
         self.set_state(AgentState::RunningTool {
             tool: "shell".to_string(),
+            detail: args_detail(args, body),
             interruptible: true,
         });
 
         // Args already contain the command arguments (everything after "shell")
         let cmd_args = args.trim().to_string();
 
+        // Record where and with which extra environment the command runs
+        let shell_header = crate::tools::shell::ShellOptions::parse(&cmd_args)
+            .map(|(options, _)| options.header())
+            .unwrap_or_default();
+
         // Create interrupt data for coordination
         let interrupt_data = Arc::new(Mutex::new(InterruptData::new()));
 
@@ -506,6 +998,9 @@ impl Agent {
         // Buffer to collect output for the conversation history
         let mut partial_output = String::new();
 
+        // How the command ended, for the structured result trailer
+        let mut exit: Option<ShellExit> = None;
+
         // This will hold the final message after command completion
         let result_message;
         let mut success = true;
@@ -515,113 +1010,23 @@ impl Agent {
         // Store the reason for interruption if provided
         let mut interruption_reason_str: Option<String> = None;
 
-        // Track time between interruption checks (to prevent excessive API calls)
+        // Output is judged locally every few seconds; a model is asked only
+        // when the heuristics cannot tell, and at most every half minute
+        let mut monitor = OutputMonitor::default();
         let mut last_check_time = std::time::Instant::now();
-
-        let min_check_interval = Duration::from_secs(30);
-
-        // Track if we have a partial tool result in the conversation
-        let mut has_partial_result = false;
+        let mut last_escalation_time = start_time;
 
         // Loop to receive output and check for interruption
         loop {
             tokio::select! {
                 // Process shell output
                 output = rx.recv() => {
-                    match output {
-                        Some(ShellOutput::Stdout(line)) => {
-                            // Sanitize line by removing ANSI escape sequences
-                            let sanitized_line = strip_ansi_sequences(&line);
-
-                            // Add sanitized output to full output record
-                            partial_output.push_str(&sanitized_line);
-                            partial_output.push('\n');
-
-                            // Check for interruption based on time or output volume
-                            let should_check_interrupt = !interrupting && last_check_time.elapsed() > min_check_interval;
-
-                            if should_check_interrupt {
-                                // Update last check time
-                                last_check_time = std::time::Instant::now();
-
-                                // Remove previous partial result if it exists
-                                if has_partial_result {
-                                    self.conversation.pop();
-                                }
-
-                                // Create partial tool result message WITHOUT the ending tag
-                                // Shell tools reuse the same index for both partial outputs and final result
-                                let partial_tool_result = format!(
-                                    "{}\n{}",
-                                    self.grammar.tool_result_start_tag(self.tool_invocation_counter),
-                                    partial_output
-                                );
-
-                                // Mark this point in conversation as a cache point
-                                self.cache_here();
-
-                                // Create the partial result message
-                                let partial_message = Message::text(
-                                    "user",
-                                    partial_tool_result,
-                                    MessageInfo::ToolResult {
-                                        tool_name: "shell".to_string(),
-                                        tool_index: Some(self.tool_invocation_counter),
-                                    }
-                                );
-
-                                // Add partial result to conversation and update tool mapper
-                                let _msg_index = self.conversation.len();
-                                self.conversation.push(partial_message);
-
-
-                                has_partial_result = true;
-
-                                // Calculate elapsed time since the command started
-                                let elapsed_duration = start_time.elapsed();
-
-                                // Send interruption check using the partial tool result and elapsed time
-                                if let Ok(interruption_check) = self.check_for_interruption(elapsed_duration).await {
-                                    if interruption_check.interrupted {
-                                        // Store the interruption reason if provided
-                                        let reason = interruption_check.reason.unwrap_or_else(||
-                                            "No specific reason provided".to_string()
-                                        );
-
-                                        // Log the interruption in a way that doesn't expose implementation details
-                                        bprintln !("{}{}Command interrupted: {}{}",
-                                            crate::constants::FORMAT_BOLD,
-                                            crate::constants::FORMAT_BLUE,
-                                            reason,
-                                            crate::constants::FORMAT_RESET);
-
-                                        // Log the internal mechanism only in debug builds
-                                        bprintln !(dev: "LLM-based interruption mechanism triggered");
-
-                                        // Set interrupt flag with reason
-                                        {
-                                            let mut data = interrupt_data.lock().unwrap();
-                                            data.interrupt(reason.clone());
-                                        }
-
-                                        // Store the reason so we can use it in the final output
-                                        interrupting = true;
-                                        interruption_reason_str = Some(reason);
-                                    }
-                                }
-                            }
-                        },
-                        Some(ShellOutput::Stderr(line)) => {
-                            // Sanitize line by removing ANSI escape sequences
-                            let sanitized_line = strip_ansi_sequences(&line);
-
-                            // Add sanitized output to full output record
-                            partial_output.push_str(&sanitized_line);
-                            partial_output.push('\n');
-                        },
-                        Some(ShellOutput::Complete(tool_result)) => {
+                    let line = match output {
+                        Some(ShellOutput::Stdout(line)) | Some(ShellOutput::Stderr(line)) => line,
+                        Some(ShellOutput::Complete(tool_result, shell_exit)) => {
                             // Command completed, store results
                             success = tool_result.success;
+                            exit = Some(shell_exit);
                             // Tool result content will be used to determine the final result
                             // Clear interrupt_shell as the command is done
                             // Update coordinator to indicate shell is no longer running
@@ -635,6 +1040,54 @@ impl Agent {
                             interrupt_coordinator.set_shell_running(false, None);
                             break;
                         }
+                    };
+
+                    // Sanitize line by removing ANSI escape sequences
+                    let sanitized_line = strip_ansi_sequences(&line);
+
+                    // Add sanitized output to full output record
+                    partial_output.push_str(&sanitized_line);
+                    partial_output.push('\n');
+                    monitor.push(&sanitized_line);
+
+                    if interrupting || last_check_time.elapsed() < OUTPUT_CHECK_INTERVAL {
+                        continue;
+                    }
+                    last_check_time = std::time::Instant::now();
+
+                    let reason = match monitor.check() {
+                        Verdict::Continue => None,
+                        Verdict::Interrupt(reason) => Some(reason),
+                        Verdict::Uncertain if last_escalation_time.elapsed() >= ESCALATION_INTERVAL => {
+                            last_escalation_time = std::time::Instant::now();
+                            let command = format!("{cmd_args}\n{body}");
+                            match self
+                                .check_for_interruption(&command, &partial_output, start_time.elapsed())
+                                .await
+                            {
+                                Ok(check) if check.interrupted => Some(check.reason.unwrap_or_else(||
+                                    "No specific reason provided".to_string()
+                                )),
+                                _ => None,
+                            }
+                        }
+                        Verdict::Uncertain => None,
+                    };
+
+                    if let Some(reason) = reason {
+                        // Log the interruption in a way that doesn't expose implementation details
+                        bprintln !("{}{}Command interrupted: {}{}",
+                            crate::constants::FORMAT_BOLD,
+                            crate::constants::FORMAT_BLUE,
+                            reason,
+                            crate::constants::FORMAT_RESET);
+
+                        // Set interrupt flag with reason
+                        interrupt_data.lock().unwrap().interrupt(reason.clone());
+
+                        // Store the reason so we can use it in the final output
+                        interrupting = true;
+                        interruption_reason_str = Some(reason);
                     }
                 },
 
@@ -695,12 +1148,6 @@ impl Agent {
             }
         }
 
-        // Properly finish the partial tool result if it exists
-        if has_partial_result {
-            // Remove the open partial result
-            self.conversation.pop();
-        }
-
         // Prepare result message with completion status
         let completion_message = if interrupting {
             let reason = interruption_reason_str
@@ -709,22 +1156,36 @@ impl Agent {
             format!("\n\n[COMMAND INTERRUPTED: {reason}]")
             // When interrupted by LLM or user, this is NOT an error, it's a successful interruption
             // Set success to true for interruptions
-        } else {
+        } else if success {
             "\n\n[COMMAND COMPLETED SUCCESSFULLY]".to_string()
+        } else {
+            "\n\n[COMMAND FAILED]".to_string()
         };
 
-        // Apply UTF-8 safe truncation to potentially large shell output
-        if partial_output.len() > crate::constants::MAX_TOOL_OUTPUT_LENGTH {
+        // Structured trailer so the agent can branch on the outcome reliably.
+        // A PTY merges both streams, so only their combined size is known.
+        let byte_counts = match exit {
+            Some(exit) if exit.terminal => format!("output: {} bytes", exit.stdout_bytes),
+            Some(exit) => format!(
+                "stdout: {} bytes | stderr: {} bytes",
+                exit.stdout_bytes, exit.stderr_bytes
+            ),
+            None => "output: unknown".to_string(),
+        };
+        let completion_message = format!(
+            "{completion_message}\n[exit_code: {} | duration: {:.2}s | {}]",
+            exit.and_then(|exit| exit.code)
+                .map_or("none".to_string(), |code| code.to_string()),
+            start_time.elapsed().as_secs_f64(),
+            byte_counts
+        );
+
+        // Truncate potentially large shell output, keeping errors and failures
+        let truncated = partial_output.len() > crate::constants::MAX_TOOL_OUTPUT_LENGTH;
+        if truncated {
             let original_length = partial_output.len();
 
-            // Apply truncation using the shared function
-            let truncated_output = crate::tools::truncate_utf8_content(
-                &partial_output,
-                None, // Use default max length
-                None, // Use default start preservation
-                None, // Use default end preservation
-                None, // Use default placeholder
-            );
+            let truncated_output = crate::tools::truncate::truncate_shell_output(&partial_output);
 
             // Log truncation if not in silent mode
             if !self.tool_executor.is_silent() {
@@ -742,10 +1203,15 @@ impl Agent {
             }
 
             // Set the result message with truncated content and completion message
-            result_message = truncated_output + &completion_message;
+            result_message = format!("{shell_header}\n{truncated_output}{completion_message}");
         } else {
             // No truncation needed
-            result_message = partial_output + &completion_message;
+            result_message = format!("{shell_header}\n{partial_output}{completion_message}");
+        }
+
+        // Interrupted commands did not finish, so they are not steps to repeat
+        if success && !interrupting {
+            self.session_record.succeeded("shell", args, body);
         }
 
         // When interrupted by LLM or user, this is NOT an error, it's a successful interruption
@@ -765,15 +1231,22 @@ impl Agent {
 
         // Add the agent_response to the conversation history
         // Interruption should be treated as a successful result
+        let provenance = crate::llm::Provenance {
+            source: crate::conversation::provenance::source_of("shell", args, body),
+            bytes: result_message.len(),
+            truncated,
+        };
         let message_info = if success || interrupting {
             MessageInfo::ToolResult {
                 tool_name: "shell".to_string(),
                 tool_index: Some(self.tool_invocation_counter),
+                provenance: Some(provenance),
             }
         } else {
             MessageInfo::ToolError {
                 tool_name: "shell".to_string(),
                 tool_index: Some(self.tool_invocation_counter),
+                provenance: Some(provenance),
             }
         };
 
@@ -783,6 +1256,7 @@ impl Agent {
         // Add to conversation and update tool mapper
         let _msg_index = self.conversation.len();
         self.conversation.push(message);
+        self.replay_trailing_text();
 
         // Reset state to Processing since we're continuing processing
         self.set_state(AgentState::Processing);
@@ -795,14 +1269,16 @@ impl Agent {
         })
     }
 
-    /// Sends a message to the LLM to check if it wants to interrupt the shell command
+    /// Ask a model whether the shell command should be interrupted
+    ///
+    /// Only the task, the command and the tail of its output are sent, to the
+    /// `--interrupt-model` if one is configured, instead of the whole conversation.
     async fn check_for_interruption(
-        &mut self,
+        &self,
+        command: &str,
+        output: &str,
         elapsed_duration: Duration,
     ) -> Result<InterruptionCheck, Box<dyn std::error::Error + Send + Sync>> {
-        // Save current cache points for efficient token usage
-        let current_cache_points = self.cache_points.clone();
-
         // Format the elapsed time in a human-readable format
         let elapsed_seconds = elapsed_duration.as_secs();
         let elapsed_time_str = if elapsed_seconds < 60 {
@@ -824,16 +1300,21 @@ impl Agent {
         // Log the detailed implementation strategy only in debug mode
         bprintln!(dev: "Creating interruption check prompt for command running for {}", elapsed_time_str);
 
-        // Create a tailored prompt for the interruption check
-        // This prompt is a business-sensitive part of the implementation and is intentionally
-        // obfuscated in the code to protect intellectual property
-        let interruption_check_message_visible = format!(
-            "========== COMMAND INTERRUPTION CHECK ==========\n\
-            The command has been running for {}.\n\
+        let task = self.task.as_deref().unwrap_or("(no task was given)");
+        let tail_start = output
+            .char_indices()
+            .rev()
+            .nth(INTERRUPT_CHECK_OUTPUT_CHARS)
+            .map_or(0, |(index, _)| index);
+        let interruption_check_message = format!(
+            "An agent working on this task:\n{}\n\n\
+            ran this shell command:\n{}\n\n\
+            The command has been running for {}. The end of its output so far:\n\
+            ---\n{}\n---\n\n\
             Evaluate if this command should be interrupted based on its current output.\n\
             \n\
             Interrupt if:\n\
-            - You have enough information to proceed\n\
+            - The agent has enough information to proceed\n\
             - The output is repetitive or redundant\n\
             - Errors indicate the command won't recover\n\
             - The command is producing excessive output with limited value\n\
@@ -844,7 +1325,10 @@ impl Agent {
             \n\
             If interrupting, provide exactly ONE SENTENCE explaining why.\n\
             Your decision:",
-            elapsed_time_str
+            task,
+            command.trim(),
+            elapsed_time_str,
+            &output[tail_start..]
         );
 
         // Log interruption check only in debug builds
@@ -855,15 +1339,27 @@ impl Agent {
             crate::constants::FORMAT_RESET
         );
 
-        // Create a temporary message to add to conversation
-        let temp_message = Message::text(
+        let messages = [Message::text(
             "user",
-            interruption_check_message_visible,
+            interruption_check_message,
             MessageInfo::User,
-        );
-
-        // Add message temporarily
-        self.conversation.push(temp_message);
+        )];
+
+        // A cheaper model judges the output if one is configured
+        let check_backend = match &self.config.interrupt_model {
+            Some(model) => {
+                let config = Config {
+                    model: model.clone(),
+                    ..self.config.clone()
+                };
+                Some(
+                    crate::llm::create_backend(&config)
+                        .map_err(|e| format!("Failed to create LLM backend: {e}"))?,
+                )
+            }
+            None => None,
+        };
+        let backend = check_backend.as_deref().unwrap_or(self.llm.as_ref());
 
         // Use "</interrupt>" as stop sequence to allow content between tags
         let stop_sequences = vec!["</interrupt>".to_string(), "<continue/>".to_string()];
@@ -871,52 +1367,35 @@ impl Agent {
         // Allow 100 tokens for interruption reason (limited to keep costs low)
         let max_tokens_for_check = 100;
 
-        // Log token budget and timeout details only in debug builds
-        bprintln!(dev: "Interruption check using token budget of {} to minimize cost", max_tokens_for_check);
-
         // Start a timeout for the interruption check
         let timeout_duration = tokio::time::Duration::from_secs(15);
 
-        // Log timeout details only in debug builds
-        bprintln!(dev: "Using {}s timeout for interruption check to prevent hanging", timeout_duration.as_secs());
-
         // Handle the LLM response with proper error conversion and timeout
         let response = match tokio::time::timeout(
             timeout_duration,
-            self.llm.send_message(
-                &self.conversation,
-                self.config.system_prompt.as_deref(), // Use the existing system prompt
+            backend.send_message(
+                &messages,
+                None,
                 Some(&stop_sequences),
                 None,
-                Some(&current_cache_points),
+                None,
                 Some(max_tokens_for_check), // Always use the small token limit for interruption checks
+                self.config.sampling.for_task(SamplingTask::Check),
             ),
         )
-            .await
+        .await
         {
-            Ok(result) => match result {
-                Ok(response) => response,
-                Err(e) => {
-                    // Convert the error to a Send + Sync error by using the string representation
-                    bprintln!(error:"Interruption check failed: {}", e);
-
-                    // Remove the temporary message before returning
-                    self.conversation.pop();
-
-                    return Err(format!("Interruption check failed: {e}").into());
-                }
-            },
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => {
+                bprintln!(error:"Interruption check failed: {}", e);
+                return Err(format!("Interruption check failed: {e}").into());
+            }
             Err(_) => {
-                // Timeout occurred - clean up and return no interruption
-                // In debug mode, show detailed implementation info about the timeout
+                // Timeout occurred - return no interruption
                 bprintln!(dev:
                     "Interruption check timed out after {} seconds",
                     timeout_duration.as_secs()
                 );
-
-                // Remove the temporary message before returning
-                self.conversation.pop();
-
                 return Ok(InterruptionCheck {
                     interrupted: false,
                     reason: None,
@@ -924,11 +1403,10 @@ impl Agent {
             }
         };
 
-        // Remove the temporary message
-        self.conversation.pop();
-
         // Check if we got a proper stop sequence
-        if response.stop_reason.as_deref() != Some("stop_sequence") {
+        if response.stop_reason.as_deref() != Some("stop_sequence")
+            || response.stop_sequence.as_deref() != Some("</interrupt>")
+        {
             bprintln!(dev: "Interruption check completed: continue execution");
             return Ok(InterruptionCheck {
                 interrupted: false,
@@ -936,8 +1414,7 @@ impl Agent {
             });
         }
 
-        // Extract and process the stop sequence
-        let stop_sequence = response.stop_sequence.unwrap();
+        // Extract the reason from <interrupt>reason</interrupt>
         let content = response
             .content
             .iter()
@@ -947,39 +1424,14 @@ impl Agent {
             })
             .collect::<Vec<String>>()
             .join("");
-
-        // Check if we're interrupting and extract the reason
-        let (should_interrupt, reason) = if stop_sequence == "</interrupt>" {
-            // Extract reason from <interrupt>reason</interrupt>
-            let reason = if content.starts_with("<interrupt>") {
-                content
-                    .strip_prefix("<interrupt>")
-                    .unwrap_or("")
-                    .to_string()
-            } else {
-                "No specific reason provided".to_string()
-            };
-
-            (true, reason)
-        } else {
-            (false, String::new())
+        let reason = match content.trim().strip_prefix("<interrupt>") {
+            Some(reason) if !reason.trim().is_empty() => reason.trim().to_string(),
+            _ => "No specific reason provided".to_string(),
         };
 
-        // Log the decision
-        if should_interrupt {
-            // We still want to log interruption decisions in release mode when they actually happen
-            bprintln!(
-                "{}{}Command interrupted: {}{}",
-                crate::constants::FORMAT_BOLD,
-                crate::constants::FORMAT_BLUE,
-                reason,
-                crate::constants::FORMAT_RESET
-            );
-        }
-
         Ok(InterruptionCheck {
-            interrupted: should_interrupt,
-            reason: if should_interrupt { Some(reason) } else { None },
+            interrupted: true,
+            reason: Some(reason),
         })
     }
 
@@ -1047,6 +1499,29 @@ impl Agent {
         Ok(false)
     }
 
+    /// Include the beginning of the scratchpad this agent kept in an earlier session
+    fn load_scratchpad_head(&mut self) {
+        let enabled = self.config.enable_tools
+            && !self
+                .config
+                .disabled_tools
+                .iter()
+                .any(|tool| tool.eq_ignore_ascii_case("scratchpad"));
+        if !enabled {
+            return;
+        }
+        let Some(head) = crate::tools::scratchpad::scratchpad_head(&self.name) else {
+            return;
+        };
+
+        self.conversation.push(Message::text(
+            "user",
+            format!("# Your scratchpad from an earlier session:\n{head}"),
+            MessageInfo::User,
+        ));
+        bprintln!(info: "Loaded the scratchpad of agent '{}'", self.name);
+    }
+
     /// Load files specified by glob patterns in .termineer/autoinclude
     ///
     /// # Returns
@@ -1204,6 +1679,38 @@ impl Agent {
         Ok(included_count)
     }
 
+    /// Add a note listing the files changed on disk since the agent read or wrote them
+    fn notify_changed_files(&mut self) {
+        let changed = self.file_watch.changed();
+        if changed.is_empty() {
+            return;
+        }
+        bprintln!(info: "{} file(s) changed on disk since the agent last saw them", changed.len());
+        self.conversation.push(Message::text(
+            "user",
+            super::file_watch::change_note(&changed),
+            MessageInfo::System,
+        ));
+    }
+
+    /// Select code from the semantic index if a new request changed the topic
+    async fn update_smart_context(&mut self) {
+        let Some(smart_context) = &mut self.smart_context else {
+            return;
+        };
+        match smart_context.update().await {
+            Ok(Some(selection)) => {
+                bprintln!(info: "Smart context: {} chunk(s) from {} file(s), about {} tokens",
+                    selection.chunks,
+                    selection.files,
+                    selection.tokens
+                );
+            }
+            Ok(None) => {}
+            Err(e) => bprintln!(warn: "Smart context unavailable: {}", e),
+        }
+    }
+
     /// Send a message to the LLM backend and process the response
     pub async fn send_message(
         &mut self,
@@ -1213,7 +1720,18 @@ impl Agent {
         // for all agents in the run method, so we don't need to do it here.
 
         // Get necessary values for token counting
-        let thinking_budget = Some(self.config.thinking_budget);
+        let thinking_budget = Some(thinking_budget_within(
+            self.config.thinking_budget,
+            self.config.max_token_output,
+        ));
+
+        // Enforce resource budgets configured by the parent agent or --max-cost
+        if let Some(result) = self.enforce_budget() {
+            return Ok(result);
+        }
+
+        self.turn_timings = TurnTimings::default();
+        let started = Instant::now();
 
         // Apply conversation maintenance to remove empty messages
         // This ensures the conversation structure is clean before sending to the LLM
@@ -1231,29 +1749,119 @@ impl Agent {
             self.reset_cache_points();
         }
 
-        // Get the system prompt after any modifications to conversation
-        let system_prompt = self.config.system_prompt.as_deref();
+        // Tell the model about files changed since it last saw them
+        self.notify_changed_files();
+
+        // Select code for a new request when it changes the topic
+        self.update_smart_context().await;
+        self.turn_timings.context = started.elapsed();
+
+        // Get the system prompt after any modifications to conversation, with
+        // the current plan so the model sees its latest state on every turn,
+        // and the code selected for the current topic
+        let sections = [
+            crate::tools::todo::format_for_prompt(self.id),
+            self.smart_context
+                .as_ref()
+                .and_then(|context| context.section().map(str::to_string)),
+        ];
+        let system_prompt = sections.into_iter().flatten().fold(
+            self.config.system_prompt.clone(),
+            |prompt, section| {
+                Some(match prompt {
+                    Some(prompt) => format!("{prompt}\n\n{section}"),
+                    None => section,
+                })
+            },
+        );
+        let system_prompt = system_prompt.as_deref();
+
+        // Shape the request for what the backend supports: without a system
+        // role the prompt goes into the conversation, and cache points are
+        // only sent to backends with cache control
+        let capabilities = self.llm.capabilities();
+        let messages = crate::llm::capabilities::adapt_messages(
+            &self.conversation,
+            system_prompt,
+            capabilities,
+        );
+        let system_prompt = system_prompt.filter(|_| capabilities.supports_system_role);
+        let cache_points = Some(&self.cache_points).filter(|_| capabilities.supports_cache_control);
+
+        let sampling = self.config.sampling.for_task(SamplingTask::Conversation);
+
+        // With early tool dispatch, stop reading the response as soon as a
+        // complete tool call has arrived instead of waiting for the stop sequence
+        let request = async {
+            if self.config.early_tool_dispatch && self.config.enable_tools {
+                let grammar = self.grammar.clone();
+                let on_text = move |text: &str| grammar.tool_call_end(text).is_none();
+                self.llm
+                    .send_message_streaming(
+                        &messages,
+                        system_prompt,
+                        self.stop_sequences.as_deref(),
+                        thinking_budget,
+                        cache_points,
+                        self.config.max_token_output,
+                        sampling,
+                        &on_text,
+                    )
+                    .await
+            } else {
+                self.llm
+                    .send_message(
+                        &messages,
+                        system_prompt,
+                        self.stop_sequences.as_deref(),
+                        thinking_budget,
+                        cache_points,
+                        self.config.max_token_output, // Use configured max_tokens if provided
+                        sampling,
+                    )
+                    .await
+            }
+        };
+
+        // After a read-only tool the request is sent right away, but dropped
+        // when the user sends a message first; the message is then handled by
+        // the run loop and the request reissued with it
+        let speculative = self.config.speculative_prefetch && self.speculative_next;
+        let started = Instant::now();
+        let request = match self.interjections.clone().filter(|_| speculative) {
+            Some(mut interjections) => {
+                interjections.borrow_and_update();
+                tokio::select! {
+                    response = request => response,
+                    Ok(()) = interjections.changed() => {
+                        bprintln!(debug: "Speculative request cancelled by new user input");
+                        return Ok(MessageResult {
+                            response: String::new(),
+                            continue_processing: true,
+                            token_usage: None,
+                        });
+                    }
+                }
+            }
+            None => request.await,
+        };
+        self.turn_timings.api = started.elapsed();
 
         // Handle the LLM response with proper error conversion
-        let response = match self
-            .llm
-            .send_message(
-                &self.conversation,
-                system_prompt,
-                self.stop_sequences.as_deref(),
-                thinking_budget,
-                Some(&self.cache_points),
-                self.config.max_token_output, // Use configured max_tokens if provided
-            )
-            .await
-        {
+        let response = match request {
             Ok(response) => response,
             Err(e) => {
+                crate::telemetry::record_llm_error(&e);
                 // Convert the error to a Send + Sync error by using the string representation
                 return Err(format!("LLM request failed: {e}").into());
             }
         };
 
+        // Track token usage against the budget
+        if let Some(usage) = &response.usage {
+            self.record_usage(usage);
+        }
+
         // Extract content from response
         let mut assistant_message = String::new();
         for content in &response.content {
@@ -1262,13 +1870,36 @@ impl Agent {
             }
         }
 
+        // A response cut short after its tool call may end with the start of
+        // the tool result marker
+        if response.stop_reason.as_deref() == Some(crate::llm::STOP_REASON_EARLY) {
+            if let Some(end) = self.grammar.tool_call_end(&assistant_message) {
+                assistant_message.truncate(end);
+            }
+        }
 
         // Parse the assistant's response using this agent's grammar
+        let started = Instant::now();
         let parsed = self.grammar.parse_response(&assistant_message);
         drop(assistant_message);
+        self.turn_timings.parsing = started.elapsed();
 
         bprintln!(dev: "Response: {}", parsed.keep_part);
 
+        // Let the model fix a tool call that could not be parsed, instead of
+        // treating it as a final answer
+        if let Some(reason) = &parsed.malformed {
+            if self.config.enable_tools && self.malformed_tool_calls < MAX_MALFORMED_TOOL_CALLS {
+                self.malformed_tool_calls += 1;
+                return Ok(self.report_malformed_tool_call(
+                    &parsed.keep_part,
+                    reason,
+                    response.usage,
+                ));
+            }
+        }
+        self.malformed_tool_calls = 0;
+
         // If tools are not enabled, or no tool was found, handle as a regular response
         if !self.config.enable_tools || parsed.tool.is_none() {
             // In interactive mode, print the response here
@@ -1291,6 +1922,9 @@ impl Agent {
                 MessageInfo::Assistant,
             ));
 
+            self.speculative_next = false;
+            self.commit_checkpoint().await;
+
             // If this is a regular response, set the state back to Idle
             // so the agent waits for the next user input
             self.state = AgentState::Idle;
@@ -1305,6 +1939,7 @@ impl Agent {
         // At this point, we know we have a tool invocation
         let tool = parsed.tool.unwrap();
         let tool_name = tool.name;
+        self.speculative_next = SPECULATIVE_TOOLS.contains(&tool_name.as_str());
         let tool_body = tool.body;
 
         // Display token stats before any other output (if not in silent mode)
@@ -1333,41 +1968,145 @@ impl Agent {
         );
 
         self.conversation.push(tool_call_message);
+        self.trailing_text =
+            (!parsed.trailing_text.is_empty()).then(|| parsed.trailing_text.clone());
 
         // Increment the tool invocation counter for all tools
         self.tool_invocation_counter += 1;
+        self.budget_usage.tool_calls += 1;
+        crate::telemetry::record_tool_call(&tool_name);
 
-        // Special handling for shell tool to support streaming and interruption
-        if tool_name == "shell" {
-            // Convert the parsed args to a space-separated string
-            let tool_args = tool.args.join(" ");
+        // Convert the parsed args to a space-separated string
+        let tool_args = tool.args.join(" ");
 
+        // A batch of edits ends with the first call of another tool
+        let is_edit = matches!(tool_name.as_str(), "write" | "patch");
+        if !is_edit {
+            self.commit_checkpoint().await;
+        }
+
+        let planned = self.config.plan_mode
+            && crate::tools::plan::should_plan(&tool_name, &tool_args, &tool_body);
+
+        // Ask the user before the first use of each tool category
+        let permission_error = if planned {
+            None
+        } else {
+            crate::tools::permissions::request_permission(&tool_name, &tool_args)
+                .await
+                .err()
+        };
+
+        // Special handling for shell tool to support streaming and interruption
+        if tool_name == "shell" && !planned && permission_error.is_none() {
             // Use a new dedicated interrupt channel
+            let started = Instant::now();
             let shell_result = self
                 .execute_streaming_shell(&tool_args, &tool_body, interrupt_coordinator)
-                .await?;
-            return Ok(shell_result);
+                .await;
+            self.turn_timings.tool = started.elapsed();
+            return shell_result;
         }
 
         // For other tools, update state
         let interruptible = false; // Only shell is interruptible for now
-        self.state = AgentState::RunningTool {
+        self.set_state(AgentState::RunningTool {
             tool: tool_name.clone(),
+            detail: args_detail(&tool_args, &tool_body),
             interruptible,
-        };
+        });
 
         // Increment the tool invocation counter
         self.tool_invocation_counter += 1;
 
-        // Execute the tool with pre-parsed components from grammar
-        let tool_args = tool.args.join(" "); // Join the args array into a string
-        let tool_result = self
-            .tool_executor
-            .execute_with_parts(&tool_name, &tool_args, &tool_body)
-            .await;
+        // Files already in the conversation are not read again while unchanged
+        let cached_read = if tool_name == "read" {
+            self.file_cache.unchanged(&tool_args, &self.conversation)
+        } else {
+            None
+        };
+
+        // Execute the tool with pre-parsed components from grammar,
+        // or record it as a planned action in plan mode
+        let started = Instant::now();
+        let mut tool_result = if let Some(error_msg) = permission_error {
+            if !self.tool_executor.is_silent() {
+                bprintln!(error: "{}", error_msg);
+            }
+            crate::tools::ToolResult::error(error_msg)
+        } else if planned {
+            self.record_planned_action(&tool_name, &tool_args, &tool_body)
+        } else if let Some(note) = &cached_read {
+            if !self.tool_executor.is_silent() {
+                bprintln!(tool: "read",
+                    "📄 {} (unchanged, already in context)",
+                    tool_args.trim()
+                );
+            }
+            crate::tools::ToolResult::success(note.clone())
+        } else {
+            self.tool_executor.set_sub_agent_cost(SubAgentCost {
+                ceiling: self.config.budget.remaining_cost(&self.budget_usage),
+                spent: self.sub_agent_cost.clone(),
+            });
+            let result = self
+                .tool_executor
+                .execute_with_parts(&tool_name, &tool_args, &tool_body)
+                .await;
+            let spent = self.sub_agent_cost.take();
+            self.add_cost(spent);
+            result
+        };
+        self.turn_timings.tool = started.elapsed();
+
+        if !tool_result.success {
+            crate::telemetry::record_tool_error(&tool_name);
+        }
+
+        // Remember the state of the files the agent has now seen
+        if tool_result.success && !planned {
+            if cached_read.is_none() {
+                self.session_record
+                    .succeeded(&tool_name, &tool_args, &tool_body);
+            }
+            match tool_name.as_str() {
+                "read" => self
+                    .file_watch
+                    .seen(super::file_watch::read_paths(&tool_args)),
+                "write" | "patch" => self.file_watch.seen([tool_args.trim()]),
+                _ => {}
+            }
+        }
+
+        // Remember edited files for the next checkpoint commit
+        if self.config.checkpoint_commits
+            && is_edit
+            && tool_result.success
+            && !planned
+            && !self.config.propose_changes
+        {
+            self.checkpoint_paths.insert(tool_args.trim().to_string());
+        }
+
+        // Attach the ordered plan to the final response in plan mode
+        if self.config.plan_mode && tool_result.state_change == crate::tools::AgentStateChange::Done
+        {
+            tool_result = self.finalize_plan(tool_result);
+        }
+
+        // Save proposed changes and summarize them in the final response
+        if tool_result.state_change == crate::tools::AgentStateChange::Done {
+            if let Some(summary) = self.tool_executor.finalize_proposals() {
+                tool_result = crate::tools::ToolResult::done(format!(
+                    "{}\n\n{}",
+                    tool_result.to_text().trim_end(),
+                    summary
+                ));
+            }
+        }
 
         // Set the state back to Processing by default - will be updated by the tool's state_change if needed
-        self.state = AgentState::Processing;
+        self.set_state(AgentState::Processing);
 
         // Convert tool result content to text for formatting
         let tool_text_output = tool_result.to_text();
@@ -1391,15 +2130,23 @@ impl Agent {
         let result_for_response = tool_text_output.clone();
 
         // Determine the MessageInfo based on whether it was a successful tool execution
+        let provenance = crate::conversation::provenance::of_tool_output(
+            &tool_name,
+            &tool_args,
+            &tool_body,
+            &tool_result.content,
+        );
         let message_info = if tool_result.success {
             MessageInfo::ToolResult {
                 tool_name: tool_name.clone(),
                 tool_index: Some(self.tool_invocation_counter),
+                provenance: Some(provenance),
             }
         } else {
             MessageInfo::ToolError {
                 tool_name: tool_name.clone(),
                 tool_index: Some(self.tool_invocation_counter),
+                provenance: Some(provenance),
             }
         };
 
@@ -1411,6 +2158,21 @@ impl Agent {
             message_info.clone(),
         ));
 
+        // Remember the files now in the conversation; images are not cached
+        if tool_name == "read"
+            && tool_result.success
+            && cached_read.is_none()
+            && tool_result.content.len() == 1
+            && matches!(&tool_result.content[0], crate::llm::Content::Text { .. })
+        {
+            self.file_cache.record(
+                &tool_args,
+                &self.conversation,
+                self.conversation.len() - 1,
+                self.tool_invocation_counter,
+            );
+        }
+
         // If there's more than one content item or the item is not a simple text,
         // also add individual content items to ensure rich content is handled properly
         if tool_result.content.len() > 1
@@ -1425,6 +2187,7 @@ impl Agent {
                 ));
             }
         }
+        self.replay_trailing_text();
 
         let response_message_len = agent_response.len();
 
@@ -1487,6 +2250,184 @@ impl Agent {
         })
     }
 
+    /// Show the text written after the last tool call and give it back to the model
+    ///
+    /// The stop sequence ends the response at the tool result marker, so anything
+    /// the model wrote between the tool call and that marker would otherwise be lost.
+    fn replay_trailing_text(&mut self) {
+        let Some(text) = self.trailing_text.take() else {
+            return;
+        };
+        if !self.tool_executor.is_silent() {
+            crate::conversation::print_assistant_response(&text);
+        }
+        self.conversation.push(Message::text(
+            "user",
+            format!("After the tool call, before seeing its result, you also wrote:\n{text}"),
+            MessageInfo::User,
+        ));
+    }
+
+    /// Answer a tool call that could not be parsed with a tool error explaining why
+    fn report_malformed_tool_call(
+        &mut self,
+        assistant_message: &str,
+        reason: &str,
+        token_usage: Option<TokenUsage>,
+    ) -> MessageResult {
+        if !self.tool_executor.is_silent() {
+            crate::conversation::print_assistant_response(assistant_message);
+            bprintln!(warn: "Malformed tool call: {}", reason);
+        }
+
+        self.conversation.push(Message::text(
+            "assistant",
+            assistant_message.to_string(),
+            MessageInfo::Assistant,
+        ));
+
+        let message = format!(
+            "Your tool call was malformed because {}. Fix the call and send it again, e.g.:\n{}",
+            reason,
+            self.grammar.format_tool_call("read", "src/main.rs")
+        );
+        self.conversation.push(Message::text(
+            "user",
+            self.grammar
+                .format_tool_error("unknown", self.tool_invocation_counter, &message),
+            MessageInfo::ToolError {
+                tool_name: "unknown".to_string(),
+                tool_index: Some(self.tool_invocation_counter),
+                provenance: None,
+            },
+        ));
+        self.tool_invocation_counter += 1;
+
+        MessageResult {
+            response: message,
+            continue_processing: true,
+            token_usage,
+        }
+    }
+
+    /// Record the timings of the turn that just ended and show them in debug output
+    fn finish_turn_timing(&mut self) {
+        let turn = std::mem::take(&mut self.turn_timings);
+        if turn.total().is_zero() {
+            return;
+        }
+        bprintln!(debug: "Turn timings: {}", turn);
+        self.timings.record(turn);
+    }
+
+    /// Check the resource budget before calling the LLM
+    ///
+    /// The first time a limit is exceeded the agent is asked to summarize and finish.
+    /// If it is still running on the following turn, it is stopped with a final result.
+    fn enforce_budget(&mut self) -> Option<MessageResult> {
+        if !self.config.budget.is_limited() {
+            return None;
+        }
+
+        if self.budget_usage.started_at.is_none() {
+            self.budget_usage.started_at = Some(std::time::Instant::now());
+            if self.config.budget.max_cost.is_some()
+                && crate::llm::pricing::price_of(&self.config.model).is_none()
+            {
+                bprintln!(warn: "The price of {} is unknown, so the cost limit of agent '{}' is not enforced",
+                    self.config.model,
+                    self.name
+                );
+            }
+        }
+
+        let exceeded = self.config.budget.check(&self.budget_usage)?;
+
+        if let Some(previous) = &self.budget_usage.enforcement {
+            bprintln!(warn: "Agent '{}' stopped: {}", self.name, previous);
+            return Some(MessageResult {
+                response: "The agent was stopped before it could summarize its work.".to_string(),
+                continue_processing: false,
+                token_usage: None,
+            });
+        }
+
+        bprintln!(warn: "Agent '{}' {}, asking it to finish", self.name, exceeded);
+        self.conversation.push(Message::text(
+            "user",
+            format!(
+                "[BUDGET EXCEEDED: {exceeded}] Stop working now. Summarize your findings so far and call the done tool immediately."
+            ),
+            MessageInfo::User,
+        ));
+        self.budget_usage.enforcement = Some(exceeded);
+        None
+    }
+
+    /// Append the budget enforcement result to the final response, if a limit was hit
+    fn annotate_budget_enforcement(&self, response: String) -> String {
+        match &self.budget_usage.enforcement {
+            Some(exceeded) => format!(
+                "{}\n\n[Budget enforcement: {}]",
+                response.trim_end(),
+                exceeded
+            ),
+            None => response,
+        }
+    }
+
+    /// Record a mutating tool invocation instead of executing it (plan mode)
+    fn record_planned_action(
+        &mut self,
+        tool_name: &str,
+        tool_args: &str,
+        tool_body: &str,
+    ) -> crate::tools::ToolResult {
+        let position = self.planned_actions.record(tool_name, tool_args, tool_body);
+        let description = self.planned_actions.actions[position - 1].describe();
+
+        if !self.tool_executor.is_silent() {
+            bprintln!(tool: tool_name,
+                "{}📝 Planned #{}: {}{}",
+                crate::constants::FORMAT_BOLD,
+                position,
+                description,
+                crate::constants::FORMAT_RESET
+            );
+        }
+
+        crate::tools::ToolResult::success(format!(
+            "PLAN MODE: action #{position} ({description}) was recorded but NOT executed. Continue planning."
+        ))
+    }
+
+    /// Append the ordered plan to the final result and save it for `--apply`
+    fn finalize_plan(&mut self, result: crate::tools::ToolResult) -> crate::tools::ToolResult {
+        let mut summary = format!(
+            "{}\n\n{}",
+            result.to_text().trim_end(),
+            self.planned_actions.format_summary()
+        );
+
+        if !self.planned_actions.is_empty() {
+            let plan_path = std::path::Path::new(crate::tools::plan::DEFAULT_PLAN_FILE);
+            match self.planned_actions.save(plan_path) {
+                Ok(()) => {
+                    summary.push_str(&format!(
+                        "\n\nPlan saved to {}. Review it, then run with --apply {} to execute.",
+                        plan_path.display(),
+                        plan_path.display()
+                    ));
+                }
+                Err(e) => {
+                    bprintln!(error: "Failed to save plan file: {}", e);
+                }
+            }
+        }
+
+        crate::tools::ToolResult::done(summary)
+    }
+
     /// Reset cache points - needed when system prompt changes or
     /// when messages before cache points are modified/removed
     pub fn reset_cache_points(&mut self) {
@@ -1509,6 +2450,8 @@ impl Agent {
     /// Clear the conversation history
     pub fn clear_conversation(&mut self) {
         self.conversation.clear();
+        self.task = None;
+        self.session_record.clear();
         // Clear all cache points when conversation is cleared
         self.cache_points.clear();
         // Reset the tool mapper
@@ -1523,9 +2466,45 @@ impl Agent {
         }
     }
 
+    /// Watch the count of user inputs, so speculative requests can be cancelled
+    pub fn set_interjections(&mut self, interjections: watch::Receiver<u64>) {
+        self.interjections = Some(interjections);
+    }
+
+    /// Report the tokens used by each request through `reporter`
+    pub fn set_usage_reporter(&mut self, reporter: watch::Sender<TokenUsage>) {
+        self.usage_reporter = Some(reporter);
+    }
+
+    /// Report the progress of tool calls through `reporter`
+    pub fn set_progress_reporter(&mut self, reporter: ProgressSender) {
+        self.progress_reporter = Some(reporter);
+    }
+
+    /// Count the tokens and cost of a request against the budget and report them
+    fn record_usage(&mut self, usage: &TokenUsage) {
+        self.budget_usage.tokens += usage.input_tokens + usage.output_tokens;
+        if let Some(cost) = crate::llm::pricing::cost(&self.config.model, usage) {
+            self.add_cost(cost);
+        }
+        if let Some(reporter) = &self.usage_reporter {
+            reporter.send_modify(|total| total.add(usage));
+        }
+    }
+
+    /// Count a cost against the budget of this agent and of its parent agent
+    fn add_cost(&mut self, cost: f64) {
+        self.budget_usage.cost += cost;
+        if let Some(parent_cost) = &self.config.parent_cost {
+            parent_cost.add(cost);
+        }
+    }
+
     /// Set the system prompt
     pub fn set_system_prompt(&mut self, prompt: String) {
         self.config.system_prompt = Some(prompt);
+        // A prompt set by the user is kept when tools are toggled
+        self.generated_prompt = false;
         // System prompt change invalidates cache
         self.reset_cache_points();
     }
@@ -1535,11 +2514,60 @@ impl Agent {
         self.config.enable_tools = enabled;
     }
 
+    /// Enable or disable a single tool for the rest of the session
+    ///
+    /// A generated system prompt is generated again, so the tool's
+    /// documentation is added or removed, and the prompt cache starts over.
+    pub fn set_tool_enabled(&mut self, tool: &str, enabled: bool) -> Result<(), String> {
+        let tool = tool.trim().to_lowercase();
+        let plus_tool = crate::prompts::PLUS_TOOLS.contains(&tool.as_str());
+        if !crate::prompts::ALL_TOOLS.contains(&tool.as_str()) && !plus_tool {
+            return Err(format!("Unknown tool: '{tool}'"));
+        }
+
+        let mut config = self.config.clone();
+        let disabled = config
+            .disabled_tools
+            .iter()
+            .any(|d| d.to_lowercase() == tool);
+        if enabled {
+            if !plus_tool && !Self::available_tools(&config).contains(&tool.as_str()) {
+                return Err(format!(
+                    "The {tool} tool is not available to this agent (read-only mode or the kind's tool set)"
+                ));
+            }
+            if !disabled {
+                return Err(format!("The {tool} tool is already enabled"));
+            }
+            config.disabled_tools.retain(|d| d.to_lowercase() != tool);
+        } else {
+            if disabled {
+                return Err(format!("The {tool} tool is already disabled"));
+            }
+            config.disabled_tools.push(tool);
+        }
+
+        if self.generated_prompt {
+            Self::generate_system_prompt(&mut config, &self.grammar)?;
+            self.stop_sequences = Some(Self::stop_sequences_for(self.grammar.as_ref(), &config));
+        }
+        self.tool_executor
+            .set_disabled_tools(config.disabled_tools.clone());
+        self.config = config;
+        self.reset_cache_points();
+        Ok(())
+    }
+
     /// Set the thinking budget
     pub fn set_thinking_budget(&mut self, budget: usize) {
         self.config.thinking_budget = budget;
     }
 
+    /// Limit the length of responses, or use the model's default with None
+    pub fn set_max_tokens(&mut self, max_tokens: Option<usize>) {
+        self.config.max_token_output = max_tokens;
+    }
+
     /// Set the model to use
     pub fn set_model(&mut self, model: String) -> Result<(), Box<dyn std::error::Error>> {
         self.config.model = model.clone();
@@ -1550,3 +2578,41 @@ impl Agent {
         Ok(())
     }
 }
+
+/// Thinking budget fitting in the response limit
+///
+/// Thinking counts towards the response's tokens and providers reject a
+/// budget that is not below the limit, so a short limit shrinks the budget to
+/// half of it, or turns thinking off when that is below the smallest budget.
+fn thinking_budget_within(budget: usize, max_tokens: Option<usize>) -> usize {
+    match max_tokens {
+        Some(max_tokens) if budget >= max_tokens => {
+            let shrunk = max_tokens / 2;
+            if shrunk >= MIN_THINKING_BUDGET {
+                shrunk
+            } else {
+                0
+            }
+        }
+        _ => budget,
+    }
+}
+
+/// Start of a tool's arguments, or of its body when it has none, to show what
+/// the tool is doing
+fn args_detail(args: &str, body: &str) -> String {
+    const MAX_CHARS: usize = 60;
+
+    let line = [args, body]
+        .iter()
+        .flat_map(|text| text.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    if line.chars().count() > MAX_CHARS {
+        let cut: String = line.chars().take(MAX_CHARS - 1).collect();
+        format!("{cut}…")
+    } else {
+        line.to_string()
+    }
+}
@@ -0,0 +1,247 @@
+//! Resource budgets for sub-agents
+//!
+//! A parent agent can limit how many tokens, how much wall-clock time and how
+//! many tool invocations a sub-agent may use, and `--max-cost` limits the
+//! estimated cost of the main agent. When a limit is exceeded the agent is
+//! asked to summarize and finish; if it keeps going it is stopped and the
+//! enforcement is reported in its final response.
+//!
+//! Sub-agents may only spend what is left of their parent's cost limit, and
+//! what they spend counts towards the parent's cost.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Limits applied to a single agent run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AgentBudget {
+    /// Maximum total tokens (input + output, summed over all LLM calls)
+    pub max_tokens: Option<usize>,
+    /// Maximum wall-clock time since the agent started working
+    pub max_duration: Option<Duration>,
+    /// Maximum number of tool invocations
+    pub max_tool_calls: Option<usize>,
+    /// Maximum estimated cost in USD, for models with a known price
+    pub max_cost: Option<f64>,
+}
+
+impl AgentBudget {
+    /// Whether any limit is configured
+    pub fn is_limited(&self) -> bool {
+        self.max_tokens.is_some()
+            || self.max_duration.is_some()
+            || self.max_tool_calls.is_some()
+            || self.max_cost.is_some()
+    }
+
+    /// Check usage against the limits, returning the first exceeded limit
+    pub fn check(&self, usage: &BudgetUsage) -> Option<BudgetExceeded> {
+        if let Some(max) = self.max_tokens {
+            if usage.tokens >= max {
+                return Some(BudgetExceeded::Tokens {
+                    used: usage.tokens,
+                    limit: max,
+                });
+            }
+        }
+
+        if let Some(max) = self.max_cost {
+            if usage.cost >= max {
+                return Some(BudgetExceeded::Cost {
+                    used: usage.cost,
+                    limit: max,
+                });
+            }
+        }
+
+        if let Some(max) = self.max_tool_calls {
+            if usage.tool_calls >= max {
+                return Some(BudgetExceeded::ToolCalls {
+                    used: usage.tool_calls,
+                    limit: max,
+                });
+            }
+        }
+
+        if let (Some(max), Some(started)) = (self.max_duration, usage.started_at) {
+            let elapsed = started.elapsed();
+            if elapsed >= max {
+                return Some(BudgetExceeded::Time {
+                    elapsed,
+                    limit: max,
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Cost left before the cost limit is reached, if there is one
+    pub fn remaining_cost(&self, usage: &BudgetUsage) -> Option<f64> {
+        self.max_cost.map(|max| (max - usage.cost).max(0.0))
+    }
+}
+
+/// Estimated cost spent by sub-agents, shared between them and their parent
+#[derive(Debug, Clone, Default)]
+pub struct SharedCost(Arc<Mutex<f64>>);
+
+impl SharedCost {
+    /// Add the cost of an LLM call
+    pub fn add(&self, cost: f64) {
+        *self.0.lock().unwrap() += cost;
+    }
+
+    /// Take the cost added since the last call
+    pub fn take(&self) -> f64 {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+/// Cost limit an agent passes on to the sub-agents it starts
+#[derive(Debug, Clone, Default)]
+pub struct SubAgentCost {
+    /// What is left of the parent's cost limit
+    pub ceiling: Option<f64>,
+    /// Where the sub-agents add what they spend
+    pub spent: SharedCost,
+}
+
+impl SubAgentCost {
+    /// Share the ceiling between sub-agents running at the same time
+    pub fn split(&self, parts: usize) -> SubAgentCost {
+        SubAgentCost {
+            ceiling: self.ceiling.map(|ceiling| ceiling / parts.max(1) as f64),
+            spent: self.spent.clone(),
+        }
+    }
+}
+
+/// Resources consumed by an agent so far
+#[derive(Debug, Clone, Default)]
+pub struct BudgetUsage {
+    /// Total tokens used across all LLM calls
+    pub tokens: usize,
+    /// Number of tool invocations
+    pub tool_calls: usize,
+    /// Estimated cost in USD of all LLM calls
+    pub cost: f64,
+    /// When the agent started working on its first message
+    pub started_at: Option<Instant>,
+    /// The limit that was exceeded, once the agent has been asked to finish
+    pub enforcement: Option<BudgetExceeded>,
+}
+
+/// Description of an exceeded budget limit
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetExceeded {
+    Tokens { used: usize, limit: usize },
+    Time { elapsed: Duration, limit: Duration },
+    ToolCalls { used: usize, limit: usize },
+    Cost { used: f64, limit: f64 },
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetExceeded::Tokens { used, limit } => {
+                write!(f, "token budget exceeded ({used} of {limit} tokens used)")
+            }
+            BudgetExceeded::Time { elapsed, limit } => write!(
+                f,
+                "time budget exceeded ({}s of {}s elapsed)",
+                elapsed.as_secs(),
+                limit.as_secs()
+            ),
+            BudgetExceeded::ToolCalls { used, limit } => {
+                write!(
+                    f,
+                    "tool call budget exceeded ({used} of {limit} calls used)"
+                )
+            }
+            BudgetExceeded::Cost { used, limit } => {
+                write!(f, "cost budget exceeded (${used:.2} of ${limit:.2} spent)")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_check() {
+        let budget = AgentBudget {
+            max_tokens: Some(1000),
+            max_duration: None,
+            max_tool_calls: Some(3),
+            max_cost: None,
+        };
+        let mut usage = BudgetUsage::default();
+        assert!(budget.check(&usage).is_none());
+
+        usage.tool_calls = 3;
+        assert_eq!(
+            budget.check(&usage),
+            Some(BudgetExceeded::ToolCalls { used: 3, limit: 3 })
+        );
+
+        usage.tokens = 1200;
+        assert_eq!(
+            budget.check(&usage),
+            Some(BudgetExceeded::Tokens {
+                used: 1200,
+                limit: 1000
+            })
+        );
+
+        let budget = AgentBudget {
+            max_cost: Some(0.5),
+            ..Default::default()
+        };
+        usage.cost = 0.6;
+        assert_eq!(
+            budget.check(&usage),
+            Some(BudgetExceeded::Cost {
+                used: 0.6,
+                limit: 0.5
+            })
+        );
+        assert_eq!(
+            budget.check(&usage).unwrap().to_string(),
+            "cost budget exceeded ($0.60 of $0.50 spent)"
+        );
+
+        assert!(!AgentBudget::default().is_limited());
+        assert!(AgentBudget::default().check(&usage).is_none());
+    }
+
+    #[test]
+    fn test_sub_agent_cost() {
+        let budget = AgentBudget {
+            max_cost: Some(1.0),
+            ..Default::default()
+        };
+        let mut usage = BudgetUsage {
+            cost: 0.25,
+            ..Default::default()
+        };
+        assert_eq!(budget.remaining_cost(&usage), Some(0.75));
+        usage.cost = 1.5;
+        assert_eq!(budget.remaining_cost(&usage), Some(0.0));
+        assert_eq!(AgentBudget::default().remaining_cost(&usage), None);
+
+        let cost = SubAgentCost {
+            ceiling: Some(0.75),
+            spent: SharedCost::default(),
+        };
+        let split = cost.split(3);
+        assert_eq!(split.ceiling, Some(0.25));
+        split.spent.add(0.125);
+        cost.spent.add(0.125);
+        assert_eq!(cost.spent.take(), 0.25);
+        assert_eq!(cost.spent.take(), 0.0);
+    }
+}
@@ -0,0 +1,153 @@
+//! Commit checkpoints of files edited by agents
+//!
+//! With `--checkpoint-commits`, every batch of successful write/patch calls is
+//! committed to a dedicated `termineer/<session>` branch. The commits are built
+//! with a temporary index, so the checked-out branch, the user's index and
+//! uncommitted changes to other files are left untouched. The work can then be
+//! reviewed with `git log -p termineer/<session>` and reverted file by file with
+//! `git checkout <commit> -- <path>`.
+
+use lazy_static::lazy_static;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Prefix of checkpoint branches
+pub const BRANCH_PREFIX: &str = "termineer/";
+
+/// Hash of the empty tree, the parent of the first commit in a new repository
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Longest diff included in the commit message request, in characters
+pub const MAX_DIFF_CHARS: usize = 12_000;
+
+lazy_static! {
+    /// Branch receiving the checkpoints of this session
+    static ref SESSION_BRANCH: String = format!(
+        "{BRANCH_PREFIX}{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    );
+}
+
+/// Branch receiving the checkpoints of this session
+pub fn branch() -> &'static str {
+    &SESSION_BRANCH
+}
+
+/// A tree with the edited files, ready to be committed
+pub struct PreparedCheckpoint {
+    parent: Option<String>,
+    /// Session branch tip the checkpoint builds on (None if the branch is new)
+    branch_tip: Option<String>,
+    tree: String,
+    /// Diff of the checkpoint against its parent
+    pub diff: String,
+}
+
+/// Run git, optionally with a separate index file, and return its trimmed output
+async fn git(args: &[&str], index: Option<&Path>) -> Result<String, String> {
+    let output = crate::git::git_in(Path::new("."), args, index).await?;
+    Ok(output.trim().to_string())
+}
+
+/// Temporary index used to build one checkpoint tree
+fn index_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "termineer-checkpoint-{}.index",
+        uuid::Uuid::new_v4()
+    ))
+}
+
+/// Build a tree with the current content of the edited files on top of the
+/// last checkpoint (or HEAD for the first one)
+///
+/// Returns None when the files are unchanged since the last checkpoint.
+pub async fn prepare(paths: &BTreeSet<String>) -> Result<Option<PreparedCheckpoint>, String> {
+    git(&["rev-parse", "--git-dir"], None)
+        .await
+        .map_err(|_| "not inside a git repository".to_string())?;
+
+    let branch_ref = format!("refs/heads/{}", branch());
+    let branch_tip = git(&["rev-parse", "--verify", "-q", branch_ref.as_str()], None)
+        .await
+        .ok();
+    let parent = match &branch_tip {
+        Some(commit) => Some(commit.clone()),
+        None => git(&["rev-parse", "--verify", "-q", "HEAD"], None)
+            .await
+            .ok(),
+    };
+
+    let index = index_path();
+    match &parent {
+        Some(parent) => git(&["read-tree", parent.as_str()], Some(&index)).await?,
+        None => git(&["read-tree", "--empty"], Some(&index)).await?,
+    };
+
+    let mut add_args = vec!["add", "-A", "--"];
+    add_args.extend(paths.iter().map(String::as_str));
+    let result = async {
+        git(&add_args, Some(&index)).await?;
+        git(&["write-tree"], Some(&index)).await
+    }
+    .await;
+    let _ = tokio::fs::remove_file(&index).await;
+    let tree = result?;
+
+    let diff = match &parent {
+        Some(parent) => {
+            let parent_tree = format!("{parent}^{{tree}}");
+            if git(&["rev-parse", parent_tree.as_str()], None).await? == tree {
+                return Ok(None);
+            }
+            git(&["diff", parent_tree.as_str(), tree.as_str()], None).await?
+        }
+        None => git(&["diff", EMPTY_TREE, tree.as_str()], None).await?,
+    };
+
+    Ok(Some(PreparedCheckpoint {
+        parent,
+        branch_tip,
+        tree,
+        diff,
+    }))
+}
+
+/// Commit a prepared tree to the session branch and return the short commit hash
+pub async fn commit(prepared: &PreparedCheckpoint, message: &str) -> Result<String, String> {
+    let mut args = vec!["commit-tree", prepared.tree.as_str(), "-m", message];
+    if let Some(parent) = &prepared.parent {
+        args.extend(["-p", parent.as_str()]);
+    }
+    let commit = git(&args, None).await?;
+    // Refuse to move the branch if another checkpoint landed in the meantime
+    let branch_ref = format!("refs/heads/{}", branch());
+    let expected_tip = prepared.branch_tip.as_deref().unwrap_or("");
+    git(
+        &[
+            "update-ref",
+            branch_ref.as_str(),
+            commit.as_str(),
+            expected_tip,
+        ],
+        None,
+    )
+    .await?;
+    Ok(commit.chars().take(8).collect())
+}
+
+/// Message used when no commit message could be generated
+pub fn fallback_message(paths: &BTreeSet<String>) -> String {
+    let files: Vec<&str> = paths.iter().map(String::as_str).collect();
+    format!("Update {}", files.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_message() {
+        let paths: BTreeSet<String> = ["src/b.rs", "src/a.rs"].map(String::from).into();
+        assert_eq!(fallback_message(&paths), "Update src/a.rs, src/b.rs");
+    }
+}
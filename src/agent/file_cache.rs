@@ -0,0 +1,157 @@
+//! Files already loaded into the conversation
+//!
+//! Reading a file the agent has already seen repeats its content in the
+//! context. The agent remembers the files returned by whole-file reads with a
+//! hash of their content and of the tool result holding it; reading them again
+//! while both are unchanged returns a short note pointing at the earlier result
+//! instead. A file that changed on disk, or whose earlier result was truncated,
+//! edited or removed from the conversation, is read again.
+
+use crate::llm::{Content, Message};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Where a file's content is in the conversation and the state it was read in
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Hash of the file's content when it was read
+    content: [u8; 32],
+    /// Conversation index of the tool result holding the content
+    message: usize,
+    /// Hash of that message's text, to notice when it was changed
+    message_content: [u8; 32],
+    /// Index of the tool result, as shown to the model
+    tool_index: usize,
+}
+
+/// Files of the conversation, by canonical path
+#[derive(Debug, Clone, Default)]
+pub struct FileCache {
+    entries: HashMap<PathBuf, Entry>,
+}
+
+impl FileCache {
+    /// Note replacing a read whose files are all unchanged and still in the conversation
+    pub fn unchanged(&self, args: &str, conversation: &[Message]) -> Option<String> {
+        let paths = whole_file_paths(args)?;
+        let mut tool_indexes = Vec::new();
+        for path in &paths {
+            let (canonical, content) = stamp(path)?;
+            let entry = self.entries.get(&canonical)?;
+            let intact = matches!(
+                conversation.get(entry.message).map(|message| &message.content),
+                Some(Content::Text { text }) if hash(text.as_bytes()) == entry.message_content
+            );
+            if !intact || entry.content != content {
+                return None;
+            }
+            tool_indexes.push(entry.tool_index);
+        }
+        tool_indexes.sort_unstable();
+        tool_indexes.dedup();
+
+        let results: Vec<String> = tool_indexes.iter().map(usize::to_string).collect();
+        Some(format!(
+            "{} already in context in tool result {} (unchanged since it was read). \
+             Use lines=START-END to read part of a file again.",
+            paths.join(", "),
+            results.join(", ")
+        ))
+    }
+
+    /// Remember the files of a successful read whose result is at `message`
+    pub fn record(
+        &mut self,
+        args: &str,
+        conversation: &[Message],
+        message: usize,
+        tool_index: usize,
+    ) {
+        let Some(paths) = whole_file_paths(args) else {
+            return;
+        };
+        let Some(Content::Text { text }) = conversation.get(message).map(|m| &m.content) else {
+            return;
+        };
+        let message_content = hash(text.as_bytes());
+        for path in paths {
+            if let Some((canonical, content)) = stamp(&path) {
+                let entry = Entry {
+                    content,
+                    message,
+                    message_content,
+                    tool_index,
+                };
+                self.entries.insert(canonical, entry);
+            }
+        }
+    }
+}
+
+/// Paths of a read returning whole files, or None when lines are selected
+fn whole_file_paths(args: &str) -> Option<Vec<String>> {
+    let mut paths = Vec::new();
+    for part in args.split_whitespace() {
+        if ["lines=", "offset=", "limit="]
+            .iter()
+            .any(|prefix| part.starts_with(prefix))
+        {
+            return None;
+        }
+        paths.push(part.to_string());
+    }
+    (!paths.is_empty()).then_some(paths)
+}
+
+/// Canonical path and content hash of a regular file
+fn stamp(path: &str) -> Option<(PathBuf, [u8; 32])> {
+    let canonical = std::fs::canonicalize(path).ok()?;
+    if !std::fs::metadata(&canonical).ok()?.is_file() {
+        return None;
+    }
+    let content = std::fs::read(&canonical).ok()?;
+    Some((canonical, hash(&content)))
+}
+
+/// SHA-256 of some bytes
+fn hash(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MessageInfo;
+
+    #[test]
+    fn test_unchanged() {
+        let path =
+            std::env::temp_dir().join(format!("termineer-cache-{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "one\n").unwrap();
+        let args = path.to_string_lossy().to_string();
+        let mut conversation = vec![Message::text("user", "one".to_string(), MessageInfo::User)];
+
+        let mut cache = FileCache::default();
+        assert_eq!(cache.unchanged(&args, &conversation), None);
+        cache.record(&args, &conversation, 0, 3);
+        assert!(cache
+            .unchanged(&args, &conversation)
+            .unwrap()
+            .contains("tool result 3"));
+        assert_eq!(
+            cache.unchanged(&format!("{args} lines=1-1"), &conversation),
+            None
+        );
+
+        // Content of the same size written within the mtime resolution is noticed
+        std::fs::write(&path, "two\n").unwrap();
+        assert_eq!(cache.unchanged(&args, &conversation), None);
+
+        // So is a tool result edited to the same length
+        cache.record(&args, &conversation, 0, 4);
+        conversation[0] = Message::text("user", "owe".to_string(), MessageInfo::User);
+        assert_eq!(cache.unchanged(&args, &conversation), None);
+        let _ = std::fs::remove_file(&path);
+    }
+}
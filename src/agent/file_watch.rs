@@ -0,0 +1,120 @@
+//! Noticing files changed on disk behind the agent's back
+//!
+//! Files the agent reads, writes or patches are remembered with their
+//! modification time and size. Before each request the files are checked
+//! again; when some changed since the agent last saw them (edited in the
+//! user's editor, reformatted, switched to another branch), a note listing
+//! them is added to the conversation so the agent reads them again instead of
+//! patching stale content.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Modification time and size of a file, None once it was deleted
+type Stamp = Option<(SystemTime, u64)>;
+
+/// Files the agent has seen, by canonical path
+#[derive(Debug, Clone, Default)]
+pub struct FileWatch {
+    files: HashMap<PathBuf, Stamp>,
+}
+
+impl FileWatch {
+    /// Remember the current state of files the agent just read or wrote
+    pub fn seen<'a>(&mut self, paths: impl IntoIterator<Item = &'a str>) {
+        for path in paths {
+            if let Ok(canonical) = std::fs::canonicalize(path) {
+                if canonical.is_file() {
+                    let current = stamp(&canonical);
+                    self.files.insert(canonical, current);
+                }
+            }
+        }
+    }
+
+    /// Files that changed or were deleted since the agent last saw them
+    ///
+    /// Each change is reported once: the new state counts as seen.
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (path, known) in self.files.iter_mut() {
+            let current = stamp(path);
+            if current != *known {
+                *known = current;
+                changed.push(path.clone());
+            }
+        }
+        changed.sort();
+        changed
+    }
+}
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Paths given to the read tool, without its parameters
+pub fn read_paths(args: &str) -> impl Iterator<Item = &str> {
+    args.split_whitespace().filter(|part| {
+        !["lines=", "offset=", "limit="]
+            .iter()
+            .any(|prefix| part.starts_with(prefix))
+    })
+}
+
+/// Note telling the agent which files changed, with paths relative to the workspace
+pub fn change_note(changed: &[PathBuf]) -> String {
+    let cwd = std::env::current_dir().ok();
+    let paths: Vec<String> = changed
+        .iter()
+        .map(|path| {
+            let relative = cwd
+                .as_deref()
+                .and_then(|cwd| path.strip_prefix(cwd).ok())
+                .unwrap_or(path);
+            let deleted = if path.exists() { "" } else { " (deleted)" };
+            format!("- {}{deleted}", relative.display())
+        })
+        .collect();
+    format!(
+        "*These files changed on disk since you last read or wrote them:*\n{}\n\
+         *Read them again before editing them; your earlier copies are outdated.*",
+        paths.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed() {
+        let dir = std::env::temp_dir().join(format!("termineer-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a.txt"), dir.join("b.txt"));
+        std::fs::write(&a, "a").unwrap();
+        std::fs::write(&b, "b").unwrap();
+
+        let mut watch = FileWatch::default();
+        watch.seen([a.to_str().unwrap(), b.to_str().unwrap()]);
+        assert!(watch.changed().is_empty());
+
+        std::fs::write(&a, "edited").unwrap();
+        std::fs::remove_file(&b).unwrap();
+        let changed = watch.changed();
+        assert_eq!(changed.len(), 2);
+        assert!(change_note(&changed).contains("b.txt (deleted)"));
+
+        // Reported once
+        assert!(watch.changed().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_read_paths() {
+        let paths: Vec<&str> = read_paths("src/a.rs lines=1-20 src/b.rs").collect();
+        assert_eq!(paths, vec!["src/a.rs", "src/b.rs"]);
+    }
+}
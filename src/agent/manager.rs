@@ -3,13 +3,18 @@
 use super::agent_impl::Agent;
 use super::types::{
     AgentError, AgentId, AgentMessage, AgentSender, AgentState, InterruptReceiver, InterruptSender,
-    InterruptSignal, StateReceiver,
+    InterruptSignal, Reminder, StateReceiver, StateSender,
 };
 use crate::agent::AgentReceiver;
 use crate::config::Config;
+use crate::llm::TokenUsage;
 use crate::output::{SharedBuffer, CURRENT_BUFFER};
+use crate::tools::progress::{self, ProgressReceiver, ToolProgress};
 use indexmap::IndexMap;
-use tokio::sync::{mpsc, watch};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio::task::JoinHandle;
 
 /// Handle to an agent task
@@ -35,9 +40,64 @@ pub struct AgentHandle {
 
     /// State of this agent
     pub state: StateReceiver,
+
+    /// Agent that spawned this agent, if any
+    pub parent: Option<AgentId>,
+
+    /// Spawn depth (0 for top-level agents)
+    pub depth: usize,
+
+    /// Fingerprint of the task this agent was spawned with, for loop detection
+    pub task_fingerprint: Option<u64>,
+
+    /// Count of user inputs sent to the agent, watched to cancel speculative requests
+    pub interjections: watch::Sender<u64>,
+
+    /// Tokens used by the agent's LLM requests so far
+    pub usage: watch::Receiver<TokenUsage>,
+
+    /// Progress of the tool the agent is running, if it reports any
+    pub progress: ProgressReceiver,
+}
+
+/// Default maximum depth of agents spawning agents
+pub const DEFAULT_MAX_SPAWN_DEPTH: usize = 3;
+
+/// Agents by ID, with a secondary index by name
+#[derive(Default)]
+struct Registry {
+    /// Map of agent ID to agent handle (primary index)
+    agents: IndexMap<AgentId, Arc<AgentHandle>>,
+
+    /// Map of agent name to agent ID for efficient name lookups (secondary index)
+    name_index: IndexMap<String, AgentId>,
 }
 
-// No external synchronization primitives needed
+impl Registry {
+    /// Add an agent, refusing a name that is already taken
+    fn insert(&mut self, handle: AgentHandle) -> Result<(), AgentError> {
+        if self.name_index.contains_key(&handle.name) {
+            return Err(AgentError::NameTaken(handle.name.clone()));
+        }
+        self.name_index.insert(handle.name.clone(), handle.id);
+        self.agents.insert(handle.id, Arc::new(handle));
+        Ok(())
+    }
+
+    fn remove(&mut self, id: AgentId) -> Option<Arc<AgentHandle>> {
+        let handle = self.agents.shift_remove(&id)?;
+        self.name_index.shift_remove(&handle.name);
+        Some(handle)
+    }
+}
+
+/// Pending reminders with the timer tasks that deliver them
+struct Reminders {
+    pending: IndexMap<u64, (Reminder, JoinHandle<()>)>,
+
+    /// Next reminder ID to assign
+    next_id: u64,
+}
 
 /// Manager for multiple agent instances
 ///
@@ -47,32 +107,116 @@ pub struct AgentHandle {
 /// - Sending messages and interruption signals
 /// - Tracking agent state and status
 ///
-/// The manager maintains two indices:
-/// 1. A primary index by AgentId for fast ID-based lookups
-/// 2. A secondary index by name for convenient name-based lookups
+/// The manager is shared by agent tasks and the TUI thread, so every method
+/// takes `&self` and locks only what it touches, for as short as possible:
+/// lookups clone the agent's handle and work on it outside the lock, agents
+/// are built before they are registered, and no lock is held across an
+/// await point or while another of the manager's locks is held.
 pub struct AgentManager {
-    /// Map of agent ID to agent handle (primary index)
-    agents: IndexMap<AgentId, AgentHandle>,
-
-    /// Map of agent name to agent ID for efficient name lookups (secondary index)
-    name_index: IndexMap<String, AgentId>,
+    /// Handles of the running agents
+    registry: RwLock<Registry>,
 
     /// Next agent ID to assign
-    next_id: u64,
+    next_id: AtomicU64,
+
+    /// Maximum allowed spawn depth for child agents
+    max_spawn_depth: AtomicUsize,
+
+    /// Pending reminders
+    reminders: Mutex<Reminders>,
 }
 
 impl AgentManager {
     /// Create a new agent manager
     pub fn new() -> Self {
         Self {
-            agents: IndexMap::new(),
-            name_index: IndexMap::new(),
-            next_id: 1,
+            registry: RwLock::new(Registry::default()),
+            next_id: AtomicU64::new(1),
+            max_spawn_depth: AtomicUsize::new(DEFAULT_MAX_SPAWN_DEPTH),
+            reminders: Mutex::new(Reminders {
+                pending: IndexMap::new(),
+                next_id: 1,
+            }),
         }
     }
 
+    fn registry(&self) -> std::sync::RwLockReadGuard<'_, Registry> {
+        self.registry.read().unwrap()
+    }
+
+    fn registry_mut(&self) -> std::sync::RwLockWriteGuard<'_, Registry> {
+        self.registry.write().unwrap()
+    }
+
+    /// Set the maximum allowed spawn depth for child agents
+    pub fn set_max_spawn_depth(&self, max_depth: usize) {
+        self.max_spawn_depth.store(max_depth, Ordering::SeqCst);
+    }
+
+    /// Create a child agent spawned by another agent for a given task
+    ///
+    /// Refuses to spawn when the depth limit would be exceeded or when an
+    /// ancestor is already working on the same task text.
+    pub fn create_child_agent(
+        &self,
+        name: String,
+        config: Config,
+        parent: Option<AgentId>,
+        task: &str,
+    ) -> Result<AgentId, AgentError> {
+        let fingerprint = task_fingerprint(task);
+
+        let buffer = SharedBuffer::new(100);
+        self.spawn_agent(
+            name.clone(),
+            buffer,
+            |registry, handle| {
+                handle.parent = parent;
+                handle.depth = self.check_spawn(registry, parent, fingerprint)?;
+                handle.task_fingerprint = Some(fingerprint);
+                Ok(())
+            },
+            |id, state_sender| Agent::new(id, name, config, state_sender),
+        )
+    }
+
+    /// Validate a spawn request and return the depth of the new agent
+    fn check_spawn(
+        &self,
+        registry: &Registry,
+        parent: Option<AgentId>,
+        fingerprint: u64,
+    ) -> Result<usize, AgentError> {
+        let max_spawn_depth = self.max_spawn_depth.load(Ordering::SeqCst);
+        let depth = parent
+            .and_then(|id| registry.agents.get(&id))
+            .map(|handle| handle.depth + 1)
+            .unwrap_or(1);
+
+        if depth > max_spawn_depth {
+            return Err(AgentError::SpawnDepthExceeded {
+                depth,
+                max: max_spawn_depth,
+            });
+        }
+
+        // Walk the lineage looking for an ancestor with the same task
+        let mut current = parent;
+        while let Some(id) = current {
+            let Some(handle) = registry.agents.get(&id) else {
+                break;
+            };
+            if handle.task_fingerprint == Some(fingerprint) {
+                return Err(AgentError::SpawnLoopDetected(handle.name.clone()));
+            }
+            current = handle.parent;
+        }
+
+        Ok(depth)
+    }
+
     /// Create a new agent with a new buffer
-    pub fn create_agent(&mut self, name: String, config: Config) -> Result<AgentId, AgentError> {
+    pub fn create_agent(&self, name: String, config: Config) -> Result<AgentId, AgentError> {
         // Create a new buffer
         let buffer = SharedBuffer::new(100);
 
@@ -82,10 +226,54 @@ impl AgentManager {
 
     /// Create a new agent with an existing buffer
     pub fn create_agent_with_buffer(
-        &mut self,
+        &self,
         name: String,
         config: Config,
         buffer: SharedBuffer,
+    ) -> Result<AgentId, AgentError> {
+        self.spawn_agent(
+            name.clone(),
+            buffer,
+            |_, _| Ok(()),
+            |id, state_sender| Agent::new(id, name, config, state_sender),
+        )
+    }
+
+    /// Clone an agent into a new agent with a snapshot of its buffer
+    ///
+    /// The new agent continues from the source's conversation and configuration;
+    /// both agents then evolve independently.
+    pub fn fork_agent(&self, source: &Agent, name: String) -> Result<AgentId, AgentError> {
+        let source_handle = self
+            .get_agent_handle(source.id)
+            .ok_or(AgentError::AgentNotFound(source.id))?;
+        let buffer = source_handle.buffer.snapshot();
+
+        self.spawn_agent(
+            name.clone(),
+            buffer,
+            |_, handle| {
+                handle.parent = source_handle.parent;
+                handle.depth = source_handle.depth;
+                Ok(())
+            },
+            |id, state_sender| source.fork(id, name, state_sender),
+        )
+    }
+
+    /// Create channels and an ID, build the agent and spawn its task
+    ///
+    /// The agent is built without holding any lock. Its task waits until the
+    /// agent is registered, and exits without running it if registration fails.
+    fn spawn_agent(
+        &self,
+        name: String,
+        buffer: SharedBuffer,
+        configure: impl FnOnce(&Registry, &mut AgentHandle) -> Result<(), AgentError>,
+        build: impl FnOnce(
+            AgentId,
+            StateSender,
+        ) -> Result<Agent, Box<dyn std::error::Error + Send + Sync>>,
     ) -> Result<AgentId, AgentError> {
         // Create message channel for this agent
         let (sender, receiver) = mpsc::channel(100);
@@ -94,19 +282,31 @@ impl AgentManager {
         let (interrupt_sender, interrupt_receiver) = mpsc::channel(10);
 
         let (state_sender, state) = watch::channel(AgentState::Idle);
+        let (interjections, interjection_receiver) = watch::channel(0);
+        let (usage_sender, usage) = watch::channel(TokenUsage::default());
+        let (progress_sender, progress) = progress::channel();
 
         // Generate unique ID
-        let id = AgentId(self.next_id);
-        self.next_id += 1;
+        let id = AgentId(self.next_id.fetch_add(1, Ordering::SeqCst));
 
         // Create the agent with state channel
-        let agent = match Agent::new(id, name.clone(), config, state_sender) {
+        let mut agent = match build(id, state_sender) {
             Ok(agent) => agent,
             Err(e) => return Err(AgentError::CreationFailed(e.to_string())),
         };
+        agent.set_interjections(interjection_receiver);
+        agent.set_usage_reporter(usage_sender);
+        agent.set_progress_reporter(progress_sender);
 
         // Spawn agent as a task with the provided buffer
-        let join_handle = spawn_agent_task(agent, buffer.clone(), receiver, interrupt_receiver);
+        let (registered, registration) = oneshot::channel();
+        let join_handle = spawn_agent_task(
+            agent,
+            buffer.clone(),
+            receiver,
+            interrupt_receiver,
+            registration,
+        );
 
         // Create and store handle with both senders
         let handle = AgentHandle {
@@ -117,61 +317,95 @@ impl AgentManager {
             join_handle,
             buffer,
             state,
+            parent: None,
+            depth: 0,
+            task_fingerprint: None,
+            interjections,
+            usage,
+            progress,
         };
-
-        // Store the name in the index first
-        self.name_index.insert(handle.name.clone(), id);
-
-        // Then store the agent handle with its ID
-        self.agents.insert(id, handle);
+        self.register(handle, configure)?;
+        let _ = registered.send(());
 
         Ok(id)
     }
 
+    /// Validate a new agent's handle against the registry and insert it
+    ///
+    /// Both happen under one write lock, so concurrent spawns cannot all pass
+    /// the same depth, loop or name check before any of them is registered.
+    fn register(
+        &self,
+        mut handle: AgentHandle,
+        configure: impl FnOnce(&Registry, &mut AgentHandle) -> Result<(), AgentError>,
+    ) -> Result<(), AgentError> {
+        let mut registry = self.registry_mut();
+        configure(&registry, &mut handle)?;
+        registry.insert(handle)
+    }
+
     /// Send a message to an agent
     pub fn send_message(&self, id: AgentId, message: AgentMessage) -> Result<(), AgentError> {
-        if let Some(handle) = self.agents.get(&id) {
-            handle
-                .sender
-                .try_send(message)
-                .map_err(|_| AgentError::MessageDeliveryFailed)?;
-            Ok(())
-        } else {
-            Err(AgentError::AgentNotFound(id))
+        let handle = self
+            .get_agent_handle(id)
+            .ok_or(AgentError::AgentNotFound(id))?;
+        let is_user_input = matches!(
+            message,
+            AgentMessage::UserInput(_) | AgentMessage::ParticipantInput { .. }
+        );
+        handle
+            .sender
+            .try_send(message)
+            .map_err(|_| AgentError::MessageDeliveryFailed)?;
+        if is_user_input {
+            handle.interjections.send_modify(|count| *count += 1);
         }
+        Ok(())
     }
 
     pub fn get_agent_buffer(&self, id: AgentId) -> Result<SharedBuffer, AgentError> {
-        if let Some(handle) = self.agents.get(&id) {
-            return Ok(handle.buffer.clone());
-        } else {
-            Err(AgentError::AgentNotFound(id))
-        }
+        self.get_agent_handle(id)
+            .map(|handle| handle.buffer.clone())
+            .ok_or(AgentError::AgentNotFound(id))
     }
 
     /// Get the current state of an agent
     pub fn get_agent_state(&self, id: AgentId) -> Result<AgentState, AgentError> {
-        if let Some(handle) = self.agents.get(&id) {
-            Ok(handle.state.borrow().clone())
-        } else {
-            Err(AgentError::AgentNotFound(id))
-        }
+        self.get_agent_handle(id)
+            .map(|handle| handle.state.borrow().clone())
+            .ok_or(AgentError::AgentNotFound(id))
     }
 
-    /// Get a reference to an agent handle by ID
-    pub fn get_agent_handle(&self, id: AgentId) -> Option<&AgentHandle> {
-        self.agents.get(&id)
+    /// Follow the state of an agent
+    pub fn subscribe_agent_state(&self, id: AgentId) -> Result<StateReceiver, AgentError> {
+        self.get_agent_handle(id)
+            .map(|handle| handle.state.clone())
+            .ok_or(AgentError::AgentNotFound(id))
     }
 
-    /// Get a mutable reference to an agent handle by ID
-    #[allow(dead_code)]
-    pub fn get_agent_handle_mut(&mut self, id: AgentId) -> Option<&mut AgentHandle> {
-        self.agents.get_mut(&id)
+    /// Get the tokens an agent has used so far
+    pub fn get_agent_usage(&self, id: AgentId) -> Result<TokenUsage, AgentError> {
+        self.get_agent_handle(id)
+            .map(|handle| handle.usage.borrow().clone())
+            .ok_or(AgentError::AgentNotFound(id))
+    }
+
+    /// Get the progress of the tool an agent is running
+    pub fn get_agent_progress(&self, id: AgentId) -> Result<Option<ToolProgress>, AgentError> {
+        self.get_agent_handle(id)
+            .map(|handle| handle.progress.borrow().clone())
+            .ok_or(AgentError::AgentNotFound(id))
+    }
+
+    /// Get the handle of an agent by ID, to use without holding the registry lock
+    pub fn get_agent_handle(&self, id: AgentId) -> Option<Arc<AgentHandle>> {
+        self.registry().agents.get(&id).cloned()
     }
 
     /// Get a list of all agents with their IDs and names
     pub fn get_agents(&self) -> Vec<(AgentId, String)> {
-        self.agents
+        self.registry()
+            .agents
             .iter()
             .map(|(id, handle)| (*id, handle.name.clone()))
             .collect()
@@ -180,23 +414,12 @@ impl AgentManager {
     /// Get an agent ID by name
     /// Returns None if no agent with that name exists
     pub fn get_agent_id_by_name(&self, name: &str) -> Option<AgentId> {
-        self.name_index.get(name).copied()
+        self.registry().name_index.get(name).copied()
     }
 
     /// Interrupt an agent through the dedicated interrupt channel
     pub fn interrupt_agent(&self, id: AgentId) -> Result<(), AgentError> {
-        if let Some(handle) = self.agents.get(&id) {
-            // Send through the dedicated interrupt channel with optional reason
-            handle
-                .interrupt_sender
-                .try_send(InterruptSignal::new(Some(
-                    "User requested interruption".to_string(),
-                )))
-                .map_err(|_| AgentError::MessageDeliveryFailed)?;
-            Ok(())
-        } else {
-            Err(AgentError::AgentNotFound(id))
-        }
+        self.interrupt_agent_with_reason(id, "User requested interruption".to_string())
     }
 
     /// Interrupt an agent with specific reason
@@ -205,87 +428,350 @@ impl AgentManager {
         id: AgentId,
         reason: String,
     ) -> Result<(), AgentError> {
-        if let Some(handle) = self.agents.get(&id) {
-            handle
-                .interrupt_sender
-                .try_send(InterruptSignal::new(Some(reason)))
-                .map_err(|_| AgentError::MessageDeliveryFailed)?;
-            Ok(())
-        } else {
-            Err(AgentError::AgentNotFound(id))
-        }
+        let handle = self
+            .get_agent_handle(id)
+            .ok_or(AgentError::AgentNotFound(id))?;
+        handle
+            .interrupt_sender
+            .try_send(InterruptSignal::new(Some(reason)))
+            .map_err(|_| AgentError::MessageDeliveryFailed)
     }
 
-    /// Remove an agent from the manager without termination
-    pub fn remove_agent(&mut self, id: AgentId) -> Result<(), AgentError> {
-        if let Some(handle) = self.agents.shift_remove(&id) {
-            // Remove from name index
-            self.name_index.shift_remove(&handle.name);
-            // Abort the task
-            handle.join_handle.abort();
-            Ok(())
-        } else {
-            Err(AgentError::AgentNotFound(id))
-        }
+    /// Schedule a message to be delivered to an agent after a delay
+    ///
+    /// Each reminder is driven by its own timer task, which hands the reminder
+    /// back to the global manager for delivery once it is due.
+    pub fn schedule_reminder(
+        &self,
+        target: AgentId,
+        source: Option<AgentId>,
+        delay: Duration,
+        message: String,
+    ) -> Result<Reminder, AgentError> {
+        let buffer = self.get_agent_buffer(target)?;
+
+        let mut reminders = self.reminders.lock().unwrap();
+        let id = reminders.next_id;
+        reminders.next_id += 1;
+
+        let due = chrono::Local::now()
+            + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero());
+        let reminder = Reminder {
+            id,
+            target,
+            source,
+            due,
+            message,
+        };
+
+        let timer = crate::output::spawn_with_buffer(buffer, async move {
+            tokio::time::sleep(delay).await;
+            super::deliver_reminder(id);
+        });
+        reminders.pending.insert(id, (reminder.clone(), timer));
+
+        Ok(reminder)
     }
 
-    /// Terminate an agent
-    #[allow(dead_code)]
-    pub async fn terminate_agent(&mut self, id: AgentId) -> Result<(), AgentError> {
-        if let Some(handle) = self.agents.shift_remove(&id) {
-            // Send interrupt signal first to stop any tool execution
-            let _ = handle
-                .interrupt_sender
-                .try_send(InterruptSignal::new(Some("Agent terminating".to_string())));
+    /// Remove a due reminder and send it to its target agent
+    pub fn deliver_reminder(&self, id: u64) -> Result<(), AgentError> {
+        let (reminder, _) = self
+            .reminders
+            .lock()
+            .unwrap()
+            .pending
+            .shift_remove(&id)
+            .ok_or(AgentError::ReminderNotFound(id))?;
+
+        let source = reminder
+            .source
+            .and_then(|source| self.get_agent_handle(source))
+            .map(|handle| format!(" source=\"{}\"", handle.name))
+            .unwrap_or_default();
+        let message = format!(
+            "<reminder id=\"{}\"{}>\n{}\n</reminder>",
+            reminder.id, source, reminder.message
+        );
+        self.send_message(reminder.target, AgentMessage::UserInput(message))
+    }
 
-            // Then send terminate message
-            let _ = handle.sender.try_send(AgentMessage::Terminate);
+    /// Cancel a pending reminder
+    ///
+    /// With a `caller`, only reminders it scheduled or receives can be
+    /// cancelled; those of other agents are reported as not found.
+    pub fn cancel_reminder(
+        &self,
+        id: u64,
+        caller: Option<AgentId>,
+    ) -> Result<Reminder, AgentError> {
+        let mut reminders = self.reminders.lock().unwrap();
+        let owned = reminders.pending.get(&id).is_some_and(|(reminder, _)| {
+            caller.map_or(true, |caller| {
+                reminder.target == caller || reminder.source == Some(caller)
+            })
+        });
+        if !owned {
+            return Err(AgentError::ReminderNotFound(id));
+        }
+        let (reminder, timer) = reminders.pending.shift_remove(&id).unwrap();
+        timer.abort();
+        Ok(reminder)
+    }
 
-            // During shutdown, don't wait for the task to complete
-            // Just abort it to avoid any issues with buffer access
-            handle.join_handle.abort();
+    /// Get pending reminders, optionally only those for one agent, ordered by due time
+    pub fn get_reminders(&self, target: Option<AgentId>) -> Vec<Reminder> {
+        let mut reminders: Vec<Reminder> = self
+            .reminders
+            .lock()
+            .unwrap()
+            .pending
+            .values()
+            .map(|(reminder, _)| reminder)
+            .filter(|reminder| target.map_or(true, |id| reminder.target == id))
+            .cloned()
+            .collect();
+        reminders.sort_by_key(|reminder| reminder.due);
+        reminders
+    }
 
-            // Remove from name index
-            self.name_index.shift_remove(&handle.name);
+    /// Cancel all reminders addressed to an agent
+    fn cancel_reminders_for(&self, target: AgentId) {
+        let mut reminders = self.reminders.lock().unwrap();
+        reminders.pending.retain(|_, (reminder, timer)| {
+            if reminder.target == target {
+                timer.abort();
+                false
+            } else {
+                true
+            }
+        });
+    }
 
-            Ok(())
-        } else {
-            Err(AgentError::AgentNotFound(id))
-        }
+    /// Remove an agent from the manager without termination
+    pub fn remove_agent(&self, id: AgentId) -> Result<(), AgentError> {
+        self.cancel_reminders_for(id);
+        let handle = self
+            .registry_mut()
+            .remove(id)
+            .ok_or(AgentError::AgentNotFound(id))?;
+        // Abort the task
+        handle.join_handle.abort();
+        Ok(())
+    }
+
+    /// Terminate an agent
+    #[allow(dead_code)]
+    pub fn terminate_agent(&self, id: AgentId) -> Result<(), AgentError> {
+        self.cancel_reminders_for(id);
+        let handle = self
+            .registry_mut()
+            .remove(id)
+            .ok_or(AgentError::AgentNotFound(id))?;
+        stop(&handle);
+        Ok(())
     }
 
     /// Terminate all agents
     #[allow(dead_code)]
-    pub async fn terminate_all(&mut self) {
-        // Don't collect ids first - just directly handle all agents
-        // This avoids any issues with buffer access during termination
-        for (_id, handle) in self.agents.drain(..) {
-            // Send interrupt signal first to stop any tool execution
-            let _ = handle
-                .interrupt_sender
-                .try_send(InterruptSignal::new(Some("Agent terminating".to_string())));
-
-            // Then send terminate message
-            let _ = handle.sender.try_send(AgentMessage::Terminate);
-
-            // Abort the task
-            handle.join_handle.abort();
+    pub fn terminate_all(&self) {
+        let registry = std::mem::take(&mut *self.registry_mut());
+        for handle in registry.agents.values() {
+            stop(handle);
         }
 
-        // Clear the name index
-        self.name_index.clear();
+        // Stop all pending reminders
+        let reminders = std::mem::take(&mut self.reminders.lock().unwrap().pending);
+        for (_reminder, timer) in reminders.values() {
+            timer.abort();
+        }
     }
 }
 
+/// Signal an agent to stop and abort its task
+fn stop(handle: &AgentHandle) {
+    // Send interrupt signal first to stop any tool execution
+    let _ = handle
+        .interrupt_sender
+        .try_send(InterruptSignal::new(Some("Agent terminating".to_string())));
+
+    // Then send terminate message
+    let _ = handle.sender.try_send(AgentMessage::Terminate);
+
+    // During shutdown, don't wait for the task to complete
+    // Just abort it to avoid any issues with buffer access
+    handle.join_handle.abort();
+}
+
+/// Hash task text, ignoring case and whitespace differences
+fn task_fingerprint(task: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let normalized = task
+        .split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    normalized.hash(&mut hasher);
+    hasher.finish()
+}
+
 /// Spawn an agent as a tokio task with its own buffer
+///
+/// The agent starts running once `registration` is signalled.
 fn spawn_agent_task(
     agent: Agent,
     buffer: SharedBuffer,
     agent_receiver: AgentReceiver,
     interrupt_receiver: InterruptReceiver,
+    registration: oneshot::Receiver<()>,
 ) -> JoinHandle<()> {
     tokio::spawn(CURRENT_BUFFER.scope(buffer, async move {
+        if registration.await.is_err() {
+            return;
+        }
         // Pass None since we now use the global agent manager
         agent.run(agent_receiver, interrupt_receiver).await;
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Handle of an agent whose task does nothing
+    fn handle(id: u64, name: &str) -> AgentHandle {
+        let (sender, _) = mpsc::channel(1);
+        let (interrupt_sender, _) = mpsc::channel(1);
+        let (_, state) = watch::channel(AgentState::Idle);
+        let (interjections, _) = watch::channel(0);
+        let (_, usage) = watch::channel(TokenUsage::default());
+        let (_, progress) = progress::channel();
+        AgentHandle {
+            id: AgentId(id),
+            name: name.to_string(),
+            sender,
+            interrupt_sender,
+            join_handle: tokio::spawn(async {}),
+            buffer: SharedBuffer::new(1),
+            state,
+            parent: None,
+            depth: 0,
+            task_fingerprint: None,
+            interjections,
+            usage,
+            progress,
+        }
+    }
+
+    /// Register a child agent the way `create_child_agent` does
+    fn spawn_child(
+        manager: &AgentManager,
+        id: u64,
+        parent: u64,
+        task: &str,
+    ) -> Result<(), AgentError> {
+        let parent = Some(AgentId(parent));
+        let fingerprint = task_fingerprint(task);
+        manager.register(handle(id, &format!("agent-{id}")), |registry, handle| {
+            handle.parent = parent;
+            handle.depth = manager.check_spawn(registry, parent, fingerprint)?;
+            handle.task_fingerprint = Some(fingerprint);
+            Ok(())
+        })
+    }
+
+    #[tokio::test]
+    async fn test_spawn_depth_limit() {
+        let manager = AgentManager::new();
+        manager.set_max_spawn_depth(2);
+        manager.register(handle(1, "main"), |_, _| Ok(())).unwrap();
+
+        spawn_child(&manager, 2, 1, "first").unwrap();
+        spawn_child(&manager, 3, 2, "second").unwrap();
+        assert!(matches!(
+            spawn_child(&manager, 4, 3, "third"),
+            Err(AgentError::SpawnDepthExceeded { depth: 3, max: 2 })
+        ));
+        assert!(manager.get_agent_handle(AgentId(4)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_spawn_loop_detection() {
+        let manager = AgentManager::new();
+        manager.register(handle(1, "main"), |_, _| Ok(())).unwrap();
+        spawn_child(&manager, 2, 1, "Fix the bug").unwrap();
+
+        // An ancestor working on the same task, ignoring case and whitespace
+        match spawn_child(&manager, 3, 2, "fix  the BUG") {
+            Err(AgentError::SpawnLoopDetected(name)) => assert_eq!(name, "agent-2"),
+            other => panic!("expected a spawn loop, got {other:?}"),
+        }
+
+        // Siblings may work on the same task
+        spawn_child(&manager, 4, 1, "Fix the bug").unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_same_name_after_task_finishes() {
+        let manager = AgentManager::new();
+        manager.register(handle(1, "main"), |_, _| Ok(())).unwrap();
+        let task = |id| manager.register(handle(id, "task_unnamed_task"), |_, _| Ok(()));
+
+        // The first task's agent is removed once its result is collected
+        task(2).unwrap();
+        assert!(matches!(task(3), Err(AgentError::NameTaken(_))));
+        manager.remove_agent(AgentId(2)).unwrap();
+        task(3).unwrap();
+        assert_eq!(
+            manager.get_agent_id_by_name("task_unnamed_task"),
+            Some(AgentId(3))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_reminder_of_other_agent() {
+        let manager = AgentManager::new();
+        manager.register(handle(1, "main"), |_, _| Ok(())).unwrap();
+        manager.register(handle(2, "other"), |_, _| Ok(())).unwrap();
+        let reminder = manager
+            .schedule_reminder(
+                AgentId(1),
+                Some(AgentId(1)),
+                Duration::from_secs(3600),
+                "check CI".to_string(),
+            )
+            .unwrap();
+
+        assert!(matches!(
+            manager.cancel_reminder(reminder.id, Some(AgentId(2))),
+            Err(AgentError::ReminderNotFound(_))
+        ));
+        assert_eq!(manager.get_reminders(None).len(), 1);
+        manager
+            .cancel_reminder(reminder.id, Some(AgentId(1)))
+            .unwrap();
+        assert!(manager.get_reminders(None).is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_concurrent_spawns_with_same_name() {
+        let manager = AgentManager::new();
+        let handles: Vec<AgentHandle> = (1..=8).map(|id| handle(id, "worker")).collect();
+
+        let results: Vec<Result<(), AgentError>> = std::thread::scope(|scope| {
+            let threads: Vec<_> = handles
+                .into_iter()
+                .map(|handle| scope.spawn(|| manager.register(handle, |_, _| Ok(()))))
+                .collect();
+            threads.into_iter().map(|t| t.join().unwrap()).collect()
+        });
+
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(results
+            .iter()
+            .filter_map(|result| result.as_ref().err())
+            .all(|e| matches!(e, AgentError::NameTaken(name) if name == "worker")));
+        assert_eq!(manager.get_agents().len(), 1);
+    }
+}
@@ -8,32 +8,40 @@
 
 // Define submodules
 mod agent_impl;
+pub mod budget;
+mod checkpoint;
+mod file_cache;
+mod file_watch;
 mod interrupt;
 mod manager;
+mod output_monitor;
+mod smart_context;
+mod timings;
 pub mod types;
 
 // Re-export public types from the submodules
-pub use types::{AgentId, AgentMessage, AgentReceiver, AgentState};
+pub use types::{AgentId, AgentMessage, AgentReceiver, AgentState, Reminder};
 
 // Import manager implementation
 use crate::config::Config;
+use crate::llm::TokenUsage;
 use crate::output::SharedBuffer;
+use crate::tools::progress::ToolProgress;
 use lazy_static::lazy_static;
 use manager::AgentManager;
-use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-// Global agent manager available to all components
+// Global agent manager available to all components; it locks internally, per
+// operation, so callers never wait on each other for long
 lazy_static! {
-    static ref AGENT_MANAGER: Arc<Mutex<AgentManager>> = Arc::new(Mutex::new(AgentManager::new()));
+    static ref AGENT_MANAGER: AgentManager = AgentManager::new();
 }
 
 // Public static methods for interacting with the agent manager
 
 /// Create a new agent with the given name and configuration
 pub fn create_agent(name: String, config: Config) -> Result<AgentId, types::AgentError> {
-    let mut manager = AGENT_MANAGER.lock().unwrap();
-    manager.create_agent(name, config)
+    AGENT_MANAGER.create_agent(name, config)
 }
 
 /// Create a new agent with the given name, configuration, and buffer
@@ -42,51 +50,105 @@ pub fn create_agent_with_buffer(
     config: Config,
     buffer: SharedBuffer,
 ) -> Result<AgentId, types::AgentError> {
-    let mut manager = AGENT_MANAGER.lock().unwrap();
-    manager.create_agent_with_buffer(name, config, buffer)
+    AGENT_MANAGER.create_agent_with_buffer(name, config, buffer)
+}
+
+/// Create an agent spawned by another agent, enforcing depth and loop limits
+pub fn create_child_agent(
+    name: String,
+    config: Config,
+    parent: Option<AgentId>,
+    task: &str,
+) -> Result<AgentId, types::AgentError> {
+    AGENT_MANAGER.create_child_agent(name, config, parent, task)
+}
+
+/// Clone an agent into a new agent (called by the agent being forked)
+fn fork_agent(source: &agent_impl::Agent, name: String) -> Result<AgentId, types::AgentError> {
+    AGENT_MANAGER.fork_agent(source, name)
+}
+
+/// Set the maximum depth of agents spawning agents
+pub fn set_max_spawn_depth(max_depth: usize) {
+    AGENT_MANAGER.set_max_spawn_depth(max_depth);
+}
+
+/// Schedule a message to be delivered to an agent after a delay
+pub fn schedule_reminder(
+    target: AgentId,
+    source: Option<AgentId>,
+    delay: Duration,
+    message: String,
+) -> Result<Reminder, types::AgentError> {
+    AGENT_MANAGER.schedule_reminder(target, source, delay, message)
+}
+
+/// Deliver a due reminder (called by the reminder timer task)
+fn deliver_reminder(id: u64) {
+    if let Err(e) = AGENT_MANAGER.deliver_reminder(id) {
+        bprintln!(debug: "Failed to deliver reminder {}: {}", id, e);
+    }
+}
+
+/// Cancel a pending reminder, limited to the reminders of `caller` if given
+pub fn cancel_reminder(id: u64, caller: Option<AgentId>) -> Result<Reminder, types::AgentError> {
+    AGENT_MANAGER.cancel_reminder(id, caller)
+}
+
+/// Get pending reminders, optionally only those addressed to one agent
+pub fn get_reminders(target: Option<AgentId>) -> Vec<Reminder> {
+    AGENT_MANAGER.get_reminders(target)
 }
 
 /// Send a message to an agent
 pub fn send_message(id: AgentId, message: AgentMessage) -> Result<(), types::AgentError> {
-    let manager = AGENT_MANAGER.lock().unwrap();
-    manager.send_message(id, message)
+    AGENT_MANAGER.send_message(id, message)
 }
 
 /// Get the buffer for an agent
 pub fn get_agent_buffer(id: AgentId) -> Result<SharedBuffer, types::AgentError> {
-    let manager = AGENT_MANAGER.lock().unwrap();
-    manager.get_agent_buffer(id)
+    AGENT_MANAGER.get_agent_buffer(id)
 }
 
 /// Get the current state of an agent
 pub fn get_agent_state(id: AgentId) -> Result<AgentState, types::AgentError> {
-    let manager = AGENT_MANAGER.lock().unwrap();
-    manager.get_agent_state(id)
+    AGENT_MANAGER.get_agent_state(id)
+}
+
+/// Follow the state of an agent: the receiver is notified of every change
+pub fn subscribe_agent_state(id: AgentId) -> Result<types::StateReceiver, types::AgentError> {
+    AGENT_MANAGER.subscribe_agent_state(id)
+}
+
+/// Get the tokens an agent has used so far
+pub fn get_agent_usage(id: AgentId) -> Result<TokenUsage, types::AgentError> {
+    AGENT_MANAGER.get_agent_usage(id)
+}
+
+/// Get the progress of the tool an agent is running, if it reports any
+pub fn get_agent_progress(id: AgentId) -> Result<Option<ToolProgress>, types::AgentError> {
+    AGENT_MANAGER.get_agent_progress(id)
 }
 
 /// Get a list of all agents with their IDs and names
 pub fn get_agents() -> Vec<(AgentId, String)> {
-    let manager = AGENT_MANAGER.lock().unwrap();
-    manager.get_agents()
+    AGENT_MANAGER.get_agents()
 }
 
 /// Get an agent ID by name
 pub fn get_agent_id_by_name(name: &str) -> Option<AgentId> {
-    let manager = AGENT_MANAGER.lock().unwrap();
-    manager.get_agent_id_by_name(name)
+    AGENT_MANAGER.get_agent_id_by_name(name)
 }
 
 /// Interrupt an agent
 #[allow(dead_code)]
 pub fn interrupt_agent(id: AgentId) -> Result<(), types::AgentError> {
-    let manager = AGENT_MANAGER.lock().unwrap();
-    manager.interrupt_agent(id)
+    AGENT_MANAGER.interrupt_agent(id)
 }
 
 /// Interrupt an agent with a specific reason
 pub fn interrupt_agent_with_reason(id: AgentId, reason: String) -> Result<(), types::AgentError> {
-    let manager = AGENT_MANAGER.lock().unwrap();
-    manager.interrupt_agent_with_reason(id, reason)
+    AGENT_MANAGER.interrupt_agent_with_reason(id, reason)
 }
 
 /// Terminate an agent
@@ -94,27 +156,23 @@ pub async fn terminate_agent(id: AgentId) -> Result<(), types::AgentError> {
     // Extract agent info before locking
     let agent_id = id;
 
-    // Get a clone of the agent handle to send termination signals outside the lock
-    let (interrupt_sender, sender) = {
-        let manager = AGENT_MANAGER.lock().unwrap();
-        if let Some(handle) = manager.get_agent_handle(agent_id) {
-            (handle.interrupt_sender.clone(), handle.sender.clone())
-        } else {
-            return Err(types::AgentError::AgentNotFound(agent_id));
-        }
-    };
+    // Get the agent handle to send termination signals outside the registry lock
+    let handle = AGENT_MANAGER
+        .get_agent_handle(agent_id)
+        .ok_or(types::AgentError::AgentNotFound(agent_id))?;
 
     // Send interrupt signal
-    let _ = interrupt_sender.try_send(types::InterruptSignal::new(Some(
-        "Agent terminating".to_string(),
-    )));
+    let _ = handle
+        .interrupt_sender
+        .try_send(types::InterruptSignal::new(Some(
+            "Agent terminating".to_string(),
+        )));
 
     // Send terminate message
-    let _ = sender.try_send(AgentMessage::Terminate);
+    let _ = handle.sender.try_send(AgentMessage::Terminate);
 
     // Now remove from manager
-    let mut manager = AGENT_MANAGER.lock().unwrap();
-    manager.remove_agent(agent_id)
+    AGENT_MANAGER.remove_agent(agent_id)
 }
 
 /// Terminate all agents
@@ -126,13 +184,19 @@ pub async fn terminate_all() {
     for (id, _) in agents {
         let _ = terminate_agent(id).await;
     }
+
+    // Stop any interactive shell sessions left open by agents
+    crate::tools::shell_session::close_all_sessions();
+
+    // Stop the browser of the browser tool
+    crate::tools::browser::close_browser().await;
 }
 
 /// Run an agent with a query until it completes and return the response
 ///
-/// This function waits for the agent to reach the Done state with a response.
-/// It relies on the agent properly setting its state to Done with the response
-/// when it completes its task.
+/// This function waits for the agent to reach the Done state with a response,
+/// woken up by each change of the agent's state. It relies on the agent
+/// properly setting its state to Done with the response when it completes its task.
 ///
 /// Parameters:
 /// - agent_id: The ID of the agent to run
@@ -146,39 +210,31 @@ pub async fn run_agent_to_completion(
     query: String,
     timeout_seconds: Option<u64>,
 ) -> Result<String, types::AgentError> {
+    // Follow the state from before the query, so the result of a previous
+    // query is not mistaken for this one's
+    let mut state = subscribe_agent_state(agent_id)?;
+    state.borrow_and_update();
+
     // Send the query to the agent
     send_message(agent_id, AgentMessage::UserInput(query))?;
 
     // Set timeout (default: 5 minutes)
     let timeout = Duration::from_secs(timeout_seconds.unwrap_or(300));
-    let start_time = std::time::Instant::now();
-    let mut last_polling_time = std::time::Instant::now();
-    let polling_interval = Duration::from_millis(500);
-
-    // Keep checking the agent state until it's done or timeout
-    while start_time.elapsed() < timeout {
-        // Only poll at the specified interval
-        if last_polling_time.elapsed() >= polling_interval {
-            last_polling_time = std::time::Instant::now();
-
-            // Get the agent state
-            let state = get_agent_state(agent_id)?;
-
-            // Check if the agent is done
-            if let AgentState::Done(Some(response)) = state {
-                // Agent is done with a response
-                return Ok(response);
-            } else if let AgentState::Done(None) = state {
-                // Agent is done but no response provided
-                return Err(types::AgentError::ResponseGenerationError);
-            } else if let AgentState::Terminated = state {
-                // Agent was terminated
-                return Err(types::AgentError::Terminated);
+
+    // Wait for the agent to finish, to be terminated or for its task to end
+    let completion = async {
+        while state.changed().await.is_ok() {
+            match &*state.borrow_and_update() {
+                AgentState::Done(Some(response)) => return Ok(response.clone()),
+                AgentState::Done(None) => return Err(types::AgentError::ResponseGenerationError),
+                AgentState::Terminated => break,
+                _ => {}
             }
         }
-
-        // Small sleep to avoid tight loop
-        tokio::time::sleep(Duration::from_millis(50)).await;
+        Err(types::AgentError::Terminated)
+    };
+    if let Ok(result) = tokio::time::timeout(timeout, completion).await {
+        return result;
     }
 
     // If we reached here, we timed out
@@ -0,0 +1,217 @@
+//! Local heuristics deciding whether a running shell command should be interrupted
+//!
+//! The output of a streaming shell command is judged every few seconds without
+//! a request: output repeating the same lines and floods of output are
+//! interrupted right away, and quiet, error-free output keeps running. Only
+//! output the heuristics cannot judge, such as errors or fast-growing logs, is
+//! escalated to a model check; a recurring error may be a retry loop or a test
+//! suite reporting on each of its binaries.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Lines kept for judging the recent output
+const WINDOW_LINES: usize = 2000;
+
+/// Fewest lines in the window before repetition is judged
+const MIN_REPEATED_LINES: usize = 50;
+
+/// Largest share of distinct lines in output considered repetitive
+const MAX_DISTINCT_SHARE: f64 = 0.1;
+
+/// Output rate, in lines per second, worth a model's judgement
+const BUSY_LINES_PER_SECOND: f64 = 50.0;
+
+/// Output rate and total size that are interrupted as a flood
+const FLOOD_LINES_PER_SECOND: f64 = 1000.0;
+const FLOOD_BYTES: usize = 4 * crate::constants::MAX_TOOL_OUTPUT_LENGTH;
+
+/// Words marking a line as an error
+const ERROR_MARKERS: &[&str] = &[
+    "error",
+    "failed",
+    "failure",
+    "exception",
+    "panicked",
+    "fatal",
+];
+
+/// Decision about the output since the previous check
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    /// Nothing suggests the command should stop
+    Continue,
+    /// The command should be interrupted, with a one-sentence reason
+    Interrupt(String),
+    /// The heuristics cannot tell; a model should judge the output
+    Uncertain,
+}
+
+/// Recent output of a shell command, judged by local heuristics
+pub struct OutputMonitor {
+    /// Recent non-empty lines, oldest first
+    window: VecDeque<String>,
+    /// Lines received since the previous check
+    new_lines: usize,
+    /// Bytes received in total
+    total_bytes: usize,
+    last_check: Instant,
+}
+
+impl Default for OutputMonitor {
+    fn default() -> Self {
+        Self {
+            window: VecDeque::new(),
+            new_lines: 0,
+            total_bytes: 0,
+            last_check: Instant::now(),
+        }
+    }
+}
+
+impl OutputMonitor {
+    /// Add a line of output, without ANSI sequences
+    pub fn push(&mut self, line: &str) {
+        self.total_bytes += line.len() + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        if self.window.len() == WINDOW_LINES {
+            self.window.pop_front();
+        }
+        self.window.push_back(line.to_string());
+        self.new_lines += 1;
+    }
+
+    /// Judge the output received since the previous check
+    pub fn check(&mut self) -> Verdict {
+        let elapsed = self.last_check.elapsed();
+        self.last_check = Instant::now();
+        let new_lines = std::mem::take(&mut self.new_lines);
+        judge(
+            self.window.make_contiguous(),
+            new_lines,
+            elapsed,
+            self.total_bytes,
+        )
+    }
+}
+
+/// Judge the recent lines of a command's output
+fn judge(window: &[String], new_lines: usize, elapsed: Duration, total_bytes: usize) -> Verdict {
+    if new_lines == 0 {
+        return Verdict::Continue;
+    }
+    let recent = &window[window.len().saturating_sub(new_lines)..];
+
+    // The same lines over and over, e.g. a polling loop or a stuck retry
+    if recent.len() >= MIN_REPEATED_LINES {
+        let mut distinct: Vec<&String> = recent.iter().collect();
+        distinct.sort();
+        distinct.dedup();
+        if (distinct.len() as f64) <= recent.len() as f64 * MAX_DISTINCT_SHARE {
+            return Verdict::Interrupt(format!(
+                "The output repeats the same {} line(s) without progress.",
+                distinct.len()
+            ));
+        }
+    }
+
+    let rate = new_lines as f64 / elapsed.as_secs_f64().max(1.0);
+    if rate >= FLOOD_LINES_PER_SECOND && total_bytes >= FLOOD_BYTES {
+        return Verdict::Interrupt(format!(
+            "The command floods the output ({} KB so far) with little new information.",
+            total_bytes / 1024
+        ));
+    }
+
+    let new_errors = recent.iter().any(|line| is_error(line));
+    if new_errors || rate >= BUSY_LINES_PER_SECOND {
+        Verdict::Uncertain
+    } else {
+        Verdict::Continue
+    }
+}
+
+/// Whether a line reports an error, ignoring counts of zero such as `0 failed`
+/// and passing tests whose name mentions errors
+fn is_error(line: &str) -> bool {
+    let line = line.to_lowercase();
+    if line.ends_with("... ok") {
+        return false;
+    }
+    ERROR_MARKERS.iter().any(|marker| {
+        line.match_indices(marker)
+            .any(|(start, _)| !ends_with_zero(&line[..start]))
+    })
+}
+
+/// Whether text ends with the number zero, e.g. `test result: ok. 0 `
+fn ends_with_zero(text: &str) -> bool {
+    match text.trim_end().strip_suffix('0') {
+        Some(rest) => !rest.ends_with(|c: char| c.is_ascii_digit() || c == '.'),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str], times: usize) -> Vec<String> {
+        text.iter()
+            .cycle()
+            .take(text.len() * times)
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_judge() {
+        let second = Duration::from_secs(1);
+
+        let progress: Vec<String> = (0..100).map(|i| format!("Compiling crate-{i}")).collect();
+        assert_eq!(judge(&progress, 100, second * 5, 2000), Verdict::Continue);
+        assert_eq!(judge(&progress, 0, second * 5, 2000), Verdict::Continue);
+
+        let polling = lines(&["Waiting for server...", "Still waiting"], 40);
+        assert!(matches!(
+            judge(&polling, 80, second * 5, 2000),
+            Verdict::Interrupt(_)
+        ));
+
+        let retries: Vec<String> = (0..6)
+            .map(|i| format!("Error: connection refused (attempt {i})"))
+            .collect();
+        assert_eq!(judge(&retries, 6, second * 5, 300), Verdict::Uncertain);
+        assert_eq!(judge(&retries, 0, second * 5, 300), Verdict::Continue);
+
+        let mut failure = progress.clone();
+        failure.push("test parser::nested ... FAILED".to_string());
+        assert_eq!(judge(&failure, 1, second * 5, 2000), Verdict::Uncertain);
+    }
+
+    #[test]
+    fn test_cargo_test_output() {
+        let second = Duration::from_secs(1);
+        let mut output = Vec::new();
+        for krate in ["core", "cli", "server", "client", "macros", "utils"] {
+            output.extend([
+                format!("     Running unittests src/lib.rs (target/debug/deps/{krate}-3f9a1c2b7d4e5f60)"),
+                "running 2 tests".to_string(),
+                "test tests::test_errors_are_reported ... ok".to_string(),
+                "test tests::test_parse ... ok".to_string(),
+                "test result: ok. 2 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.01s".to_string(),
+            ]);
+        }
+        let added = output.len();
+        assert_eq!(judge(&output, added, second * 5, 3000), Verdict::Continue);
+
+        output.push("test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.02s".to_string());
+        assert_eq!(judge(&output, 1, second * 5, 3100), Verdict::Uncertain);
+        assert!(is_error("error[E0308]: mismatched types"));
+        assert!(is_error("10 failed"));
+        assert!(!is_error("0 errors, 0 failures"));
+    }
+}
@@ -0,0 +1,176 @@
+//! Smart context: code relevant to the current request, from the semantic index
+//!
+//! With `--smart-context [TOKENS]`, the chunks of the semantic index closest to
+//! the user's request are added to the system prompt, up to a token budget.
+//! The selection is kept while requests stay on the same topic and made again
+//! when a request is no longer similar to the one it was made for, so the
+//! cached prompt prefix is only invalidated when the topic changes.
+
+use crate::index::SearchHit;
+use crate::prompts::stats::estimate_tokens;
+
+/// Requests less similar than this to the request of the current selection
+/// start a new topic
+const TOPIC_SHIFT_SIMILARITY: f32 = 0.5;
+
+/// Chunks retrieved before fitting them into the budget
+const CANDIDATES: usize = 40;
+
+/// Code selected for the agent's current topic
+#[derive(Debug, Clone)]
+pub struct SmartContext {
+    /// Token budget of the selected code
+    budget: usize,
+    /// Request waiting to be compared with the current topic
+    pending: Option<String>,
+    /// Embedding of the request the selection was made for
+    topic: Option<Vec<f32>>,
+    /// Prompt section with the selected code
+    section: Option<String>,
+}
+
+/// Outcome of a new selection
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    pub chunks: usize,
+    pub files: usize,
+    pub tokens: usize,
+}
+
+impl SmartContext {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            pending: None,
+            topic: None,
+            section: None,
+        }
+    }
+
+    /// Remember a user request, checked against the topic before the next LLM call
+    pub fn request(&mut self, text: &str) {
+        self.pending = Some(text.to_string());
+    }
+
+    /// Section appended to the system prompt, if code was selected
+    pub fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    /// Select code for the pending request if it starts a new topic
+    ///
+    /// Returns the new selection, or None when there was no pending request or
+    /// it continues the current topic.
+    pub async fn update(&mut self) -> Result<Option<Selection>, String> {
+        let Some(request) = self.pending.take() else {
+            return Ok(None);
+        };
+        let vector = crate::index::embed_query(&request).await?;
+        if let Some(topic) = &self.topic {
+            if similarity(topic, &vector) >= TOPIC_SHIFT_SIMILARITY {
+                return Ok(None);
+            }
+        }
+
+        let hits = crate::index::search_vector(&vector, CANDIDATES).await?;
+        let (section, selection) = select(hits, self.budget);
+        self.topic = Some(vector);
+        self.section = section;
+        Ok(Some(selection))
+    }
+}
+
+/// Cosine similarity of two normalized vectors
+fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Fit the best hits into the budget, skipping chunks overlapping a better one,
+/// and format them in file order
+fn select(hits: Vec<SearchHit>, budget: usize) -> (Option<String>, Selection) {
+    let mut selected: Vec<(SearchHit, String)> = Vec::new();
+    let mut tokens = 0;
+    for hit in hits {
+        let overlaps = selected.iter().any(|(other, _)| {
+            other.path == hit.path
+                && other.start_line <= hit.end_line
+                && hit.start_line <= other.end_line
+        });
+        if overlaps || hit.text.trim().is_empty() {
+            continue;
+        }
+        let block = format!(
+            "## {}:{}-{}\n```\n{}\n```",
+            hit.path, hit.start_line, hit.end_line, hit.text
+        );
+        let block_tokens = estimate_tokens(&block);
+        // A smaller chunk further down may still fit
+        if tokens + block_tokens > budget {
+            continue;
+        }
+        tokens += block_tokens;
+        selected.push((hit, block));
+    }
+
+    selected.sort_by(|(a, _), (b, _)| (&a.path, a.start_line).cmp(&(&b.path, b.start_line)));
+    let mut files: Vec<&str> = selected.iter().map(|(hit, _)| hit.path.as_str()).collect();
+    files.dedup();
+    let selection = Selection {
+        chunks: selected.len(),
+        files: files.len(),
+        tokens,
+    };
+    if selected.is_empty() {
+        return (None, selection);
+    }
+
+    let blocks: Vec<&str> = selected.iter().map(|(_, block)| block.as_str()).collect();
+    let section = format!(
+        "# Code relevant to the current request\n\
+         Selected automatically from the workspace; read the files for more context. \
+         The excerpts may be outdated once you edit the files.\n\n{}",
+        blocks.join("\n\n")
+    );
+    (Some(section), selection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(path: &str, start_line: usize, end_line: usize, text: &str) -> SearchHit {
+        SearchHit {
+            path: path.to_string(),
+            start_line,
+            end_line,
+            score: 0.5,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_select() {
+        let hits = vec![
+            hit("src/b.rs", 51, 110, "fn retry() {}"),
+            hit("src/b.rs", 101, 160, "fn backoff() {}"),
+            hit("src/a.rs", 1, 60, &"x".repeat(400)),
+            hit("src/a.rs", 51, 110, "fn parse() {}"),
+        ];
+        let (section, selection) = select(hits, 60);
+        let section = section.unwrap();
+
+        // The overlapping chunk is skipped and the large one doesn't fit
+        assert_eq!((selection.chunks, selection.files), (2, 2));
+        assert!(selection.tokens <= 60);
+        assert!(!section.contains("backoff") && !section.contains("xxxx"));
+        let first = section.find("## src/a.rs:51-110").unwrap();
+        assert!(first < section.find("## src/b.rs:51-110").unwrap());
+    }
+
+    #[test]
+    fn test_select_nothing() {
+        let (section, selection) = select(Vec::new(), 1000);
+        assert_eq!(section, None);
+        assert_eq!(selection.chunks, 0);
+    }
+}
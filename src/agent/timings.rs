@@ -0,0 +1,147 @@
+//! Time spent in the phases of agent turns
+//!
+//! A turn is one LLM request and the tool call it makes. Its time is split
+//! into preparing the context (conversation maintenance, changed files and
+//! smart context), waiting for the API, parsing the response and running the
+//! tool. Each turn's timings are shown in debug output, and `/timings`
+//! summarizes the session to find where long turns go.
+
+use std::fmt;
+use std::time::Duration;
+
+/// Time spent in each phase of a turn
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TurnTimings {
+    pub context: Duration,
+    pub api: Duration,
+    pub parsing: Duration,
+    pub tool: Duration,
+}
+
+impl TurnTimings {
+    fn phases(&self) -> [(&'static str, Duration); 4] {
+        [
+            ("context", self.context),
+            ("API", self.api),
+            ("parsing", self.parsing),
+            ("tool", self.tool),
+        ]
+    }
+
+    pub fn total(&self) -> Duration {
+        self.context + self.api + self.parsing + self.tool
+    }
+}
+
+impl fmt::Display for TurnTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let phases: Vec<String> = self
+            .phases()
+            .iter()
+            .map(|(name, time)| format!("{name} {}", format_duration(*time)))
+            .collect();
+        write!(
+            f,
+            "{} (total {})",
+            phases.join(", "),
+            format_duration(self.total())
+        )
+    }
+}
+
+/// Timings of all turns of a session
+#[derive(Debug, Clone, Default)]
+pub struct SessionTimings {
+    turns: Vec<TurnTimings>,
+}
+
+impl SessionTimings {
+    pub fn record(&mut self, turn: TurnTimings) {
+        self.turns.push(turn);
+    }
+
+    /// Table of the total, mean and longest time of each phase
+    pub fn summary(&self) -> String {
+        if self.turns.is_empty() {
+            return "No turns timed yet".to_string();
+        }
+        let count = self.turns.len() as u32;
+        let total: Duration = self.turns.iter().map(TurnTimings::total).sum();
+
+        let mut text = format!(
+            "{count} turns, {} in total\n{:8}  {:>9}  {:>9}  {:>9}  {:>6}\n",
+            format_duration(total),
+            "Phase",
+            "Total",
+            "Mean",
+            "Longest",
+            "Share"
+        );
+        for (index, (name, _)) in TurnTimings::default().phases().iter().enumerate() {
+            let times = self.turns.iter().map(|turn| turn.phases()[index].1);
+            let sum: Duration = times.clone().sum();
+            let longest = times.max().unwrap_or_default();
+            let share = if total.is_zero() {
+                0.0
+            } else {
+                sum.as_secs_f64() / total.as_secs_f64() * 100.0
+            };
+            text.push_str(&format!(
+                "{name:8}  {:>9}  {:>9}  {:>9}  {share:>5.0}%\n",
+                format_duration(sum),
+                format_duration(sum / count),
+                format_duration(longest)
+            ));
+        }
+
+        let (slowest, turn) = self
+            .turns
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, turn)| turn.total())
+            .expect("there is at least one turn");
+        text.push_str(&format!("Slowest turn (#{}): {turn}", slowest + 1));
+        text
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        format!("{millis} ms")
+    } else if millis < 60_000 {
+        format!("{:.1} s", duration.as_secs_f64())
+    } else {
+        format!("{}m {}s", millis / 60_000, millis % 60_000 / 1000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut timings = SessionTimings::default();
+        assert_eq!(timings.summary(), "No turns timed yet");
+
+        timings.record(TurnTimings {
+            api: Duration::from_secs(3),
+            tool: Duration::from_millis(500),
+            ..Default::default()
+        });
+        timings.record(TurnTimings {
+            context: Duration::from_millis(20),
+            api: Duration::from_secs(5),
+            tool: Duration::from_secs(120),
+            ..Default::default()
+        });
+
+        let summary = timings.summary();
+        assert!(summary.starts_with("2 turns, 2m 8s in total"));
+        assert!(summary.contains("tool          2m 0s"));
+        assert!(summary.ends_with(
+            "Slowest turn (#2): context 20 ms, API 5.0 s, parsing 0 ms, tool 2m 0s (total 2m 5s)"
+        ));
+    }
+}
@@ -38,6 +38,14 @@ pub enum AgentMessage {
     /// Regular user input to be processed
     UserInput(String),
 
+    /// Input from a named participant of a shared session
+    ParticipantInput {
+        /// Name of the participant
+        author: String,
+        /// Content of the message
+        content: String,
+    },
+
     /// Message from another agent with source information
     #[allow(dead_code)]
     AgentInput {
@@ -73,6 +81,62 @@ pub enum AgentCommand {
 
     /// Set the thinking budget in tokens
     SetThinkingBudget(usize),
+
+    /// Limit the length of responses in tokens (None = model default)
+    SetMaxTokens(Option<usize>),
+
+    /// Set the sampling temperature of all requests or of one class (None = provider default)
+    SetTemperature {
+        /// Class of requests, or None for all requests without their own
+        task: Option<crate::llm::sampling::SamplingTask>,
+        /// Temperature between 0 and 2
+        temperature: Option<f32>,
+    },
+
+    /// Enable or disable a single tool, updating the system prompt
+    SetToolEnabled {
+        /// Name of the tool
+        tool: String,
+        /// Whether the tool is enabled
+        enabled: bool,
+    },
+
+    /// Clone the agent into a new agent with the given name
+    Fork(String),
+
+    /// Continue the conversation in a new agent of another kind, seeded with
+    /// a summary, and mark this agent done
+    Handoff {
+        /// Name of the new agent
+        name: String,
+        /// Kind of the new agent
+        kind: String,
+        /// Model of the new agent (None = keep the current model)
+        model: Option<String>,
+    },
+
+    /// Open a conversation in a new agent of another kind with a starter's prompt
+    Start {
+        /// Name of the new agent
+        name: String,
+        /// Kind of the new agent
+        kind: String,
+        /// Model of the new agent (None = keep the current model)
+        model: Option<String>,
+        /// Opening prompt
+        prompt: String,
+    },
+
+    /// Save the requests and successful tool calls of the session as a
+    /// workflow draft with the given name
+    ExportWorkflow(String),
+
+    /// Save the conversation, with the provenance of tool results, as JSON
+    /// lines to the given file
+    ExportConversation(String),
+
+    /// Print where the turns of the session spent their time
+    ShowTimings,
 }
 
 /// Possible states of an agent
@@ -84,8 +148,12 @@ pub enum AgentState {
     /// Agent is processing input or generating a response
     Processing,
 
-    /// Agent is running a tool
-    RunningTool { tool: String, interruptible: bool },
+    /// Agent is running a tool; `detail` is the start of its arguments
+    RunningTool {
+        tool: String,
+        detail: String,
+        interruptible: bool,
+    },
 
     /// Agent has been terminated
     Terminated,
@@ -99,11 +167,11 @@ impl AgentState {
     /// Get a readable string representation of the state
     pub fn as_display_string(&self) -> String {
         match self {
-            AgentState::Idle => "Ready".to_string(),
-            AgentState::Processing => "Thinking...".to_string(),
-            AgentState::RunningTool { tool, .. } => format!("Running: {}", tool),
-            AgentState::Terminated => "Terminated".to_string(),
-            AgentState::Done(_) => "Task completed".to_string(),
+            AgentState::Idle => t!("tui.state.ready"),
+            AgentState::Processing => t!("tui.state.thinking"),
+            AgentState::RunningTool { tool, .. } => t!("tui.state.running", tool = tool),
+            AgentState::Terminated => t!("tui.state.terminated"),
+            AgentState::Done(_) => t!("tui.state.done"),
         }
     }
 }
@@ -127,11 +195,42 @@ pub enum AgentError {
     #[error("Operation timed out: {0}")]
     Timeout(String),
 
+    #[error("An agent named '{0}' already exists")]
+    NameTaken(String),
+
     #[error("Failed to create agent: {0}")]
     CreationFailed(String),
 
     #[error("Error generating or retrieving response")]
     ResponseGenerationError,
+
+    #[error("Agent spawn depth limit reached: depth {depth} exceeds the maximum of {max}")]
+    SpawnDepthExceeded { depth: usize, max: usize },
+
+    #[error("Agent spawn loop detected: ancestor agent '{0}' is already working on the same task")]
+    SpawnLoopDetected(String),
+
+    #[error("Reminder not found: {0}")]
+    ReminderNotFound(u64),
+}
+
+/// A message scheduled for delivery to an agent at a later time
+#[derive(Debug, Clone)]
+pub struct Reminder {
+    /// Unique reminder identifier
+    pub id: u64,
+
+    /// Agent that receives the reminder
+    pub target: AgentId,
+
+    /// Agent that scheduled the reminder, if any
+    pub source: Option<AgentId>,
+
+    /// Time at which the reminder is delivered
+    pub due: chrono::DateTime<chrono::Local>,
+
+    /// Message delivered to the target agent
+    pub message: String,
 }
 
 /// Type alias for an agent message sender
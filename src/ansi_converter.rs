@@ -14,8 +14,8 @@ struct StyleState {
     fg_color: Option<Color>,
     /// Text background color
     bg_color: Option<Color>,
-    /// Is text bold
-    bold: bool,
+    /// Text modifiers (bold, italic, underline, ...)
+    modifiers: Modifier,
 }
 
 impl StyleState {
@@ -36,8 +36,8 @@ impl StyleState {
             style = style.bg(bg);
         }
 
-        if self.bold {
-            style = style.add_modifier(Modifier::BOLD);
+        if !self.modifiers.is_empty() {
+            style = style.add_modifier(self.modifiers);
         }
 
         style
@@ -47,11 +47,15 @@ impl StyleState {
     fn reset(&mut self) {
         self.fg_color = None;
         self.bg_color = None;
-        self.bold = false;
+        self.modifiers = Modifier::empty();
     }
 }
 
 /// Convert a string with ANSI escape sequences to a ratatui Line
+///
+/// SGR sequences (`ESC[...m`) are translated into styles, cursor-forward
+/// sequences (`ESC[nC`) become spaces, and all other control sequences,
+/// including OSC sequences such as hyperlinks, are dropped.
 pub fn ansi_to_line(text: &str) -> Line<'static> {
     let mut spans = Vec::new();
     let mut current_text = String::new();
@@ -62,30 +66,61 @@ pub fn ansi_to_line(text: &str) -> Line<'static> {
     let chars: Vec<char> = text.chars().collect();
 
     while i < chars.len() {
-        // Check for escape sequence start
-        if chars[i] == '\x1b' && i + 1 < chars.len() && chars[i + 1] == '[' {
-            // If we have accumulated text, add it as a span with the current style
-            if !current_text.is_empty() {
-                spans.push(Span::styled(current_text.clone(), current_style.to_style()));
-                current_text.clear();
-            }
-
-            // Find the end of the escape sequence (marked by 'm')
-            let mut j = i + 2;
-            while j < chars.len() && chars[j] != 'm' {
-                j += 1;
-            }
-
-            if j < chars.len() {
-                // Extract the escape sequence
-                let escape_seq: String = chars[i..=j].iter().collect();
+        if chars[i] == '\x1b' && i + 1 < chars.len() {
+            match chars[i + 1] {
+                // Control sequence introducer
+                '[' => {
+                    // Find the final byte of the sequence (in the range '@'..='~')
+                    let mut j = i + 2;
+                    while j < chars.len() && !('@'..='~').contains(&chars[j]) {
+                        j += 1;
+                    }
 
-                // Parse the escape sequence
-                parse_escape_sequence(&escape_seq, &mut current_style);
+                    if j < chars.len() {
+                        let params: String = chars[i + 2..j].iter().collect();
+                        match chars[j] {
+                            'm' => {
+                                // If we have accumulated text, add it as a span with the current style
+                                if !current_text.is_empty() {
+                                    spans.push(Span::styled(
+                                        current_text.clone(),
+                                        current_style.to_style(),
+                                    ));
+                                    current_text.clear();
+                                }
+                                apply_sgr(&params, &mut current_style);
+                            }
+                            'C' => {
+                                // Cursor forward: render as spaces
+                                let count = params.parse::<usize>().unwrap_or(1).max(1);
+                                current_text.extend(std::iter::repeat(' ').take(count));
+                            }
+                            _ => {} // Other cursor/screen control sequences are ignored
+                        }
 
-                // Move past the escape sequence
-                i = j + 1;
-                continue;
+                        // Move past the escape sequence
+                        i = j + 1;
+                        continue;
+                    }
+                }
+                // Operating system command, terminated by BEL or ST (ESC \)
+                ']' => {
+                    let mut j = i + 2;
+                    while j < chars.len() {
+                        if chars[j] == '\x07' {
+                            j += 1;
+                            break;
+                        }
+                        if chars[j] == '\x1b' && chars.get(j + 1) == Some(&'\\') {
+                            j += 2;
+                            break;
+                        }
+                        j += 1;
+                    }
+                    i = j;
+                    continue;
+                }
+                _ => {}
             }
         }
 
@@ -102,104 +137,151 @@ pub fn ansi_to_line(text: &str) -> Line<'static> {
     Line::from(spans)
 }
 
-/// Parse an ANSI escape sequence and update the style state
-fn parse_escape_sequence(sequence: &str, style: &mut StyleState) {
-    // Extract the numeric part of the sequence
-    if let Some(code_str) = sequence
-        .strip_prefix("\x1b[")
-        .and_then(|s| s.strip_suffix('m'))
-    {
-        // Check if this is an empty code (which resets formatting)
-        if code_str.is_empty() {
-            style.reset();
-            return;
-        }
+/// Apply the parameters of an SGR sequence to the style state
+///
+/// Supports both `;`-separated (`38;5;n`, `38;2;r;g;b`) and `:`-separated
+/// (`38:5:n`, `38:2::r:g:b`) extended color forms.
+fn apply_sgr(params: &str, style: &mut StyleState) {
+    // An empty parameter list resets formatting
+    if params.is_empty() {
+        style.reset();
+        return;
+    }
 
-        // Process the code parts
-        let parts: Vec<&str> = code_str.split(';').collect();
-        let mut i = 0;
-
-        while i < parts.len() {
-            let part = parts[i];
-
-            match part {
-                "0" => style.reset(),
-                "1" => style.bold = true,
-
-                // Basic foreground colors
-                "30" => style.fg_color = Some(Color::Black),
-                "31" => style.fg_color = Some(Color::Red),
-                "32" => style.fg_color = Some(Color::Green),
-                "33" => style.fg_color = Some(Color::Yellow),
-                "34" => style.fg_color = Some(Color::Blue),
-                "35" => style.fg_color = Some(Color::Magenta),
-                "36" => style.fg_color = Some(Color::Cyan),
-                "37" => style.fg_color = Some(Color::White),
-                "90" => style.fg_color = Some(Color::Gray),
-
-                // Basic background colors
-                "40" => style.bg_color = Some(Color::Black),
-                "41" => style.bg_color = Some(Color::Red),
-                "42" => style.bg_color = Some(Color::Green),
-                "43" => style.bg_color = Some(Color::Yellow),
-                "44" => style.bg_color = Some(Color::Blue),
-                "45" => style.bg_color = Some(Color::Magenta),
-                "46" => style.bg_color = Some(Color::Cyan),
-                "47" => style.bg_color = Some(Color::White),
-
-                // Extended color codes for foreground and background
-                "38" => {
-                    if i + 2 < parts.len() {
-                        // 256 color mode uses "5" as the first parameter
-                        if parts[i + 1] == "5" {
-                            if let Ok(color_index) = parts[i + 2].parse::<u8>() {
-                                style.fg_color = Some(Color::Indexed(color_index));
-                                i += 2; // Skip the next two parts
-                            }
-                        }
-                        // RGB color mode uses "2" as the first parameter
-                        else if parts[i + 1] == "2" && i + 4 < parts.len() {
-                            if let (Ok(r), Ok(g), Ok(b)) = (
-                                parts[i + 2].parse::<u8>(),
-                                parts[i + 3].parse::<u8>(),
-                                parts[i + 4].parse::<u8>(),
-                            ) {
-                                style.fg_color = Some(Color::Rgb(r, g, b));
-                                i += 4; // Skip the next four parts
-                            }
-                        }
-                    }
+    let parts: Vec<&str> = params.split(';').collect();
+    let mut i = 0;
+
+    while i < parts.len() {
+        let part = parts[i];
+
+        // Colon-separated sub-parameters carry the whole extended color in one part
+        if let Some((code, rest)) = part.split_once(':') {
+            let sub: Vec<&str> = rest.split(':').collect();
+            if let Some(color) = parse_extended_color(&sub, true) {
+                match code {
+                    "38" => style.fg_color = Some(color.0),
+                    "48" => style.bg_color = Some(color.0),
+                    _ => {}
+                }
+            } else if code == "4" {
+                // Underline styles (4:0 = none, 4:1.. = single/double/curly/...)
+                if rest == "0" {
+                    style.modifiers.remove(Modifier::UNDERLINED);
+                } else {
+                    style.modifiers.insert(Modifier::UNDERLINED);
                 }
+            }
+            i += 1;
+            continue;
+        }
 
-                // Extended background colors
-                "48" => {
-                    if i + 2 < parts.len() {
-                        // 256 color mode uses "5" as the first parameter
-                        if parts[i + 1] == "5" {
-                            if let Ok(color_index) = parts[i + 2].parse::<u8>() {
-                                style.bg_color = Some(Color::Indexed(color_index));
-                                i += 2; // Skip the next two parts
-                            }
-                        }
-                        // RGB color mode uses "2" as the first parameter
-                        else if parts[i + 1] == "2" && i + 4 < parts.len() {
-                            if let (Ok(r), Ok(g), Ok(b)) = (
-                                parts[i + 2].parse::<u8>(),
-                                parts[i + 3].parse::<u8>(),
-                                parts[i + 4].parse::<u8>(),
-                            ) {
-                                style.bg_color = Some(Color::Rgb(r, g, b));
-                                i += 4; // Skip the next four parts
-                            }
-                        }
+        // Empty parameters count as 0; unknown values are skipped
+        let code = if part.is_empty() {
+            0
+        } else {
+            match part.parse::<u8>() {
+                Ok(code) => code,
+                Err(_) => {
+                    i += 1;
+                    continue;
+                }
+            }
+        };
+
+        match code {
+            0 => style.reset(),
+
+            // Text modifiers
+            1 => style.modifiers.insert(Modifier::BOLD),
+            2 => style.modifiers.insert(Modifier::DIM),
+            3 => style.modifiers.insert(Modifier::ITALIC),
+            4 | 21 => style.modifiers.insert(Modifier::UNDERLINED),
+            5 => style.modifiers.insert(Modifier::SLOW_BLINK),
+            6 => style.modifiers.insert(Modifier::RAPID_BLINK),
+            7 => style.modifiers.insert(Modifier::REVERSED),
+            8 => style.modifiers.insert(Modifier::HIDDEN),
+            9 => style.modifiers.insert(Modifier::CROSSED_OUT),
+            22 => style.modifiers.remove(Modifier::BOLD | Modifier::DIM),
+            23 => style.modifiers.remove(Modifier::ITALIC),
+            24 => style.modifiers.remove(Modifier::UNDERLINED),
+            25 => style
+                .modifiers
+                .remove(Modifier::SLOW_BLINK | Modifier::RAPID_BLINK),
+            27 => style.modifiers.remove(Modifier::REVERSED),
+            28 => style.modifiers.remove(Modifier::HIDDEN),
+            29 => style.modifiers.remove(Modifier::CROSSED_OUT),
+
+            // Basic and bright colors
+            code @ 30..=37 => style.fg_color = Some(basic_color(code - 30, false)),
+            code @ 90..=97 => style.fg_color = Some(basic_color(code - 90, true)),
+            code @ 40..=47 => style.bg_color = Some(basic_color(code - 40, false)),
+            code @ 100..=107 => style.bg_color = Some(basic_color(code - 100, true)),
+            39 => style.fg_color = None,
+            49 => style.bg_color = None,
+
+            // Extended colors (38;5;n / 38;2;r;g;b and the 48 equivalents)
+            code @ (38 | 48) => {
+                if let Some((color, consumed)) = parse_extended_color(&parts[i + 1..], false) {
+                    if code == 38 {
+                        style.fg_color = Some(color);
+                    } else {
+                        style.bg_color = Some(color);
                     }
+                    i += consumed;
                 }
-
-                _ => {} // Ignore unsupported codes
             }
 
-            i += 1;
+            _ => {} // Ignore unsupported codes
         }
+
+        i += 1;
+    }
+}
+
+/// Parse an extended color specification following a 38/48 code
+///
+/// Returns the color and the number of parameters consumed. In the colon form
+/// the RGB variant may include an (empty) color space id before the components.
+fn parse_extended_color(params: &[&str], colon_form: bool) -> Option<(Color, usize)> {
+    match *params.first()? {
+        "5" => {
+            let index = params.get(1)?.parse::<u8>().ok()?;
+            Some((Color::Indexed(index), 2))
+        }
+        "2" => {
+            let components = if colon_form && params.len() >= 5 {
+                &params[2..5]
+            } else {
+                params.get(1..4)?
+            };
+            let r = components[0].parse::<u8>().ok()?;
+            let g = components[1].parse::<u8>().ok()?;
+            let b = components[2].parse::<u8>().ok()?;
+            Some((Color::Rgb(r, g, b), 4))
+        }
+        _ => None,
+    }
+}
+
+/// Map a basic color index (0-7) to a ratatui color
+fn basic_color(index: u8, bright: bool) -> Color {
+    match (index, bright) {
+        (0, false) => Color::Black,
+        (1, false) => Color::Red,
+        (2, false) => Color::Green,
+        (3, false) => Color::Yellow,
+        (4, false) => Color::Blue,
+        (5, false) => Color::Magenta,
+        (6, false) => Color::Cyan,
+        (7, false) => Color::White,
+        (0, true) => Color::Gray,
+        (1, true) => Color::LightRed,
+        (2, true) => Color::LightGreen,
+        (3, true) => Color::LightYellow,
+        (4, true) => Color::LightBlue,
+        (5, true) => Color::LightMagenta,
+        (6, true) => Color::LightCyan,
+        _ => Color::White,
     }
 }
 
@@ -256,6 +338,32 @@ mod tests {
         assert_eq!(line.spans[1].style.fg, Some(Color::Red));
         assert!(line.spans[1].style.add_modifier.contains(Modifier::BOLD));
     }
+
+    #[test]
+    fn test_extended_colors() {
+        let line =
+            ansi_to_line("\x1b[38;5;208mindexed\x1b[48;2;10;20;30mrgb\x1b[38:2::1:2:3mcolon");
+
+        assert_eq!(line.spans[0].style.fg, Some(Color::Indexed(208)));
+        assert_eq!(line.spans[1].style.bg, Some(Color::Rgb(10, 20, 30)));
+        assert_eq!(line.spans[2].style.fg, Some(Color::Rgb(1, 2, 3)));
+    }
+
+    #[test]
+    fn test_modifiers_and_cursor_forward() {
+        let line = ansi_to_line("\x1b[3;4mstyled\x1b[23mplain\x1b[3Cgap\x1b[2K");
+
+        let styled = line.spans[0].style.add_modifier;
+        assert!(styled.contains(Modifier::ITALIC));
+        assert!(styled.contains(Modifier::UNDERLINED));
+
+        assert_eq!(line.spans[1].content, "plain   gap");
+        assert!(!line.spans[1].style.add_modifier.contains(Modifier::ITALIC));
+        assert!(line.spans[1]
+            .style
+            .add_modifier
+            .contains(Modifier::UNDERLINED));
+    }
 }
 
 /// Strips ANSI escape sequences from text
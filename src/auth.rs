@@ -0,0 +1,267 @@
+//! Optional login to a Termineer account
+//!
+//! Logging in is not required; it unlocks the features of a paid subscription.
+//! `termineer login` uses the OAuth device-code flow: the server hands out a
+//! short user code, the user approves it in a browser, and the CLI polls until
+//! it receives an access token. The token and the subscription claims returned
+//! with it are stored in the OS keychain, never in plain files.
+//!
+//! On startup the stored claims are refreshed from the server; when the server
+//! cannot be reached, the cached claims are used until the token expires.
+
+// Debug builds always run as Pro and never consult the stored session
+#![cfg_attr(debug_assertions, allow(dead_code))]
+
+use crate::config::AppMode;
+use crate::credentials;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Base URL of the Termineer server
+const SERVER_URL: &str = "https://termineer.io";
+
+/// Environment variable overriding the server URL
+const SERVER_URL_ENV: &str = "TERMINEER_SERVER_URL";
+
+/// Keychain account under which the session is stored
+const KEYRING_ACCOUNT: &str = "account-session";
+
+/// Timeout for refreshing claims on startup
+const REFRESH_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Subscription claims returned by the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountClaims {
+    pub email: String,
+    pub name: Option<String>,
+    /// Active plan: "free", "plus" or "pro"
+    pub subscription: String,
+    /// Unix timestamp when the access token expires
+    pub expires_at: i64,
+}
+
+impl AccountClaims {
+    /// Feature tier granted by the subscription
+    pub fn app_mode(&self) -> AppMode {
+        match self.subscription.as_str() {
+            "pro" => AppMode::Pro,
+            "plus" => AppMode::Plus,
+            _ => AppMode::Free,
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        self.expires_at <= chrono::Utc::now().timestamp()
+    }
+}
+
+/// A logged-in session as stored in the keychain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSession {
+    pub access_token: String,
+    pub claims: AccountClaims,
+}
+
+/// Device code issued by the server
+#[derive(Debug, Deserialize)]
+struct DeviceCodeResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    verification_uri_complete: Option<String>,
+    expires_in: u64,
+    interval: u64,
+}
+
+/// Successful or failed answer of the token endpoint
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TokenResponse {
+    Token {
+        access_token: String,
+        claims: AccountClaims,
+    },
+    Error {
+        error: String,
+    },
+}
+
+fn server_url() -> String {
+    std::env::var(SERVER_URL_ENV).unwrap_or_else(|_| SERVER_URL.to_string())
+}
+
+/// Load the stored session, if logged in
+pub fn load_session() -> Option<AccountSession> {
+    let secret = credentials::keychain_get(KEYRING_ACCOUNT)?;
+    serde_json::from_str(&secret).ok()
+}
+
+fn store_session(session: &AccountSession) -> Result<(), String> {
+    let secret = serde_json::to_string(session).map_err(|e| e.to_string())?;
+    credentials::keychain_set(KEYRING_ACCOUNT, &secret)
+}
+
+/// Forget the stored session; returns whether one existed
+pub fn logout() -> Result<bool, String> {
+    credentials::keychain_delete(KEYRING_ACCOUNT)
+}
+
+/// Log in with the device-code flow and store the resulting session
+pub async fn login() -> Result<AccountSession, String> {
+    let client = crate::net::client();
+    let base = server_url();
+
+    let device: DeviceCodeResponse = client
+        .post(format!("{base}/api/device/code"))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to start login: {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid response from server: {e}"))?;
+
+    println!("To log in, open this page in your browser:");
+    println!();
+    println!(
+        "    {}",
+        device
+            .verification_uri_complete
+            .as_deref()
+            .unwrap_or(&device.verification_uri)
+    );
+    println!();
+    println!("and confirm the code: {}", device.user_code);
+    println!();
+    println!("Waiting for approval...");
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(device.expires_in);
+    let mut interval = Duration::from_secs(device.interval.max(1));
+    while std::time::Instant::now() < deadline {
+        tokio::time::sleep(interval).await;
+
+        let response = client
+            .post(format!("{base}/api/device/token"))
+            .json(&serde_json::json!({ "device_code": device.device_code }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to poll for login: {e}"))?;
+        let token: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid response from server: {e}"))?;
+
+        match token {
+            TokenResponse::Token {
+                access_token,
+                claims,
+            } => {
+                let session = AccountSession {
+                    access_token,
+                    claims,
+                };
+                store_session(&session)?;
+                return Ok(session);
+            }
+            TokenResponse::Error { error } => match error.as_str() {
+                "authorization_pending" => {}
+                "slow_down" => interval += Duration::from_secs(5),
+                "expired_token" => break,
+                "access_denied" => return Err("Login was denied".to_string()),
+                other => return Err(format!("Login failed: {other}")),
+            },
+        }
+    }
+
+    Err("Login code expired; run `termineer login` again".to_string())
+}
+
+/// Outcome of asking the server for fresh claims
+enum Refresh {
+    Claims(AccountClaims),
+    /// The server rejected the token (revoked or expired)
+    Rejected,
+    /// The server could not be reached
+    Unavailable,
+}
+
+async fn refresh_claims(session: &AccountSession) -> Refresh {
+    let client = match crate::net::client_builder()
+        .timeout(REFRESH_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(_) => return Refresh::Unavailable,
+    };
+    let response = match client
+        .get(format!("{}/api/cli/session", server_url()))
+        .bearer_auth(&session.access_token)
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(_) => return Refresh::Unavailable,
+    };
+
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        return Refresh::Rejected;
+    }
+    if !response.status().is_success() {
+        return Refresh::Unavailable;
+    }
+    match response.json().await {
+        Ok(claims) => Refresh::Claims(claims),
+        Err(_) => Refresh::Unavailable,
+    }
+}
+
+/// Determine the feature tier from the stored session
+///
+/// Returns Free when not logged in or when the session is no longer valid.
+pub async fn resolve_app_mode() -> AppMode {
+    let Some(mut session) = load_session() else {
+        return AppMode::Free;
+    };
+
+    match refresh_claims(&session).await {
+        Refresh::Claims(claims) => {
+            session.claims = claims;
+            if let Err(e) = store_session(&session) {
+                bprintln!(debug: "{}", e);
+            }
+        }
+        Refresh::Rejected => return AppMode::Free,
+        Refresh::Unavailable => {}
+    }
+
+    if session.claims.is_expired() {
+        AppMode::Free
+    } else {
+        session.claims.app_mode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_response() {
+        let pending: TokenResponse =
+            serde_json::from_str(r#"{"error": "authorization_pending"}"#).unwrap();
+        assert!(
+            matches!(pending, TokenResponse::Error { error } if error == "authorization_pending")
+        );
+
+        let token: TokenResponse = serde_json::from_str(
+            r#"{"access_token": "abc", "token_type": "Bearer",
+                "claims": {"email": "a@b.c", "name": null, "subscription": "pro", "expires_at": 4102444800}}"#,
+        )
+        .unwrap();
+        let TokenResponse::Token { claims, .. } = token else {
+            panic!("expected a token");
+        };
+        assert_eq!(claims.app_mode(), AppMode::Pro);
+        assert!(!claims.is_expired());
+    }
+}
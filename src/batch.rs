@@ -0,0 +1,292 @@
+//! Batch mode: many queries from a JSONL file
+//!
+//! `termineer batch queries.jsonl` runs every query like single-query mode,
+//! each with its own agent and output buffer, up to `--parallel` at a time.
+//! Each input line is a JSON string, or an object with a `query` and an
+//! optional `id` (the line number by default). As each query finishes, a line
+//! with its response or error, the tokens used, the estimated cost and the
+//! duration is appended to the output file (`queries.results.jsonl` next to
+//! the input by default).
+//!
+//! Running the same batch again resumes it: queries with a successful result in
+//! the output file are skipped, and failed ones run again.
+
+use crate::agent;
+use crate::config::Config;
+use crate::llm::TokenUsage;
+use crate::output::SharedBuffer;
+use futures::StreamExt;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Seconds a query may run when no --timeout is given, as in single-query mode
+const DEFAULT_TIMEOUT: u64 = 150;
+
+/// A query of the batch
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchItem {
+    pub id: String,
+    pub query: String,
+}
+
+/// Result line written for each query
+#[derive(Debug, Serialize)]
+pub struct BatchResult {
+    pub id: String,
+    pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    pub cache_creation_input_tokens: usize,
+    pub cache_read_input_tokens: usize,
+    /// Estimated from list prices; None for models without a known price
+    pub cost_usd: Option<f64>,
+    pub duration_ms: u64,
+}
+
+/// Totals of a batch run
+#[derive(Debug, Default)]
+pub struct BatchSummary {
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Queries with a successful result from an earlier run
+    pub skipped: usize,
+    pub tokens: usize,
+    pub cost_usd: f64,
+    pub output: PathBuf,
+}
+
+impl BatchSummary {
+    pub fn format(&self) -> String {
+        format!(
+            "{} succeeded, {} failed, {} skipped (done earlier); {} tokens, ${:.4} estimated\n\
+             Results: {}",
+            self.succeeded,
+            self.failed,
+            self.skipped,
+            self.tokens,
+            self.cost_usd,
+            self.output.display()
+        )
+    }
+}
+
+/// Parse the queries of a JSONL file
+pub fn parse_items(content: &str) -> Result<Vec<BatchItem>, String> {
+    let mut items = Vec::new();
+    let mut ids = HashSet::new();
+    for (index, line) in content.lines().enumerate() {
+        let number = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value =
+            serde_json::from_str(line).map_err(|e| format!("Line {number}: invalid JSON: {e}"))?;
+        let (id, query) = match value {
+            Value::String(query) => (None, query),
+            Value::Object(mut object) => {
+                let Some(Value::String(query)) = object.remove("query") else {
+                    return Err(format!("Line {number}: missing \"query\" string"));
+                };
+                let id = match object.remove("id") {
+                    None | Some(Value::Null) => None,
+                    Some(Value::String(id)) => Some(id),
+                    Some(Value::Number(id)) => Some(id.to_string()),
+                    Some(_) => {
+                        return Err(format!("Line {number}: \"id\" must be a string or number"))
+                    }
+                };
+                (id, query)
+            }
+            _ => return Err(format!("Line {number}: expected a string or an object")),
+        };
+        let id = id.unwrap_or_else(|| number.to_string());
+        if !ids.insert(id.clone()) {
+            return Err(format!("Line {number}: duplicate id '{id}'"));
+        }
+        items.push(BatchItem { id, query });
+    }
+    Ok(items)
+}
+
+/// Default output file: `NAME.results.jsonl` next to `NAME.jsonl`
+pub fn default_output(input: &Path) -> PathBuf {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    input.with_file_name(format!("{stem}.results.jsonl"))
+}
+
+/// Ids with a successful result in the output, which is rewritten without the
+/// failed results so they can be replaced
+fn resume(output: &Path) -> Result<HashSet<String>, String> {
+    let Ok(content) = std::fs::read_to_string(output) else {
+        return Ok(HashSet::new());
+    };
+    let mut done = HashSet::new();
+    let mut kept = String::new();
+    for line in content.lines() {
+        let Ok(result) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let succeeded = result.get("error").map_or(true, Value::is_null);
+        if let (true, Some(id)) = (succeeded, result.get("id").and_then(Value::as_str)) {
+            done.insert(id.to_string());
+            kept.push_str(line);
+            kept.push('\n');
+        }
+    }
+    std::fs::write(output, kept)
+        .map_err(|e| format!("Failed to rewrite {}: {e}", output.display()))?;
+    Ok(done)
+}
+
+/// Run the queries of `input` that have no successful result yet
+pub async fn run(
+    config: Config,
+    input: &Path,
+    output: Option<PathBuf>,
+    parallel: usize,
+) -> Result<BatchSummary, String> {
+    let content = std::fs::read_to_string(input)
+        .map_err(|e| format!("Failed to read {}: {e}", input.display()))?;
+    let items = parse_items(&content)?;
+    let output = output.unwrap_or_else(|| default_output(input));
+
+    let done = resume(&output)?;
+    let pending: Vec<BatchItem> = items
+        .into_iter()
+        .filter(|item| !done.contains(&item.id))
+        .collect();
+    let mut summary = BatchSummary {
+        skipped: done.len(),
+        output: output.clone(),
+        ..Default::default()
+    };
+    if summary.skipped > 0 {
+        eprintln!("Resuming: {} queries already done", summary.skipped);
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&output)
+        .map_err(|e| format!("Failed to open {}: {e}", output.display()))?;
+
+    let total = pending.len();
+    let timeout = config.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT);
+    let mut results = futures::stream::iter(pending)
+        .map(|item| run_item(&config, item, timeout))
+        .buffer_unordered(parallel.max(1));
+
+    let mut finished = 0;
+    while let Some(result) = results.next().await {
+        finished += 1;
+        let tokens = result.input_tokens
+            + result.output_tokens
+            + result.cache_creation_input_tokens
+            + result.cache_read_input_tokens;
+        summary.tokens += tokens;
+        summary.cost_usd += result.cost_usd.unwrap_or_default();
+        match &result.error {
+            None => {
+                summary.succeeded += 1;
+                eprintln!("[{finished}/{total}] ✓ {} ({tokens} tokens)", result.id);
+            }
+            Some(error) => {
+                summary.failed += 1;
+                eprintln!("[{finished}/{total}] ✗ {}: {error}", result.id);
+            }
+        }
+
+        // Written as each query finishes, so an interrupted batch can be resumed
+        let line = serde_json::to_string(&result).map_err(|e| e.to_string())?;
+        writeln!(file, "{line}")
+            .and_then(|_| file.flush())
+            .map_err(|e| format!("Failed to write {}: {e}", output.display()))?;
+    }
+
+    Ok(summary)
+}
+
+/// Run one query in a new agent with its own buffer
+async fn run_item(config: &Config, item: BatchItem, timeout: u64) -> BatchResult {
+    let started = Instant::now();
+    let name = format!("batch-{}", item.id);
+    let buffer = SharedBuffer::new(200);
+
+    let (outcome, usage) = match agent::create_agent_with_buffer(name, config.clone(), buffer) {
+        Ok(agent_id) => {
+            let outcome =
+                agent::run_agent_to_completion(agent_id, item.query.clone(), Some(timeout))
+                    .await
+                    .map_err(|e| e.to_string());
+            let usage = agent::get_agent_usage(agent_id).unwrap_or_default();
+            let _ = agent::terminate_agent(agent_id).await;
+            (outcome, usage)
+        }
+        Err(e) => (Err(e.to_string()), TokenUsage::default()),
+    };
+
+    let (response, error) = match outcome {
+        Ok(response) => (Some(response), None),
+        Err(error) => (None, Some(error)),
+    };
+    BatchResult {
+        id: item.id,
+        query: item.query,
+        response,
+        error,
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+        cache_creation_input_tokens: usage.cache_creation_input_tokens,
+        cache_read_input_tokens: usage.cache_read_input_tokens,
+        cost_usd: crate::llm::pricing::cost(&config.model, &usage),
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_items() {
+        let content = "\"first\"\n\n{\"id\": \"b\", \"query\": \"second\"}\n{\"id\": 7, \"query\": \"third\"}\n";
+        let items = parse_items(content).unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, ["1", "b", "7"]);
+        assert_eq!(items[1].query, "second");
+
+        assert!(parse_items("{\"id\": \"a\"}").is_err());
+        assert!(parse_items("\"x\"\n{\"id\": \"1\", \"query\": \"y\"}").is_err());
+        assert!(parse_items("[1]").is_err());
+    }
+
+    #[test]
+    fn test_resume() {
+        let path =
+            std::env::temp_dir().join(format!("termineer-batch-{}.jsonl", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "{\"id\": \"1\", \"response\": \"ok\"}\n{\"id\": \"2\", \"error\": \"timeout\"}\n",
+        )
+        .unwrap();
+
+        let done = resume(&path).unwrap();
+        assert_eq!(done, HashSet::from(["1".to_string()]));
+        let kept = std::fs::read_to_string(&path).unwrap();
+        assert!(!kept.contains("timeout"));
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            default_output(Path::new("data/queries.jsonl")),
+            PathBuf::from("data/queries.results.jsonl")
+        );
+    }
+}
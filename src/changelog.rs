@@ -0,0 +1,303 @@
+//! Release notes from the commits of a release
+//!
+//! `termineer changelog --from v1.2.0` lists the commits since the tag along
+//! the first parent, so that merged pull requests count once with their
+//! titles, and groups them by conventional-commit type (`feat:`, `fix:`, ...).
+//! An agent of the `changelog_writer` kind turns the groups into release
+//! notes, which are shown for approval and then added to CHANGELOG.md above
+//! the notes of earlier releases.
+
+use crate::agent;
+use crate::config::Config;
+use crate::git::git;
+use crate::output::SharedBuffer;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+
+/// Seconds the notes may take when no --timeout is given
+const DEFAULT_TIMEOUT: u64 = 300;
+
+/// Section of the changelog a commit belongs to, in the order of the notes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Group {
+    Breaking,
+    Features,
+    Fixes,
+    Performance,
+    Documentation,
+    Refactoring,
+    Other,
+}
+
+impl Group {
+    const ALL: [Group; 7] = [
+        Group::Breaking,
+        Group::Features,
+        Group::Fixes,
+        Group::Performance,
+        Group::Documentation,
+        Group::Refactoring,
+        Group::Other,
+    ];
+
+    /// Group of a conventional-commit type
+    fn of_type(kind: &str) -> Group {
+        match kind.to_lowercase().as_str() {
+            "feat" | "feature" => Group::Features,
+            "fix" | "bugfix" => Group::Fixes,
+            "perf" => Group::Performance,
+            "docs" | "doc" => Group::Documentation,
+            "refactor" => Group::Refactoring,
+            _ => Group::Other,
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Group::Breaking => "Breaking Changes",
+            Group::Features => "Features",
+            Group::Fixes => "Bug Fixes",
+            Group::Performance => "Performance",
+            Group::Documentation => "Documentation",
+            Group::Refactoring => "Refactoring",
+            Group::Other => "Other Changes",
+        }
+    }
+}
+
+/// A commit or merged pull request of the release
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub hash: String,
+    pub group: Group,
+    pub scope: Option<String>,
+    /// Subject without the type, scope and pull request number
+    pub title: String,
+    pub pull_request: Option<u32>,
+}
+
+/// Entry of a commit, or None for merges of branches other than pull requests
+pub fn parse_commit(hash: &str, subject: &str, body: &str) -> Option<Entry> {
+    let mut subject = subject.trim().to_string();
+    let mut pull_request = None;
+
+    // GitHub merge commits carry the title of the pull request in the body
+    if let Some(rest) = subject.strip_prefix("Merge pull request #") {
+        pull_request = rest.split_whitespace().next()?.parse().ok();
+        subject = body
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty())?
+            .to_string();
+    } else if subject.starts_with("Merge ") {
+        return None;
+    }
+    // Squash merges end with the number: `Add the thing (#123)`
+    let squashed = subject
+        .strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once(" (#"))
+        .and_then(|(title, number)| Some((title.to_string(), number.parse().ok()?)));
+    if let Some((title, number)) = squashed {
+        subject = title;
+        pull_request = Some(number);
+    }
+
+    // `type(scope)!: title`
+    let conventional = subject.split_once(": ").and_then(|(prefix, title)| {
+        let (prefix, breaking) = match prefix.strip_suffix('!') {
+            Some(prefix) => (prefix, true),
+            None => (prefix, false),
+        };
+        let (kind, scope) = match prefix.split_once('(') {
+            Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?.to_string())),
+            None => (prefix, None),
+        };
+        let valid = !kind.is_empty() && kind.chars().all(|c| c.is_ascii_alphabetic());
+        valid.then(|| (Group::of_type(kind), scope, breaking, title.to_string()))
+    });
+    let (group, scope, breaking, title) =
+        conventional.unwrap_or((Group::Other, None, false, subject.clone()));
+    let breaking = breaking || body.contains("BREAKING CHANGE");
+
+    Some(Entry {
+        hash: hash.to_string(),
+        group: if breaking { Group::Breaking } else { group },
+        scope,
+        title,
+        pull_request,
+    })
+}
+
+/// Entries of the commits in `from..to`, oldest first
+async fn entries(from: &str, to: &str) -> Result<Vec<Entry>, String> {
+    let range = format!("{from}..{to}");
+    // Fields end with unit separators and commits with record separators
+    let log = git(&[
+        "log",
+        "--first-parent",
+        "--reverse",
+        "--format=%h%x1f%s%x1f%b%x1e",
+        &range,
+    ])
+    .await?;
+    Ok(log
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start().split('\x1f');
+            let (hash, subject) = (fields.next()?, fields.next()?);
+            parse_commit(hash, subject, fields.next().unwrap_or_default())
+        })
+        .collect())
+}
+
+/// The entries as grouped lists, the input of the changelog writer
+pub fn format_groups(entries: &[Entry]) -> String {
+    let mut text = String::new();
+    for group in Group::ALL {
+        let mut entries = entries.iter().filter(|e| e.group == group).peekable();
+        if entries.peek().is_none() {
+            continue;
+        }
+        text.push_str(&format!("### {}\n", group.title()));
+        for entry in entries {
+            text.push_str("- ");
+            if let Some(scope) = &entry.scope {
+                text.push_str(&format!("{scope}: "));
+            }
+            text.push_str(&entry.title);
+            if let Some(number) = entry.pull_request {
+                text.push_str(&format!(" (#{number})"));
+            }
+            text.push_str(&format!(" [{}]\n", entry.hash));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Changelog with the notes of a release above those of earlier releases
+pub fn insert_release(changelog: &str, section: &str) -> String {
+    if changelog.trim().is_empty() {
+        return format!("# Changelog\n\n{section}");
+    }
+    // Releases are the second-level headings; the title and intro stay first
+    let mut offset = 0;
+    for line in changelog.split_inclusive('\n') {
+        if line.starts_with("## ") {
+            return format!(
+                "{}{section}\n{}",
+                &changelog[..offset],
+                &changelog[offset..]
+            );
+        }
+        offset += line.len();
+    }
+    format!("{}\n\n{section}", changelog.trim_end())
+}
+
+/// Write the notes of the commits in `from..to` into `output` as the release
+/// `release`, after showing them for approval unless `yes`; returns whether
+/// the file was written
+pub async fn run(
+    mut config: Config,
+    from: &str,
+    to: &str,
+    release: &str,
+    output: &Path,
+    yes: bool,
+) -> Result<bool, String> {
+    let entries = entries(from, to).await?;
+    if entries.is_empty() {
+        return Err(format!("No commits between {from} and {to}"));
+    }
+
+    config.kind = Some("changelog_writer".to_string());
+    let timeout = config.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT);
+    let query = format!(
+        "Write the release notes of {release} from these commits since {from}.\n\n{}",
+        format_groups(&entries)
+    );
+    let agent_id =
+        agent::create_agent_with_buffer("changelog".to_string(), config, SharedBuffer::new(200))
+            .map_err(|e| e.to_string())?;
+    let notes = agent::run_agent_to_completion(agent_id, query, Some(timeout))
+        .await
+        .map_err(|e| e.to_string());
+    let _ = agent::terminate_agent(agent_id).await;
+
+    let date = chrono::Local::now().format("%Y-%m-%d");
+    let section = format!("## {release} - {date}\n\n{}\n", notes?.trim());
+    println!("{section}");
+    if !yes && !confirm(output) {
+        return Ok(false);
+    }
+
+    let changelog = std::fs::read_to_string(output).unwrap_or_default();
+    std::fs::write(output, insert_release(&changelog, &section))
+        .map_err(|e| format!("Failed to write {}: {e}", output.display()))?;
+    Ok(true)
+}
+
+/// Ask whether to write the notes; without a terminal they are not written
+fn confirm(output: &Path) -> bool {
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+    print!("{} ", t!("cli.changelog_confirm", path = output.display()));
+    let _ = std::io::stdout().flush();
+    let mut input = String::new();
+    match std::io::stdin().read_line(&mut input) {
+        Ok(0) | Err(_) => false,
+        Ok(_) => matches!(input.trim().to_lowercase().as_str(), "y" | "yes"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commit() {
+        let entry = parse_commit("a1", "feat(cli)!: drop --legacy (#42)", "").unwrap();
+        assert_eq!(entry.group, Group::Breaking);
+        assert_eq!(entry.scope.as_deref(), Some("cli"));
+        assert_eq!(entry.title, "drop --legacy");
+        assert_eq!(entry.pull_request, Some(42));
+
+        let entry = parse_commit(
+            "b2",
+            "Merge pull request #7 from someone/fix-crash",
+            "fix: crash on empty input\n",
+        )
+        .unwrap();
+        assert_eq!((entry.group, entry.pull_request), (Group::Fixes, Some(7)));
+        assert_eq!(entry.title, "crash on empty input");
+
+        let entry = parse_commit("c3", "Update the README", "").unwrap();
+        assert_eq!(
+            (entry.group, entry.title.as_str()),
+            (Group::Other, "Update the README")
+        );
+        assert!(parse_commit("d4", "Merge branch 'main' into topic", "").is_none());
+
+        let entries = [parse_commit("a1", "fix: x (#1)", "").unwrap()];
+        assert_eq!(format_groups(&entries), "### Bug Fixes\n- x (#1) [a1]\n\n");
+    }
+
+    #[test]
+    fn test_insert_release() {
+        let section = "## 1.3.0 - 2024-05-01\n\n### Features\n- New\n";
+        assert_eq!(
+            insert_release("", section),
+            format!("# Changelog\n\n{section}")
+        );
+        assert_eq!(
+            insert_release("# Changelog\n\nIntro\n\n## 1.2.0\n- Old\n", section),
+            format!("# Changelog\n\nIntro\n\n{section}\n## 1.2.0\n- Old\n")
+        );
+        assert_eq!(
+            insert_release("# Changelog\n", section),
+            format!("# Changelog\n\n{section}")
+        );
+    }
+}
@@ -18,7 +18,7 @@ pub struct Cli {
     /// The query to process in non-interactive mode
     pub query: Option<String>,
 
-    /// The model to use for the AI assistant
+    /// The model to use for the AI assistant, or an alias such as `sonnet` or `fast`
     #[arg(long, default_value = "claude-3-7-sonnet-20250219")]
     pub model: String,
 
@@ -42,6 +42,23 @@ pub struct Cli {
     #[arg(long)]
     pub max_tokens: Option<usize>,
 
+    /// Sampling temperature of requests, between 0 and 2 (default: provider's)
+    #[arg(long, value_parser = crate::llm::sampling::parse_temperature)]
+    pub temperature: Option<f32>,
+
+    /// Nucleus sampling top-p of requests, between 0 and 1 (default: provider's)
+    #[arg(long, value_parser = crate::llm::sampling::parse_top_p)]
+    pub top_p: Option<f32>,
+
+    /// Sampling of one class of requests (conversation, summary, check), e.g.
+    /// `check:temperature=0` (repeatable)
+    #[arg(
+        long = "task-sampling",
+        value_name = "CLASS:temperature=T,top_p=P",
+        value_parser = crate::llm::sampling::parse_task_sampling
+    )]
+    pub task_sampling: Vec<(crate::llm::sampling::SamplingTask, crate::llm::Sampling)>,
+
     /// Use minimal prompt
     #[arg(long)]
     pub minimal_prompt: bool,
@@ -58,6 +75,181 @@ pub struct Cli {
     #[arg(long)]
     pub timeout: Option<u64>,
 
+    /// Plan-only mode: record mutating actions instead of executing them
+    #[arg(long, conflicts_with = "apply")]
+    pub plan: bool,
+
+    /// Execute an approved plan file produced by a previous --plan run
+    #[arg(long, value_name = "PLAN_FILE")]
+    pub apply: Option<String>,
+
+    /// Read-only mode: disable tools that modify files
+    #[arg(long)]
+    pub readonly: bool,
+
+    /// Read-only mode that saves write/patch calls as a proposal patch file
+    #[arg(long)]
+    pub propose: bool,
+
+    /// Maximum depth of agents spawning sub-agents (default: 3)
+    #[arg(long, value_name = "DEPTH")]
+    pub max_agent_depth: Option<usize>,
+
+    /// Reuse cached responses to identical LLM requests from disk
+    #[arg(long)]
+    pub llm_cache: bool,
+
+    /// Time-to-live of cached LLM responses in seconds (default: 86400)
+    #[arg(long, value_name = "SECONDS")]
+    pub llm_cache_ttl: Option<u64>,
+
+    /// Do not record token usage in the ledger (~/.termineer/usage.jsonl)
+    #[arg(long)]
+    pub no_usage_ledger: bool,
+
+    /// Ask the agent to summarize and finish once its estimated cost reaches this
+    /// amount in USD (default: TERMINEER_MAX_COST)
+    #[arg(long, value_name = "USD", value_parser = parse_usd)]
+    pub max_cost: Option<f64>,
+
+    /// Warn when the estimated LLM cost of the month approaches this amount in USD
+    #[arg(long, value_name = "USD", value_parser = parse_usd)]
+    pub monthly_budget: Option<f64>,
+
+    /// How requests use multiple API keys of a provider: on-rate-limit (default) or round-robin
+    #[arg(long, value_name = "STRATEGY", value_parser = crate::llm::keys::parse_key_rotation)]
+    pub key_rotation: Option<crate::llm::keys::KeyRotation>,
+
+    /// Disable the disk cache of pages retrieved by the fetch tool
+    #[arg(long)]
+    pub no_fetch_cache: bool,
+
+    /// Seconds before cached fetch results are revalidated (default: 3600)
+    #[arg(long, value_name = "SECONDS")]
+    pub fetch_cache_ttl: Option<u64>,
+
+    /// Only allow network tools to access this domain and its subdomains (repeatable)
+    #[arg(long = "allow-domain", value_name = "DOMAIN")]
+    pub allowed_domains: Vec<String>,
+
+    /// Block network tools from accessing this domain and its subdomains (repeatable)
+    #[arg(long = "block-domain", value_name = "DOMAIN")]
+    pub blocked_domains: Vec<String>,
+
+    /// Maximum size of responses downloaded by network tools, in bytes
+    #[arg(long, value_name = "BYTES")]
+    pub max_download_size: Option<u64>,
+
+    /// Do not fetch pages disallowed by the site's robots.txt
+    #[arg(long)]
+    pub respect_robots: bool,
+
+    /// Inputs each agent may send with the computer tool (default: 50)
+    #[arg(long, value_name = "N")]
+    pub computer_max_actions: Option<usize>,
+
+    /// Only let the computer tool use windows of this application (repeatable)
+    #[arg(long = "computer-app", value_name = "APP")]
+    pub computer_apps: Vec<String>,
+
+    /// Do not wrap and escape output of fetch, search and MCP tools as untrusted content
+    #[arg(long)]
+    pub no_content_guard: bool,
+
+    /// Model that screens fetched content for prompt injection (e.g. claude-3-5-haiku-latest)
+    #[arg(long, value_name = "MODEL")]
+    pub injection_classifier: Option<String>,
+
+    /// Model judging whether a long shell command should be interrupted (e.g. claude-3-5-haiku-latest)
+    #[arg(long, value_name = "MODEL")]
+    pub interrupt_model: Option<String>,
+
+    /// Put every MCP tool description in the system prompt instead of a summary per server
+    #[arg(long)]
+    pub eager_mcp_tools: bool,
+
+    /// Proxy URL for all outbound HTTP (default: HTTP_PROXY/HTTPS_PROXY)
+    #[arg(long, value_name = "URL")]
+    pub proxy: Option<String>,
+
+    /// PEM file with additional trusted CA certificates (default: SSL_CERT_FILE)
+    #[arg(long, value_name = "PATH")]
+    pub ca_bundle: Option<std::path::PathBuf>,
+
+    /// Run shell commands with plain pipes instead of a pseudo-terminal
+    #[arg(long)]
+    pub no_pty: bool,
+
+    /// Terminal size for shell commands as COLSxROWS (default: 120x40)
+    #[arg(long, value_name = "COLSxROWS", value_parser = crate::tools::shell::parse_pty_size)]
+    pub pty_size: Option<(u16, u16)>,
+
+    /// Extra regex for lines to keep when truncating long shell output (repeatable)
+    #[arg(long = "keep-pattern", value_name = "REGEX")]
+    pub keep_patterns: Vec<String>,
+
+    /// Extra stop sequence that ends the model's response (repeatable)
+    #[arg(long = "stop-sequence", value_name = "TEXT")]
+    pub stop_sequences: Vec<String>,
+
+    /// Stream responses and start a tool as soon as its call is complete
+    #[arg(long)]
+    pub early_tool_dispatch: bool,
+
+    /// Continue right after read-only tools, cancelling the request if you type a message
+    #[arg(long)]
+    pub speculative_prefetch: bool,
+
+    /// Commit each batch of edits to a termineer/<session> branch with a generated message
+    #[arg(long)]
+    pub checkpoint_commits: bool,
+
+    /// Work in a temporary copy of the working tree and review the changes at the end
+    #[arg(long)]
+    pub isolated: bool,
+
+    /// Enable definition/references/hover/rename tools backed by a language server
+    /// (detected from the project files unless COMMAND is given)
+    #[arg(long, value_name = "COMMAND", num_args = 0..=1, default_missing_value = "")]
+    pub lsp: Option<String>,
+
+    /// Embedding model for semantic search, as PROVIDER[/MODEL] (openai, google or cohere)
+    #[arg(long, value_name = "PROVIDER[/MODEL]")]
+    pub embedding_model: Option<String>,
+
+    /// Add the code most relevant to each new topic from the semantic index to the
+    /// prompt, up to TOKENS (default 8000)
+    #[arg(long, value_name = "TOKENS", num_args = 0..=1, default_missing_value = "8000")]
+    pub smart_context: Option<usize>,
+
+    /// Let others join the interactive session with `termineer attach`; messages are
+    /// attributed by name (NAME defaults to the login name)
+    #[arg(long, value_name = "NAME", num_args = 0..=1, default_missing_value = "")]
+    pub share: Option<String>,
+
+    /// Run interactive mode as plain lines without the full-screen interface,
+    /// for screen readers and basic terminals
+    #[arg(long)]
+    pub plain_ui: bool,
+
+    /// Send Markdown files dropped in .termineer/inbox/ to the agent named AGENT (default:
+    /// main) and write its output for each to .termineer/outbox/
+    #[arg(long, value_name = "AGENT", num_args = 0..=1, default_missing_value = "main")]
+    pub inbox: Option<String>,
+
+    /// Answer the query with JSON matching this JSON Schema, correcting invalid answers
+    #[arg(long, value_name = "FILE", requires = "query")]
+    pub schema: Option<std::path::PathBuf>,
+
+    /// Open with the prompt of .termineer/starters/NAME.md, using its kind and model;
+    /// a query adds details to the prompt
+    #[arg(long, value_name = "NAME")]
+    pub starter: Option<String>,
+
+    /// Language of the command line and terminal UI: en, de or ja (default: from the locale)
+    #[arg(long, value_name = "LANG", value_parser = crate::i18n::parse_language)]
+    pub language: Option<String>,
+
     /// Subcommand to execute
     #[command(subcommand)]
     pub command: Option<Commands>,
@@ -66,12 +258,18 @@ pub struct Cli {
 /// Subcommands for Termineer
 #[derive(Subcommand, Debug)]
 pub enum Commands {
-    /// Login to Termineer account
-    #[clap(hide = true)]
+    /// Log in to a Termineer account to unlock subscription features
     Login,
 
+    /// Log out and remove stored account credentials
+    Logout,
+
     /// List available agent kinds/templates
-    ListKinds,
+    ListKinds {
+        /// Print the kinds as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
     /// Run a workflow from the .termineer/workflows directory
     #[clap(hide = true)]
@@ -91,12 +289,263 @@ pub enum Commands {
     /// Start the graphical user interface
     Gui,
 
+    /// Join a session shared with --share in the current directory
+    Attach {
+        /// Name your messages are attributed to (defaults to the login name)
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// Print the state of the agent selected in the interactive session of the current directory
+    Status {
+        /// Print it as text, as a short line for tmux or zellij status bars, or as JSON
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            default_value = "text",
+            value_parser = crate::control::parse_format
+        )]
+        format: crate::control::StatusFormat,
+    },
+
+    /// Run an eval suite and print a comparison table of the results
+    Eval {
+        /// Path to the suite YAML file
+        suite: String,
+    },
+
+    /// Run the queries of a JSONL file and write the results to another
+    Batch {
+        /// File with one query per line: a JSON string or {"id": ..., "query": ...}
+        queries: std::path::PathBuf,
+
+        /// Number of queries run at the same time
+        #[arg(long, short = 'j', default_value_t = 1)]
+        parallel: usize,
+
+        /// Results file (defaults to NAME.results.jsonl next to the queries)
+        #[arg(long)]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// Write the release notes of the commits since a tag and add them to CHANGELOG.md
+    Changelog {
+        /// Tag or commit of the previous release
+        #[arg(long)]
+        from: String,
+
+        /// Last commit of the release
+        #[arg(long, default_value = "HEAD")]
+        to: String,
+
+        /// Name of the release in the heading, e.g. v1.3.0
+        #[arg(long, value_name = "NAME", default_value = "Unreleased")]
+        release: String,
+
+        /// Changelog the notes are added to
+        #[arg(long, default_value = "CHANGELOG.md")]
+        output: std::path::PathBuf,
+
+        /// Add the notes without asking for approval
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+
+    /// Review the changes of the current branch with parallel agents and print a report
+    Review {
+        /// Branch or commit the changes are compared with (`git diff BASE...HEAD`)
+        #[arg(long, default_value = "main")]
+        base: String,
+
+        /// Estimated tokens of diff given to each reviewing agent
+        #[arg(long, default_value_t = 12000)]
+        chunk_tokens: usize,
+
+        /// Number of agents reviewing at the same time
+        #[arg(long, short = 'j', default_value_t = 4)]
+        parallel: usize,
+
+        /// Add the report as a review comment to a pull request with the GitHub CLI
+        /// (the current branch's by default)
+        #[arg(long, value_name = "PR", num_args = 0..=1, default_missing_value = "")]
+        post: Option<String>,
+    },
+
+    /// Build or update the semantic search index of the current directory
+    Index {
+        #[command(subcommand)]
+        action: IndexAction,
+    },
+
+    /// Manage knowledge bases of documentation searched with the kb tool
+    Kb {
+        #[command(subcommand)]
+        action: KbAction,
+    },
+
+    /// Manage provider API keys stored in the OS keychain
+    Keys {
+        #[command(subcommand)]
+        action: KeysAction,
+    },
+
+    /// List the models of each provider with an API key, with their context size and price
+    Models {
+        /// Only list the models of this provider
+        #[arg(long, value_name = "PROVIDER")]
+        provider: Option<String>,
+
+        /// Print the models as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Report the tokens and estimated cost recorded in the usage ledger
+    Usage {
+        /// Only include usage of this period, e.g. 12h, 7d or 2w
+        #[arg(long, value_name = "PERIOD", value_parser = crate::llm::ledger::parse_since)]
+        since: Option<std::time::Duration>,
+
+        /// Group the usage by day, model or project
+        #[arg(
+            long,
+            value_name = "GROUPING",
+            default_value = "day",
+            value_parser = crate::llm::ledger::parse_grouping
+        )]
+        by: crate::llm::ledger::Grouping,
+    },
+
+    /// Check the macOS permissions the UI tools need
+    Doctor {
+        /// Ask macOS to show the request for each missing permission
+        #[arg(long)]
+        prompt: bool,
+    },
+
+    /// Manage anonymous usage statistics (off by default)
+    Telemetry {
+        #[command(subcommand)]
+        action: TelemetryAction,
+    },
+
+    /// Manage git hooks that write commit messages and review changes before a push
+    Hooks {
+        #[command(subcommand)]
+        action: HooksAction,
+    },
+
     /// Dump prompt templates (hidden, debug-only feature)
     #[cfg(debug_assertions)]
     DumpPrompts {
         /// Template name to dump
         template: String,
+
+        /// Print the estimated token count of each prompt section instead of the prompt
+        #[arg(long)]
+        stats: bool,
+    },
+}
+
+/// Actions of the keys subcommand
+#[derive(Subcommand, Debug)]
+pub enum KeysAction {
+    /// Store an API key for a provider (read from the terminal or stdin)
+    Set {
+        /// Provider name (anthropic, openai, google, openrouter, deepseek, cohere, grok)
+        provider: String,
+    },
+    /// Add another API key for a provider, used in rotation with the stored ones
+    Add {
+        /// Provider name
+        provider: String,
+    },
+    /// Remove a provider's API keys from the keychain
+    Remove {
+        /// Provider name
+        provider: String,
     },
+    /// Show where each provider's API key comes from
+    List,
+    /// Validate every configured key and show remaining rate-limit headroom
+    Check,
+}
+
+/// Actions of the index subcommand
+#[derive(Subcommand, Debug)]
+pub enum IndexAction {
+    /// Embed every file again, discarding the existing index
+    Build,
+    /// Embed only the files changed since the last update
+    Update,
+}
+
+/// Actions of the kb subcommand
+#[derive(Subcommand, Debug)]
+pub enum KbAction {
+    /// Add documents from a file, a directory or a URL, replacing earlier copies
+    Add {
+        /// File, directory (markdown, text, HTML, PDF and DOCX files) or URL
+        source: String,
+
+        /// Knowledge base to add the documents to
+        #[arg(long, default_value = "docs")]
+        name: String,
+
+        /// Pages of a site to crawl below the URL
+        #[arg(long, default_value_t = 1)]
+        max_pages: usize,
+    },
+    /// List the knowledge bases of the current directory
+    List,
+    /// Delete a knowledge base
+    Remove {
+        /// Knowledge base name
+        name: String,
+    },
+}
+
+/// Actions of the telemetry subcommand
+#[derive(Subcommand, Debug)]
+pub enum TelemetryAction {
+    /// Show whether telemetry is enabled
+    Status,
+    /// Send anonymous usage counters (never prompts or file contents)
+    On,
+    /// Stop sending usage counters and discard unsent ones
+    Off,
+}
+
+/// Actions of the hooks subcommand
+#[derive(Subcommand, Debug)]
+pub enum HooksAction {
+    /// Install the hooks enabled in .termineer/config.json into the current repository
+    Install {
+        /// Replace hooks of other tools
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove the hooks installed by termineer
+    Uninstall,
+    /// Run a hook (called by git)
+    #[command(hide = true)]
+    Run {
+        /// Hook name
+        #[arg(value_parser = crate::hooks::parse_hook)]
+        hook: crate::hooks::Hook,
+
+        /// Arguments git gave the hook
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+/// Parse a positive amount in USD
+fn parse_usd(arg: &str) -> Result<f64, String> {
+    match arg.trim().trim_start_matches('$').parse::<f64>() {
+        Ok(budget) if budget > 0.0 => Ok(budget),
+        _ => Err(format!("Expected a positive amount in USD, got '{arg}'")),
+    }
 }
 
 /// Parse grammar type from string
@@ -112,7 +561,10 @@ fn parse_grammar_type(arg: &str) -> Result<GrammarType, String> {
 }
 
 /// Convert the Cli struct to the application's Config
-pub fn cli_to_config(cli: &Cli) -> crate::config::Config {
+pub fn cli_to_config(
+    cli: &Cli,
+    starter: Option<&crate::starters::Starter>,
+) -> crate::config::Config {
     let mut config = crate::config::Config::new();
 
     // Basic options
@@ -122,14 +574,70 @@ pub fn cli_to_config(cli: &Cli) -> crate::config::Config {
     config.disabled_tools = cli.disabled_tools.clone();
     config.thinking_budget = cli.thinking_budget;
     config.max_token_output = cli.max_tokens;
+    config.sampling.default.temperature = cli.temperature;
+    config.sampling.default.top_p = cli.top_p;
+    config.sampling.tasks = cli.task_sampling.iter().copied().collect();
     config.use_minimal_prompt = cli.minimal_prompt;
     config.grammar_type = cli.grammar;
     config.skip_auth = cli.skip_auth;
     config.timeout_seconds = cli.timeout;
+    config.plan_mode = cli.plan;
+    config.readonly = cli.readonly || cli.propose;
+    config.propose_changes = cli.propose;
+    config.max_spawn_depth = cli.max_agent_depth;
+    config.llm_cache = cli.llm_cache;
+    config.llm_cache_ttl = cli.llm_cache_ttl;
+    config.usage_ledger = !cli.no_usage_ledger;
+    config.monthly_budget = cli.monthly_budget;
+    config.budget.max_cost = cli.max_cost.or_else(|| {
+        std::env::var("TERMINEER_MAX_COST")
+            .ok()
+            .and_then(|value| parse_usd(&value).ok())
+    });
+    config.key_rotation = cli.key_rotation.unwrap_or_default();
+    config.fetch_cache = !cli.no_fetch_cache;
+    config.fetch_cache_ttl = cli.fetch_cache_ttl;
+    config.allowed_domains = cli.allowed_domains.clone();
+    config.blocked_domains = cli.blocked_domains.clone();
+    config.max_download_size = cli.max_download_size;
+    config.respect_robots_txt = cli.respect_robots;
+    config.computer_max_actions = cli.computer_max_actions;
+    config.computer_apps = cli.computer_apps.clone();
+    config.content_guard = !cli.no_content_guard;
+    config.injection_classifier = cli.injection_classifier.clone();
+    config.interrupt_model = cli.interrupt_model.clone();
+    config.eager_mcp_tools = cli.eager_mcp_tools;
+    config.proxy = cli.proxy.clone();
+    config.ca_bundle = cli.ca_bundle.clone();
+    config.shell_pty = !cli.no_pty;
+    config.pty_size = cli.pty_size;
+    config.truncation_keep_patterns = cli.keep_patterns.clone();
+    config.stop_sequences = cli.stop_sequences.clone();
+    config.early_tool_dispatch = cli.early_tool_dispatch;
+    config.speculative_prefetch = cli.speculative_prefetch;
+    config.checkpoint_commits = cli.checkpoint_commits;
+    config.isolated = cli.isolated;
+    config.lsp = cli.lsp.clone();
+    config.embedding_model = cli.embedding_model.clone();
+    config.smart_context = cli.smart_context;
+    config.share = cli.share.clone();
+    config.plain_ui = cli.plain_ui;
+    config.inbox = cli.inbox.clone();
+    config.output_schema = cli.schema.clone();
+
+    // A starter's kind applies unless --kind is given, its model replaces --model
+    if let Some(starter) = starter {
+        if config.kind.is_none() {
+            config.kind = starter.kind.clone();
+        }
+        if let Some(model) = &starter.model {
+            config.model = model.clone();
+        }
+    }
 
     // Special commands
     #[cfg(debug_assertions)]
-    if let Some(Commands::DumpPrompts { template }) = &cli.command {
+    if let Some(Commands::DumpPrompts { template, .. }) = &cli.command {
         config.dump_prompts = Some(template.clone());
     }
 
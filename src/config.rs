@@ -71,6 +71,9 @@ pub struct Config {
     /// Maximum tokens to generate in the response (None = use model default)
     pub max_token_output: Option<usize>,
 
+    /// Temperature and top-p of requests, with overrides per class of request
+    pub sampling: crate::llm::sampling::SamplingConfig,
+
     /// Whether to use a minimal system prompt
     pub use_minimal_prompt: bool,
 
@@ -95,6 +98,130 @@ pub struct Config {
 
     /// Timeout in seconds for non-interactive mode
     pub timeout_seconds: Option<u64>,
+
+    /// Plan-only mode: record mutating tool calls instead of executing them
+    pub plan_mode: bool,
+
+    /// Read-only mode: tools that modify files are not available
+    pub readonly: bool,
+
+    /// In read-only mode, collect write/patch calls into a proposal patch file
+    pub propose_changes: bool,
+
+    /// Resource limits for this agent (set by a parent for sub-agents)
+    pub budget: crate::agent::budget::AgentBudget,
+
+    /// Where this agent adds its estimated cost, for the budget of its parent agent
+    pub parent_cost: Option<crate::agent::budget::SharedCost>,
+
+    /// Maximum depth of agents spawning agents (task and agent tools)
+    pub max_spawn_depth: Option<usize>,
+
+    /// Whether identical LLM requests may be served from the disk cache
+    pub llm_cache: bool,
+
+    /// Time-to-live of cached LLM responses in seconds (None = default)
+    pub llm_cache_ttl: Option<u64>,
+
+    /// Whether the usage of requests is recorded in the ledger across sessions
+    pub usage_ledger: bool,
+
+    /// Estimated monthly LLM cost in USD to warn about (None = no budget)
+    pub monthly_budget: Option<f64>,
+
+    /// How requests are spread across multiple API keys of a provider
+    pub key_rotation: crate::llm::keys::KeyRotation,
+
+    /// Whether the fetch tool may reuse pages from its disk cache
+    pub fetch_cache: bool,
+
+    /// Seconds before cached fetch results are revalidated (None = default)
+    pub fetch_cache_ttl: Option<u64>,
+
+    /// Domains network tools may access (empty = all)
+    pub allowed_domains: Vec<String>,
+
+    /// Domains network tools may not access without asking
+    pub blocked_domains: Vec<String>,
+
+    /// Largest response body network tools download, in bytes (None = unlimited)
+    pub max_download_size: Option<u64>,
+
+    /// Whether the fetch tool respects robots.txt
+    pub respect_robots_txt: bool,
+
+    /// Inputs each agent may send with the computer tool (None = the tool's default)
+    pub computer_max_actions: Option<usize>,
+
+    /// Applications whose windows the computer tool may use (empty = all)
+    pub computer_apps: Vec<String>,
+
+    /// Whether output of fetch, search and MCP tools is marked as untrusted
+    pub content_guard: bool,
+
+    /// Model classifying untrusted content for prompt injection (None = disabled)
+    pub injection_classifier: Option<String>,
+
+    /// Whether every MCP tool description is embedded in the system prompt
+    /// instead of a one-line summary per server
+    pub eager_mcp_tools: bool,
+
+    /// Proxy for all outbound HTTP (None = use HTTP(S)_PROXY from the environment)
+    pub proxy: Option<String>,
+
+    /// PEM bundle of additional trusted CA certificates
+    pub ca_bundle: Option<std::path::PathBuf>,
+
+    /// Cheaper model asked whether a long shell command should be interrupted
+    /// (None = the agent's model)
+    pub interrupt_model: Option<String>,
+
+    /// Whether shell commands run inside a pseudo-terminal
+    pub shell_pty: bool,
+
+    /// Terminal size (columns, rows) for PTY shell commands (None = default)
+    pub pty_size: Option<(u16, u16)>,
+
+    /// Extra regexes for lines preserved when long shell output is truncated
+    pub truncation_keep_patterns: Vec<String>,
+
+    /// Stop sequences added to the grammar's, from the command line and the
+    /// kind template
+    pub stop_sequences: Vec<String>,
+
+    /// Stream responses and run a tool as soon as its call is complete
+    pub early_tool_dispatch: bool,
+
+    /// Send the next request right after a read-only tool in interactive mode,
+    /// cancelling it when the user sends a message first
+    pub speculative_prefetch: bool,
+
+    /// Commit each batch of successful edits to a `termineer/<session>` branch
+    pub checkpoint_commits: bool,
+
+    /// Run the session in a temporary copy of the working tree
+    pub isolated: bool,
+
+    /// Language server command for the navigation tools (empty to detect it; None disables them)
+    pub lsp: Option<String>,
+
+    /// Embedding model for semantic search as `provider[/model]`
+    pub embedding_model: Option<String>,
+
+    /// Token budget of code selected from the semantic index for each topic (None disables it)
+    pub smart_context: Option<usize>,
+
+    /// Name of the local user when the interactive session is shared (empty for the login name)
+    pub share: Option<String>,
+
+    /// Run interactive mode as plain lines instead of the full-screen interface
+    pub plain_ui: bool,
+
+    /// Agent that receives the files of the inbox in interactive mode (None disables it)
+    pub inbox: Option<String>,
+
+    /// JSON Schema file the answer of a single query must match
+    pub output_schema: Option<std::path::PathBuf>,
 }
 
 impl Config {
@@ -109,6 +236,7 @@ impl Config {
             disabled_tools: Vec::new(), // No tools disabled by default
             thinking_budget: 8192,
             max_token_output: None, // No limit by default, use model's default
+            sampling: Default::default(), // Use the provider's defaults
             use_minimal_prompt: false,
             #[cfg(debug_assertions)]
             dump_prompts: None,
@@ -117,6 +245,46 @@ impl Config {
             subscription_type: None,
             skip_auth: false,
             timeout_seconds: None, // Default timeout (will use 150 seconds if None)
+            plan_mode: false,
+            readonly: false,
+            propose_changes: false,
+            budget: Default::default(), // No limits by default
+            parent_cost: None,          // Not a sub-agent
+            max_spawn_depth: None,      // Use the agent manager default
+            llm_cache: false,
+            llm_cache_ttl: None, // Use the cache default (24 hours)
+            usage_ledger: true,
+            monthly_budget: None,
+            key_rotation: Default::default(), // Switch keys only when rate limited
+            fetch_cache: true,
+            fetch_cache_ttl: None, // Use the cache default (1 hour)
+            allowed_domains: Vec::new(),
+            blocked_domains: Vec::new(),
+            max_download_size: None,
+            respect_robots_txt: false,
+            computer_max_actions: None,
+            computer_apps: Vec::new(),
+            content_guard: true,
+            injection_classifier: None,
+            eager_mcp_tools: false, // Agents list MCP tools on demand
+            proxy: None,
+            ca_bundle: None,
+            interrupt_model: None,
+            shell_pty: true,
+            pty_size: None, // Use the default terminal size (120x40)
+            truncation_keep_patterns: Vec::new(), // Only the built-in error/failure patterns
+            stop_sequences: Vec::new(), // Only the grammar's stop sequences
+            early_tool_dispatch: false, // Wait for the complete response
+            speculative_prefetch: false, // Requests are never cancelled by user input
+            checkpoint_commits: false, // Edits are left uncommitted
+            isolated: false, // Tools work on the current directory
+            lsp: None,      // No language server tools
+            embedding_model: None, // First provider with an API key
+            smart_context: None, // Only autoinclude files are added
+            share: None,    // The session is not shared
+            plain_ui: false, // Full-screen interactive mode
+            inbox: None,    // No inbox is watched
+            output_schema: None, // Answers are plain text
         }
     }
 
@@ -130,8 +298,8 @@ impl Config {
             return;
         }
 
-        // Use model name to determine appropriate grammar type
-        let model_lower = self.model.to_lowercase();
+        // Use model name (or the model of its alias) to determine appropriate grammar type
+        let model_lower = crate::llm::catalog::resolve(&self.model).to_lowercase();
 
         // Set the grammar based on model name patterns
         if model_lower.contains("gemini") {
@@ -152,6 +320,74 @@ impl Config {
         }
     }
 
+    /// Check the settings before any agent is created
+    ///
+    /// Every problem is reported at once, each with how to fix it: model
+    /// catalog overrides must parse, models must have a known provider with
+    /// an API key, the kind must exist, disabled tools must be spelled
+    /// correctly and timeouts must be positive.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems: Vec<String> = crate::llm::catalog::problems()
+            .iter()
+            .map(|problem| format!("Model catalog {problem}"))
+            .collect();
+
+        let models = [
+            ("--model", Some(&self.model)),
+            ("--interrupt-model", self.interrupt_model.as_ref()),
+            ("--injection-classifier", self.injection_classifier.as_ref()),
+        ];
+        for (option, model) in models {
+            if let Some(Err(e)) = model.map(|model| crate::llm::factory::check_model(model)) {
+                problems.push(format!("{option}: {e}"));
+            }
+        }
+
+        if let Some(kind) = &self.kind {
+            if !crate::prompts::is_valid_kind(kind) {
+                let kinds = crate::prompts::AVAILABLE_KINDS_ARRAY
+                    .iter()
+                    .cloned()
+                    .chain(crate::prompts::kinds::custom_kinds())
+                    .collect::<Vec<_>>();
+                problems.push(format!(
+                    "--kind: Unknown agent kind '{kind}'.{} Run `termineer list-kinds` to see all kinds.",
+                    did_you_mean(kind, kinds.iter().map(String::as_str))
+                ));
+            }
+        }
+
+        let tools = || {
+            crate::prompts::ALL_TOOLS
+                .iter()
+                .chain(crate::prompts::PLUS_TOOLS)
+                .copied()
+        };
+        for tool in &self.disabled_tools {
+            let name = tool.trim().to_lowercase();
+            if !tools().any(|known| known == name) {
+                problems.push(format!(
+                    "--disable-tool: Unknown tool '{tool}'.{} Tools: {}",
+                    did_you_mean(&name, tools()),
+                    tools().collect::<Vec<_>>().join(", ")
+                ));
+            }
+        }
+
+        if self.timeout_seconds == Some(0) {
+            problems.push("--timeout: The timeout must be at least 1 second.".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid configuration:\n  - {}",
+                problems.join("\n  - ")
+            ))
+        }
+    }
+
     /// Get the resolved grammar type, applying model-specific resolution if needed
     #[allow(dead_code)]
     pub fn get_grammar_type(&mut self) -> GrammarType {
@@ -167,3 +403,67 @@ impl Config {
             .expect("Grammar type should be set either explicitly or by model")
     }
 }
+
+/// The candidate closest to a misspelled name, if any is close enough to be
+/// what was meant
+pub fn suggest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(&name, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, candidate)| {
+            let length = candidate.chars().count();
+            *distance <= (length / 3).max(2) && *distance < length
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// " Did you mean '<suggestion>'?" when a candidate is close to the name
+fn did_you_mean<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> String {
+    suggest(name, candidates)
+        .map(|suggestion| format!(" Did you mean '{suggestion}'?"))
+        .unwrap_or_default()
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest() {
+        let tools = ["shell", "read", "write", "patch", "fetch"];
+        assert_eq!(suggest("shel", tools), Some("shell"));
+        assert_eq!(suggest("Wirte", tools), Some("write"));
+        assert_eq!(suggest("browser", tools), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = Config::new();
+        config.model = "mock:script.yaml".to_string();
+        assert_eq!(config.validate(), Ok(()));
+
+        config.disabled_tools = vec!["Shell".to_string(), "fecth".to_string()];
+        config.timeout_seconds = Some(0);
+        let error = config.validate().unwrap_err();
+        assert!(error.contains("Unknown tool 'fecth'. Did you mean 'fetch'?"));
+        assert!(!error.contains("'Shell'"));
+        assert!(error.contains("--timeout"));
+    }
+}
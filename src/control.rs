@@ -0,0 +1,256 @@
+//! Control socket of a running interactive session
+//!
+//! Each interactive session listens on a Unix socket in
+//! `.termineer/control.sock` and answers a `status` line with the state of
+//! the agent selected in the interface, as a line of JSON. `termineer status`
+//! reads it, so the status bar of tmux or zellij in another pane can show
+//! whether the agent is thinking, running a tool or done.
+
+use crate::agent::{AgentId, AgentState};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::RwLock;
+#[cfg(unix)]
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Socket of the interactive session, relative to the workspace
+pub const SOCKET_PATH: &str = ".termineer/control.sock";
+
+#[cfg(not(unix))]
+const UNSUPPORTED: &str = "The control socket needs Unix domain sockets, which this platform lacks";
+
+lazy_static! {
+    /// Agent selected in the interface while the control socket is served
+    static ref ACTIVE_AGENT: RwLock<Option<AgentId>> = RwLock::new(None);
+}
+
+/// How `termineer status` prints the state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusFormat {
+    /// A sentence with the agent's name, ID and state
+    Text,
+    /// A short line for status bars; empty without a session
+    Tmux,
+    /// The status as JSON
+    Json,
+}
+
+/// Parse a status format from the command line
+pub fn parse_format(arg: &str) -> Result<StatusFormat, String> {
+    match arg.to_lowercase().as_str() {
+        "text" => Ok(StatusFormat::Text),
+        // zellij runs the same command in its status bar plugins
+        "tmux" | "zellij" => Ok(StatusFormat::Tmux),
+        "json" => Ok(StatusFormat::Json),
+        _ => Err(format!(
+            "Unknown format: {arg}. Valid options: text, tmux, json"
+        )),
+    }
+}
+
+/// State of the agent selected in a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    /// Name of the agent
+    pub agent: String,
+    /// ID of the agent
+    pub id: AgentId,
+    /// `idle`, `processing`, `running_tool`, `done` or `terminated`
+    pub state: String,
+    /// Tool being run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+}
+
+impl Status {
+    /// Status of an agent in the given state
+    pub fn new(agent: String, id: AgentId, state: &AgentState) -> Self {
+        let (state, tool) = match state {
+            AgentState::Idle => ("idle", None),
+            AgentState::Processing => ("processing", None),
+            AgentState::RunningTool { tool, .. } => ("running_tool", Some(tool.clone())),
+            AgentState::Done(_) => ("done", None),
+            AgentState::Terminated => ("terminated", None),
+        };
+        Self {
+            agent,
+            id,
+            state: state.to_string(),
+            tool,
+        }
+    }
+
+    /// State in the words of the TUI's input title
+    fn label(&self) -> String {
+        match self.state.as_str() {
+            "processing" => t!("tui.state.thinking"),
+            "running_tool" => t!(
+                "tui.state.running",
+                tool = self.tool.as_deref().unwrap_or_default()
+            ),
+            "done" => t!("tui.state.done"),
+            "terminated" => t!("tui.state.terminated"),
+            _ => t!("tui.state.ready"),
+        }
+    }
+
+    /// Indicator of the state, as in the TUI's agent list
+    fn indicator(&self) -> &'static str {
+        match self.state.as_str() {
+            "processing" => "🤔",
+            "running_tool" => "🔧",
+            "done" => "✅",
+            "terminated" => "⛔",
+            _ => "🟢",
+        }
+    }
+
+    /// The status as printed by `termineer status`
+    pub fn format(&self, format: StatusFormat) -> String {
+        match format {
+            StatusFormat::Text => format!("{} [{}]: {}", self.agent, self.id, self.label()),
+            StatusFormat::Tmux => format!("{} {}: {}", self.indicator(), self.agent, self.label()),
+            StatusFormat::Json => serde_json::to_string(self).unwrap_or_default(),
+        }
+    }
+}
+
+/// Report the state of `agent_id` as the selected agent from now on
+pub fn set_active_agent(agent_id: AgentId) {
+    // Only while served, and without locking for writing on every frame
+    let active = *ACTIVE_AGENT.read().unwrap();
+    if active.is_some_and(|active| active != agent_id) {
+        *ACTIVE_AGENT.write().unwrap() = Some(agent_id);
+    }
+}
+
+/// Status of the selected agent
+fn active_status() -> Option<Status> {
+    let agent_id = (*ACTIVE_AGENT.read().unwrap())?;
+    let state = crate::agent::get_agent_state(agent_id).ok()?;
+    let name = crate::agent::get_agents()
+        .into_iter()
+        .find_map(|(id, name)| (id == agent_id).then_some(name))?;
+    Some(Status::new(name, agent_id, &state))
+}
+
+fn socket_path() -> Result<PathBuf, String> {
+    std::env::current_dir()
+        .map(|dir| dir.join(SOCKET_PATH))
+        .map_err(|e| format!("Failed to read the current directory: {e}"))
+}
+
+/// Answer status requests about the session, starting with `agent_id` selected
+///
+/// Only one session of a workspace serves the socket; for others this fails.
+#[cfg(unix)]
+pub async fn serve(agent_id: AgentId) -> Result<(), String> {
+    let path = socket_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    // A socket left behind by a session that crashed
+    if tokio::net::UnixStream::connect(&path).await.is_err() {
+        let _ = std::fs::remove_file(&path);
+    }
+    let listener = tokio::net::UnixListener::bind(&path)
+        .map_err(|e| format!("Failed to listen on {}: {e}", path.display()))?;
+    *ACTIVE_AGENT.write().unwrap() = Some(agent_id);
+
+    crate::output::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            crate::output::spawn(serve_client(stream));
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn serve(_agent_id: AgentId) -> Result<(), String> {
+    Err(UNSUPPORTED.to_string())
+}
+
+/// Remove the socket when the session ends
+pub fn stop() {
+    if ACTIVE_AGENT.write().unwrap().take().is_some() {
+        if let Ok(path) = socket_path() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Answer the requests of a client, one per line
+#[cfg(unix)]
+async fn serve_client(stream: tokio::net::UnixStream) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let response = match line.trim() {
+            "status" => serde_json::to_string(&active_status()).unwrap_or_default(),
+            other => {
+                serde_json::json!({ "error": format!("Unknown request: {other}") }).to_string()
+            }
+        };
+        if writer
+            .write_all(format!("{response}\n").as_bytes())
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+/// Ask the session of the current directory for the selected agent's state
+#[cfg(unix)]
+pub async fn status() -> Result<Option<Status>, String> {
+    let path = socket_path()?;
+    let stream = tokio::net::UnixStream::connect(&path)
+        .await
+        .map_err(|_| t!("cli.no_session"))?;
+    let (reader, mut writer) = stream.into_split();
+    writer
+        .write_all(b"status\n")
+        .await
+        .map_err(|e| format!("Failed to ask the session: {e}"))?;
+    let line = BufReader::new(reader)
+        .lines()
+        .next_line()
+        .await
+        .map_err(|e| format!("Failed to read the session's answer: {e}"))?
+        .ok_or_else(|| t!("cli.no_session"))?;
+    serde_json::from_str(&line).map_err(|e| format!("Invalid answer from the session: {e}"))
+}
+
+#[cfg(not(unix))]
+pub async fn status() -> Result<Option<Status>, String> {
+    Err(UNSUPPORTED.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status() {
+        let state = AgentState::RunningTool {
+            tool: "shell".to_string(),
+            detail: "cargo test".to_string(),
+            interruptible: true,
+        };
+        let status = Status::new("main".to_string(), AgentId(1), &state);
+        assert_eq!(status.indicator(), "🔧");
+
+        let json = status.format(StatusFormat::Json);
+        assert_eq!(
+            json,
+            r#"{"agent":"main","id":1,"state":"running_tool","tool":"shell"}"#
+        );
+        assert_eq!(serde_json::from_str::<Status>(&json).unwrap(), status);
+
+        assert_eq!(parse_format("zellij"), Ok(StatusFormat::Tmux));
+        assert!(parse_format("xml").is_err());
+    }
+}
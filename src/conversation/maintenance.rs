@@ -8,7 +8,7 @@
 //! - Improve LLM processing by ensuring clean, meaningful conversation structure
 //! - Prevent errors from malformed message content
 
-use crate::llm::{Content, ImageSource, Message};
+use crate::llm::{Content, DocumentSource, ImageSource, Message};
 
 /// Check if message content is empty or lacks meaningful content
 ///
@@ -35,8 +35,16 @@ pub fn is_empty_content(content: &Content) -> bool {
             ImageSource::Base64 { data, .. } => data.trim().is_empty(),
         },
 
-        // Document without source
-        Content::Document { source } => source.trim().is_empty(),
+        // Document without data
+        Content::Document { source, .. } => match source {
+            DocumentSource::Base64 { data, .. } | DocumentSource::Text { data, .. } => {
+                data.trim().is_empty()
+            }
+            DocumentSource::Url { url } => url.trim().is_empty(),
+        },
+
+        // Tool calls and their results pair up, so neither is dropped
+        Content::ToolUse { .. } | Content::ToolResult { .. } => false,
     }
 }
 
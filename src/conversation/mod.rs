@@ -5,9 +5,11 @@
 //! - Maintenance of conversation structure (removing empty messages)
 //! - Truncation of conversations to stay within token limits
 //! - Tool mapping to track relationships between tool calls and results
+//! - Provenance of tool results (source, size and truncation of the output)
 //! - Utility functions for conversation display and manipulation
 
 mod maintenance;
+pub mod provenance;
 mod truncation;
 
 // Re-export all the components
@@ -0,0 +1,80 @@
+//! Provenance of tool results
+//!
+//! Each tool result in the conversation records where its content came from:
+//! the file, URL or command the tool worked on, the size of its output and
+//! whether the output was truncated. Truncation uses it to prefer content
+//! that can be produced again, and conversation exports include it so a
+//! transcript shows what the agent based its work on.
+
+use crate::llm::{Content, DocumentSource, ImageSource, Provenance};
+
+/// Marker the read tool adds to files cut at its line limit
+const READ_TRUNCATION_NOTICE: &str = "TRUNCATION NOTICE";
+
+/// Provenance of a tool's output
+pub fn of_tool_output(tool_name: &str, args: &str, body: &str, content: &[Content]) -> Provenance {
+    let truncated = content.iter().any(|content| match content {
+        Content::Text { text } => {
+            text.contains(crate::constants::TRUNCATION_PLACEHOLDER)
+                || text.contains(READ_TRUNCATION_NOTICE)
+        }
+        _ => false,
+    });
+    Provenance {
+        source: source_of(tool_name, args, body),
+        bytes: content.iter().map(content_bytes).sum(),
+        truncated,
+    }
+}
+
+/// File, URL or command a tool call works on
+pub fn source_of(tool_name: &str, args: &str, body: &str) -> Option<String> {
+    let source = match tool_name {
+        "read" | "write" | "patch" | "fetch" => args.trim(),
+        // Commands are given as arguments or, for scripts, in the body
+        "shell" if args.trim().is_empty() => body.lines().next().unwrap_or_default().trim(),
+        "shell" => args.trim(),
+        _ => "",
+    };
+    (!source.is_empty()).then(|| source.to_string())
+}
+
+/// Size of content in bytes, as sent to the model
+fn content_bytes(content: &Content) -> usize {
+    match content {
+        Content::Text { text } => text.len(),
+        Content::Image {
+            source: ImageSource::Base64 { data, .. },
+        } => data.len(),
+        Content::Document { source, .. } => match source {
+            DocumentSource::Base64 { data, .. } | DocumentSource::Text { data, .. } => data.len(),
+            DocumentSource::Url { url } => url.len(),
+        },
+        other => other.fallback_text().len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_of_tool_output() {
+        let content = [Content::Text {
+            text: format!("fn main() {{}}{}", crate::constants::TRUNCATION_PLACEHOLDER),
+        }];
+        let provenance = of_tool_output("read", " src/main.rs\n", "", &content);
+        assert_eq!(provenance.source.as_deref(), Some("src/main.rs"));
+        assert_eq!(
+            provenance.bytes,
+            12 + crate::constants::TRUNCATION_PLACEHOLDER.len()
+        );
+        assert!(provenance.truncated);
+
+        assert_eq!(
+            source_of("shell", "", "cargo test\necho done"),
+            Some("cargo test".to_string())
+        );
+        assert_eq!(source_of("task", "review", "details"), None);
+    }
+}
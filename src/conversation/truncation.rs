@@ -14,6 +14,9 @@
 //! 4. Preserves recent tool outputs (for continuity)
 //! 5. Replaces lengthy tool outputs with short placeholders
 //! 6. Maintains overall conversation structure and flow
+//!
+//! Outputs whose provenance names a file or URL can be read again, so they are
+//! truncated sooner, and their placeholder says where the content came from.
 
 #![allow(dead_code)]
 
@@ -86,8 +89,13 @@ struct TruncationCandidate {
     /// Whether this is a tool invocation or result
     #[allow(dead_code)]
     is_invocation: bool,
+    /// Whether the content can be read again from its source
+    rereadable: bool,
 }
 
+/// Tools whose output can be produced again from the source in their provenance
+const REREADABLE_TOOLS: [&str; 2] = ["read", "fetch"];
+
 /// Identifies and truncates eligible tool outputs in a conversation
 ///
 /// This is the main entry point for the truncation system. It examines the current
@@ -170,19 +178,26 @@ fn collect_truncation_candidates(messages: &[Message]) -> Vec<TruncationCandidat
     for (i, message) in messages.iter().enumerate() {
         match &message.info {
             // Collect tool results
-            MessageInfo::ToolResult { tool_name, .. } => {
+            MessageInfo::ToolResult {
+                tool_name,
+                provenance,
+                ..
+            } => {
                 // Skip "done" tool which is typically important
                 if tool_name != "done" {
                     let content_length = match &message.content {
                         Content::Text { text } => text.len(),
                         _ => 0,
                     };
+                    let rereadable = REREADABLE_TOOLS.contains(&tool_name.as_str())
+                        && provenance.as_ref().is_some_and(|p| p.source.is_some());
 
                     candidates.push(TruncationCandidate {
                         index: i,
                         tool_name: tool_name.clone(),
                         content_length,
                         is_invocation: false,
+                        rereadable,
                     });
                 }
             }
@@ -209,8 +224,9 @@ fn identify_truncation_candidates(
 
     // Add truncation candidates (skipping preserved indices)
     for i in preserve_start..preserve_end {
-        // Prioritize truncating larger outputs first
-        if tool_results[i].content_length > 500 {
+        // Prioritize truncating larger outputs first, and those that can be read again
+        let threshold = if tool_results[i].rereadable { 200 } else { 500 };
+        if tool_results[i].content_length > threshold {
             candidates.insert(tool_results[i].index);
         }
     }
@@ -247,6 +263,16 @@ fn apply_truncation(
 
     for &idx in indices_to_truncate {
         if idx < messages.len() {
+            // Name the source of the content, so the agent can read it again
+            let placeholder = match messages[idx]
+                .info
+                .provenance()
+                .and_then(|p| p.source.as_ref())
+            {
+                Some(source) => format!("{} (from {})", config.placeholder_text, source),
+                None => config.placeholder_text.clone(),
+            };
+
             // Replace the content with a placeholder while keeping the message structure
             if let Content::Text { ref mut text } = messages[idx].content {
                 // Save the original length for estimating tokens saved
@@ -255,7 +281,7 @@ fn apply_truncation(
                 // Create truncated text with header and footer
                 let (header, footer) = extract_header_footer(text);
 
-                let truncated_text = format!("{}\n{}\n{}", header, config.placeholder_text, footer);
+                let truncated_text = format!("{}\n{}\n{}", header, placeholder, footer);
 
                 // Replace the text
                 *text = truncated_text;
@@ -266,6 +292,14 @@ fn apply_truncation(
                 // Estimate tokens saved (rough approximation: ~4 chars per token)
                 let chars_saved = original_length.saturating_sub(text.len());
                 estimated_tokens_saved += chars_saved / 4;
+
+                if let MessageInfo::ToolResult {
+                    provenance: Some(provenance),
+                    ..
+                } = &mut messages[idx].info
+                {
+                    provenance.truncated = true;
+                }
             }
         }
     }
@@ -328,5 +362,48 @@ mod tests {
         assert!(should_truncate(&above_usage, safe_limit));
     }
 
+    #[test]
+    fn test_truncation_uses_provenance() {
+        let result = |tool: &str, source: Option<&str>| {
+            Message::text(
+                "user",
+                format!("[result]\n{}\n[end]", "x".repeat(300)),
+                MessageInfo::ToolResult {
+                    tool_name: tool.to_string(),
+                    tool_index: None,
+                    provenance: Some(crate::llm::Provenance {
+                        source: source.map(str::to_string),
+                        bytes: 310,
+                        truncated: false,
+                    }),
+                },
+            )
+        };
+        let mut messages = vec![
+            result("read", Some("src/main.rs")),
+            result("shell", Some("cargo test")),
+        ];
+        let candidates = collect_truncation_candidates(&messages);
+        let config = TruncationConfig {
+            preserve_initial_tools: 0,
+            preserve_recent_tools: 0,
+            ..TruncationConfig::default()
+        };
+
+        // Only the file can be read again, so only it is truncated at this size
+        let indices = identify_truncation_candidates(&candidates, &config);
+        assert_eq!(indices, BTreeSet::from([0]));
+
+        apply_truncation(&mut messages, &indices, &config);
+        assert_eq!(
+            messages[0].content,
+            Content::Text {
+                text: "[result]\n[Tool output truncated to save context space] (from src/main.rs)\n[end]"
+                    .to_string()
+            }
+        );
+        assert!(messages[0].info.provenance().unwrap().truncated);
+    }
+
     // Additional tests could be added here
 }
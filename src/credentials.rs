@@ -0,0 +1,227 @@
+//! Provider API keys and other secrets stored in the OS keychain
+//!
+//! `termineer keys set <provider>` stores a key in the keychain (macOS Keychain,
+//! Windows Credential Manager or the Secret Service on Linux). When a backend is
+//! created, the keychain is consulted first and the provider's environment
+//! variable (e.g. `ANTHROPIC_API_KEY`) is used as a fallback, so CI systems and
+//! `.env` files keep working unchanged.
+//!
+//! A provider may have several keys (see [`crate::llm::keys`]): the keychain
+//! entry holds one key per line and environment variables may list keys
+//! separated by commas.
+
+use std::io::Write;
+
+/// Keychain service name under which all secrets are stored
+const KEYRING_SERVICE: &str = "termineer";
+
+/// Providers with API keys and their environment variables
+pub const PROVIDERS: &[(&str, &str)] = &[
+    ("anthropic", "ANTHROPIC_API_KEY"),
+    ("openai", "OPENAI_API_KEY"),
+    ("google", "GOOGLE_API_KEY"),
+    ("openrouter", "OPENROUTER_API_KEY"),
+    ("deepseek", "DEEPSEEK_API_KEY"),
+    ("cohere", "COHERE_API_KEY"),
+    ("grok", "GROK_API_KEY"),
+];
+
+/// Where an API key was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySource {
+    Keychain,
+    Environment,
+}
+
+impl std::fmt::Display for KeySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeySource::Keychain => write!(f, "keychain"),
+            KeySource::Environment => write!(f, "environment"),
+        }
+    }
+}
+
+fn entry(account: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, account)
+        .map_err(|e| format!("Failed to access the OS keychain: {e}"))
+}
+
+/// Read a secret from the keychain; unavailable keychains count as empty
+pub fn keychain_get(account: &str) -> Option<String> {
+    entry(account).ok()?.get_password().ok()
+}
+
+/// Store a secret in the keychain
+pub fn keychain_set(account: &str, secret: &str) -> Result<(), String> {
+    entry(account)?
+        .set_password(secret)
+        .map_err(|e| format!("Failed to store credentials in the OS keychain: {e}"))
+}
+
+/// Remove a secret from the keychain; returns whether one existed
+pub fn keychain_delete(account: &str) -> Result<bool, String> {
+    match entry(account)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(format!(
+            "Failed to remove credentials from the OS keychain: {e}"
+        )),
+    }
+}
+
+/// Environment variable holding a provider's API key
+pub fn env_var(provider: &str) -> Option<&'static str> {
+    PROVIDERS
+        .iter()
+        .find(|(name, _)| *name == provider)
+        .map(|(_, var)| *var)
+}
+
+/// Validate a provider name given on the command line
+pub fn check_provider(provider: &str) -> Result<(), String> {
+    if env_var(provider).is_some() {
+        Ok(())
+    } else {
+        let known: Vec<&str> = PROVIDERS.iter().map(|(name, _)| *name).collect();
+        let suggestion = crate::config::suggest(provider, known.iter().copied())
+            .map(|name| format!(" Did you mean '{name}'?"))
+            .unwrap_or_default();
+        Err(format!(
+            "Unknown provider '{provider}'.{suggestion} Known providers: {}",
+            known.join(", ")
+        ))
+    }
+}
+
+fn account(provider: &str) -> String {
+    format!("api-key:{provider}")
+}
+
+/// Split a list of keys on newlines and commas, dropping empty entries
+fn split_keys(value: &str) -> Vec<String> {
+    value
+        .split(['\n', ','])
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Find a provider's API keys, preferring the keychain over the environment
+pub fn find_api_keys(provider: &str) -> Option<(Vec<String>, KeySource)> {
+    let keychain_keys = split_keys(&keychain_get(&account(provider)).unwrap_or_default());
+    if !keychain_keys.is_empty() {
+        return Some((keychain_keys, KeySource::Keychain));
+    }
+    let env_keys = split_keys(&std::env::var(env_var(provider)?).unwrap_or_default());
+    if !env_keys.is_empty() {
+        return Some((env_keys, KeySource::Environment));
+    }
+    None
+}
+
+/// A provider's API keys, or an error explaining how to configure one
+pub fn api_keys(provider: &str) -> Result<Vec<String>, String> {
+    find_api_keys(provider)
+        .map(|(keys, _)| keys)
+        .ok_or_else(|| {
+            format!(
+                "No API key for {provider}: run `termineer keys set {provider}` or set {}",
+                env_var(provider).unwrap_or("the provider's API key variable")
+            )
+        })
+}
+
+/// A provider's first API key, or an error explaining how to configure one
+pub fn api_key(provider: &str) -> Result<String, String> {
+    api_keys(provider).map(|mut keys| keys.remove(0))
+}
+
+/// Store a provider's API key in the keychain, replacing any stored keys
+pub fn set_api_key(provider: &str, key: &str) -> Result<(), String> {
+    check_provider(provider)?;
+    keychain_set(&account(provider), key)
+}
+
+/// Add another API key for a provider to the keychain
+pub fn add_api_key(provider: &str, key: &str) -> Result<usize, String> {
+    check_provider(provider)?;
+    let mut keys = split_keys(&keychain_get(&account(provider)).unwrap_or_default());
+    if !keys.iter().any(|existing| existing == key) {
+        keys.push(key.to_string());
+    }
+    keychain_set(&account(provider), &keys.join("\n"))?;
+    Ok(keys.len())
+}
+
+/// Remove a provider's API keys from the keychain
+pub fn remove_api_key(provider: &str) -> Result<bool, String> {
+    check_provider(provider)?;
+    keychain_delete(&account(provider))
+}
+
+/// Read a secret from the terminal without echoing it
+///
+/// Falls back to a plain line read when stdin is not a terminal (e.g. piped).
+pub fn read_secret(prompt: &str) -> std::io::Result<String> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+
+    if !atty::is(atty::Stream::Stdin) {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        return Ok(line.trim().to_string());
+    }
+
+    print!("{prompt}");
+    std::io::stdout().flush()?;
+
+    enable_raw_mode()?;
+    let mut secret = String::new();
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => match key.code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err(std::io::Error::new(
+                        std::io::ErrorKind::Interrupted,
+                        "Cancelled",
+                    ))
+                }
+                KeyCode::Char(c) => secret.push(c),
+                KeyCode::Backspace => {
+                    secret.pop();
+                }
+                _ => {}
+            },
+            // Pasted keys arrive as a single event when bracketed paste is on
+            Ok(Event::Paste(text)) => secret.push_str(&text),
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    disable_raw_mode()?;
+    println!();
+
+    result.map(|_| secret.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_names() {
+        assert_eq!(env_var("anthropic"), Some("ANTHROPIC_API_KEY"));
+        assert!(check_provider("grok").is_ok());
+        assert!(check_provider("nonexistent").is_err());
+    }
+
+    #[test]
+    fn test_split_keys() {
+        assert_eq!(split_keys("a, b,,c"), vec!["a", "b", "c"]);
+        assert_eq!(split_keys("a\nb\n"), vec!["a", "b"]);
+        assert!(split_keys("  ").is_empty());
+    }
+}
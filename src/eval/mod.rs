@@ -0,0 +1,12 @@
+//! Benchmark/eval support
+//!
+//! `termineer eval <suite.yaml>` runs a set of task fixtures against one or more
+//! models and agent kinds, scores each result with regex, file or LLM-judge
+//! assertions, and prints a comparison table. This lets maintainers quantify the
+//! effect of prompt and grammar changes.
+
+pub mod runner;
+pub mod suite;
+
+pub use runner::run_suite;
+pub use suite::EvalSuite;
@@ -0,0 +1,429 @@
+//! Eval suite execution and scoring
+
+use crate::agent;
+use crate::config::Config;
+use crate::eval::suite::{Assertion, EvalSuite, EvalTask};
+use crate::isolation::copy_dir;
+use crate::llm::sampling::SamplingTask;
+use crate::llm::{create_backend, Content, Message, MessageInfo};
+use regex::Regex;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Default timeout per task in seconds
+const DEFAULT_TASK_TIMEOUT: u64 = 300;
+
+/// A model/kind combination being evaluated
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub model: String,
+    pub kind: Option<String>,
+}
+
+impl Variant {
+    /// Column label for the results table
+    pub fn label(&self) -> String {
+        match &self.kind {
+            Some(kind) => format!("{} ({})", self.model, kind),
+            None => self.model.clone(),
+        }
+    }
+}
+
+/// Result of running one task with one variant
+#[derive(Debug, Clone)]
+pub struct TaskOutcome {
+    /// Number of assertions that passed
+    pub passed: usize,
+    /// Total number of assertions
+    pub total: usize,
+    /// Descriptions of failed assertions or run errors
+    pub failures: Vec<String>,
+    /// Wall-clock time of the run
+    pub duration: Duration,
+}
+
+impl TaskOutcome {
+    /// Whether every assertion passed and the run completed
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Results of a full suite run
+pub struct EvalReport {
+    pub suite_name: String,
+    pub variants: Vec<Variant>,
+    pub task_names: Vec<String>,
+    /// Outcomes indexed by [variant][task]
+    pub outcomes: Vec<Vec<TaskOutcome>>,
+}
+
+impl EvalReport {
+    /// Number of failed task runs across all variants
+    pub fn failed_runs(&self) -> usize {
+        self.outcomes
+            .iter()
+            .flatten()
+            .filter(|outcome| !outcome.is_success())
+            .count()
+    }
+
+    /// Render the comparison table and failure details
+    pub fn format_table(&self) -> String {
+        let mut columns: Vec<Vec<String>> = Vec::new();
+
+        let mut first = vec!["Task".to_string()];
+        first.extend(self.task_names.iter().cloned());
+        first.push("Total".to_string());
+        columns.push(first);
+
+        for (variant, outcomes) in self.variants.iter().zip(&self.outcomes) {
+            let mut column = vec![variant.label()];
+            for outcome in outcomes {
+                let mark = if outcome.is_success() { "✓" } else { "✗" };
+                column.push(format!(
+                    "{} {}/{} {:.1}s",
+                    mark,
+                    outcome.passed,
+                    outcome.total,
+                    outcome.duration.as_secs_f64()
+                ));
+            }
+            let succeeded = outcomes.iter().filter(|o| o.is_success()).count();
+            column.push(format!("{}/{} tasks", succeeded, outcomes.len()));
+            columns.push(column);
+        }
+
+        let widths: Vec<usize> = columns
+            .iter()
+            .map(|column| {
+                column
+                    .iter()
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut output = format!("Eval suite: {}\n\n", self.suite_name);
+        let rows = columns[0].len();
+        for row in 0..rows {
+            let cells: Vec<String> = columns
+                .iter()
+                .zip(&widths)
+                .map(|(column, width)| format!("{:<width$}", column[row], width = *width))
+                .collect();
+            output.push_str(cells.join(" | ").trim_end());
+            output.push('\n');
+
+            if row == 0 || row == rows - 2 {
+                let separator: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+                output.push_str(&separator.join("-+-"));
+                output.push('\n');
+            }
+        }
+
+        for (variant, outcomes) in self.variants.iter().zip(&self.outcomes) {
+            for (task, outcome) in self.task_names.iter().zip(outcomes) {
+                for failure in &outcome.failures {
+                    output.push_str(&format!("\n✗ [{}] {}: {}", variant.label(), task, failure));
+                }
+            }
+        }
+
+        output
+    }
+}
+
+/// Run every task of the suite against every model/kind variant
+pub async fn run_suite(suite: &EvalSuite, base_config: &Config) -> EvalReport {
+    let models = if suite.models.is_empty() {
+        vec![base_config.model.clone()]
+    } else {
+        suite.models.clone()
+    };
+    let kinds: Vec<Option<String>> = if suite.kinds.is_empty() {
+        vec![base_config.kind.clone()]
+    } else {
+        suite.kinds.iter().cloned().map(Some).collect()
+    };
+
+    let variants: Vec<Variant> = models
+        .iter()
+        .flat_map(|model| {
+            kinds.iter().map(move |kind| Variant {
+                model: model.clone(),
+                kind: kind.clone(),
+            })
+        })
+        .collect();
+
+    let mut outcomes = Vec::new();
+    for variant in &variants {
+        let mut variant_outcomes = Vec::new();
+        for task in &suite.tasks {
+            eprintln!("▶ [{}] {}", variant.label(), task.name);
+            let outcome = run_task(suite, task, variant, base_config).await;
+            eprintln!(
+                "  {} {}/{} assertions passed",
+                if outcome.is_success() { "✓" } else { "✗" },
+                outcome.passed,
+                outcome.total
+            );
+            variant_outcomes.push(outcome);
+        }
+        outcomes.push(variant_outcomes);
+    }
+
+    EvalReport {
+        suite_name: suite.name.clone(),
+        variants,
+        task_names: suite.tasks.iter().map(|task| task.name.clone()).collect(),
+        outcomes,
+    }
+}
+
+/// Run a single task in a copy of its working directory and score the result
+async fn run_task(
+    suite: &EvalSuite,
+    task: &EvalTask,
+    variant: &Variant,
+    base_config: &Config,
+) -> TaskOutcome {
+    let started = Instant::now();
+    let mut outcome = TaskOutcome {
+        passed: 0,
+        total: task.assertions.len(),
+        failures: Vec::new(),
+        duration: Duration::ZERO,
+    };
+
+    // Tools operate on the process working directory, so tasks run sequentially.
+    // Each run gets a fresh copy of the fixture, so runs do not see each other's
+    // changes and the fixture itself is never modified.
+    let original_dir = std::env::current_dir().ok();
+    let run_dir = task
+        .workdir
+        .as_ref()
+        .map(|_| std::env::temp_dir().join(format!("termineer-eval-{}", uuid::Uuid::new_v4())));
+    if let (Some(workdir), Some(run_dir)) = (&task.workdir, &run_dir) {
+        let fixture = suite.base_dir.join(workdir);
+        let prepared = if fixture.is_dir() {
+            copy_dir(&fixture, run_dir)
+        } else {
+            std::fs::create_dir_all(run_dir)
+        };
+        if let Err(e) = prepared.and_then(|_| std::env::set_current_dir(run_dir)) {
+            outcome.failures.push(format!(
+                "Failed to copy workdir '{}': {}",
+                fixture.display(),
+                e
+            ));
+            let _ = std::fs::remove_dir_all(run_dir);
+            outcome.duration = started.elapsed();
+            return outcome;
+        }
+    }
+
+    match run_setup(&task.setup) {
+        Ok(()) => match run_agent(task, variant, base_config, suite.timeout).await {
+            Ok(response) => {
+                let judge_model = suite.judge_model.as_deref().unwrap_or(&variant.model);
+                for assertion in &task.assertions {
+                    match check_assertion(assertion, &response, judge_model, base_config).await {
+                        Ok(()) => outcome.passed += 1,
+                        Err(reason) => {
+                            outcome
+                                .failures
+                                .push(format!("{} ({})", assertion.describe(), reason))
+                        }
+                    }
+                }
+            }
+            Err(e) => outcome.failures.push(format!("Agent run failed: {e}")),
+        },
+        Err(e) => outcome.failures.push(e),
+    }
+
+    if let Some(dir) = original_dir {
+        let _ = std::env::set_current_dir(dir);
+    }
+    if let Some(run_dir) = &run_dir {
+        let _ = std::fs::remove_dir_all(run_dir);
+    }
+
+    outcome.duration = started.elapsed();
+    outcome
+}
+
+/// Run the setup commands of a task, stopping at the first failure
+fn run_setup(commands: &[String]) -> Result<(), String> {
+    for command in commands {
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .status()
+            .map_err(|e| format!("Setup command '{command}' failed to start: {e}"))?;
+        if !status.success() {
+            return Err(format!("Setup command '{command}' failed with {status}"));
+        }
+    }
+    Ok(())
+}
+
+/// Create a fresh agent for the variant, run the task prompt and return the response
+async fn run_agent(
+    task: &EvalTask,
+    variant: &Variant,
+    base_config: &Config,
+    timeout: Option<u64>,
+) -> Result<String, String> {
+    let mut config = base_config.clone();
+    config.model = variant.model.clone();
+    config.kind = variant.kind.clone();
+    config.grammar_type = None;
+    config.apply_model_specific_grammar();
+
+    let agent_id =
+        agent::create_agent(format!("eval-{}", task.name), config).map_err(|e| e.to_string())?;
+    let result = agent::run_agent_to_completion(
+        agent_id,
+        task.prompt.clone(),
+        Some(timeout.unwrap_or(DEFAULT_TASK_TIMEOUT)),
+    )
+    .await
+    .map_err(|e| e.to_string());
+    let _ = agent::terminate_agent(agent_id).await;
+
+    result
+}
+
+/// Check a single assertion, returning the failure reason if it does not hold
+async fn check_assertion(
+    assertion: &Assertion,
+    response: &str,
+    judge_model: &str,
+    base_config: &Config,
+) -> Result<(), String> {
+    match assertion {
+        Assertion::ResponseMatches(pattern) => {
+            if compile(pattern)?.is_match(response) {
+                Ok(())
+            } else {
+                Err("no match in response".to_string())
+            }
+        }
+        Assertion::ResponseNotMatches(pattern) => {
+            if compile(pattern)?.is_match(response) {
+                Err("pattern found in response".to_string())
+            } else {
+                Ok(())
+            }
+        }
+        Assertion::FileExists(path) => {
+            if Path::new(path).exists() {
+                Ok(())
+            } else {
+                Err("file not found".to_string())
+            }
+        }
+        Assertion::FileMatches { path, pattern } => {
+            let content =
+                std::fs::read_to_string(path).map_err(|e| format!("cannot read file: {e}"))?;
+            if compile(pattern)?.is_match(&content) {
+                Ok(())
+            } else {
+                Err("no match in file".to_string())
+            }
+        }
+        Assertion::Judge(criteria) => judge(criteria, response, judge_model, base_config).await,
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| format!("invalid regex: {e}"))
+}
+
+/// Ask an LLM judge whether the response satisfies the criteria
+async fn judge(
+    criteria: &str,
+    response: &str,
+    judge_model: &str,
+    base_config: &Config,
+) -> Result<(), String> {
+    let mut config = base_config.clone();
+    config.model = judge_model.to_string();
+    let backend = create_backend(&config).map_err(|e| format!("judge unavailable: {e}"))?;
+
+    let prompt = format!(
+        "You are grading the output of an AI assistant.\n\n\
+         Criteria:\n{criteria}\n\n\
+         Assistant response:\n<response>\n{response}\n</response>\n\n\
+         Reply with PASS or FAIL on the first line, followed by a one-sentence reason."
+    );
+    let messages = vec![Message::text("user", prompt, MessageInfo::User)];
+
+    let verdict = backend
+        .send_message(
+            &messages,
+            None,
+            None,
+            None,
+            None,
+            Some(256),
+            config.sampling.for_task(SamplingTask::Check),
+        )
+        .await
+        .map_err(|e| format!("judge request failed: {e}"))?;
+
+    let text: String = verdict
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            Content::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let first_line = text.trim().lines().next().unwrap_or("").to_uppercase();
+    if first_line.starts_with("PASS") {
+        Ok(())
+    } else {
+        Err(format!("judge: {}", text.trim().replace('\n', " ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_table() {
+        let outcome = |failures: Vec<String>| TaskOutcome {
+            passed: 2 - failures.len(),
+            total: 2,
+            failures,
+            duration: Duration::from_millis(1500),
+        };
+        let report = EvalReport {
+            suite_name: "basics".to_string(),
+            variants: vec![Variant {
+                model: "mock:a.yaml".to_string(),
+                kind: Some("basic".to_string()),
+            }],
+            task_names: vec!["one".to_string(), "two".to_string()],
+            outcomes: vec![vec![
+                outcome(Vec::new()),
+                outcome(vec!["file exists: x (file not found)".to_string()]),
+            ]],
+        };
+
+        let table = report.format_table();
+        assert!(table.contains("mock:a.yaml (basic)"));
+        assert!(table.contains("✓ 2/2 1.5s"));
+        assert!(table.contains("1/2 tasks"));
+        assert!(table.contains("✗ [mock:a.yaml (basic)] two: file exists: x"));
+        assert_eq!(report.failed_runs(), 1);
+    }
+}
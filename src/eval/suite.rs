@@ -0,0 +1,158 @@
+//! Eval suite definition
+//!
+//! Suites are YAML files of the form:
+//!
+//! ```yaml
+//! name: basics
+//! models: [claude-3-7-sonnet-20250219, gpt-4o]
+//! kinds: [basic, minimal]
+//! judge_model: claude-3-7-sonnet-20250219
+//! tasks:
+//!   - name: count-files
+//!     workdir: fixtures/count
+//!     setup: ["touch a.txt b.txt"]
+//!     prompt: How many .txt files are in the current directory?
+//!     assertions:
+//!       - response_matches: "\\b2\\b"
+//!       - file_exists: a.txt
+//!       - file_matches: { path: a.txt, pattern: "^$" }
+//!       - judge: The answer states that there are two text files
+//! ```
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// A complete eval suite
+#[derive(Debug, Deserialize, Clone)]
+pub struct EvalSuite {
+    /// Name of the suite
+    pub name: String,
+
+    /// Models to compare (defaults to the model given on the command line)
+    #[serde(default)]
+    pub models: Vec<String>,
+
+    /// Agent kinds to compare (defaults to the default kind)
+    #[serde(default)]
+    pub kinds: Vec<String>,
+
+    /// Model used for `judge` assertions (defaults to the model being evaluated)
+    pub judge_model: Option<String>,
+
+    /// Timeout per task in seconds (default: 300)
+    pub timeout: Option<u64>,
+
+    /// Task fixtures
+    pub tasks: Vec<EvalTask>,
+
+    /// Directory containing the suite file, used to resolve task workdirs
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+}
+
+/// A single task fixture
+#[derive(Debug, Deserialize, Clone)]
+pub struct EvalTask {
+    /// Name of the task, shown in the results table
+    pub name: String,
+
+    /// Prompt sent to the agent
+    pub prompt: String,
+
+    /// Fixture directory relative to the suite file; each run works on a fresh
+    /// temporary copy of it
+    pub workdir: Option<String>,
+
+    /// Shell commands run in the workdir before the task
+    #[serde(default)]
+    pub setup: Vec<String>,
+
+    /// Assertions used to score the result
+    #[serde(default)]
+    pub assertions: Vec<Assertion>,
+}
+
+/// An assertion about the agent's result
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Assertion {
+    /// The final response matches a regular expression
+    ResponseMatches(String),
+
+    /// The final response does not match a regular expression
+    ResponseNotMatches(String),
+
+    /// A file exists after the task
+    FileExists(String),
+
+    /// A file's content matches a regular expression
+    FileMatches { path: String, pattern: String },
+
+    /// An LLM judge decides whether the response satisfies the criteria
+    Judge(String),
+}
+
+impl Assertion {
+    /// Short description for failure reports
+    pub fn describe(&self) -> String {
+        match self {
+            Assertion::ResponseMatches(pattern) => format!("response matches /{pattern}/"),
+            Assertion::ResponseNotMatches(pattern) => {
+                format!("response does not match /{pattern}/")
+            }
+            Assertion::FileExists(path) => format!("file exists: {path}"),
+            Assertion::FileMatches { path, pattern } => format!("{path} matches /{pattern}/"),
+            Assertion::Judge(criteria) => format!("judge: {criteria}"),
+        }
+    }
+}
+
+impl EvalSuite {
+    /// Load a suite from a YAML file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read eval suite '{}': {}", path.display(), e))?;
+        let mut suite: EvalSuite = serde_yaml::from_str(&content)
+            .map_err(|e| format!("Invalid eval suite '{}': {}", path.display(), e))?;
+
+        if suite.tasks.is_empty() {
+            return Err(format!("Eval suite '{}' contains no tasks", path.display()));
+        }
+
+        suite.base_dir = path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        Ok(suite)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_suite() {
+        let suite: EvalSuite = serde_yaml::from_str(
+            r#"
+name: basics
+models: [mock:a.yaml]
+tasks:
+  - name: hello
+    prompt: Say hello
+    assertions:
+      - response_matches: "(?i)hello"
+      - file_matches: { path: out.txt, pattern: "done" }
+      - judge: Greets the user
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(suite.models, vec!["mock:a.yaml".to_string()]);
+        assert!(suite.kinds.is_empty());
+        let assertions = &suite.tasks[0].assertions;
+        assert_eq!(assertions.len(), 3);
+        assert!(matches!(&assertions[1], Assertion::FileMatches { path, .. } if path == "out.txt"));
+        assert_eq!(assertions[2].describe(), "judge: Greets the user");
+    }
+}
@@ -0,0 +1,62 @@
+//! Running git and preparing its output for agents
+//!
+//! Checkpoints, isolated workspaces, hooks, reviews and changelogs all run git
+//! as a subprocess and hand diffs to models; they share these helpers.
+
+use std::path::Path;
+use tokio::process::Command;
+
+/// Run git in the current directory and return its output
+pub async fn git(args: &[&str]) -> Result<String, String> {
+    git_in(Path::new("."), args, None).await
+}
+
+/// Run git in a directory, optionally with a separate index file, and return its output
+pub async fn git_in(dir: &Path, args: &[&str], index: Option<&Path>) -> Result<String, String> {
+    let mut command = Command::new("git");
+    command.current_dir(dir).args(args);
+    if let Some(index) = index {
+        command.env("GIT_INDEX_FILE", index);
+    }
+    let output = command
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run git: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Cut a diff to at most `max_chars` bytes, noting how much was left out
+pub fn truncate_diff(diff: &str, max_chars: usize) -> String {
+    if diff.len() <= max_chars {
+        return diff.to_string();
+    }
+    let mut end = max_chars;
+    while !diff.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!(
+        "{}\n[diff truncated: {} more characters]",
+        &diff[..end],
+        diff.len() - end
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_diff() {
+        assert_eq!(truncate_diff("+a", 10), "+a");
+        let long = "é".repeat(10);
+        let truncated = truncate_diff(&long, 5);
+        assert_eq!(truncated, "éé\n[diff truncated: 16 more characters]");
+    }
+}
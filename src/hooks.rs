@@ -0,0 +1,417 @@
+//! Git hooks that run agents
+//!
+//! `termineer hooks install` writes git hooks calling `termineer hooks run`:
+//! - `prepare-commit-msg` drafts the message of `git commit` from the staged
+//!   changes with the `commit_writer` kind, for the author to edit
+//! - `pre-push` reviews the commits being pushed with the `pre_push_reviewer`
+//!   kind and stops the push when the review finds a blocking problem
+//!
+//! The `hooks` section of `.termineer/config.json` selects the hooks and how
+//! many seconds each may run; both are enabled by default:
+//!
+//! ```json
+//! { "hooks": { "pre-push": { "enabled": false }, "prepare-commit-msg": { "timeout": 30 } } }
+//! ```
+//!
+//! Hooks never stand in the way when the agent fails or runs out of time: the
+//! commit gets the usual empty message and the push goes ahead.
+
+use crate::agent;
+use crate::config::Config;
+use crate::git::{git, truncate_diff};
+use crate::output::SharedBuffer;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Project configuration file holding the `hooks` section
+const CONFIG_FILE: &str = ".termineer/config.json";
+
+/// Line identifying hooks written by termineer
+const MARKER: &str = "# Installed by `termineer hooks install`";
+
+/// Characters of diff given to an agent; the rest is left out
+const MAX_DIFF_CHARS: usize = 100_000;
+
+/// Last line of a review that stops the push
+const BLOCK_VERDICT: &str = "VERDICT: BLOCK";
+
+/// A git hook termineer can install
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    PrepareCommitMsg,
+    PrePush,
+}
+
+impl Hook {
+    pub const ALL: [Hook; 2] = [Hook::PrepareCommitMsg, Hook::PrePush];
+
+    /// Name of the hook file
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::PrepareCommitMsg => "prepare-commit-msg",
+            Hook::PrePush => "pre-push",
+        }
+    }
+
+    /// Agent kind that runs the hook
+    fn kind(self) -> &'static str {
+        match self {
+            Hook::PrepareCommitMsg => "commit_writer",
+            Hook::PrePush => "pre_push_reviewer",
+        }
+    }
+
+    /// Seconds the hook may run when the configuration sets no timeout
+    fn default_timeout(self) -> u64 {
+        match self {
+            Hook::PrepareCommitMsg => 60,
+            Hook::PrePush => 300,
+        }
+    }
+}
+
+/// Parse a hook name from the command line
+pub fn parse_hook(arg: &str) -> Result<Hook, String> {
+    Hook::ALL
+        .into_iter()
+        .find(|hook| hook.name() == arg)
+        .ok_or_else(|| format!("Unknown hook: {arg}. Valid options: prepare-commit-msg, pre-push"))
+}
+
+/// Settings of one hook
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HookSettings {
+    /// Whether `hooks install` installs the hook
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Seconds the agent may run
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for HookSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout: None,
+        }
+    }
+}
+
+/// The `hooks` section of the project configuration
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HooksConfig {
+    #[serde(default, rename = "prepare-commit-msg")]
+    pub prepare_commit_msg: HookSettings,
+    #[serde(default, rename = "pre-push")]
+    pub pre_push: HookSettings,
+}
+
+impl HooksConfig {
+    /// Settings of a hook
+    pub fn get(&self, hook: Hook) -> &HookSettings {
+        match hook {
+            Hook::PrepareCommitMsg => &self.prepare_commit_msg,
+            Hook::PrePush => &self.pre_push,
+        }
+    }
+
+    /// Seconds a hook may run
+    pub fn timeout(&self, hook: Hook) -> u64 {
+        self.get(hook).timeout.unwrap_or(hook.default_timeout())
+    }
+}
+
+/// Read the `hooks` section of `.termineer/config.json`, which other settings share
+pub fn load_config() -> Result<HooksConfig, String> {
+    #[derive(Deserialize)]
+    struct ProjectConfig {
+        #[serde(default)]
+        hooks: HooksConfig,
+    }
+
+    let Ok(content) = std::fs::read_to_string(CONFIG_FILE) else {
+        return Ok(HooksConfig::default());
+    };
+    serde_json::from_str::<ProjectConfig>(&content)
+        .map(|config| config.hooks)
+        .map_err(|e| format!("Invalid hooks in {CONFIG_FILE}: {e}"))
+}
+
+/// Directory of the repository's hooks, honoring `core.hooksPath`
+async fn hooks_dir() -> Result<PathBuf, String> {
+    let dir = git(&["rev-parse", "--git-path", "hooks"]).await?;
+    Ok(PathBuf::from(dir.trim()))
+}
+
+/// Script of a hook calling this executable
+fn script(hook: Hook) -> String {
+    let exe = std::env::current_exe()
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "termineer".to_string());
+    // Single-quoted for the shell, with quotes in the path escaped
+    let exe = format!("'{}'", exe.replace('\'', r"'\''"));
+    format!(
+        "#!/bin/sh\n{MARKER}; remove with `termineer hooks uninstall`\nexec {exe} hooks run {} \"$@\"\n",
+        hook.name()
+    )
+}
+
+/// Whether a hook file was written by termineer
+fn is_ours(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|content| content.contains(MARKER))
+}
+
+/// Install the enabled hooks and remove the disabled ones installed earlier;
+/// hooks of other tools are only replaced with `force`
+pub async fn install(force: bool) -> Result<Vec<String>, String> {
+    let config = load_config()?;
+    let dir = hooks_dir().await?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+
+    let mut report = Vec::new();
+    for hook in Hook::ALL {
+        let path = dir.join(hook.name());
+        let exists = path.exists();
+        if !config.get(hook).enabled {
+            if exists && is_ours(&path) {
+                std::fs::remove_file(&path)
+                    .map_err(|e| format!("Failed to remove {}: {e}", path.display()))?;
+                report.push(t!("cli.hooks_removed", path = path.display()));
+            }
+            continue;
+        }
+        if exists && !is_ours(&path) && !force {
+            report.push(t!("cli.hooks_foreign", path = path.display()));
+            continue;
+        }
+        std::fs::write(&path, script(hook))
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+        make_executable(&path)?;
+        report.push(t!("cli.hooks_installed", path = path.display()));
+    }
+    Ok(report)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+        .map_err(|e| format!("Failed to make {} executable: {e}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<(), String> {
+    // Git for Windows runs hooks through its own shell
+    Ok(())
+}
+
+/// Remove the hooks installed by termineer
+pub async fn uninstall() -> Result<Vec<String>, String> {
+    let dir = hooks_dir().await?;
+    let mut report = Vec::new();
+    for hook in Hook::ALL {
+        let path = dir.join(hook.name());
+        if is_ours(&path) {
+            std::fs::remove_file(&path)
+                .map_err(|e| format!("Failed to remove {}: {e}", path.display()))?;
+            report.push(t!("cli.hooks_removed", path = path.display()));
+        }
+    }
+    Ok(report)
+}
+
+/// Run a query in a new agent of the hook's kind
+async fn ask(config: &Config, hook: Hook, timeout: u64, query: String) -> Result<String, String> {
+    let mut config = config.clone();
+    config.kind = Some(hook.kind().to_string());
+    let agent_id =
+        agent::create_agent_with_buffer(hook.name().to_string(), config, SharedBuffer::new(200))
+            .map_err(|e| e.to_string())?;
+    let result = agent::run_agent_to_completion(agent_id, query, Some(timeout))
+        .await
+        .map_err(|e| e.to_string());
+    let _ = agent::terminate_agent(agent_id).await;
+    result
+}
+
+/// Commit message without the code fences agents sometimes add
+fn clean_message(message: &str) -> String {
+    let message = message.trim();
+    let message = message
+        .strip_prefix("```")
+        .and_then(|rest| rest.split_once('\n'))
+        .and_then(|(_, rest)| rest.trim_end().strip_suffix("```"))
+        .unwrap_or(message);
+    message.trim().to_string()
+}
+
+/// Whether a review ends with the verdict that stops the push
+fn blocks_push(review: &str) -> bool {
+    review
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .is_some_and(|line| line.trim().eq_ignore_ascii_case(BLOCK_VERDICT))
+}
+
+/// Run a hook with the arguments git gave it; Err stops the commit or push
+pub async fn run(config: Config, hook: Hook, args: &[String]) -> Result<(), String> {
+    match run_hook(&config, hook, args).await {
+        Ok(()) => Ok(()),
+        Err(HookError::Blocked) => Err(t!("cli.hooks_blocked")),
+        Err(HookError::Failed(e)) => {
+            eprintln!("{}", t!("cli.hooks_skipped", hook = hook.name(), error = e));
+            Ok(())
+        }
+    }
+}
+
+/// Run a hook, telling a blocked push apart from a hook that could not run
+async fn run_hook(config: &Config, hook: Hook, args: &[String]) -> Result<(), HookError> {
+    // Invalid settings skip the hook rather than stop git
+    let timeout = load_config()?.timeout(hook);
+    config.validate()?;
+    match hook {
+        Hook::PrepareCommitMsg => prepare_commit_msg(config, timeout, args).await,
+        Hook::PrePush => pre_push(config, timeout).await,
+    }
+}
+
+/// Why a hook did not let git continue as usual
+enum HookError {
+    /// The review found a blocking problem
+    Blocked,
+    /// The hook could not run; git continues
+    Failed(String),
+}
+
+impl From<String> for HookError {
+    fn from(error: String) -> Self {
+        HookError::Failed(error)
+    }
+}
+
+/// Write a message for `git commit` without a message of its own
+async fn prepare_commit_msg(
+    config: &Config,
+    timeout: u64,
+    args: &[String],
+) -> Result<(), HookError> {
+    let Some(file) = args.first() else {
+        return Err("git gave no message file".to_string().into());
+    };
+    // Messages from -m, -F, templates, merges, squashes and amends are kept
+    if args.get(1).is_some_and(|source| !source.is_empty()) {
+        return Ok(());
+    }
+    let diff = git(&["diff", "--cached", "--no-color"]).await?;
+    if diff.trim().is_empty() {
+        return Ok(());
+    }
+    let recent = git(&["log", "-n", "10", "--format=%s"])
+        .await
+        .unwrap_or_default();
+
+    eprintln!("{}", t!("cli.hooks_writing"));
+    let query = format!(
+        "Write the commit message for these staged changes.\n\n\
+         Recent commit subjects:\n{}\n\nStaged diff:\n```diff\n{}\n```",
+        recent.trim(),
+        truncate_diff(&diff, MAX_DIFF_CHARS)
+    );
+    let message = clean_message(&ask(config, Hook::PrepareCommitMsg, timeout, query).await?);
+    if message.is_empty() {
+        return Err("the agent wrote no message".to_string().into());
+    }
+
+    // Git's comments stay below the message
+    let existing = std::fs::read_to_string(file).unwrap_or_default();
+    std::fs::write(file, format!("{message}\n{existing}"))
+        .map_err(|e| format!("Failed to write {file}: {e}"))?;
+    Ok(())
+}
+
+/// Review the commits of a push, given by git on stdin
+async fn pre_push(config: &Config, timeout: u64) -> Result<(), HookError> {
+    use tokio::io::AsyncReadExt;
+
+    let mut refs = String::new();
+    tokio::io::stdin()
+        .read_to_string(&mut refs)
+        .await
+        .map_err(|e| format!("Failed to read the pushed refs: {e}"))?;
+
+    let mut diff = String::new();
+    for line in refs.lines() {
+        // <local ref> <local sha> <remote ref> <remote sha>
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [_, local, _, remote] = fields[..] else {
+            continue;
+        };
+        let deleted = local.chars().all(|c| c == '0');
+        if deleted {
+            continue;
+        }
+        let new_branch = remote.chars().all(|c| c == '0');
+        let changes = if new_branch {
+            // Commits of a new branch that no remote branch has yet
+            git(&["log", "-p", "--no-color", local, "--not", "--remotes"]).await?
+        } else {
+            git(&["diff", "--no-color", remote, local]).await?
+        };
+        diff.push_str(&changes);
+    }
+    if diff.trim().is_empty() {
+        return Ok(());
+    }
+
+    eprintln!("{}", t!("cli.hooks_reviewing"));
+    let query = format!(
+        "Review the changes being pushed.\n\n```diff\n{}\n```",
+        truncate_diff(&diff, MAX_DIFF_CHARS)
+    );
+    let review = ask(config, Hook::PrePush, timeout, query).await?;
+    eprintln!("{}", review.trim());
+    if blocks_push(&review) {
+        return Err(HookError::Blocked);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hooks_config() {
+        let config: HooksConfig =
+            serde_json::from_str(r#"{ "pre-push": { "enabled": false, "timeout": 120 } }"#)
+                .unwrap();
+        assert!(config.get(Hook::PrepareCommitMsg).enabled);
+        assert!(!config.get(Hook::PrePush).enabled);
+        assert_eq!(config.timeout(Hook::PrePush), 120);
+        assert_eq!(config.timeout(Hook::PrepareCommitMsg), 60);
+        assert_eq!(parse_hook("pre-push"), Ok(Hook::PrePush));
+    }
+
+    #[test]
+    fn test_agent_answers() {
+        assert_eq!(
+            clean_message("```text\nFix the parser\n\nBody\n```\n"),
+            "Fix the parser\n\nBody"
+        );
+        assert_eq!(clean_message(" Fix the parser \n"), "Fix the parser");
+
+        assert!(blocks_push("src/main.rs:3 - leaked key\nVERDICT: BLOCK\n"));
+        assert!(!blocks_push("VERDICT: PASS"));
+        assert!(!blocks_push("VERDICT: BLOCK if you like\nVERDICT: PASS"));
+    }
+}
@@ -0,0 +1,136 @@
+//! Localized text of the command line and the terminal UI
+//!
+//! User-facing text of the CLI and the TUI is looked up by key in the bundle
+//! of the user's language, chosen with `--language` or else from the locale
+//! (`LC_ALL`, `LC_MESSAGES`, `LANG`). Bundles are the YAML files next to this
+//! module; keys missing from a bundle fall back to English. Prompts and
+//! everything else sent to models stay in English.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+
+/// Languages with a bundle; English is the fallback
+pub const LANGUAGES: [&str; 3] = ["en", "de", "ja"];
+
+/// Built-in bundles of the languages
+const BUNDLES: [(&str, &str); 3] = [
+    ("en", include_str!("en.yaml")),
+    ("de", include_str!("de.yaml")),
+    ("ja", include_str!("ja.yaml")),
+];
+
+lazy_static! {
+    static ref TEXTS: HashMap<&'static str, HashMap<String, String>> = BUNDLES
+        .iter()
+        .map(|(language, yaml)| {
+            let texts = serde_yaml::from_str(yaml).expect("the built-in bundles are valid");
+            (*language, texts)
+        })
+        .collect();
+    static ref LANGUAGE: RwLock<&'static str> = RwLock::new(detect());
+}
+
+/// Parse a `--language` value into one of [`LANGUAGES`]
+pub fn parse_language(value: &str) -> Result<String, String> {
+    language_of(value).map(str::to_string).ok_or_else(|| {
+        format!(
+            "unsupported language '{value}'; use one of {}",
+            LANGUAGES.join(", ")
+        )
+    })
+}
+
+/// Use `language` for all text from now on
+pub fn set_language(language: &str) {
+    if let Some(language) = language_of(language) {
+        *LANGUAGE.write().unwrap() = language;
+    }
+}
+
+/// Language of the locale, or English if it has no bundle
+pub fn detect() -> &'static str {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|locale| !locale.is_empty())
+        .and_then(|locale| language_of(&locale))
+        .unwrap_or("en")
+}
+
+/// Language with a bundle named by a locale such as `de_DE.UTF-8` or `ja`
+fn language_of(locale: &str) -> Option<&'static str> {
+    let code = locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    LANGUAGES.into_iter().find(|language| *language == code)
+}
+
+/// Text of `key` in the current language, with `{name}` replaced by the
+/// argument of that name; use [`t!`](crate::t) instead of calling this
+pub fn text(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let language = *LANGUAGE.read().unwrap();
+    text_in(language, key, args)
+}
+
+fn text_in(language: &str, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let lookup = |language: &str| TEXTS.get(language).and_then(|texts| texts.get(key));
+    let Some(template) = lookup(language).or_else(|| lookup("en")) else {
+        return key.to_string();
+    };
+    let mut text = template.clone();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Names of the `{name}` arguments of a text
+    fn arguments(text: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = text
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn test_bundles_match_english() {
+        let english = &TEXTS["en"];
+        for language in LANGUAGES {
+            let texts = &TEXTS[language];
+            for (key, text) in english {
+                let translated = texts
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{language} lacks {key}"));
+                assert_eq!(arguments(text), arguments(translated), "{language}: {key}");
+            }
+            assert_eq!(texts.len(), english.len(), "{language} has extra keys");
+        }
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(language_of("de_DE.UTF-8"), Some("de"));
+        assert_eq!(language_of("ja"), Some("ja"));
+        assert_eq!(language_of("C"), None);
+        assert!(parse_language("fr").is_err());
+
+        let path = "out.txt";
+        assert_eq!(
+            text_in("de", "tui.transcript_saved", &[("path", &path)]),
+            "Protokoll in out.txt gespeichert"
+        );
+        assert_eq!(text_in("ja", "no.such.key", &[]), "no.such.key");
+    }
+}
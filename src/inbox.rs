@@ -0,0 +1,183 @@
+//! File-based inbox of an interactive session
+//!
+//! With `--inbox [AGENT]`, the session watches `.termineer/inbox/` for
+//! Markdown files dropped there by scripts, git hooks or editors. Each file is
+//! consumed in name order as a user message to the agent (`main` by default)
+//! once the agent is waiting for input, and the agent's output for it is
+//! written to a file of the same name in `.termineer/outbox/`. Results are
+//! written to a temporary file first and then renamed, so a complete file
+//! appears at once.
+
+use crate::agent::{self, AgentMessage, AgentState};
+use crate::ansi_converter::strip_ansi_sequences;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Directory of messages, relative to the workspace
+pub const INBOX_DIR: &str = ".termineer/inbox";
+
+/// Directory of results, relative to the workspace
+pub const OUTBOX_DIR: &str = ".termineer/outbox";
+
+/// How often the inbox is checked for new files
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Files modified more recently are assumed to be still being written
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Markdown files of the inbox that are ready to be consumed, in name order
+pub fn pending_files(inbox: &Path, now: SystemTime) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(inbox) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let settled = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| {
+                    now.duration_since(modified).unwrap_or_default() >= SETTLE_TIME
+                });
+            settled && entry.file_type().is_ok_and(|kind| kind.is_file())
+        })
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Consume the inbox of the workspace, sending its files to the agent named `agent_name`
+pub async fn watch(agent_name: String) {
+    let inbox = PathBuf::from(INBOX_DIR);
+    let outbox = PathBuf::from(OUTBOX_DIR);
+    if let Err(e) = std::fs::create_dir_all(&inbox).and_then(|_| std::fs::create_dir_all(&outbox)) {
+        bprintln!(error: "Failed to create the inbox: {}", e);
+        return;
+    }
+    bprintln!(info: "📥 Watching {} for messages to agent '{}'", INBOX_DIR, agent_name);
+
+    loop {
+        for path in pending_files(&inbox, SystemTime::now()) {
+            // Files wait until the agent exists, e.g. after /fork
+            let Some(agent_id) = agent::get_agent_id_by_name(&agent_name) else {
+                break;
+            };
+            let message = match std::fs::read_to_string(&path) {
+                Ok(message) => message,
+                Err(e) => {
+                    bprintln!(error: "Failed to read {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            if let Err(e) = std::fs::remove_file(&path) {
+                bprintln!(error: "Failed to consume {}: {}", path.display(), e);
+                continue;
+            }
+
+            let name = path.file_name().unwrap_or_default();
+            bprintln!(info: "📥 {} sent to agent '{}'", name.to_string_lossy(), agent_name);
+            let output = match run_message(agent_id, message).await {
+                Ok(output) => output,
+                Err(output) => {
+                    let name = name.to_string_lossy();
+                    bprintln!(warn: "Agent '{}' stopped before answering {}", agent_name, name);
+                    output
+                }
+            };
+            let result = outbox.join(name);
+            if let Err(e) = write_result(&result, &output) {
+                bprintln!(error: "Failed to write {}: {}", result.display(), e);
+            }
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Send a message to the agent once it waits for input, and return its output
+/// until it waits again; the output so far is the error if the agent ends
+async fn run_message(agent_id: agent::AgentId, message: String) -> Result<String, String> {
+    let (Ok(mut state), Ok(buffer)) = (
+        agent::subscribe_agent_state(agent_id),
+        agent::get_agent_buffer(agent_id),
+    ) else {
+        return Err(String::new());
+    };
+
+    // Messages sent while the agent works would interrupt it
+    loop {
+        match &*state.borrow_and_update() {
+            AgentState::Idle | AgentState::Done(_) => break,
+            AgentState::Terminated => return Err(String::new()),
+            _ => {}
+        }
+        if state.changed().await.is_err() {
+            return Err(String::new());
+        }
+    }
+
+    let mut reader = buffer.reader(0);
+    let mut output = String::new();
+    if agent::send_message(agent_id, AgentMessage::UserInput(message)).is_err() {
+        return Err(output);
+    }
+    loop {
+        tokio::select! {
+            lines = reader.next_lines() => {
+                for line in lines {
+                    output.push_str(&strip_ansi_sequences(&line.content));
+                    output.push('\n');
+                }
+            }
+            changed = state.changed() => {
+                if changed.is_err() {
+                    return Err(output);
+                }
+                let finished = match &*state.borrow_and_update() {
+                    AgentState::Idle | AgentState::Done(_) => true,
+                    AgentState::Terminated => return Err(output),
+                    _ => false,
+                };
+                if finished {
+                    for line in reader.pending() {
+                        output.push_str(&strip_ansi_sequences(&line.content));
+                        output.push('\n');
+                    }
+                    return Ok(output);
+                }
+            }
+        }
+    }
+}
+
+/// Write a result so that it appears complete at once
+fn write_result(path: &Path, output: &str) -> std::io::Result<()> {
+    let partial = path.with_extension("md.partial");
+    std::fs::write(&partial, output)?;
+    std::fs::rename(&partial, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_files() {
+        let dir = std::env::temp_dir().join(format!("termineer-inbox-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("nested.md")).unwrap();
+        for name in ["b.md", "a.md", "notes.txt"] {
+            std::fs::write(dir.join(name), "review the last commit").unwrap();
+        }
+
+        let later = SystemTime::now() + SETTLE_TIME;
+        assert_eq!(
+            pending_files(&dir, later),
+            vec![dir.join("a.md"), dir.join("b.md")]
+        );
+        // Files still being written are left for later
+        assert!(pending_files(&dir, SystemTime::now() - SETTLE_TIME).is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
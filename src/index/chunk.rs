@@ -0,0 +1,159 @@
+//! Selecting workspace files and splitting them into chunks for embedding
+
+use std::path::Path;
+
+/// Lines per chunk
+const CHUNK_LINES: usize = 60;
+
+/// Lines shared by consecutive chunks, so code at a boundary is found in both
+const OVERLAP_LINES: usize = 10;
+
+/// Characters of a chunk sent for embedding
+const MAX_CHUNK_CHARS: usize = 4000;
+
+/// Files larger than this are not indexed (generated code, data, bundles)
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+
+/// Directories skipped outside git repositories
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules", "dist", "build", "vendor"];
+
+/// Lock files are large and never what a search is looking for
+const SKIPPED_FILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "poetry.lock",
+    "go.sum",
+];
+
+/// A range of lines of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    /// First line (1-based)
+    pub start_line: usize,
+    /// Last line (inclusive)
+    pub end_line: usize,
+    pub text: String,
+}
+
+/// Relative paths of the files to index: tracked and untracked files that are
+/// not ignored in git repositories, otherwise every file outside hidden and
+/// build directories
+pub fn workspace_files(root: &Path) -> Vec<String> {
+    let output = std::process::Command::new("git")
+        .args([
+            "ls-files",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+            "-z",
+        ])
+        .current_dir(root)
+        .output();
+    let mut files = match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .split('\0')
+            .filter(|path| !path.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => {
+            let mut files = Vec::new();
+            walk(root, root, &mut files);
+            files
+        }
+    };
+    files.retain(|path| is_indexable(root, path));
+    files.sort();
+    files.dedup();
+    files
+}
+
+fn walk(root: &Path, dir: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => {
+                if !SKIPPED_DIRS.contains(&name.as_str()) {
+                    walk(root, &path, files);
+                }
+            }
+            Ok(file_type) if file_type.is_file() => {
+                if let Ok(relative) = path.strip_prefix(root) {
+                    files.push(relative.to_string_lossy().to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether a file is small, textual and not session state or a lock file
+fn is_indexable(root: &Path, path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    if path.starts_with(".termineer/") || SKIPPED_FILES.contains(&name) {
+        return false;
+    }
+    std::fs::metadata(root.join(path))
+        .map(|metadata| metadata.is_file() && metadata.len() <= MAX_FILE_BYTES)
+        .unwrap_or(false)
+}
+
+/// Split a file into overlapping chunks of lines
+pub fn chunk_text(text: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let end = (start + CHUNK_LINES).min(lines.len());
+        let text = lines[start..end].join("\n");
+        if !text.trim().is_empty() {
+            chunks.push(Chunk {
+                start_line: start + 1,
+                end_line: end,
+                text,
+            });
+        }
+        if end == lines.len() {
+            break;
+        }
+        start = end - OVERLAP_LINES;
+    }
+    chunks
+}
+
+/// Text sent for embedding: the path gives the model context the code lacks
+pub fn embedding_text(path: &str, chunk: &Chunk) -> String {
+    let mut text = format!("{path}\n{}", chunk.text);
+    if text.len() > MAX_CHUNK_CHARS {
+        let mut end = MAX_CHUNK_CHARS;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_text() {
+        let text: String = (1..=130).map(|n| format!("line {n}\n")).collect();
+        let ranges: Vec<(usize, usize)> = chunk_text(&text)
+            .iter()
+            .map(|chunk| (chunk.start_line, chunk.end_line))
+            .collect();
+        assert_eq!(ranges, vec![(1, 60), (51, 110), (101, 130)]);
+        assert!(chunk_text("\n  \n").is_empty());
+    }
+}
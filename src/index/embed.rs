@@ -0,0 +1,222 @@
+//! Embeddings from provider APIs (OpenAI, Google, Cohere)
+
+use crate::tools::progress;
+use serde_json::{json, Value};
+
+/// Models used when only a provider is given, or when the model is detected
+const DEFAULT_MODELS: &[(&str, &str)] = &[
+    ("openai", "text-embedding-3-small"),
+    ("google", "text-embedding-004"),
+    ("cohere", "embed-english-v3.0"),
+];
+
+/// Texts sent in one request
+const BATCH_SIZE: usize = 96;
+
+/// An embedding model of a provider
+pub struct Embedder {
+    provider: &'static str,
+    model: String,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+impl Embedder {
+    /// Embedder for `PROVIDER[/MODEL]`, or for the first provider with an API key
+    pub fn new(spec: Option<&str>) -> Result<Self, String> {
+        let (provider, model) = match spec {
+            Some(spec) => {
+                let (provider, model) = spec.split_once('/').unwrap_or((spec, ""));
+                let (provider, default_model) = DEFAULT_MODELS
+                    .iter()
+                    .find(|(name, _)| *name == provider)
+                    .ok_or_else(|| {
+                        format!(
+                            "Unsupported embedding provider '{provider}'; use openai, google or cohere"
+                        )
+                    })?;
+                let model = if model.is_empty() {
+                    *default_model
+                } else {
+                    model
+                };
+                (*provider, model.to_string())
+            }
+            None => DEFAULT_MODELS
+                .iter()
+                .find(|(provider, _)| crate::credentials::find_api_keys(provider).is_some())
+                .map(|(provider, model)| (*provider, model.to_string()))
+                .ok_or_else(|| {
+                    "Semantic search needs an OpenAI, Google or Cohere API key for embeddings"
+                        .to_string()
+                })?,
+        };
+
+        Ok(Self {
+            provider,
+            api_key: crate::credentials::api_key(provider)?,
+            model,
+            client: crate::net::client(),
+        })
+    }
+
+    /// `provider/model`, stored with the index so a model change triggers a rebuild
+    pub fn id(&self) -> String {
+        format!("{}/{}", self.provider, self.model)
+    }
+
+    /// Embed texts as documents (`query` false) or search queries
+    pub async fn embed(&self, texts: &[String], query: bool) -> Result<Vec<Vec<f32>>, String> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(BATCH_SIZE) {
+            let embedded = match self.provider {
+                "openai" => self.embed_openai(batch).await?,
+                "google" => self.embed_google(batch, query).await?,
+                _ => self.embed_cohere(batch, query).await?,
+            };
+            if embedded.len() != batch.len() {
+                return Err(format!(
+                    "The embedding API returned {} vector(s) for {} text(s)",
+                    embedded.len(),
+                    batch.len()
+                ));
+            }
+            vectors.extend(embedded.into_iter().map(normalize));
+            if texts.len() > BATCH_SIZE {
+                progress::report_count(vectors.len(), texts.len(), "chunks embedded");
+            }
+        }
+        Ok(vectors)
+    }
+
+    async fn embed_openai(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let response = self
+            .post(
+                self.client
+                    .post("https://api.openai.com/v1/embeddings")
+                    .bearer_auth(&self.api_key),
+                json!({ "model": self.model, "input": texts }),
+            )
+            .await?;
+        let mut data: Vec<&Value> = response
+            .get("data")
+            .and_then(|d| d.as_array())
+            .map(|d| d.iter().collect())
+            .unwrap_or_default();
+        data.sort_by_key(|item| item.get("index").and_then(|i| i.as_u64()).unwrap_or(0));
+        Ok(data
+            .into_iter()
+            .filter_map(|item| floats(item.get("embedding")?))
+            .collect())
+    }
+
+    async fn embed_google(&self, texts: &[String], query: bool) -> Result<Vec<Vec<f32>>, String> {
+        let model = format!("models/{}", self.model);
+        let task_type = if query {
+            "RETRIEVAL_QUERY"
+        } else {
+            "RETRIEVAL_DOCUMENT"
+        };
+        let requests: Vec<Value> = texts
+            .iter()
+            .map(|text| {
+                json!({
+                    "model": model,
+                    "content": { "parts": [{ "text": text }] },
+                    "taskType": task_type,
+                })
+            })
+            .collect();
+        let url =
+            format!("https://generativelanguage.googleapis.com/v1beta/{model}:batchEmbedContents");
+        let response = self
+            .post(
+                self.client
+                    .post(url)
+                    .header("x-goog-api-key", &self.api_key),
+                json!({ "requests": requests }),
+            )
+            .await?;
+        Ok(response
+            .get("embeddings")
+            .and_then(|e| e.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|embedding| floats(embedding.get("values")?))
+            .collect())
+    }
+
+    async fn embed_cohere(&self, texts: &[String], query: bool) -> Result<Vec<Vec<f32>>, String> {
+        let input_type = if query {
+            "search_query"
+        } else {
+            "search_document"
+        };
+        let response = self
+            .post(
+                self.client
+                    .post("https://api.cohere.com/v1/embed")
+                    .bearer_auth(&self.api_key),
+                json!({
+                    "model": self.model,
+                    "texts": texts,
+                    "input_type": input_type,
+                    "embedding_types": ["float"],
+                }),
+            )
+            .await?;
+        Ok(response
+            .pointer("/embeddings/float")
+            .and_then(|e| e.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(floats)
+            .collect())
+    }
+
+    /// Send a JSON request and return the JSON response
+    async fn post(&self, request: reqwest::RequestBuilder, body: Value) -> Result<Value, String> {
+        let response = request
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Embedding request failed: {e}"))?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read the embedding response: {e}"))?;
+        if !status.is_success() {
+            return Err(format!("Embedding API error ({status}): {text}"));
+        }
+        serde_json::from_str(&text).map_err(|e| format!("Invalid embedding response: {e}"))
+    }
+}
+
+fn floats(value: &Value) -> Option<Vec<f32>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|v| v.as_f64().map(|f| f as f32))
+        .collect()
+}
+
+/// Scale a vector to unit length, so cosine similarity is a dot product
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize(vec![3.0, 4.0]), vec![0.6, 0.8]);
+        assert_eq!(normalize(vec![0.0, 0.0]), vec![0.0, 0.0]);
+    }
+}
@@ -0,0 +1,470 @@
+//! Knowledge bases of external documentation
+//!
+//! `termineer kb add <path|url>` extracts the text of documents (markdown and
+//! text files, HTML, PDF and DOCX, or pages of a documentation site), stores it
+//! in `.termineer/kb/<name>/docs/` and embeds it like workspace files. The `kb`
+//! tool searches the stored text, so agents can consult the documentation
+//! without fetching it again.
+
+use super::chunk::{chunk_text, embedding_text};
+use super::store::{content_hash, Index, IndexedChunk, IndexedFile};
+use crate::tools::documents::DocumentKind;
+use crate::tools::progress;
+use scraper::{Html, Selector};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Directory of the knowledge bases, relative to the workspace
+pub const KB_DIR: &str = ".termineer/kb";
+
+/// Subdirectory holding the extracted text of each document
+const DOCS_DIR: &str = "docs";
+
+/// Maps document files to the path or URL they were extracted from
+const SOURCES_FILE: &str = "sources.json";
+
+/// Extensions of the files picked up when a directory is added
+const DOC_EXTENSIONS: &[&str] = &[
+    "md", "markdown", "mdx", "txt", "rst", "adoc", "html", "htm", "pdf", "docx",
+];
+
+/// Outcome of adding a source
+#[derive(Debug)]
+pub struct AddStats {
+    pub documents: usize,
+    pub chunks: usize,
+    /// Documents that could not be read or fetched, with the reason
+    pub skipped: Vec<String>,
+}
+
+/// Summary of a knowledge base
+#[derive(Debug)]
+pub struct KbInfo {
+    pub name: String,
+    pub documents: usize,
+    pub chunks: usize,
+    pub model: String,
+}
+
+/// A passage matching a knowledge base search
+#[derive(Debug, Clone)]
+pub struct KbHit {
+    pub kb: String,
+    /// Path or URL of the document
+    pub source: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub score: f32,
+    pub text: String,
+}
+
+fn kb_dir(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid knowledge base name '{name}': use letters, digits, '-' and '_'"
+        ));
+    }
+    Ok(super::root()?.join(KB_DIR).join(name))
+}
+
+fn load_sources(dir: &Path) -> BTreeMap<String, String> {
+    std::fs::read_to_string(dir.join(SOURCES_FILE))
+        .ok()
+        .and_then(|sources| serde_json::from_str(&sources).ok())
+        .unwrap_or_default()
+}
+
+/// Names of the knowledge bases of the workspace
+pub fn names() -> Vec<String> {
+    let Ok(root) = super::root() else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = std::fs::read_dir(root.join(KB_DIR))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().join(SOURCES_FILE).is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Summaries of the knowledge bases of the workspace
+pub fn list() -> Vec<KbInfo> {
+    names()
+        .into_iter()
+        .filter_map(|name| {
+            let index = Index::load(&kb_dir(&name).ok()?).ok().flatten()?;
+            Some(KbInfo {
+                documents: index.files.len(),
+                chunks: index.chunk_count(),
+                model: index.model,
+                name,
+            })
+        })
+        .collect()
+}
+
+/// Delete a knowledge base, returning whether it existed
+pub fn remove(name: &str) -> Result<bool, String> {
+    let dir = kb_dir(name)?;
+    if !dir.exists() {
+        return Ok(false);
+    }
+    std::fs::remove_dir_all(&dir)
+        .map(|_| true)
+        .map_err(|e| format!("Failed to remove {}: {e}", dir.display()))
+}
+
+/// Extract, store and embed the documents of a path or URL
+///
+/// Documents added earlier from the same path or URL are replaced. Sites are
+/// crawled up to `max_pages` pages below the URL's directory.
+pub async fn add(name: &str, source: &str, max_pages: usize) -> Result<AddStats, String> {
+    let dir = kb_dir(name)?;
+    let mut skipped = Vec::new();
+    let (source, documents) = if source.starts_with("http://") || source.starts_with("https://") {
+        let documents = fetch_site(source, max_pages.max(1), &mut skipped).await?;
+        (source.to_string(), documents)
+    } else {
+        let path = Path::new(source)
+            .canonicalize()
+            .map_err(|e| format!("Cannot read {source}: {e}"))?;
+        let documents = read_path(&path, &mut skipped);
+        (path.to_string_lossy().to_string(), documents)
+    };
+    let source = source.as_str();
+    if documents.is_empty() {
+        return Err(format!("No documents found in {source}"));
+    }
+
+    let embedder = super::embedder()?;
+    let model = embedder.id();
+    let docs_dir = dir.join(DOCS_DIR);
+    std::fs::create_dir_all(&docs_dir)
+        .map_err(|e| format!("Failed to create {}: {e}", docs_dir.display()))?;
+
+    let mut sources = load_sources(&dir);
+    let mut index = Index::load(&dir)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| Index::new(model.clone()));
+
+    // Vectors of another model can't be compared with new ones: embed every
+    // stored document again
+    let mut pending: Vec<(String, String)> = Vec::new();
+    if index.model != model {
+        for file in index.files.keys() {
+            if let Ok(text) = std::fs::read_to_string(docs_dir.join(file)) {
+                pending.push((file.clone(), text));
+            }
+        }
+        index = Index::new(model.clone());
+    }
+
+    // Replace the documents previously added from the same source
+    let replaced: Vec<String> = sources
+        .iter()
+        .filter(|(_, origin)| is_within(origin, source))
+        .map(|(file, _)| file.clone())
+        .collect();
+    for file in replaced {
+        sources.remove(&file);
+        index.files.remove(&file);
+        pending.retain(|(pending_file, _)| *pending_file != file);
+        let _ = std::fs::remove_file(docs_dir.join(&file));
+    }
+
+    let added = documents.len();
+    for (origin, text) in documents {
+        let file = format!("{:016x}.md", content_hash(&origin));
+        std::fs::write(docs_dir.join(&file), &text)
+            .map_err(|e| format!("Failed to store {origin}: {e}"))?;
+        sources.insert(file.clone(), origin);
+        pending.push((file, text));
+    }
+
+    let chunked: Vec<(String, u64, Vec<super::chunk::Chunk>)> = pending
+        .into_iter()
+        .map(|(file, text)| (file, content_hash(&text), chunk_text(&text)))
+        .collect();
+    let texts: Vec<String> = chunked
+        .iter()
+        .flat_map(|(file, _, chunks)| {
+            let origin = sources.get(file).map_or(file.as_str(), String::as_str);
+            chunks
+                .iter()
+                .map(move |chunk| embedding_text(origin, chunk))
+        })
+        .collect();
+    let mut vectors = embedder.embed(&texts, false).await?.into_iter();
+    for (file, hash, chunks) in chunked {
+        let chunks = chunks
+            .into_iter()
+            .map(|chunk| IndexedChunk {
+                start_line: chunk.start_line,
+                end_line: chunk.end_line,
+                vector: vectors.next().unwrap_or_default(),
+            })
+            .collect();
+        index.files.insert(file, IndexedFile { hash, chunks });
+    }
+
+    index.save(&dir)?;
+    let sources_json = serde_json::to_string_pretty(&sources)
+        .map_err(|e| format!("Failed to serialize the sources: {e}"))?;
+    std::fs::write(dir.join(SOURCES_FILE), sources_json)
+        .map_err(|e| format!("Failed to write the sources: {e}"))?;
+
+    Ok(AddStats {
+        documents: added,
+        chunks: index.chunk_count(),
+        skipped,
+    })
+}
+
+/// Search one knowledge base, or all of them
+pub async fn search(name: Option<&str>, query: &str, limit: usize) -> Result<Vec<KbHit>, String> {
+    let names = match name {
+        Some(name) => vec![name.to_string()],
+        None => names(),
+    };
+    if names.is_empty() {
+        return Err("No knowledge bases: add one with `termineer kb add <path|url>`".to_string());
+    }
+
+    let model = super::embedder()?.id();
+    let query_vector = super::embed_query(query).await?;
+    let mut hits = Vec::new();
+    for name in names {
+        let dir = kb_dir(&name)?;
+        let index =
+            Index::load(&dir)?.ok_or_else(|| format!("Knowledge base '{name}' does not exist"))?;
+        if index.model != model {
+            return Err(format!(
+                "Knowledge base '{name}' was embedded with {}; add its documents again to use {model}",
+                index.model
+            ));
+        }
+        let sources = load_sources(&dir);
+        for (file, chunk, score) in index.nearest(&query_vector, limit) {
+            hits.push(KbHit {
+                kb: name.clone(),
+                source: sources
+                    .get(file)
+                    .cloned()
+                    .unwrap_or_else(|| file.to_string()),
+                start_line: chunk.start_line,
+                end_line: chunk.end_line,
+                score,
+                text: super::read_lines(
+                    &dir.join(DOCS_DIR).join(file),
+                    chunk.start_line,
+                    chunk.end_line,
+                ),
+            });
+        }
+    }
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+/// Whether a document came from a source: the same path or URL, or one below it
+fn is_within(origin: &str, source: &str) -> bool {
+    let source = source.trim_end_matches('/');
+    origin == source
+        || origin
+            .strip_prefix(source)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Text of a document from its content type, falling back to its extension
+fn document_text(content_type: &str, location: &str, bytes: Vec<u8>) -> Result<String, String> {
+    if let Some(kind) = DocumentKind::detect(content_type, location) {
+        return kind.extract_text(&bytes);
+    }
+    let text = String::from_utf8(bytes).map_err(|_| "not a text document".to_string())?;
+    let extension = location.rsplit('.').next().unwrap_or("").to_lowercase();
+    if content_type.contains("html") || extension == "html" || extension == "htm" {
+        Ok(crate::tools::html::html_to_markdown(
+            &text,
+            Some(location),
+            false,
+        ))
+    } else {
+        Ok(text)
+    }
+}
+
+/// Documents of a file, or of the documentation files in a directory
+fn read_path(path: &Path, skipped: &mut Vec<String>) -> Vec<(String, String)> {
+    let files = if path.is_dir() {
+        let mut files = Vec::new();
+        collect_files(path, &mut files);
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut documents = Vec::new();
+    for file in files {
+        let location = file.to_string_lossy().to_string();
+        let text = std::fs::read(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| document_text("", &location, bytes));
+        match text {
+            Ok(text) if !text.trim().is_empty() => documents.push((location, text)),
+            Ok(_) => {}
+            Err(e) => skipped.push(format!("{location}: {e}")),
+        }
+    }
+    documents
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| DOC_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// Download a page or document and return its text and, for HTML, its links
+async fn fetch_document(client: &reqwest::Client, url: &Url) -> Result<(String, Vec<Url>), String> {
+    let response = client
+        .get(url.as_str())
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {e}"))?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP status {}", response.status()));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("text/html")
+        .to_string();
+    let body = crate::tools::network::read_body(response).await?;
+
+    let links = if content_type.contains("html") {
+        page_links(&String::from_utf8_lossy(&body), url)
+    } else {
+        Vec::new()
+    };
+    let text = document_text(&content_type, url.as_str(), body)?;
+    Ok((text, links))
+}
+
+/// Crawl a site breadth-first, staying below the directory of the start URL
+async fn fetch_site(
+    start: &str,
+    max_pages: usize,
+    skipped: &mut Vec<String>,
+) -> Result<Vec<(String, String)>, String> {
+    let start = Url::parse(start).map_err(|e| format!("Invalid URL '{start}': {e}"))?;
+    // Up to and including the last '/' of the path
+    let scope = match start.as_str().rfind('/') {
+        Some(end) => start.as_str()[..=end].to_string(),
+        None => start.to_string(),
+    };
+    let client = crate::net::client();
+
+    let mut queue = VecDeque::from([start.clone()]);
+    let mut seen = HashSet::from([start.to_string()]);
+    let mut documents = Vec::new();
+    while let Some(url) = queue.pop_front() {
+        if documents.len() >= max_pages {
+            break;
+        }
+        match fetch_document(&client, &url).await {
+            Ok((text, links)) => {
+                for link in links {
+                    if link.as_str().starts_with(&scope) && seen.insert(link.to_string()) {
+                        queue.push_back(link);
+                    }
+                }
+                if !text.trim().is_empty() {
+                    documents.push((url.to_string(), text));
+                }
+                progress::report(
+                    None,
+                    format!("{} pages fetched, {} queued", documents.len(), queue.len()),
+                );
+            }
+            // The start page must be readable, later pages are best effort
+            Err(e) if url == start => return Err(format!("Failed to fetch {url}: {e}")),
+            Err(e) => skipped.push(format!("{url}: {e}")),
+        }
+    }
+    Ok(documents)
+}
+
+/// Absolute http(s) links of a page, without fragments
+fn page_links(html: &str, base: &Url) -> Vec<Url> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("a[href]").unwrap();
+    document
+        .select(&selector)
+        .filter_map(|link| base.join(link.value().attr("href")?).ok())
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .map(|mut url| {
+            url.set_fragment(None);
+            url
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_links() {
+        let base = Url::parse("https://docs.example.com/guide/intro.html").unwrap();
+        let html = r#"<a href="setup.html#install">Setup</a>
+            <a href="/api/">API</a> <a href="mailto:team@example.com">Mail</a>"#;
+        let links: Vec<String> = page_links(html, &base)
+            .iter()
+            .map(|url| url.to_string())
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                "https://docs.example.com/guide/setup.html",
+                "https://docs.example.com/api/"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_within() {
+        assert!(is_within(
+            "https://docs.example.com/guide/a",
+            "https://docs.example.com/guide/"
+        ));
+        assert!(is_within("/docs/api.md", "/docs/api.md"));
+        assert!(!is_within("/docs-old/api.md", "/docs"));
+    }
+}
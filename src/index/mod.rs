@@ -0,0 +1,196 @@
+//! Semantic code search over an embeddings index of the workspace
+//!
+//! Workspace files are split into overlapping chunks of lines and embedded with
+//! a provider's embedding model (`--embedding-model PROVIDER[/MODEL]`, by default
+//! the first of OpenAI, Google and Cohere with an API key). The index lives in
+//! `.termineer/index/` and is updated incrementally: only files whose content
+//! changed since the last update are embedded again. `termineer index build`
+//! and `termineer index update` maintain it from the command line, and the
+//! `semsearch` tool brings it up to date before every search.
+
+mod chunk;
+mod embed;
+pub mod kb;
+mod store;
+
+use embed::Embedder;
+use lazy_static::lazy_static;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use store::{content_hash, Index, IndexedChunk, IndexedFile};
+
+/// Directory of the index, relative to the workspace
+pub const INDEX_DIR: &str = ".termineer/index";
+
+lazy_static! {
+    /// Embedding model given with --embedding-model
+    static ref EMBEDDING_MODEL: RwLock<Option<String>> = RwLock::new(None);
+    /// Index loaded by a previous search; also serializes updates
+    static ref LOADED: tokio::sync::Mutex<Option<Index>> = tokio::sync::Mutex::new(None);
+}
+
+/// Select the embedding model (`PROVIDER[/MODEL]`)
+pub fn configure(model: Option<&str>) {
+    *EMBEDDING_MODEL.write().unwrap() = model.map(str::to_string);
+}
+
+/// Whether embeddings can be computed, i.e. an API key of the embedding provider is set
+pub fn is_available() -> bool {
+    embedder().is_ok()
+}
+
+/// Outcome of an index update
+#[derive(Debug, Default)]
+pub struct UpdateStats {
+    pub files: usize,
+    pub chunks: usize,
+    /// Chunks embedded in this update
+    pub embedded: usize,
+    /// Files dropped because they were deleted or are no longer indexed
+    pub removed: usize,
+    pub model: String,
+}
+
+/// A chunk matching a search
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Cosine similarity to the query
+    pub score: f32,
+    /// Current text of the lines
+    pub text: String,
+}
+
+/// Bring the index up to date, or rebuild it from scratch
+pub async fn update(rebuild: bool) -> Result<UpdateStats, String> {
+    let embedder = embedder()?;
+    let mut loaded = LOADED.lock().await;
+    let stats = refresh(&mut loaded, &embedder, rebuild).await?;
+    Ok(stats)
+}
+
+/// The chunks most similar to a query, after updating the index
+pub async fn search(query: &str, limit: usize) -> Result<Vec<SearchHit>, String> {
+    let query_vector = embed_query(query).await?;
+    search_vector(&query_vector, limit).await
+}
+
+/// Normalized embedding of a search query
+pub async fn embed_query(query: &str) -> Result<Vec<f32>, String> {
+    embedder()?
+        .embed(&[query.to_string()], true)
+        .await?
+        .pop()
+        .ok_or_else(|| "No embedding returned for the query".to_string())
+}
+
+/// The chunks most similar to an embedded query, after updating the index
+pub async fn search_vector(query_vector: &[f32], limit: usize) -> Result<Vec<SearchHit>, String> {
+    let embedder = embedder()?;
+    let mut loaded = LOADED.lock().await;
+    refresh(&mut loaded, &embedder, false).await?;
+    let index = loaded.as_ref().ok_or("The index could not be loaded")?;
+
+    let root = root()?;
+    Ok(index
+        .nearest(query_vector, limit)
+        .into_iter()
+        .map(|(path, chunk, score)| SearchHit {
+            path: path.to_string(),
+            start_line: chunk.start_line,
+            end_line: chunk.end_line,
+            score,
+            text: read_lines(&root.join(path), chunk.start_line, chunk.end_line),
+        })
+        .collect())
+}
+
+fn embedder() -> Result<Embedder, String> {
+    Embedder::new(EMBEDDING_MODEL.read().unwrap().as_deref())
+}
+
+fn root() -> Result<PathBuf, String> {
+    std::env::current_dir().map_err(|e| format!("Failed to read the current directory: {e}"))
+}
+
+/// Update `loaded` (loading it from disk first if needed) and save it
+async fn refresh(
+    loaded: &mut Option<Index>,
+    embedder: &Embedder,
+    rebuild: bool,
+) -> Result<UpdateStats, String> {
+    let root = root()?;
+    let dir = root.join(INDEX_DIR);
+    let model = embedder.id();
+
+    let previous = match loaded.take() {
+        _ if rebuild => None,
+        Some(index) => Some(index),
+        // A corrupt index is rebuilt rather than reported
+        None => Index::load(&dir).ok().flatten(),
+    };
+    // Vectors of another model can't be compared with new ones
+    let mut previous = previous
+        .filter(|index| index.model == model)
+        .unwrap_or_else(|| Index::new(model.clone()));
+
+    let mut index = Index::new(model.clone());
+    let mut pending: Vec<(String, u64, Vec<chunk::Chunk>)> = Vec::new();
+    for path in chunk::workspace_files(&root) {
+        // Binary and non-UTF-8 files are skipped
+        let Ok(text) = std::fs::read_to_string(root.join(&path)) else {
+            continue;
+        };
+        let hash = content_hash(&text);
+        match previous.files.remove(&path) {
+            Some(file) if file.hash == hash => {
+                index.files.insert(path, file);
+            }
+            _ => pending.push((path, hash, chunk::chunk_text(&text))),
+        }
+    }
+    let removed = previous.files.len();
+
+    let texts: Vec<String> = pending
+        .iter()
+        .flat_map(|(path, _, chunks)| chunks.iter().map(|c| chunk::embedding_text(path, c)))
+        .collect();
+    let mut vectors = embedder.embed(&texts, false).await?.into_iter();
+    for (path, hash, chunks) in pending {
+        let chunks = chunks
+            .into_iter()
+            .map(|chunk| IndexedChunk {
+                start_line: chunk.start_line,
+                end_line: chunk.end_line,
+                vector: vectors.next().unwrap_or_default(),
+            })
+            .collect();
+        index.files.insert(path, IndexedFile { hash, chunks });
+    }
+
+    if !texts.is_empty() || removed > 0 || rebuild {
+        index.save(&dir)?;
+    }
+    let stats = UpdateStats {
+        files: index.files.len(),
+        chunks: index.chunk_count(),
+        embedded: texts.len(),
+        removed,
+        model,
+    };
+    *loaded = Some(index);
+    Ok(stats)
+}
+
+/// Lines `start..=end` (1-based) of a file
+fn read_lines(path: &Path, start: usize, end: usize) -> String {
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .skip(start.saturating_sub(1))
+        .take(end + 1 - start.max(1))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
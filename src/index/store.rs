@@ -0,0 +1,242 @@
+//! On-disk storage of the embeddings index
+//!
+//! `index.json` lists the indexed files with their content hash and chunk line
+//! ranges; `vectors.bin` holds the chunk embeddings as little-endian `f32`s in
+//! the same order. Search compares the query with every chunk, which is fast
+//! enough for the tens of thousands of chunks of a large repository.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const MANIFEST_FILE: &str = "index.json";
+const VECTORS_FILE: &str = "vectors.bin";
+
+/// A chunk of a file and its embedding
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedChunk {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub vector: Vec<f32>,
+}
+
+/// An indexed file
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedFile {
+    /// Hash of the content the chunks were computed from
+    pub hash: u64,
+    pub chunks: Vec<IndexedChunk>,
+}
+
+/// Embeddings of the workspace
+#[derive(Debug, Clone, PartialEq)]
+pub struct Index {
+    /// `provider/model` the vectors were computed with
+    pub model: String,
+    pub files: BTreeMap<String, IndexedFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    model: String,
+    dimensions: usize,
+    files: BTreeMap<String, ManifestFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestFile {
+    hash: u64,
+    /// Line ranges of the chunks
+    chunks: Vec<(usize, usize)>,
+}
+
+impl Index {
+    pub fn new(model: String) -> Self {
+        Self {
+            model,
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Number of chunks in the index
+    pub fn chunk_count(&self) -> usize {
+        self.files.values().map(|file| file.chunks.len()).sum()
+    }
+
+    /// Load the index from a directory, if one was saved there
+    pub fn load(dir: &Path) -> Result<Option<Self>, String> {
+        let manifest = match std::fs::read_to_string(dir.join(MANIFEST_FILE)) {
+            Ok(manifest) => manifest,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Failed to read the index: {e}")),
+        };
+        let manifest: Manifest =
+            serde_json::from_str(&manifest).map_err(|e| format!("Corrupt index manifest: {e}"))?;
+        let bytes = std::fs::read(dir.join(VECTORS_FILE))
+            .map_err(|e| format!("Failed to read the index vectors: {e}"))?;
+
+        let floats: Vec<f32> = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        let expected: usize = manifest.files.values().map(|f| f.chunks.len()).sum();
+        if (manifest.dimensions == 0 && expected > 0)
+            || floats.len() != expected * manifest.dimensions
+        {
+            return Err(
+                "The index vectors do not match the manifest; rebuild the index".to_string(),
+            );
+        }
+
+        let mut vectors = floats.chunks(manifest.dimensions.max(1));
+        let files = manifest
+            .files
+            .into_iter()
+            .map(|(path, file)| {
+                let chunks = file
+                    .chunks
+                    .into_iter()
+                    .map(|(start_line, end_line)| IndexedChunk {
+                        start_line,
+                        end_line,
+                        vector: vectors.next().unwrap_or_default().to_vec(),
+                    })
+                    .collect();
+                (
+                    path,
+                    IndexedFile {
+                        hash: file.hash,
+                        chunks,
+                    },
+                )
+            })
+            .collect();
+        Ok(Some(Self {
+            model: manifest.model,
+            files,
+        }))
+    }
+
+    /// Save the index to a directory
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+
+        let chunks = || self.files.values().flat_map(|file| &file.chunks);
+        let dimensions = chunks().next().map_or(0, |chunk| chunk.vector.len());
+        let mut bytes = Vec::with_capacity(self.chunk_count() * dimensions * 4);
+        for chunk in chunks() {
+            if chunk.vector.len() != dimensions {
+                return Err("Embeddings of different sizes in one index".to_string());
+            }
+            for value in &chunk.vector {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        let manifest = Manifest {
+            model: self.model.clone(),
+            dimensions,
+            files: self
+                .files
+                .iter()
+                .map(|(path, file)| {
+                    let chunks = file
+                        .chunks
+                        .iter()
+                        .map(|chunk| (chunk.start_line, chunk.end_line))
+                        .collect();
+                    (
+                        path.clone(),
+                        ManifestFile {
+                            hash: file.hash,
+                            chunks,
+                        },
+                    )
+                })
+                .collect(),
+        };
+        let manifest = serde_json::to_string(&manifest)
+            .map_err(|e| format!("Failed to serialize the index: {e}"))?;
+
+        // Write the vectors first: a manifest never refers to missing vectors
+        std::fs::write(dir.join(VECTORS_FILE), bytes)
+            .and_then(|_| std::fs::write(dir.join(MANIFEST_FILE), manifest))
+            .map_err(|e| format!("Failed to write the index: {e}"))
+    }
+
+    /// The `limit` chunks most similar to a normalized query vector
+    pub fn nearest(&self, query: &[f32], limit: usize) -> Vec<(&str, &IndexedChunk, f32)> {
+        let mut scored: Vec<(&str, &IndexedChunk, f32)> = self
+            .files
+            .iter()
+            .flat_map(|(path, file)| {
+                file.chunks.iter().map(move |chunk| {
+                    let score = chunk
+                        .vector
+                        .iter()
+                        .zip(query)
+                        .map(|(a, b)| a * b)
+                        .sum::<f32>();
+                    (path.as_str(), chunk, score)
+                })
+            })
+            .collect();
+        scored.sort_by(|a, b| b.2.total_cmp(&a.2));
+        scored.truncate(limit);
+        scored
+    }
+}
+
+/// FNV-1a hash of file content, stable across runs and builds
+pub fn content_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Index {
+        let mut index = Index::new("openai/text-embedding-3-small".to_string());
+        index.files.insert(
+            "src/a.rs".to_string(),
+            IndexedFile {
+                hash: content_hash("a"),
+                chunks: vec![
+                    IndexedChunk {
+                        start_line: 1,
+                        end_line: 60,
+                        vector: vec![1.0, 0.0],
+                    },
+                    IndexedChunk {
+                        start_line: 51,
+                        end_line: 80,
+                        vector: vec![0.0, 1.0],
+                    },
+                ],
+            },
+        );
+        index
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("termineer-index-{}", uuid::Uuid::new_v4()));
+        let index = sample();
+        index.save(&dir).unwrap();
+        assert_eq!(Index::load(&dir).unwrap(), Some(index));
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(Index::load(&dir).unwrap(), None);
+    }
+
+    #[test]
+    fn test_nearest() {
+        let index = sample();
+        let nearest = index.nearest(&[0.6, 0.8], 1);
+        assert_eq!(nearest.len(), 1);
+        assert_eq!((nearest[0].0, nearest[0].1.start_line), ("src/a.rs", 51));
+    }
+}
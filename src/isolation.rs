@@ -0,0 +1,398 @@
+//! Isolated workspaces for `--isolated`
+//!
+//! The session runs in a temporary copy of the working tree: a detached git
+//! worktree carrying over uncommitted and untracked files, or a plain copy of
+//! the directory when it is not a git repository. Termineer changes into the
+//! copy before any agent starts, so every file and shell tool works on it.
+//! When the session ends, the changes are shown as a diff against the original
+//! tree, and the user chooses to apply them, discard them or export a patch.
+//! This also happens when a single query is interrupted with Ctrl+C, so the
+//! copy is never left behind.
+
+use crate::git::git_in;
+use lazy_static::lazy_static;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Directory excluded from the diff (caches, scratchpads and other session state)
+const STATE_DIR: &str = ".termineer";
+
+/// What to do with the changes made in the isolated workspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Choice {
+    Apply,
+    Discard,
+    Export,
+}
+
+/// A temporary copy of the working tree used for the session
+struct Isolation {
+    /// Directory termineer was started in
+    original_dir: PathBuf,
+    /// Root of the original tree (the repository root inside git repositories)
+    original_root: PathBuf,
+    /// Root of the isolated copy, mirroring `original_root`
+    root: PathBuf,
+    /// Whether the copy is a worktree of the original repository
+    worktree: bool,
+    /// Tree of the copy before the session
+    baseline: String,
+}
+
+lazy_static! {
+    /// Isolated copy of the running session, taken by whoever finishes it first
+    static ref ACTIVE: Mutex<Option<Isolation>> = Mutex::new(None);
+}
+
+/// Create the isolated copy for the session and change into it
+pub async fn start() -> Result<(), String> {
+    let isolation = Isolation::create().await?;
+    *ACTIVE.lock().unwrap() = Some(isolation);
+    Ok(())
+}
+
+/// Review the changes of the session's isolated copy and remove it, if there is one
+pub async fn finish() -> Result<(), String> {
+    let isolation = ACTIVE.lock().unwrap().take();
+    match isolation {
+        Some(isolation) => isolation.finish().await,
+        None => Ok(()),
+    }
+}
+
+/// Finish the session's isolated copy from a Ctrl+C handler, before the process
+/// exits; the handler runs on its own thread, outside the async runtime
+pub fn finish_interrupted() -> Result<(), String> {
+    if ACTIVE.lock().unwrap().is_none() {
+        return Ok(());
+    }
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to start a runtime: {e}"))?
+        .block_on(finish())
+}
+
+/// Temporary file outside both trees
+fn temp_path(suffix: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "termineer-isolated-{}{suffix}",
+        uuid::Uuid::new_v4()
+    ))
+}
+
+impl Isolation {
+    /// Create the isolated copy and change into it
+    async fn create() -> Result<Self, String> {
+        let original_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to read the current directory: {e}"))?;
+        let root = temp_path("");
+
+        let repo_root = git_in(&original_dir, &["rev-parse", "--show-toplevel"], None)
+            .await
+            .ok()
+            .map(|output| PathBuf::from(output.trim()));
+        let has_head = git_in(
+            &original_dir,
+            &["rev-parse", "--verify", "-q", "HEAD"],
+            None,
+        )
+        .await
+        .is_ok();
+
+        let (original_root, worktree) = match repo_root {
+            Some(repo_root) if has_head => {
+                create_worktree(&repo_root, &root).await?;
+                (repo_root, true)
+            }
+            repo_root => {
+                // Repositories without commits are copied like plain directories
+                let original_root = repo_root.unwrap_or_else(|| original_dir.clone());
+                copy_dir(&original_root, &root)
+                    .map_err(|e| format!("Failed to copy {}: {e}", original_root.display()))?;
+                git_in(&root, &["init", "-q"], None).await?;
+                (original_root, false)
+            }
+        };
+
+        let mut isolation = Self {
+            original_dir,
+            original_root,
+            root,
+            worktree,
+            baseline: String::new(),
+        };
+        match snapshot(&isolation.root).await {
+            Ok(baseline) => isolation.baseline = baseline,
+            Err(e) => {
+                isolation.remove().await;
+                return Err(e);
+            }
+        }
+
+        let relative = isolation
+            .original_dir
+            .strip_prefix(&isolation.original_root)
+            .unwrap_or(Path::new(""));
+        let workdir = isolation.root.join(relative);
+        if let Err(e) = std::env::set_current_dir(&workdir) {
+            isolation.remove().await;
+            return Err(format!("Failed to enter {}: {e}", workdir.display()));
+        }
+
+        println!(
+            "Working in an isolated copy at {}; changes are reviewed when the session ends.",
+            workdir.display()
+        );
+        Ok(isolation)
+    }
+
+    /// Leave the isolated copy, let the user decide what to do with the changes
+    /// and remove the copy
+    async fn finish(self) -> Result<(), String> {
+        std::env::set_current_dir(&self.original_dir)
+            .map_err(|e| format!("Failed to return to {}: {e}", self.original_dir.display()))?;
+        let result = self.review().await;
+        self.remove().await;
+        result
+    }
+
+    /// Show the changes and carry out the user's choice
+    async fn review(&self) -> Result<(), String> {
+        let tree = snapshot(&self.root).await?;
+        if tree == self.baseline {
+            println!("No changes were made in the isolated workspace.");
+            return Ok(());
+        }
+
+        let range = [self.baseline.as_str(), tree.as_str()];
+        let stat = git_in(&self.root, &["diff", "--stat", range[0], range[1]], None).await?;
+        let patch = git_in(&self.root, &["diff", "--binary", range[0], range[1]], None).await?;
+        println!(
+            "\nChanges made in the isolated workspace:\n{}",
+            stat.trim_end()
+        );
+
+        match ask_choice(&self.original_root) {
+            Choice::Apply => match self.apply(&patch).await {
+                Ok(()) => {
+                    println!("Applied the changes to {}.", self.original_root.display());
+                    Ok(())
+                }
+                Err(e) => {
+                    let path = self.export(&patch)?;
+                    Err(format!("{e}; the patch was saved to {}", path.display()))
+                }
+            },
+            Choice::Export => {
+                let path = self.export(&patch)?;
+                println!("Saved the patch to {}.", path.display());
+                Ok(())
+            }
+            Choice::Discard => {
+                println!("Discarded the changes.");
+                Ok(())
+            }
+        }
+    }
+
+    /// Apply the patch to the original tree
+    async fn apply(&self, patch: &str) -> Result<(), String> {
+        let patch_file = temp_path(".patch");
+        std::fs::write(&patch_file, patch)
+            .map_err(|e| format!("Failed to write the patch: {e}"))?;
+        let patch_arg = patch_file.to_string_lossy().to_string();
+        let result = git_in(
+            &self.original_root,
+            &["apply", "--binary", patch_arg.as_str()],
+            None,
+        )
+        .await;
+        let _ = std::fs::remove_file(&patch_file);
+        result.map(|_| ())
+    }
+
+    /// Save the patch under the state directory of the original tree
+    fn export(&self, patch: &str) -> Result<PathBuf, String> {
+        let dir = self.original_root.join(STATE_DIR);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+        let path = dir.join(format!(
+            "isolated-{}.patch",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        std::fs::write(&path, patch)
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Delete the isolated copy
+    async fn remove(&self) {
+        let root = self.root.to_string_lossy().to_string();
+        if self.worktree
+            && git_in(
+                &self.original_root,
+                &["worktree", "remove", "--force", root.as_str()],
+                None,
+            )
+            .await
+            .is_ok()
+        {
+            return;
+        }
+        let _ = std::fs::remove_dir_all(&self.root);
+        if self.worktree {
+            let _ = git_in(&self.original_root, &["worktree", "prune"], None).await;
+        }
+    }
+}
+
+/// Check out HEAD into a detached worktree and carry over uncommitted and
+/// untracked files
+async fn create_worktree(repo_root: &Path, root: &Path) -> Result<(), String> {
+    let root_arg = root.to_string_lossy().to_string();
+    git_in(
+        repo_root,
+        &[
+            "worktree",
+            "add",
+            "--detach",
+            "-q",
+            root_arg.as_str(),
+            "HEAD",
+        ],
+        None,
+    )
+    .await?;
+
+    let result = async {
+        let changes = git_in(repo_root, &["diff", "HEAD", "--binary"], None).await?;
+        if !changes.is_empty() {
+            let patch_file = temp_path(".patch");
+            std::fs::write(&patch_file, &changes)
+                .map_err(|e| format!("Failed to write the patch: {e}"))?;
+            let patch_arg = patch_file.to_string_lossy().to_string();
+            let applied = git_in(root, &["apply", "--binary", patch_arg.as_str()], None).await;
+            let _ = std::fs::remove_file(&patch_file);
+            applied?;
+        }
+
+        let untracked = git_in(
+            repo_root,
+            &["ls-files", "--others", "--exclude-standard", "-z"],
+            None,
+        )
+        .await?;
+        for path in untracked.split('\0').filter(|path| !path.is_empty()) {
+            let target = root.join(path);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+            }
+            std::fs::copy(repo_root.join(path), &target)
+                .map_err(|e| format!("Failed to copy {path}: {e}"))?;
+        }
+        Ok(())
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = git_in(
+            repo_root,
+            &["worktree", "remove", "--force", root_arg.as_str()],
+            None,
+        )
+        .await;
+    }
+    result
+}
+
+/// Copy a directory recursively, leaving out git metadata
+pub fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        let target = to.join(entry.file_name());
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else if file_type.is_file() {
+            std::fs::copy(entry.path(), &target)?;
+        } else if file_type.is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(std::fs::read_link(entry.path())?, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the current content of a tree (minus the state directory) to the git
+/// object store and return the tree hash
+async fn snapshot(root: &Path) -> Result<String, String> {
+    let index = temp_path(".index");
+    let exclude = format!(":!{STATE_DIR}");
+    let result = async {
+        git_in(
+            root,
+            &["add", "-A", "--", ".", exclude.as_str()],
+            Some(&index),
+        )
+        .await?;
+        git_in(root, &["write-tree"], Some(&index)).await
+    }
+    .await;
+    let _ = std::fs::remove_file(&index);
+    result.map(|tree| tree.trim().to_string())
+}
+
+/// Ask what to do with the changes; without a terminal the patch is exported
+/// so that no work is lost
+fn ask_choice(original_root: &Path) -> Choice {
+    if !std::io::stdin().is_terminal() {
+        return Choice::Export;
+    }
+    loop {
+        print!(
+            "[a]pply to {}, [d]iscard, or [e]xport a patch? ",
+            original_root.display()
+        );
+        let _ = std::io::stdout().flush();
+        let mut input = String::new();
+        match std::io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => return Choice::Export,
+            Ok(_) => {
+                if let Some(choice) = parse_choice(&input) {
+                    return choice;
+                }
+            }
+        }
+    }
+}
+
+/// Parse the answer to the review prompt
+fn parse_choice(input: &str) -> Option<Choice> {
+    match input.trim().to_lowercase().as_str() {
+        "a" | "apply" => Some(Choice::Apply),
+        "d" | "discard" => Some(Choice::Discard),
+        "e" | "export" => Some(Choice::Export),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_choice() {
+        assert_eq!(parse_choice("a\n"), Some(Choice::Apply));
+        assert_eq!(parse_choice(" Discard "), Some(Choice::Discard));
+        assert_eq!(parse_choice("e"), Some(Choice::Export));
+        assert_eq!(parse_choice("x"), None);
+        assert_eq!(parse_choice(""), None);
+    }
+}
@@ -14,6 +14,12 @@
 //! - remove: Remove an element
 //! - set: Set a value
 //! - insert: Insert a value
+//!
+//! Query expressions additionally support JSONPath syntax with filters, slices
+//! and recursive descent, e.g. `$.store..book[?(@.price > 10)].title`:
+//! - query: Retrieve all matching values
+//! - set_all: Set all matching values
+//! - delete_all: Remove all matching values
 
 use serde_json::Value;
 use std::fmt;
@@ -1028,6 +1034,811 @@ where
     }
 }
 
+// ---------------------------------------------------------------------------
+// Query expressions
+// ---------------------------------------------------------------------------
+
+/// One step of a concrete location inside a JSON document
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Location {
+    /// Object key
+    Key(String),
+    /// Array index
+    Index(usize),
+}
+
+/// Format a concrete location as a normalized path (e.g. `$['store']['book'][0]`)
+pub fn format_location(location: &[Location]) -> String {
+    let mut path = String::from("$");
+    for step in location {
+        match step {
+            Location::Key(key) => path.push_str(&format!(
+                "['{}']",
+                key.replace('\\', "\\\\").replace('\'', "\\'")
+            )),
+            Location::Index(index) => path.push_str(&format!("[{}]", index)),
+        }
+    }
+    path
+}
+
+/// A selector in a query expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selector {
+    /// Named child (`.name`, `['name']`)
+    Child(String),
+    /// All children of an object or array (`.*`, `[*]`)
+    Wildcard,
+    /// Array index (`[0]`, `[-1]`)
+    Index(isize),
+    /// Array slice (`[start:end:step]`)
+    Slice(Option<isize>, Option<isize>, Option<isize>),
+    /// Several names or indices (`['a','b']`, `[0,2]`)
+    Union(Vec<Selector>),
+    /// Recursive descent: applies the inner selector to the node and all its descendants (`..name`)
+    Descendant(Box<Selector>),
+    /// Children for which the filter holds (`[?(@.price > 10)]`)
+    Filter(FilterExpr),
+}
+
+/// A filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Not(Box<FilterExpr>),
+    /// True when the path matches at least one value (`@.isbn`)
+    Exists(Operand),
+    Compare(Operand, CompareOp, Operand),
+}
+
+/// A value referenced in a filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    /// Path relative to the node being filtered (`@.a.b`)
+    Current(Vec<Selector>),
+    /// Path from the document root (`$.a.b`)
+    Root(Vec<Selector>),
+    /// Literal string, number, boolean or null
+    Literal(Value),
+}
+
+/// Comparison operators in filter expressions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Regular expression search (`=~`)
+    Match,
+}
+
+/// Parse a query expression into selectors
+///
+/// Accepts JSONPath expressions starting with `$` as well as the slash paths
+/// understood by [`parse_path`].
+pub fn parse_query(expr: &str) -> Result<Vec<Selector>, JsonPathError> {
+    let expr = expr.trim();
+
+    if expr.starts_with('/') {
+        return parse_path(expr)?
+            .into_iter()
+            .filter_map(|segment| match segment {
+                PathSegment::Root => None,
+                PathSegment::Property(name) => Some(Ok(Selector::Child(name.to_string()))),
+                PathSegment::Index(index) => Some(Ok(Selector::Index(index))),
+                PathSegment::Range(start, end) => Some(Ok(Selector::Slice(start, end, None))),
+            })
+            .collect();
+    }
+
+    let mut parser = QueryParser::new(expr);
+    if !parser.eat('$') {
+        return Err(JsonPathError::InvalidPath(format!(
+            "Query must start with '$' or '/', got: {}",
+            expr
+        )));
+    }
+    let selectors = parser.selectors()?;
+    if parser.peek().is_some() {
+        return Err(parser.error("Unexpected character"));
+    }
+    Ok(selectors)
+}
+
+/// Recursive descent parser for query expressions
+struct QueryParser<'a> {
+    source: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl<'a> QueryParser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            chars: source.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn error(&self, message: &str) -> JsonPathError {
+        JsonPathError::InvalidPath(format!(
+            "{} at position {} in '{}'",
+            message, self.pos, self.source
+        ))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let matches = s
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c));
+        if matches {
+            self.pos += s.chars().count();
+        }
+        matches
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), JsonPathError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", c)))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Parse a sequence of `.name`, `..name`, `.*` and `[...]` selectors
+    fn selectors(&mut self) -> Result<Vec<Selector>, JsonPathError> {
+        let mut selectors = Vec::new();
+        loop {
+            match self.peek() {
+                Some('.') if self.peek_at(1) == Some('.') => {
+                    self.pos += 2;
+                    let inner = if self.peek() == Some('[') {
+                        self.bracket()?
+                    } else {
+                        self.dot_member()?
+                    };
+                    selectors.push(Selector::Descendant(Box::new(inner)));
+                }
+                Some('.') => {
+                    self.pos += 1;
+                    selectors.push(self.dot_member()?);
+                }
+                Some('[') => selectors.push(self.bracket()?),
+                _ => return Ok(selectors),
+            }
+        }
+    }
+
+    /// Parse the member after a dot: `*` or a name
+    fn dot_member(&mut self) -> Result<Selector, JsonPathError> {
+        if self.eat('*') {
+            return Ok(Selector::Wildcard);
+        }
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '$')
+        {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error("Expected member name"));
+        }
+        Ok(Selector::Child(
+            self.chars[start..self.pos].iter().collect(),
+        ))
+    }
+
+    /// Parse a bracketed selector: names, indices, slices, `*` or a filter
+    fn bracket(&mut self) -> Result<Selector, JsonPathError> {
+        self.expect('[')?;
+        self.skip_whitespace();
+
+        if self.eat('?') {
+            let filter = self.filter_or()?;
+            self.skip_whitespace();
+            self.expect(']')?;
+            return Ok(Selector::Filter(filter));
+        }
+
+        if self.eat('*') {
+            self.skip_whitespace();
+            self.expect(']')?;
+            return Ok(Selector::Wildcard);
+        }
+
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let item = match self.peek() {
+                Some('\'') | Some('"') => Selector::Child(self.string()?),
+                _ => self.index_or_slice()?,
+            };
+            items.push(item);
+            self.skip_whitespace();
+            if self.eat(']') {
+                break;
+            }
+            self.expect(',')?;
+        }
+
+        if items.len() == 1 {
+            Ok(items.remove(0))
+        } else {
+            Ok(Selector::Union(items))
+        }
+    }
+
+    fn index_or_slice(&mut self) -> Result<Selector, JsonPathError> {
+        let start = self.integer()?;
+        self.skip_whitespace();
+        if !self.eat(':') {
+            return start
+                .map(Selector::Index)
+                .ok_or_else(|| self.error("Expected index, slice or name"));
+        }
+
+        self.skip_whitespace();
+        let end = self.integer()?;
+        self.skip_whitespace();
+        let step = if self.eat(':') {
+            self.skip_whitespace();
+            self.integer()?
+        } else {
+            None
+        };
+        Ok(Selector::Slice(start, end, step))
+    }
+
+    /// Parse an optional signed integer
+    fn integer(&mut self) -> Result<Option<isize>, JsonPathError> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Ok(None);
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse()
+            .map(Some)
+            .map_err(|_| self.error(&format!("Invalid integer '{}'", text)))
+    }
+
+    /// Parse a quoted string with backslash escapes
+    fn string(&mut self) -> Result<String, JsonPathError> {
+        let quote = self.peek().ok_or_else(|| self.error("Expected string"))?;
+        self.pos += 1;
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("Unterminated string")),
+                Some('\\') => {
+                    let escaped = self
+                        .peek_at(1)
+                        .ok_or_else(|| self.error("Unterminated string"))?;
+                    value.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        other => other,
+                    });
+                    self.pos += 2;
+                }
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(value);
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn filter_or(&mut self) -> Result<FilterExpr, JsonPathError> {
+        let mut expr = self.filter_and()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat_str("||") {
+                return Ok(expr);
+            }
+            let right = self.filter_and()?;
+            expr = FilterExpr::Or(Box::new(expr), Box::new(right));
+        }
+    }
+
+    fn filter_and(&mut self) -> Result<FilterExpr, JsonPathError> {
+        let mut expr = self.filter_unary()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat_str("&&") {
+                return Ok(expr);
+            }
+            let right = self.filter_unary()?;
+            expr = FilterExpr::And(Box::new(expr), Box::new(right));
+        }
+    }
+
+    fn filter_unary(&mut self) -> Result<FilterExpr, JsonPathError> {
+        self.skip_whitespace();
+        if self.peek() == Some('!') && self.peek_at(1) != Some('=') {
+            self.pos += 1;
+            return Ok(FilterExpr::Not(Box::new(self.filter_unary()?)));
+        }
+        if self.eat('(') {
+            let expr = self.filter_or()?;
+            self.skip_whitespace();
+            self.expect(')')?;
+            return Ok(expr);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<FilterExpr, JsonPathError> {
+        let left = self.operand()?;
+        self.skip_whitespace();
+
+        let op = if self.eat_str("==") {
+            CompareOp::Eq
+        } else if self.eat_str("!=") {
+            CompareOp::Ne
+        } else if self.eat_str("<=") {
+            CompareOp::Le
+        } else if self.eat_str(">=") {
+            CompareOp::Ge
+        } else if self.eat_str("=~") {
+            CompareOp::Match
+        } else if self.eat('<') {
+            CompareOp::Lt
+        } else if self.eat('>') {
+            CompareOp::Gt
+        } else {
+            return match left {
+                Operand::Literal(_) => Err(self.error("Expected comparison operator")),
+                path => Ok(FilterExpr::Exists(path)),
+            };
+        };
+
+        let right = self.operand()?;
+        Ok(FilterExpr::Compare(left, op, right))
+    }
+
+    fn operand(&mut self) -> Result<Operand, JsonPathError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('@') => {
+                self.pos += 1;
+                Ok(Operand::Current(self.selectors()?))
+            }
+            Some('$') => {
+                self.pos += 1;
+                Ok(Operand::Root(self.selectors()?))
+            }
+            Some('\'') | Some('"') => Ok(Operand::Literal(Value::String(self.string()?))),
+            Some('/') => {
+                // Regular expression literal: /pattern/
+                self.pos += 1;
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c != '/') {
+                    if self.peek() == Some('\\') {
+                        self.pos += 1;
+                    }
+                    self.pos += 1;
+                }
+                let pattern: String = self.chars[start..self.pos.min(self.chars.len())]
+                    .iter()
+                    .collect();
+                self.expect('/')?;
+                Ok(Operand::Literal(Value::String(pattern)))
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+                {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                serde_json::from_str::<Value>(&text)
+                    .ok()
+                    .filter(Value::is_number)
+                    .map(Operand::Literal)
+                    .ok_or_else(|| self.error(&format!("Invalid number '{}'", text)))
+            }
+            _ => {
+                for (keyword, value) in [
+                    ("true", Value::Bool(true)),
+                    ("false", Value::Bool(false)),
+                    ("null", Value::Null),
+                ] {
+                    if self.eat_str(keyword) {
+                        return Ok(Operand::Literal(value));
+                    }
+                }
+                Err(self.error("Expected operand"))
+            }
+        }
+    }
+}
+
+/// Follow a concrete location to its value
+fn value_at<'a>(json: &'a Value, location: &[Location]) -> Option<&'a Value> {
+    location.iter().try_fold(json, |current, step| match step {
+        Location::Key(key) => current.as_object()?.get(key),
+        Location::Index(index) => current.as_array()?.get(*index),
+    })
+}
+
+/// Follow a concrete location to a mutable value
+fn value_at_mut<'a>(json: &'a mut Value, location: &[Location]) -> Option<&'a mut Value> {
+    location.iter().try_fold(json, |current, step| match step {
+        Location::Key(key) => current.as_object_mut()?.get_mut(key),
+        Location::Index(index) => current.as_array_mut()?.get_mut(*index),
+    })
+}
+
+/// Indices selected by a slice, with Python-style semantics
+fn slice_indices(
+    len: usize,
+    start: Option<isize>,
+    end: Option<isize>,
+    step: Option<isize>,
+) -> Vec<usize> {
+    let len = len as isize;
+    let step = step.unwrap_or(1);
+    let normalize = |i: isize| if i < 0 { len + i } else { i };
+
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        (lower..upper)
+            .step_by(step as usize)
+            .map(|i| i as usize)
+            .collect()
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+        let lower = normalize(end.unwrap_or(-len - 1)).clamp(-1, len - 1);
+        let mut indices = Vec::new();
+        let mut i = upper;
+        while i > lower {
+            indices.push(i as usize);
+            i += step;
+        }
+        indices
+    } else {
+        Vec::new()
+    }
+}
+
+/// The node and all its descendants, in document order
+fn descendants(value: &Value, location: Vec<Location>, out: &mut Vec<Vec<Location>>) {
+    match value {
+        Value::Object(obj) => {
+            out.push(location.clone());
+            for (key, child) in obj {
+                let mut child_location = location.clone();
+                child_location.push(Location::Key(key.clone()));
+                descendants(child, child_location, out);
+            }
+        }
+        Value::Array(arr) => {
+            out.push(location.clone());
+            for (index, child) in arr.iter().enumerate() {
+                let mut child_location = location.clone();
+                child_location.push(Location::Index(index));
+                descendants(child, child_location, out);
+            }
+        }
+        _ => out.push(location),
+    }
+}
+
+/// Apply a single selector to a node, collecting the selected child locations
+fn apply_selector(
+    root: &Value,
+    value: &Value,
+    location: &[Location],
+    selector: &Selector,
+    out: &mut Vec<Vec<Location>>,
+) {
+    let child = |step: Location| {
+        let mut child_location = location.to_vec();
+        child_location.push(step);
+        child_location
+    };
+
+    match selector {
+        Selector::Child(name) => {
+            if value.as_object().is_some_and(|obj| obj.contains_key(name)) {
+                out.push(child(Location::Key(name.clone())));
+            }
+        }
+        Selector::Wildcard => match value {
+            Value::Object(obj) => {
+                out.extend(obj.keys().map(|key| child(Location::Key(key.clone()))))
+            }
+            Value::Array(arr) => out.extend((0..arr.len()).map(|i| child(Location::Index(i)))),
+            _ => {}
+        },
+        Selector::Index(index) => {
+            if let Value::Array(arr) = value {
+                if let Ok(index) = normalize_index(*index, arr.len()) {
+                    out.push(child(Location::Index(index)));
+                }
+            }
+        }
+        Selector::Slice(start, end, step) => {
+            if let Value::Array(arr) = value {
+                out.extend(
+                    slice_indices(arr.len(), *start, *end, *step)
+                        .into_iter()
+                        .map(|i| child(Location::Index(i))),
+                );
+            }
+        }
+        Selector::Union(selectors) => {
+            for selector in selectors {
+                apply_selector(root, value, location, selector, out);
+            }
+        }
+        Selector::Descendant(inner) => {
+            let mut nodes = Vec::new();
+            descendants(value, location.to_vec(), &mut nodes);
+            for node in nodes {
+                if let Some(node_value) = value_at(root, &node) {
+                    apply_selector(root, node_value, &node, inner, out);
+                }
+            }
+        }
+        Selector::Filter(filter) => {
+            let children: Vec<(Location, &Value)> = match value {
+                Value::Object(obj) => obj
+                    .iter()
+                    .map(|(key, v)| (Location::Key(key.clone()), v))
+                    .collect(),
+                Value::Array(arr) => arr
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (Location::Index(i), v))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for (step, child_value) in children {
+                if eval_filter(root, child_value, filter) {
+                    out.push(child(step));
+                }
+            }
+        }
+    }
+}
+
+/// Evaluate selectors starting from `start`, returning locations relative to `root`
+fn select_from(root: &Value, start: Vec<Location>, selectors: &[Selector]) -> Vec<Vec<Location>> {
+    let mut current = vec![start];
+    for selector in selectors {
+        let mut next = Vec::new();
+        for location in &current {
+            if let Some(value) = value_at(root, location) {
+                apply_selector(root, value, location, selector, &mut next);
+            }
+        }
+        current = next;
+    }
+    current
+}
+
+/// Resolve a filter operand to its first value
+fn resolve_operand<'a>(
+    root: &'a Value,
+    current: &'a Value,
+    operand: &'a Operand,
+) -> Option<&'a Value> {
+    match operand {
+        Operand::Literal(value) => Some(value),
+        Operand::Current(selectors) => select_from(current, Vec::new(), selectors)
+            .first()
+            .and_then(|location| value_at(current, location)),
+        Operand::Root(selectors) => select_from(root, Vec::new(), selectors)
+            .first()
+            .and_then(|location| value_at(root, location)),
+    }
+}
+
+fn eval_filter(root: &Value, current: &Value, filter: &FilterExpr) -> bool {
+    match filter {
+        FilterExpr::Or(left, right) => {
+            eval_filter(root, current, left) || eval_filter(root, current, right)
+        }
+        FilterExpr::And(left, right) => {
+            eval_filter(root, current, left) && eval_filter(root, current, right)
+        }
+        FilterExpr::Not(inner) => !eval_filter(root, current, inner),
+        FilterExpr::Exists(operand) => resolve_operand(root, current, operand).is_some(),
+        FilterExpr::Compare(left, op, right) => compare(
+            resolve_operand(root, current, left),
+            *op,
+            resolve_operand(root, current, right),
+        ),
+    }
+}
+
+fn compare(left: Option<&Value>, op: CompareOp, right: Option<&Value>) -> bool {
+    use std::cmp::Ordering;
+
+    let equal = match (left, right) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a.as_f64() == b.as_f64(),
+        (Some(a), Some(b)) => a == b,
+        (None, None) => true,
+        _ => false,
+    };
+
+    let ordering = match (left, right) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a
+            .as_f64()
+            .zip(b.as_f64())
+            .and_then(|(a, b)| a.partial_cmp(&b)),
+        (Some(Value::String(a)), Some(Value::String(b))) => Some(a.cmp(b)),
+        _ => None,
+    };
+
+    match op {
+        CompareOp::Eq => equal,
+        CompareOp::Ne => !equal,
+        CompareOp::Lt => matches!(ordering, Some(Ordering::Less)),
+        CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        CompareOp::Gt => matches!(ordering, Some(Ordering::Greater)),
+        CompareOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        CompareOp::Match => match (left, right) {
+            (Some(Value::String(text)), Some(Value::String(pattern))) => regex::Regex::new(pattern)
+                .map(|re| re.is_match(text))
+                .unwrap_or(false),
+            _ => false,
+        },
+    }
+}
+
+/// Find the locations of all values matching a query expression
+pub fn query_locations(json: &Value, expr: &str) -> Result<Vec<Vec<Location>>, JsonPathError> {
+    let selectors = parse_query(expr)?;
+    Ok(select_from(json, Vec::new(), &selectors))
+}
+
+/// Get all values matching a query expression
+///
+/// Supports `$.a.b`, `$['a']`, `$.*`, `$..name` (recursive descent),
+/// `$[0]`, `$[-1]`, `$[1:5:2]` (slices), `$['a','b']` (unions) and filters
+/// such as `$.books[?(@.price > 10 && @.tags)]`.
+pub fn query<'a>(json: &'a Value, expr: &str) -> Result<Vec<&'a Value>, JsonPathError> {
+    Ok(query_locations(json, expr)?
+        .iter()
+        .filter_map(|location| value_at(json, location))
+        .collect())
+}
+
+/// Locations written by a set operation
+///
+/// These are the locations matching the query or, when nothing matches and the
+/// last selector is a name, that member in every object matched by the rest of
+/// the expression (so it can be created).
+pub fn write_locations(json: &Value, expr: &str) -> Result<Vec<Vec<Location>>, JsonPathError> {
+    let selectors = parse_query(expr)?;
+    let locations = select_from(json, Vec::new(), &selectors);
+    if !locations.is_empty() {
+        return Ok(locations);
+    }
+
+    if let Some((Selector::Child(name), parent_selectors)) = selectors.split_last() {
+        let created: Vec<Vec<Location>> = select_from(json, Vec::new(), parent_selectors)
+            .into_iter()
+            .filter(|parent| value_at(json, parent).is_some_and(Value::is_object))
+            .map(|mut parent| {
+                parent.push(Location::Key(name.clone()));
+                parent
+            })
+            .collect();
+        if !created.is_empty() {
+            return Ok(created);
+        }
+    }
+
+    Err(JsonPathError::PathNotFound(format!(
+        "No values match '{}'",
+        expr
+    )))
+}
+
+/// Set every value matching a query expression, returning the number of values set
+///
+/// When nothing matches and the last selector is a name, the member is created
+/// in every object matched by the rest of the expression.
+pub fn set_all(json: &mut Value, expr: &str, new_value: Value) -> Result<usize, JsonPathError> {
+    let locations = write_locations(json, expr)?;
+
+    let mut count = 0;
+    for location in &locations {
+        let Some((last, parent)) = location.split_last() else {
+            *json = new_value.clone();
+            count += 1;
+            continue;
+        };
+        match (value_at_mut(json, parent), last) {
+            (Some(Value::Object(obj)), Location::Key(key)) => {
+                obj.insert(key.clone(), new_value.clone());
+                count += 1;
+            }
+            (Some(Value::Array(arr)), Location::Index(index)) if *index < arr.len() => {
+                arr[*index] = new_value.clone();
+                count += 1;
+            }
+            _ => {}
+        }
+    }
+    Ok(count)
+}
+
+/// Delete every value matching a query expression and return the removed values
+pub fn delete_all(json: &mut Value, expr: &str) -> Result<Vec<Value>, JsonPathError> {
+    let mut locations = query_locations(json, expr)?;
+    if locations.iter().any(|location| location.is_empty()) {
+        return Err(JsonPathError::InvalidPath("Cannot remove root".to_string()));
+    }
+
+    // Remove later array elements (and deeper values) first so indices stay valid
+    locations.sort();
+    locations.dedup();
+    locations.reverse();
+
+    let mut removed = Vec::new();
+    for location in &locations {
+        let (last, parent) = location.split_last().unwrap();
+        let value = match (value_at_mut(json, parent), last) {
+            (Some(Value::Object(obj)), Location::Key(key)) => obj.remove(key),
+            (Some(Value::Array(arr)), Location::Index(index)) if *index < arr.len() => {
+                Some(arr.remove(*index))
+            }
+            _ => None,
+        };
+        removed.extend(value);
+    }
+
+    removed.reverse();
+    Ok(removed)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1565,4 +2376,87 @@ mod tests {
             }
         }
     }
+
+    fn store() -> Value {
+        json!({
+            "store": {
+                "book": [
+                    {"title": "Sayings", "price": 8.95, "tags": ["quotes"]},
+                    {"title": "Sword", "price": 12.99},
+                    {"title": "Moby Dick", "price": 8.99, "isbn": "0-553"},
+                    {"title": "Rings", "price": 22.99, "isbn": "0-395"}
+                ],
+                "bicycle": {"color": "red", "price": 19.95}
+            }
+        })
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let data = store();
+
+        let titles = query(&data, "$.store.book[?(@.price > 10)].title").unwrap();
+        assert_eq!(titles, vec![&json!("Sword"), &json!("Rings")]);
+
+        let titles = query(&data, "$.store.book[?(@.isbn && @.price < 10)].title").unwrap();
+        assert_eq!(titles, vec![&json!("Moby Dick")]);
+
+        let titles = query(&data, "$..book[?(@.title =~ /^S/)].title").unwrap();
+        assert_eq!(titles, vec![&json!("Sayings"), &json!("Sword")]);
+
+        let titles = query(&data, "$.store.book[?(!@.isbn)].title").unwrap();
+        assert_eq!(titles, vec![&json!("Sayings"), &json!("Sword")]);
+
+        let colors = query(&data, "$.store[?(@.color == 'red')].price").unwrap();
+        assert_eq!(colors, vec![&json!(19.95)]);
+    }
+
+    #[test]
+    fn test_query_slices_and_descent() {
+        let data = store();
+
+        assert_eq!(query(&data, "$..price").unwrap().len(), 5);
+        assert_eq!(
+            query(&data, "$.store.book[1:3].title").unwrap(),
+            vec![&json!("Sword"), &json!("Moby Dick")]
+        );
+        assert_eq!(
+            query(&data, "$.store.book[::-2].title").unwrap(),
+            vec![&json!("Rings"), &json!("Sword")]
+        );
+        assert_eq!(
+            query(&data, "$.store.book[-1]['title','price']").unwrap(),
+            vec![&json!("Rings"), &json!(22.99)]
+        );
+        assert_eq!(
+            query(&data, "/store/bicycle/color").unwrap(),
+            vec![&json!("red")]
+        );
+        assert!(parse_query("$.store[?(@.price >)]").is_err());
+        assert!(parse_query("store.book").is_err());
+    }
+
+    #[test]
+    fn test_set_and_delete_all() {
+        let mut data = store();
+
+        let count = set_all(&mut data, "$.store.book[?(@.price < 10)].sale", json!(true)).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(data["store"]["book"][0]["sale"], json!(true));
+        assert!(data["store"]["book"][1].get("sale").is_none());
+
+        set_all(&mut data, "$.store.bicycle.color", json!("blue")).unwrap();
+        assert_eq!(data["store"]["bicycle"]["color"], json!("blue"));
+
+        let removed = delete_all(&mut data, "$.store.book[?(@.price > 10)]").unwrap();
+        assert_eq!(removed.len(), 2);
+        assert_eq!(removed[0]["title"], json!("Sword"));
+        assert_eq!(
+            query(&data, "$.store.book[*].title").unwrap(),
+            vec![&json!("Sayings"), &json!("Moby Dick")]
+        );
+
+        assert!(delete_all(&mut data, "$").is_err());
+        assert!(set_all(&mut data, "$.missing.key", json!(1)).is_err());
+    }
 }
@@ -3,48 +3,14 @@
 //! Implementation of the LLM provider for Anthropic's Claude models.
 
 use crate::jsonpath;
-use crate::llm::{Backend, Content, LlmError, LlmResponse, Message, TokenUsage};
+use crate::llm::{
+    Backend, Capabilities, Content, LlmError, LlmResponse, Message, Sampling, TokenUsage,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::BTreeSet;
 use std::time::Duration;
 
-/// Get the token limit for an Anthropic model
-///
-/// Uses a pattern-matching approach to determine the appropriate token limit
-/// for a given model name, based on the model family and version.
-///
-/// This function supports all Anthropic Claude models with correct token limits
-/// and can be extended as new models are released. It uses a pattern-based approach
-/// rather than an explicit list to be more maintainable and future-proof.
-///
-/// Token limits are sourced from Anthropic's official documentation:
-/// https://docs.anthropic.com/claude/docs/model-comparison
-fn get_model_token_limit(model_name: &str) -> usize {
-    // Default to a conservative limit if no pattern matches
-    const DEFAULT_TOKEN_LIMIT: usize = 100_000;
-
-    // Claude 3 and newer models (generally have 200K token context)
-    // This covers all Claude 3 variants including:
-    if model_name.starts_with("claude-3") {
-        return 200_000;
-    }
-
-    // Claude 2.1 (200K token context)
-    if model_name.contains("claude-2.1") {
-        return 200_000;
-    }
-
-    // Claude 2.0 and Claude 2 base (100K token context)
-    if model_name.contains("claude-2") | model_name.contains("claude-instant") {
-        return 100_000;
-    }
-
-    // Fall back to default for any unknown models
-    // Using a conservative default ensures we don't exceed context windows
-    DEFAULT_TOKEN_LIMIT
-}
-
 // URLs and version info for the Anthropic API - using lazy initialization for protection
 use lazy_static::lazy_static;
 
@@ -83,6 +49,10 @@ struct MessageRequest {
     stop_sequences: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     thinking: Option<ThinkingConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
 }
 
 /// Response from the Anthropic API
@@ -113,6 +83,125 @@ struct CountTokensResponse {
     input_tokens: usize,
 }
 
+/// A message assembled from the events of a streamed response
+#[derive(Debug, Default)]
+struct StreamedMessage {
+    content: Vec<Content>,
+    usage: TokenUsage,
+    stop_reason: Option<String>,
+    stop_sequence: Option<String>,
+    /// Input of the tool call being streamed, as JSON text received so far
+    tool_input: String,
+}
+
+impl StreamedMessage {
+    /// Apply one server-sent event; returns whether the text content changed
+    fn apply_event(&mut self, event: &str) -> Result<bool, LlmError> {
+        let Some(data) = event.lines().find_map(|line| line.strip_prefix("data:")) else {
+            return Ok(false);
+        };
+        let data: serde_json::Value = serde_json::from_str(data.trim())
+            .map_err(|e| LlmError::ApiError(format!("Invalid Anthropic stream event: {e}")))?;
+
+        let usize_at = |value: &serde_json::Value| value.as_u64().unwrap_or(0) as usize;
+        match data["type"].as_str().unwrap_or_default() {
+            "message_start" => {
+                let usage = &data["message"]["usage"];
+                self.usage.input_tokens = usize_at(&usage["input_tokens"]);
+                self.usage.output_tokens = usize_at(&usage["output_tokens"]);
+                self.usage.cache_creation_input_tokens =
+                    usize_at(&usage["cache_creation_input_tokens"]);
+                self.usage.cache_read_input_tokens = usize_at(&usage["cache_read_input_tokens"]);
+            }
+            "content_block_start" => {
+                let block = serde_json::from_value(data["content_block"].clone()).map_err(|e| {
+                    LlmError::ApiError(format!("Invalid Anthropic content block: {e}"))
+                })?;
+                self.content.push(block);
+                return Ok(matches!(
+                    self.content.last(),
+                    Some(Content::Text { text }) if !text.is_empty()
+                ));
+            }
+            "content_block_delta" => {
+                let delta = &data["delta"];
+                match (self.content.last_mut(), delta["type"].as_str()) {
+                    (Some(Content::Text { text }), Some("text_delta")) => {
+                        text.push_str(delta["text"].as_str().unwrap_or_default());
+                        return Ok(true);
+                    }
+                    (Some(Content::Thinking { thinking, .. }), Some("thinking_delta")) => {
+                        thinking
+                            .get_or_insert_with(String::new)
+                            .push_str(delta["thinking"].as_str().unwrap_or_default());
+                    }
+                    (Some(Content::Thinking { signature, .. }), Some("signature_delta")) => {
+                        signature
+                            .get_or_insert_with(String::new)
+                            .push_str(delta["signature"].as_str().unwrap_or_default());
+                    }
+                    (Some(Content::ToolUse { .. }), Some("input_json_delta")) => {
+                        self.tool_input
+                            .push_str(delta["partial_json"].as_str().unwrap_or_default());
+                    }
+                    _ => {}
+                }
+            }
+            "content_block_stop" => {
+                let tool_input = std::mem::take(&mut self.tool_input);
+                match self.content.last_mut() {
+                    Some(Content::ToolUse { input, .. }) if !tool_input.is_empty() => {
+                        *input = serde_json::from_str(&tool_input).map_err(|e| {
+                            LlmError::ApiError(format!("Invalid Anthropic tool input: {e}"))
+                        })?;
+                    }
+                    _ => {}
+                }
+            }
+            "message_delta" => {
+                self.stop_reason = data["delta"]["stop_reason"].as_str().map(str::to_string);
+                self.stop_sequence = data["delta"]["stop_sequence"].as_str().map(str::to_string);
+                self.usage.output_tokens = usize_at(&data["usage"]["output_tokens"]);
+            }
+            "error" => {
+                return Err(LlmError::ApiError(format!(
+                    "Anthropic stream error: {}",
+                    data["error"]["message"].as_str().unwrap_or("unknown error")
+                )));
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Text generated so far
+    fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|content| match content {
+                Content::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Convert into a response, with the stop reason overridden when the
+    /// stream was not read to the end
+    fn finish(mut self, stop_reason: Option<String>) -> LlmResponse {
+        if stop_reason.is_some() {
+            // The final usage is only reported at the end; estimate the output
+            self.usage.output_tokens = self.usage.output_tokens.max(self.text().len().div_ceil(4));
+            self.stop_reason = stop_reason;
+        }
+        LlmResponse {
+            content: self.content,
+            usage: Some(self.usage),
+            stop_reason: self.stop_reason,
+            stop_sequence: self.stop_sequence,
+        }
+    }
+}
+
 /// Implementation of LLM provider for Anthropic
 pub struct Anthropic {
     /// API key for Anthropic
@@ -131,7 +220,7 @@ impl Anthropic {
         Self {
             api_key,
             model,
-            client: reqwest::Client::new(),
+            client: crate::net::client(),
         }
     }
 
@@ -164,44 +253,24 @@ impl Anthropic {
         with_jitter.min(Self::MAX_RETRY_DELAY_MS)
     }
 
-    /// Send a request to the Anthropic API using the standardized retry utility
-    async fn send_api_request<T: serde::de::DeserializeOwned>(
+    /// Build an authenticated request to the Anthropic API
+    fn prepare_request(
         &self,
-        request_json: serde_json::Value,
         url: &str,
-        timeout: Duration,
-    ) -> Result<T, LlmError> {
-        use crate::llm::retry_utils::{send_api_request_with_retry, RetryConfig};
-
-        // Create retry configuration based on constants
-        let config = RetryConfig {
-            max_attempts: Self::MAX_ATTEMPTS,
-            base_delay_ms: Self::BASE_RETRY_DELAY_MS,
-            max_delay_ms: Self::MAX_RETRY_DELAY_MS,
-            timeout_secs: timeout.as_secs(),
-            use_exponential: false, // Use linear backoff as specified in TODO
-        };
-
-        // Create a request builder closure
-        let prepare_request = || {
-            self.client
-                .post(url)
-                .header("Content-Type", "application/json")
-                .header("X-Api-Key", &self.api_key)
-                .header("anthropic-version", &*ANTHROPIC_VERSION)
-                .header("anthropic-beta", "output-128k-2025-02-19")
-                .json(&request_json)
-        };
-
-        // Use the standardized retry utility
-        send_api_request_with_retry::<T, _>(&self.client, url, prepare_request, config, "Anthropic")
-            .await
+        request_json: &serde_json::Value,
+    ) -> reqwest::RequestBuilder {
+        self.client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header("X-Api-Key", &self.api_key)
+            .header("anthropic-version", &*ANTHROPIC_VERSION)
+            .header("anthropic-beta", "output-128k-2025-02-19")
+            .json(request_json)
     }
-}
 
-#[async_trait::async_trait]
-impl Backend for Anthropic {
-    async fn send_message(
+    /// Build the JSON body of a messages request
+    #[allow(clippy::too_many_arguments)]
+    fn request_json(
         &self,
         messages: &[Message],
         system: Option<&str>,
@@ -209,11 +278,22 @@ impl Backend for Anthropic {
         thinking_budget: Option<usize>,
         cache_points: Option<&BTreeSet<usize>>,
         max_tokens: Option<usize>,
-    ) -> Result<LlmResponse, LlmError> {
+        sampling: Sampling,
+    ) -> Result<serde_json::Value, LlmError> {
         // Default max tokens if not provided
         let default_max_tokens = 32768; // Large default for Claude's capabilities
         let tokens = max_tokens.unwrap_or(default_max_tokens);
 
+        // Extended thinking requires the default temperature and top_p
+        let thinking = thinking_budget.filter(|budget| *budget > 0);
+        let sampling = match thinking {
+            Some(_) if sampling != Sampling::default() => {
+                bprintln!(dev: "Sampling parameters ignored while thinking is enabled");
+                Sampling::default()
+            }
+            _ => sampling,
+        };
+
         // Create the message request
         let request = MessageRequest {
             model: self.model.clone(),
@@ -221,20 +301,16 @@ impl Backend for Anthropic {
             messages: messages.to_vec(),
             system: system.map(|s| s.to_string()),
             stop_sequences: stop_sequences.map(|s| s.to_vec()),
-            thinking: thinking_budget.and_then(|budget| {
-                if budget > 0 {
-                    Some(ThinkingConfig {
-                        budget_tokens: budget,
-                        type_: ThinkingType::Enabled,
-                    })
-                } else {
-                    None // Disable thinking when budget is 0
-                }
+            thinking: thinking.map(|budget| ThinkingConfig {
+                budget_tokens: budget,
+                type_: ThinkingType::Enabled,
             }),
+            temperature: sampling.temperature,
+            top_p: sampling.top_p,
         };
 
         // Convert to JSON and prepare for the API
-        let mut json = serde_json::to_value(request.clone())
+        let mut json = serde_json::to_value(request)
             .map_err(|e| LlmError::ApiError(format!("Failed to serialize request: {e}")))?;
 
         // Remove info field which is not part of the API schema
@@ -248,6 +324,58 @@ impl Backend for Anthropic {
                 .map_err(|e| LlmError::ApiError(format!("Failed to process request: {e}")))?;
         }
 
+        Ok(json)
+    }
+
+    /// Send a request to the Anthropic API using the standardized retry utility
+    async fn send_api_request<T: serde::de::DeserializeOwned>(
+        &self,
+        request_json: serde_json::Value,
+        url: &str,
+        timeout: Duration,
+    ) -> Result<T, LlmError> {
+        use crate::llm::retry_utils::{send_api_request_with_retry, RetryConfig};
+
+        // Create retry configuration based on constants
+        let config = RetryConfig {
+            max_attempts: Self::MAX_ATTEMPTS,
+            base_delay_ms: Self::BASE_RETRY_DELAY_MS,
+            max_delay_ms: Self::MAX_RETRY_DELAY_MS,
+            timeout_secs: timeout.as_secs(),
+            use_exponential: false, // Use linear backoff as specified in TODO
+        };
+
+        // Create a request builder closure
+        let prepare_request = || self.prepare_request(url, &request_json);
+
+        // Use the standardized retry utility
+        send_api_request_with_retry::<T, _>(&self.client, url, prepare_request, config, "Anthropic")
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl Backend for Anthropic {
+    async fn send_message(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        thinking_budget: Option<usize>,
+        cache_points: Option<&BTreeSet<usize>>,
+        max_tokens: Option<usize>,
+        sampling: Sampling,
+    ) -> Result<LlmResponse, LlmError> {
+        let json = self.request_json(
+            messages,
+            system,
+            stop_sequences,
+            thinking_budget,
+            cache_points,
+            max_tokens,
+            sampling,
+        )?;
+
         // Send the request with appropriate URL and timeout
         let response: MessageResponse = self
             .send_api_request(
@@ -266,6 +394,63 @@ impl Backend for Anthropic {
         })
     }
 
+    async fn send_message_streaming(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        thinking_budget: Option<usize>,
+        cache_points: Option<&BTreeSet<usize>>,
+        max_tokens: Option<usize>,
+        sampling: Sampling,
+        on_text: &(dyn Fn(&str) -> bool + Send + Sync),
+    ) -> Result<LlmResponse, LlmError> {
+        use crate::llm::retry_utils::{send_request_with_retry, RetryConfig};
+
+        let mut json = self.request_json(
+            messages,
+            system,
+            stop_sequences,
+            thinking_budget,
+            cache_points,
+            max_tokens,
+            sampling,
+        )?;
+        json["stream"] = json!(true);
+
+        let config = RetryConfig {
+            max_attempts: Self::MAX_ATTEMPTS,
+            base_delay_ms: Self::BASE_RETRY_DELAY_MS,
+            max_delay_ms: Self::MAX_RETRY_DELAY_MS,
+            timeout_secs: Self::REQUEST_TIMEOUT_SECS,
+            use_exponential: false,
+        };
+        let prepare_request = || self.prepare_request(&*API_URL, &json);
+        let mut response = send_request_with_retry(prepare_request, config, "Anthropic").await?;
+
+        // Server-sent events are separated by blank lines and may span chunks
+        let mut stream = StreamedMessage::default();
+        let mut pending = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| LlmError::ApiError(format!("Failed to read Anthropic stream: {e}")))?
+        {
+            pending.extend_from_slice(&chunk);
+            let mut text_changed = false;
+            while let Some(end) = pending.windows(2).position(|w| w == b"\n\n") {
+                let event: Vec<u8> = pending.drain(..end + 2).collect();
+                text_changed |= stream.apply_event(&String::from_utf8_lossy(&event))?;
+            }
+            if text_changed && !on_text(&stream.text()) {
+                // Dropping the response closes the connection
+                return Ok(stream.finish(Some(crate::llm::STOP_REASON_EARLY.to_string())));
+            }
+        }
+
+        Ok(stream.finish(None))
+    }
+
     fn name(&self) -> &str {
         "anthropic"
     }
@@ -275,7 +460,106 @@ impl Backend for Anthropic {
     }
 
     fn max_token_limit(&self) -> usize {
-        // Get the token limit based on the model name pattern
-        get_model_token_limit(&self.model)
+        crate::llm::catalog::context_window(&self.model)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        // Models missing from the catalog are assumed to be recent Claude models
+        let known = crate::llm::catalog::lookup(&self.model).is_some();
+        let catalog = Capabilities::for_model(&self.model);
+        Capabilities {
+            supports_cache_control: true,
+            supports_system_role: true,
+            supports_images: catalog.supports_images || !known,
+            supports_native_tools: catalog.supports_native_tools || !known,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streamed_message() {
+        let events = [
+            r#"event: message_start
+data: {"type":"message_start","message":{"usage":{"input_tokens":25,"output_tokens":1}}}"#,
+            r#"data: {"type":"content_block_start","index":0,"content_block":{"type":"thinking","thinking":"","signature":""}}"#,
+            r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"Hmm"}}"#,
+            r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"sig"}}"#,
+            r#"data: {"type":"content_block_start","index":1,"content_block":{"type":"text","text":""}}"#,
+            r#"data: {"type":"content_block_delta","index":1,"delta":{"type":"text_delta","text":"Hello"}}"#,
+            r#"data: {"type":"content_block_delta","index":1,"delta":{"type":"text_delta","text":" world"}}"#,
+            r#"data: {"type":"message_delta","delta":{"stop_reason":"end_turn","stop_sequence":null},"usage":{"output_tokens":15}}"#,
+            "event: ping",
+        ];
+
+        let mut stream = StreamedMessage::default();
+        let changed: Vec<bool> = events
+            .iter()
+            .map(|event| stream.apply_event(event).unwrap())
+            .collect();
+        assert_eq!(
+            changed,
+            [false, false, false, false, false, true, true, false, false]
+        );
+        assert_eq!(stream.text(), "Hello world");
+
+        let response = stream.finish(None);
+        assert_eq!(response.stop_reason.as_deref(), Some("end_turn"));
+        assert_eq!(response.usage.unwrap().output_tokens, 15);
+        assert!(matches!(
+            &response.content[0],
+            Content::Thinking { thinking: Some(t), signature: Some(s) } if t == "Hmm" && s == "sig"
+        ));
+    }
+
+    #[test]
+    fn test_streamed_tool_use() {
+        let events = [
+            r#"data: {"type":"content_block_start","index":0,"content_block":{"type":"tool_use","id":"toolu_1","name":"read","input":{}}}"#,
+            r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"{\"path\": "}}"#,
+            r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"\"README.md\"}"}}"#,
+            r#"data: {"type":"content_block_stop","index":0}"#,
+        ];
+        let mut stream = StreamedMessage::default();
+        for event in events {
+            stream.apply_event(event).unwrap();
+        }
+        let content = stream.finish(None).content;
+        assert_eq!(
+            content[0],
+            Content::ToolUse {
+                id: "toolu_1".to_string(),
+                name: "read".to_string(),
+                input: json!({ "path": "README.md" }),
+            }
+        );
+
+        // Results go back in Anthropic's own shape
+        let result = Content::ToolResult {
+            tool_use_id: "toolu_1".to_string(),
+            content: vec![Content::Text {
+                text: "# Termineer".to_string(),
+            }],
+            is_error: false,
+        };
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            json!({
+                "type": "tool_result",
+                "tool_use_id": "toolu_1",
+                "content": [{ "type": "text", "text": "# Termineer" }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_streamed_message_error() {
+        let mut stream = StreamedMessage::default();
+        let event =
+            r#"data: {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert!(stream.apply_event(event).is_err());
     }
 }
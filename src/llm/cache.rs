@@ -0,0 +1,365 @@
+//! Disk-backed cache for identical LLM requests
+//!
+//! Responses are stored under `.termineer/cache/llm/`, keyed by a SHA-256 hash of
+//! the provider, model, system prompt, messages and request parameters. Replaying a
+//! workflow with the same inputs returns the stored responses instead of calling
+//! the provider again, which keeps development iterations cheap and CI runs
+//! deterministic.
+//!
+//! The cache is off by default, since a replayed response may no longer match the
+//! state of the files it talks about. `--llm-cache` enables it with a 24 hour
+//! TTL and `--llm-cache-ttl` changes the TTL.
+
+use super::{Backend, Capabilities, LlmError, LlmResponse, Message, Sampling};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Directory where cached responses are stored, relative to the working directory
+pub const CACHE_DIR: &str = ".termineer/cache/llm";
+
+/// Default time-to-live of cached responses
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Global cache settings
+struct CacheSettings {
+    enabled: bool,
+    ttl: Duration,
+}
+
+lazy_static! {
+    static ref CACHE_SETTINGS: RwLock<CacheSettings> = RwLock::new(CacheSettings {
+        enabled: false,
+        ttl: DEFAULT_TTL,
+    });
+}
+
+/// Configure the response cache for all backends created afterwards
+pub fn configure(enabled: bool, ttl: Option<Duration>) {
+    let mut settings = CACHE_SETTINGS.write().unwrap();
+    settings.enabled = enabled;
+    settings.ttl = ttl.unwrap_or(DEFAULT_TTL);
+}
+
+/// Whether the response cache is enabled
+pub fn is_enabled() -> bool {
+    CACHE_SETTINGS.read().unwrap().enabled
+}
+
+fn ttl() -> Duration {
+    CACHE_SETTINGS.read().unwrap().ttl
+}
+
+/// A response stored on disk
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    /// Unix timestamp (seconds) when the entry was written
+    created_at: u64,
+    /// The cached response
+    response: LlmResponse,
+}
+
+/// Parameters that make up the cache key
+#[derive(Serialize)]
+struct CacheKey<'a> {
+    provider: &'a str,
+    model: &'a str,
+    system: Option<&'a str>,
+    messages: &'a [Message],
+    stop_sequences: Option<&'a [String]>,
+    thinking_budget: Option<usize>,
+    max_tokens: Option<usize>,
+    sampling: Sampling,
+    json_mode: bool,
+}
+
+impl CacheKey<'_> {
+    /// Hex digest identifying this request, stable across builds and platforms
+    fn digest(&self) -> Option<String> {
+        let value = canonicalize(serde_json::to_value(self).ok()?);
+        let serialized = serde_json::to_string(&value).ok()?;
+        let hash = Sha256::digest(serialized.as_bytes());
+        Some(hash.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+}
+
+/// Sort the keys of every object, so that equal requests serialize equally
+/// regardless of the order their fields and tool inputs were built in
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, canonicalize(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonicalize).collect()),
+        other => other,
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Backend wrapper that serves identical requests from the disk cache
+pub struct CachedBackend {
+    inner: Box<dyn Backend>,
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl CachedBackend {
+    /// Wrap a backend with the disk cache using the global TTL
+    pub fn new(inner: Box<dyn Backend>) -> Self {
+        Self {
+            inner,
+            dir: PathBuf::from(CACHE_DIR),
+            ttl: ttl(),
+        }
+    }
+
+    fn entry_path(&self, digest: &str) -> PathBuf {
+        self.dir.join(format!("{digest}.json"))
+    }
+
+    /// Load a cached response if present and not expired
+    fn load(&self, path: &Path) -> Option<LlmResponse> {
+        let content = std::fs::read_to_string(path).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&content).ok()?;
+        let age = now_secs().saturating_sub(entry.created_at);
+        if age > self.ttl.as_secs() {
+            let _ = std::fs::remove_file(path);
+            return None;
+        }
+        Some(entry.response)
+    }
+
+    /// Store a response, reporting but otherwise ignoring failures
+    fn store(&self, path: &Path, response: &LlmResponse) {
+        let entry = CacheEntry {
+            created_at: now_secs(),
+            response: response.clone(),
+        };
+        let result = std::fs::create_dir_all(&self.dir).and_then(|_| {
+            let json = serde_json::to_string(&entry)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            std::fs::write(path, json)
+        });
+        if let Err(e) = result {
+            bprintln!(debug: "Failed to write LLM cache entry {}: {}", path.display(), e);
+        }
+    }
+
+    /// Path of the cache entry for a request, if it can be keyed
+    fn cache_path(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        thinking_budget: Option<usize>,
+        max_tokens: Option<usize>,
+        sampling: Sampling,
+    ) -> Option<PathBuf> {
+        let key = CacheKey {
+            provider: self.inner.name(),
+            model: self.inner.model(),
+            system,
+            messages,
+            stop_sequences,
+            thinking_budget,
+            max_tokens,
+            sampling,
+            json_mode: super::json_mode(),
+        };
+        key.digest().map(|digest| self.entry_path(&digest))
+    }
+}
+
+#[async_trait]
+impl Backend for CachedBackend {
+    async fn send_message(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        thinking_budget: Option<usize>,
+        cache_points: Option<&BTreeSet<usize>>,
+        max_tokens: Option<usize>,
+        sampling: Sampling,
+    ) -> Result<LlmResponse, LlmError> {
+        let path = self.cache_path(
+            messages,
+            system,
+            stop_sequences,
+            thinking_budget,
+            max_tokens,
+            sampling,
+        );
+
+        if let Some(cached) = path.as_deref().and_then(|p| self.load(p)) {
+            bprintln!(debug: "LLM cache hit for {}", self.inner.model());
+            return Ok(cached);
+        }
+
+        let response = self
+            .inner
+            .send_message(
+                messages,
+                system,
+                stop_sequences,
+                thinking_budget,
+                cache_points,
+                max_tokens,
+                sampling,
+            )
+            .await?;
+
+        if let Some(path) = path.as_deref() {
+            self.store(path, &response);
+        }
+
+        Ok(response)
+    }
+
+    async fn send_message_streaming(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        thinking_budget: Option<usize>,
+        cache_points: Option<&BTreeSet<usize>>,
+        max_tokens: Option<usize>,
+        sampling: Sampling,
+        on_text: &(dyn Fn(&str) -> bool + Send + Sync),
+    ) -> Result<LlmResponse, LlmError> {
+        let path = self.cache_path(
+            messages,
+            system,
+            stop_sequences,
+            thinking_budget,
+            max_tokens,
+            sampling,
+        );
+
+        if let Some(cached) = path.as_deref().and_then(|p| self.load(p)) {
+            bprintln!(debug: "LLM cache hit for {}", self.inner.model());
+            on_text(&cached.text());
+            return Ok(cached);
+        }
+
+        let response = self
+            .inner
+            .send_message_streaming(
+                messages,
+                system,
+                stop_sequences,
+                thinking_budget,
+                cache_points,
+                max_tokens,
+                sampling,
+                on_text,
+            )
+            .await?;
+
+        // Responses cut short by the caller are not complete answers to the request
+        if response.stop_reason.as_deref() != Some(super::STOP_REASON_EARLY) {
+            if let Some(path) = path.as_deref() {
+                self.store(path, &response);
+            }
+        }
+
+        Ok(response)
+    }
+
+    fn max_token_limit(&self) -> usize {
+        self.inner.max_token_limit()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.inner.capabilities()
+    }
+
+    fn safe_input_token_limit(&self) -> usize {
+        self.inner.safe_input_token_limit()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{Content, MessageInfo};
+
+    #[test]
+    fn test_cache_key_depends_on_inputs() {
+        let messages = vec![Message::text(
+            "user",
+            "hello".to_string(),
+            MessageInfo::User,
+        )];
+        let key = |system, max_tokens, temperature| CacheKey {
+            provider: "anthropic",
+            model: "claude-3-7-sonnet-20250219",
+            system,
+            messages: &messages,
+            stop_sequences: None,
+            thinking_budget: None,
+            max_tokens,
+            sampling: Sampling {
+                temperature,
+                top_p: None,
+            },
+            json_mode: false,
+        };
+
+        let base = key(Some("sys"), None, None).digest();
+        assert_eq!(base, key(Some("sys"), None, None).digest());
+        assert_ne!(base, key(Some("other"), None, None).digest());
+        assert_ne!(base, key(Some("sys"), Some(10), None).digest());
+        assert_ne!(base, key(Some("sys"), None, Some(0.2)).digest());
+        assert_eq!(base.unwrap().len(), 64);
+
+        // Key order does not change the digest
+        assert_eq!(
+            canonicalize(serde_json::json!({"b": 1, "a": {"d": [{"f": 2, "e": 3}], "c": 4}}))
+                .to_string(),
+            r#"{"a":{"c":4,"d":[{"e":3,"f":2}]},"b":1}"#
+        );
+
+        let entry = CacheEntry {
+            created_at: 1,
+            response: LlmResponse {
+                content: vec![Content::Text {
+                    text: "hi".to_string(),
+                }],
+                usage: None,
+                stop_sequence: None,
+                stop_reason: Some("end_turn".to_string()),
+            },
+        };
+        let json = serde_json::to_string(&entry).unwrap();
+        let parsed: CacheEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.response, entry.response);
+    }
+}
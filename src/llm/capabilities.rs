@@ -0,0 +1,135 @@
+//! What a backend's API supports
+//!
+//! Agents build requests in Anthropic's shape: a separate system prompt,
+//! cache points on messages and images in the conversation. Backends report
+//! which of these they support, and [`adapt_messages`] reshapes a conversation
+//! for those that do not, instead of each backend silently dropping what it
+//! cannot send.
+
+use std::borrow::Cow;
+
+use super::{Content, Message, MessageInfo};
+
+/// Text sent in place of an image to backends without image support
+pub const IMAGE_PLACEHOLDER: &str = "[Image omitted: this model does not accept images]";
+
+/// Features of a backend's API
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Cache control markers on messages at cache points
+    pub supports_cache_control: bool,
+    /// A system prompt separate from the conversation
+    pub supports_system_role: bool,
+    /// Images in messages
+    pub supports_images: bool,
+    /// Native tool definitions; reported only, as tools are described by the
+    /// grammar in the prompt
+    #[allow(dead_code)]
+    pub supports_native_tools: bool,
+}
+
+impl Capabilities {
+    /// Image and tool support of a model according to the catalog, with a
+    /// system role and without cache control
+    pub fn for_model(model: &str) -> Self {
+        let spec = super::catalog::lookup(model);
+        Self {
+            supports_cache_control: false,
+            supports_system_role: true,
+            supports_images: spec.is_some_and(|spec| spec.vision),
+            supports_native_tools: spec.is_some_and(|spec| spec.tools),
+        }
+    }
+}
+
+/// The conversation as a backend with these capabilities can take it
+///
+/// Without a system role the system prompt is put before the first user
+/// message, and the caller sends no separate system prompt. Images are
+/// replaced by [`IMAGE_PLACEHOLDER`] for backends without image support.
+pub fn adapt_messages<'a>(
+    messages: &'a [Message],
+    system: Option<&str>,
+    capabilities: Capabilities,
+) -> Cow<'a, [Message]> {
+    let fold_system = system.filter(|_| !capabilities.supports_system_role);
+    let has_images = messages
+        .iter()
+        .any(|message| matches!(message.content, Content::Image { .. }));
+    let strip_images = has_images && !capabilities.supports_images;
+    if fold_system.is_none() && !strip_images {
+        return Cow::Borrowed(messages);
+    }
+
+    let mut messages = messages.to_vec();
+    if strip_images {
+        for message in &mut messages {
+            if matches!(message.content, Content::Image { .. }) {
+                message.content = Content::Text {
+                    text: IMAGE_PLACEHOLDER.to_string(),
+                };
+            }
+        }
+    }
+    if let Some(system) = fold_system {
+        match messages.first_mut() {
+            Some(Message {
+                role,
+                content: Content::Text { text },
+                ..
+            }) if role == "user" => *text = format!("{system}\n\n{text}"),
+            _ => messages.insert(
+                0,
+                Message::text("user", system.to_string(), MessageInfo::User),
+            ),
+        }
+    }
+    Cow::Owned(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ImageSource;
+
+    #[test]
+    fn test_adapt_messages() {
+        let messages = vec![
+            Message::text("user", "Describe this".to_string(), MessageInfo::User),
+            Message::new(
+                "user",
+                Content::Image {
+                    source: ImageSource::Base64 {
+                        media_type: "image/png".to_string(),
+                        data: "AAAA".to_string(),
+                    },
+                },
+                MessageInfo::User,
+            ),
+        ];
+        let full = Capabilities {
+            supports_cache_control: true,
+            supports_system_role: true,
+            supports_images: true,
+            supports_native_tools: true,
+        };
+        assert!(matches!(
+            adapt_messages(&messages, Some("Be brief"), full),
+            Cow::Borrowed(_)
+        ));
+
+        let adapted = adapt_messages(&messages, Some("Be brief"), Capabilities::default());
+        assert_eq!(
+            adapted[0].content,
+            Content::Text {
+                text: "Be brief\n\nDescribe this".to_string()
+            }
+        );
+        assert_eq!(
+            adapted[1].content,
+            Content::Text {
+                text: IMAGE_PLACEHOLDER.to_string()
+            }
+        );
+    }
+}
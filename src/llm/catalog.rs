@@ -0,0 +1,251 @@
+//! Catalog of known models
+//!
+//! Maps aliases such as `sonnet` or `fast` to model IDs and records each
+//! model's provider, context window, list price and whether it accepts images
+//! and native tool definitions. The built-in catalog is `models.yaml` next to
+//! this module; `models.yaml` in `~/.termineer` and then `.termineer` can add
+//! models and aliases or replace built-in ones.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use super::pricing::Price;
+
+/// The built-in catalog
+const BUILTIN_CATALOG: &str = include_str!("models.yaml");
+
+/// File name of catalog overrides in the user's and the project's `.termineer`
+pub const CATALOG_FILE: &str = "models.yaml";
+
+/// Context window assumed for models missing from the catalog, small enough for any model
+pub const DEFAULT_CONTEXT_WINDOW: usize = 8_000;
+
+lazy_static! {
+    static ref CATALOG: (Catalog, Vec<String>) = load();
+}
+
+/// What is known about a family of models
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelSpec {
+    /// Prefix of the model names this entry covers, in normalized form
+    pub id: String,
+    /// Name of the provider serving the models (see [`crate::credentials::PROVIDERS`])
+    pub provider: String,
+    /// Context window in tokens, including the response
+    pub context_window: usize,
+    /// List prices, if known
+    pub price: Option<Price>,
+    /// Whether images can be sent to the model
+    pub vision: bool,
+    /// Whether the provider's API accepts native tool definitions for the model
+    pub tools: bool,
+}
+
+/// Aliases and models
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    aliases: BTreeMap<String, String>,
+    models: Vec<ModelSpec>,
+}
+
+/// Catalog as written in a `models.yaml` file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CatalogFile {
+    #[serde(default)]
+    aliases: BTreeMap<String, String>,
+    #[serde(default)]
+    models: Vec<ModelEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModelEntry {
+    id: String,
+    provider: String,
+    context_window: usize,
+    price: Option<PriceEntry>,
+    #[serde(default)]
+    vision: bool,
+    #[serde(default)]
+    tools: bool,
+}
+
+/// Price of a model; cache prices default to the input price
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PriceEntry {
+    input: f64,
+    output: f64,
+    cache_write: Option<f64>,
+    cache_read: Option<f64>,
+}
+
+impl Catalog {
+    /// Parse a catalog from YAML
+    pub fn parse(yaml: &str) -> Result<Self, String> {
+        let file: CatalogFile = serde_yaml::from_str(yaml).map_err(|e| e.to_string())?;
+        let aliases = file
+            .aliases
+            .into_iter()
+            .map(|(alias, model)| (alias.to_lowercase(), model))
+            .collect();
+        let models = file
+            .models
+            .into_iter()
+            .map(|entry| ModelSpec {
+                id: normalize(&entry.id),
+                provider: entry.provider.to_lowercase(),
+                context_window: entry.context_window,
+                price: entry.price.map(|price| Price {
+                    input: price.input,
+                    output: price.output,
+                    cache_write: price.cache_write.unwrap_or(price.input),
+                    cache_read: price.cache_read.unwrap_or(price.input),
+                }),
+                vision: entry.vision,
+                tools: entry.tools,
+            })
+            .collect();
+        Ok(Self { aliases, models })
+    }
+
+    /// Add the aliases and models of `overrides`, replacing entries with the
+    /// same alias or ID
+    pub fn merge(&mut self, overrides: Catalog) {
+        self.aliases.extend(overrides.aliases);
+        for model in overrides.models {
+            match self.models.iter_mut().find(|known| known.id == model.id) {
+                Some(known) => *known = model,
+                None => self.models.push(model),
+            }
+        }
+    }
+
+    /// Model ID of an alias, or the model itself if it is not an alias
+    pub fn resolve<'a>(&'a self, model: &'a str) -> &'a str {
+        self.aliases
+            .get(&model.trim().to_lowercase())
+            .map(String::as_str)
+            .unwrap_or(model)
+    }
+
+    /// Entry of a model or alias, the one with the longest matching ID
+    pub fn lookup(&self, model: &str) -> Option<&ModelSpec> {
+        let name = normalize(self.resolve(model));
+        self.models
+            .iter()
+            .filter(|spec| name.starts_with(&spec.id))
+            .max_by_key(|spec| spec.id.len())
+    }
+
+    /// All aliases with the model IDs they stand for
+    pub fn aliases(&self) -> &BTreeMap<String, String> {
+        &self.aliases
+    }
+
+    /// All model entries
+    pub fn models(&self) -> &[ModelSpec] {
+        &self.models
+    }
+}
+
+/// Model name without provider prefix, lowercase and with dashes for dots
+pub fn normalize(model: &str) -> String {
+    let name = model.trim().rsplit('/').next().unwrap_or(model);
+    name.to_lowercase().replace('.', "-")
+}
+
+/// Override files, the user's before the project's
+fn override_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        files.push(home.join(".termineer").join(CATALOG_FILE));
+    }
+    files.push(PathBuf::from(".termineer").join(CATALOG_FILE));
+    files
+}
+
+/// Load the built-in catalog with the overrides that can be read
+fn load() -> (Catalog, Vec<String>) {
+    let mut catalog = Catalog::parse(BUILTIN_CATALOG).expect("the built-in catalog is valid");
+    let mut problems = Vec::new();
+    for path in override_files() {
+        let Ok(yaml) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        match Catalog::parse(&yaml) {
+            Ok(overrides) => catalog.merge(overrides),
+            Err(e) => problems.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    (catalog, problems)
+}
+
+/// The catalog with the user's and the project's overrides
+pub fn catalog() -> &'static Catalog {
+    &CATALOG.0
+}
+
+/// Override files that could not be parsed, with their errors
+pub fn problems() -> &'static [String] {
+    &CATALOG.1
+}
+
+/// Model ID of an alias, or the model itself if it is not an alias
+pub fn resolve(model: &str) -> String {
+    catalog().resolve(model).to_string()
+}
+
+/// Entry of a model or alias, if the catalog knows it
+pub fn lookup(model: &str) -> Option<&'static ModelSpec> {
+    catalog().lookup(model)
+}
+
+/// Context window of a model in tokens, or a conservative default for models
+/// missing from the catalog
+pub fn context_window(model: &str) -> usize {
+    lookup(model).map_or(DEFAULT_CONTEXT_WINDOW, |spec| spec.context_window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let mut catalog = Catalog::parse(BUILTIN_CATALOG).unwrap();
+        assert_eq!(catalog.resolve("Sonnet"), "claude-sonnet-4-20250514");
+        assert_eq!(catalog.resolve("gpt-4o"), "gpt-4o");
+        assert_eq!(catalog.lookup("sonnet").unwrap().id, "claude-sonnet-4");
+        assert_eq!(
+            catalog.lookup("gpt-4o-mini-2024").unwrap().id,
+            "gpt-4o-mini"
+        );
+        assert_eq!(
+            catalog
+                .lookup("openrouter/anthropic/claude-3.7-sonnet")
+                .unwrap()
+                .id,
+            "claude-3-7-sonnet"
+        );
+        assert!(catalog.lookup("llama-3").is_none());
+        assert_eq!(context_window("llama-3"), DEFAULT_CONTEXT_WINDOW);
+
+        let overrides = Catalog::parse(
+            "aliases: { fast: llama-3.3-70b }\n\
+             models:\n  - { id: llama-3.3, provider: openrouter, context_window: 131072 }\n",
+        )
+        .unwrap();
+        catalog.merge(overrides);
+        let fast = catalog.lookup("fast").unwrap();
+        assert_eq!(
+            (fast.id.as_str(), fast.context_window),
+            ("llama-3-3", 131072)
+        );
+        assert_eq!(fast.price, None);
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! Implementation of the LLM provider for Cohere's language models.
 
-use crate::llm::{Backend, Content, LlmError, LlmResponse, Message, TokenUsage};
+use crate::llm::{Backend, Content, LlmError, LlmResponse, Message, Sampling, TokenUsage};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json;
@@ -11,34 +11,6 @@ use std::collections::BTreeSet;
 // API base URL for Cohere
 const API_BASE_URL: &str = "https://api.cohere.ai/v1";
 
-/// Get the token limit for a Cohere model
-///
-/// Uses a pattern-matching approach to determine the appropriate token limit
-/// for a given model name, based on the model family and version.
-fn get_model_token_limit(model_name: &str) -> usize {
-    // Default to a conservative limit if no pattern matches
-    const DEFAULT_TOKEN_LIMIT: usize = 8_000;
-
-    // Command models and their token limits
-    if model_name.contains("command-r") || model_name == "command-r" {
-        return 128_000; // 128K tokens for Command-R
-    }
-    if model_name.contains("command-r-plus") || model_name == "command-r-plus" {
-        return 128_000; // 128K tokens for Command-R+
-    }
-    if model_name.contains("command-light") || model_name == "command-light" {
-        return 4_000; // 4K tokens for Command Light
-    }
-
-    // Legacy command model
-    if model_name.contains("command") || model_name == "command" {
-        return 4_096; // 4K tokens for Command
-    }
-
-    // Fall back to default for any unknown models
-    DEFAULT_TOKEN_LIMIT
-}
-
 /// Cohere API request type for chat
 #[derive(Debug, Serialize)]
 struct CohereRequest {
@@ -50,8 +22,13 @@ struct CohereRequest {
     max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    /// Top-p, named `p` by Cohere
+    #[serde(skip_serializing_if = "Option::is_none")]
+    p: Option<f32>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     stop_sequences: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
 }
 
 /// Cohere chat history message
@@ -110,7 +87,7 @@ impl CohereBackend {
         Self {
             api_key,
             model,
-            client: reqwest::Client::new(),
+            client: crate::net::client(),
         }
     }
 
@@ -137,6 +114,9 @@ impl CohereBackend {
             // Extract text content
             let text = match &message.content {
                 Content::Text { text } => text.clone(),
+                Content::Document { .. } | Content::ToolUse { .. } | Content::ToolResult { .. } => {
+                    message.content.fallback_text()
+                }
                 _ => continue, // Skip non-text content for now
             };
 
@@ -225,6 +205,7 @@ impl Backend for CohereBackend {
         thinking_budget: Option<usize>,
         cache_points: Option<&BTreeSet<usize>>,
         max_tokens: Option<usize>,
+        sampling: Sampling,
     ) -> Result<LlmResponse, LlmError> {
         // Cohere doesn't support thinking or cache points
         if thinking_budget.is_some() {
@@ -252,8 +233,10 @@ impl Backend for CohereBackend {
             chat_history,
             preamble,
             max_tokens: Some(tokens as u32),
-            temperature: Some(0.8), // Default temperature
+            temperature: Some(sampling.temperature.unwrap_or(0.8)), // Default temperature
+            p: sampling.top_p,
             stop_sequences: stop_seqs,
+            response_format: crate::llm::json_response_format(),
         };
 
         // Send request to the Cohere chat endpoint
@@ -291,7 +274,7 @@ impl Backend for CohereBackend {
 
 
     fn max_token_limit(&self) -> usize {
-        get_model_token_limit(&self.model)
+        crate::llm::catalog::context_window(&self.model)
     }
 
     fn name(&self) -> &str {
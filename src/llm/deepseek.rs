@@ -3,7 +3,7 @@
 //! Implementation of the LLM provider for DeepSeek's models
 //! including deepseek-chat (V3) and deepseek-reasoner (R1).
 
-use crate::llm::{Backend, Content, LlmError, LlmResponse, Message, TokenUsage};
+use crate::llm::{Backend, Content, LlmError, LlmResponse, Message, Sampling, TokenUsage};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json;
@@ -13,18 +13,6 @@ use std::time::Duration;
 // Constants for DeepSeek API
 const API_BASE_URL: &str = "https://api.deepseek.com";
 
-/// Get the token limit for a DeepSeek model
-///
-/// Uses a pattern-matching approach to determine the appropriate token limit
-/// for a given model name.
-fn get_model_token_limit(model_name: &str) -> usize {
-    match model_name {
-        "deepseek-chat" => 32_768,     // DeepSeek-V3 (32K context)
-        "deepseek-reasoner" => 32_768, // DeepSeek-R1 reasoner model (32K context)
-        _ => 16_000,                   // Default to a conservative limit if unknown model
-    }
-}
-
 /// DeepSeek API request types (Compatible with OpenAI format)
 #[derive(Debug, Serialize)]
 struct DeepSeekRequest {
@@ -40,6 +28,8 @@ struct DeepSeekRequest {
     stop: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -102,7 +92,7 @@ pub struct DeepSeekBackend {
 impl DeepSeekBackend {
     /// Create a new DeepSeek client
     pub fn new(api_key: String, model_name: String) -> Self {
-        let client = reqwest::Client::builder()
+        let client = crate::net::client_builder()
             .timeout(Duration::from_secs(300)) // 5 minute timeout for long context
             .build()
             .expect("Failed to create HTTP client");
@@ -147,7 +137,9 @@ impl DeepSeekBackend {
                 Content::Text { text } => text.clone(),
                 Content::Thinking { thinking, .. } => thinking.clone().unwrap_or_default(),
                 Content::RedactedThinking { data } => data.clone().unwrap_or_default(),
-                Content::Document { source } => source.clone(),
+                Content::Document { .. } | Content::ToolUse { .. } | Content::ToolResult { .. } => {
+                    message.content.fallback_text()
+                }
                 Content::Image { .. } => {
                     // DeepSeek doesn't support image inputs in messages
                     // Skip this message or include a placeholder
@@ -215,6 +207,7 @@ impl Backend for DeepSeekBackend {
         thinking_budget: Option<usize>,
         cache_points: Option<&BTreeSet<usize>>,
         max_tokens: Option<usize>,
+        sampling: Sampling,
     ) -> Result<LlmResponse, LlmError> {
         // DeepSeek doesn't support thinking or cache features
         if thinking_budget.is_some() {
@@ -235,11 +228,12 @@ impl Backend for DeepSeekBackend {
         let request = DeepSeekRequest {
             model: self.model_name.clone(),
             messages: deepseek_messages,
-            temperature: Some(0.7), // Default temperature
+            temperature: Some(sampling.temperature.unwrap_or(0.7)), // Default temperature
             max_tokens: max_tokens.map(|t| t as u32),
-            top_p: Some(0.95), // Default top_p
+            top_p: Some(sampling.top_p.unwrap_or(0.95)), // Default top_p
             stop,
             stream: None, // Not using streaming in this implementation
+            response_format: crate::llm::json_response_format(),
         };
 
         // Send the request to the chat completions endpoint
@@ -324,7 +318,7 @@ impl Backend for DeepSeekBackend {
 
 
     fn max_token_limit(&self) -> usize {
-        get_model_token_limit(&self.model_name)
+        crate::llm::catalog::context_window(&self.model_name)
     }
 
     fn name(&self) -> &str {
@@ -339,6 +333,7 @@ impl Backend for DeepSeekBackend {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::llm::catalog::{context_window, DEFAULT_CONTEXT_WINDOW};
     use crate::llm::MessageInfo;
 
     #[test]
@@ -366,8 +361,8 @@ mod tests {
 
     #[test]
     fn test_model_token_limits() {
-        assert_eq!(get_model_token_limit("deepseek-chat"), 32_768);
-        assert_eq!(get_model_token_limit("deepseek-reasoner"), 32_768);
-        assert_eq!(get_model_token_limit("unknown-model"), 16_000);
+        assert_eq!(context_window("deepseek-chat"), 32_768);
+        assert_eq!(context_window("deepseek-reasoner"), 32_768);
+        assert_eq!(context_window("unknown-model"), DEFAULT_CONTEXT_WINDOW);
     }
 }
@@ -0,0 +1,386 @@
+//! Models available from the configured providers
+//!
+//! Each provider with an API key is asked for its models, and the models it
+//! lists are merged with the catalog (see [`super::catalog`]): the provider's
+//! own context size and price are preferred, the catalog fills in the rest.
+//! Catalog entries the provider does not list, or of providers without an API
+//! key, are reported with why they are not available.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::catalog::{self, ModelSpec};
+use crate::credentials::{self, PROVIDERS};
+
+/// Time allowed for each provider to list its models
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Whether a model can be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Availability {
+    /// The provider lists the model
+    Available,
+    /// The provider was asked, but does not list the model
+    NotListed,
+    /// The provider has no API key
+    NoApiKey,
+    /// The provider could not be asked
+    Unknown,
+}
+
+impl Availability {
+    fn label(self) -> &'static str {
+        match self {
+            Availability::Available => "yes",
+            Availability::NotListed => "not listed",
+            Availability::NoApiKey => "no API key",
+            Availability::Unknown => "unknown",
+        }
+    }
+}
+
+/// A model of a provider
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelListing {
+    pub provider: String,
+    /// Model ID as listed by the provider, or the catalog ID of a model family
+    pub id: String,
+    pub availability: Availability,
+    /// Context window in tokens
+    pub context_window: Option<usize>,
+    /// List price of input tokens in USD per million
+    pub input_price: Option<f64>,
+    /// List price of output tokens in USD per million
+    pub output_price: Option<f64>,
+}
+
+/// Result of asking one provider for its models
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderStatus {
+    pub provider: String,
+    pub configured: bool,
+    /// Why the models could not be listed
+    pub error: Option<String>,
+}
+
+/// Models of all providers, with the catalog's aliases
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelReport {
+    pub providers: Vec<ProviderStatus>,
+    pub models: Vec<ModelListing>,
+    pub aliases: std::collections::BTreeMap<String, String>,
+}
+
+/// A model as listed by a provider's models endpoint
+#[derive(Debug, Clone, PartialEq)]
+struct RemoteModel {
+    id: String,
+    context_window: Option<usize>,
+    /// Input and output price in USD per million tokens
+    price: Option<(f64, f64)>,
+}
+
+/// List the models of all providers, or only of `only`
+pub async fn list_models(only: Option<&str>) -> ModelReport {
+    let providers: Vec<&str> = PROVIDERS
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| only.is_none() || only == Some(*name))
+        .collect();
+    let results =
+        futures::future::join_all(providers.iter().map(|provider| fetch_provider(provider))).await;
+
+    let mut report = ModelReport {
+        providers: Vec::new(),
+        models: Vec::new(),
+        aliases: catalog::catalog().aliases().clone(),
+    };
+    for (provider, result) in providers.into_iter().zip(results) {
+        let configured = !matches!(result, Fetched::NoApiKey);
+        let (remote, availability, error) = match result {
+            Fetched::Models(models) => (models, Availability::NotListed, None),
+            Fetched::NoApiKey => (Vec::new(), Availability::NoApiKey, None),
+            Fetched::Failed(error) => (Vec::new(), Availability::Unknown, Some(error)),
+        };
+        let known = catalog::catalog()
+            .models()
+            .iter()
+            .filter(|spec| spec.provider == provider);
+        report
+            .models
+            .extend(merge(provider, remote, known, availability));
+        report.providers.push(ProviderStatus {
+            provider: provider.to_string(),
+            configured,
+            error,
+        });
+    }
+    report
+}
+
+enum Fetched {
+    Models(Vec<RemoteModel>),
+    NoApiKey,
+    Failed(String),
+}
+
+async fn fetch_provider(provider: &str) -> Fetched {
+    let Ok(api_key) = credentials::api_key(provider) else {
+        return Fetched::NoApiKey;
+    };
+    match fetch_models(provider, &api_key).await {
+        Ok(models) => Fetched::Models(models),
+        Err(e) => Fetched::Failed(e),
+    }
+}
+
+/// Ask a provider's models endpoint for its models
+async fn fetch_models(provider: &str, api_key: &str) -> Result<Vec<RemoteModel>, String> {
+    let client = crate::net::client_builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let request = match provider {
+        "anthropic" => client
+            .get("https://api.anthropic.com/v1/models?limit=1000")
+            .header("X-Api-Key", api_key)
+            .header("anthropic-version", "2023-06-01"),
+        "google" => client
+            .get("https://generativelanguage.googleapis.com/v1beta/models?pageSize=1000")
+            .query(&[("key", api_key)]),
+        "openai" => client
+            .get("https://api.openai.com/v1/models")
+            .bearer_auth(api_key),
+        "openrouter" => client
+            .get("https://openrouter.ai/api/v1/models")
+            .bearer_auth(api_key),
+        "deepseek" => client
+            .get("https://api.deepseek.com/models")
+            .bearer_auth(api_key),
+        "cohere" => client
+            .get("https://api.cohere.ai/v1/models?page_size=1000")
+            .bearer_auth(api_key),
+        "grok" => client
+            .get("https://api.x.ai/v1/models")
+            .bearer_auth(api_key),
+        other => return Err(format!("{other} has no models endpoint")),
+    };
+
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("the models endpoint answered {status}"));
+    }
+    let json: Value = response.json().await.map_err(|e| e.to_string())?;
+    Ok(parse_models(provider, &json))
+}
+
+/// Models in a models endpoint's response
+fn parse_models(provider: &str, json: &Value) -> Vec<RemoteModel> {
+    // Google and Cohere list `models`, the others `data`
+    let entries = json["data"]
+        .as_array()
+        .or_else(|| json["models"].as_array())
+        .cloned()
+        .unwrap_or_default();
+    let per_million = |price: &Value| {
+        let price = match price {
+            Value::String(text) => text.parse::<f64>().ok()?,
+            other => other.as_f64()?,
+        };
+        Some(price * 1_000_000.0)
+    };
+
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let id = match provider {
+                "google" => entry["name"].as_str()?.trim_start_matches("models/"),
+                "cohere" => entry["name"].as_str()?,
+                _ => entry["id"].as_str()?,
+            };
+            let context_window = ["context_length", "inputTokenLimit", "context_window"]
+                .iter()
+                .find_map(|key| entry[key].as_u64())
+                .map(|tokens| tokens as usize);
+            // Only OpenRouter lists prices, per token
+            let pricing = &entry["pricing"];
+            let price = per_million(&pricing["prompt"]).zip(per_million(&pricing["completion"]));
+            Some(RemoteModel {
+                id: id.to_string(),
+                context_window,
+                price,
+            })
+        })
+        .collect()
+}
+
+/// Listings of a provider's models and of catalog entries it does not list
+///
+/// `unlisted` is the availability of catalog entries that match none of the
+/// provider's models.
+fn merge<'a>(
+    provider: &str,
+    remote: Vec<RemoteModel>,
+    known: impl Iterator<Item = &'a ModelSpec>,
+    unlisted: Availability,
+) -> Vec<ModelListing> {
+    let remote_names: Vec<String> = remote.iter().map(|m| catalog::normalize(&m.id)).collect();
+    let mut listings: Vec<ModelListing> = remote
+        .into_iter()
+        .map(|model| {
+            let spec = catalog::lookup(&model.id);
+            let catalog_price = spec.and_then(|spec| spec.price);
+            let (input_price, output_price) = match model.price {
+                Some((input, output)) => (Some(input), Some(output)),
+                None => (
+                    catalog_price.map(|price| price.input),
+                    catalog_price.map(|price| price.output),
+                ),
+            };
+            ModelListing {
+                provider: provider.to_string(),
+                context_window: model
+                    .context_window
+                    .or_else(|| spec.map(|spec| spec.context_window)),
+                id: model.id,
+                availability: Availability::Available,
+                input_price,
+                output_price,
+            }
+        })
+        .collect();
+    listings.sort_by(|a, b| a.id.cmp(&b.id));
+
+    for spec in known {
+        if remote_names.iter().any(|name| name.starts_with(&spec.id)) {
+            continue;
+        }
+        listings.push(ModelListing {
+            provider: provider.to_string(),
+            id: spec.id.clone(),
+            availability: unlisted,
+            context_window: Some(spec.context_window),
+            input_price: spec.price.map(|price| price.input),
+            output_price: spec.price.map(|price| price.output),
+        });
+    }
+    listings
+}
+
+/// Table of the models, followed by provider errors and the aliases
+pub fn format_table(report: &ModelReport) -> String {
+    let rows: Vec<[String; 6]> = report
+        .models
+        .iter()
+        .map(|model| {
+            let price = |price: Option<f64>| price.map_or("-".to_string(), |p| format!("{p:.2}"));
+            [
+                model.provider.clone(),
+                model.id.clone(),
+                model.availability.label().to_string(),
+                model
+                    .context_window
+                    .map_or("-".to_string(), |tokens| format!("{}K", tokens / 1000)),
+                price(model.input_price),
+                price(model.output_price),
+            ]
+        })
+        .collect();
+    let header = [
+        "PROVIDER",
+        "MODEL",
+        "AVAILABLE",
+        "CONTEXT",
+        "INPUT $/M",
+        "OUTPUT $/M",
+    ];
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut output = String::new();
+    for row in std::iter::once(header.map(str::to_string)).chain(rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(column, (cell, width))| {
+                // Left-align the names, right-align the numbers
+                if column < 3 {
+                    format!("{cell:width$}")
+                } else {
+                    format!("{cell:>width$}")
+                }
+            })
+            .collect();
+        output.push_str(cells.join("  ").trim_end());
+        output.push('\n');
+    }
+
+    for status in &report.providers {
+        if let Some(error) = &status.error {
+            output.push_str(&format!(
+                "\nCould not list the models of {}: {error}",
+                status.provider
+            ));
+        }
+    }
+    if !report.aliases.is_empty() {
+        output.push_str("\nAliases:\n");
+        for (alias, model) in &report.aliases {
+            output.push_str(&format!("  {alias} -> {model}\n"));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_merge() {
+        let json = serde_json::json!({
+            "data": [
+                { "id": "anthropic/claude-3.7-sonnet", "context_length": 200000,
+                  "pricing": { "prompt": "0.000003", "completion": "0.000015" } },
+                { "id": "meta-llama/llama-3.3-70b-instruct", "context_length": 131072 },
+            ]
+        });
+        let remote = parse_models("openrouter", &json);
+        assert_eq!(remote.len(), 2);
+        let (input, output) = remote[0].price.unwrap();
+        assert!((input - 3.0).abs() < 1e-9 && (output - 15.0).abs() < 1e-9);
+
+        let google = parse_models(
+            "google",
+            &serde_json::json!({ "models": [{ "name": "models/gemini-2.5-flash", "inputTokenLimit": 1048576 }] }),
+        );
+        assert_eq!(google[0].id, "gemini-2.5-flash");
+
+        // Anthropic does not list prices or context sizes; the catalog fills them in
+        let anthropic = parse_models(
+            "anthropic",
+            &serde_json::json!({ "data": [{ "id": "claude-3-7-sonnet-20250219" }] }),
+        );
+        let known = catalog::catalog()
+            .models()
+            .iter()
+            .filter(|spec| spec.provider == "anthropic");
+        let listings = merge("anthropic", anthropic, known, Availability::NotListed);
+        assert_eq!(listings[0].availability, Availability::Available);
+        assert_eq!(listings[0].context_window, Some(200_000));
+        assert_eq!(listings[0].input_price, Some(3.0));
+        assert!(listings
+            .iter()
+            .any(|m| m.id == "claude-opus-4" && m.availability == Availability::NotListed));
+        assert!(!listings.iter().any(|m| m.id == "claude-3-7-sonnet"));
+    }
+}
@@ -5,9 +5,14 @@
 
 use crate::config::Config;
 use crate::llm::anthropic::Anthropic;
+use crate::llm::cache::{self, CachedBackend};
 use crate::llm::cohere::CohereBackend;
 use crate::llm::deepseek::DeepSeekBackend;
 use crate::llm::grok::GrokBackend;
+use crate::llm::keys::KeyRotatingBackend;
+use crate::llm::ledger::{self, RecordingBackend};
+use crate::llm::limiter::ConcurrencyLimitedBackend;
+use crate::llm::mock::{MockBackend, MOCK_MODEL_PREFIX};
 use crate::llm::openai::OpenAIBackend; // Import OpenAIBackend
 use crate::llm::openrouter::OpenRouterBackend;
 use crate::llm::{Backend, LlmError};
@@ -34,10 +39,34 @@ pub enum Provider {
     Unknown(String),
 }
 
-/// Resolve OpenAI API key from environment variables
-fn resolve_openai_api_key() -> Result<String, LlmError> {
-    env::var("OPENAI_API_KEY")
-        .map_err(|_| LlmError::ConfigError("OPENAI_API_KEY environment variable not set".into()))
+impl Provider {
+    /// Provider of an explicit `provider/model` prefix or a catalog entry
+    fn from_name(name: &str) -> Self {
+        match name.trim().to_lowercase().as_str() {
+            "anthropic" => Provider::Anthropic,
+            "openai" => Provider::OpenAI,
+            "google" => Provider::Google,
+            "openrouter" => Provider::OpenRouter,
+            "deepseek" => Provider::DeepSeek,
+            "cohere" => Provider::Cohere,
+            "grok" | "xai" => Provider::Grok,
+            other => Provider::Unknown(other.to_string()),
+        }
+    }
+
+    /// Name under which the provider's API keys are stored (see [`crate::credentials`])
+    fn credential_name(&self) -> Option<&'static str> {
+        match self {
+            Provider::Anthropic => Some("anthropic"),
+            Provider::OpenAI => Some("openai"),
+            Provider::Google => Some("google"),
+            Provider::OpenRouter => Some("openrouter"),
+            Provider::DeepSeek => Some("deepseek"),
+            Provider::Cohere => Some("cohere"),
+            Provider::Grok => Some("grok"),
+            Provider::Unknown(_) => None,
+        }
+    }
 }
 
 /// Model information after parsing
@@ -50,9 +79,51 @@ struct ModelInfo {
 
 /// Create an LLM backend from configuration, inferring the provider from model name
 pub fn create_backend(config: &Config) -> Result<Box<dyn Backend>, LlmError> {
-    // Create the backend directly using the requested model
+    // Scripted playback backend for tests - never cached or rate limited
+    if config.model.starts_with(MOCK_MODEL_PREFIX) {
+        return Ok(Box::new(MockBackend::from_model(&config.model)?));
+    }
+
+    // Create the backend directly using the requested model or its alias
     // No model restrictions based on app mode - all users can access all models
-    infer_backend_from_model(&config.model)
+    let backend = infer_backend_from_model(&super::catalog::resolve(&config.model))?;
+
+    // Queue requests through the shared per-provider concurrency limiter
+    let backend: Box<dyn Backend> = Box::new(ConcurrencyLimitedBackend::new(backend));
+
+    // Record the usage of requests answered by the provider in the ledger
+    let backend: Box<dyn Backend> = if ledger::is_enabled() {
+        Box::new(RecordingBackend::new(backend))
+    } else {
+        backend
+    };
+
+    // Serve identical requests from the disk cache without waiting in the queue
+    if cache::is_enabled() {
+        Ok(Box::new(CachedBackend::new(backend)))
+    } else {
+        Ok(backend)
+    }
+}
+
+/// Check that a model's provider is known and has an API key, without
+/// creating a backend
+pub fn check_model(model_str: &str) -> Result<(), String> {
+    if model_str.starts_with(MOCK_MODEL_PREFIX) {
+        return Ok(());
+    }
+    let model_info = parse_model_string(&super::catalog::resolve(model_str));
+    let Some(credential) = model_info.provider.credential_name() else {
+        let mut message = unknown_model_message(model_str, &model_info.provider);
+        if let Provider::Unknown(provider) = &model_info.provider {
+            let providers = crate::credentials::PROVIDERS.iter().map(|(name, _)| *name);
+            if let Some(suggestion) = crate::config::suggest(provider, providers) {
+                message.push_str(&format!("\nDid you mean '{suggestion}'?"));
+            }
+        }
+        return Err(message);
+    };
+    crate::credentials::api_keys(credential).map(|_| ())
 }
 
 /// Parse a model string which may be in either format:
@@ -73,18 +144,8 @@ fn parse_model_string(model_str: &str) -> ModelInfo {
         }
 
         // Extract provider and model for non-OpenRouter providers
-        let provider_type = match provider.trim().to_lowercase().as_str() {
-            "anthropic" => Provider::Anthropic,
-            "openai" => Provider::OpenAI, // Handle explicit openai/ prefix
-            "google" => Provider::Google,
-            "deepseek" => Provider::DeepSeek,
-            "cohere" => Provider::Cohere,
-            "grok" | "xai" => Provider::Grok,
-            other => Provider::Unknown(other.to_string()),
-        };
-
         return ModelInfo {
-            provider: provider_type,
+            provider: Provider::from_name(provider),
             model_name: model.trim().to_string(),
         };
     }
@@ -104,6 +165,9 @@ fn parse_model_string(model_str: &str) -> ModelInfo {
         Provider::Cohere
     } else if is_grok_model(model_str) {
         Provider::Grok
+    } else if let Some(spec) = super::catalog::lookup(model_str) {
+        // Models added to the catalog name their provider
+        Provider::from_name(&spec.provider)
     } else {
         Provider::Unknown(String::new())
     };
@@ -115,77 +179,74 @@ fn parse_model_string(model_str: &str) -> ModelInfo {
 }
 
 /// Infer and create the appropriate backend based on model name
+///
+/// Providers with several API keys get one backend per key, wrapped in a
+/// [`KeyRotatingBackend`].
 fn infer_backend_from_model(model_str: &str) -> Result<Box<dyn Backend>, LlmError> {
     let model_info = parse_model_string(model_str);
 
+    let Some(credential) = model_info.provider.credential_name() else {
+        return Err(LlmError::ConfigError(unknown_model_message(
+            model_str,
+            &model_info.provider,
+        )));
+    };
+    let mut backends = resolve_api_keys(credential)?
+        .into_iter()
+        .map(|api_key| create_provider_backend(&model_info, api_key))
+        .collect::<Vec<_>>();
+
+    if backends.len() == 1 {
+        Ok(backends.remove(0))
+    } else {
+        Ok(Box::new(KeyRotatingBackend::new(credential, backends)))
+    }
+}
+
+/// Create the backend of a known provider with one API key
+fn create_provider_backend(model_info: &ModelInfo, api_key: String) -> Box<dyn Backend> {
+    let model_name = model_info.model_name.clone();
     match model_info.provider {
-        Provider::Anthropic => {
-            let api_key = resolve_anthropic_api_key()?;
-            Ok(Box::new(Anthropic::new(api_key, model_info.model_name)))
-        }
-        Provider::OpenAI => { // Add OpenAI provider case
-            let api_key = resolve_openai_api_key()?;
-            Ok(Box::new(OpenAIBackend::new(api_key, model_info.model_name)))
-        }
-        Provider::Google => {
-            let api_key = resolve_google_api_key()?;
-            // Pass model name directly without translation
-            Ok(Box::new(crate::llm::gemini::GeminiBackend::new(
-                api_key,
-                model_info.model_name,
-            )))
-        }
-        Provider::DeepSeek => {
-            let api_key = resolve_deepseek_api_key()?;
-            Ok(Box::new(DeepSeekBackend::new(
-                api_key,
-                model_info.model_name,
-            )))
-        }
-        Provider::Cohere => {
-            let api_key = resolve_cohere_api_key()?;
-            Ok(Box::new(CohereBackend::new(api_key, model_info.model_name)))
-        }
-        Provider::Grok => {
-            let api_key = resolve_grok_api_key()?;
-            Ok(Box::new(GrokBackend::new(api_key, model_info.model_name)))
-        }
+        Provider::Anthropic => Box::new(Anthropic::new(api_key, model_name)),
+        Provider::OpenAI => Box::new(OpenAIBackend::new(api_key, model_name)),
+        // Pass model name directly without translation
+        Provider::Google => Box::new(crate::llm::gemini::GeminiBackend::new(api_key, model_name)),
+        Provider::DeepSeek => Box::new(DeepSeekBackend::new(api_key, model_name)),
+        Provider::Cohere => Box::new(CohereBackend::new(api_key, model_name)),
+        Provider::Grok => Box::new(GrokBackend::new(api_key, model_name)),
         Provider::OpenRouter => {
-            let api_key = resolve_openrouter_api_key()?;
-
             // Get optional site URL and name for ranking on OpenRouter
             let site_url = env::var("OPENROUTER_SITE_URL").ok();
             let site_name = env::var("OPENROUTER_SITE_NAME").ok();
 
-            Ok(Box::new(OpenRouterBackend::new(
-                api_key,
-                model_info.model_name,
-                site_url,
-                site_name,
-            )))
+            Box::new(OpenRouterBackend::new(
+                api_key, model_name, site_url, site_name,
+            ))
         }
-        Provider::Unknown(provider) => {
-            let provider_msg = if provider.is_empty() {
-                format!("Unknown model '{model_str}'. Cannot determine provider.")
-            } else {
-                format!(
-                    "Unknown provider '{provider}' specified in '{model_str}'"
-                )
-            };
+        Provider::Unknown(_) => unreachable!("unknown providers have no credentials"),
+    }
+}
 
-            Err(LlmError::ConfigError(format!(
-                "{provider_msg}. Currently supporting these providers:\n\
-                 - Anthropic models: 'claude-3-opus', 'claude-3-sonnet', etc.\n\
-                 - Google models: 'gemini-1.5-pro', 'gemini-1.0-pro', etc.\n\
-                 - OpenAI models: 'gpt-4o', 'gpt-4-turbo', 'gpt-3.5-turbo', etc.\n\
-                 - DeepSeek models: 'deepseek-chat', 'deepseek-reasoner'\n\
-                 - Cohere models: 'command-r', 'command-r-plus', 'command-light', etc.\n\
-                 - Grok models: 'grok-2-1212', 'grok-beta'\n\
-                 - OpenRouter: 'openrouter/openai/gpt-4o', 'openrouter/anthropic/claude-3-opus', etc.\n\
-                 - Explicit provider format: 'openai/gpt-4o', 'anthropic/claude-3-opus', 'google/gemini-1.5-pro', 'grok/grok-2-1212'"
-            )))
+/// Error message for a model whose provider cannot be determined
+fn unknown_model_message(model_str: &str, provider: &Provider) -> String {
+    let provider_msg = match provider {
+        Provider::Unknown(provider) if !provider.is_empty() => {
+            format!("Unknown provider '{provider}' specified in '{model_str}'")
         }
-    }
+        _ => format!("Unknown model '{model_str}'. Cannot determine provider."),
+    };
+
+    format!(
+        "{provider_msg}. Currently supporting these providers:\n\
+         - Anthropic models: 'claude-3-opus', 'claude-3-sonnet', etc.\n\
+         - Google models: 'gemini-1.5-pro', 'gemini-1.0-pro', etc.\n\
+         - OpenAI models: 'gpt-4o', 'gpt-4-turbo', 'gpt-3.5-turbo', etc.\n\
+         - DeepSeek models: 'deepseek-chat', 'deepseek-reasoner'\n\
+         - Cohere models: 'command-r', 'command-r-plus', 'command-light', etc.\n\
+         - Grok models: 'grok-2-1212', 'grok-beta'\n\
+         - OpenRouter: 'openrouter/openai/gpt-4o', 'openrouter/anthropic/claude-3-opus', etc.\n\
+         - Explicit provider format: 'openai/gpt-4o', 'anthropic/claude-3-opus', 'google/gemini-1.5-pro', 'grok/grok-2-1212'"
+    )
 }
 
 /// Determine if a model name belongs to the Anthropic Claude family
@@ -236,39 +297,7 @@ fn is_grok_model(model: &str) -> bool {
     model.starts_with("grok-") || model == "grok-2-1212" || model == "grok-beta"
 }
 
-/// Resolve Anthropic API key from environment variables
-fn resolve_anthropic_api_key() -> Result<String, LlmError> {
-    env::var("ANTHROPIC_API_KEY")
-        .map_err(|_| LlmError::ConfigError("ANTHROPIC_API_KEY environment variable not set".into()))
-}
-
-/// Resolve Google API key from environment variables
-fn resolve_google_api_key() -> Result<String, LlmError> {
-    env::var("GOOGLE_API_KEY")
-        .map_err(|_| LlmError::ConfigError("GOOGLE_API_KEY environment variable not set".into()))
-}
-
-/// Resolve OpenRouter API key from environment variables
-fn resolve_openrouter_api_key() -> Result<String, LlmError> {
-    env::var("OPENROUTER_API_KEY").map_err(|_| {
-        LlmError::ConfigError("OPENROUTER_API_KEY environment variable not set".into())
-    })
-}
-
-/// Resolve DeepSeek API key from environment variables
-fn resolve_deepseek_api_key() -> Result<String, LlmError> {
-    env::var("DEEPSEEK_API_KEY")
-        .map_err(|_| LlmError::ConfigError("DEEPSEEK_API_KEY environment variable not set".into()))
-}
-
-/// Resolve Cohere API key from environment variables
-fn resolve_cohere_api_key() -> Result<String, LlmError> {
-    env::var("COHERE_API_KEY")
-        .map_err(|_| LlmError::ConfigError("COHERE_API_KEY environment variable not set".into()))
-}
-
-/// Resolve Grok API key from environment variables
-fn resolve_grok_api_key() -> Result<String, LlmError> {
-    env::var("GROK_API_KEY")
-        .map_err(|_| LlmError::ConfigError("GROK_API_KEY environment variable not set".into()))
+/// Resolve a provider's API keys from the OS keychain or its environment variable
+fn resolve_api_keys(provider: &str) -> Result<Vec<String>, LlmError> {
+    crate::credentials::api_keys(provider).map_err(LlmError::ConfigError)
 }
@@ -5,7 +5,7 @@
 //! Supports Gemini 1.0, 1.5, and 2.0 model families with
 //! appropriate token context limits.
 
-use crate::llm::{Backend, Content, LlmError, LlmResponse, Message, TokenUsage};
+use crate::llm::{Backend, Content, LlmError, LlmResponse, Message, Sampling, TokenUsage};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json;
@@ -16,33 +16,6 @@ use std::time::Duration;
 // Constants for Gemini API
 const API_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
 
-/// Get the token limit for a Gemini model
-///
-/// Uses a pattern-matching approach to determine the appropriate token limit
-/// for a given model name, based on the model family and version.
-fn get_model_token_limit(model_name: &str) -> usize {
-    // Default to a conservative limit if no pattern matches
-    const DEFAULT_TOKEN_LIMIT: usize = 32_000;
-
-    // Gemini 2.0 models (2M token context)
-    if model_name.contains("gemini-2.0") || model_name.starts_with("gemini-2") {
-        return 2_097_152; // 2M tokens
-    }
-
-    // Gemini 1.5 models (1M token context)
-    if model_name.contains("gemini-1.5") || model_name.starts_with("gemini-1.5") {
-        return 1_048_576; // 1M tokens
-    }
-
-    // Gemini 1.0 Pro models (32K token context)
-    if model_name.contains("gemini-1.0-pro") {
-        return 32_768; // 32K tokens
-    }
-
-    // Fall back to default for any unknown models
-    DEFAULT_TOKEN_LIMIT
-}
-
 /// Gemini API request types
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")] // Add rename_all here too for consistency
@@ -90,6 +63,8 @@ struct GeminiGenerationConfig {
     top_k: Option<u32>,
     #[serde(skip_serializing_if = "Vec::is_empty", rename = "stopSequences")]
     stop_sequences: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "responseMimeType")]
+    response_mime_type: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -212,7 +187,7 @@ struct GeminiCountTokensRequest {
 impl GeminiBackend {
     /// Create a new Gemini client
     pub fn new(api_key: String, model_name: String) -> Self {
-        let client = reqwest::Client::builder()
+        let client = crate::net::client_builder()
             .timeout(Duration::from_secs(300)) // 5 minute timeout for long context
             .build()
             .expect("Failed to create HTTP client");
@@ -377,6 +352,9 @@ impl GeminiBackend {
 fn extract_text_content(content: &Content) -> Option<String> {
     match content {
         Content::Text { text } => Some(text.clone()),
+        Content::Document { .. } | Content::ToolUse { .. } | Content::ToolResult { .. } => {
+            Some(content.fallback_text())
+        }
         _ => None,
     }
 }
@@ -391,6 +369,7 @@ impl Backend for GeminiBackend {
         _: Option<usize>,
         _: Option<&BTreeSet<usize>>,
         max_tokens: Option<usize>,
+        sampling: Sampling,
     ) -> Result<LlmResponse, LlmError> {
 
         // Convert *all* messages and system prompt
@@ -406,10 +385,11 @@ impl Backend for GeminiBackend {
 
         let generation_config = GeminiGenerationConfig {
             max_output_tokens: Some(tokens as u32),
-            temperature: Some(0.5), // Default temperature
-            top_p: Some(0.95),      // Default top_p
-            top_k: None,            // Default top_k
+            temperature: Some(sampling.temperature.unwrap_or(0.5)), // Default temperature
+            top_p: Some(sampling.top_p.unwrap_or(0.95)),            // Default top_p
+            top_k: None,                                            // Default top_k
             stop_sequences: stop_sequences.map(|seqs| seqs.to_vec()).unwrap_or_default(),
+            response_mime_type: crate::llm::json_mode().then(|| "application/json".to_string()),
         };
 
         // Prepare the final GeminiRequest
@@ -511,7 +491,7 @@ impl Backend for GeminiBackend {
     }
 
     fn max_token_limit(&self) -> usize {
-        get_model_token_limit(&self.model_name)
+        crate::llm::catalog::context_window(&self.model_name)
     }
 
     fn name(&self) -> &str {
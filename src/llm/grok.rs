@@ -2,7 +2,7 @@
 //!
 //! Implementation of the LLM provider for xAI's Grok language models.
 
-use crate::llm::{Backend, Content, LlmError, LlmResponse, Message, TokenUsage};
+use crate::llm::{Backend, Content, LlmError, LlmResponse, Message, Sampling, TokenUsage};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json;
@@ -11,22 +11,6 @@ use std::collections::BTreeSet;
 // API base URL for Grok
 const API_BASE_URL: &str = "https://api.x.ai/v1";
 
-/// Get the token limit for a Grok model
-fn get_model_token_limit(model_name: &str) -> usize {
-    const DEFAULT_TOKEN_LIMIT: usize = 32_000;
-
-    // Model-specific token limits
-    if model_name.contains("grok-2") {
-        return 128_000; // 128K context window for Grok 2
-    }
-
-    if model_name.contains("grok-beta") {
-        return 32_000; // Older model has smaller context window
-    }
-
-    DEFAULT_TOKEN_LIMIT
-}
-
 /// Grok API request for chat completions
 #[derive(Debug, Serialize)]
 struct GrokChatRequest {
@@ -36,8 +20,12 @@ struct GrokChatRequest {
     max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     stop: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
 }
 
 /// Grok chat message format
@@ -90,7 +78,7 @@ impl GrokBackend {
         Self {
             api_key,
             model,
-            client: reqwest::Client::new(),
+            client: crate::net::client(),
         }
     }
 
@@ -111,6 +99,9 @@ impl GrokBackend {
             // Extract text content
             let text = match &message.content {
                 Content::Text { text } => text.clone(),
+                Content::Document { .. } | Content::ToolUse { .. } | Content::ToolResult { .. } => {
+                    message.content.fallback_text()
+                }
                 _ => continue, // Skip non-text content for now
             };
 
@@ -181,6 +172,7 @@ impl Backend for GrokBackend {
         thinking_budget: Option<usize>,
         cache_points: Option<&BTreeSet<usize>>,
         max_tokens: Option<usize>,
+        sampling: Sampling,
     ) -> Result<LlmResponse, LlmError> {
         // Grok doesn't support thinking or cache points
         if thinking_budget.is_some() {
@@ -206,8 +198,10 @@ impl Backend for GrokBackend {
             model: self.model.clone(),
             messages: grok_messages,
             max_tokens: Some(tokens as u32),
-            temperature: Some(0.7), // Default temperature
+            temperature: Some(sampling.temperature.unwrap_or(0.7)), // Default temperature
+            top_p: sampling.top_p,
             stop: stop_seqs,
+            response_format: crate::llm::json_response_format(),
         };
 
         // Send request to the Grok chat endpoint
@@ -245,7 +239,7 @@ impl Backend for GrokBackend {
 
 
     fn max_token_limit(&self) -> usize {
-        get_model_token_limit(&self.model)
+        crate::llm::catalog::context_window(&self.model)
     }
 
     fn name(&self) -> &str {
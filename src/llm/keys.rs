@@ -0,0 +1,421 @@
+//! Rotation across multiple API keys of one provider
+//!
+//! A provider may have several API keys: multiple lines stored with
+//! `termineer keys add <provider>`, or a comma-separated environment variable
+//! (`ANTHROPIC_API_KEY=key1,key2`). With more than one key, requests are spread
+//! across them according to `--key-rotation`:
+//! - `on-rate-limit` (default) - keep using one key and move to the next when
+//!   the provider answers 429
+//! - `round-robin` - use the next key for every request, and still move on
+//!   when a key is rate limited
+//!
+//! The position in the key list is shared by all agents using the provider.
+//! `termineer keys check` validates every configured key.
+
+use super::{Backend, Capabilities, LlmError, LlmResponse, Message, Sampling};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+/// Timeout for validating a key
+const CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How requests are distributed across a provider's keys
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyRotation {
+    /// Use the current key until it is rate limited
+    #[default]
+    OnRateLimit,
+    /// Use the next key for every request
+    RoundRobin,
+}
+
+/// Parse a key rotation strategy from the command line
+pub fn parse_key_rotation(arg: &str) -> Result<KeyRotation, String> {
+    match arg.to_lowercase().as_str() {
+        "on-rate-limit" | "429" => Ok(KeyRotation::OnRateLimit),
+        "round-robin" | "rr" => Ok(KeyRotation::RoundRobin),
+        _ => Err(format!(
+            "Unknown key rotation: {arg}. Valid options: on-rate-limit, round-robin"
+        )),
+    }
+}
+
+lazy_static! {
+    static ref ROTATION: RwLock<KeyRotation> = RwLock::new(KeyRotation::default());
+    /// Index of the key to use next, per provider
+    static ref CURSORS: Mutex<HashMap<String, Arc<AtomicUsize>>> = Mutex::new(HashMap::new());
+}
+
+tokio::task_local! {
+    /// Set while another key is available, so rate limits are reported
+    /// immediately instead of being retried on the same key
+    static FAIL_FAST_ON_RATE_LIMIT: bool;
+}
+
+/// Set the key rotation strategy for all providers
+pub fn configure(rotation: KeyRotation) {
+    *ROTATION.write().unwrap() = rotation;
+}
+
+/// Whether a rate-limited request should fail instead of waiting and retrying
+pub fn fail_fast_on_rate_limit() -> bool {
+    FAIL_FAST_ON_RATE_LIMIT
+        .try_with(|fail_fast| *fail_fast)
+        .unwrap_or(false)
+}
+
+fn provider_cursor(provider: &str) -> Arc<AtomicUsize> {
+    CURSORS
+        .lock()
+        .unwrap()
+        .entry(provider.to_string())
+        .or_default()
+        .clone()
+}
+
+/// Backend that spreads requests across one inner backend per API key
+pub struct KeyRotatingBackend {
+    backends: Vec<Box<dyn Backend>>,
+    cursor: Arc<AtomicUsize>,
+}
+
+impl KeyRotatingBackend {
+    /// Combine backends created with different keys of one provider
+    pub fn new(provider: &str, backends: Vec<Box<dyn Backend>>) -> Self {
+        assert!(!backends.is_empty(), "at least one backend is required");
+        Self {
+            backends,
+            cursor: provider_cursor(provider),
+        }
+    }
+
+    /// Send a request with the current key, moving on to the next key when
+    /// it is rate limited
+    #[allow(clippy::too_many_arguments)]
+    async fn send_rotating(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        thinking_budget: Option<usize>,
+        cache_points: Option<&BTreeSet<usize>>,
+        max_tokens: Option<usize>,
+        sampling: Sampling,
+        on_text: Option<&(dyn Fn(&str) -> bool + Send + Sync)>,
+    ) -> Result<LlmResponse, LlmError> {
+        let count = self.backends.len();
+        let start = match *ROTATION.read().unwrap() {
+            KeyRotation::RoundRobin => self.cursor.fetch_add(1, Ordering::Relaxed),
+            KeyRotation::OnRateLimit => self.cursor.load(Ordering::Relaxed),
+        };
+
+        for attempt in 0..count {
+            let index = (start + attempt) % count;
+            let is_last = attempt + 1 == count;
+            let backend = &self.backends[index];
+            let request = async {
+                match on_text {
+                    Some(on_text) => {
+                        backend
+                            .send_message_streaming(
+                                messages,
+                                system,
+                                stop_sequences,
+                                thinking_budget,
+                                cache_points,
+                                max_tokens,
+                                sampling,
+                                on_text,
+                            )
+                            .await
+                    }
+                    None => {
+                        backend
+                            .send_message(
+                                messages,
+                                system,
+                                stop_sequences,
+                                thinking_budget,
+                                cache_points,
+                                max_tokens,
+                                sampling,
+                            )
+                            .await
+                    }
+                }
+            };
+            // The last key falls back to the usual wait-and-retry behavior
+            let result = FAIL_FAST_ON_RATE_LIMIT.scope(!is_last, request).await;
+
+            match result {
+                Err(LlmError::RateLimitError { .. }) if !is_last => {
+                    let next = (index + 1) % count;
+                    self.cursor.store(next, Ordering::Relaxed);
+                    bprintln!(warn: "Rate limited on {} key {} of {}, switching to key {}",
+                             self.name(), index + 1, count, next + 1);
+                }
+                result => return result,
+            }
+        }
+
+        unreachable!("the last key always returns")
+    }
+}
+
+#[async_trait]
+impl Backend for KeyRotatingBackend {
+    async fn send_message(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        thinking_budget: Option<usize>,
+        cache_points: Option<&BTreeSet<usize>>,
+        max_tokens: Option<usize>,
+        sampling: Sampling,
+    ) -> Result<LlmResponse, LlmError> {
+        self.send_rotating(
+            messages,
+            system,
+            stop_sequences,
+            thinking_budget,
+            cache_points,
+            max_tokens,
+            sampling,
+            None,
+        )
+        .await
+    }
+
+    async fn send_message_streaming(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        thinking_budget: Option<usize>,
+        cache_points: Option<&BTreeSet<usize>>,
+        max_tokens: Option<usize>,
+        sampling: Sampling,
+        on_text: &(dyn Fn(&str) -> bool + Send + Sync),
+    ) -> Result<LlmResponse, LlmError> {
+        self.send_rotating(
+            messages,
+            system,
+            stop_sequences,
+            thinking_budget,
+            cache_points,
+            max_tokens,
+            sampling,
+            Some(on_text),
+        )
+        .await
+    }
+
+    fn max_token_limit(&self) -> usize {
+        self.backends[0].max_token_limit()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.backends[0].capabilities()
+    }
+
+    fn safe_input_token_limit(&self) -> usize {
+        self.backends[0].safe_input_token_limit()
+    }
+
+    fn name(&self) -> &str {
+        self.backends[0].name()
+    }
+
+    fn model(&self) -> &str {
+        self.backends[0].model()
+    }
+}
+
+/// Result of validating one key
+#[derive(Debug)]
+pub enum KeyStatus {
+    Valid,
+    Invalid,
+    RateLimited,
+    Error(String),
+}
+
+impl std::fmt::Display for KeyStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyStatus::Valid => write!(f, "valid"),
+            KeyStatus::Invalid => write!(f, "invalid"),
+            KeyStatus::RateLimited => write!(f, "rate limited"),
+            KeyStatus::Error(e) => write!(f, "error: {e}"),
+        }
+    }
+}
+
+/// Validation result with whatever rate-limit headroom the API reports
+#[derive(Debug)]
+pub struct KeyCheck {
+    pub status: KeyStatus,
+    pub headroom: Vec<String>,
+}
+
+/// Shorten a key for display, keeping only its start and end
+pub fn mask_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 12 {
+        return "*".repeat(chars.len());
+    }
+    let start: String = chars[..6].iter().collect();
+    let end: String = chars[chars.len() - 4..].iter().collect();
+    format!("{start}…{end}")
+}
+
+/// Remaining-quota headers, e.g. `x-ratelimit-remaining-requests` or
+/// `anthropic-ratelimit-tokens-remaining`
+fn headroom_headers(headers: &reqwest::header::HeaderMap) -> Vec<String> {
+    let mut headroom: Vec<String> = headers
+        .iter()
+        .filter(|(name, _)| {
+            let name = name.as_str();
+            name.contains("ratelimit") && name.contains("remaining")
+        })
+        .filter_map(|(name, value)| Some(format!("{}={}", name, value.to_str().ok()?)))
+        .collect();
+    headroom.sort();
+    headroom
+}
+
+/// Headroom reported in the body of key information endpoints
+fn headroom_body(provider: &str, body: &serde_json::Value) -> Vec<String> {
+    match provider {
+        "openrouter" => {
+            let data = &body["data"];
+            let mut headroom = Vec::new();
+            if let Some(remaining) = data["limit_remaining"].as_f64() {
+                headroom.push(format!("credits remaining={remaining}"));
+            }
+            if let (Some(requests), Some(interval)) = (
+                data["rate_limit"]["requests"].as_u64(),
+                data["rate_limit"]["interval"].as_str(),
+            ) {
+                headroom.push(format!("rate limit={requests}/{interval}"));
+            }
+            headroom
+        }
+        "deepseek" => body["balance_infos"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|info| {
+                Some(format!(
+                    "balance={} {}",
+                    info["total_balance"].as_str()?,
+                    info["currency"].as_str().unwrap_or("")
+                ))
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Validate a key with an inexpensive request to the provider
+pub async fn check_key(provider: &str, key: &str) -> KeyCheck {
+    let client = match crate::net::client_builder().timeout(CHECK_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            return KeyCheck {
+                status: KeyStatus::Error(e.to_string()),
+                headroom: Vec::new(),
+            }
+        }
+    };
+
+    let request = match provider {
+        "anthropic" => client
+            .get("https://api.anthropic.com/v1/models")
+            .header("x-api-key", key)
+            .header("anthropic-version", "2023-06-01"),
+        "openai" => client
+            .get("https://api.openai.com/v1/models")
+            .bearer_auth(key),
+        "google" => client
+            .get("https://generativelanguage.googleapis.com/v1beta/models")
+            .query(&[("key", key)]),
+        "openrouter" => client
+            .get("https://openrouter.ai/api/v1/key")
+            .bearer_auth(key),
+        "deepseek" => client
+            .get("https://api.deepseek.com/user/balance")
+            .bearer_auth(key),
+        "cohere" => client
+            .post("https://api.cohere.com/v1/check-api-key")
+            .bearer_auth(key),
+        "grok" => client.get("https://api.x.ai/v1/api-key").bearer_auth(key),
+        other => {
+            return KeyCheck {
+                status: KeyStatus::Error(format!("no check available for {other}")),
+                headroom: Vec::new(),
+            }
+        }
+    };
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            return KeyCheck {
+                status: KeyStatus::Error(e.to_string()),
+                headroom: Vec::new(),
+            }
+        }
+    };
+
+    let status = response.status();
+    let mut headroom = headroom_headers(response.headers());
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    headroom.extend(headroom_body(provider, &body));
+
+    let status = if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        KeyStatus::RateLimited
+    } else if matches!(status.as_u16(), 400 | 401 | 403) {
+        KeyStatus::Invalid
+    } else if !status.is_success() {
+        KeyStatus::Error(format!("HTTP {status}"))
+    } else if body["valid"] == false
+        || body["api_key_blocked"] == true
+        || body["api_key_disabled"] == true
+    {
+        // Cohere and xAI answer 200 and describe the key in the body
+        KeyStatus::Invalid
+    } else {
+        KeyStatus::Valid
+    };
+
+    KeyCheck { status, headroom }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_key() {
+        assert_eq!(mask_key("sk-ant-REDACTED"), "sk-ant…mnop");
+        assert_eq!(mask_key("short"), "*****");
+    }
+
+    #[test]
+    fn test_headroom_body() {
+        let body = serde_json::json!({
+            "data": {"limit_remaining": 12.5, "rate_limit": {"requests": 20, "interval": "10s"}}
+        });
+        assert_eq!(
+            headroom_body("openrouter", &body),
+            vec!["credits remaining=12.5", "rate limit=20/10s"]
+        );
+        assert!(headroom_body("anthropic", &body).is_empty());
+    }
+}
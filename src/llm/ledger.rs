@@ -0,0 +1,459 @@
+//! Ledger of token usage across sessions
+//!
+//! Every request answered by a provider (not served from the response cache)
+//! appends an entry with its time, project directory, model, tokens and
+//! estimated cost to `~/.termineer/usage.jsonl`. `termineer usage` sums the
+//! entries per day, model or project, e.g. `termineer usage --since 7d`.
+//!
+//! With `--monthly-budget USD`, a warning is shown when the estimated cost of
+//! the calendar month passes 80% of the budget and again when it passes the
+//! budget. `--no-usage-ledger` stops recording.
+
+use super::{Backend, Capabilities, LlmError, LlmResponse, Message, Sampling, TokenUsage};
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Local, Utc};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+/// Share of the monthly budget at which the first warning is shown
+const BUDGET_WARNING_SHARE: f64 = 0.8;
+
+/// A request recorded in the ledger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub time: DateTime<Utc>,
+    /// Working directory of the session
+    pub project: String,
+    pub model: String,
+    #[serde(flatten)]
+    pub usage: TokenUsage,
+    /// None for models without a known price
+    pub cost_usd: Option<f64>,
+}
+
+/// Global ledger settings
+struct LedgerSettings {
+    enabled: bool,
+    monthly_budget: Option<f64>,
+}
+
+/// Estimated cost of the current month, loaded from the ledger on first use
+struct MonthSpend {
+    month: (i32, u32),
+    cost: f64,
+    /// Budget warnings already shown: 1 after the first, 2 after the second
+    warnings: u8,
+}
+
+lazy_static! {
+    static ref SETTINGS: RwLock<LedgerSettings> = RwLock::new(LedgerSettings {
+        enabled: true,
+        monthly_budget: None,
+    });
+    /// Serializes appends from concurrent requests
+    static ref MONTH_SPEND: Mutex<Option<MonthSpend>> = Mutex::new(None);
+}
+
+/// Configure recording and the monthly budget for all backends created afterwards
+pub fn configure(enabled: bool, monthly_budget: Option<f64>) {
+    let mut settings = SETTINGS.write().unwrap();
+    settings.enabled = enabled;
+    settings.monthly_budget = monthly_budget;
+}
+
+/// Whether requests are recorded in the ledger
+pub fn is_enabled() -> bool {
+    SETTINGS.read().unwrap().enabled
+}
+
+fn monthly_budget() -> Option<f64> {
+    SETTINGS.read().unwrap().monthly_budget
+}
+
+fn ledger_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".termineer").join("usage.jsonl"))
+}
+
+fn current_project() -> String {
+    std::env::current_dir()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default()
+}
+
+fn month_of(time: &DateTime<Utc>) -> (i32, u32) {
+    let local = time.with_timezone(&Local);
+    (local.year(), local.month())
+}
+
+/// All entries of the ledger; unreadable lines are skipped
+pub fn load() -> Vec<LedgerEntry> {
+    let Some(content) = ledger_path().and_then(|path| std::fs::read_to_string(path).ok()) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Estimated cost of the entries of a calendar month
+fn month_cost(entries: &[LedgerEntry], month: (i32, u32)) -> f64 {
+    entries
+        .iter()
+        .filter(|entry| month_of(&entry.time) == month)
+        .filter_map(|entry| entry.cost_usd)
+        .sum()
+}
+
+/// Append the usage of a request to the ledger and check the monthly budget
+pub fn record(model: &str, usage: &TokenUsage) {
+    let entry = LedgerEntry {
+        time: Utc::now(),
+        project: current_project(),
+        model: model.to_string(),
+        usage: usage.clone(),
+        cost_usd: super::pricing::cost(model, usage),
+    };
+    let Some(path) = ledger_path() else {
+        return;
+    };
+
+    let mut spend = MONTH_SPEND.lock().unwrap();
+    let month = month_of(&entry.time);
+    if spend.as_ref().map_or(true, |spend| spend.month != month) {
+        *spend = Some(MonthSpend {
+            month,
+            cost: month_cost(&load(), month),
+            warnings: 0,
+        });
+    }
+
+    let appended = serde_json::to_string(&entry)
+        .map_err(|e| e.to_string())
+        .and_then(|line| {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            }
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(|e| e.to_string())?;
+            writeln!(file, "{line}").map_err(|e| e.to_string())
+        });
+    if let Err(e) = appended {
+        bprintln!(debug: "Failed to record token usage in {}: {}", path.display(), e);
+    }
+
+    let (Some(spend), Some(budget)) = (spend.as_mut(), monthly_budget()) else {
+        return;
+    };
+    spend.cost += entry.cost_usd.unwrap_or_default();
+    if spend.cost >= budget && spend.warnings < 2 {
+        spend.warnings = 2;
+        bprintln!(warn: "Estimated LLM cost this month is ${:.2}, over the monthly budget of ${:.2}", spend.cost, budget);
+    } else if spend.cost >= budget * BUDGET_WARNING_SHARE && spend.warnings < 1 {
+        spend.warnings = 1;
+        bprintln!(warn: "Estimated LLM cost this month is ${:.2}, {:.0}% of the monthly budget of ${:.2}", spend.cost, spend.cost / budget * 100.0, budget);
+    }
+}
+
+/// Parse a period like `7d`, `12h` or `2w` from the command line
+pub fn parse_since(arg: &str) -> Result<Duration, String> {
+    let arg = arg.trim();
+    let unit = arg.chars().last().unwrap_or_default();
+    let seconds = match unit {
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return Err(format!("Expected a period like 12h, 7d or 2w, got '{arg}'")),
+    };
+    match arg[..arg.len() - 1].parse::<u64>() {
+        Ok(count) if count > 0 => Ok(Duration::from_secs(count * seconds)),
+        _ => Err(format!("Expected a period like 12h, 7d or 2w, got '{arg}'")),
+    }
+}
+
+/// How usage is grouped in a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    Day,
+    Model,
+    Project,
+}
+
+/// Parse a grouping of the usage report from the command line
+pub fn parse_grouping(arg: &str) -> Result<Grouping, String> {
+    match arg.to_lowercase().as_str() {
+        "day" => Ok(Grouping::Day),
+        "model" => Ok(Grouping::Model),
+        "project" => Ok(Grouping::Project),
+        _ => Err(format!(
+            "Unknown grouping: {arg}. Valid options: day, model, project"
+        )),
+    }
+}
+
+/// Totals of a group of entries
+#[derive(Debug, Default, PartialEq)]
+struct Totals {
+    requests: usize,
+    usage: TokenUsage,
+    cost: f64,
+    /// Whether some entries have no price
+    unpriced: bool,
+}
+
+impl Totals {
+    fn add(&mut self, entry: &LedgerEntry) {
+        self.requests += 1;
+        self.usage.add(&entry.usage);
+        match entry.cost_usd {
+            Some(cost) => self.cost += cost,
+            None => self.unpriced = true,
+        }
+    }
+}
+
+fn group_totals(
+    entries: &[LedgerEntry],
+    since: Option<DateTime<Utc>>,
+    grouping: Grouping,
+) -> BTreeMap<String, Totals> {
+    let mut groups: BTreeMap<String, Totals> = BTreeMap::new();
+    for entry in entries {
+        if since.is_some_and(|since| entry.time < since) {
+            continue;
+        }
+        let key = match grouping {
+            Grouping::Day => entry
+                .time
+                .with_timezone(&Local)
+                .format("%Y-%m-%d")
+                .to_string(),
+            Grouping::Model => entry.model.clone(),
+            Grouping::Project => entry.project.clone(),
+        };
+        groups.entry(key).or_default().add(entry);
+    }
+    groups
+}
+
+/// Table of the usage recorded since a time ago, grouped
+pub fn report(since: Option<Duration>, grouping: Grouping) -> String {
+    let entries = load();
+    let since = since.and_then(|since| chrono::Duration::from_std(since).ok());
+    let groups = group_totals(&entries, since.map(|since| Utc::now() - since), grouping);
+    let mut text = format_table(&groups, grouping);
+
+    if let Some(budget) = monthly_budget() {
+        let spent = month_cost(&entries, month_of(&Utc::now()));
+        text.push_str(&format!(
+            "\nThis month: ${spent:.2} of the ${budget:.2} monthly budget ({:.0}%)\n",
+            spent / budget * 100.0
+        ));
+    }
+    text
+}
+
+fn format_table(groups: &BTreeMap<String, Totals>, grouping: Grouping) -> String {
+    if groups.is_empty() {
+        return "No usage recorded in this period.\n".to_string();
+    }
+    let heading = match grouping {
+        Grouping::Day => "Day",
+        Grouping::Model => "Model",
+        Grouping::Project => "Project",
+    };
+    let width = groups
+        .keys()
+        .map(String::len)
+        .chain([heading.len(), "Total".len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut total = Totals::default();
+    let mut text = format!(
+        "{heading:width$}  {:>8}  {:>12}  {:>12}  {:>12}  {:>10}\n",
+        "Requests", "Input", "Output", "Cached", "Cost"
+    );
+    let row = |name: &str, totals: &Totals| {
+        let usage = &totals.usage;
+        let cached = usage.cache_creation_input_tokens + usage.cache_read_input_tokens;
+        let cost = format!(
+            "${:.2}{}",
+            totals.cost,
+            if totals.unpriced { "*" } else { "" }
+        );
+        format!(
+            "{name:width$}  {:>8}  {:>12}  {:>12}  {:>12}  {cost:>10}\n",
+            totals.requests, usage.input_tokens, usage.output_tokens, cached
+        )
+    };
+    for (name, totals) in groups {
+        text.push_str(&row(name, totals));
+        total.requests += totals.requests;
+        total.usage.add(&totals.usage);
+        total.cost += totals.cost;
+        total.unpriced |= totals.unpriced;
+    }
+    text.push_str(&row("Total", &total));
+    if total.unpriced {
+        text.push_str("* includes models without a known price, counted as $0\n");
+    }
+    text
+}
+
+/// Backend wrapper recording the usage of each response in the ledger
+pub struct RecordingBackend {
+    inner: Box<dyn Backend>,
+}
+
+impl RecordingBackend {
+    pub fn new(inner: Box<dyn Backend>) -> Self {
+        Self { inner }
+    }
+
+    fn record(&self, response: &LlmResponse) {
+        if let Some(usage) = &response.usage {
+            record(self.inner.model(), usage);
+        }
+    }
+}
+
+#[async_trait]
+impl Backend for RecordingBackend {
+    async fn send_message(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        thinking_budget: Option<usize>,
+        cache_points: Option<&BTreeSet<usize>>,
+        max_tokens: Option<usize>,
+        sampling: Sampling,
+    ) -> Result<LlmResponse, LlmError> {
+        let response = self
+            .inner
+            .send_message(
+                messages,
+                system,
+                stop_sequences,
+                thinking_budget,
+                cache_points,
+                max_tokens,
+                sampling,
+            )
+            .await?;
+        self.record(&response);
+        Ok(response)
+    }
+
+    async fn send_message_streaming(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        thinking_budget: Option<usize>,
+        cache_points: Option<&BTreeSet<usize>>,
+        max_tokens: Option<usize>,
+        sampling: Sampling,
+        on_text: &(dyn Fn(&str) -> bool + Send + Sync),
+    ) -> Result<LlmResponse, LlmError> {
+        let response = self
+            .inner
+            .send_message_streaming(
+                messages,
+                system,
+                stop_sequences,
+                thinking_budget,
+                cache_points,
+                max_tokens,
+                sampling,
+                on_text,
+            )
+            .await?;
+        self.record(&response);
+        Ok(response)
+    }
+
+    fn max_token_limit(&self) -> usize {
+        self.inner.max_token_limit()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.inner.capabilities()
+    }
+
+    fn safe_input_token_limit(&self) -> usize {
+        self.inner.safe_input_token_limit()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(time: &str, model: &str, input_tokens: usize, cost_usd: Option<f64>) -> LedgerEntry {
+        LedgerEntry {
+            time: time.parse().unwrap(),
+            project: "/work/app".to_string(),
+            model: model.to_string(),
+            usage: TokenUsage {
+                input_tokens,
+                output_tokens: 10,
+                cache_creation_input_tokens: 0,
+                cache_read_input_tokens: 0,
+            },
+            cost_usd,
+        }
+    }
+
+    #[test]
+    fn test_group_totals() {
+        let entries = [
+            entry("2025-03-01T12:00:00Z", "gpt-4o", 100, Some(0.5)),
+            entry("2025-03-05T12:00:00Z", "gpt-4o", 200, Some(1.0)),
+            entry("2025-03-05T13:00:00Z", "local-model", 50, None),
+        ];
+
+        let groups = group_totals(&entries, None, Grouping::Model);
+        assert_eq!(groups["gpt-4o"].requests, 2);
+        assert_eq!(groups["gpt-4o"].usage.input_tokens, 300);
+        assert!((groups["gpt-4o"].cost - 1.5).abs() < 1e-9);
+        assert!(groups["local-model"].unpriced);
+
+        let since = "2025-03-02T00:00:00Z".parse().ok();
+        let groups = group_totals(&entries, since, Grouping::Project);
+        assert_eq!(groups["/work/app"].requests, 2);
+
+        let line = serde_json::to_string(&entries[0]).unwrap();
+        assert!(line.contains("\"input_tokens\":100"));
+        assert_eq!(
+            serde_json::from_str::<LedgerEntry>(&line).unwrap(),
+            entries[0]
+        );
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(parse_since("7d").unwrap(), Duration::from_secs(7 * 86400));
+        assert_eq!(parse_since("12h").unwrap(), Duration::from_secs(12 * 3600));
+        assert!(parse_since("0d").is_err());
+        assert!(parse_since("7").is_err());
+        assert!(parse_since("").is_err());
+    }
+}
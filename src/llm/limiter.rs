@@ -0,0 +1,151 @@
+//! Global concurrency limiter for LLM requests
+//!
+//! All agents share one semaphore per provider, so many agents working at once
+//! queue their requests instead of tripping provider rate limits.
+//!
+//! Limits are configured through environment variables:
+//! - `TERMINEER_LLM_CONCURRENCY` - default limit for every provider
+//! - `TERMINEER_LLM_CONCURRENCY_<PROVIDER>` - per-provider override,
+//!   e.g. `TERMINEER_LLM_CONCURRENCY_ANTHROPIC=2`
+
+use super::{Backend, Capabilities, LlmError, LlmResponse, Message, Sampling};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Default number of concurrent requests per provider
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Queue waits shorter than this are not reported
+const REPORT_WAIT_THRESHOLD: Duration = Duration::from_millis(100);
+
+lazy_static! {
+    static ref PROVIDER_SEMAPHORES: Mutex<HashMap<String, Arc<Semaphore>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Resolve the configured concurrency limit for a provider
+fn concurrency_limit(provider: &str) -> usize {
+    let provider_var = format!("TERMINEER_LLM_CONCURRENCY_{}", provider.to_uppercase());
+    std::env::var(provider_var)
+        .or_else(|_| std::env::var("TERMINEER_LLM_CONCURRENCY"))
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(DEFAULT_CONCURRENCY)
+}
+
+/// Get the shared semaphore for a provider, creating it on first use
+fn provider_semaphore(provider: &str) -> Arc<Semaphore> {
+    let mut semaphores = PROVIDER_SEMAPHORES.lock().unwrap();
+    semaphores
+        .entry(provider.to_string())
+        .or_insert_with(|| Arc::new(Semaphore::new(concurrency_limit(provider))))
+        .clone()
+}
+
+/// Backend wrapper that queues requests through the provider's shared semaphore
+pub struct ConcurrencyLimitedBackend {
+    inner: Box<dyn Backend>,
+    semaphore: Arc<Semaphore>,
+}
+
+impl ConcurrencyLimitedBackend {
+    /// Wrap a backend with the global limiter for its provider
+    pub fn new(inner: Box<dyn Backend>) -> Self {
+        let semaphore = provider_semaphore(inner.name());
+        Self { inner, semaphore }
+    }
+
+    /// Wait for a free slot of the provider
+    async fn acquire(&self) -> Result<SemaphorePermit<'_>, LlmError> {
+        let queued_at = Instant::now();
+        let permit = self
+            .semaphore
+            .acquire()
+            .await
+            .map_err(|e| LlmError::Other(Box::new(e)))?;
+
+        let waited = queued_at.elapsed();
+        if waited >= REPORT_WAIT_THRESHOLD {
+            bprintln!(debug: "LLM request to {} waited {} ms in queue", self.inner.name(), waited.as_millis());
+        }
+        Ok(permit)
+    }
+}
+
+#[async_trait]
+impl Backend for ConcurrencyLimitedBackend {
+    async fn send_message(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        thinking_budget: Option<usize>,
+        cache_points: Option<&BTreeSet<usize>>,
+        max_tokens: Option<usize>,
+        sampling: Sampling,
+    ) -> Result<LlmResponse, LlmError> {
+        let _permit = self.acquire().await?;
+        self.inner
+            .send_message(
+                messages,
+                system,
+                stop_sequences,
+                thinking_budget,
+                cache_points,
+                max_tokens,
+                sampling,
+            )
+            .await
+    }
+
+    async fn send_message_streaming(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        thinking_budget: Option<usize>,
+        cache_points: Option<&BTreeSet<usize>>,
+        max_tokens: Option<usize>,
+        sampling: Sampling,
+        on_text: &(dyn Fn(&str) -> bool + Send + Sync),
+    ) -> Result<LlmResponse, LlmError> {
+        let _permit = self.acquire().await?;
+        self.inner
+            .send_message_streaming(
+                messages,
+                system,
+                stop_sequences,
+                thinking_budget,
+                cache_points,
+                max_tokens,
+                sampling,
+                on_text,
+            )
+            .await
+    }
+
+    fn max_token_limit(&self) -> usize {
+        self.inner.max_token_limit()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.inner.capabilities()
+    }
+
+    fn safe_input_token_limit(&self) -> usize {
+        self.inner.safe_input_token_limit()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+}
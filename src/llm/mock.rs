@@ -0,0 +1,261 @@
+//! Deterministic mock backend that plays back scripted responses
+//!
+//! Selected with `--model mock:<script.yaml>`. Each LLM request returns the next
+//! response from the script, which makes it possible to exercise the agent loop,
+//! grammar parsing and tools without API keys.
+//!
+//! Script format:
+//!
+//! ```yaml
+//! responses:
+//!   - text: |
+//!       Let me look at the files.
+//!       <function_calls>...</function_calls>
+//!   - expect: "Cargo.toml"   # optional: last message must contain this text
+//!     text: All done.
+//! ```
+//!
+//! Stop sequences requested by the agent are honored like a real provider: the
+//! text is cut before the first stop sequence it contains.
+
+use super::{Backend, Content, LlmError, LlmResponse, Message, Sampling, TokenUsage};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+/// Prefix of model names that select the mock backend
+pub const MOCK_MODEL_PREFIX: &str = "mock:";
+
+/// A single scripted response
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptedResponse {
+    /// Text returned by the mock model
+    pub text: String,
+    /// Text that the last message of the request must contain
+    #[serde(default)]
+    pub expect: Option<String>,
+}
+
+/// A playback script
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockScript {
+    /// Responses returned in order, one per request
+    pub responses: Vec<ScriptedResponse>,
+}
+
+impl MockScript {
+    /// Parse a script from YAML
+    pub fn parse(content: &str) -> Result<Self, LlmError> {
+        serde_yaml::from_str(content)
+            .map_err(|e| LlmError::ConfigError(format!("Invalid mock script: {e}")))
+    }
+
+    /// Load a script from a YAML file
+    pub fn load(path: &str) -> Result<Self, LlmError> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            LlmError::ConfigError(format!("Failed to read mock script '{path}': {e}"))
+        })?;
+        Self::parse(&content)
+    }
+}
+
+/// Backend that plays back a [`MockScript`]
+pub struct MockBackend {
+    model: String,
+    script: MockScript,
+    next: Mutex<usize>,
+}
+
+impl MockBackend {
+    /// Create a mock backend from a model string of the form `mock:<script.yaml>`
+    pub fn from_model(model: &str) -> Result<Self, LlmError> {
+        let path = model
+            .strip_prefix(MOCK_MODEL_PREFIX)
+            .unwrap_or(model)
+            .trim();
+        if path.is_empty() {
+            return Err(LlmError::ConfigError(
+                "Mock model requires a script path: mock:<script.yaml>".to_string(),
+            ));
+        }
+        Ok(Self::new(model.to_string(), MockScript::load(path)?))
+    }
+
+    /// Create a mock backend from an already parsed script
+    pub fn new(model: String, script: MockScript) -> Self {
+        Self {
+            model,
+            script,
+            next: Mutex::new(0),
+        }
+    }
+}
+
+/// Rough token estimate so budgets and stats behave plausibly
+fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// Concatenate the text parts of a message
+fn message_text(message: &Message) -> String {
+    match &message.content {
+        Content::Text { text } => text.clone(),
+        _ => String::new(),
+    }
+}
+
+#[async_trait]
+impl Backend for MockBackend {
+    async fn send_message(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        _thinking_budget: Option<usize>,
+        _cache_points: Option<&BTreeSet<usize>>,
+        _max_tokens: Option<usize>,
+        _sampling: Sampling,
+    ) -> Result<LlmResponse, LlmError> {
+        let index = {
+            let mut next = self.next.lock().unwrap();
+            let index = *next;
+            *next += 1;
+            index
+        };
+
+        let scripted = self.script.responses.get(index).ok_or_else(|| {
+            LlmError::ApiError(format!(
+                "Mock script exhausted: request {} but only {} responses scripted",
+                index + 1,
+                self.script.responses.len()
+            ))
+        })?;
+
+        let last_message = messages.last().map(message_text).unwrap_or_default();
+        if let Some(expected) = &scripted.expect {
+            if !last_message.contains(expected.as_str()) {
+                return Err(LlmError::ApiError(format!(
+                    "Mock expectation failed at request {}: last message does not contain '{}'",
+                    index + 1,
+                    expected
+                )));
+            }
+        }
+
+        // Cut the text at the first stop sequence, like a real provider
+        let mut text = scripted.text.clone();
+        let mut stop_sequence = None;
+        if let Some(sequences) = stop_sequences {
+            let first_stop = sequences
+                .iter()
+                .filter_map(|seq| text.find(seq.as_str()).map(|pos| (pos, seq)))
+                .min_by_key(|(pos, _)| *pos);
+            if let Some((pos, seq)) = first_stop {
+                text.truncate(pos);
+                stop_sequence = Some(seq.clone());
+            }
+        }
+
+        let input_tokens = estimate_tokens(system.unwrap_or(""))
+            + messages
+                .iter()
+                .map(|m| estimate_tokens(&message_text(m)))
+                .sum::<usize>();
+
+        Ok(LlmResponse {
+            usage: Some(TokenUsage {
+                input_tokens,
+                output_tokens: estimate_tokens(&text),
+                ..Default::default()
+            }),
+            stop_reason: Some(if stop_sequence.is_some() {
+                "stop_sequence".to_string()
+            } else {
+                "end_turn".to_string()
+            }),
+            stop_sequence,
+            content: vec![Content::Text { text }],
+        })
+    }
+
+    fn max_token_limit(&self) -> usize {
+        200_000
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MessageInfo;
+
+    fn response_text(response: &LlmResponse) -> &str {
+        match &response.content[0] {
+            Content::Text { text } => text,
+            _ => panic!("expected text content"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_playback() {
+        let script = MockScript::parse(
+            r#"
+responses:
+  - text: "first<stop>ignored"
+  - text: "second"
+    expect: "hello"
+"#,
+        )
+        .unwrap();
+        let backend = MockBackend::new("mock:test".to_string(), script);
+        let messages = vec![Message::text(
+            "user",
+            "hello there".to_string(),
+            MessageInfo::User,
+        )];
+        let stops = vec!["<stop>".to_string()];
+
+        let first = backend
+            .send_message(
+                &messages,
+                None,
+                Some(&stops),
+                None,
+                None,
+                None,
+                Sampling::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response_text(&first), "first");
+        assert_eq!(first.stop_sequence.as_deref(), Some("<stop>"));
+
+        let second = backend
+            .send_message(
+                &messages,
+                None,
+                Some(&stops),
+                None,
+                None,
+                None,
+                Sampling::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response_text(&second), "second");
+        assert_eq!(second.stop_reason.as_deref(), Some("end_turn"));
+
+        assert!(backend
+            .send_message(&messages, None, None, None, None, None, Sampling::default())
+            .await
+            .is_err());
+    }
+}
@@ -6,24 +6,62 @@
 pub use async_trait::async_trait;
 
 pub mod anthropic;
+pub mod cache;
+pub mod capabilities;
+pub mod catalog;
 pub mod cohere;
 pub mod deepseek;
+pub mod discovery;
 pub mod factory;
 pub mod gemini;
 pub mod grok;
+pub mod keys;
+pub mod ledger;
+pub mod limiter;
+pub mod mock;
 pub mod openrouter;
 pub mod openai; // Add openai module
+pub mod pricing;
 pub mod retry_utils;
+pub mod sampling;
 mod types;
 
+pub use self::capabilities::Capabilities;
 pub use self::factory::create_backend;
+pub use self::sampling::Sampling;
 pub use self::types::*;
 use std::collections::BTreeSet;
 
+/// Stop reason of a streamed response that the caller stopped reading early
+pub const STOP_REASON_EARLY: &str = "stopped_early";
+
+tokio::task_local! {
+    /// Set while requests must answer with a single JSON value
+    static JSON_MODE: bool;
+}
+
+/// Send the requests of `request` in the provider's JSON mode, where it has one
+pub async fn with_json_mode<F: std::future::Future>(request: F) -> F::Output {
+    JSON_MODE.scope(true, request).await
+}
+
+/// Whether the current request is sent in JSON mode
+pub fn json_mode() -> bool {
+    JSON_MODE.try_with(|json_mode| *json_mode).unwrap_or(false)
+}
+
+/// `response_format` of chat completion APIs selecting their JSON mode
+pub fn json_response_format() -> Option<serde_json::Value> {
+    json_mode().then(|| serde_json::json!({ "type": "json_object" }))
+}
+
 /// Common trait for all LLM backends
 #[async_trait]
 pub trait Backend: Send + Sync {
     /// Send a message to the LLM and get a response
+    ///
+    /// `sampling` parameters the model does not accept are left out of the request.
+    #[allow(clippy::too_many_arguments)]
     async fn send_message(
         &self,
         messages: &[Message],
@@ -32,8 +70,42 @@ pub trait Backend: Send + Sync {
         thinking_budget: Option<usize>,
         cache_points: Option<&BTreeSet<usize>>,
         max_tokens: Option<usize>, // Maximum tokens to generate in the response
+        sampling: Sampling,
     ) -> Result<LlmResponse, LlmError>;
 
+    /// Send a message and report the response text as it is generated
+    ///
+    /// `on_text` receives all text generated so far and returns false to stop
+    /// reading; the partial response is then returned with the stop reason
+    /// [`STOP_REASON_EARLY`]. Backends without streaming support send a
+    /// regular request and report the whole text once.
+    #[allow(clippy::too_many_arguments)]
+    async fn send_message_streaming(
+        &self,
+        messages: &[Message],
+        system: Option<&str>,
+        stop_sequences: Option<&[String]>,
+        thinking_budget: Option<usize>,
+        cache_points: Option<&BTreeSet<usize>>,
+        max_tokens: Option<usize>,
+        sampling: Sampling,
+        on_text: &(dyn Fn(&str) -> bool + Send + Sync),
+    ) -> Result<LlmResponse, LlmError> {
+        let response = self
+            .send_message(
+                messages,
+                system,
+                stop_sequences,
+                thinking_budget,
+                cache_points,
+                max_tokens,
+                sampling,
+            )
+            .await?;
+        on_text(&response.text());
+        Ok(response)
+    }
+
     /// Get the maximum token limit for this model
     ///
     /// Returns the total context window size for the current model,
@@ -60,6 +132,16 @@ pub trait Backend: Send + Sync {
         (self.max_token_limit() as f64 * 0.8) as usize
     }
 
+    /// Features of the backend's API the caller adapts requests to
+    ///
+    /// Defaults to a system prompt without cache control, images or native tools.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            supports_system_role: true,
+            ..Capabilities::default()
+        }
+    }
+
     /// Get the provider name
     /// Included in the API for provider identification but not currently used
     #[allow(dead_code)]
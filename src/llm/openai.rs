@@ -2,7 +2,12 @@
 //!
 //! Implementation of the LLM provider for OpenAI's models (GPT-3.5, GPT-4, etc.).
 
-use crate::llm::{Backend, Content, LlmError, LlmResponse, Message, TokenUsage};
+use crate::llm::retry_utils;
+#[cfg(test)]
+use crate::llm::ImageSource;
+use crate::llm::{
+    Backend, Capabilities, Content, LlmError, LlmResponse, Message, Sampling, TokenUsage,
+};
 #[cfg(test)]
 use crate::llm::ImageSource;
 use crate::llm::retry_utils;
@@ -15,44 +20,6 @@ use std::time::Duration;
 // Constants for OpenAI API
 const API_BASE_URL: &str = "https://api.openai.com/v1";
 
-/// Get the token limit for an OpenAI model
-///
-/// Uses a pattern-matching approach to determine the appropriate token limit
-/// for a given model name. Limits based on OpenAI documentation.
-fn get_model_token_limit(model_name: &str) -> usize {
-    // Default to a conservative limit if no pattern matches
-    const DEFAULT_TOKEN_LIMIT: usize = 8_000;
-
-    // GPT-4o models (128k context)
-    if model_name.starts_with("gpt-4o") {
-        return 128_000;
-    }
-    // GPT-4 Turbo models (128k context)
-    if model_name.starts_with("gpt-4-turbo") || model_name.starts_with("gpt-4-1106") || model_name.starts_with("gpt-4-0125") {
-        return 128_000;
-    }
-    // GPT-4 32k models
-    if model_name.starts_with("gpt-4-32k") {
-        return 32_768;
-    }
-    // Standard GPT-4 models (8k context)
-    if model_name.starts_with("gpt-4") {
-        return 8_192;
-    }
-    // GPT-3.5 Turbo 16k models (older naming)
-    if model_name.starts_with("gpt-3.5-turbo-16k") {
-        return 16_384;
-    }
-    // Standard GPT-3.5 Turbo models (16k context in newer versions)
-    if model_name.starts_with("gpt-3.5-turbo") {
-        // Newer gpt-3.5-turbo models default to 16k, older was 4k. Assume 16k.
-        return 16_384;
-    }
-
-    // Fall back to default for any unknown models
-    DEFAULT_TOKEN_LIMIT
-}
-
 /// OpenAI API request structure (Completions)
 #[derive(Debug, Serialize)]
 struct OpenAICompletionRequest {
@@ -127,7 +94,7 @@ pub struct OpenAIBackend {
 impl OpenAIBackend {
     /// Create a new OpenAI client
     pub fn new(api_key: String, model_name: String) -> Self {
-        let client = reqwest::Client::builder()
+        let client = crate::net::client_builder()
             .timeout(Duration::from_secs(180)) // Standard timeout
             .build()
             .expect("Failed to create HTTP client");
@@ -179,7 +146,9 @@ impl OpenAIBackend {
                     let data_text = data.clone().unwrap_or_default();
                     format!("[Redacted Thinking]: {data_text}")
                 }
-                Content::Document { source } => format!("[Document Source]: {source}"),
+                Content::Document { .. } | Content::ToolUse { .. } | Content::ToolResult { .. } => {
+                    message.content.fallback_text()
+                }
             };
 
             prompt_parts.push(format!("{role_prefix}: {content_text}"));
@@ -237,6 +206,7 @@ impl Backend for OpenAIBackend {
         thinking_budget: Option<usize>, // OpenAI doesn't use this
         cache_points: Option<&BTreeSet<usize>>, // OpenAI doesn't use this
         max_tokens: Option<usize>,
+        sampling: Sampling,
     ) -> Result<LlmResponse, LlmError> {
         // Log unsupported features if used
         if thinking_budget.is_some() {
@@ -256,9 +226,9 @@ impl Backend for OpenAIBackend {
         let request = OpenAICompletionRequest {
             model: self.model_name.clone(),
             prompt,
-            temperature: Some(0.7), // Default temperature
+            temperature: Some(sampling.temperature.unwrap_or(0.7)), // Default temperature
             max_tokens: max_tokens.map(|t| t as u32),
-            top_p: Some(1.0), // Default top_p for OpenAI
+            top_p: Some(sampling.top_p.unwrap_or(1.0)), // Default top_p for OpenAI
             stop,
             stream: None, // Not using streaming
         };
@@ -322,7 +292,12 @@ impl Backend for OpenAIBackend {
     }
 
     fn max_token_limit(&self) -> usize {
-        get_model_token_limit(&self.model_name)
+        crate::llm::catalog::context_window(&self.model_name)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        // The completions API takes a single prompt of text
+        Capabilities::default()
     }
 
     fn name(&self) -> &str {
@@ -337,6 +312,7 @@ impl Backend for OpenAIBackend {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::llm::catalog::{context_window, DEFAULT_CONTEXT_WINDOW};
     use crate::llm::MessageInfo;
 
     #[test]
@@ -407,17 +383,17 @@ mod tests {
 
     #[test]
     fn test_openai_model_token_limits() {
-        assert_eq!(get_model_token_limit("gpt-4o-2024-05-13"), 128_000);
-        assert_eq!(get_model_token_limit("gpt-4-turbo"), 128_000);
-        assert_eq!(get_model_token_limit("gpt-4-turbo-preview"), 128_000);
-        assert_eq!(get_model_token_limit("gpt-4-0125-preview"), 128_000);
-        assert_eq!(get_model_token_limit("gpt-4-1106-preview"), 128_000);
-        assert_eq!(get_model_token_limit("gpt-4-32k"), 32_768);
-        assert_eq!(get_model_token_limit("gpt-4-0613"), 8_192);
-        assert_eq!(get_model_token_limit("gpt-4"), 8_192);
-        assert_eq!(get_model_token_limit("gpt-3.5-turbo-16k"), 16_384);
-        assert_eq!(get_model_token_limit("gpt-3.5-turbo-0125"), 16_384);
-        assert_eq!(get_model_token_limit("gpt-3.5-turbo"), 16_384); // Assuming newer 16k default
-        assert_eq!(get_model_token_limit("unknown-model"), 8_000); // Default
+        assert_eq!(context_window("gpt-4o-2024-05-13"), 128_000);
+        assert_eq!(context_window("gpt-4-turbo"), 128_000);
+        assert_eq!(context_window("gpt-4-turbo-preview"), 128_000);
+        assert_eq!(context_window("gpt-4-0125-preview"), 128_000);
+        assert_eq!(context_window("gpt-4-1106-preview"), 128_000);
+        assert_eq!(context_window("gpt-4-32k"), 32_768);
+        assert_eq!(context_window("gpt-4-0613"), 8_192);
+        assert_eq!(context_window("gpt-4"), 8_192);
+        assert_eq!(context_window("gpt-3.5-turbo-16k"), 16_384);
+        assert_eq!(context_window("gpt-3.5-turbo-0125"), 16_384);
+        assert_eq!(context_window("gpt-3.5-turbo"), 16_384); // Assuming newer 16k default
+        assert_eq!(context_window("unknown-model"), DEFAULT_CONTEXT_WINDOW);
     }
 }
\ No newline at end of file
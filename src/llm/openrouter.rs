@@ -4,7 +4,9 @@
 //! which provides access to models from multiple providers including
 //! OpenAI, Anthropic, and more.
 
-use crate::llm::{Backend, Content, LlmError, LlmResponse, Message, TokenUsage};
+use crate::llm::{
+    Backend, Capabilities, Content, LlmError, LlmResponse, Message, Sampling, TokenUsage,
+};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json;
@@ -14,53 +16,6 @@ use std::time::Duration;
 // Constants for OpenRouter API
 const API_BASE_URL: &str = "https://openrouter.ai/api/v1";
 
-/// Get the token limit for an OpenRouter model
-///
-/// Uses a pattern-matching approach to determine the appropriate token limit
-/// for a given model name, based on the provider and model.
-fn get_model_token_limit(model_name: &str) -> usize {
-    // Default to a conservative limit if no pattern matches
-    const DEFAULT_TOKEN_LIMIT: usize = 16_000;
-
-    // Extract provider and model parts if in provider/model format
-    let parts: Vec<&str> = model_name.split('/').collect();
-    let (provider, model) = if parts.len() >= 2 {
-        (parts[0], parts[1])
-    } else {
-        // If no provider prefix, assume it's just a model name
-        ("", model_name)
-    };
-
-    // Handle OpenAI models
-    if provider == "openai" || model.starts_with("gpt-") {
-        if model.contains("gpt-4-turbo") || model.contains("gpt-4o") {
-            return 128_000; // 128k tokens
-        } else if model.contains("gpt-4-32k") {
-            return 32_768; // 32k tokens
-        } else if model.contains("gpt-4") {
-            return 8_192; // 8k tokens
-        } else if model.contains("gpt-3.5-turbo-16k") {
-            return 16_384; // 16k tokens
-        } else if model.contains("gpt-3.5") {
-            return 4_096; // 4k tokens
-        }
-    }
-
-    // Handle Anthropic models
-    if provider == "anthropic" || model.starts_with("claude-") {
-        if model.contains("claude-3") {
-            // All Claude 3 models support 200k tokens
-            return 200_000; // 200k tokens
-        } else if model.contains("claude-2") || model.contains("claude-instant") {
-            // Claude 2 and Claude Instant support 100k tokens
-            return 100_000; // 100k tokens
-        }
-    }
-
-    // For any other model, use a reasonable default
-    DEFAULT_TOKEN_LIMIT
-}
-
 /// OpenRouter API request types
 #[derive(Debug, Serialize)]
 struct OpenRouterRequest {
@@ -80,6 +35,8 @@ struct OpenRouterRequest {
     stop: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     seed: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -174,7 +131,7 @@ impl OpenRouterBackend {
         site_url: Option<String>,
         site_name: Option<String>,
     ) -> Self {
-        let client = reqwest::Client::builder()
+        let client = crate::net::client_builder()
             .timeout(Duration::from_secs(300)) // 5 minute timeout for long context
             .build()
             .expect("Failed to create HTTP client");
@@ -246,7 +203,9 @@ impl OpenRouterBackend {
                 Content::RedactedThinking { data } => {
                     OpenRouterContent::Text(data.clone().unwrap_or_default())
                 }
-                Content::Document { source } => OpenRouterContent::Text(source.clone()),
+                Content::Document { .. } | Content::ToolUse { .. } | Content::ToolResult { .. } => {
+                    OpenRouterContent::Text(message.content.fallback_text())
+                }
             };
 
             // No user info in MessageInfo in this codebase - just pass None for name
@@ -325,6 +284,7 @@ impl Backend for OpenRouterBackend {
         thinking_budget: Option<usize>,
         cache_points: Option<&BTreeSet<usize>>,
         max_tokens: Option<usize>,
+        sampling: Sampling,
     ) -> Result<LlmResponse, LlmError> {
         // OpenRouter doesn't support thinking or cache features
         if thinking_budget.is_some() {
@@ -347,10 +307,11 @@ impl Backend for OpenRouterBackend {
             messages: openrouter_messages,
             stream: None, // Not using streaming in this implementation
             max_tokens: max_tokens.map(|t| t as u32),
-            temperature: Some(0.7), // Default temperature
-            top_p: Some(0.95),      // Default top_p
+            temperature: Some(sampling.temperature.unwrap_or(0.7)), // Default temperature
+            top_p: Some(sampling.top_p.unwrap_or(0.95)),            // Default top_p
             stop,
             seed: None, // No deterministic seed by default
+            response_format: crate::llm::json_response_format(),
         };
 
         // Send the request to the chat completions endpoint
@@ -404,7 +365,12 @@ impl Backend for OpenRouterBackend {
 
 
     fn max_token_limit(&self) -> usize {
-        get_model_token_limit(&self.model_name)
+        crate::llm::catalog::context_window(&self.model_name)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        // Images are passed on to models the catalog knows to accept them
+        Capabilities::for_model(&self.model_name)
     }
 
     fn name(&self) -> &str {
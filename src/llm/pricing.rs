@@ -0,0 +1,60 @@
+//! Estimated cost of LLM requests
+//!
+//! Prices are the providers' published list prices in USD per million tokens,
+//! taken from the model catalog (see [`super::catalog`]). Models without a
+//! price in the catalog have no estimate; a model is matched by its name
+//! without the provider prefix, so `openrouter/anthropic/claude-3.7-sonnet` is
+//! priced like `claude-3-7-sonnet`.
+
+use super::TokenUsage;
+
+/// List prices of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Price {
+    pub input: f64,
+    pub output: f64,
+    /// Writing input to the prompt cache
+    pub cache_write: f64,
+    /// Reading input from the prompt cache
+    pub cache_read: f64,
+}
+
+/// List prices of a model or alias, if the catalog knows them
+pub fn price_of(model: &str) -> Option<Price> {
+    super::catalog::lookup(model).and_then(|spec| spec.price)
+}
+
+/// Estimated cost in USD of the tokens used with a model, if its price is known
+pub fn cost(model: &str, usage: &TokenUsage) -> Option<f64> {
+    let price = price_of(model)?;
+    let millions = |tokens: usize| tokens as f64 / 1_000_000.0;
+    Some(
+        millions(usage.input_tokens) * price.input
+            + millions(usage.output_tokens) * price.output
+            + millions(usage.cache_creation_input_tokens) * price.cache_write
+            + millions(usage.cache_read_input_tokens) * price.cache_read,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost() {
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 1_000_000,
+        };
+        let cost = cost("claude-3-7-sonnet-20250219", &usage).unwrap();
+        assert!((cost - 4.8).abs() < 1e-9);
+
+        let openrouter = price_of("openrouter/anthropic/claude-3.7-sonnet");
+        assert_eq!(openrouter, price_of("claude-3-7-sonnet-20250219"));
+        assert_eq!(price_of("gpt-4.1-mini").unwrap().input, 0.4);
+        assert_eq!(price_of("sonnet"), price_of("claude-sonnet-4-20250514"));
+        assert_eq!(cost("unknown-model", &usage), None);
+    }
+}
@@ -220,6 +220,39 @@ pub async fn send_api_request_with_retry<T, F>(
 where
     T: serde::de::DeserializeOwned,
     F: Fn() -> reqwest::RequestBuilder,
+{
+    let res = send_request_with_retry(prepare_request, config, provider_name).await?;
+
+    // Read the response body first to allow logging it on parsing failure
+    let response_body = match res.text().await {
+        Ok(body) => body,
+        Err(e) => {
+            // Error reading the response body itself
+            return Err(LlmError::ApiError(format!(
+                "Failed to read {provider_name} response body: {e}"
+            )));
+        }
+    };
+
+    // Attempt to parse the captured body
+    serde_json::from_str::<T>(&response_body).map_err(|e| {
+        // Log the raw body along with the parsing error
+        bprintln!(error: "Failed to parse {} response. Error: {}. Body:\n{}", provider_name, e, response_body);
+        LlmError::ApiError(format!("Failed to parse {provider_name} response: {e}"))
+    })
+}
+
+/// Send a request with the retry logic of [`send_api_request_with_retry`] and
+/// return the successful response without reading its body
+///
+/// Used for streaming responses, which are read incrementally by the caller.
+pub async fn send_request_with_retry<F>(
+    prepare_request: F,
+    config: RetryConfig,
+    provider_name: &str,
+) -> Result<reqwest::Response, LlmError>
+where
+    F: Fn() -> reqwest::RequestBuilder,
 {
     let mut attempts = 0;
     let timeout = Duration::from_secs(config.timeout_secs);
@@ -243,45 +276,29 @@ where
                 bprintln!(dev: "{} API response status: {}", provider_name, res.status());
 
                 if res.status().is_success() {
-                    // Read the response body first to allow logging it on parsing failure
-                    let response_body = match res.text().await {
-                        Ok(body) => body,
-                        Err(e) => {
-                            // Error reading the response body itself
-                            return Err(LlmError::ApiError(format!(
-                                "Failed to read {provider_name} response body: {e}"
-                            )));
-                        }
-                    };
-
-                    // Attempt to parse the captured body
-                    return serde_json::from_str::<T>(&response_body).map_err(|e| {
-                        // Log the raw body along with the parsing error
-                        bprintln!(error: "Failed to parse {} response. Error: {}. Body:\n{}", provider_name, e, response_body);
-                        LlmError::ApiError(format!(
-                            "Failed to parse {provider_name} response: {e}"
-                        ))
-                        // Consider including a truncated body in the error message itself if needed,
-                        // but logging it might be sufficient. Example:
-                        // let truncated_body = response_body.chars().take(500).collect::<String>();
-                        // LlmError::ApiError(format!(
-                        //     "Failed to parse {provider_name} response: {e}. Body (truncated): {truncated_body}"
-                        // ))
-                    });
+                    return Ok(res);
                 } else if res.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
                     // Handle rate limiting (429 Too Many Requests)
+                    let retry_after_header = res
+                        .headers()
+                        .get("retry-after")
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse::<u64>().ok());
+
+                    // Let the caller switch to another API key instead of waiting
+                    if crate::llm::keys::fail_fast_on_rate_limit() {
+                        return Err(LlmError::RateLimitError {
+                            retry_after: retry_after_header,
+                        });
+                    }
+
                     attempts += 1;
                     if attempts >= config.max_attempts {
                         return Err(LlmError::RateLimitError { retry_after: None });
                     }
 
-                    // Try to get retry-after header, default to backoff strategy if not present
-                    let retry_after = match res
-                        .headers()
-                        .get("retry-after")
-                        .and_then(|v| v.to_str().ok())
-                        .and_then(|v| v.parse::<u64>().ok())
-                    {
+                    // Use the retry-after header, default to backoff strategy if not present
+                    let retry_after = match retry_after_header {
                         Some(value) => {
                             let delay_ms = value * 1000;
                             bprintln!(
@@ -0,0 +1,200 @@
+//! Sampling parameters of LLM requests
+//!
+//! Temperature and top-p are set for all requests with `--temperature` and
+//! `--top-p`, and can be overridden for one class of request with
+//! `--task-sampling CLASS:temperature=T,top_p=P`:
+//! - `conversation` - the agent's turns
+//! - `summary` - checkpoint commit messages and handoff summaries
+//! - `check` - interruption checks, the prompt injection classifier and eval judges
+//!
+//! Parameters left unset use the provider's default. Each backend maps them to
+//! its own request fields and leaves out the ones a model does not accept.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Temperature and nucleus sampling of a request (None = provider default)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Sampling {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+}
+
+impl Sampling {
+    /// These parameters, with unset ones taken from `fallback`
+    pub fn or(self, fallback: Sampling) -> Sampling {
+        Sampling {
+            temperature: self.temperature.or(fallback.temperature),
+            top_p: self.top_p.or(fallback.top_p),
+        }
+    }
+}
+
+impl fmt::Display for Sampling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(temperature) = self.temperature {
+            parts.push(format!("temperature={temperature}"));
+        }
+        if let Some(top_p) = self.top_p {
+            parts.push(format!("top_p={top_p}"));
+        }
+        if parts.is_empty() {
+            write!(f, "provider defaults")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+/// Class of request with its own sampling parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SamplingTask {
+    /// The agent's turns
+    Conversation,
+    /// Commit messages and conversation summaries
+    Summary,
+    /// Short yes/no decisions: interruption checks and classifiers
+    Check,
+}
+
+impl SamplingTask {
+    pub const ALL: [SamplingTask; 3] = [Self::Conversation, Self::Summary, Self::Check];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Conversation => "conversation",
+            Self::Summary => "summary",
+            Self::Check => "check",
+        }
+    }
+}
+
+impl FromStr for SamplingTask {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|task| task.name() == s.to_lowercase())
+            .ok_or_else(|| {
+                format!("Unknown request class: {s}. Valid options: conversation, summary, check")
+            })
+    }
+}
+
+/// Sampling parameters of all requests and overrides per class
+#[derive(Debug, Clone, Default)]
+pub struct SamplingConfig {
+    pub default: Sampling,
+    pub tasks: HashMap<SamplingTask, Sampling>,
+}
+
+impl SamplingConfig {
+    /// Parameters of requests of a class
+    pub fn for_task(&self, task: SamplingTask) -> Sampling {
+        let overrides = self.tasks.get(&task).copied().unwrap_or_default();
+        overrides.or(self.default)
+    }
+
+    /// Set the temperature of all requests, or of one class (None = default)
+    pub fn set_temperature(&mut self, task: Option<SamplingTask>, temperature: Option<f32>) {
+        match task {
+            Some(task) => self.tasks.entry(task).or_default().temperature = temperature,
+            None => self.default.temperature = temperature,
+        }
+    }
+
+    /// Summary of the parameters of every class
+    pub fn describe(&self) -> String {
+        SamplingTask::ALL
+            .iter()
+            .map(|task| format!("{}: {}", task.name(), self.for_task(*task)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Parse a temperature between 0 and 2
+pub fn parse_temperature(arg: &str) -> Result<f32, String> {
+    parse_in_range(arg, "Temperature", 2.0)
+}
+
+/// Parse a top-p value between 0 and 1
+pub fn parse_top_p(arg: &str) -> Result<f32, String> {
+    parse_in_range(arg, "Top-p", 1.0)
+}
+
+fn parse_in_range(arg: &str, name: &str, max: f32) -> Result<f32, String> {
+    match arg.trim().parse::<f32>() {
+        Ok(value) if (0.0..=max).contains(&value) => Ok(value),
+        _ => Err(format!(
+            "{name} must be a number between 0 and {max}, got '{arg}'"
+        )),
+    }
+}
+
+/// Parse `CLASS:temperature=T,top_p=P` from the command line
+pub fn parse_task_sampling(arg: &str) -> Result<(SamplingTask, Sampling), String> {
+    let (task, params) = arg
+        .split_once(':')
+        .ok_or_else(|| format!("Expected CLASS:temperature=T,top_p=P, got '{arg}'"))?;
+    let task = task.trim().parse()?;
+    let mut sampling = Sampling::default();
+    for param in params.split(',').filter(|param| !param.trim().is_empty()) {
+        match param.split_once('=').map(|(k, v)| (k.trim(), v)) {
+            Some(("temperature" | "temp", value)) => {
+                sampling.temperature = Some(parse_temperature(value)?)
+            }
+            Some(("top_p" | "top-p", value)) => sampling.top_p = Some(parse_top_p(value)?),
+            _ => {
+                return Err(format!(
+                    "Unknown sampling parameter '{param}'. Valid options: temperature, top_p"
+                ))
+            }
+        }
+    }
+    Ok((task, sampling))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_task() {
+        let (task, sampling) = parse_task_sampling("check:temperature=0").unwrap();
+        assert_eq!(task, SamplingTask::Check);
+
+        let mut config = SamplingConfig::default();
+        config.default.temperature = Some(0.7);
+        config.default.top_p = Some(0.9);
+        config.tasks.insert(task, sampling);
+
+        let check = config.for_task(SamplingTask::Check);
+        assert_eq!(check.temperature, Some(0.0));
+        assert_eq!(check.top_p, Some(0.9));
+        assert_eq!(config.for_task(SamplingTask::Summary), config.default);
+
+        config.set_temperature(None, None);
+        assert_eq!(
+            config.for_task(SamplingTask::Conversation).temperature,
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_task_sampling() {
+        let (task, sampling) = parse_task_sampling("summary:temperature=0.2,top_p=0.5").unwrap();
+        assert_eq!(task, SamplingTask::Summary);
+        assert_eq!(sampling.temperature, Some(0.2));
+        assert_eq!(sampling.top_p, Some(0.5));
+
+        assert!(parse_task_sampling("summary").is_err());
+        assert!(parse_task_sampling("other:temperature=1").is_err());
+        assert!(parse_task_sampling("summary:top_p=2").is_err());
+        assert!(parse_task_sampling("summary:seed=1").is_err());
+    }
+}
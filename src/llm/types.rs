@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
  // Import HashMap
 
 /// Response from an LLM provider
-#[derive(Debug, Clone, PartialEq)] // Added Clone and PartialEq
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)] // Added Clone and PartialEq
 pub struct LlmResponse {
     /// The content of the response
     pub content: Vec<Content>,
@@ -24,8 +24,21 @@ pub struct LlmResponse {
     pub stop_reason: Option<String>,
 }
 
+impl LlmResponse {
+    /// Concatenated text content of the response
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|content| match content {
+                Content::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
 /// Token usage statistics
-#[derive(Debug, Clone, Default, Deserialize, PartialEq)] // Added PartialEq
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)] // Added PartialEq
 pub struct TokenUsage {
     /// Input tokens for the current request
     pub input_tokens: usize,
@@ -38,6 +51,16 @@ pub struct TokenUsage {
     pub cache_read_input_tokens: usize,
 }
 
+impl TokenUsage {
+    /// Add the usage of another request
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+    }
+}
+
 /// Information about a message
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -61,15 +84,43 @@ pub enum MessageInfo {
     ToolResult {
         tool_name: String,
         tool_index: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provenance: Option<Provenance>,
     },
 
     /// Tool error
     ToolError {
         tool_name: String,
         tool_index: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provenance: Option<Provenance>,
     },
 }
 
+impl MessageInfo {
+    /// Where the content of a tool result or error came from, if recorded
+    pub fn provenance(&self) -> Option<&Provenance> {
+        match self {
+            MessageInfo::ToolResult { provenance, .. }
+            | MessageInfo::ToolError { provenance, .. } => provenance.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Where the content of a tool result came from
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct Provenance {
+    /// File, URL or command the tool produced the content from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Size of the tool's output in bytes, as it entered the conversation
+    pub bytes: usize,
+    /// Whether the output was truncated before it entered the conversation
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
 /// Cache control information
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -118,6 +169,18 @@ impl Message {
 pub enum ImageSource {
     Base64 { media_type: String, data: String },
 }
+/// Source of a document, in the shape of Anthropic's document blocks
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DocumentSource {
+    /// Base64-encoded file, such as a PDF
+    Base64 { media_type: String, data: String },
+    /// Plain text
+    Text { media_type: String, data: String },
+    /// File the provider fetches itself
+    Url { url: String },
+}
+
 /// Content of a message
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)] // Added PartialEq
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -142,7 +205,64 @@ pub enum Content {
     /// Image content
     Image { source: ImageSource },
 
-    /// Document content
-    Document { source: String },
+    /// Document content, such as a PDF
+    Document {
+        source: DocumentSource,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+    },
+
+    /// Call of a tool through the provider's native tool calling
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
 
+    /// Result of a native tool call, answering the [`Content::ToolUse`] with `tool_use_id`
+    ToolResult {
+        tool_use_id: String,
+        #[serde(default)]
+        content: Vec<Content>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        is_error: bool,
+    },
+}
+
+impl Content {
+    /// Text standing in for the content with backends that cannot send it natively
+    pub fn fallback_text(&self) -> String {
+        match self {
+            Content::Text { text } => text.clone(),
+            Content::Thinking { thinking, .. } => thinking.clone().unwrap_or_default(),
+            Content::RedactedThinking { data } => data.clone().unwrap_or_default(),
+            Content::Image { .. } => "[Image]".to_string(),
+            Content::Document { source, title } => {
+                let title = title.as_deref().unwrap_or("untitled");
+                match source {
+                    DocumentSource::Text { data, .. } => format!("[Document: {title}]\n{data}"),
+                    DocumentSource::Base64 { media_type, .. } => {
+                        format!("[Document: {title} ({media_type}), not readable by this model]")
+                    }
+                    DocumentSource::Url { url } => format!("[Document: {title} at {url}]"),
+                }
+            }
+            Content::ToolUse { id, name, input } => {
+                format!("[Tool call {id}]: {name} {input}")
+            }
+            Content::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } => {
+                let kind = if *is_error {
+                    "Tool error"
+                } else {
+                    "Tool result"
+                };
+                let parts: Vec<String> = content.iter().map(Content::fallback_text).collect();
+                format!("[{kind} {tool_use_id}]: {}", parts.join("\n"))
+            }
+        }
+    }
 }
@@ -0,0 +1,284 @@
+//! JSON-RPC transport to a language server over stdio
+//!
+//! Messages are framed with `Content-Length` headers. Requests from the client
+//! are matched to responses by id; requests from the server (progress tokens,
+//! capability registration, configuration) are answered with empty results so
+//! the server does not wait on us.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Duration;
+
+/// Longest a single request may take (servers index the workspace on startup)
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+type PendingResponses = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, String>>>>>;
+
+/// A running language server
+pub struct LspClient {
+    outgoing: mpsc::UnboundedSender<Value>,
+    pending: PendingResponses,
+    next_id: AtomicU64,
+    /// Version and text of each document opened on the server
+    documents: Mutex<HashMap<PathBuf, (i64, String)>>,
+    /// Kept so the server is killed when the client is dropped
+    _child: Child,
+}
+
+impl LspClient {
+    /// Start the server and perform the initialize handshake
+    pub async fn start(command: &str, root: &Path) -> Result<Self, String> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or("Empty language server command")?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .current_dir(root)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to start language server '{program}': {e}"))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or("Failed to open language server stdin")?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or("Failed to open language server stdout")?;
+
+        let (outgoing, receiver) = mpsc::unbounded_channel();
+        let pending: PendingResponses = Arc::new(Mutex::new(HashMap::new()));
+        crate::output::spawn(write_messages(stdin, receiver));
+        crate::output::spawn(read_messages(stdout, pending.clone(), outgoing.clone()));
+
+        let client = Self {
+            outgoing,
+            pending,
+            next_id: AtomicU64::new(1),
+            documents: Mutex::new(HashMap::new()),
+            _child: child,
+        };
+
+        let root_uri = super::path_to_uri(root);
+        client
+            .request(
+                "initialize",
+                json!({
+                    "processId": std::process::id(),
+                    "rootUri": root_uri,
+                    "workspaceFolders": [{ "uri": root_uri, "name": "workspace" }],
+                    "capabilities": {
+                        "textDocument": {
+                            "definition": { "linkSupport": true },
+                            "references": {},
+                            "hover": { "contentFormat": ["markdown", "plaintext"] },
+                            "rename": { "prepareSupport": false },
+                        },
+                        "workspace": {
+                            "workspaceEdit": { "documentChanges": true },
+                            "configuration": true,
+                        },
+                        "window": { "workDoneProgress": true },
+                    },
+                }),
+            )
+            .await?;
+        client.notify("initialized", json!({}))?;
+        Ok(client)
+    }
+
+    /// Send a request and wait for its result
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, String> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, sender);
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, receiver).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err("The language server exited".to_string()),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                Err(format!(
+                    "The language server did not answer {method} within {} seconds",
+                    REQUEST_TIMEOUT.as_secs()
+                ))
+            }
+        }
+    }
+
+    /// Send a notification
+    pub fn notify(&self, method: &str, params: Value) -> Result<(), String> {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    fn send(&self, message: Value) -> Result<(), String> {
+        self.outgoing
+            .send(message)
+            .map_err(|_| "The language server exited".to_string())
+    }
+
+    /// Make the server see the current content of a file, opening it if needed
+    pub fn sync_document(&self, path: &Path) -> Result<(), String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let uri = super::path_to_uri(path);
+
+        let mut documents = self.documents.lock().unwrap();
+        match documents.get_mut(path) {
+            Some((_, current)) if *current == text => Ok(()),
+            Some((version, current)) => {
+                *version += 1;
+                *current = text.clone();
+                self.notify(
+                    "textDocument/didChange",
+                    json!({
+                        "textDocument": { "uri": uri, "version": *version },
+                        "contentChanges": [{ "text": text }],
+                    }),
+                )
+            }
+            None => {
+                documents.insert(path.to_path_buf(), (1, text.clone()));
+                self.notify(
+                    "textDocument/didOpen",
+                    json!({
+                        "textDocument": {
+                            "uri": uri,
+                            "languageId": super::language_id(path),
+                            "version": 1,
+                            "text": text,
+                        },
+                    }),
+                )
+            }
+        }
+    }
+}
+
+/// Write framed messages to the server
+async fn write_messages(mut stdin: ChildStdin, mut receiver: mpsc::UnboundedReceiver<Value>) {
+    while let Some(message) = receiver.recv().await {
+        let body = message.to_string();
+        let frame = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+        if stdin.write_all(frame.as_bytes()).await.is_err() || stdin.flush().await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Read framed messages from the server, resolving pending requests and
+/// answering server requests
+async fn read_messages(
+    stdout: ChildStdout,
+    pending: PendingResponses,
+    outgoing: mpsc::UnboundedSender<Value>,
+) {
+    let mut reader = BufReader::new(stdout);
+    while let Some(message) = read_message(&mut reader).await {
+        let id = message.get("id").cloned();
+        match (id, message.get("method").and_then(|m| m.as_str())) {
+            // Request from the server
+            (Some(id), Some(method)) => {
+                let _ = outgoing.send(json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "result": server_request_result(method, message.get("params")),
+                }));
+            }
+            // Response to one of our requests
+            (Some(id), None) => {
+                let Some(sender) = id
+                    .as_u64()
+                    .and_then(|id| pending.lock().unwrap().remove(&id))
+                else {
+                    continue;
+                };
+                let result = match message.get("error") {
+                    Some(error) => Err(format!(
+                        "Language server error: {}",
+                        error
+                            .get("message")
+                            .and_then(|m| m.as_str())
+                            .unwrap_or("unknown error")
+                    )),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = sender.send(result);
+            }
+            // Notifications (diagnostics, progress, log messages) are not used
+            _ => {}
+        }
+    }
+
+    // Fail the requests still waiting for an answer
+    pending.lock().unwrap().clear();
+}
+
+/// Read one `Content-Length` framed message
+async fn read_message(reader: &mut BufReader<ChildStdout>) -> Option<Value> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await.ok()? == 0 {
+            return None;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body = vec![0; length?];
+    reader.read_exact(&mut body).await.ok()?;
+    // Skip malformed messages instead of closing the connection
+    Some(serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// Result sent for a request from the server
+fn server_request_result(method: &str, params: Option<&Value>) -> Value {
+    match method {
+        // One (empty) setting per requested section
+        "workspace/configuration" => {
+            let items = params
+                .and_then(|p| p.get("items"))
+                .and_then(|items| items.as_array())
+                .map_or(0, |items| items.len());
+            Value::Array(vec![Value::Null; items])
+        }
+        // Edits are applied by the rename tool itself
+        "workspace/applyEdit" => json!({ "applied": false }),
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_request_result() {
+        let params = json!({ "items": [{ "section": "rust-analyzer" }, { "section": "files" }] });
+        assert_eq!(
+            server_request_result("workspace/configuration", Some(&params)),
+            json!([null, null])
+        );
+        assert_eq!(
+            server_request_result("window/workDoneProgress/create", None),
+            Value::Null
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Language server integration for precise code navigation
+//!
+//! With `--lsp`, the `definition`, `references`, `hover` and `rename` tools are
+//! answered by a language server for the workspace. The server is chosen from
+//! the project files (rust-analyzer, gopls, typescript-language-server, pylsp)
+//! unless a command is given with `--lsp COMMAND`, and it is started on first
+//! use so sessions that never navigate code don't pay for indexing.
+
+mod client;
+
+pub use client::LspClient;
+
+use lazy_static::lazy_static;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Tools backed by the language server
+pub const TOOLS: &[&str] = &["definition", "references", "hover", "rename"];
+
+/// Language servers used when no command is configured, by project file
+const DEFAULT_SERVERS: &[(&str, &str)] = &[
+    ("Cargo.toml", "rust-analyzer"),
+    ("go.mod", "gopls"),
+    ("tsconfig.json", "typescript-language-server --stdio"),
+    ("package.json", "typescript-language-server --stdio"),
+    ("pyproject.toml", "pylsp"),
+    ("setup.py", "pylsp"),
+];
+
+/// Whether the language server tools are enabled and the server command
+#[derive(Default)]
+struct LspSettings {
+    enabled: bool,
+    command: Option<String>,
+}
+
+lazy_static! {
+    static ref SETTINGS: RwLock<LspSettings> = RwLock::new(LspSettings::default());
+    /// The running server, started on first use
+    static ref CLIENT: tokio::sync::Mutex<Option<Arc<LspClient>>> = tokio::sync::Mutex::new(None);
+}
+
+/// Enable the language server tools; an empty command selects the server
+/// from the project files
+pub fn configure(command: Option<&str>) {
+    let mut settings = SETTINGS.write().unwrap();
+    settings.enabled = command.is_some();
+    settings.command = command
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+        .map(str::to_string);
+}
+
+/// Whether the language server tools are available
+pub fn is_enabled() -> bool {
+    SETTINGS.read().unwrap().enabled
+}
+
+/// The language server for the current directory, starting it if needed
+pub async fn client() -> Result<Arc<LspClient>, String> {
+    if !is_enabled() {
+        return Err("Language server tools are disabled; start termineer with --lsp".to_string());
+    }
+    let mut client = CLIENT.lock().await;
+    if let Some(client) = client.as_ref() {
+        return Ok(client.clone());
+    }
+
+    // Canonical, like the paths the tools validate
+    let root = std::env::current_dir()
+        .and_then(|dir| dir.canonicalize())
+        .map_err(|e| format!("Failed to read the current directory: {e}"))?;
+    let configured = SETTINGS.read().unwrap().command.clone();
+    let command = configured
+        .or_else(|| detect_server(&root).map(str::to_string))
+        .ok_or_else(|| {
+            "No language server known for this project; pass one with --lsp COMMAND".to_string()
+        })?;
+    bprintln!(info: "Starting language server: {}", command);
+    let started = Arc::new(LspClient::start(&command, &root).await?);
+    *client = Some(started.clone());
+    Ok(started)
+}
+
+/// Default server for the project files in a directory
+fn detect_server(dir: &Path) -> Option<&'static str> {
+    DEFAULT_SERVERS
+        .iter()
+        .find(|(file, _)| dir.join(file).exists())
+        .map(|(_, command)| *command)
+}
+
+/// Language identifier sent when opening a document
+fn language_id(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).unwrap_or("") {
+        "rs" => "rust",
+        "go" => "go",
+        "py" => "python",
+        "ts" => "typescript",
+        "tsx" => "typescriptreact",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "javascriptreact",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "hpp" => "cpp",
+        _ => "plaintext",
+    }
+}
+
+/// `file://` URI of an absolute path
+pub fn path_to_uri(path: &Path) -> String {
+    let encoded: Vec<String> = path
+        .to_string_lossy()
+        .split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect();
+    format!("file://{}", encoded.join("/"))
+}
+
+/// Path of a `file://` URI, if it is one
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let decoded = urlencoding::decode(path).ok()?;
+    Some(PathBuf::from(decoded.into_owned()))
+}
+
+/// Byte offset of an LSP position (0-based line, UTF-16 column) in a text
+pub fn position_to_offset(text: &str, line: u32, character: u32) -> Option<usize> {
+    let line_start = if line == 0 {
+        0
+    } else {
+        text.match_indices('\n').nth(line as usize - 1)?.0 + 1
+    };
+    let line_text = text[line_start..].split('\n').next().unwrap_or("");
+    let mut units = 0;
+    for (offset, ch) in line_text.char_indices() {
+        if units >= character {
+            return Some(line_start + offset);
+        }
+        units += ch.len_utf16() as u32;
+    }
+    Some(line_start + line_text.len())
+}
+
+/// UTF-16 column of a byte offset within a line
+pub fn utf16_column(line_text: &str, byte_offset: usize) -> u32 {
+    line_text[..byte_offset.min(line_text.len())]
+        .chars()
+        .map(|ch| ch.len_utf16() as u32)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri_round_trip() {
+        let path = Path::new("/home/me/my project/src/main.rs");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///home/me/my%20project/src/main.rs");
+        assert_eq!(uri_to_path(&uri).as_deref(), Some(path));
+        assert_eq!(uri_to_path("untitled:1"), None);
+    }
+
+    #[test]
+    fn test_positions() {
+        let text = "fn main() {\n    let é = \"😀x\";\n}\n";
+        assert_eq!(position_to_offset(text, 0, 3), Some(3));
+        // "😀" is two UTF-16 code units
+        let line = "    let é = \"😀x\";";
+        let x = line.find('x').unwrap();
+        assert_eq!(utf16_column(line, x), 15);
+        assert_eq!(position_to_offset(text, 1, 15), Some(12 + x));
+        // Columns past the end of the line clamp to it
+        assert_eq!(position_to_offset(text, 2, 10), Some(text.len() - 1));
+        assert_eq!(position_to_offset(text, 9, 0), None);
+    }
+}
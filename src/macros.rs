@@ -131,3 +131,20 @@ macro_rules! bprintln {
         });
     }};
 }
+
+/// Localized text of the command line and the terminal UI (see `crate::i18n`)
+///
+/// - t!("cli.logged_out")                    - Text without arguments
+/// - t!("cli.logged_in", email = address)    - Text with `{email}` replaced
+#[macro_export]
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::text($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::text(
+            $key,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
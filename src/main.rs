@@ -2,18 +2,37 @@
 mod macros;
 mod agent;
 mod ansi_converter;
+mod auth;
+mod batch;
+mod changelog;
 mod cli;
 mod config;
 mod constants;
+mod control;
 mod conversation;
+mod credentials;
+mod eval;
+mod git;
+mod hooks;
+mod index;
 pub mod jsonpath;
 mod llm;
+mod lsp;
+mod net;
 
 mod gui;
+mod i18n;
+mod inbox;
+mod isolation;
 mod mcp;
 mod output;
 mod prompts;
+mod review;
 pub mod serde;
+mod share;
+mod starters;
+mod structured;
+mod telemetry;
 mod tools;
 mod tui;
 mod version_check;
@@ -22,7 +41,9 @@ mod workflow;
 use crate::agent::AgentId;
 use anyhow::format_err;
 use clap::Parser;
-use cli::{cli_to_config, Cli, Commands};
+use cli::{
+    cli_to_config, Cli, Commands, HooksAction, IndexAction, KbAction, KeysAction, TelemetryAction,
+};
 use config::Config;
 use crossterm::{
     cursor, execute,
@@ -31,7 +52,7 @@ use crossterm::{
 use std::collections::HashMap;
 use std::io;
 use std::time::Duration;
-use tui::TuiInterface;
+use tui::{PlainInterface, TuiInterface};
 
 /// Get comprehensive information about all MCP tools
 ///
@@ -55,7 +76,7 @@ pub async fn get_mcp_tools_info() -> HashMap<String, Vec<(String, String)>> {
 
                 for tool in tools {
                     let description = if tool.description.is_empty() {
-                        "No description".to_string()
+                        t!("cli.no_description")
                     } else {
                         tool.description.clone()
                     };
@@ -78,7 +99,7 @@ pub async fn get_mcp_tools_info() -> HashMap<String, Vec<(String, String)>> {
 async fn initialize_and_log_mcp() {
     // Initialize MCP connections from config (silent mode = true)
     if let Err(e) = crate::mcp::config::initialize_mcp_from_config(true).await {
-        bprintln!(error: "Failed to initialize MCP connections: {e}");
+        bprintln!(error: "{}", t!("cli.mcp_init_failed", error = e));
         // Continue even if MCP initialization fails
     }
 
@@ -93,8 +114,9 @@ async fn initialize_and_log_mcp() {
 
     // Log header
     bprintln!(
-        "\n🔌 {}Available MCP tools:{}",
+        "\n🔌 {}{}{}",
         crate::constants::FORMAT_BOLD,
+        t!("cli.mcp_tools"),
         crate::constants::FORMAT_RESET
     );
 
@@ -108,24 +130,28 @@ async fn initialize_and_log_mcp() {
         if !tools.is_empty() {
             // Log provider name and tool count
             bprintln!(
-                "{}📦 {} ({} tools){}",
+                "{}📦 {}{}",
                 crate::constants::FORMAT_BLUE,
-                provider_name,
-                tools.len(),
+                t!(
+                    "cli.mcp_server_tools",
+                    server = provider_name,
+                    count = tools.len()
+                ),
                 crate::constants::FORMAT_RESET
             );
 
             // Log each tool with its description
             for tool in tools {
                 let description = if tool.description.is_empty() {
-                    "No description".to_string()
+                    t!("cli.no_description")
                 } else {
                     tool.description.clone()
                 };
 
                 bprintln!(
-                    "  • {}{}{}: {}",
+                    "  • {}{}.{}{}: {}",
                     crate::constants::FORMAT_BOLD,
+                    provider_name,
                     tool.name,
                     crate::constants::FORMAT_RESET,
                     description
@@ -134,6 +160,11 @@ async fn initialize_and_log_mcp() {
         }
     }
 
+    // Report tools that cannot be called by their bare name
+    for conflict in crate::mcp::manager::conflict_report() {
+        bprintln!(warn: "{}", conflict);
+    }
+
     // Add a blank line at the end
     bprintln!("");
 }
@@ -152,8 +183,36 @@ async fn main() -> anyhow::Result<()> {
     // Parse command line arguments using clap
     let cli = Cli::parse();
 
+    // Show the command line and TUI in the requested language, or the locale's
+    if let Some(language) = &cli.language {
+        i18n::set_language(language);
+    }
+
+    // A project starter gives the opening prompt and may set the kind and model
+    let starter = match &cli.starter {
+        Some(name) => Some(starters::load(name).map_err(|e| format_err!(e))?),
+        None => None,
+    };
+
     // Convert to application config
-    let config = cli_to_config(&cli);
+    let config = cli_to_config(&cli, starter.as_ref());
+
+    // Check the settings of modes that create agents before any is created
+    let creates_agents = cli.apply.is_none()
+        && matches!(
+            cli.command,
+            None | Some(Commands::Batch { .. })
+                | Some(Commands::Changelog { .. })
+                | Some(Commands::Review { .. })
+                | Some(Commands::Workflow { .. })
+        );
+    if creates_agents {
+        config.validate().map_err(|e| format_err!(e))?;
+    }
+
+    // Apply proxy and CA settings to every HTTP client created from now on
+    net::configure(config.proxy.as_deref(), config.ca_bundle.as_deref())
+        .map_err(|e| format_err!(e))?;
 
     // Set the app mode based on build configuration
     #[cfg(debug_assertions)]
@@ -163,30 +222,207 @@ async fn main() -> anyhow::Result<()> {
     }
     #[cfg(not(debug_assertions))]
     {
-        // In release builds, use Free mode (authentication removed)
-        config::set_app_mode(config::AppMode::Free);
+        // In release builds, features depend on the logged-in account's subscription
+        config::set_app_mode(auth::resolve_app_mode().await);
     }
 
+    // Load the telemetry preference (anonymous counters are only kept when opted in)
+    telemetry::init();
+
+    // Limit how deep agents may spawn sub-agents
+    if let Some(max_depth) = config.max_spawn_depth {
+        agent::set_max_spawn_depth(max_depth);
+    }
+
+    // Configure the LLM response cache before any backend is created
+    llm::cache::configure(
+        config.llm_cache,
+        config.llm_cache_ttl.map(Duration::from_secs),
+    );
+
+    // Record token usage across sessions and warn about the monthly budget
+    llm::ledger::configure(config.usage_ledger, config.monthly_budget);
+
+    // Choose how providers with several API keys rotate between them
+    llm::keys::configure(config.key_rotation);
+
+    // Configure the cache of pages retrieved by the fetch tool
+    tools::fetch_cache::configure(
+        config.fetch_cache,
+        config.fetch_cache_ttl.map(Duration::from_secs),
+    );
+
+    // Configure which sites network tools may access
+    tools::network::configure(tools::network::NetworkPolicy {
+        allowed_domains: config.allowed_domains.clone(),
+        blocked_domains: config.blocked_domains.clone(),
+        max_download_size: config.max_download_size,
+        respect_robots_txt: config.respect_robots_txt,
+    });
+
+    // Limit what agents may do with the computer tool
+    #[cfg(target_os = "macos")]
+    tools::ui::computer::configure(tools::ui::computer::ComputerPolicy {
+        max_actions: config
+            .computer_max_actions
+            .unwrap_or(tools::ui::computer::DEFAULT_MAX_ACTIONS),
+        allowed_apps: config.computer_apps.clone(),
+    });
+
+    // Treat output of tools returning third-party content as untrusted
+    tools::guard::configure(tools::guard::GuardSettings {
+        enabled: config.content_guard,
+        classifier_model: config.injection_classifier.clone(),
+        classifier_sampling: config.sampling.for_task(llm::sampling::SamplingTask::Check),
+    });
+
+    // Summarize MCP servers in the prompt unless full tool docs are requested
+    mcp::manager::set_eager_tool_docs(config.eager_mcp_tools);
+
+    // Configure the pseudo-terminal used by shell commands
+    let mut pty_settings = tools::shell::PtySettings {
+        enabled: config.shell_pty,
+        ..Default::default()
+    };
+    if let Some((cols, rows)) = config.pty_size {
+        pty_settings.cols = cols;
+        pty_settings.rows = rows;
+    }
+    tools::shell::configure_pty(pty_settings);
+
+    // Offer the language server tools when --lsp is given
+    lsp::configure(config.lsp.as_deref());
+
+    // Select the embedding model used by semantic search
+    index::configure(config.embedding_model.as_deref());
+
+    // Add user patterns for lines preserved when truncating shell output
+    tools::truncate::configure_priority_patterns(&config.truncation_keep_patterns)
+        .map_err(|e| format_err!(e))?;
+
     // Note: MCP servers will now be initialized with a buffer right before agent creation
 
     // Handle different command/argument combinations
     match &cli.command {
         Some(Commands::Login) => {
-            // Authentication has been removed
+            let session = auth::login()
+                .await
+                .map_err(|e| format_err!(t!("cli.login_failed", error = e)))?;
             execute!(
                 io::stdout(),
-                SetForegroundColor(Color::Blue),
-                Print("ℹ️ Authentication has been removed from this version"),
+                SetForegroundColor(Color::Green),
+                Print(t!("cli.logged_in", email = session.claims.email)),
                 ResetColor,
                 cursor::MoveToNextLine(1),
             )
             .unwrap();
-            println!("All functionality is available without authentication.");
+            println!(
+                "{}",
+                t!("cli.subscription", mode = session.claims.app_mode())
+            );
+            return Ok(());
+        }
+        Some(Commands::Logout) => {
+            if auth::logout().map_err(|e| format_err!(e))? {
+                println!("{}", t!("cli.logged_out"));
+            } else {
+                println!("{}", t!("cli.not_logged_in"));
+            }
+            return Ok(());
+        }
+        Some(Commands::Keys { action }) => {
+            run_keys_command(action).await.map_err(|e| format_err!(e))?;
             return Ok(());
         }
-        Some(Commands::ListKinds) => {
+        Some(Commands::Index { action }) => {
+            let stats = index::update(matches!(action, IndexAction::Build))
+                .await
+                .map_err(|e| format_err!(e))?;
+            println!(
+                "{}",
+                t!(
+                    "cli.indexed",
+                    files = stats.files,
+                    chunks = stats.chunks,
+                    model = stats.model,
+                    embedded = stats.embedded,
+                    removed = stats.removed,
+                )
+            );
+            return Ok(());
+        }
+        Some(Commands::Kb { action }) => {
+            run_kb_command(action).await.map_err(|e| format_err!(e))?;
+            return Ok(());
+        }
+        Some(Commands::Models { provider, json }) => {
+            telemetry::record_command("models");
+            list_models(provider.as_deref(), *json)
+                .await
+                .map_err(|e| format_err!(t!("cli.models_failed", error = e)))?;
+            return Ok(());
+        }
+        Some(Commands::Usage { since, by }) => {
+            telemetry::record_command("usage");
+            print!("{}", llm::ledger::report(*since, *by));
+            return Ok(());
+        }
+        Some(Commands::Doctor { prompt }) => {
+            telemetry::record_command("doctor");
+            run_doctor(*prompt).map_err(|e| format_err!(e))?;
+            return Ok(());
+        }
+        Some(Commands::Telemetry { action }) => {
+            match action {
+                TelemetryAction::Status => {}
+                TelemetryAction::On => telemetry::set_enabled(true)
+                    .map_err(|e| format_err!(t!("cli.telemetry_failed", error = e)))?,
+                TelemetryAction::Off => telemetry::set_enabled(false)
+                    .map_err(|e| format_err!(t!("cli.telemetry_failed", error = e)))?,
+            }
+            println!("{}", telemetry::status());
+            return Ok(());
+        }
+        Some(Commands::Hooks { action }) => {
+            let report = match action {
+                HooksAction::Install { force } => hooks::install(*force).await,
+                HooksAction::Uninstall => hooks::uninstall().await,
+                HooksAction::Run { hook, args } => {
+                    telemetry::record_command("hooks");
+                    hooks::run(config, *hook, args)
+                        .await
+                        .map_err(|e| format_err!(e))?;
+                    return Ok(());
+                }
+            }
+            .map_err(|e| format_err!(t!("cli.hooks_failed", error = e)))?;
+            if report.is_empty() {
+                println!("{}", t!("cli.hooks_none"));
+            }
+            for line in report {
+                println!("{line}");
+            }
+            return Ok(());
+        }
+        Some(Commands::ListKinds { json }) => {
             // List available agent kinds and exit
-            list_available_kinds().map_err(|e| format_err!("Error listing kinds: {}", e))?;
+            list_available_kinds(*json)
+                .map_err(|e| format_err!(t!("cli.kinds_failed", error = e)))?;
+            return Ok(());
+        }
+        Some(Commands::Attach { name }) => {
+            let name = name.clone().unwrap_or_else(share::default_name);
+            share::attach(&name).await.map_err(|e| format_err!(e))?;
+            return Ok(());
+        }
+        Some(Commands::Status { format }) => {
+            match control::status().await {
+                Ok(Some(status)) => println!("{}", status.format(*format)),
+                Ok(None) => {}
+                // Status bars show nothing while no session runs
+                Err(_) if *format == control::StatusFormat::Tmux => {}
+                Err(e) => return Err(format_err!(e)),
+            }
             return Ok(());
         }
         Some(Commands::Gui) => {
@@ -194,6 +430,65 @@ async fn main() -> anyhow::Result<()> {
             gui::run_gui();
             return Ok(());
         }
+        Some(Commands::Eval { suite }) => {
+            telemetry::record_command("eval");
+            run_eval_mode(config, suite)
+                .await
+                .map_err(|e| format_err!(t!("cli.eval_failed", error = e)))?;
+            return Ok(());
+        }
+        Some(Commands::Batch {
+            queries,
+            parallel,
+            output,
+        }) => {
+            telemetry::record_command("batch");
+            run_batch_mode(config, queries, *parallel, output.clone())
+                .await
+                .map_err(|e| format_err!(t!("cli.batch_failed", error = e)))?;
+            return Ok(());
+        }
+        Some(Commands::Changelog {
+            from,
+            to,
+            release,
+            output,
+            yes,
+        }) => {
+            telemetry::record_command("changelog");
+            let written = changelog::run(config, from, to, release, output, *yes).await;
+            agent::terminate_all().await;
+            let written =
+                written.map_err(|e| format_err!(t!("cli.changelog_failed", error = e)))?;
+            if written {
+                println!("{}", t!("cli.changelog_written", path = output.display()));
+            } else {
+                println!(
+                    "{}",
+                    t!("cli.changelog_not_written", path = output.display())
+                );
+            }
+            return Ok(());
+        }
+        Some(Commands::Review {
+            base,
+            chunk_tokens,
+            parallel,
+            post,
+        }) => {
+            telemetry::record_command("review");
+            let report = review::run(config, base, *chunk_tokens, *parallel).await;
+            agent::terminate_all().await;
+            let report = report.map_err(|e| format_err!(t!("cli.review_failed", error = e)))?;
+            println!("{report}");
+            if let Some(pull_request) = post {
+                review::post(&report, Some(pull_request))
+                    .await
+                    .map_err(|e| format_err!(t!("cli.review_failed", error = e)))?;
+                eprintln!("{}", t!("cli.review_posted"));
+            }
+            return Ok(());
+        }
         Some(Commands::Workflow {
             name,
             parameters,
@@ -204,14 +499,12 @@ async fn main() -> anyhow::Result<()> {
                 execute!(
                     io::stdout(),
                     SetForegroundColor(Color::Yellow),
-                    Print("⚠️ Workflows are a Pro-only feature"),
+                    Print(t!("cli.workflows_pro")),
                     ResetColor,
                     cursor::MoveToNextLine(1),
                 )
                 .unwrap();
-                println!(
-                    "Upgrade to Pro for access to workflows and advanced orchestration features."
-                );
+                println!("{}", t!("cli.workflows_upgrade"));
                 return Ok(());
             }
 
@@ -223,14 +516,15 @@ async fn main() -> anyhow::Result<()> {
             };
 
             // Run in workflow mode
+            telemetry::record_command("workflow");
             run_workflow_mode(config, name.clone(), parameters.clone(), query_string)
                 .await
-                .map_err(|e| format_err!("Error in workflow mode: {}", e))?;
+                .map_err(|e| format_err!(t!("cli.workflow_failed", error = e)))?;
 
             return Ok(());
         }
         #[cfg(debug_assertions)]
-        Some(Commands::DumpPrompts { .. }) => {
+        Some(Commands::DumpPrompts { stats, .. }) => {
             // Dump prompt templates and exit
             // The template name is already in the config
             if let Some(template_name) = &config.dump_prompts {
@@ -247,6 +541,41 @@ async fn main() -> anyhow::Result<()> {
                 all_tools_vec.sort_unstable();
                 all_tools_vec.dedup();
 
+                if *stats {
+                    // Additions from user settings that are appended to the prompt
+                    let mut additions = Vec::new();
+                    if config.plan_mode {
+                        additions.push(("plan mode", crate::tools::plan::PLAN_MODE_PROMPT));
+                    }
+                    if config.propose_changes {
+                        additions
+                            .push(("propose mode", crate::tools::proposals::PROPOSE_MODE_PROMPT));
+                    }
+
+                    // Connect configured MCP servers so their section is measured too;
+                    // their log output is discarded
+                    let result = crate::output::CURRENT_BUFFER
+                        .scope(crate::output::SharedBuffer::new(200), async {
+                            let _ = crate::mcp::config::initialize_mcp_from_config(true).await;
+                            prompts::stats::prompt_stats(
+                                template_name,
+                                &all_tools_vec,
+                                grammar,
+                                &additions,
+                            )
+                        })
+                        .await;
+
+                    match result {
+                        Ok(stats) => print!("{}", prompts::stats::format_stats(&stats)),
+                        Err(e) => {
+                            eprintln!("Error rendering template '{template_name}': {e}");
+                            std::process::exit(1);
+                        }
+                    }
+                    return Ok(());
+                }
+
                 // Render the template
                 match prompts::render_template(template_name, &all_tools_vec, grammar) {
                     Ok(rendered_content) => {
@@ -278,57 +607,283 @@ async fn main() -> anyhow::Result<()> {
             return Ok(());
         }
         None => {
-            // Check if we have a query for non-interactive mode
-            if let Some(query) = cli.query {
-                // Run in single query mode
-                run_single_query_mode(config, query)
+            if let Some(plan_file) = &cli.apply {
+                // Execute a previously approved plan
+                telemetry::record_command("apply");
+                run_apply_mode(plan_file)
                     .await
-                    .map_err(|e| format_err!("Error in single query mode: {}", e))?;
+                    .map_err(|e| format_err!(t!("cli.apply_failed", error = e)))?;
             } else {
-                // Run in interactive mode
-                run_interactive_mode(config)
+                // Move into a temporary copy of the working tree before any agent starts
+                if config.isolated {
+                    isolation::start()
+                        .await
+                        .map_err(|e| format_err!(t!("cli.isolation_failed", error = e)))?;
+                }
+
+                let result = if let Some(query) = cli.query {
+                    // Run in single query mode
+                    telemetry::record_command("query");
+                    let query = match &starter {
+                        Some(starter) => starter.prompt_with(&query),
+                        None => query,
+                    };
+                    run_single_query_mode(config, query)
+                        .await
+                        .map_err(|e| format_err!(t!("cli.query_failed", error = e)))
+                } else {
+                    // Run in interactive mode
+                    telemetry::record_command("interactive");
+                    let opening = starter.map(|starter| starter.prompt);
+                    run_interactive_mode(config, opening)
+                        .await
+                        .map_err(|e| format_err!(t!("cli.interactive_failed", error = e)))
+                };
+
+                // Review the isolated changes even if the session failed
+                isolation::finish()
                     .await
-                    .map_err(|e| format_err!("Error in interactive mode: {}", e))?;
+                    .map_err(|e| format_err!(t!("cli.isolation_finish_failed", error = e)))?;
+                result?;
             }
         }
     }
 
-    println!("Termineer terminated successfully.");
+    // Buffer this run's counters and upload them if the user opted in
+    if telemetry::is_enabled() {
+        telemetry::flush().await;
+    }
+
+    println!("{}", t!("cli.terminated"));
     // Explicit use of Result with the expected return type
     Ok(())
 }
 
+/// List the models of the configured providers, merged with the model catalog
+async fn list_models(provider: Option<&str>, json: bool) -> anyhow::Result<()> {
+    if let Some(provider) = provider {
+        credentials::check_provider(provider).map_err(|e| format_err!(e))?;
+    }
+
+    let report = llm::discovery::list_models(provider).await;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", llm::discovery::format_table(&report));
+    }
+    Ok(())
+}
+
 /// List all available agent kinds
-fn list_available_kinds() -> anyhow::Result<()> {
-    // Print the list of available kinds - use Pro mode to show all kinds for upselling
-    println!("{}", prompts::get_kinds_for_mode(config::AppMode::Pro));
+fn list_available_kinds(json: bool) -> anyhow::Result<()> {
+    // List all kinds, including those of higher tiers for upselling
+    let kinds = prompts::kinds::list().map_err(|e| format_err!(e))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&kinds)?);
+        return Ok(());
+    }
+    print!("{}", prompts::kinds::format_table(&kinds));
 
     // Usage information in a single string
-    let usage_text = obfstr::obfstring!(
-        r#"
-Use with: --kind KIND_NAME
-Example: --kind researcher
-For advanced templates: --kind plus/researcher"#
-    );
-
-    println!("{}", usage_text);
+    println!("{}", t!("cli.kinds_usage"));
 
     // Explicit use of Result with the expected return type
     Ok(())
 }
 
-/// Run the application in interactive mode with TUI
-async fn run_interactive_mode(config: Config) -> anyhow::Result<()> {
+/// Prompt for a provider's API key
+fn read_api_key(provider: &str) -> Result<String, String> {
+    credentials::check_provider(provider)?;
+    let key = credentials::read_secret(&t!("cli.api_key_prompt", provider = provider))
+        .map_err(|e| t!("cli.api_key_read_failed", error = e))?;
+    if key.is_empty() {
+        return Err(t!("cli.api_key_empty"));
+    }
+    Ok(key)
+}
+
+/// Store, remove, list or check provider API keys
+async fn run_keys_command(action: &KeysAction) -> Result<(), String> {
+    match action {
+        KeysAction::Set { provider } => {
+            credentials::set_api_key(provider, &read_api_key(provider)?)?;
+            println!("{}", t!("cli.key_stored", provider = provider));
+        }
+        KeysAction::Add { provider } => {
+            let count = credentials::add_api_key(provider, &read_api_key(provider)?)?;
+            println!(
+                "{}",
+                t!("cli.key_count", provider = provider, count = count)
+            );
+        }
+        KeysAction::Remove { provider } => {
+            if credentials::remove_api_key(provider)? {
+                println!("{}", t!("cli.keys_removed", provider = provider));
+            } else {
+                println!("{}", t!("cli.no_key", provider = provider));
+            }
+        }
+        KeysAction::List => {
+            for (provider, env_var) in credentials::PROVIDERS {
+                let source = match credentials::find_api_keys(provider) {
+                    Some((keys, source)) if keys.len() > 1 => {
+                        t!("cli.key_source_count", source = source, count = keys.len())
+                    }
+                    Some((_, source)) => source.to_string(),
+                    None => t!("cli.key_not_set", provider = provider, env_var = env_var),
+                };
+                println!("{provider:<12} {source}");
+            }
+        }
+        KeysAction::Check => {
+            let mut found = false;
+            for (provider, _) in credentials::PROVIDERS {
+                let Some((keys, source)) = credentials::find_api_keys(provider) else {
+                    continue;
+                };
+                found = true;
+                let source = source.to_string();
+                for key in keys {
+                    let check = llm::keys::check_key(provider, &key).await;
+                    println!(
+                        "{provider:<12} {:<14} {:<12} {}",
+                        llm::keys::mask_key(&key),
+                        source,
+                        check.status
+                    );
+                    for headroom in check.headroom {
+                        println!("{:<12}   {headroom}", "");
+                    }
+                }
+            }
+            if !found {
+                println!("{}", t!("cli.no_keys"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add documents to, list or remove knowledge bases
+/// Report the macOS permissions of the UI tools, asking for the missing ones
+/// when `prompt` is set
+#[cfg(target_os = "macos")]
+fn run_doctor(prompt: bool) -> Result<(), String> {
+    use tools::ui::macos::permissions::Permission;
+
+    let mut missing = Vec::new();
+    for permission in Permission::ALL {
+        let mut granted = permission.granted();
+        if !granted && prompt {
+            granted = permission.request();
+            if !granted {
+                println!(
+                    "{}",
+                    t!("cli.doctor_requested", permission = permission.name())
+                );
+            }
+        }
+        let status = if granted {
+            t!("cli.doctor_granted")
+        } else {
+            t!("cli.doctor_missing", tools = permission.tools())
+        };
+        println!("{:<18} {status}", permission.name());
+        if !granted {
+            println!(
+                "{:<18} {}",
+                "",
+                t!("cli.doctor_guidance", permission = permission.name())
+            );
+            missing.push(permission.name());
+        }
+    }
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(t!("cli.doctor_failed", permissions = missing.join(", ")))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn run_doctor(_prompt: bool) -> Result<(), String> {
+    println!("{}", t!("cli.doctor_unsupported"));
+    Ok(())
+}
+
+async fn run_kb_command(action: &KbAction) -> Result<(), String> {
+    match action {
+        KbAction::Add {
+            source,
+            name,
+            max_pages,
+        } => {
+            let stats = index::kb::add(name, source, *max_pages).await?;
+            for skipped in &stats.skipped {
+                eprintln!("{}", t!("cli.kb_skipped", item = skipped));
+            }
+            println!(
+                "{}",
+                t!(
+                    "cli.kb_added",
+                    documents = stats.documents,
+                    name = name,
+                    chunks = stats.chunks,
+                )
+            );
+        }
+        KbAction::List => {
+            let knowledge_bases = index::kb::list();
+            if knowledge_bases.is_empty() {
+                println!("{}", t!("cli.no_kbs"));
+            }
+            for kb in knowledge_bases {
+                let entry = t!(
+                    "cli.kb_entry",
+                    documents = kb.documents,
+                    chunks = kb.chunks,
+                    model = kb.model,
+                );
+                println!("{:<16} {}", kb.name, entry);
+            }
+        }
+        KbAction::Remove { name } => {
+            if index::kb::remove(name)? {
+                println!("{}", t!("cli.kb_removed", name = name));
+            } else {
+                println!("{}", t!("cli.kb_missing", name = name));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run the application in interactive mode with TUI, optionally opening with a prompt
+async fn run_interactive_mode(config: Config, opening: Option<String>) -> anyhow::Result<()> {
     // Check if stdin is a TTY (interactive terminal)
     let is_tty = atty::is(atty::Stream::Stdin);
+    let plain_ui = config.plain_ui;
+    let inbox_agent = config.inbox.clone();
 
-    if !is_tty {
+    if !is_tty && !plain_ui {
         // Non-interactive mode requires a TTY for the TUI
-        eprintln!("TUI interface requires an interactive terminal. Exiting...");
+        eprintln!("{}", t!("cli.tty_required"));
         return // Explicit use of Result with the expected return type
             Ok(());
     }
 
+    // Ask the user before the first use of each tool category
+    tools::permissions::enable_prompts();
+
+    // Name of the local user if the session is shared
+    let share_name = config.share.clone().map(|name| {
+        if name.is_empty() {
+            share::default_name()
+        } else {
+            name
+        }
+    });
+
     // Create a default buffer to be shared between the main agent and TUI
     let default_buffer = crate::output::SharedBuffer::new(200);
 
@@ -346,29 +901,32 @@ async fn run_interactive_mode(config: Config) -> anyhow::Result<()> {
             ) {
                 Ok(id) => {
                     bprintln!(
-                        "🤖 {}Agent{} 'main' created successfully with ID: {}",
-                        crate::constants::FORMAT_BOLD,
-                        crate::constants::FORMAT_RESET,
-                        id
+                        "{}",
+                        t!(
+                            "cli.agent_created",
+                            bold = crate::constants::FORMAT_BOLD,
+                            reset = crate::constants::FORMAT_RESET,
+                            id = id,
+                        )
                     );
                     Ok(id)
                 }
                 Err(e) => {
                     // Use buffer printing for the error
-                    bprintln!(error: "Failed to create main agent: {e}");
+                    bprintln!(error: "{}", t!("cli.agent_failed", error = e));
 
                     // Also print to stderr for TUI visibility
                     execute!(
                         io::stderr(),
                         SetForegroundColor(Color::Red),
-                        Print(format!("Failed to create main agent: {e}")),
+                        Print(t!("cli.agent_failed", error = e)),
                         ResetColor,
                         cursor::MoveToNextLine(1)
                     )?;
 
                     Err(Box::new(std::io::Error::new(
                         std::io::ErrorKind::Other,
-                        format!("Failed to create main agent: {e}"),
+                        t!("cli.agent_failed", error = e),
                     ))
                     .into())
                 }
@@ -391,15 +949,44 @@ async fn run_interactive_mode(config: Config) -> anyhow::Result<()> {
                     };
                 }
             }
-            Err(e) => bprintln!(error: "Failed to check for updates: {:?}", e),
+            Err(e) => {
+                bprintln!(error: "{}", t!("cli.update_check_failed", error = format!("{e:?}")))
+            }
         }
     });
 
+    // Answer `termineer status` while the session runs, unless another session
+    // of the workspace already does
+    let _ = control::serve(main_agent_id).await;
+
+    // Let other participants attach to the main agent
+    if let Some(name) = share_name {
+        share::serve(name, main_agent_id, default_buffer.clone())
+            .await
+            .map_err(|e| format_err!(e))?;
+    }
+
+    // Consume messages dropped in the inbox by other tools
+    if let Some(agent_name) = inbox_agent {
+        output::spawn_with_buffer(default_buffer.clone(), inbox::watch(agent_name));
+    }
+
+    // Start on the opening prompt of a starter
+    if let Some(prompt) = opening {
+        agent::send_message(main_agent_id, agent::AgentMessage::UserInput(prompt))?;
+    }
+
     // Initialize and run the TUI interface with the same buffer
-    let mut tui = TuiInterface::new(main_agent_id)?;
-    tui.run().await.unwrap();
+    if plain_ui {
+        PlainInterface::new(main_agent_id).run().await?;
+    } else {
+        let mut tui = TuiInterface::new(main_agent_id)?;
+        tui.run().await.unwrap();
+    }
 
     // When TUI exits, terminate all agents
+    share::stop();
+    control::stop();
     agent::terminate_all().await;
 
     // Explicit use of Result with the expected return type
@@ -431,18 +1018,21 @@ async fn run_workflow_mode(
             ) {
                 Ok(id) => {
                     bprintln!(
-                        "🤖 {}Agent{} 'main' created successfully with ID: {}",
-                        crate::constants::FORMAT_BOLD,
-                        crate::constants::FORMAT_RESET,
-                        id
+                        "{}",
+                        t!(
+                            "cli.agent_created",
+                            bold = crate::constants::FORMAT_BOLD,
+                            reset = crate::constants::FORMAT_RESET,
+                            id = id,
+                        )
                     );
                     Ok(id)
                 }
                 Err(e) => {
-                    bprintln!(error: "Failed to create main agent: {e}");
+                    bprintln!(error: "{}", t!("cli.agent_failed", error = e));
                     Err(std::io::Error::new(
                         std::io::ErrorKind::Other,
-                        format!("Failed to create main agent: {e}"),
+                        t!("cli.agent_failed", error = e),
                     )
                     .into())
                 }
@@ -471,7 +1061,7 @@ async fn run_workflow_mode(
                     )
                     .await
                     {
-                        bprintln!(error: "Workflow error: {e}");
+                        bprintln!(error: "{}", t!("cli.workflow_error", error = e));
                     }
 
                     // Clean up: terminate all agents
@@ -480,26 +1070,124 @@ async fn run_workflow_mode(
                     Ok(())
                 }
                 Err(e) => {
-                    bprintln!(error: "Failed to load workflow: {e}");
+                    bprintln!(error: "{}", t!("cli.workflow_load_failed", error = e));
 
                     // Clean up: terminate all agents
                     agent::terminate_all().await;
 
-                    Err(anyhow::anyhow!("Failed to load workflow: {}", e))
+                    Err(anyhow::anyhow!(t!("cli.workflow_load_failed", error = e)))
                 }
             }
         })
         .await
 }
 
+/// Execute an approved plan file produced by a `--plan` run
+async fn run_apply_mode(plan_file: &str) -> anyhow::Result<()> {
+    let plan = tools::plan::ActionPlan::load(std::path::Path::new(plan_file))
+        .map_err(|e| format_err!(t!("cli.plan_load_failed", path = plan_file, error = e)))?;
+
+    if plan.is_empty() {
+        println!("{}", t!("cli.plan_empty", path = plan_file));
+        return Ok(());
+    }
+
+    eprintln!("{}", plan.format_summary());
+
+    // Stream tool output to stderr while the plan is applied
+    let buffer = crate::output::SharedBuffer::new(200);
+    let result = crate::output::CURRENT_BUFFER
+        .scope(buffer.clone(), tools::plan::apply_plan(&plan, false))
+        .await;
+
+    for line in buffer.lines().iter() {
+        eprintln!("{}", line.content);
+    }
+
+    match result {
+        Ok(count) => {
+            println!(
+                "{}",
+                t!("cli.plan_applied", count = count, path = plan_file)
+            );
+            Ok(())
+        }
+        Err(e) => Err(format_err!(e)),
+    }
+}
+
+/// Run an eval suite and print the comparison table
+async fn run_eval_mode(config: Config, suite_path: &str) -> anyhow::Result<()> {
+    let suite =
+        eval::EvalSuite::load(std::path::Path::new(suite_path)).map_err(|e| format_err!(e))?;
+
+    let report = eval::run_suite(&suite, &config).await;
+    agent::terminate_all().await;
+
+    println!("{}", report.format_table());
+
+    let failed = report.failed_runs();
+    if failed > 0 {
+        return Err(format_err!(t!("cli.eval_runs_failed", count = failed)));
+    }
+    Ok(())
+}
+
+/// Run the queries of a JSONL file, resuming an interrupted batch
+async fn run_batch_mode(
+    config: Config,
+    queries: &std::path::Path,
+    parallel: usize,
+    output: Option<std::path::PathBuf>,
+) -> anyhow::Result<()> {
+    ctrlc::set_handler(move || {
+        eprintln!("\n{}", t!("cli.batch_interrupted"));
+        std::process::exit(130);
+    })
+    .expect("Failed to set Ctrl+C handler");
+
+    let buffer = crate::output::SharedBuffer::new(200);
+    crate::output::CURRENT_BUFFER
+        .scope(buffer, initialize_and_log_mcp())
+        .await;
+
+    let summary = batch::run(config, queries, output, parallel).await;
+    agent::terminate_all().await;
+    let summary = summary.map_err(|e| format_err!(e))?;
+
+    println!("{}", summary.format());
+    if summary.failed > 0 {
+        return Err(format_err!(t!(
+            "cli.batch_queries_failed",
+            count = summary.failed
+        )));
+    }
+    Ok(())
+}
+
 /// Run the application in single query mode (non-interactive)
 async fn run_single_query_mode(config: Config, query: String) -> anyhow::Result<()> {
     // Extract the timeout value before config is moved
     let timeout_seconds = config.timeout_seconds.unwrap_or(150); // Default to 150 seconds (2.5 minutes) if not specified
 
+    // With a schema, the answer is JSON that may need corrections afterwards
+    let schema = match &config.output_schema {
+        Some(path) => Some(structured::load(path).map_err(|e| format_err!(e))?),
+        None => None,
+    };
+    let query = match &schema {
+        Some(schema) => format!("{query}\n\n{}", structured::instructions(schema)),
+        None => query,
+    };
+    let schema_config = config.clone();
+
     // Set up Ctrl+C handler - use this simplified approach
     ctrlc::set_handler(move || {
-        eprintln!("\nOperation interrupted by user");
+        eprintln!("\n{}", t!("cli.interrupted"));
+        // Review and remove an isolated copy instead of leaving it behind
+        if let Err(e) = isolation::finish_interrupted() {
+            eprintln!("{}", t!("cli.isolation_finish_failed", error = e));
+        }
         std::process::exit(130); // Standard exit code for Ctrl+C termination
     })
     .expect("Failed to set Ctrl+C handler");
@@ -521,23 +1209,26 @@ async fn run_single_query_mode(config: Config, query: String) -> anyhow::Result<
             ) {
                 Ok(id) => {
                     bprintln!(
-                        "🤖 {}Agent{} 'main' created successfully with ID: {}",
-                        crate::constants::FORMAT_BOLD,
-                        crate::constants::FORMAT_RESET,
-                        id
+                        "{}",
+                        t!(
+                            "cli.agent_created",
+                            bold = crate::constants::FORMAT_BOLD,
+                            reset = crate::constants::FORMAT_RESET,
+                            id = id,
+                        )
                     );
                     Ok(id)
                 }
                 Err(e) => {
                     // Use buffer printing for the error
-                    bprintln!(error: "Failed to create main agent: {e}");
+                    bprintln!(error: "{}", t!("cli.agent_failed", error = e));
 
                     // Also print to stderr for CLI visibility
-                    eprintln!("Failed to create main agent: {e}");
+                    eprintln!("{}", t!("cli.agent_failed", error = e));
 
                     Err(std::io::Error::new(
                         std::io::ErrorKind::Other,
-                        format!("Failed to create main agent: {e}"),
+                        t!("cli.agent_failed", error = e),
                     )
                     .into())
                 }
@@ -546,61 +1237,45 @@ async fn run_single_query_mode(config: Config, query: String) -> anyhow::Result<
         })
         .await?;
 
-    // Set up buffer streaming for real-time feedback
-    let mut last_line_count = 0;
-    let mut buffer_check_time = std::time::Instant::now();
-
-    // Spawn a task to continuously stream buffer content to stderr
+    // Stream the agent's output to stderr as it arrives, until told to stop
+    let output = agent::get_agent_buffer(main_agent_id).ok();
+    let (stop_streaming, stop) = tokio::sync::oneshot::channel();
     let buffer_task = tokio::spawn(async move {
-        loop {
-            // Check every 100ms
-            if buffer_check_time.elapsed() >= Duration::from_millis(100) {
-                buffer_check_time = std::time::Instant::now();
-
-                // Get the current buffer content
-                if let Ok(buffer) = agent::get_agent_buffer(main_agent_id) {
-                    let lines = buffer.lines();
-                    let current_count = lines.len();
-
-                    // If there are new lines, print them to stderr
-                    if current_count > last_line_count {
-                        for i in last_line_count..current_count {
-                            if let Some(line) = lines.get(i) {
-                                eprintln!("{}", line.content);
-                            }
-                        }
-                        last_line_count = current_count;
-                    }
-                }
-            }
-
-            // Sleep briefly to avoid tight loop
-            tokio::time::sleep(Duration::from_millis(50)).await;
+        if let Some(buffer) = output {
+            let _ = stream_to_stderr(buffer, stop).await;
         }
     });
 
-    eprintln!("Processing query, please wait...");
+    eprintln!("{}", t!("cli.processing"));
 
     // Run the agent and wait for completion
     // timeout_seconds was extracted at the beginning of the function
     let final_response =
-        match agent::run_agent_to_completion(main_agent_id, query, Some(timeout_seconds)).await {
+        match agent::run_agent_to_completion(main_agent_id, query.clone(), Some(timeout_seconds))
+            .await
+        {
             Ok(response) => response,
             Err(e) => {
-                eprintln!("Failed to get response: {e}");
+                eprintln!("{}", t!("cli.response_failed", error = e));
                 String::new()
             }
         };
 
-    // Abort the buffer task
-    buffer_task.abort();
+    // Let the buffer task write the rest of the output, then finish
+    let _ = stop_streaming.send(());
+    let _ = buffer_task.await;
 
     // Clean up: terminate all agents
     agent::terminate_all().await;
 
-    // Output the final response to stdout
-    if final_response.trim().is_empty() {
-        println!("No response was generated. Please try again.");
+    // Output the final response to stdout, as JSON matching the schema when given
+    if let Some(schema) = schema.filter(|_| !final_response.trim().is_empty()) {
+        let value = structured::conform(&schema_config, &schema, &query, final_response)
+            .await
+            .map_err(|e| format_err!(e))?;
+        println!("{}", serde_json::to_string_pretty(&value)?);
+    } else if final_response.trim().is_empty() {
+        println!("{}", t!("cli.no_response"));
     } else {
         // Just print the raw response without any markup
         println!("{}", final_response.trim());
@@ -609,3 +1284,34 @@ async fn run_single_query_mode(config: Config, query: String) -> anyhow::Result<
     // Explicit use of Result with the expected return type
     Ok(())
 }
+
+/// Write the lines of a buffer to stderr as they are pushed
+///
+/// Each batch is written before the next is read, so a slow stderr holds the
+/// reader back instead of losing lines (up to the buffer's history). Once
+/// `stop` fires, the lines pushed until then are written and the task ends.
+async fn stream_to_stderr(
+    buffer: crate::output::SharedBuffer,
+    mut stop: tokio::sync::oneshot::Receiver<()>,
+) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut stderr = tokio::io::stderr();
+    let mut reader = buffer.reader(usize::MAX);
+    loop {
+        let (lines, stopping) = tokio::select! {
+            lines = reader.next_lines() => (lines, false),
+            _ = &mut stop => (reader.pending(), true),
+        };
+        let mut text = String::new();
+        for line in lines {
+            text.push_str(&line.content);
+            text.push('\n');
+        }
+        stderr.write_all(text.as_bytes()).await?;
+        stderr.flush().await?;
+        if stopping {
+            return Ok(());
+        }
+    }
+}
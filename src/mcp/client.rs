@@ -159,6 +159,22 @@ impl McpClient {
         conn_guard.is_some() // If we have a connection, we're connected
     }
 
+    /// Check whether the server process is still running
+    ///
+    /// Returns None while a request holds the connection, as the server is
+    /// evidently alive enough to be working on it.
+    pub fn try_is_connected(&self) -> Option<bool> {
+        let conn_guard = self.connection.try_lock().ok()?;
+        Some(conn_guard.as_ref().is_some_and(|conn| conn.is_connected()))
+    }
+
+    /// Send a ping request to check that the server responds
+    pub async fn ping(&self) -> McpResult<()> {
+        self.send_request::<_, serde_json::Value>("ping", json!({}))
+            .await
+            .map(|_| ())
+    }
+
     /// Check if the client is initialized
     #[allow(dead_code)]
     pub fn is_initialized(&self) -> bool {
@@ -18,6 +18,48 @@ pub struct McpServerConfig {
     /// Environment variables to set for the command
     #[serde(default)]
     pub env: HashMap<String, String>,
+
+    /// Additional names for this server's tools, mapping alias to tool name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub aliases: HashMap<String, String>,
+
+    /// Which of the server's tools are exposed to agents
+    #[serde(default, skip_serializing_if = "McpToolFilter::is_empty")]
+    pub tools: McpToolFilter,
+}
+
+/// Glob patterns selecting the tools of a server that agents can see and call
+///
+/// With no `include` patterns every tool is included; `exclude` patterns are
+/// applied afterwards.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct McpToolFilter {
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl McpToolFilter {
+    /// Whether the filter has no patterns and allows every tool
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether a tool passes the filter
+    pub fn allows(&self, tool_name: &str) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|p| pattern_matches(p, tool_name));
+        included && !self.exclude.iter().any(|p| pattern_matches(p, tool_name))
+    }
+}
+
+/// Match a tool name against a glob pattern; invalid patterns match literally
+fn pattern_matches(pattern: &str, tool_name: &str) -> bool {
+    match glob::Pattern::new(pattern) {
+        Ok(pattern) => pattern.matches(tool_name),
+        Err(_) => pattern == tool_name,
+    }
 }
 
 /// Complete MCP configuration structure
@@ -152,6 +194,26 @@ mod tests {
         fs::remove_dir_all(temp_dir).unwrap();
     }
 
+    #[test]
+    fn test_tool_filter() {
+        let filter: McpToolFilter = serde_json::from_str(
+            r#"{"include": ["issue_*", "search"], "exclude": ["issue_delete*"]}"#,
+        )
+        .unwrap();
+        assert!(filter.allows("issue_create"));
+        assert!(filter.allows("search"));
+        assert!(!filter.allows("issue_delete_comment"));
+        assert!(!filter.allows("repo_list"));
+
+        let exclude_only = McpToolFilter {
+            include: Vec::new(),
+            exclude: vec!["*_admin".to_string()],
+        };
+        assert!(exclude_only.allows("repo_list"));
+        assert!(!exclude_only.allows("user_admin"));
+        assert!(McpToolFilter::default().allows("anything"));
+    }
+
     #[test]
     fn test_config_merge() {
         // Create base config
@@ -170,6 +232,8 @@ mod tests {
                 command: "cmd1".to_string(),
                 args: vec!["arg1".to_string()],
                 env: env_vars1,
+                aliases: HashMap::new(),
+                tools: McpToolFilter::default(),
             },
         );
 
@@ -185,6 +249,8 @@ mod tests {
                 command: "cmd2".to_string(),
                 args: vec!["arg2".to_string()],
                 env: HashMap::new(),
+                aliases: HashMap::new(),
+                tools: McpToolFilter::default(),
             },
         );
 
@@ -199,6 +265,8 @@ mod tests {
                 command: "different".to_string(),
                 args: vec!["different-arg".to_string()],
                 env: env_vars2,
+                aliases: HashMap::new(),
+                tools: McpToolFilter::default(),
             },
         );
 
@@ -317,19 +385,32 @@ async fn initialize_mcp_server(
     }
 
     // Create provider with environment variables
-    match McpToolProvider::new_process_with_env(server_name, executable, &args_slice, &config.env)
-        .await
+    match McpToolProvider::new_process_with_env(
+        server_name,
+        executable,
+        &args_slice,
+        &config.env,
+        config.tools.clone(),
+    )
+    .await
     {
         Ok(provider) => {
             let provider: Arc<McpToolProvider> = Arc::new(provider);
 
             // Get tools from the provider to count them
             let tools = provider.list_tools();
-            let tool_count = tools.len();
+            let tool_count = match provider.hidden_tool_count() {
+                0 => tools.len().to_string(),
+                hidden => format!("{} ({} hidden by the tool filter)", tools.len(), hidden),
+            };
 
             // Register the provider with the MCP manager
             // Check if registration was successful
             if crate::mcp::register_provider(server_name, Arc::clone(&provider)) {
+                // Restart the server automatically if its process dies
+                crate::mcp::monitor_server(server_name, config.clone());
+                crate::mcp::manager::register_aliases(server_name, &config.aliases);
+
                 if !silent_mode {
                     bprintln !(tool: "mcp",
                         "Connected to MCP server: {}. Found {} tools.",
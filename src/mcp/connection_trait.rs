@@ -14,7 +14,6 @@ pub trait Connection: Send + Sync {
     /// Close the connection. Reserved for future use in dynamically connecting to servers
     async fn close(&self) -> McpResult<()>;
 
-    #[allow(dead_code)]
     /// Check if the connection is still active
     fn is_connected(&self) -> bool;
 }
@@ -1,12 +1,30 @@
 //! MCP Manager for handling Model Context Protocol servers and tools
 
+use crate::mcp::config::McpServerConfig;
 use crate::mcp::protocol::tools::Tool;
 use crate::mcp::tool_provider::McpToolProvider;
 use anyhow::format_err;
 use lazy_static::lazy_static;
 use serde_json::json;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Interval between checks that server processes are still running
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Interval between ping heartbeats to each server
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Time a server has to answer a ping
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before the first restart attempt; doubled after each failed attempt
+const INITIAL_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest delay between restart attempts
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(300);
 
 /// Enhanced tool information with examples for documentation
 #[derive(Clone, Debug)]
@@ -16,10 +34,49 @@ pub struct ToolInfo {
     pub example_request: String,
 }
 
+/// Health of a monitored MCP server
+#[derive(Clone, Debug, PartialEq)]
+pub enum ServerHealth {
+    /// Running and answering pings
+    Healthy,
+    /// Exited or stopped responding; waiting for the next restart attempt
+    Unhealthy(String),
+    /// A restart is in progress
+    Restarting,
+}
+
+/// Health information about a monitored MCP server
+#[derive(Clone, Debug)]
+pub struct ServerStatus {
+    pub health: ServerHealth,
+    /// Number of successful restarts
+    pub restarts: u32,
+    /// Consecutive failed restart attempts
+    pub failed_attempts: u32,
+    /// Most recent failure, kept after a successful restart
+    pub last_error: Option<String>,
+    /// When the next restart will be attempted
+    pub next_restart: Option<Instant>,
+    /// When the server last answered a ping
+    pub last_ping: Option<Instant>,
+}
+
+/// A server whose process is monitored and restarted when it dies
+struct MonitoredServer {
+    config: McpServerConfig,
+    status: ServerStatus,
+}
+
 /// Singleton manager for all MCP tool providers
 pub struct McpManager {
     /// Map of provider names to provider instances
     providers: HashMap<String, Arc<McpToolProvider>>,
+    /// Monitored servers by provider name
+    servers: HashMap<String, MonitoredServer>,
+    /// Tool aliases mapped to (server name, tool name)
+    aliases: HashMap<String, (String, String)>,
+    /// Aliases rejected during registration, with the reason
+    alias_conflicts: Vec<String>,
 }
 
 impl McpManager {
@@ -27,6 +84,9 @@ impl McpManager {
     pub fn new() -> Self {
         Self {
             providers: HashMap::new(),
+            servers: HashMap::new(),
+            aliases: HashMap::new(),
+            alias_conflicts: Vec::new(),
         }
     }
 
@@ -50,6 +110,35 @@ impl McpManager {
         self.providers.keys().cloned().collect()
     }
 
+    /// Resolve a namespaced (`server.tool`) or aliased tool name to its server and tool
+    pub fn resolve_tool(&self, name: &str) -> Option<(String, String)> {
+        let name = name.to_lowercase();
+        if let Some(target) = self.aliases.get(&name) {
+            return Some(target.clone());
+        }
+
+        // Prefer the longest server name, in case server names contain dots
+        let (server_name, provider) = self
+            .providers
+            .iter()
+            .filter(|(server_name, _)| {
+                name.len() > server_name.len() + 1
+                    && name.starts_with(&server_name.to_lowercase())
+                    && name.as_bytes()[server_name.len()] == b'.'
+            })
+            .max_by_key(|(server_name, _)| server_name.len())?;
+        let tool_name = &name[server_name.len() + 1..];
+
+        // Tool names are lowercased by the grammar parsers; recover the original case
+        let tool_name = provider
+            .list_tools()
+            .into_iter()
+            .map(|tool| tool.name)
+            .find(|candidate| candidate.to_lowercase() == tool_name)
+            .unwrap_or_else(|| tool_name.to_string());
+        Some((server_name.clone(), tool_name))
+    }
+
     /// Get a provider by name and return error if not found
     #[allow(dead_code)]
     pub fn get_provider_or_error(&self, name: &str) -> anyhow::Result<Arc<McpToolProvider>> {
@@ -146,6 +235,20 @@ lazy_static! {
     static ref MCP_MANAGER: Mutex<McpManager> = Mutex::new(McpManager::new());
 }
 
+/// Whether the health monitor task is running
+static MONITOR_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether full tool descriptions are embedded in the prompt up front
+static EAGER_TOOL_DOCS: AtomicBool = AtomicBool::new(false);
+
+/// Most tool names listed in a server's one-line prompt summary
+const SUMMARY_MAX_TOOL_NAMES: usize = 12;
+
+/// Embed every tool description in the prompt instead of one-line server summaries
+pub fn set_eager_tool_docs(eager: bool) {
+    EAGER_TOOL_DOCS.store(eager, Ordering::Relaxed);
+}
+
 // Public API - all interaction with MCP providers happens through these functions
 
 /// Register a provider with the MCP manager
@@ -170,6 +273,103 @@ pub fn register_provider(name: &str, provider: Arc<McpToolProvider>) -> bool {
     }
 }
 
+/// Register the aliases of a server's tools
+///
+/// Aliases colliding with built-in tools, server names or other aliases are
+/// rejected and listed in the conflict report.
+pub fn register_aliases(server_name: &str, aliases: &HashMap<String, String>) {
+    let Ok(mut manager) = MCP_MANAGER.lock() else {
+        return;
+    };
+
+    let mut aliases: Vec<(&String, &String)> = aliases.iter().collect();
+    aliases.sort();
+    for (alias, tool_name) in aliases {
+        let alias = alias.trim().to_lowercase();
+        let conflict = if is_built_in_tool_name(&alias) {
+            Some("collides with a built-in tool".to_string())
+        } else if manager.has_provider(&alias) {
+            Some("collides with an MCP server name".to_string())
+        } else if let Some((other_server, other_tool)) = manager.aliases.get(&alias) {
+            Some(format!("already used for {other_server}.{other_tool}"))
+        } else if alias.is_empty() || alias.contains(char::is_whitespace) {
+            Some("is not a valid tool name".to_string())
+        } else {
+            None
+        };
+
+        match conflict {
+            Some(reason) => manager.alias_conflicts.push(format!(
+                "Alias '{alias}' for {server_name}.{tool_name} ignored: it {reason}"
+            )),
+            None => {
+                manager
+                    .aliases
+                    .insert(alias, (server_name.to_string(), tool_name.clone()));
+            }
+        }
+    }
+}
+
+/// Resolve a namespaced (`server.tool`) or aliased tool name to its server and tool
+pub fn resolve_tool(name: &str) -> Option<(String, String)> {
+    MCP_MANAGER.lock().ok()?.resolve_tool(name)
+}
+
+/// Whether a tool name refers to an MCP server, a namespaced MCP tool or an alias
+pub fn is_mcp_tool(name: &str) -> bool {
+    has_provider(name) || resolve_tool(name).is_some()
+}
+
+/// Describe tool name conflicts between MCP servers and built-in tools
+///
+/// Namespaced names always work, so these are informational: they explain
+/// which name to use for a tool that cannot be called by its bare name.
+pub fn conflict_report() -> Vec<String> {
+    let Ok(manager) = MCP_MANAGER.lock() else {
+        return Vec::new();
+    };
+
+    // Servers providing each tool name
+    let mut tool_servers: HashMap<String, Vec<String>> = HashMap::new();
+    for (server_name, provider) in &manager.providers {
+        for tool in provider.list_tools() {
+            tool_servers
+                .entry(tool.name.to_lowercase())
+                .or_default()
+                .push(server_name.clone());
+        }
+    }
+
+    let mut report = Vec::new();
+    let mut tool_names: Vec<&String> = tool_servers.keys().collect();
+    tool_names.sort();
+    for tool_name in tool_names {
+        let mut servers = tool_servers[tool_name].clone();
+        servers.sort();
+        let qualified: Vec<String> = servers
+            .iter()
+            .map(|server| format!("{server}.{tool_name}"))
+            .collect();
+
+        if is_built_in_tool_name(tool_name) {
+            report.push(format!(
+                "MCP tool '{tool_name}' has the same name as a built-in tool; call it as {}",
+                qualified.join(" or ")
+            ));
+        } else if servers.len() > 1 {
+            report.push(format!(
+                "MCP tool '{tool_name}' is provided by servers {}; call it as {}",
+                servers.join(", "),
+                qualified.join(" or ")
+            ));
+        }
+    }
+
+    report.extend(manager.alias_conflicts.iter().cloned());
+    report
+}
+
 /// Check if a name conflicts with a built-in tool name
 fn is_built_in_tool_name(name: &str) -> bool {
     // Convert name to lowercase for case-insensitive comparison
@@ -183,6 +383,11 @@ fn is_built_in_tool_name(name: &str) -> bool {
         }
     }
 
+    // The `mcp` meta-tool lists the tools of MCP servers
+    if name_lower == "mcp" {
+        return true;
+    }
+
     // Check against plus tools
     let plus_tools = crate::prompts::PLUS_TOOLS;
     for tool in plus_tools {
@@ -289,16 +494,93 @@ pub fn get_mcp_tools_for_prompt() -> (Vec<String>, HashMap<String, Vec<ToolInfo>
     }
 }
 
+/// One-line prompt summary of a server, expanded on demand with `mcp list-tools`
+fn server_summary(server_name: &str, provider: &McpToolProvider) -> serde_json::Value {
+    let mut names: Vec<String> = provider
+        .list_tools()
+        .into_iter()
+        .map(|tool| tool.name)
+        .collect();
+    names.sort();
+    let tool_count = names.len();
+    let mut tools = names
+        .into_iter()
+        .take(SUMMARY_MAX_TOOL_NAMES)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if tool_count > SUMMARY_MAX_TOOL_NAMES {
+        tools.push_str(", ...");
+    }
+    json!({ "name": server_name, "tool_count": tool_count, "tools": tools })
+}
+
+/// Detailed documentation of a server's tools, for the `mcp list-tools` tool
+pub fn describe_server_tools(server_name: &str) -> Option<String> {
+    let provider = get_provider(server_name)?;
+    let aliases: Vec<(String, String)> = match MCP_MANAGER.lock() {
+        Ok(manager) => manager
+            .aliases
+            .iter()
+            .filter(|(_, (server, _))| server == server_name)
+            .map(|(alias, (_, tool))| (alias.clone(), tool.clone()))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    let mut tools = provider.list_tools();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut output = format!(
+        "MCP server '{}' provides {} tools. Call them as `{}.<tool>` with the arguments as a JSON body.\n",
+        server_name,
+        tools.len(),
+        server_name
+    );
+    for tool in tools {
+        output.push_str(&format!("\n## {}.{}\n", server_name, tool.name));
+        if !tool.description.is_empty() {
+            output.push_str(&format!("{}\n", tool.description.trim()));
+        }
+        for (alias, _) in aliases.iter().filter(|(_, target)| *target == tool.name) {
+            output.push_str(&format!("Alias: {alias}\n"));
+        }
+        output.push_str(&format!("Arguments:\n{}\n", tool.input_schema.describe()));
+    }
+    Some(output)
+}
+
 /// Helper function to update MCP tools in prompts
 ///
-/// This function will add all MCP tools information to the prompt template data.
-pub fn add_mcp_tools_to_prompt(template_data: &mut serde_json::Value) {
+/// This function will add all MCP tools information to the prompt template data:
+/// a one-line summary per server by default, or every tool description when
+/// eager tool docs are enabled. Tools hidden by the servers' tool filters are
+/// left out; returns their number.
+pub fn add_mcp_tools_to_prompt(template_data: &mut serde_json::Value) -> usize {
     // Get detailed MCP tools information
     let (server_names, tools_info) = get_mcp_tools_for_prompt();
 
     // Skip if no MCP servers are configured
     if server_names.is_empty() {
-        return;
+        return 0;
+    }
+
+    let hidden_tools: usize = server_names
+        .iter()
+        .filter_map(|name| get_provider(name))
+        .map(|provider| provider.hidden_tool_count())
+        .sum();
+
+    if !EAGER_TOOL_DOCS.load(Ordering::Relaxed) {
+        let mut names = server_names;
+        names.sort();
+        let summaries: Vec<serde_json::Value> = names
+            .iter()
+            .filter_map(|name| Some(server_summary(name, &get_provider(name)?)))
+            .collect();
+        if let Some(obj) = template_data.as_object_mut() {
+            obj.insert("mcp_server_summaries".to_string(), json!(summaries));
+        }
+        return hidden_tools;
     }
 
     // Add server names array to template data
@@ -306,11 +588,30 @@ pub fn add_mcp_tools_to_prompt(template_data: &mut serde_json::Value) {
         // Add detailed tools information
         let mut tools_array = Vec::new();
 
+        let aliases: Vec<(String, (String, String))> = match MCP_MANAGER.lock() {
+            Ok(manager) => manager
+                .aliases
+                .iter()
+                .map(|(alias, target)| (alias.clone(), target.clone()))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
         for (server_name, tools) in tools_info {
             for tool_info in tools {
                 let mut tool_obj = serde_json::Map::new();
                 tool_obj.insert("server".to_string(), serde_json::json!(server_name));
                 tool_obj.insert("name".to_string(), serde_json::json!(tool_info.name));
+                tool_obj.insert(
+                    "qualified_name".to_string(),
+                    serde_json::json!(format!("{}.{}", server_name, tool_info.name)),
+                );
+                if let Some((alias, _)) = aliases
+                    .iter()
+                    .find(|(_, target)| *target == (server_name.clone(), tool_info.name.clone()))
+                {
+                    tool_obj.insert("alias".to_string(), serde_json::json!(alias));
+                }
                 tool_obj.insert(
                     "description".to_string(),
                     serde_json::json!(tool_info.description),
@@ -325,4 +626,285 @@ pub fn add_mcp_tools_to_prompt(template_data: &mut serde_json::Value) {
 
         obj.insert("mcp_tools".to_string(), serde_json::json!(tools_array));
     }
+
+    hidden_tools
+}
+
+/// Monitor a registered server and restart it with the same configuration when it dies
+///
+/// Starts the health monitor task on first use.
+pub fn monitor_server(name: &str, config: McpServerConfig) {
+    if let Ok(mut manager) = MCP_MANAGER.lock() {
+        manager.servers.insert(
+            name.to_string(),
+            MonitoredServer {
+                config,
+                status: ServerStatus {
+                    health: ServerHealth::Healthy,
+                    restarts: 0,
+                    failed_attempts: 0,
+                    last_error: None,
+                    next_restart: None,
+                    last_ping: Some(Instant::now()),
+                },
+            },
+        );
+    }
+
+    if !MONITOR_STARTED.swap(true, Ordering::SeqCst) {
+        crate::output::spawn(monitor_servers());
+    }
+}
+
+/// Mark a server as failed, e.g. after a tool call lost its connection
+///
+/// The monitor restarts it on its next check.
+pub fn report_failure(name: &str, error: &str) {
+    if let Ok(mut manager) = MCP_MANAGER.lock() {
+        if let Some(server) = manager.servers.get_mut(name) {
+            if server.status.health == ServerHealth::Healthy {
+                mark_unhealthy(name, &mut server.status, error.to_string());
+            }
+        }
+    }
+}
+
+/// Health of a monitored server, if it is monitored
+pub fn server_status(name: &str) -> Option<ServerStatus> {
+    let manager = MCP_MANAGER.lock().ok()?;
+    manager
+        .servers
+        .get(name)
+        .map(|server| server.status.clone())
+}
+
+/// Human-readable health report of all servers, for `/mcp status`
+pub fn format_status() -> String {
+    let Ok(manager) = MCP_MANAGER.lock() else {
+        return "MCP state unavailable".to_string();
+    };
+    if manager.providers.is_empty() && manager.servers.is_empty() {
+        return "No MCP servers configured".to_string();
+    }
+
+    let mut names: Vec<&String> = manager
+        .providers
+        .keys()
+        .chain(manager.servers.keys())
+        .collect();
+    names.sort();
+    names.dedup();
+
+    let now = Instant::now();
+    let mut lines = Vec::new();
+    for name in names {
+        let tools = manager
+            .providers
+            .get(name)
+            .map(|provider| provider.list_tools().len())
+            .unwrap_or(0);
+        let Some(server) = manager.servers.get(name) else {
+            lines.push(format!("{name}: not monitored, {tools} tools"));
+            continue;
+        };
+
+        let status = &server.status;
+        let health = match &status.health {
+            ServerHealth::Healthy => "healthy".to_string(),
+            ServerHealth::Restarting => "restarting".to_string(),
+            ServerHealth::Unhealthy(reason) => match status.next_restart {
+                Some(at) => format!(
+                    "down ({reason}), restart in {}s",
+                    at.saturating_duration_since(now).as_secs()
+                ),
+                None => format!("down ({reason})"),
+            },
+        };
+        let mut line = format!(
+            "{name}: {health}, {tools} tools, {} restarts",
+            status.restarts
+        );
+        if let Some(last_ping) = status.last_ping {
+            line.push_str(&format!(
+                ", last ping {}s ago",
+                now.duration_since(last_ping).as_secs()
+            ));
+        }
+        if let Some(error) = &status.last_error {
+            line.push_str(&format!("\n  last error: {error}"));
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/// Delay before the next restart after a number of failed attempts
+fn restart_backoff(failed_attempts: u32) -> Duration {
+    INITIAL_RESTART_BACKOFF
+        .saturating_mul(2u32.saturating_pow(failed_attempts))
+        .min(MAX_RESTART_BACKOFF)
+}
+
+fn mark_unhealthy(name: &str, status: &mut ServerStatus, reason: String) {
+    bprintln!(warn: "MCP server '{}' is unavailable: {}", name, reason);
+    status.last_error = Some(reason.clone());
+    status.health = ServerHealth::Unhealthy(reason);
+    status.next_restart = Some(Instant::now() + restart_backoff(status.failed_attempts));
+}
+
+/// Periodically check every monitored server
+async fn monitor_servers() {
+    loop {
+        tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+
+        let names: Vec<String> = match MCP_MANAGER.lock() {
+            Ok(manager) => manager.servers.keys().cloned().collect(),
+            Err(_) => return,
+        };
+        for name in names {
+            check_server(&name).await;
+        }
+    }
+}
+
+/// Detect exited or unresponsive servers and restart them when their backoff has passed
+async fn check_server(name: &str) {
+    let Some(status) = server_status(name) else {
+        return;
+    };
+
+    if status.health == ServerHealth::Healthy {
+        let Some(provider) = get_provider(name) else {
+            return;
+        };
+        let failure = match provider.is_alive() {
+            Some(false) => Some("process exited".to_string()),
+            // Busy with a request, so it is running
+            None => None,
+            Some(true) => {
+                let ping_due = !status
+                    .last_ping
+                    .is_some_and(|last_ping| last_ping.elapsed() < PING_INTERVAL);
+                if ping_due {
+                    match tokio::time::timeout(PING_TIMEOUT, provider.ping()).await {
+                        Ok(Ok(())) => {
+                            update_status(name, |status| status.last_ping = Some(Instant::now()));
+                            None
+                        }
+                        Ok(Err(e)) => Some(format!("ping failed: {e}")),
+                        Err(_) => Some("ping timed out".to_string()),
+                    }
+                } else {
+                    None
+                }
+            }
+        };
+
+        match failure {
+            Some(reason) => update_status(name, |status| mark_unhealthy(name, status, reason)),
+            None => return,
+        }
+    }
+
+    let restart_due = server_status(name)
+        .and_then(|status| status.next_restart)
+        .is_some_and(|at| Instant::now() >= at);
+    if restart_due {
+        restart_server(name).await;
+    }
+}
+
+fn update_status(name: &str, update: impl FnOnce(&mut ServerStatus)) {
+    if let Ok(mut manager) = MCP_MANAGER.lock() {
+        if let Some(server) = manager.servers.get_mut(name) {
+            update(&mut server.status);
+        }
+    }
+}
+
+/// Start a fresh server process and replace the provider
+async fn restart_server(name: &str) {
+    let config = {
+        let Ok(mut manager) = MCP_MANAGER.lock() else {
+            return;
+        };
+        let Some(server) = manager.servers.get_mut(name) else {
+            return;
+        };
+        server.status.health = ServerHealth::Restarting;
+        server.status.next_restart = None;
+        server.config.clone()
+    };
+
+    bprintln!(info: "Restarting MCP server '{}'", name);
+    let args: Vec<&str> = config.args.iter().map(|s| s.as_str()).collect();
+    let result = McpToolProvider::new_process_with_env(
+        name,
+        &config.command,
+        &args,
+        &config.env,
+        config.tools.clone(),
+    )
+    .await;
+
+    let Ok(mut manager) = MCP_MANAGER.lock() else {
+        return;
+    };
+    match result {
+        Ok(provider) => {
+            let tool_count = provider.list_tools().len();
+            // Dropping the old provider kills whatever is left of its process
+            manager.register(name, Arc::new(provider));
+            if let Some(server) = manager.servers.get_mut(name) {
+                server.status.health = ServerHealth::Healthy;
+                server.status.restarts += 1;
+                server.status.failed_attempts = 0;
+                server.status.last_ping = Some(Instant::now());
+            }
+            bprintln!(info: "MCP server '{}' restarted with {} tools", name, tool_count);
+        }
+        Err(e) => {
+            if let Some(server) = manager.servers.get_mut(name) {
+                let status = &mut server.status;
+                status.failed_attempts += 1;
+                let backoff = restart_backoff(status.failed_attempts);
+                let reason = format!("restart failed: {e}");
+                status.last_error = Some(reason.clone());
+                status.health = ServerHealth::Unhealthy(reason);
+                status.next_restart = Some(Instant::now() + backoff);
+                bprintln!(warn: "Failed to restart MCP server '{}': {}; retrying in {}s",
+                         name, e, backoff.as_secs());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alias_conflicts() {
+        let mut aliases = HashMap::new();
+        aliases.insert("read".to_string(), "read_file".to_string());
+        aliases.insert("web_lookup".to_string(), "search".to_string());
+        register_aliases("test-alias-server", &aliases);
+
+        assert_eq!(
+            resolve_tool("WEB_LOOKUP"),
+            Some(("test-alias-server".to_string(), "search".to_string()))
+        );
+        assert_eq!(resolve_tool("read"), None);
+        assert!(conflict_report().iter().any(|line| line
+            == "Alias 'read' for test-alias-server.read_file ignored: it collides with a built-in tool"));
+    }
+
+    #[test]
+    fn test_restart_backoff() {
+        assert_eq!(restart_backoff(0), Duration::from_secs(1));
+        assert_eq!(restart_backoff(1), Duration::from_secs(2));
+        assert_eq!(restart_backoff(4), Duration::from_secs(16));
+        assert_eq!(restart_backoff(20), MAX_RESTART_BACKOFF);
+        assert_eq!(restart_backoff(u32::MAX), MAX_RESTART_BACKOFF);
+    }
 }
@@ -16,5 +16,6 @@ pub use connection_trait::Connection;
 // Re-export manager functions for easy access
 pub use manager::{
     add_mcp_tools_to_prompt, get_provider, get_provider_names, has_provider, has_providers,
-    register_provider,
+    is_mcp_tool, monitor_server, register_provider, report_failure, resolve_tool, server_status,
+    ServerHealth,
 };
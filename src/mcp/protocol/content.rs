@@ -1,6 +1,6 @@
 //! Content types for MCP protocol messages
 
-use crate::llm::ImageSource;
+use crate::llm::{DocumentSource, ImageSource};
 use serde::{Deserialize, Serialize};
 
 /// Base trait for MCP content types
@@ -155,9 +155,22 @@ impl McpContent for EmbeddedResource {
                             data: blob.blob.clone(),
                         },
                     }
-                } else {
+                } else if mime_type == "application/pdf" {
                     crate::llm::Content::Document {
-                        source: blob.uri.clone(),
+                        source: DocumentSource::Base64 {
+                            media_type: mime_type,
+                            data: blob.blob.clone(),
+                        },
+                        title: Some(blob.uri.clone()),
+                    }
+                } else {
+                    // Models only read PDF documents; describe other files
+                    crate::llm::Content::Text {
+                        text: format!(
+                            "Resource {}: binary content of type {mime_type}, {} bytes base64-encoded",
+                            blob.uri,
+                            blob.blob.len()
+                        ),
                     }
                 }
             }
@@ -1,6 +1,7 @@
 //! JSON Schema related structures and helpers for MCP protocol
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 
 /// JSON Schema primitive type
@@ -20,7 +21,6 @@ pub enum SchemaType {
 
 impl SchemaType {
     /// Convert the type to a string representation
-    #[allow(dead_code)]
     pub fn to_string(&self) -> String {
         match self {
             SchemaType::String => "string".to_string(),
@@ -33,6 +33,35 @@ impl SchemaType {
             SchemaType::Any => "any".to_string(),
         }
     }
+
+    /// Whether a JSON value is of this type
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            SchemaType::String => value.is_string(),
+            SchemaType::Number => value.is_number(),
+            SchemaType::Integer => {
+                value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+            }
+            SchemaType::Boolean => value.is_boolean(),
+            SchemaType::Array => value.is_array(),
+            SchemaType::Object => value.is_object(),
+            SchemaType::Null => value.is_null(),
+            SchemaType::Any => true,
+        }
+    }
+}
+
+/// Name of a JSON value's type, for error messages
+fn value_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
 }
 
 /// Value for examples, defaults, or enum options
@@ -50,7 +79,6 @@ pub enum SchemaValue {
 
 impl SchemaValue {
     /// Format the value as a string suitable for example display
-    #[allow(dead_code)]
     pub fn format(&self) -> String {
         match self {
             SchemaValue::String(s) => format!("\"{}\"", s),
@@ -73,6 +101,30 @@ impl SchemaValue {
             SchemaValue::Object(_) => "{ ... }".to_string(),
         }
     }
+
+    /// Whether a JSON value equals this value, comparing numbers numerically
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            SchemaValue::String(s) => value.as_str() == Some(s.as_str()),
+            SchemaValue::Number(n) => value.as_f64() == Some(*n),
+            SchemaValue::Integer(i) => value.as_f64() == Some(*i as f64),
+            SchemaValue::Boolean(b) => value.as_bool() == Some(*b),
+            SchemaValue::Null => value.is_null(),
+            SchemaValue::Array(items) => value.as_array().is_some_and(|values| {
+                items.len() == values.len()
+                    && items
+                        .iter()
+                        .zip(values)
+                        .all(|(item, value)| item.matches(value))
+            }),
+            SchemaValue::Object(fields) => value.as_object().is_some_and(|values| {
+                fields.len() == values.len()
+                    && fields.iter().all(|(key, field)| {
+                        values.get(key).is_some_and(|value| field.matches(value))
+                    })
+            }),
+        }
+    }
 }
 
 /// Represents a JSON Schema object for tool input
@@ -96,6 +148,85 @@ pub struct JsonSchema {
     pub description: Option<String>,
 }
 
+impl JsonSchema {
+    /// Check tool arguments against the schema
+    ///
+    /// Returns every problem found, so the caller can report them all at once.
+    pub fn validate(&self, arguments: &Value) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if let Some(schema_type) = &self.schema_type {
+            if !schema_type.matches(arguments) {
+                errors.push(format!(
+                    "arguments must be {}, got {}",
+                    schema_type.to_string(),
+                    value_type_name(arguments)
+                ));
+                return Err(errors);
+            }
+        }
+
+        if let Some(object) = arguments.as_object() {
+            for name in self.required.iter().flatten() {
+                if !object.contains_key(name) {
+                    errors.push(format!("missing required property '{name}'"));
+                }
+            }
+
+            for (name, value) in object {
+                match self.properties.as_ref().and_then(|p| p.get(name)) {
+                    Some(property) => property.validate(name, value, &mut errors),
+                    None if self.additional_properties == Some(false) => {
+                        errors.push(format!("unknown property '{name}'"));
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Describe the expected arguments, one property per line
+    pub fn describe(&self) -> String {
+        let properties = match &self.properties {
+            Some(properties) if !properties.is_empty() => properties,
+            _ => return "(no arguments)".to_string(),
+        };
+        let required = self.required.as_deref().unwrap_or_default();
+
+        // Required properties first, then alphabetically
+        let mut names: Vec<&String> = properties.keys().collect();
+        names.sort_by(|a, b| {
+            (!required.contains(a), a.as_str()).cmp(&(!required.contains(b), b.as_str()))
+        });
+
+        names
+            .into_iter()
+            .map(|name| {
+                let property = &properties[name];
+                let mut line = format!("- {name} ({}", property.type_string());
+                if required.contains(name) {
+                    line.push_str(", required");
+                }
+                line.push(')');
+                if let Some(description) = &property.description {
+                    line.push_str(&format!(": {description}"));
+                }
+                for constraint in property.constraints() {
+                    line.push_str(&format!(" [{constraint}]"));
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
 /// Represents a property schema in a JSON Schema object
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PropertySchema {
@@ -133,8 +264,77 @@ pub struct PropertySchema {
 }
 
 impl PropertySchema {
+    /// Check a property value, appending problems to `errors`
+    fn validate(&self, path: &str, value: &Value, errors: &mut Vec<String>) {
+        if let Some(property_type) = &self.property_type {
+            if !property_type.matches(value) {
+                errors.push(format!(
+                    "'{path}' must be {}, got {}",
+                    property_type.to_string(),
+                    value_type_name(value)
+                ));
+                return;
+            }
+        }
+
+        if let Some(enum_values) = &self.enum_values {
+            if !enum_values.iter().any(|option| option.matches(value)) {
+                let options: Vec<String> = enum_values.iter().map(|v| v.format()).collect();
+                errors.push(format!("'{path}' must be one of {}", options.join(", ")));
+            }
+        }
+
+        if let Some(number) = value.as_f64() {
+            if let Some(minimum) = self.minimum.filter(|minimum| number < *minimum) {
+                errors.push(format!("'{path}' must be at least {minimum}"));
+            }
+            if let Some(maximum) = self.maximum.filter(|maximum| number > *maximum) {
+                errors.push(format!("'{path}' must be at most {maximum}"));
+            }
+        }
+
+        if let (Some(text), Some(pattern)) = (value.as_str(), &self.pattern) {
+            // Patterns the regex crate cannot compile are left to the server
+            if let Ok(regex) = regex::Regex::new(pattern) {
+                if !regex.is_match(text) {
+                    errors.push(format!("'{path}' must match the pattern {pattern}"));
+                }
+            }
+        }
+
+        if let (Some(values), Some(items)) = (value.as_array(), &self.items) {
+            for (index, item) in values.iter().enumerate() {
+                items.validate(&format!("{path}[{index}]"), item, errors);
+            }
+        }
+    }
+
+    /// Human-readable constraints on the value (enum, range, pattern, format)
+    fn constraints(&self) -> Vec<String> {
+        let mut constraints = Vec::new();
+        if let Some(enum_values) = &self.enum_values {
+            let options: Vec<String> = enum_values.iter().map(|v| v.format()).collect();
+            constraints.push(format!("one of: {}", options.join(", ")));
+        }
+        if let Some(minimum) = self.minimum {
+            constraints.push(format!("min: {minimum}"));
+        }
+        if let Some(maximum) = self.maximum {
+            constraints.push(format!("max: {maximum}"));
+        }
+        if let Some(pattern) = &self.pattern {
+            constraints.push(format!("pattern: {pattern}"));
+        }
+        if let Some(format) = &self.format {
+            constraints.push(format!("format: {format}"));
+        }
+        if let Some(items) = &self.items {
+            constraints.push(format!("items: {}", items.type_string()));
+        }
+        constraints
+    }
+
     /// Get the type as a string
-    #[allow(dead_code)]
     pub fn type_string(&self) -> String {
         self.property_type
             .as_ref()
@@ -193,3 +393,69 @@ impl PropertySchema {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> JsonSchema {
+        serde_json::from_value(json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "description": "File to read"},
+                "limit": {"type": "integer", "minimum": 1, "maximum": 100},
+                "mode": {"type": "string", "enum": ["fast", "full"]},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["path"],
+            "additionalProperties": false
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_accepts_valid_arguments() {
+        let arguments = json!({"path": "a.txt", "limit": 10, "mode": "full", "tags": ["x"]});
+        assert!(schema().validate(&arguments).is_ok());
+        // Whole floats are accepted as integers
+        assert!(schema()
+            .validate(&json!({"path": "a", "limit": 5.0}))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_validate_reports_all_problems() {
+        let arguments = json!({"limit": "10", "mode": "slow", "tags": ["x", 1], "extra": true});
+        let errors = schema().validate(&arguments).unwrap_err();
+        assert!(errors.contains(&"missing required property 'path'".to_string()));
+        assert!(errors.contains(&"'limit' must be integer, got string".to_string()));
+        assert!(errors.contains(&"'mode' must be one of \"fast\", \"full\"".to_string()));
+        assert!(errors.contains(&"'tags[1]' must be string, got integer".to_string()));
+        assert!(errors.contains(&"unknown property 'extra'".to_string()));
+        assert_eq!(errors.len(), 5);
+    }
+
+    #[test]
+    fn test_validate_range_and_type() {
+        let errors = schema()
+            .validate(&json!({"path": "a", "limit": 0}))
+            .unwrap_err();
+        assert_eq!(errors, vec!["'limit' must be at least 1".to_string()]);
+
+        let errors = schema().validate(&json!(["a.txt"])).unwrap_err();
+        assert_eq!(
+            errors,
+            vec!["arguments must be object, got array".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        let description = schema().describe();
+        let lines: Vec<&str> = description.lines().collect();
+        assert_eq!(lines[0], "- path (string, required): File to read");
+        assert!(lines.contains(&"- limit (integer) [min: 1] [max: 100]"));
+        assert!(lines.contains(&"- mode (string) [one of: \"fast\", \"full\"]"));
+    }
+}
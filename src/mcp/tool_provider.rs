@@ -5,6 +5,7 @@ use std::collections::HashMap;
 use std::sync::Mutex;
 
 use crate::mcp::client::McpClient;
+use crate::mcp::config::McpToolFilter;
 use crate::mcp::error::{McpError, McpResult};
 use crate::mcp::protocol::Tool;
 
@@ -20,6 +21,8 @@ pub struct McpToolProvider {
     server_url: String,
     /// Available tools, cached for efficiency
     tools: Mutex<HashMap<String, Tool>>,
+    /// Tools exposed to agents; others are hidden and cannot be called
+    filter: McpToolFilter,
 }
 
 impl McpToolProvider {
@@ -31,6 +34,7 @@ impl McpToolProvider {
         executable: &str,
         args: &[&str],
         env: &HashMap<String, String>,
+        filter: McpToolFilter,
     ) -> McpResult<Self> {
         // Create client
         let client = McpClient::new();
@@ -53,6 +57,7 @@ impl McpToolProvider {
             client,
             server_url: format!("process://{}", executable),
             tools: Mutex::new(HashMap::new()),
+            filter,
         };
 
         // Refresh tools
@@ -82,14 +87,42 @@ impl McpToolProvider {
         Ok(())
     }
 
-    /// List available tools
+    /// Whether the server process is alive; None while a request is in flight
+    pub fn is_alive(&self) -> Option<bool> {
+        self.client.try_is_connected()
+    }
+
+    /// Check that the server answers requests
+    pub async fn ping(&self) -> McpResult<()> {
+        self.client.ping().await
+    }
+
+    /// List available tools that pass the tool filter
     pub fn list_tools(&self) -> Vec<Tool> {
-        self.tools.lock().unwrap().values().cloned().collect()
+        self.tools
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|tool| self.filter.allows(&tool.name))
+            .cloned()
+            .collect()
     }
 
-    /// Get a tool by ID
-    #[allow(dead_code)]
+    /// Number of tools hidden by the tool filter
+    pub fn hidden_tool_count(&self) -> usize {
+        self.tools
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|name| !self.filter.allows(name))
+            .count()
+    }
+
+    /// Get a tool by ID, unless it is hidden by the tool filter
     pub fn get_tool(&self, id: &str) -> Option<Tool> {
+        if !self.filter.allows(id) {
+            return None;
+        }
         self.tools.lock().unwrap().get(id).cloned()
     }
 
@@ -102,7 +135,7 @@ impl McpToolProvider {
         // Get the tool info or return an error if not found
         {
             let tools_map = self.tools.lock().unwrap();
-            if !tools_map.contains_key(id) {
+            if !tools_map.contains_key(id) || !self.filter.allows(id) {
                 return Err(McpError::ToolNotFound(id.to_string()));
             }
         };
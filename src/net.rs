@@ -0,0 +1,86 @@
+//! Shared construction of HTTP clients
+//!
+//! Every outbound HTTP client (LLM backends, fetch, search, version check) is
+//! built here so that proxy and certificate settings apply everywhere:
+//! - `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY` are honored by default
+//! - `--proxy URL` sends all traffic through an explicit proxy (still honoring `NO_PROXY`)
+//! - `--ca-bundle PATH` (or `SSL_CERT_FILE`) adds the certificates of a PEM bundle
+//!   to the trusted roots, for corporate TLS-inspecting proxies
+
+use lazy_static::lazy_static;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Environment variable consulted for a CA bundle when none is configured
+const CA_BUNDLE_ENV: &str = "SSL_CERT_FILE";
+
+/// Settings applied to every HTTP client
+#[derive(Default)]
+struct NetSettings {
+    proxy: Option<reqwest::Proxy>,
+    certificates: Vec<reqwest::Certificate>,
+}
+
+lazy_static! {
+    static ref NET_SETTINGS: RwLock<NetSettings> = RwLock::new(NetSettings::default());
+}
+
+/// Configure the proxy and additional CA certificates for all HTTP clients
+pub fn configure(proxy: Option<&str>, ca_bundle: Option<&Path>) -> Result<(), String> {
+    let proxy = proxy
+        .map(|url| {
+            reqwest::Proxy::all(url)
+                .map(|proxy| proxy.no_proxy(reqwest::NoProxy::from_env()))
+                .map_err(|e| format!("Invalid proxy URL '{url}': {e}"))
+        })
+        .transpose()?;
+
+    let ca_bundle = ca_bundle
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var_os(CA_BUNDLE_ENV).map(PathBuf::from));
+    let certificates = match ca_bundle {
+        Some(path) => load_certificates(&path)?,
+        None => Vec::new(),
+    };
+
+    *NET_SETTINGS.write().unwrap() = NetSettings {
+        proxy,
+        certificates,
+    };
+    Ok(())
+}
+
+/// Read all certificates from a PEM bundle
+fn load_certificates(path: &Path) -> Result<Vec<reqwest::Certificate>, String> {
+    let pem = std::fs::read(path)
+        .map_err(|e| format!("Failed to read CA bundle '{}': {e}", path.display()))?;
+    let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+        .map_err(|e| format!("Invalid CA bundle '{}': {e}", path.display()))?;
+    if certificates.is_empty() {
+        return Err(format!(
+            "CA bundle '{}' contains no certificates",
+            path.display()
+        ));
+    }
+    Ok(certificates)
+}
+
+/// A client builder with the configured proxy and certificates applied
+pub fn client_builder() -> reqwest::ClientBuilder {
+    let settings = NET_SETTINGS.read().unwrap();
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = &settings.proxy {
+        builder = builder.proxy(proxy.clone());
+    }
+    for certificate in &settings.certificates {
+        builder = builder.add_root_certificate(certificate.clone());
+    }
+    builder
+}
+
+/// A client with the configured proxy and certificates and no other options
+pub fn client() -> reqwest::Client {
+    client_builder()
+        .build()
+        .expect("Failed to create HTTP client")
+}
@@ -2,12 +2,21 @@
 //!
 //! This module provides a buffer system where each task has its own output buffer
 //! accessed through task-local storage, allowing for clean API with no buffer passing.
+//!
+//! A buffer keeps a bounded history of lines. Consumers that stream the output
+//! follow it with a [`BufferReader`], which is woken up when lines are pushed
+//! instead of polling the buffer and comparing line counts.
 
 use chrono::Utc;
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::watch;
 use tokio::task;
 
+/// Lines kept in the history of a buffer; older lines are dropped
+pub const HISTORY_LINES: usize = 20_000;
+
 /// Types of output lines that can be stored in the buffer
 #[derive(Debug, Clone, PartialEq)]
 pub enum OutputType {
@@ -51,24 +60,83 @@ pub struct OutputLine {
 pub struct SharedBuffer {
     /// The mutex-protected queue of output lines
     queue: Arc<Mutex<VecDeque<OutputLine>>>,
+    /// Lines dropped from the front of the queue, updated with the queue locked
+    dropped: Arc<AtomicU64>,
+    /// Count of lines pushed so far, watched by readers
+    updates: Arc<watch::Sender<u64>>,
 }
 
 impl SharedBuffer {
     /// Create a new shared buffer with the given capacity
     pub fn new(capacity: usize) -> Self {
+        Self::with_lines(VecDeque::with_capacity(capacity))
+    }
+
+    fn with_lines(lines: VecDeque<OutputLine>) -> Self {
+        let (updates, _) = watch::channel(lines.len() as u64);
         Self {
-            queue: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            queue: Arc::new(Mutex::new(lines)),
+            dropped: Arc::new(AtomicU64::new(0)),
+            updates: Arc::new(updates),
         }
     }
+
     pub fn lines(&self) -> MutexGuard<VecDeque<OutputLine>> {
         self.queue.lock().unwrap()
     }
 
+    /// Count of lines dropped from the history so far
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::SeqCst)
+    }
+
+    /// Follow the lines pushed from now on, starting with up to `backlog`
+    /// lines already in the history
+    pub fn reader(&self, backlog: usize) -> BufferReader {
+        let mut updates = self.updates.subscribe();
+        let total = *updates.borrow_and_update();
+        let start = total.saturating_sub(backlog as u64).max(self.dropped());
+        BufferReader {
+            buffer: self.clone(),
+            next: start,
+            updates,
+        }
+    }
+
+    /// Create an independent buffer holding a copy of the current lines
+    pub fn snapshot(&self) -> Self {
+        Self::with_lines(self.lines().clone())
+    }
+
+    /// Render the buffer contents as a plain-text transcript
+    ///
+    /// ANSI escape sequences are stripped unless `preserve_colors` is set, in which
+    /// case the transcript can be replayed with `cat` or `less -R`.
+    pub fn export_transcript(&self, preserve_colors: bool) -> String {
+        let lines = self.lines();
+        let mut transcript = String::new();
+        for line in lines.iter() {
+            if preserve_colors {
+                transcript.push_str(&line.content);
+            } else {
+                transcript.push_str(&crate::ansi_converter::strip_ansi_sequences(&line.content));
+            }
+            transcript.push('\n');
+        }
+        transcript
+    }
+
     /// Push a line to the buffer
     pub fn push(&self, line: OutputLine) -> Result<(), String> {
         match self.queue.lock() {
             Ok(mut queue) => {
                 queue.push_back(line);
+                if queue.len() > HISTORY_LINES {
+                    queue.pop_front();
+                    self.dropped.fetch_add(1, Ordering::SeqCst);
+                }
+                self.updates
+                    .send_replace(self.dropped() + queue.len() as u64);
                 Ok(())
             }
             Err(e) => Err(format!("Failed to lock buffer queue: {e}")),
@@ -79,7 +147,11 @@ impl SharedBuffer {
     #[allow(dead_code)]
     pub fn pop(&self) -> Option<OutputLine> {
         match self.queue.lock() {
-            Ok(mut queue) => queue.pop_front(),
+            Ok(mut queue) => {
+                let line = queue.pop_front()?;
+                self.dropped.fetch_add(1, Ordering::SeqCst);
+                Some(line)
+            }
             Err(e) => {
                 eprintln!("Failed to lock buffer queue: {e}");
                 None
@@ -161,6 +233,59 @@ impl SharedBuffer {
     }
 }
 
+/// Follows the lines pushed to a buffer
+///
+/// Lines dropped from the history before they were read are replaced by a
+/// notice with their count.
+pub struct BufferReader {
+    buffer: SharedBuffer,
+    /// Sequence number of the next line to read
+    next: u64,
+    updates: watch::Receiver<u64>,
+}
+
+impl BufferReader {
+    /// Lines pushed since the previous read, without waiting
+    pub fn pending(&mut self) -> Vec<OutputLine> {
+        self.updates.borrow_and_update();
+        let lines = self.buffer.lines();
+        let dropped = self.buffer.dropped();
+
+        let mut pending = Vec::new();
+        if self.next < dropped {
+            let content = format!(
+                "[{} lines dropped from the output history]",
+                dropped - self.next
+            );
+            pending.push(OutputLine {
+                output_type: OutputType::System,
+                converted_line: ansi_to_line(&content),
+                content,
+                formatting: None,
+                timestamp: Utc::now(),
+            });
+            self.next = dropped;
+        }
+        pending.extend(lines.iter().skip((self.next - dropped) as usize).cloned());
+        self.next = dropped + lines.len() as u64;
+        pending
+    }
+
+    /// Wait until lines are pushed, then return all lines not read yet
+    pub async fn next_lines(&mut self) -> Vec<OutputLine> {
+        loop {
+            let pending = self.pending();
+            if !pending.is_empty() {
+                return pending;
+            }
+            // The reader holds the buffer, so the sender is never dropped
+            if self.updates.changed().await.is_err() {
+                return Vec::new();
+            }
+        }
+    }
+}
+
 // Task-local storage for the current task's output buffer
 tokio::task_local! {
     pub static CURRENT_BUFFER: SharedBuffer;
@@ -182,3 +307,44 @@ where
 {
     spawn_with_buffer(CURRENT_BUFFER.get(), future)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reader() {
+        let buffer = SharedBuffer::new(10);
+        buffer.stdout("before").unwrap();
+
+        let mut reader = buffer.reader(0);
+        assert!(reader.pending().is_empty());
+
+        let writer = buffer.clone();
+        tokio::spawn(async move { writer.stdout("first\nsecond").unwrap() });
+        let contents = |lines: Vec<OutputLine>| -> Vec<String> {
+            lines.into_iter().map(|line| line.content).collect()
+        };
+        let mut read = contents(reader.next_lines().await);
+        if read.len() < 2 {
+            read.extend(contents(reader.next_lines().await));
+        }
+        assert_eq!(read, ["first", "second"]);
+
+        // Lines dropped before they were read are reported with their count
+        for i in 0..HISTORY_LINES + 5 {
+            buffer.stdout(format!("line {i}")).unwrap();
+        }
+        let lines = reader.pending();
+        assert_eq!(
+            lines[0].content,
+            "[5 lines dropped from the output history]"
+        );
+        assert_eq!(lines[1].content, "line 5");
+        assert_eq!(lines.len(), HISTORY_LINES + 1);
+        assert_eq!(
+            contents(buffer.reader(2).pending()),
+            ["line 20003", "line 20004"]
+        );
+    }
+}
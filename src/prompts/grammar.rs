@@ -9,6 +9,18 @@ use crate::constants::{
     PATCH_DELIMITER_AFTER, PATCH_DELIMITER_BEFORE, PATCH_DELIMITER_END, TOOL_END, TOOL_ERROR_END,
     TOOL_ERROR_START_PREFIX, TOOL_RESULT_END, TOOL_RESULT_START_PREFIX, TOOL_START,
 };
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    /// `<tool>` or `</tool>` with stray whitespace or in another case
+    static ref LOOSE_XML_TAG: Regex = Regex::new(r"(?i)<\s*(/?)\s*tool\s*>").unwrap();
+    /// Opening fence of a markdown tool call, including common slips: more
+    /// backticks, a `tool_call` tag, stray whitespace or a line break before
+    /// the tool name
+    static ref LOOSE_MD_FENCE: Regex =
+        Regex::new(r"(?i)`{3,}[ \t]*tool_(?:use|call)\b[ \t]*(?:\r?\n[ \t]*)?").unwrap();
+}
 
 // Constants for markdown-based grammar
 /// Represents a tool invocation with name, arguments, and body content
@@ -28,6 +40,94 @@ pub struct ParsedResponse {
     pub tool: Option<ToolInvocation>,
     // Raw text (including first tool call).
     pub keep_part: String,
+    // Why the tool call in the response could not be parsed, if one was attempted
+    pub malformed: Option<String>,
+    // Text the model wrote after the tool call, not part of keep_part
+    pub trailing_text: String,
+}
+
+impl ParsedResponse {
+    /// A response without a tool call
+    fn text(response: &str) -> Self {
+        Self {
+            human_text_prefix: response.to_string(),
+            tool: None,
+            keep_part: response.to_string(),
+            malformed: None,
+            trailing_text: String::new(),
+        }
+    }
+
+    /// A response with a tool call that could not be parsed
+    fn malformed(response: &str, reason: impl Into<String>) -> Self {
+        Self {
+            malformed: Some(reason.into()),
+            ..Self::text(response)
+        }
+    }
+}
+
+/// Split the content of a tool call into the tool name, arguments and body
+fn parse_tool_content(content: &str) -> Result<ToolInvocation, String> {
+    let content = content.trim();
+    let (header, body) = content.split_once('\n').unwrap_or((content, ""));
+
+    let mut args = header.split_whitespace();
+    let Some(name) = args.next() else {
+        return Err(
+            "the tool name is missing; the call must start with the name of a tool".to_string(),
+        );
+    };
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(format!(
+            "`{name}` is not a tool name; the call must start with the name of a tool, followed by its arguments"
+        ));
+    }
+
+    Ok(ToolInvocation {
+        name: name.to_lowercase(),
+        args: args.map(str::to_string).collect(),
+        body: body.to_string(),
+    })
+}
+
+/// Find the fence closing a markdown tool call block
+///
+/// A line opening a fence with an info string (e.g. "```rust") starts a
+/// nested code block inside the body, which the next bare fence closes. When
+/// the nesting does not balance, the last bare fence closes the call unless
+/// `balanced_only` is set (e.g. for text that is still being generated).
+fn find_closing_fence(block: &str, balanced_only: bool) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut offset = 0;
+    let mut last_bare_fence = None;
+    for (line_number, line) in block.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim();
+        let fence = line.find(MD_CODE_END).map(|index| offset + index);
+        if line_number == 0 || !trimmed.starts_with(MD_CODE_END) {
+            // A fence within a line, e.g. "read src/main.rs```"
+            if depth == 0 && fence.is_some() {
+                return fence;
+            }
+        } else if trimmed.chars().all(|c| c == '`') {
+            if depth == 0 {
+                return fence;
+            }
+            depth -= 1;
+            last_bare_fence = fence;
+        } else {
+            depth += 1;
+        }
+        offset += line.len();
+    }
+    if balanced_only {
+        None
+    } else {
+        last_bare_fence
+    }
 }
 
 /// Stop sequences for LLM generation
@@ -38,6 +138,16 @@ pub struct StopSequences {
     pub error_stop_sequence: &'static str,
 }
 
+impl StopSequences {
+    /// All stop sequences of the grammar
+    pub fn to_vec(&self) -> Vec<String> {
+        vec![
+            self.done_stop_sequence.to_string(),
+            self.error_stop_sequence.to_string(),
+        ]
+    }
+}
+
 /// Grammar trait for prompt generation and parsing
 pub trait Grammar: Send + Sync + 'static {
     /// Returns the stop sequences for this grammar
@@ -62,6 +172,10 @@ pub trait Grammar: Send + Sync + 'static {
     /// Parses a response from the assistant
     fn parse_response(&self, response: &str) -> ParsedResponse;
 
+    /// Byte offset where the first tool call ends in a response that is still
+    /// being generated, or None while no tool call is complete
+    fn tool_call_end(&self, partial: &str) -> Option<usize>;
+
     /// Returns the tool start tag for this grammar
     fn tool_start_tag(&self) -> &str;
 
@@ -138,66 +252,61 @@ impl Grammar for XmlGrammar {
     }
 
     fn parse_response(&self, response: &str) -> ParsedResponse {
-        // Direct implementation of parsing logic to avoid circular dependency
-        if !response.contains(TOOL_START) {
-            // No tool invocation found
-            return ParsedResponse {
-                human_text_prefix: response.to_string(),
-                tool: None,
-                keep_part: response.to_string(),
-            };
-        }
-
-        // Find the tool invocation (from start to end tag)
-        if let Some(tool_start_idx) = response.find(TOOL_START) {
-            if let Some(tool_end_relative_idx) = response[tool_start_idx..].find(TOOL_END) {
-                let tool_end_idx = tool_start_idx + tool_end_relative_idx;
-
-                // Get the text before the tool invocation
-                let text_before_tool = response[0..tool_start_idx].trim().to_string();
-                let text_including_tool = response[0..tool_end_idx + TOOL_END.len()].trim().to_string();
-
-                // Extract tool content
-                let tool_content = &response[tool_start_idx + TOOL_START.len()..tool_end_idx];
-
-                let (header, body) = match tool_content.trim().split_once('\n') {
-                    Some((header, body)) => (header, body),
-                    None => (tool_content, ""),
-                };
-
-                let mut args = header.split_whitespace().collect::<Vec<&str>>();
-
-                if args.is_empty() {
-                    // No tool name found, return the original text
-                    return ParsedResponse {
-                        human_text_prefix: response.to_string(),
-                        tool: None,
-                        keep_part: response.to_string(),
-                    };
-                }
-
-                let tool_name = args.remove(0).to_lowercase();
-
-                return ParsedResponse {
-                    human_text_prefix: text_before_tool,
-                    tool: Some(ToolInvocation {
-                        name: tool_name,
-                        args: args.iter().map(|s| s.to_string()).collect::<Vec<String>>(),
-                        body: body.to_string(),
-                    }),
-                    keep_part: text_including_tool,
-                };
+        // Accept tags with stray whitespace or in another case, e.g. "< Tool >"
+        let response = LOOSE_XML_TAG.replace_all(response, "<${1}tool>");
+
+        let Some(tool_start_idx) = response.find(TOOL_START) else {
+            if response.contains(TOOL_END) {
+                return ParsedResponse::malformed(
+                    &response,
+                    "it has a closing `</tool>` tag without an opening `<tool>` tag",
+                );
             }
+            return ParsedResponse::text(&response);
+        };
+
+        let content_start = tool_start_idx + TOOL_START.len();
+        let content_end = match response[content_start..].find(TOOL_END) {
+            Some(index) => content_start + index,
+            // An unclosed tag most likely ran into the stop sequence
+            None => response.len(),
+        };
+        let tool_content = &response[content_start..content_end];
+        if tool_content.contains(TOOL_START) {
+            return ParsedResponse::malformed(
+                &response,
+                "a second `<tool>` tag was opened before the first one was closed with `</tool>`",
+            );
         }
 
-        // If we reach here, no proper tool invocation was found
-        ParsedResponse {
-            human_text_prefix: response.to_string(),
-            tool: None,
-            keep_part: response.to_string(),
+        // Get the text before the tool invocation
+        let text_before_tool = response[..tool_start_idx].trim().to_string();
+        let call_end = (content_end + TOOL_END.len()).min(response.len());
+        let text_including_tool = if content_end < response.len() {
+            response[..call_end].trim().to_string()
+        } else {
+            format!("{}{}", response.trim(), TOOL_END)
+        };
+
+        match parse_tool_content(tool_content) {
+            Ok(tool) => ParsedResponse {
+                human_text_prefix: text_before_tool,
+                tool: Some(tool),
+                keep_part: text_including_tool,
+                malformed: None,
+                trailing_text: response[call_end..].trim().to_string(),
+            },
+            Err(reason) => ParsedResponse::malformed(&response, reason),
         }
     }
 
+    fn tool_call_end(&self, partial: &str) -> Option<usize> {
+        let mut tags = LOOSE_XML_TAG.find_iter(partial);
+        tags.find(|tag| !tag.as_str().contains('/'))?;
+        tags.find(|tag| tag.as_str().contains('/'))
+            .map(|tag| tag.end())
+    }
+
     fn tool_start_tag(&self) -> &str {
         TOOL_START
     }
@@ -318,114 +427,57 @@ impl Grammar for MarkdownGrammar {
     }
 
     fn parse_response(&self, response: &str) -> ParsedResponse {
-        // Defensive programming - return early if response is empty or too short
-        if response.is_empty() || response.len() < MD_TOOL_CALL_START.len() + 3 {
-            return ParsedResponse {
-                human_text_prefix: response.to_string(),
-                tool: None,
-                keep_part: response.to_string(),
-            };
+        // Accept fences with the wrong tag, stray whitespace or the tool name
+        // on the next line, e.g. "``` tool_call\nread"
+        let response = LOOSE_MD_FENCE.replace_all(response, MD_TOOL_CALL_START);
+
+        let Some(tool_start_idx) = response.find(MD_TOOL_CALL_START) else {
+            return ParsedResponse::text(&response);
+        };
+
+        let block_start = tool_start_idx + MD_TOOL_CALL_START.len();
+        let block_end = match find_closing_fence(&response[block_start..], false) {
+            Some(index) => block_start + index,
+            // An unclosed block most likely ran into the stop sequence
+            None => response.len(),
+        };
+
+        // Get the text before the tool invocation
+        let text_before_tool = response[..tool_start_idx].trim().to_string();
+        // A longer closing fence (e.g. "````") is consumed whole
+        let fence_len = response[block_end..]
+            .bytes()
+            .take_while(|b| *b == b'`')
+            .count();
+        let call_end = block_end + fence_len;
+        let text_including_tool = if block_end < response.len() {
+            response[..call_end].trim().to_string()
+        } else {
+            format!("{}\n{}", response.trim(), MD_CODE_END)
+        };
+
+        match parse_tool_content(&response[block_start..block_end]) {
+            Ok(tool) => ParsedResponse {
+                human_text_prefix: text_before_tool,
+                tool: Some(tool),
+                keep_part: text_including_tool,
+                malformed: None,
+                trailing_text: response[call_end..].trim().to_string(),
+            },
+            Err(reason) => ParsedResponse::malformed(&response, reason),
         }
+    }
 
-        // Check for markdown tool call pattern
-        if !response.contains(MD_TOOL_CALL_START) {
-            // No tool invocation found
-            return ParsedResponse {
-                human_text_prefix: response.to_string(),
-                tool: None,
-                keep_part: response.to_string(),
-            };
-        }
+    fn tool_call_end(&self, partial: &str) -> Option<usize> {
+        let block_start = LOOSE_MD_FENCE.find(partial)?.end();
+        let fence = block_start + find_closing_fence(&partial[block_start..], true)?;
+        let fence_end = fence + partial[fence..].bytes().take_while(|b| *b == b'`').count();
 
-        // Find the tool invocation (from markdown start to end)
-        if let Some(tool_start_idx) = response.find(MD_TOOL_CALL_START) {
-            // Safely get the substring after the tool call start
-            let after_tool_start = match response.get(tool_start_idx..) {
-                Some(s) => s,
-                None => {
-                    // This shouldn't happen given our earlier check, but just in case
-                    return ParsedResponse {
-                        human_text_prefix: response.to_string(),
-                        tool: None,
-                        keep_part: response.to_string(),
-                    };
-                }
-            };
-
-            if let Some(code_end_relative_idx) =
-                after_tool_start[MD_TOOL_CALL_START.len()..].find(MD_CODE_END)
-            {
-                let code_end_idx =
-                    tool_start_idx + MD_TOOL_CALL_START.len() + code_end_relative_idx;
-                // Get the text before the tool invocation (safely)
-                let text_before_tool =  response[0..tool_start_idx].trim().to_string();
-
-                let text_including_tool = response[0..code_end_idx + MD_CODE_END.len()].trim().to_string();
-
-                // Extract the entire code block content (without the backticks and marker)
-                // Use safe substring operations with bounds checking
-                let block_start_idx = tool_start_idx + MD_TOOL_CALL_START.len();
-                let block_content = if block_start_idx < code_end_idx {
-                    match response.get(block_start_idx..code_end_idx) {
-                        Some(content) => content,
-                        None => {
-                            // Bounds error - fall back to treating as plain text
-                            return ParsedResponse {
-                                human_text_prefix: response.to_string(),
-                                tool: None,
-                                keep_part: response.to_string(),
-                            };
-                        }
-                    }
-                } else {
-                    // Empty block content
-                    ""
-                };
-
-                // Split into first line (tool name + args) and the rest (body)
-                // Use more robust approach to handle different line endings
-                let trimmed_block = block_content.trim();
-                let (header, body) = match trimmed_block.find('\n') {
-                    Some(newline_pos) => {
-                        let (h, b) = trimmed_block.split_at(newline_pos);
-                        // Skip the newline character when returning body
-                        (h, if b.len() > 1 { &b[1..] } else { "" })
-                    }
-                    None => (trimmed_block, ""),
-                };
-
-                // Parse the tool name and args from the header
-                let mut args = header.split_whitespace().collect::<Vec<&str>>();
-
-                if args.is_empty() {
-                    // No tool name found - return the original text
-                    return ParsedResponse {
-                        human_text_prefix: response.to_string(),
-                        tool: None,
-                        keep_part: response.to_string(),
-                    };
-                }
-
-                let tool_name = args.remove(0).to_lowercase();
-
-                return ParsedResponse {
-                    human_text_prefix: text_before_tool,
-                    tool: Some(ToolInvocation {
-                        name: tool_name,
-                        args: args.iter().map(|s| s.to_string()).collect::<Vec<String>>(),
-                        body: body.to_string(),
-                    }),
-                    keep_part: text_including_tool,
-                };
-            }
-        }
-
-        // If we reach here, no proper tool invocation was found
-        ParsedResponse {
-            human_text_prefix: response.to_string(),
-            tool: None,
-            keep_part: response.to_string()
-        }
+        // A fence at the end of the text may still turn into "```rust"
+        partial[fence_end..]
+            .trim_start_matches([' ', '\t', '\r'])
+            .starts_with('\n')
+            .then_some(fence_end)
     }
 
     fn tool_start_tag(&self) -> &str {
@@ -452,3 +504,99 @@ impl Grammar for MarkdownGrammar {
         MD_CODE_END
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xml_recovers_loose_tags() {
+        let parsed = XmlGrammar.parse_response("Reading.\n< TOOL >read src/main.rs</ Tool >");
+        let tool = parsed.tool.unwrap();
+        assert_eq!(tool.name, "read");
+        assert_eq!(tool.args, vec!["src/main.rs"]);
+        assert_eq!(parsed.keep_part, "Reading.\n<tool>read src/main.rs</tool>");
+        assert!(parsed.trailing_text.is_empty());
+
+        // An unclosed call is closed at the end of the response
+        let parsed = XmlGrammar.parse_response("<tool>shell ls -la\n");
+        assert_eq!(parsed.tool.unwrap().args, vec!["ls", "-la"]);
+        assert_eq!(parsed.keep_part, "<tool>shell ls -la</tool>");
+    }
+
+    #[test]
+    fn test_xml_reports_malformed_calls() {
+        let parsed = XmlGrammar.parse_response("<tool>\n</tool>");
+        assert!(parsed.tool.is_none());
+        assert!(parsed.malformed.unwrap().contains("tool name is missing"));
+
+        let parsed = XmlGrammar.parse_response("<tool>read a <tool>read b</tool>");
+        assert!(parsed.malformed.unwrap().contains("second `<tool>`"));
+
+        let parsed = XmlGrammar.parse_response("read src/main.rs</tool>");
+        assert!(parsed.malformed.unwrap().contains("without an opening"));
+
+        let parsed = XmlGrammar.parse_response("<tool>{\"name\": \"read\"}</tool>");
+        assert!(parsed.malformed.unwrap().contains("is not a tool name"));
+
+        assert!(XmlGrammar.parse_response("Just text").malformed.is_none());
+    }
+
+    #[test]
+    fn test_markdown_recovers_loose_fences() {
+        let parsed = MarkdownGrammar.parse_response("```tool_call\nread src/main.rs\n```");
+        assert_eq!(parsed.tool.unwrap().args, vec!["src/main.rs"]);
+        assert_eq!(parsed.keep_part, "```tool_use read src/main.rs\n```");
+
+        let parsed = MarkdownGrammar.parse_response("```tool_use shell ls");
+        assert_eq!(parsed.tool.unwrap().name, "shell");
+        assert_eq!(parsed.keep_part, "```tool_use shell ls\n```");
+    }
+
+    #[test]
+    fn test_markdown_nested_fences() {
+        let response =
+            "```tool_use write README.md\n# Usage\n```sh\ncargo run\n```\nDone.\n```\nAfter.";
+        let parsed = MarkdownGrammar.parse_response(response);
+        let tool = parsed.tool.unwrap();
+        assert_eq!(tool.name, "write");
+        assert_eq!(tool.body, "# Usage\n```sh\ncargo run\n```\nDone.");
+        assert!(!parsed.keep_part.contains("After."));
+        assert_eq!(parsed.trailing_text, "After.");
+
+        // An unbalanced nested fence leaves the last bare fence as the end
+        let response = "```tool_use write a.md\n```rust\nfn main() {}\n```";
+        let tool = MarkdownGrammar.parse_response(response).tool.unwrap();
+        assert_eq!(tool.body, "```rust\nfn main() {}");
+    }
+
+    #[test]
+    fn test_tool_call_end() {
+        assert_eq!(
+            XmlGrammar.tool_call_end("Let me look.\n<tool>read a.rs"),
+            None
+        );
+        let partial = "Let me look.\n<tool>read a.rs</tool>\n<tool_res";
+        assert_eq!(
+            XmlGrammar.tool_call_end(partial),
+            Some(partial.find("\n<tool_res").unwrap())
+        );
+
+        let partial = "```tool_use write a.md\n```rust\nfn main() {}\n```";
+        assert_eq!(MarkdownGrammar.tool_call_end(partial), None);
+        let partial = "```tool_use write a.md\n```rust\nfn main() {}\n```\n```";
+        assert_eq!(MarkdownGrammar.tool_call_end(partial), None);
+        let partial = "```tool_use write a.md\n```rust\nfn main() {}\n```\n```\nDone";
+        assert_eq!(
+            MarkdownGrammar.tool_call_end(partial),
+            Some(partial.len() - "\nDone".len())
+        );
+    }
+
+    #[test]
+    fn test_markdown_reports_malformed_calls() {
+        let parsed = MarkdownGrammar.parse_response("```tool_use\n```");
+        assert!(parsed.tool.is_none());
+        assert!(parsed.malformed.unwrap().contains("tool name is missing"));
+    }
+}
@@ -15,7 +15,7 @@ use std::path::PathBuf;
 use thiserror::Error;
 
 use crate::prompts::Grammar;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 /// Errors that can occur with templates
 #[derive(Error, Debug)]
@@ -43,6 +43,9 @@ pub struct TemplateManager {
 
     /// Whether to prefer embedded templates (true in release mode)
     prefer_embedded: bool,
+
+    /// Stop sequences registered by templates while rendering
+    stop_sequences: Arc<Mutex<Vec<String>>>,
 }
 
 /// Helper for formatting tool invocations
@@ -204,6 +207,38 @@ impl HelperDef for ErrorHelper {
     }
 }
 
+/// Helper for registering an extra stop sequence, e.g. `{{stop_sequence "</answer>"}}`
+///
+/// Kinds that introduce their own tags use it to end the response at a tag,
+/// the way the grammar ends it at a tool result. It renders nothing.
+struct StopSequenceHelper {
+    stop_sequences: Arc<Mutex<Vec<String>>>,
+}
+
+impl HelperDef for StopSequenceHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _r: &'reg Handlebars<'reg>,
+        _ctx: &'rc Context,
+        _rc: &mut RenderContext<'reg, 'rc>,
+        _out: &mut dyn Output,
+    ) -> HelperResult {
+        #[allow(deprecated)]
+        let sequence = h
+            .param(0)
+            .and_then(|v| v.value().as_str())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| RenderError::new("Stop sequence is required"))?;
+
+        let mut stop_sequences = self.stop_sequences.lock().unwrap();
+        if !stop_sequences.iter().any(|s| s == sequence) {
+            stop_sequences.push(sequence.to_string());
+        }
+        Ok(())
+    }
+}
+
 struct PatchHelper {
     grammar: Arc<dyn Grammar>,
 }
@@ -232,7 +267,9 @@ impl HelperDef for PatchHelper {
 impl TemplateManager {
     /// Create a new template manager
     pub fn new(grammar: Arc<dyn Grammar>) -> Self {
-        let handlebars_instance = Self::create_handlebars_with_helpers(Arc::clone(&grammar));
+        let stop_sequences = Arc::new(Mutex::new(Vec::new()));
+        let handlebars_instance =
+            Self::create_handlebars_with_helpers(Arc::clone(&grammar), Arc::clone(&stop_sequences));
 
         // Use embedded templates in release mode, file-based in debug mode
         let prefer_embedded = !cfg!(debug_assertions);
@@ -241,9 +278,15 @@ impl TemplateManager {
             handlebars: handlebars_instance,
             templates_dir: PathBuf::from("prompts"),
             prefer_embedded,
+            stop_sequences,
         }
     }
 
+    /// Stop sequences registered by the templates rendered so far
+    pub fn registered_stop_sequences(&self) -> Vec<String> {
+        self.stop_sequences.lock().unwrap().clone()
+    }
+
     /// Load all templates from embedded content and/or the templates directory
     /// This loads regular templates and registers partials using path-based namespacing
     pub fn load_all_templates(&mut self) -> Result<(), TemplateError> {
@@ -348,7 +391,10 @@ impl TemplateManager {
     }
 
     /// Create a Handlebars instance with registered helpers
-    fn create_handlebars_with_helpers(grammar: Arc<dyn Grammar>) -> Handlebars<'static> {
+    fn create_handlebars_with_helpers(
+        grammar: Arc<dyn Grammar>,
+        stop_sequences: Arc<Mutex<Vec<String>>>,
+    ) -> Handlebars<'static> {
         let mut handlebars = Handlebars::new();
 
         // Clone Arc for each helper
@@ -387,6 +433,12 @@ impl TemplateManager {
         // Register the mcptools helper that formats MCP tools
         handlebars.register_helper("mcptools", Box::new(McpToolsHelper));
 
+        // Register the stop_sequence helper for kinds with their own tags
+        handlebars.register_helper(
+            "stop_sequence",
+            Box::new(StopSequenceHelper { stop_sequences }),
+        );
+
         handlebars.register_escape_fn(|s| s.to_string());
 
         handlebars
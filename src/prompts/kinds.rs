@@ -0,0 +1,433 @@
+//! Metadata of agent kinds
+//!
+//! A kind template starts with a title comment whose text after the dash is
+//! the kind's description, optionally followed by front matter in a
+//! Handlebars block comment:
+//!
+//! ```handlebars
+//! {{! Researcher Agent Template - Specialized for information gathering }}
+//! {{!--
+//! tools: readonly
+//! models: [claude-3-7-sonnet-20250219, gemini-2.5-pro]
+//! --}}
+//! ```
+//!
+//! `tools` is the default tool set of the kind: `all` (the default),
+//! `readonly` or a list of tool names. `models` lists the models recommended
+//! for the kind. Both comments are left out of the rendered prompt.
+//!
+//! Kinds are composed from shared blocks:
+//! - `mixins: [coding-standards, safety]` appends mixins, partial files in
+//!   `prompts/mixins/` or a `.termineer/mixins/` directory, to the prompt
+//! - `extends: KIND` starts the prompt with another kind's, whose tools and
+//!   models are used unless the front matter sets them
+//!
+//! Besides the built-in kinds, custom kinds are read from `.termineer/kinds/`
+//! in the project and then in the home directory, typically extending a
+//! built-in kind with project conventions.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Front matter of a kind template
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FrontMatter {
+    /// Default tool set
+    #[serde(default)]
+    pub tools: Option<ToolSet>,
+    /// Recommended models
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// Kind whose prompt this kind builds on
+    #[serde(default)]
+    pub extends: Option<String>,
+    /// Shared blocks appended to the prompt
+    #[serde(default)]
+    pub mixins: Vec<String>,
+}
+
+/// Tiers of kinds with their titles, in listing order
+const TIERS: &[(&str, &str)] = &[
+    ("standard", "Standard agent kinds"),
+    ("plus", "Plus agent kinds"),
+    ("pro", "Pro agent kinds"),
+    ("custom", "Custom agent kinds"),
+];
+
+/// Tools a kind uses by default
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ToolSet {
+    /// `all` or `readonly`
+    Named(String),
+    /// Tool names
+    List(Vec<String>),
+}
+
+/// Description and defaults of an agent kind, as listed by `list-kinds`
+#[derive(Debug, Clone, Serialize)]
+pub struct KindInfo {
+    /// Name given to `--kind`
+    pub name: String,
+    /// Subscription the kind needs (standard, plus or pro), or custom
+    pub tier: &'static str,
+    /// Text of the title comment after its dash
+    pub description: String,
+    /// Default tool set: all, readonly or the tool names
+    pub tool_set: String,
+    /// Tools enabled by default
+    pub tools: Vec<String>,
+    /// Recommended models
+    pub models: Vec<String>,
+}
+
+/// Description from the title comment: the text after its dash
+pub fn description(template: &str) -> String {
+    let title = template
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("{{!"));
+    let Some(title) = title.filter(|title| !title.starts_with("{{!--")) else {
+        return String::new();
+    };
+    let title = title.trim_start_matches("{{!").trim_end_matches("}}");
+    title
+        .split_once('-')
+        .map(|(_, description)| description.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Front matter of a template; templates without it use the defaults
+pub fn front_matter(template: &str) -> Result<FrontMatter, String> {
+    // The front matter follows the one-line title comment, if there is one
+    let template = template.trim_start();
+    let block = if template.starts_with("{{!--") {
+        template.strip_prefix("{{!--")
+    } else if template.starts_with("{{!") {
+        let rest = template
+            .split_once('\n')
+            .map_or("", |(_, rest)| rest.trim_start());
+        rest.strip_prefix("{{!--")
+    } else {
+        None
+    };
+    let Some(block) = block else {
+        return Ok(FrontMatter::default());
+    };
+    let Some((yaml, _)) = block.split_once("--}}") else {
+        return Err("Unterminated front matter: missing --}}".to_string());
+    };
+    serde_yaml::from_str::<Option<FrontMatter>>(yaml)
+        .map(Option::unwrap_or_default)
+        .map_err(|e| format!("Invalid front matter: {e}"))
+}
+
+/// Default tools of a front matter's tool set
+fn resolve_tools(tools: Option<&ToolSet>) -> Result<Vec<&'static str>, String> {
+    match tools {
+        None => Ok(super::ALL_TOOLS.to_vec()),
+        Some(ToolSet::Named(name)) => match name.as_str() {
+            "all" => Ok(super::ALL_TOOLS.to_vec()),
+            "readonly" => Ok(super::READONLY_TOOLS.to_vec()),
+            _ => Err(format!(
+                "Unknown tool set '{name}': use all, readonly or a list"
+            )),
+        },
+        Some(ToolSet::List(names)) => {
+            let unknown = names
+                .iter()
+                .find(|name| !super::ALL_TOOLS.contains(&name.as_str()));
+            if let Some(unknown) = unknown {
+                return Err(format!("Unknown tool '{unknown}' in the tool set"));
+            }
+            Ok(super::ALL_TOOLS
+                .iter()
+                .copied()
+                .filter(|tool| names.iter().any(|name| name == tool))
+                .collect())
+        }
+    }
+}
+
+/// Directories of custom kinds or mixins: the project's, then the home directory's
+fn user_dirs(name: &str) -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from(".termineer").join(name)];
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(".termineer").join(name));
+    }
+    dirs
+}
+
+/// Template of a custom kind or mixin
+fn user_template(dir: &str, name: &str) -> Option<String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return None;
+    }
+    user_dirs(dir)
+        .into_iter()
+        .find_map(|dir| std::fs::read_to_string(dir.join(format!("{name}.hbs"))).ok())
+}
+
+/// Source of a kind or a mixin: built-in first, then custom
+fn load(source: Source, name: &str) -> Option<String> {
+    let (builtin, dir) = match source {
+        Source::Kind => (format!("kind/{name}"), "kinds"),
+        Source::Mixin => (format!("mixins/{name}"), "mixins"),
+    };
+    super::protected::get_prompt_template(&builtin).or_else(|| user_template(dir, name))
+}
+
+/// What a template is loaded as
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Source {
+    Kind,
+    Mixin,
+}
+
+/// Names of the custom kinds, which do not shadow built-in kinds
+pub fn custom_kinds() -> Vec<String> {
+    let mut kinds = Vec::new();
+    for dir in user_dirs("kinds") {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            if path.extension().is_some_and(|ext| ext == "hbs") {
+                if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    kinds.push(name.to_string());
+                }
+            }
+        }
+    }
+    kinds.retain(|kind| !super::AVAILABLE_KINDS_ARRAY.contains(kind));
+    kinds.sort();
+    kinds.dedup();
+    kinds
+}
+
+/// Whether a custom kind with this name exists
+pub fn is_custom_kind(kind: &str) -> bool {
+    let builtin = super::AVAILABLE_KINDS_ARRAY.iter().any(|k| k == kind);
+    !builtin && load(Source::Kind, kind).is_some()
+}
+
+/// A kind with its parents and mixins composed into one template
+#[derive(Debug, Clone)]
+pub struct ComposedKind {
+    /// Template source to render
+    pub source: String,
+    /// Tool set, inherited from the parent when not set
+    pub tools: Option<ToolSet>,
+    /// Recommended models, inherited from the parent when not set
+    pub models: Vec<String>,
+}
+
+/// Compose a kind from its parents and mixins
+pub fn compose(kind: &str) -> Result<ComposedKind, String> {
+    compose_with(kind, &load, &mut Vec::new())
+}
+
+fn compose_with(
+    kind: &str,
+    load: &dyn Fn(Source, &str) -> Option<String>,
+    seen: &mut Vec<String>,
+) -> Result<ComposedKind, String> {
+    if seen.iter().any(|k| k == kind) {
+        seen.push(kind.to_string());
+        return Err(format!(
+            "Kinds extend each other in a cycle: {}",
+            seen.join(" -> ")
+        ));
+    }
+    seen.push(kind.to_string());
+
+    let template =
+        load(Source::Kind, kind).ok_or_else(|| format!("Unknown agent kind: '{kind}'"))?;
+    let front_matter = front_matter(&template).map_err(|e| format!("Kind '{kind}': {e}"))?;
+
+    let mut composed = ComposedKind {
+        source: String::new(),
+        tools: front_matter.tools,
+        models: front_matter.models,
+    };
+    if let Some(parent_name) = &front_matter.extends {
+        // Extending a kind needs the same subscription as using it
+        super::check_kind_access(parent_name).map_err(|e| e.to_string())?;
+        let parent = compose_with(parent_name, load, seen)?;
+        composed.source = format!("{}\n\n", parent.source.trim_end());
+        composed.tools = composed.tools.or(parent.tools);
+        if composed.models.is_empty() {
+            composed.models = parent.models;
+        }
+    }
+    composed.source.push_str(&template);
+
+    for name in &front_matter.mixins {
+        let mixin = load(Source::Mixin, name)
+            .ok_or_else(|| format!("Kind '{kind}': unknown mixin '{name}'"))?;
+        composed.source = format!("{}\n\n{}", composed.source.trim_end(), mixin.trim());
+    }
+    Ok(composed)
+}
+
+/// Tools enabled by default for a kind; all tools for the default kinds
+pub fn default_tools(kind: Option<&str>) -> Vec<&'static str> {
+    let tools = kind
+        .and_then(|kind| compose(kind).ok())
+        .and_then(|composed| resolve_tools(composed.tools.as_ref()).ok());
+    tools.unwrap_or_else(|| super::ALL_TOOLS.to_vec())
+}
+
+/// Description and defaults of a kind
+pub fn info(kind: &str) -> Result<KindInfo, String> {
+    let template =
+        load(Source::Kind, kind).ok_or_else(|| format!("Unknown agent kind: '{kind}'"))?;
+    let composed = compose(kind)?;
+    let tools =
+        resolve_tools(composed.tools.as_ref()).map_err(|e| format!("Kind '{kind}': {e}"))?;
+    let tool_set = match &composed.tools {
+        None => "all".to_string(),
+        Some(ToolSet::Named(name)) => name.clone(),
+        Some(ToolSet::List(names)) => names.join(", "),
+    };
+    let tier = if kind.starts_with("pro/") {
+        "pro"
+    } else if kind.starts_with("plus/") {
+        "plus"
+    } else if is_custom_kind(kind) {
+        "custom"
+    } else {
+        "standard"
+    };
+    Ok(KindInfo {
+        name: kind.to_string(),
+        tier,
+        description: description(&template),
+        tool_set,
+        tools: tools.into_iter().map(str::to_string).collect(),
+        models: composed.models,
+    })
+}
+
+/// All kinds, built-in kinds by tier and then custom kinds
+pub fn list() -> Result<Vec<KindInfo>, String> {
+    let names = super::AVAILABLE_KINDS_ARRAY
+        .iter()
+        .cloned()
+        .chain(custom_kinds());
+    let mut kinds = names
+        .map(|kind| info(&kind))
+        .collect::<Result<Vec<_>, _>>()?;
+    let rank = |tier: &str| TIERS.iter().position(|(t, _)| *t == tier);
+    kinds.sort_by_key(|kind| (rank(kind.tier), kind.name.clone()));
+    Ok(kinds)
+}
+
+/// Kinds as a table grouped by tier
+pub fn format_table(kinds: &[KindInfo]) -> String {
+    let name_width = kinds.iter().map(|k| k.name.len()).max().unwrap_or(0).max(4);
+    let tools_width = kinds
+        .iter()
+        .map(|k| k.tool_set.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    let models = |kind: &KindInfo| {
+        if kind.models.is_empty() {
+            "any".to_string()
+        } else {
+            kind.models.join(", ")
+        }
+    };
+    let models_width = kinds
+        .iter()
+        .map(|k| models(k).len())
+        .max()
+        .unwrap_or(0)
+        .max(6);
+
+    let mut output = String::new();
+    for (tier, title) in TIERS {
+        let tier_kinds: Vec<&KindInfo> = kinds.iter().filter(|k| k.tier == *tier).collect();
+        if tier_kinds.is_empty() {
+            continue;
+        }
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(&format!("{title}:\n"));
+        output.push_str(&format!(
+            "  {:name_width$}  {:tools_width$}  {:models_width$}  DESCRIPTION\n",
+            "KIND", "TOOLS", "MODELS"
+        ));
+        for kind in tier_kinds {
+            output.push_str(&format!(
+                "  {:name_width$}  {:tools_width$}  {:models_width$}  {}\n",
+                kind.name,
+                kind.tool_set,
+                models(kind),
+                kind.description
+            ));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_front_matter() {
+        let template = "{{! Researcher Agent Template - Finds things out }}\n\
+                        {{!--\ntools: readonly\nmodels: [model-a, model-b]\n--}}\n\nYou research.";
+        assert_eq!(description(template), "Finds things out");
+        let parsed = front_matter(template).unwrap();
+        assert_eq!(parsed.tools, Some(ToolSet::Named("readonly".to_string())));
+        assert_eq!(parsed.models, ["model-a", "model-b"]);
+        assert_eq!(
+            resolve_tools(parsed.tools.as_ref()).unwrap(),
+            crate::prompts::READONLY_TOOLS
+        );
+
+        let plain = "{{! Minimal Agent Template - Short }}\n{{!\n  Notes\n}}\nBe brief.";
+        assert_eq!(front_matter(plain).unwrap(), FrontMatter::default());
+
+        let list = ToolSet::List(vec!["read".to_string(), "shell".to_string()]);
+        assert_eq!(resolve_tools(Some(&list)).unwrap(), ["shell", "read"]);
+        let unknown = ToolSet::List(vec!["teleport".to_string()]);
+        assert!(resolve_tools(Some(&unknown)).is_err());
+        assert!(front_matter("{{! T - d }}\n{{!--\ncolour: red\n--}}").is_err());
+    }
+
+    #[test]
+    fn test_compose() {
+        let load = |source: Source, name: &str| -> Option<String> {
+            let template = match (source, name) {
+                (Source::Kind, "base") => "{{!--\ntools: readonly\nmodels: [m]\n--}}\nBase.",
+                (Source::Kind, "ours") => "{{!--\nextends: base\nmixins: [rules]\n--}}\nOurs.",
+                (Source::Kind, "loop") => "{{!--\nextends: loop\n--}}\n",
+                (Source::Mixin, "rules") => "Rules.\n",
+                _ => return None,
+            };
+            Some(template.to_string())
+        };
+
+        let composed = compose_with("ours", &load, &mut Vec::new()).unwrap();
+        assert!(composed.source.starts_with("{{!--\ntools: readonly"));
+        assert!(composed.source.ends_with("Ours.\n\nRules."));
+        assert!(composed.source.find("Base.").unwrap() < composed.source.find("Ours.").unwrap());
+        assert_eq!(composed.tools, Some(ToolSet::Named("readonly".to_string())));
+        assert_eq!(composed.models, ["m"]);
+
+        assert!(compose_with("loop", &load, &mut Vec::new())
+            .unwrap_err()
+            .contains("cycle"));
+        assert!(compose_with("missing", &load, &mut Vec::new()).is_err());
+    }
+}
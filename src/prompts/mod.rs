@@ -6,6 +6,8 @@
 
 pub mod grammar;
 pub mod handlebars;
+pub mod kinds;
+pub mod stats;
 
 // Protected prompts module for encrypted templates
 pub mod protected;
@@ -21,20 +23,43 @@ include!(concat!(env!("OUT_DIR"), "/encrypted_prompts.rs"));
 /// List of all available tools
 pub const ALL_TOOLS: &[&str] = &[
     "shell",
+    "shell-session",
     "read",
     "write",
     "patch",
+    "json",
+    "config-edit",
     "fetch",
     "search",
+    "browser",
     #[cfg(target_os = "macos")]
     "screenshot",
     #[cfg(target_os = "macos")]
     "screendump",
     #[cfg(target_os = "macos")]
     "input",
+    #[cfg(target_os = "macos")]
+    "record",
+    #[cfg(target_os = "macos")]
+    "computer",
+    "visual-diff",
     "task",
     "done",
     "wait",
+    "remind",
+    "scratchpad",
+    "plan",
+    "test",
+    "check",
+    "semsearch",
+    "kb",
+    "runbook",
+    "logs",
+    "sys",
+    "definition",
+    "references",
+    "hover",
+    "rename",
 ];
 
 /// List of tools available to Plus/Pro users only
@@ -46,10 +71,26 @@ pub const READONLY_TOOLS: &[&str] = &[
     "read",
     "fetch",
     "search",
+    "browser",
     "screenshot",
     "screendump",
+    "record",
+    "visual-diff",
     "done",
     "wait",
+    "remind",
+    "scratchpad",
+    "plan",
+    "test",
+    "check",
+    "semsearch",
+    "kb",
+    "runbook",
+    "logs",
+    "sys",
+    "definition",
+    "references",
+    "hover",
     // Note: 'input' is not included as it modifies application state
 ];
 
@@ -62,6 +103,7 @@ pub fn is_valid_kind(kind_name: &str) -> bool {
         .iter()
         .position(|it| it == &kind_name)
         .is_some()
+        || kinds::is_custom_kind(kind_name)
 }
 
 /// Render a template with specific tools enabled
@@ -80,12 +122,50 @@ pub fn render_template(
     enabled_tools: &[&str],
     grammar: Arc<dyn Grammar>,
 ) -> anyhow::Result<String> {
+    render_template_with_mcp(template_name, enabled_tools, grammar, true)
+}
+
+/// Render a template, optionally leaving out the MCP tools section
+pub fn render_template_with_mcp(
+    template_name: &str,
+    enabled_tools: &[&str],
+    grammar: Arc<dyn Grammar>,
+    include_mcp: bool,
+) -> anyhow::Result<String> {
+    render_prompt(template_name, enabled_tools, grammar, include_mcp).map(|prompt| prompt.text)
+}
+
+/// A rendered system prompt
+#[derive(Debug, Clone)]
+pub struct SystemPrompt {
+    /// Prompt text
+    pub text: String,
+    /// Extra stop sequences registered by the template with `{{stop_sequence}}`
+    pub stop_sequences: Vec<String>,
+}
+
+/// Render a template along with the stop sequences it registers
+fn render_prompt(
+    template_name: &str,
+    enabled_tools: &[&str],
+    grammar: Arc<dyn Grammar>,
+    include_mcp: bool,
+) -> anyhow::Result<SystemPrompt> {
     // Create a template manager
     let mut template_manager = TemplateManager::new(grammar);
 
     // Load all templates to ensure partials are available
     match template_manager.load_all_templates() {
         Ok(_) => {
+            // Kinds are rendered with their parents and mixins
+            if let Some(kind) = template_name.strip_prefix("kind/") {
+                let composed = kinds::compose(kind).map_err(anyhow::Error::msg)?;
+                template_manager
+                    .handlebars
+                    .register_template_string(template_name, &composed.source)
+                    .map_err(|e| anyhow::anyhow!("Invalid template {}: {}", template_name, e))?;
+            }
+
             // Create a data object for template variables
             let mut data = serde_json::Map::new();
 
@@ -101,7 +181,12 @@ pub fn render_template(
 
             // Add MCP tools information to the template data
             let mut data_value = serde_json::Value::Object(data);
-            crate::mcp::add_mcp_tools_to_prompt(&mut data_value);
+            if include_mcp {
+                let hidden_mcp_tools = crate::mcp::add_mcp_tools_to_prompt(&mut data_value);
+                if hidden_mcp_tools > 0 {
+                    bprintln!(debug: "{} MCP tools hidden from the prompt by tool filters", hidden_mcp_tools);
+                }
+            }
 
             // Render the template with the variables
             match template_manager
@@ -110,7 +195,10 @@ pub fn render_template(
             {
                 Ok(rendered) => {
                     // bprintln!(dev: "{}", rendered);
-                    Ok(rendered)
+                    Ok(SystemPrompt {
+                        text: rendered,
+                        stop_sequences: template_manager.registered_stop_sequences(),
+                    })
                 }
                 Err(_) => {
                     bail!("Error generating system prompt: {}", template_name);
@@ -140,7 +228,7 @@ pub fn generate_system_prompt(
     kind_name: Option<&str>,
     grammar: Arc<dyn Grammar>,
     disabled_tools: Option<&[String]>,
-) -> Result<String, anyhow::Error> {
+) -> Result<SystemPrompt, anyhow::Error> {
     // Determine which template to use
     let requested_kind = if let Some(name) = kind_name {
         // If a specific template is provided, validate it
@@ -204,6 +292,23 @@ pub fn generate_system_prompt(
         if !has_plus && premium_tools.contains(tool) {
             continue;
         }
+        // Language server tools are only offered when a server is configured
+        if !crate::lsp::is_enabled() && crate::lsp::TOOLS.contains(tool) {
+            continue;
+        }
+        // Semantic search needs an embedding provider
+        if *tool == "semsearch" && !crate::index::is_available() {
+            continue;
+        }
+        // The knowledge base tool needs a knowledge base to search
+        if *tool == "kb" && (crate::index::kb::names().is_empty() || !crate::index::is_available())
+        {
+            continue;
+        }
+        // The runbook tool needs runbooks to read
+        if *tool == "runbook" && !crate::tools::runbook::available() {
+            continue;
+        }
         combined_tools.push(*tool);
     }
 
@@ -246,7 +351,7 @@ pub fn generate_system_prompt(
     }
 
     // Render the template with the complete tool list
-    match render_template(&kind, &combined_tools, grammar) {
+    match render_prompt(&kind, &combined_tools, grammar, true) {
         Ok(prompt) => Ok(prompt),
         Err(e) => {
             bail!("Failed to render template '{}': {}", kind, e);
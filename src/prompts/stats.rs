@@ -0,0 +1,173 @@
+//! Token budget report for rendered system prompts
+//!
+//! `dump-prompts <template> --stats` breaks the system prompt down into the
+//! kind body, the documentation of each tool, the MCP section and additions
+//! from user settings (plan and propose mode). Sections are measured by
+//! rendering the template with and without them, so the numbers stay accurate
+//! whatever the template layout.
+
+use super::{render_template_with_mcp, Grammar};
+use std::sync::Arc;
+
+/// A measured part of the system prompt
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptSection {
+    pub name: String,
+    pub tokens: usize,
+}
+
+/// Token breakdown of a rendered system prompt
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptStats {
+    pub template: String,
+    pub total_tokens: usize,
+    pub sections: Vec<PromptSection>,
+}
+
+/// Rough token estimate (about four bytes per token for English text and code)
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// Measure the sections of a template rendered with the given tools
+///
+/// `additions` are texts appended to the rendered prompt, such as the plan
+/// mode instructions, given as (name, text) pairs.
+pub fn prompt_stats(
+    template_name: &str,
+    enabled_tools: &[&str],
+    grammar: Arc<dyn Grammar>,
+    additions: &[(&str, &str)],
+) -> anyhow::Result<PromptStats> {
+    let render = |tools: &[&str], include_mcp: bool| {
+        render_template_with_mcp(template_name, tools, grammar.clone(), include_mcp)
+            .map(|text| estimate_tokens(&text))
+    };
+
+    let with_mcp = render(enabled_tools, true)?;
+    let with_tools = render(enabled_tools, false)?;
+    let body = render(&[], false)?;
+
+    let mut sections = vec![PromptSection {
+        name: "kind body".to_string(),
+        tokens: body,
+    }];
+
+    // Each tool's docs are what disappears when only that tool is left out
+    let mut per_tool_total = 0;
+    for tool in enabled_tools {
+        let without: Vec<&str> = enabled_tools
+            .iter()
+            .copied()
+            .filter(|t| t != tool)
+            .collect();
+        let tokens = with_tools.saturating_sub(render(&without, false)?);
+        per_tool_total += tokens;
+        sections.push(PromptSection {
+            name: format!("tool: {tool}"),
+            tokens,
+        });
+    }
+
+    // Text rendered only when some tools are enabled (general tool formatting)
+    let shared = with_tools.saturating_sub(body + per_tool_total);
+    if shared > 0 {
+        sections.push(PromptSection {
+            name: "shared tool docs".to_string(),
+            tokens: shared,
+        });
+    }
+
+    sections.push(PromptSection {
+        name: "MCP tools".to_string(),
+        tokens: with_mcp.saturating_sub(with_tools),
+    });
+
+    for (name, text) in additions {
+        sections.push(PromptSection {
+            name: format!("user setting: {name}"),
+            tokens: estimate_tokens(text),
+        });
+    }
+
+    let addition_tokens: usize = additions
+        .iter()
+        .map(|(_, text)| estimate_tokens(text))
+        .sum();
+    let total_tokens = with_mcp + addition_tokens;
+    Ok(PromptStats {
+        template: template_name.to_string(),
+        total_tokens,
+        sections,
+    })
+}
+
+/// Format the stats as a table, largest sections first
+pub fn format_stats(stats: &PromptStats) -> String {
+    let mut sections = stats.sections.clone();
+    sections.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.name.cmp(&b.name)));
+
+    let width = sections
+        .iter()
+        .map(|section| section.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("total".len());
+
+    let mut output = format!("Prompt size for {} (estimated tokens)\n", stats.template);
+    for section in &sections {
+        let share = if stats.total_tokens > 0 {
+            section.tokens as f64 * 100.0 / stats.total_tokens as f64
+        } else {
+            0.0
+        };
+        output.push_str(&format!(
+            "  {:<width$}  {:>7}  {:>5.1}%\n",
+            section.name, section.tokens, share
+        ));
+    }
+    output.push_str(&format!(
+        "  {:<width$}  {:>7}\n",
+        "total", stats.total_tokens
+    ));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+
+    #[test]
+    fn test_format_stats_sorts_sections() {
+        let stats = PromptStats {
+            template: "kind/programmer".to_string(),
+            total_tokens: 200,
+            sections: vec![
+                PromptSection {
+                    name: "kind body".to_string(),
+                    tokens: 50,
+                },
+                PromptSection {
+                    name: "tool: shell".to_string(),
+                    tokens: 150,
+                },
+            ],
+        };
+        let report = format_stats(&stats);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(
+            lines[0],
+            "Prompt size for kind/programmer (estimated tokens)"
+        );
+        assert!(lines[1].contains("tool: shell") && lines[1].contains("75.0%"));
+        assert!(lines[2].contains("kind body") && lines[2].contains("25.0%"));
+        assert!(lines[3].trim_start().starts_with("total"));
+    }
+}
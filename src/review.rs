@@ -0,0 +1,389 @@
+//! Code review of a git diff range
+//!
+//! `termineer review --base main` reviews the changes of the current branch
+//! since it left the base (`git diff main...HEAD`). The diff is split per file
+//! into chunks that fit a token budget, and each chunk is reviewed by its own
+//! read-only agent of the `code_reviewer` kind, up to `--parallel` at a time.
+//! Their findings are merged into one Markdown report, most severe first,
+//! which `--post` adds as a review comment to the branch's pull request with
+//! the GitHub CLI (`gh`).
+
+use crate::agent;
+use crate::config::Config;
+use crate::git::git;
+use crate::output::SharedBuffer;
+use crate::prompts::stats::estimate_tokens;
+use futures::StreamExt;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Seconds a chunk may be reviewed when no --timeout is given
+const DEFAULT_TIMEOUT: u64 = 300;
+
+/// Severity of a finding, most severe first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Critical,
+    High,
+    Medium,
+    Low,
+}
+
+impl Severity {
+    const ALL: [Severity; 4] = [
+        Severity::Critical,
+        Severity::High,
+        Severity::Medium,
+        Severity::Low,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Severity::Critical => "critical",
+            Severity::High => "high",
+            Severity::Medium => "medium",
+            Severity::Low => "low",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Severity::Critical => "Critical",
+            Severity::High => "High",
+            Severity::Medium => "Medium",
+            Severity::Low => "Low",
+        }
+    }
+}
+
+/// A problem found by a reviewer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub file: String,
+    /// Line in the new version of the file; None for the whole file
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+/// Files of the diff reviewed by one agent
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub files: Vec<String>,
+    pub diff: String,
+}
+
+/// Split a diff into the diffs of its files, with their paths
+pub fn split_files(diff: &str) -> Vec<(String, String)> {
+    let mut files: Vec<(String, String)> = Vec::new();
+    for line in diff.split_inclusive('\n') {
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            // `a/old b/new`; the new path is the one reviewed
+            let path = header
+                .trim_end()
+                .rsplit_once(" b/")
+                .map_or(header.trim_end(), |(_, path)| path);
+            files.push((path.to_string(), String::new()));
+        }
+        if let Some((_, file_diff)) = files.last_mut() {
+            file_diff.push_str(line);
+        }
+    }
+    files
+}
+
+/// Group the diffs of files into chunks of about `budget` tokens; the diff of
+/// a file over the budget is split between its hunks
+pub fn chunk_files(files: Vec<(String, String)>, budget: usize) -> Vec<Chunk> {
+    let mut chunks: Vec<Chunk> = Vec::new();
+    let mut current = Chunk {
+        files: Vec::new(),
+        diff: String::new(),
+    };
+    let flush = |current: &mut Chunk, chunks: &mut Vec<Chunk>| {
+        if !current.files.is_empty() {
+            chunks.push(std::mem::replace(
+                current,
+                Chunk {
+                    files: Vec::new(),
+                    diff: String::new(),
+                },
+            ));
+        }
+    };
+
+    for (path, diff) in files {
+        if estimate_tokens(&current.diff) + estimate_tokens(&diff) > budget {
+            flush(&mut current, &mut chunks);
+        }
+        if estimate_tokens(&diff) <= budget {
+            current.files.push(path);
+            current.diff.push_str(&diff);
+            continue;
+        }
+
+        // Each part repeats the file header, so reviewers know the file
+        let (header, hunks) = diff.split_at(diff.find("\n@@").map_or(diff.len(), |i| i + 1));
+        let mut part = String::new();
+        for hunk in split_hunks(hunks) {
+            let tokens = estimate_tokens(header) + estimate_tokens(&part) + estimate_tokens(hunk);
+            if !part.is_empty() && tokens > budget {
+                chunks.push(Chunk {
+                    files: vec![path.clone()],
+                    diff: format!("{header}{part}"),
+                });
+                part.clear();
+            }
+            part.push_str(hunk);
+        }
+        chunks.push(Chunk {
+            files: vec![path],
+            diff: format!("{header}{part}"),
+        });
+    }
+    flush(&mut current, &mut chunks);
+    chunks
+}
+
+/// Hunks of a file's diff, each starting with its `@@` line
+fn split_hunks(hunks: &str) -> Vec<&str> {
+    let mut starts: Vec<usize> = hunks.match_indices("\n@@").map(|(i, _)| i + 1).collect();
+    starts.insert(0, 0);
+    starts.push(hunks.len());
+    starts.windows(2).map(|w| &hunks[w[0]..w[1]]).collect()
+}
+
+/// Parse the findings of a reviewer's answer, one per line:
+/// `[severity] file:line - message` or `[severity] file - message`
+pub fn parse_findings(answer: &str) -> Vec<Finding> {
+    answer
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim().trim_start_matches(['-', '*']).trim();
+            let (severity, rest) = line.strip_prefix('[')?.split_once(']')?;
+            let severity = Severity::ALL
+                .into_iter()
+                .find(|s| s.label().eq_ignore_ascii_case(severity.trim()))?;
+            let (location, message) = rest.trim().split_once(" - ")?;
+            let location = location.trim().trim_matches('`');
+            let (file, line) = match location.rsplit_once(':') {
+                Some((file, line)) if line.parse::<u32>().is_ok() => {
+                    (file, line.parse::<u32>().ok())
+                }
+                _ => (location, None),
+            };
+            Some(Finding {
+                severity,
+                file: file.to_string(),
+                line,
+                message: message.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Markdown report of the findings, most severe first
+pub fn format_report(range: &str, findings: &mut [Finding], failures: &[String]) -> String {
+    findings.sort_by(|a, b| (a.severity, &a.file, a.line).cmp(&(b.severity, &b.file, b.line)));
+
+    let mut report = format!("## Review of {range}\n\n");
+    if findings.is_empty() {
+        report.push_str("No findings.\n");
+    }
+    for severity in Severity::ALL {
+        let mut findings = findings
+            .iter()
+            .filter(|f| f.severity == severity)
+            .peekable();
+        if findings.peek().is_none() {
+            continue;
+        }
+        report.push_str(&format!("### {}\n\n", severity.title()));
+        for finding in findings {
+            let location = match finding.line {
+                Some(line) => format!("{}:{line}", finding.file),
+                None => finding.file.clone(),
+            };
+            report.push_str(&format!("- `{location}` {}\n", finding.message));
+        }
+        report.push('\n');
+    }
+    if !failures.is_empty() {
+        report.push_str("### Not reviewed\n\n");
+        for failure in failures {
+            report.push_str(&format!("- {failure}\n"));
+        }
+    }
+    report.trim_end().to_string() + "\n"
+}
+
+/// Review the changes of the current branch since `base`
+pub async fn run(
+    mut config: Config,
+    base: &str,
+    budget: usize,
+    parallel: usize,
+) -> Result<String, String> {
+    let range = format!("{base}...HEAD");
+    let diff = git(&["diff", "--no-color", &range]).await?;
+    let chunks = chunk_files(split_files(&diff), budget);
+    if chunks.is_empty() {
+        return Ok(format_report(&range, &mut [], &[]));
+    }
+
+    config.kind = Some("code_reviewer".to_string());
+    config.readonly = true;
+    let timeout = config.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT);
+
+    let total = chunks.len();
+    let mut reviews = futures::stream::iter(chunks.into_iter().enumerate())
+        .map(|(index, chunk)| {
+            let config = &config;
+            let range = &range;
+            async move {
+                let query = format!(
+                    "Review this part of the changes in {range} ({}).\n\n```diff\n{}```",
+                    chunk.files.join(", "),
+                    chunk.diff
+                );
+                let name = format!("review-{}", index + 1);
+                let outcome = match agent::create_agent_with_buffer(
+                    name,
+                    config.clone(),
+                    SharedBuffer::new(200),
+                ) {
+                    Ok(agent_id) => {
+                        let outcome =
+                            agent::run_agent_to_completion(agent_id, query, Some(timeout))
+                                .await
+                                .map_err(|e| e.to_string());
+                        let _ = agent::terminate_agent(agent_id).await;
+                        outcome
+                    }
+                    Err(e) => Err(e.to_string()),
+                };
+                (chunk.files, outcome)
+            }
+        })
+        .buffer_unordered(parallel.max(1));
+
+    let mut findings = Vec::new();
+    let mut failures = Vec::new();
+    let mut finished = 0;
+    while let Some((files, outcome)) = reviews.next().await {
+        finished += 1;
+        match outcome {
+            Ok(answer) => {
+                let found = parse_findings(&answer);
+                eprintln!(
+                    "[{finished}/{total}] ✓ {} ({} findings)",
+                    files.join(", "),
+                    found.len()
+                );
+                findings.extend(found);
+            }
+            Err(error) => {
+                eprintln!("[{finished}/{total}] ✗ {}: {error}", files.join(", "));
+                failures.push(format!("{}: {error}", files.join(", ")));
+            }
+        }
+    }
+
+    Ok(format_report(&range, &mut findings, &failures))
+}
+
+/// Add the report as a review comment to a pull request, by default the one
+/// of the current branch, with the GitHub CLI
+pub async fn post(report: &str, pull_request: Option<&str>) -> Result<(), String> {
+    let mut command = Command::new("gh");
+    command.args(["pr", "review"]);
+    if let Some(pull_request) = pull_request.filter(|pr| !pr.is_empty()) {
+        command.arg(pull_request);
+    }
+    let mut child = command
+        .args(["--comment", "--body-file", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run the GitHub CLI (gh): {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(report.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to send the report to gh: {e}"))?;
+    }
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("Failed to run the GitHub CLI (gh): {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "gh pr review failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/src/a.rs b/src/a.rs\n\
+                        --- a/src/a.rs\n\
+                        +++ b/src/a.rs\n\
+                        @@ -1,2 +1,2 @@\n\
+                        -old\n\
+                        +new\n\
+                        @@ -10,2 +10,2 @@\n\
+                        -old\n\
+                        +new\n\
+                        diff --git a/b.md b/b.md\n\
+                        @@ -1 +1 @@\n\
+                        +text\n";
+
+    #[test]
+    fn test_chunk_files() {
+        let files = split_files(DIFF);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].0, "src/a.rs");
+        assert_eq!(files[1].0, "b.md");
+
+        // Both files fit one chunk
+        let chunks = chunk_files(files.clone(), 1000);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].diff, DIFF);
+
+        // The first file is split between its hunks, each part with its header
+        let chunks = chunk_files(files, 20);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].files, vec!["src/a.rs"]);
+        assert!(chunks[1].diff.starts_with("diff --git a/src/a.rs"));
+        assert!(chunks[1].diff.contains("@@ -10,2"));
+        assert!(!chunks[1].diff.contains("@@ -1,2"));
+        assert_eq!(chunks[2].files, vec!["b.md"]);
+    }
+
+    #[test]
+    fn test_report() {
+        let mut findings = parse_findings(
+            "[low] src/a.rs - no test\n\
+             [critical] `src/b.rs:7` - key committed\n\
+             Some commentary\n\
+             - [high] src/a.rs:3 - off by one\n",
+        );
+        assert_eq!(findings.len(), 3);
+        assert_eq!(findings[1].line, Some(7));
+
+        let report = format_report("main...HEAD", &mut findings, &[]);
+        assert_eq!(
+            report,
+            "## Review of main...HEAD\n\n\
+             ### Critical\n\n- `src/b.rs:7` key committed\n\n\
+             ### High\n\n- `src/a.rs:3` off by one\n\n\
+             ### Low\n\n- `src/a.rs` no test\n"
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Shared sessions: other participants attaching to a running session
+//!
+//! With `--share`, the interactive session listens on a Unix socket in
+//! `.termineer/session.sock`. `termineer attach --name NAME` connects to it
+//! from another terminal (or over ssh), streams the main agent's output and
+//! sends each line typed as a message to the agent. While a session is shared,
+//! every user message is attributed by name in the conversation and the
+//! output, so the agent and the participants know who asked for what.
+//!
+//! Only the user running the session may connect: the socket is readable and
+//! writable by its owner alone. Participants still choose their own names, so
+//! those are marked as unverified in the conversation.
+
+use crate::agent::{AgentId, AgentMessage};
+use crate::output::SharedBuffer;
+use lazy_static::lazy_static;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::sync::RwLock;
+#[cfg(unix)]
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Socket of the shared session, relative to the workspace
+pub const SOCKET_PATH: &str = ".termineer/session.sock";
+
+/// Output lines sent to a participant when attaching
+#[cfg(unix)]
+const BACKLOG_LINES: usize = 50;
+
+/// Longest participant name kept
+#[cfg(unix)]
+const MAX_NAME_CHARS: usize = 32;
+
+#[cfg(not(unix))]
+const UNSUPPORTED: &str = "Shared sessions need Unix domain sockets, which this platform lacks";
+
+lazy_static! {
+    /// Name of the local user while the session is shared
+    static ref HOST_NAME: RwLock<Option<String>> = RwLock::new(None);
+}
+
+/// Name of the local user, if the session is shared
+pub fn host_name() -> Option<String> {
+    HOST_NAME.read().unwrap().clone()
+}
+
+/// Name used when none is given: the login name
+pub fn default_name() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "user".to_string())
+}
+
+/// User message text with its author, as added to the conversation
+pub fn attributed(author: &str, content: &str) -> String {
+    format!("[{author}] {content}")
+}
+
+/// Message for an agent from the local user, attributed while the session is shared
+pub fn user_message(content: String) -> AgentMessage {
+    match host_name() {
+        Some(author) => AgentMessage::ParticipantInput { author, content },
+        None => AgentMessage::UserInput(content),
+    }
+}
+
+fn socket_path() -> Result<PathBuf, String> {
+    std::env::current_dir()
+        .map(|dir| dir.join(SOCKET_PATH))
+        .map_err(|e| format!("Failed to read the current directory: {e}"))
+}
+
+/// Accept participants for the agent writing to `buffer`
+#[cfg(unix)]
+pub async fn serve(host: String, agent_id: AgentId, buffer: SharedBuffer) -> Result<(), String> {
+    let path = socket_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    // A socket left behind by a session that crashed
+    if tokio::net::UnixStream::connect(&path).await.is_err() {
+        let _ = std::fs::remove_file(&path);
+    }
+    let listener = tokio::net::UnixListener::bind(&path)
+        .map_err(|e| format!("Failed to share the session on {}: {e}", path.display()))?;
+    // Other local users must not be able to send messages to the agent
+    if let Err(e) = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)) {
+        let _ = std::fs::remove_file(&path);
+        return Err(format!(
+            "Failed to restrict access to {}: {e}",
+            path.display()
+        ));
+    }
+    *HOST_NAME.write().unwrap() = Some(host);
+
+    crate::output::spawn_with_buffer(buffer.clone(), async move {
+        bprintln!(info: "Session shared: others can join with `termineer attach --name NAME`");
+        while let Ok((stream, _)) = listener.accept().await {
+            crate::output::spawn(serve_participant(stream, agent_id, buffer.clone()));
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn serve(_host: String, _agent_id: AgentId, _buffer: SharedBuffer) -> Result<(), String> {
+    Err(UNSUPPORTED.to_string())
+}
+
+/// Remove the socket when the shared session ends
+pub fn stop() {
+    if HOST_NAME.write().unwrap().take().is_some() {
+        if let Ok(path) = socket_path() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Forward output to a participant and their lines to the agent
+#[cfg(unix)]
+async fn serve_participant(
+    stream: tokio::net::UnixStream,
+    agent_id: AgentId,
+    buffer: SharedBuffer,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    // The first line is the name the participant chose
+    let name = match lines.next_line().await {
+        Ok(Some(name)) => match participant_author(&name) {
+            Some(name) => name,
+            None => return,
+        },
+        _ => return,
+    };
+    bprintln!(info: "👥 {} joined the session", name);
+
+    let output = crate::output::spawn(async move {
+        let mut reader = buffer.reader(BACKLOG_LINES);
+        loop {
+            for line in reader.next_lines().await {
+                let line = format!("{}\n", line.content);
+                if writer.write_all(line.as_bytes()).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message = AgentMessage::ParticipantInput {
+            author: name.clone(),
+            content: line,
+        };
+        if let Err(e) = crate::agent::send_message(agent_id, message) {
+            bprintln!(error: "Failed to deliver the message of {}: {}", name, e);
+        }
+    }
+    output.abort();
+    bprintln!(info: "👥 {} left the session", name);
+}
+
+/// Author of a participant's messages: their chosen name, marked as unverified
+///
+/// Brackets and control characters are dropped so the name cannot close the
+/// attribution early or pose as another line.
+#[cfg(unix)]
+fn participant_author(name: &str) -> Option<String> {
+    let name: String = name
+        .chars()
+        .filter(|c| !c.is_control() && !matches!(c, '[' | ']'))
+        .take(MAX_NAME_CHARS)
+        .collect();
+    let name = name.trim();
+    (!name.is_empty()).then(|| format!("{name} (unverified)"))
+}
+
+/// Join a shared session of the current directory from this terminal
+#[cfg(unix)]
+pub async fn attach(name: &str) -> Result<(), String> {
+    let path = socket_path()?;
+    let stream = tokio::net::UnixStream::connect(&path).await.map_err(|e| {
+        format!("No shared session in this directory ({e}); start one with `termineer --share`")
+    })?;
+    let (reader, mut writer) = stream.into_split();
+    writer
+        .write_all(format!("{name}\n").as_bytes())
+        .await
+        .map_err(|e| format!("Failed to join the session: {e}"))?;
+    println!("Joined the session as {name}. Type a message and press Enter; Ctrl+D leaves.");
+
+    let mut output = tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            println!("{line}");
+        }
+        println!("The session ended.");
+    });
+
+    let mut input = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        tokio::select! {
+            line = input.next_line() => match line {
+                Ok(Some(line)) => {
+                    if writer.write_all(format!("{line}\n").as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+                _ => break,
+            },
+            _ = &mut output => break,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn attach(_name: &str) -> Result<(), String> {
+    Err(UNSUPPORTED.to_string())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_participant_author() {
+        assert_eq!(
+            participant_author(" alice "),
+            Some("alice (unverified)".to_string())
+        );
+        assert_eq!(
+            participant_author("bob] [carol"),
+            Some("bob carol (unverified)".to_string())
+        );
+        assert_eq!(
+            participant_author("\u{1b}[2J"),
+            Some("2J (unverified)".to_string())
+        );
+        assert_eq!(participant_author(" [] "), None);
+    }
+}
@@ -0,0 +1,207 @@
+//! Conversation starters of a project
+//!
+//! A starter is a canned opening prompt for a task the project does often,
+//! such as triaging flaky tests, in `.termineer/starters/NAME.md`. The file may
+//! start with front matter overriding the agent's settings:
+//!
+//! ```markdown
+//! ---
+//! description: Find and fix flaky tests
+//! kind: debugger
+//! model: claude-3-7-sonnet-20250219
+//! ---
+//! Run the test suite three times and list the tests whose results differ...
+//! ```
+//!
+//! Starters are picked in the TUI with `/start NAME` or on the command line
+//! with `--starter NAME`. Without a description, the first line of the prompt
+//! describes the starter.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Directory of the project's starters
+pub const STARTERS_DIR: &str = ".termineer/starters";
+
+/// Longest description shown in listings
+const MAX_DESCRIPTION: usize = 60;
+
+/// A canned opening prompt with optional agent settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct Starter {
+    pub name: String,
+    pub description: String,
+    /// Kind of the agent running the starter
+    pub kind: Option<String>,
+    /// Model of the agent running the starter
+    pub model: Option<String>,
+    pub prompt: String,
+}
+
+/// Front matter of a starter file
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FrontMatter {
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    kind: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+}
+
+impl Starter {
+    /// Parse the content of a starter file
+    pub fn parse(name: &str, content: &str) -> Result<Starter, String> {
+        let (front_matter, prompt) = split_front_matter(content);
+        let front_matter: FrontMatter = match front_matter {
+            Some(yaml) if !yaml.trim().is_empty() => serde_yaml::from_str(yaml)
+                .map_err(|e| format!("Invalid front matter in starter '{name}': {e}"))?,
+            _ => FrontMatter::default(),
+        };
+
+        let prompt = prompt.trim().to_string();
+        if prompt.is_empty() {
+            return Err(format!("Starter '{name}' has no prompt"));
+        }
+        let description = front_matter.description.unwrap_or_else(|| {
+            let line = prompt.lines().next().unwrap_or_default();
+            let line = line.trim_start_matches('#').trim();
+            match line.char_indices().nth(MAX_DESCRIPTION) {
+                Some((end, _)) => format!("{}...", &line[..end]),
+                None => line.to_string(),
+            }
+        });
+
+        Ok(Starter {
+            name: name.to_string(),
+            description,
+            kind: front_matter.kind,
+            model: front_matter.model,
+            prompt,
+        })
+    }
+
+    /// The prompt, followed by details given when the starter is picked
+    pub fn prompt_with(&self, details: &str) -> String {
+        if details.trim().is_empty() {
+            self.prompt.clone()
+        } else {
+            format!("{}\n\n{}", self.prompt, details.trim())
+        }
+    }
+}
+
+/// Front matter between `---` lines at the start of a file, and the rest
+fn split_front_matter(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, content)
+}
+
+fn path_of(name: &str) -> PathBuf {
+    Path::new(STARTERS_DIR).join(format!("{name}.md"))
+}
+
+/// Load a starter of the project by name
+pub fn load(name: &str) -> Result<Starter, String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    let content = valid
+        .then(|| std::fs::read_to_string(path_of(name)).ok())
+        .flatten();
+    let Some(content) = content else {
+        let names: Vec<String> = list().into_iter().map(|starter| starter.name).collect();
+        return Err(if names.is_empty() {
+            format!("Unknown starter '{name}': there are no starters in {STARTERS_DIR}")
+        } else {
+            format!(
+                "Unknown starter '{name}'. Available starters: {}",
+                names.join(", ")
+            )
+        });
+    };
+    Starter::parse(name, &content)
+}
+
+/// The project's starters, sorted by name; invalid files are left out
+pub fn list() -> Vec<Starter> {
+    let Ok(entries) = std::fs::read_dir(STARTERS_DIR) else {
+        return Vec::new();
+    };
+    let mut starters: Vec<Starter> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            let content = std::fs::read_to_string(&path).ok()?;
+            Starter::parse(&name, &content).ok()
+        })
+        .collect();
+    starters.sort_by(|a, b| a.name.cmp(&b.name));
+    starters
+}
+
+/// Listing of the starters for the TUI
+pub fn format_list(starters: &[Starter]) -> String {
+    if starters.is_empty() {
+        return format!(
+            "No starters in {STARTERS_DIR}.\n\
+             Add NAME.md files with an opening prompt to pick them with /start NAME."
+        );
+    }
+    let width = starters.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    let mut text = String::new();
+    for starter in starters {
+        text.push_str(&format!("{:width$}  {}", starter.name, starter.description));
+        let overrides: Vec<String> = [("kind", &starter.kind), ("model", &starter.model)]
+            .into_iter()
+            .filter_map(|(key, value)| value.as_ref().map(|value| format!("{key}: {value}")))
+            .collect();
+        if !overrides.is_empty() {
+            text.push_str(&format!(" ({})", overrides.join(", ")));
+        }
+        text.push('\n');
+    }
+    text.push_str("\nUse /start NAME [DETAILS] to open a conversation with a starter.");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let content = "---\ndescription: Triage flaky tests\nkind: debugger\n---\n\nRun the tests three times.\n";
+        let starter = Starter::parse("flaky", content).unwrap();
+        assert_eq!(starter.description, "Triage flaky tests");
+        assert_eq!(starter.kind.as_deref(), Some("debugger"));
+        assert_eq!(starter.model, None);
+        assert_eq!(starter.prompt, "Run the tests three times.");
+        assert_eq!(
+            starter.prompt_with("Only the api crate"),
+            "Run the tests three times.\n\nOnly the api crate"
+        );
+
+        let plain = Starter::parse("review", "# Review the open changes\nBe thorough.").unwrap();
+        assert_eq!(plain.description, "Review the open changes");
+        assert_eq!(plain.kind, None);
+
+        assert!(Starter::parse("empty", "---\nkind: basic\n---\n").is_err());
+        assert!(Starter::parse("bad", "---\ncolor: red\n---\nPrompt").is_err());
+    }
+}
@@ -0,0 +1,364 @@
+//! Structured output: answers of a single query as JSON matching a schema
+//!
+//! With `--schema FILE`, the query is sent with the JSON Schema the answer must
+//! match. The agent works on the query as usual; its final answer is then
+//! parsed and validated. An answer that does not match is sent back to the
+//! model with the validation errors, in the provider's JSON mode where it has
+//! one, until it matches or the attempts are used up. The valid JSON is the
+//! only output on stdout.
+//!
+//! Validation supports the commonly used part of JSON Schema: `type`, `enum`,
+//! `const`, `properties`, `required`, `additionalProperties`, `items`,
+//! `minItems`/`maxItems`, `minLength`/`maxLength`, `pattern`, numeric bounds,
+//! `allOf`/`anyOf`/`oneOf`/`not` and local `$ref`s.
+
+use crate::config::Config;
+use crate::llm::sampling::SamplingTask;
+use crate::llm::{Message, MessageInfo};
+use serde_json::Value;
+use std::path::Path;
+
+/// Corrections asked for before giving up on an answer
+const MAX_CORRECTIONS: usize = 3;
+
+/// Load a JSON Schema from a file
+pub fn load(path: &Path) -> Result<Value, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read schema {}: {e}", path.display()))?;
+    let schema: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid JSON in schema {}: {e}", path.display()))?;
+    if !schema.is_object() && !schema.is_boolean() {
+        return Err(format!("Schema {} is not a JSON object", path.display()));
+    }
+    Ok(schema)
+}
+
+/// Instructions appended to the query
+pub fn instructions(schema: &Value) -> String {
+    let schema = serde_json::to_string_pretty(schema).unwrap_or_default();
+    format!(
+        "Your final answer must be a single JSON value matching this JSON Schema, \
+         without any other text or explanation:\n```json\n{schema}\n```"
+    )
+}
+
+/// JSON value of an answer, which may be wrapped in a code block or text
+pub fn extract(answer: &str) -> Result<Value, String> {
+    let mut text = answer.trim();
+    if let Some(start) = text.find("```") {
+        let block = &text[start + 3..];
+        // Skip the language of the fence
+        let block = block.split_once('\n').map_or("", |(_, rest)| rest);
+        text = block.split("```").next().unwrap_or(block).trim();
+    }
+    if let Ok(value) = serde_json::from_str(text) {
+        return Ok(value);
+    }
+
+    // The outermost object or array within surrounding text
+    let start = text.find(['{', '[']);
+    let end = text.rfind(['}', ']']);
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => serde_json::from_str(&text[start..=end])
+            .map_err(|e| format!("The answer is not valid JSON: {e}")),
+        _ => Err("The answer contains no JSON value".to_string()),
+    }
+}
+
+/// Errors of a value against a schema, empty when it matches
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(schema, schema, value, "$", &mut errors);
+    errors
+}
+
+fn check(root: &Value, schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            errors.push(format!("{path}: no value is allowed here"));
+            return;
+        }
+        Value::Object(schema) => schema,
+        _ => return,
+    };
+
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+        {
+            Some(target) => check(root, target, value, path, errors),
+            None => errors.push(format!("{path}: unsupported $ref '{reference}'")),
+        }
+    }
+
+    if let Some(types) = schema.get("type") {
+        let allowed: Vec<&str> = match types {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|name| has_type(value, name)) {
+            errors.push(format!(
+                "{path}: expected {}, got {}",
+                allowed.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            let options: Vec<String> = options.iter().map(Value::to_string).collect();
+            errors.push(format!("{path}: must be one of {}", options.join(", ")));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            errors.push(format!("{path}: must be {expected}"));
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for name in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                if let Some(name) = name.as_str().filter(|name| !object.contains_key(*name)) {
+                    errors.push(format!("{path}: missing required property '{name}'"));
+                }
+            }
+            for (name, item) in object {
+                let item_path = format!("{path}.{name}");
+                match properties.and_then(|properties| properties.get(name)) {
+                    Some(property) => check(root, property, item, &item_path, errors),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            errors.push(format!("{path}: unexpected property '{name}'"))
+                        }
+                        Some(additional) => check(root, additional, item, &item_path, errors),
+                        None => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check(root, item_schema, item, &format!("{path}[{index}]"), errors);
+                }
+            }
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if (items.len() as u64) < min {
+                    errors.push(format!("{path}: expected at least {min} items"));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if items.len() as u64 > max {
+                    errors.push(format!("{path}: expected at most {max} items"));
+                }
+            }
+        }
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if length < min {
+                    errors.push(format!("{path}: shorter than {min} characters"));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if length > max {
+                    errors.push(format!("{path}: longer than {max} characters"));
+                }
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                match regex::Regex::new(pattern) {
+                    Ok(regex) if !regex.is_match(text) => {
+                        errors.push(format!("{path}: does not match pattern '{pattern}'"))
+                    }
+                    Ok(_) => {}
+                    Err(e) => errors.push(format!("{path}: invalid pattern '{pattern}': {e}")),
+                }
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+            if bound("minimum").is_some_and(|min| number < min)
+                || bound("exclusiveMinimum").is_some_and(|min| number <= min)
+            {
+                errors.push(format!("{path}: {number} is below the minimum"));
+            }
+            if bound("maximum").is_some_and(|max| number > max)
+                || bound("exclusiveMaximum").is_some_and(|max| number >= max)
+            {
+                errors.push(format!("{path}: {number} is above the maximum"));
+            }
+        }
+        _ => {}
+    }
+
+    let subschemas = |key: &str| {
+        schema
+            .get(key)
+            .and_then(Value::as_array)
+            .map(|schemas| schemas.as_slice())
+    };
+    for subschema in subschemas("allOf").into_iter().flatten() {
+        check(root, subschema, value, path, errors);
+    }
+    if let Some(options) = subschemas("anyOf") {
+        let matching = options.iter().filter(|option| matches(root, option, value));
+        if matching.count() == 0 {
+            errors.push(format!("{path}: matches none of the anyOf schemas"));
+        }
+    }
+    if let Some(options) = subschemas("oneOf") {
+        let matching = options.iter().filter(|option| matches(root, option, value));
+        let count = matching.count();
+        if count != 1 {
+            errors.push(format!(
+                "{path}: matches {count} of the oneOf schemas instead of one"
+            ));
+        }
+    }
+    if let Some(not) = schema.get("not") {
+        if matches(root, not, value) {
+            errors.push(format!("{path}: must not match the 'not' schema"));
+        }
+    }
+}
+
+fn matches(root: &Value, schema: &Value, value: &Value) -> bool {
+    let mut errors = Vec::new();
+    check(root, schema, value, "$", &mut errors);
+    errors.is_empty()
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "integer" => value.as_f64().is_some_and(|number| number.fract() == 0.0),
+        "number" => value.is_number(),
+        _ => type_name(value) == name,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// JSON of an answer matching the schema, asking the model to correct it as needed
+pub async fn conform(
+    config: &Config,
+    schema: &Value,
+    query: &str,
+    answer: String,
+) -> Result<Value, String> {
+    let backend = crate::llm::create_backend(config)
+        .map_err(|e| format!("Failed to create a backend for corrections: {e}"))?;
+    let mut answer = answer;
+
+    for correction in 0..=MAX_CORRECTIONS {
+        let errors = match extract(&answer) {
+            Ok(value) => match validate(schema, &value) {
+                errors if errors.is_empty() => return Ok(value),
+                errors => errors,
+            },
+            Err(e) => vec![e],
+        };
+        if correction == MAX_CORRECTIONS {
+            return Err(format!(
+                "The answer does not match the schema after {MAX_CORRECTIONS} corrections:\n{}",
+                errors.join("\n")
+            ));
+        }
+        eprintln!(
+            "The answer does not match the schema ({} errors), asking for a correction...",
+            errors.len()
+        );
+
+        let feedback = format!(
+            "Your answer does not match the JSON Schema:\n{}\n\n\
+             Reply with only the corrected JSON value.",
+            errors.join("\n")
+        );
+        let messages = [
+            Message::text("user", query.to_string(), MessageInfo::User),
+            Message::text("assistant", answer, MessageInfo::Assistant),
+            Message::text("user", feedback, MessageInfo::User),
+        ];
+        let response = crate::llm::with_json_mode(backend.send_message(
+            &messages,
+            None,
+            None,
+            None,
+            None,
+            config.max_token_output,
+            config.sampling.for_task(SamplingTask::Conversation),
+        ))
+        .await
+        .map_err(|e| format!("Failed to ask for a corrected answer: {e}"))?;
+        answer = response.text();
+    }
+    unreachable!("the last attempt always returns")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate() {
+        let schema = json!({
+            "type": "object",
+            "required": ["name", "tags"],
+            "additionalProperties": false,
+            "properties": {
+                "name": { "type": "string", "minLength": 1 },
+                "score": { "type": "integer", "minimum": 0, "maximum": 10 },
+                "tags": { "type": "array", "items": { "$ref": "#/$defs/tag" } }
+            },
+            "$defs": { "tag": { "enum": ["bug", "feature"] } }
+        });
+
+        let valid = json!({ "name": "x", "score": 3, "tags": ["bug"] });
+        assert!(validate(&schema, &valid).is_empty());
+
+        let invalid = json!({ "score": 11.5, "tags": ["other"], "extra": true });
+        let errors = validate(&schema, &invalid);
+        assert!(errors.contains(&"$: missing required property 'name'".to_string()));
+        assert!(errors.contains(&"$: unexpected property 'extra'".to_string()));
+        assert!(errors.contains(&"$.score: expected integer, got number".to_string()));
+        assert!(errors
+            .iter()
+            .any(|e| e.starts_with("$.tags[0]: must be one of")));
+    }
+
+    #[test]
+    fn test_extract() {
+        assert_eq!(extract("{\"a\": 1}").unwrap(), json!({ "a": 1 }));
+        assert_eq!(
+            extract("Here it is:\n```json\n[1, 2]\n```\nDone.").unwrap(),
+            json!([1, 2])
+        );
+        assert_eq!(
+            extract("The result is {\"ok\": true}.").unwrap(),
+            json!({ "ok": true })
+        );
+        assert!(extract("no json").is_err());
+    }
+}
@@ -0,0 +1,302 @@
+//! Opt-in anonymous usage statistics
+//!
+//! Telemetry is off unless the user runs `termineer telemetry on`. When enabled,
+//! only coarse counters are collected: which commands were run, how often each
+//! built-in tool was called or failed, which model families were used and which
+//! classes of LLM errors occurred. Prompts, file contents, tool arguments and
+//! outputs are never recorded.
+//!
+//! Counters are appended to a local buffer (`~/.termineer/telemetry-pending.json`)
+//! when the process exits and uploaded to the Termineer server; reports that
+//! cannot be sent (e.g. offline) stay buffered until the next run.
+
+use crate::llm::LlmError;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Endpoint receiving telemetry reports
+const TELEMETRY_URL: &str = "https://termineer.io/api/telemetry";
+
+/// Environment variable overriding the telemetry endpoint
+const TELEMETRY_URL_ENV: &str = "TERMINEER_TELEMETRY_URL";
+
+/// Maximum number of buffered reports kept while offline
+const MAX_PENDING_REPORTS: usize = 50;
+
+/// Timeout for uploading reports
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Persisted telemetry preference
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TelemetrySettings {
+    enabled: bool,
+    /// Random identifier of this installation, not linked to any account
+    installation_id: Option<String>,
+}
+
+/// A batch of counters from one run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryReport {
+    pub installation_id: String,
+    pub version: String,
+    pub os: String,
+    pub counters: BTreeMap<String, u64>,
+}
+
+struct TelemetryState {
+    settings: TelemetrySettings,
+    counters: BTreeMap<String, u64>,
+}
+
+lazy_static! {
+    static ref TELEMETRY: Mutex<TelemetryState> = Mutex::new(TelemetryState {
+        settings: TelemetrySettings::default(),
+        counters: BTreeMap::new(),
+    });
+}
+
+fn telemetry_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".termineer"))
+}
+
+fn settings_path() -> Option<PathBuf> {
+    telemetry_dir().map(|dir| dir.join("telemetry.json"))
+}
+
+fn pending_path() -> Option<PathBuf> {
+    telemetry_dir().map(|dir| dir.join("telemetry-pending.json"))
+}
+
+fn load_settings() -> TelemetrySettings {
+    settings_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(settings: &TelemetrySettings) -> std::io::Result<()> {
+    let path = settings_path().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "Home directory not found")
+    })?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    std::fs::write(path, json)
+}
+
+/// Load the telemetry preference; counters are only collected when it is enabled
+pub fn init() {
+    TELEMETRY.lock().unwrap().settings = load_settings();
+}
+
+/// Whether the user opted in to telemetry
+pub fn is_enabled() -> bool {
+    TELEMETRY.lock().unwrap().settings.enabled
+}
+
+/// Enable or disable telemetry and persist the choice
+pub fn set_enabled(enabled: bool) -> std::io::Result<()> {
+    let mut settings = load_settings();
+    settings.enabled = enabled;
+    if enabled && settings.installation_id.is_none() {
+        settings.installation_id = Some(uuid::Uuid::new_v4().to_string());
+    }
+    if !enabled {
+        // Drop anything collected but not yet sent
+        if let Some(path) = pending_path() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+    save_settings(&settings)?;
+    TELEMETRY.lock().unwrap().settings = settings;
+    Ok(())
+}
+
+/// Human-readable telemetry status for `termineer telemetry status`
+pub fn status() -> String {
+    let settings = load_settings();
+    let pending = load_pending().len();
+    if settings.enabled {
+        format!(
+            "Telemetry is enabled (anonymous counters only). {pending} report(s) waiting to be sent.\nDisable with: termineer telemetry off"
+        )
+    } else {
+        "Telemetry is disabled. Enable anonymous usage statistics with: termineer telemetry on"
+            .to_string()
+    }
+}
+
+/// Increment a counter if telemetry is enabled
+fn record(counter: String) {
+    let mut state = TELEMETRY.lock().unwrap();
+    if state.settings.enabled {
+        *state.counters.entry(counter).or_insert(0) += 1;
+    }
+}
+
+/// Count a CLI command (interactive, query, workflow, ...)
+pub fn record_command(command: &str) {
+    record(format!("command:{command}"));
+}
+
+/// Count a tool call; tools provided by MCP servers are counted together
+pub fn record_tool_call(tool_name: &str) {
+    record(format!("tool:{}", tool_label(tool_name)));
+}
+
+/// Count a failed tool call
+pub fn record_tool_error(tool_name: &str) {
+    record(format!("tool_error:{}", tool_label(tool_name)));
+}
+
+/// Count use of a model, reduced to its family
+pub fn record_model(model: &str) {
+    record(format!("model:{}", model_family(model)));
+}
+
+/// Count an LLM error by class
+pub fn record_llm_error(error: &LlmError) {
+    let class = match error {
+        LlmError::ApiError(_) => "api",
+        LlmError::ConfigError(_) => "config",
+        LlmError::RateLimitError { .. } => "rate_limit",
+        LlmError::Other(_) => "other",
+    };
+    record(format!("llm_error:{class}"));
+}
+
+/// Built-in tool names are reported as-is, anything else as "mcp"
+fn tool_label(tool_name: &str) -> &str {
+    if crate::prompts::ALL_TOOLS.contains(&tool_name) {
+        tool_name
+    } else {
+        "mcp"
+    }
+}
+
+/// Strip provider prefixes, dates and snapshot suffixes from a model name
+fn model_family(model: &str) -> String {
+    let name = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+    let mut parts: Vec<&str> = name.split('-').collect();
+    while parts.len() > 1 {
+        let last = parts[parts.len() - 1];
+        if last.chars().all(|c| c.is_ascii_digit()) || matches!(last, "latest" | "preview" | "exp")
+        {
+            parts.pop();
+        } else {
+            break;
+        }
+    }
+    parts.join("-")
+}
+
+fn load_pending() -> Vec<TelemetryReport> {
+    pending_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_pending(reports: &[TelemetryReport]) -> std::io::Result<()> {
+    let Some(path) = pending_path() else {
+        return Ok(());
+    };
+    if reports.is_empty() {
+        return match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string(reports)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    std::fs::write(path, json)
+}
+
+/// Buffer the counters of this run and try to upload all buffered reports
+///
+/// Failures are silent: reports stay buffered and are retried on the next run.
+pub async fn flush() {
+    let (settings, counters) = {
+        let mut state = TELEMETRY.lock().unwrap();
+        (state.settings.clone(), std::mem::take(&mut state.counters))
+    };
+    if !settings.enabled {
+        return;
+    }
+    let Some(installation_id) = settings.installation_id else {
+        return;
+    };
+
+    let mut pending = load_pending();
+    if !counters.is_empty() {
+        pending.push(TelemetryReport {
+            installation_id,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            counters,
+        });
+    }
+    if pending.is_empty() {
+        return;
+    }
+
+    // Keep the most recent reports when the buffer grows while offline
+    let overflow = pending.len().saturating_sub(MAX_PENDING_REPORTS);
+    pending.drain(..overflow);
+
+    let url = std::env::var(TELEMETRY_URL_ENV).unwrap_or_else(|_| TELEMETRY_URL.to_string());
+    let client = match crate::net::client_builder().timeout(UPLOAD_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(_) => {
+            let _ = save_pending(&pending);
+            return;
+        }
+    };
+
+    let mut unsent = Vec::new();
+    for report in pending {
+        let sent = client
+            .post(&url)
+            .json(&report)
+            .send()
+            .await
+            .map(|response| response.status().is_success())
+            .unwrap_or(false);
+        if !sent {
+            unsent.push(report);
+        }
+    }
+    let _ = save_pending(&unsent);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_family() {
+        assert_eq!(
+            model_family("claude-3-7-sonnet-20250219"),
+            "claude-3-7-sonnet"
+        );
+        assert_eq!(model_family("openai/gpt-4o-2024-08-06"), "gpt-4o");
+        assert_eq!(model_family("gemini-2.0-flash-exp"), "gemini-2.0-flash");
+        assert_eq!(model_family("deepseek-chat"), "deepseek-chat");
+    }
+
+    #[test]
+    fn test_tool_label() {
+        assert_eq!(tool_label("read"), "read");
+        assert_eq!(tool_label("shell"), "shell");
+        assert_eq!(tool_label("my-private-server"), "mcp");
+    }
+}
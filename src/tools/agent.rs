@@ -38,7 +38,9 @@ pub async fn execute_agent_tool(
     let subcommand_args = parts.get(1).map(|s| s.trim()).unwrap_or("");
 
     match subcommand {
-        "create" => execute_create_subcommand(subcommand_args, body, silent_mode).await,
+        "create" => {
+            execute_create_subcommand(subcommand_args, body, silent_mode, source_agent_id).await
+        }
         "send" => {
             execute_send_subcommand(subcommand_args, body, silent_mode, source_agent_id).await
         }
@@ -56,7 +58,12 @@ pub async fn execute_agent_tool(
 }
 
 /// Execute the 'create' subcommand to spawn a new agent
-async fn execute_create_subcommand(args: &str, body: &str, silent_mode: bool) -> ToolResult {
+async fn execute_create_subcommand(
+    args: &str,
+    body: &str,
+    silent_mode: bool,
+    parent_agent_id: Option<AgentId>,
+) -> ToolResult {
     // Parse the agent name and check for parameters using key=value syntax
     let args_string = args.trim().to_string();
     let mut kind_name = None;
@@ -121,8 +128,13 @@ async fn execute_create_subcommand(args: &str, body: &str, silent_mode: bool) ->
         }
     }
 
-    // Create the new agent
-    let agent_id = match crate::agent::create_agent(agent_name.clone(), config) {
+    // Create the new agent, enforcing spawn depth and loop limits
+    let agent_id = match crate::agent::create_child_agent(
+        agent_name.clone(),
+        config,
+        parent_agent_id,
+        agent_instructions,
+    ) {
         Ok(id) => id,
         Err(e) => {
             let error_msg = format!("Failed to create agent: {e}");
@@ -0,0 +1,616 @@
+//! Browser automation through the Chrome DevTools Protocol
+//!
+//! The `browser` tool drives a Chrome or Chromium started on first use, headless
+//! unless asked otherwise, so agents can work with web applications without
+//! OS-level clicks or a display. One page stays open between calls:
+//! - `browser open URL [headed]` navigates to a URL, starting the browser if needed
+//! - `browser click SELECTOR` clicks the first element matching a CSS selector
+//! - `browser fill SELECTOR` replaces the value of an input with the body
+//! - `browser text [SELECTOR]` returns the visible text of an element or the page
+//! - `browser query SELECTOR` lists the matching elements with their attributes
+//! - `browser screenshot [SELECTOR]` captures the viewport or an element
+//! - `browser close` stops the browser
+//!
+//! Like Playwright, actions wait for their element to appear (`timeout=SECONDS`).
+//! Pages are opened under the network policy, and a page that redirects or a
+//! click that leaves for a domain the policy rejects is turned back.
+
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
+use crate::llm::{Content, ImageSource};
+use crate::tools::network;
+use crate::tools::ToolResult;
+use base64::{engine::general_purpose, Engine as _};
+use chromiumoxide::browser::{Browser, BrowserConfig};
+use chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotFormat;
+use chromiumoxide::element::Element;
+use chromiumoxide::error::CdpError;
+use chromiumoxide::handler::viewport::Viewport;
+use chromiumoxide::page::{Page, ScreenshotParams};
+use futures::StreamExt;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// Size of the page viewport in pixels
+const VIEWPORT_WIDTH: u32 = 1280;
+const VIEWPORT_HEIGHT: u32 = 800;
+
+/// Largest screenshots sent to models; larger ones are scaled down
+const MAX_IMAGE_WIDTH: u32 = 1600;
+const MAX_IMAGE_HEIGHT: u32 = 1200;
+
+/// Time to wait for an element when no `timeout` is given
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between lookups of an element that is not there yet
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Time given to a page to react to a click
+const CLICK_SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Characters of page text returned
+const MAX_TEXT_CHARS: usize = 20_000;
+
+/// Elements listed by `query`
+const MAX_QUERY_RESULTS: usize = 20;
+
+/// Attributes listed by `query`, in this order
+const QUERY_ATTRIBUTES: &[&str] = &[
+    "id",
+    "name",
+    "type",
+    "role",
+    "href",
+    "value",
+    "placeholder",
+    "aria-label",
+];
+
+/// The running browser with its page
+struct Session {
+    browser: Browser,
+    page: Page,
+    /// Processes the browser's protocol messages until it closes
+    handler: JoinHandle<()>,
+}
+
+lazy_static! {
+    static ref SESSION: Mutex<Option<Session>> = Mutex::new(None);
+}
+
+/// Whether a call of the browser tool can change the state of a web application
+pub fn is_mutating(args: &str) -> bool {
+    matches!(args.split_whitespace().next(), Some("click" | "fill"))
+}
+
+/// Parsed browser command
+#[derive(Debug, PartialEq)]
+enum BrowserCommand {
+    Open { url: String, headed: bool },
+    Click { selector: String },
+    Fill { selector: String, text: String },
+    Text { selector: Option<String> },
+    Query { selector: String },
+    Screenshot { selector: Option<String> },
+    Close,
+}
+
+/// Parse the arguments and body of a call; returns the command and the time
+/// to wait for its element
+fn parse_command(args: &str, body: &str) -> Result<(BrowserCommand, Duration), String> {
+    let mut timeout = DEFAULT_TIMEOUT;
+    let mut words = Vec::new();
+    for word in args.split_whitespace() {
+        match word.strip_prefix("timeout=") {
+            Some(value) => {
+                let seconds = value
+                    .parse::<f64>()
+                    .map_err(|_| format!("Invalid timeout '{value}': expected seconds"))?;
+                timeout = Duration::from_secs_f64(seconds.clamp(0.0, 120.0));
+            }
+            None => words.push(word),
+        }
+    }
+
+    let action = words.first().copied().unwrap_or_default();
+    // Selectors may contain spaces, e.g. `form > button`
+    let rest = words.get(1..).unwrap_or_default().join(" ");
+    let selector = || {
+        if rest.is_empty() {
+            Err(format!("browser {action} needs a CSS selector"))
+        } else {
+            Ok(rest.clone())
+        }
+    };
+    let optional_selector = (!rest.is_empty()).then(|| rest.clone());
+
+    let command = match action {
+        "open" | "goto" => {
+            let url = words
+                .get(1)
+                .ok_or("browser open needs a URL")?
+                .to_string();
+            let headed = match words.get(2..).unwrap_or_default() {
+                [] => false,
+                ["headed"] => true,
+                other => return Err(format!("Unexpected arguments: {}", other.join(" "))),
+            };
+            BrowserCommand::Open { url, headed }
+        }
+        "click" => BrowserCommand::Click {
+            selector: selector()?,
+        },
+        "fill" => BrowserCommand::Fill {
+            selector: selector()?,
+            text: body.trim_end_matches(['\r', '\n']).to_string(),
+        },
+        "text" => BrowserCommand::Text {
+            selector: optional_selector,
+        },
+        "query" => BrowserCommand::Query {
+            selector: selector()?,
+        },
+        "screenshot" => BrowserCommand::Screenshot {
+            selector: optional_selector,
+        },
+        "close" => BrowserCommand::Close,
+        "" => {
+            return Err(
+                "An action is required: open, click, fill, text, query, screenshot or close"
+                    .to_string(),
+            )
+        }
+        other => {
+            return Err(format!(
+                "Unknown browser action '{other}'. Use open, click, fill, text, query, screenshot or close"
+            ))
+        }
+    };
+    Ok((command, timeout))
+}
+
+/// Execute the browser tool
+pub async fn execute_browser(args: &str, body: &str, silent_mode: bool) -> ToolResult {
+    let result = match parse_command(args, body) {
+        Ok((command, timeout)) => run(command, timeout).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(content) => {
+            if !silent_mode {
+                let summary = content
+                    .iter()
+                    .map(|content| match content {
+                        Content::Text { text } => text.as_str(),
+                        _ => "[screenshot]",
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let preview: String = summary.lines().take(10).collect::<Vec<_>>().join("\n");
+                bprintln!(tool: "browser",
+                    "{FORMAT_BOLD}🌐 browser {}{FORMAT_RESET}\n{FORMAT_GRAY}{}{FORMAT_RESET}",
+                    args.trim(),
+                    preview
+                );
+            }
+            ToolResult::success_with_content(content)
+        }
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            ToolResult::error(error_msg)
+        }
+    }
+}
+
+async fn run(command: BrowserCommand, timeout: Duration) -> Result<Vec<Content>, String> {
+    let mut session = SESSION.lock().await;
+
+    if let BrowserCommand::Open { url, headed } = &command {
+        let url = normalize_url(url)?;
+        network::check_url("browser", &url).await?;
+        if session.is_none() {
+            *session = Some(launch(*headed).await?);
+        }
+        let page = &session.as_ref().unwrap().page;
+        page.goto(url.as_str())
+            .await
+            .map_err(|e| format!("Failed to open {url}: {e}"))?;
+        check_navigation(page, Some(&url)).await?;
+        return Ok(text(describe_page(page).await));
+    }
+    if command == BrowserCommand::Close {
+        return match session.take() {
+            Some(session) => {
+                stop(session).await;
+                Ok(text("Browser closed".to_string()))
+            }
+            None => Ok(text("No browser is running".to_string())),
+        };
+    }
+
+    let page = &session
+        .as_ref()
+        .ok_or("No browser is running: use `browser open URL` first")?
+        .page;
+    match command {
+        BrowserCommand::Click { selector } => {
+            let before = page.url().await.ok().flatten();
+            find(page, &selector, timeout)
+                .await?
+                .click()
+                .await
+                .map_err(|e| format!("Failed to click '{selector}': {e}"))?;
+            tokio::time::sleep(CLICK_SETTLE_TIME).await;
+
+            // Clicks may follow links the network policy does not allow
+            check_navigation(page, before.as_ref()).await?;
+            Ok(text(format!(
+                "Clicked '{selector}'\n{}",
+                describe_page(page).await
+            )))
+        }
+        BrowserCommand::Fill {
+            selector,
+            text: value,
+        } => {
+            let element = find(page, &selector, timeout).await?;
+            let error = |e: CdpError| format!("Failed to fill '{selector}': {e}");
+            element.focus().await.map_err(error)?;
+            element
+                .call_js_fn(
+                    "function() { if ('value' in this) { this.value = ''; } }",
+                    false,
+                )
+                .await
+                .map_err(error)?;
+            element.type_str(&value).await.map_err(error)?;
+            Ok(text(format!(
+                "Filled '{selector}' with {} characters",
+                value.chars().count()
+            )))
+        }
+        BrowserCommand::Text { selector } => {
+            let content = match &selector {
+                Some(selector) => find(page, selector, timeout)
+                    .await?
+                    .inner_text()
+                    .await
+                    .map_err(|e| format!("Failed to read the text of '{selector}': {e}"))?
+                    .unwrap_or_default(),
+                None => page
+                    .evaluate("document.body ? document.body.innerText : ''")
+                    .await
+                    .and_then(|result| Ok(result.into_value::<String>()?))
+                    .map_err(|e| format!("Failed to read the text of the page: {e}"))?,
+            };
+            Ok(text(truncate_text(content.trim())))
+        }
+        BrowserCommand::Query { selector } => {
+            let result: QueryResult = page
+                .evaluate(query_script(&selector))
+                .await
+                .and_then(|result| Ok(result.into_value()?))
+                .map_err(|e| format!("Failed to query '{selector}': {e}"))?;
+            Ok(text(format_elements(&selector, &result)))
+        }
+        BrowserCommand::Screenshot { selector } => {
+            let png = match &selector {
+                Some(selector) => {
+                    find(page, selector, timeout)
+                        .await?
+                        .screenshot(CaptureScreenshotFormat::Png)
+                        .await
+                }
+                None => {
+                    page.screenshot(
+                        ScreenshotParams::builder()
+                            .format(CaptureScreenshotFormat::Png)
+                            .build(),
+                    )
+                    .await
+                }
+            }
+            .map_err(|e| format!("Failed to take the screenshot: {e}"))?;
+            let image = fit_image(png)?;
+            Ok(vec![
+                Content::Text {
+                    text: describe_page(page).await,
+                },
+                Content::Image {
+                    source: ImageSource::Base64 {
+                        media_type: "image/png".to_string(),
+                        data: general_purpose::STANDARD.encode(image),
+                    },
+                },
+            ])
+        }
+        BrowserCommand::Open { .. } | BrowserCommand::Close => unreachable!(),
+    }
+}
+
+fn text(text: String) -> Vec<Content> {
+    vec![Content::Text { text }]
+}
+
+/// Start the browser with an empty page
+async fn launch(headed: bool) -> Result<Session, String> {
+    let mut builder = BrowserConfig::builder()
+        .window_size(VIEWPORT_WIDTH, VIEWPORT_HEIGHT)
+        .viewport(Viewport {
+            width: VIEWPORT_WIDTH,
+            height: VIEWPORT_HEIGHT,
+            ..Default::default()
+        });
+    if headed {
+        builder = builder.with_head();
+    }
+    let config = builder.build().map_err(|e| {
+        format!("Cannot start a browser: {e}. Install Chrome or Chromium to use the browser tool")
+    })?;
+
+    let (browser, mut handler) = Browser::launch(config)
+        .await
+        .map_err(|e| format!("Failed to start the browser: {e}"))?;
+    let handler = tokio::spawn(async move {
+        while let Some(event) = handler.next().await {
+            if event.is_err() {
+                break;
+            }
+        }
+    });
+    let page = browser
+        .new_page("about:blank")
+        .await
+        .map_err(|e| format!("Failed to open a page: {e}"))?;
+
+    Ok(Session {
+        browser,
+        page,
+        handler,
+    })
+}
+
+async fn stop(mut session: Session) {
+    let _ = session.browser.close().await;
+    let _ = session.browser.wait().await;
+    session.handler.abort();
+}
+
+/// Stop the browser if one is running
+pub async fn close_browser() {
+    if let Some(session) = SESSION.lock().await.take() {
+        stop(session).await;
+    }
+}
+
+/// Check the URL the page ended up on when it differs from `before`, turning
+/// the page back if the network policy rejects it
+async fn check_navigation(page: &Page, before: Option<&String>) -> Result<(), String> {
+    let after = page.url().await.ok().flatten();
+    if let Some(url) = after.filter(|url| Some(url) != before) {
+        if let Err(e) = network::check_url("browser", &url).await {
+            let _ = page.goto("about:blank").await;
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Add `https://` to URLs without a scheme; only web pages may be opened
+fn normalize_url(url: &str) -> Result<String, String> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(url.to_string())
+    } else if url.contains("://") {
+        Err(format!(
+            "Only http and https URLs can be opened, not '{url}'"
+        ))
+    } else {
+        Ok(format!("https://{url}"))
+    }
+}
+
+/// First element matching a selector, waiting until it appears
+async fn find(page: &Page, selector: &str, timeout: Duration) -> Result<Element, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match page.find_element(selector).await {
+            Ok(element) => return Ok(element),
+            Err(e) if Instant::now() >= deadline => {
+                return Err(format!(
+                    "No element matches '{selector}' after {:.1}s: {e}",
+                    timeout.as_secs_f64()
+                ))
+            }
+            Err(_) => tokio::time::sleep(POLL_INTERVAL).await,
+        }
+    }
+}
+
+async fn describe_page(page: &Page) -> String {
+    let title = page.get_title().await.ok().flatten().unwrap_or_default();
+    let url = page.url().await.ok().flatten().unwrap_or_default();
+    format!("Title: {title}\nURL: {url}")
+}
+
+fn truncate_text(text: &str) -> String {
+    let total = text.chars().count();
+    if total <= MAX_TEXT_CHARS {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(MAX_TEXT_CHARS).collect();
+    format!(
+        "{kept}\n[... {} more characters; read a part of the page with `browser text SELECTOR`]",
+        total - MAX_TEXT_CHARS
+    )
+}
+
+/// Scale a PNG down to the size sent to models
+fn fit_image(png: Vec<u8>) -> Result<Vec<u8>, String> {
+    let image = image::load_from_memory(&png)
+        .map_err(|e| format!("Failed to decode the screenshot: {e}"))?;
+    if image.width() <= MAX_IMAGE_WIDTH && image.height() <= MAX_IMAGE_HEIGHT {
+        return Ok(png);
+    }
+    let mut fitted = Vec::new();
+    image
+        .resize(
+            MAX_IMAGE_WIDTH,
+            MAX_IMAGE_HEIGHT,
+            image::imageops::FilterType::Lanczos3,
+        )
+        .write_to(
+            &mut std::io::Cursor::new(&mut fitted),
+            image::ImageFormat::Png,
+        )
+        .map_err(|e| format!("Failed to encode the screenshot: {e}"))?;
+    Ok(fitted)
+}
+
+/// Elements found by `query`
+#[derive(Debug, Deserialize)]
+struct QueryResult {
+    count: usize,
+    elements: Vec<QueriedElement>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueriedElement {
+    tag: String,
+    attributes: Vec<(String, String)>,
+    text: String,
+    visible: bool,
+}
+
+/// Script describing the first elements matching a selector
+fn query_script(selector: &str) -> String {
+    format!(
+        "(() => {{
+            const elements = Array.from(document.querySelectorAll({selector}));
+            return {{
+                count: elements.length,
+                elements: elements.slice(0, {MAX_QUERY_RESULTS}).map(e => ({{
+                    tag: e.tagName.toLowerCase(),
+                    attributes: {attributes}
+                        .filter(a => e.hasAttribute(a))
+                        .map(a => [a, e.getAttribute(a)]),
+                    text: (e.innerText || '').trim().replace(/\\s+/g, ' ').slice(0, 100),
+                    visible: !!(e.offsetWidth || e.offsetHeight || e.getClientRects().length),
+                }})),
+            }};
+        }})()",
+        selector = serde_json::to_string(selector).unwrap(),
+        attributes = serde_json::to_string(QUERY_ATTRIBUTES).unwrap(),
+    )
+}
+
+fn format_elements(selector: &str, result: &QueryResult) -> String {
+    if result.count == 0 {
+        return format!("No elements match '{selector}'");
+    }
+    let mut output = format!("{} elements match '{selector}':\n", result.count);
+    for (index, element) in result.elements.iter().enumerate() {
+        output.push_str(&format!("[{index}] <{}", element.tag));
+        for (name, value) in &element.attributes {
+            output.push_str(&format!(" {name}=\"{value}\""));
+        }
+        output.push('>');
+        if !element.text.is_empty() {
+            output.push_str(&format!(" {}", element.text));
+        }
+        if !element.visible {
+            output.push_str(" (hidden)");
+        }
+        output.push('\n');
+    }
+    if result.count > result.elements.len() {
+        output.push_str(&format!(
+            "... and {} more; use a more specific selector\n",
+            result.count - result.elements.len()
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("open example.com headed", "").unwrap(),
+            (
+                BrowserCommand::Open {
+                    url: "example.com".to_string(),
+                    headed: true
+                },
+                DEFAULT_TIMEOUT
+            )
+        );
+        assert_eq!(
+            parse_command("click form > button[type=submit] timeout=2", "").unwrap(),
+            (
+                BrowserCommand::Click {
+                    selector: "form > button[type=submit]".to_string()
+                },
+                Duration::from_secs(2)
+            )
+        );
+        assert_eq!(
+            parse_command("fill #email", "user@example.com\n")
+                .unwrap()
+                .0,
+            BrowserCommand::Fill {
+                selector: "#email".to_string(),
+                text: "user@example.com".to_string()
+            }
+        );
+        assert_eq!(
+            parse_command("text", "").unwrap().0,
+            BrowserCommand::Text { selector: None }
+        );
+        assert!(parse_command("click", "").is_err());
+        assert!(parse_command("scroll down", "").is_err());
+        assert!(is_mutating("fill #email"));
+        assert!(!is_mutating("text #email"));
+
+        assert_eq!(
+            normalize_url("example.com/a").unwrap(),
+            "https://example.com/a"
+        );
+        assert!(normalize_url("file:///etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_format_elements() {
+        let result = QueryResult {
+            count: 3,
+            elements: vec![
+                QueriedElement {
+                    tag: "a".to_string(),
+                    attributes: vec![("href".to_string(), "/docs".to_string())],
+                    text: "Docs".to_string(),
+                    visible: true,
+                },
+                QueriedElement {
+                    tag: "input".to_string(),
+                    attributes: vec![("type".to_string(), "hidden".to_string())],
+                    text: String::new(),
+                    visible: false,
+                },
+            ],
+        };
+        assert_eq!(
+            format_elements("a, input", &result),
+            "3 elements match 'a, input':\n\
+             [0] <a href=\"/docs\"> Docs\n\
+             [1] <input type=\"hidden\"> (hidden)\n\
+             ... and 1 more; use a more specific selector\n"
+        );
+    }
+}
@@ -0,0 +1,515 @@
+//! Check tool returning compiler and linter diagnostics as compact records
+//!
+//! Compiler output repeats code snippets, notes and the same warning for every
+//! target. The check tool runs the checker in a machine-readable mode where one
+//! exists, parses each diagnostic into a file/line/severity/message record,
+//! drops duplicates and returns the first ones, errors before warnings.
+//!
+//! Usage:
+//! - `check` detects the checker from the project files
+//! - `check cargo|tsc|eslint|mypy [limit=N] [ARGS...]` picks the checker and
+//!   passes the remaining arguments to it
+
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
+use crate::tools::test_runner::run_with_timeout;
+use crate::tools::ToolResult;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Diagnostics returned when no limit is given
+const DEFAULT_LIMIT: usize = 30;
+
+/// Lines of raw output included when the check failed without diagnostics
+const RAW_TAIL_LINES: usize = 40;
+
+lazy_static! {
+    static ref TSC_DIAGNOSTIC: Regex =
+        Regex::new(r"^(.+?)\((\d+),(\d+)\): (error|warning) (TS\d+): (.*)$").unwrap();
+    static ref MYPY_DIAGNOSTIC: Regex =
+        Regex::new(r"^(.+?):(\d+):(?:(\d+):)? (error|warning|note): (.*?)(?:  \[([\w-]+)\])?$")
+            .unwrap();
+}
+
+/// Supported checkers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Checker {
+    Cargo,
+    Tsc,
+    Eslint,
+    Mypy,
+}
+
+impl Checker {
+    fn name(self) -> &'static str {
+        match self {
+            Checker::Cargo => "cargo check",
+            Checker::Tsc => "tsc",
+            Checker::Eslint => "eslint",
+            Checker::Mypy => "mypy",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "cargo" => Some(Checker::Cargo),
+            "tsc" => Some(Checker::Tsc),
+            "eslint" => Some(Checker::Eslint),
+            "mypy" => Some(Checker::Mypy),
+            _ => None,
+        }
+    }
+
+    /// Pick the checker from the files in the project directory
+    fn detect(dir: &Path) -> Option<Self> {
+        let has = |file: &str| dir.join(file).exists();
+        if has("Cargo.toml") {
+            Some(Checker::Cargo)
+        } else if has("tsconfig.json") {
+            Some(Checker::Tsc)
+        } else if [
+            "eslint.config.js",
+            "eslint.config.mjs",
+            ".eslintrc.js",
+            ".eslintrc.json",
+            ".eslintrc.yml",
+            ".eslintrc",
+        ]
+        .iter()
+        .any(|file| has(file))
+        {
+            Some(Checker::Eslint)
+        } else if ["mypy.ini", "pyproject.toml", "setup.py", "setup.cfg"]
+            .iter()
+            .any(|file| has(file))
+        {
+            Some(Checker::Mypy)
+        } else {
+            None
+        }
+    }
+
+    /// Program and arguments
+    fn command(self, args: &[&str]) -> (&'static str, Vec<String>) {
+        let (program, fixed, default_target): (_, &[&str], _) = match self {
+            Checker::Cargo => ("cargo", &["check", "--message-format=json"], None),
+            Checker::Tsc => ("npx", &["tsc", "--noEmit", "--pretty", "false"], None),
+            Checker::Eslint => ("npx", &["eslint", "--format", "json"], Some(".")),
+            Checker::Mypy => (
+                "mypy",
+                &[
+                    "--show-column-numbers",
+                    "--no-error-summary",
+                    "--no-color-output",
+                    "--no-pretty",
+                ],
+                Some("."),
+            ),
+        };
+        let mut command: Vec<String> = fixed.iter().map(|arg| arg.to_string()).collect();
+        command.extend(args.iter().map(|arg| arg.to_string()));
+        if let Some(target) = default_target.filter(|_| args.is_empty()) {
+            command.push(target.to_string());
+        }
+        (program, command)
+    }
+}
+
+/// Severity of a diagnostic, most severe first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    fn label(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// A single compiler or linter finding
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Diagnostic {
+    file: String,
+    line: u32,
+    column: Option<u32>,
+    severity: Severity,
+    /// Error code or lint rule, e.g. `E0308` or `no-unused-vars`
+    code: Option<String>,
+    message: String,
+}
+
+/// Execute the check tool
+pub async fn execute_check(args: &str, _body: &str, silent_mode: bool) -> ToolResult {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let (explicit, limit, rest) = match parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+    };
+    let checker = match explicit.or_else(|| Checker::detect(&cwd)) {
+        Some(checker) => checker,
+        None => {
+            let error_msg = "Could not detect the checker; use `check cargo`, `check tsc`, \
+                             `check eslint` or `check mypy`"
+                .to_string();
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+    };
+
+    let (program, command_args) = checker.command(&rest);
+    if !silent_mode {
+        bprintln !(tool: "check",
+            "{FORMAT_BOLD}🔎 Running {}:{FORMAT_RESET} {FORMAT_GRAY}{} {}{FORMAT_RESET}",
+            checker.name(),
+            program,
+            command_args.join(" ")
+        );
+    }
+
+    let output = match run_with_timeout(program, &command_args).await {
+        Ok(output) => output,
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let diagnostics = match checker {
+        Checker::Cargo => parse_cargo(&stdout),
+        Checker::Tsc => parse_lines(&stdout, parse_tsc_line),
+        Checker::Eslint => parse_eslint(&stdout, &cwd),
+        Checker::Mypy => parse_lines(&stdout, parse_mypy_line),
+    };
+
+    let diagnostics = dedupe(diagnostics);
+    let summary = if diagnostics.is_empty() && !output.status.success() {
+        // The checker failed without reporting anything that could be parsed
+        let raw = crate::ansi_converter::strip_ansi_sequences(&format!(
+            "{}\n{}",
+            stdout,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+        let lines: Vec<&str> = raw.lines().filter(|line| !line.trim().is_empty()).collect();
+        let tail = &lines[lines.len().saturating_sub(RAW_TAIL_LINES)..];
+        format!(
+            "{} failed without diagnostics. Last lines of output:\n{}",
+            checker.name(),
+            tail.join("\n")
+        )
+    } else {
+        format_diagnostics(checker, diagnostics, limit)
+    };
+
+    if !silent_mode {
+        let headline = summary.lines().next().unwrap_or("");
+        bprintln !(tool: "check", "{FORMAT_GRAY}{}{FORMAT_RESET}", headline);
+    }
+    ToolResult::success(summary)
+}
+
+/// Split the arguments into the checker, the limit and the checker arguments
+fn parse_args(args: &str) -> Result<(Option<Checker>, usize, Vec<&str>), String> {
+    let mut tokens: Vec<&str> = args.split_whitespace().collect();
+    let checker = tokens.first().and_then(|token| Checker::parse(token));
+    if checker.is_some() {
+        tokens.remove(0);
+    }
+    let mut limit = DEFAULT_LIMIT;
+    if let Some(value) = tokens
+        .first()
+        .and_then(|token| token.strip_prefix("limit="))
+    {
+        limit = value
+            .parse::<usize>()
+            .ok()
+            .filter(|limit| *limit > 0)
+            .ok_or_else(|| format!("Invalid limit '{value}', expected a positive number"))?;
+        tokens.remove(0);
+    }
+    Ok((checker, limit, tokens))
+}
+
+/// Parse the `--message-format=json` output of cargo
+fn parse_cargo(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for line in output.lines() {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if value.get("reason").and_then(|v| v.as_str()) != Some("compiler-message") {
+            continue;
+        }
+        let Some(message) = value.get("message") else {
+            continue;
+        };
+        let severity = match message.get("level").and_then(|v| v.as_str()) {
+            Some("error") => Severity::Error,
+            Some("warning") => Severity::Warning,
+            _ => continue,
+        };
+        // Summaries such as "aborting due to 2 previous errors" have no primary span
+        let spans = message.get("spans").and_then(|v| v.as_array());
+        let Some(span) = spans.into_iter().flatten().find(|span| {
+            span.get("is_primary")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+        }) else {
+            continue;
+        };
+
+        let text = message
+            .get("message")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let label = span.get("label").and_then(|v| v.as_str());
+        diagnostics.push(Diagnostic {
+            file: span
+                .get("file_name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            line: span.get("line_start").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+            column: span
+                .get("column_start")
+                .and_then(|v| v.as_u64())
+                .map(|c| c as u32),
+            severity,
+            code: message
+                .get("code")
+                .and_then(|code| code.get("code"))
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            message: match label {
+                Some(label) if !label.is_empty() => format!("{text} ({label})"),
+                _ => text.to_string(),
+            },
+        });
+    }
+    diagnostics
+}
+
+/// Parse line-oriented checker output
+fn parse_lines(output: &str, parse_line: fn(&str) -> Option<Diagnostic>) -> Vec<Diagnostic> {
+    output.lines().filter_map(parse_line).collect()
+}
+
+/// Parse a line of `tsc --pretty false` output
+fn parse_tsc_line(line: &str) -> Option<Diagnostic> {
+    let captures = TSC_DIAGNOSTIC.captures(line)?;
+    Some(Diagnostic {
+        file: captures[1].to_string(),
+        line: captures[2].parse().ok()?,
+        column: captures[3].parse().ok(),
+        severity: if &captures[4] == "error" {
+            Severity::Error
+        } else {
+            Severity::Warning
+        },
+        code: Some(captures[5].to_string()),
+        message: captures[6].to_string(),
+    })
+}
+
+/// Parse a line of mypy output, skipping notes
+fn parse_mypy_line(line: &str) -> Option<Diagnostic> {
+    let captures = MYPY_DIAGNOSTIC.captures(line)?;
+    let severity = match &captures[4] {
+        "error" => Severity::Error,
+        "warning" => Severity::Warning,
+        _ => return None,
+    };
+    Some(Diagnostic {
+        file: captures[1].to_string(),
+        line: captures[2].parse().ok()?,
+        column: captures.get(3).and_then(|c| c.as_str().parse().ok()),
+        severity,
+        code: captures.get(6).map(|code| code.as_str().to_string()),
+        message: captures[5].to_string(),
+    })
+}
+
+/// Parse the JSON output of `eslint --format json`
+fn parse_eslint(output: &str, cwd: &Path) -> Vec<Diagnostic> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(output) else {
+        return Vec::new();
+    };
+    let mut diagnostics = Vec::new();
+    for file in value.as_array().into_iter().flatten() {
+        let path = file.get("filePath").and_then(|v| v.as_str()).unwrap_or("");
+        let path = Path::new(path)
+            .strip_prefix(cwd)
+            .map(|relative| relative.display().to_string())
+            .unwrap_or_else(|_| path.to_string());
+        let messages = file.get("messages").and_then(|v| v.as_array());
+        for message in messages.into_iter().flatten() {
+            let number = |name: &str| message.get(name).and_then(|v| v.as_u64());
+            diagnostics.push(Diagnostic {
+                file: path.clone(),
+                line: number("line").unwrap_or(0) as u32,
+                column: number("column").map(|c| c as u32),
+                severity: if number("severity") == Some(2) {
+                    Severity::Error
+                } else {
+                    Severity::Warning
+                },
+                code: message
+                    .get("ruleId")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string),
+                message: message
+                    .get("message")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Drop repeated diagnostics (e.g. reported for several targets) and sort
+/// errors first, then by location
+fn dedupe(diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    let mut seen = HashSet::new();
+    let mut unique: Vec<Diagnostic> = diagnostics
+        .into_iter()
+        .filter(|diagnostic| seen.insert(diagnostic.clone()))
+        .collect();
+    unique.sort_by(|a, b| {
+        (a.severity, &a.file, a.line, a.column).cmp(&(b.severity, &b.file, b.line, b.column))
+    });
+    unique
+}
+
+/// Compact report with at most `limit` diagnostics
+fn format_diagnostics(checker: Checker, diagnostics: Vec<Diagnostic>, limit: usize) -> String {
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    if diagnostics.is_empty() {
+        return format!("{}: no errors or warnings", checker.name());
+    }
+
+    let mut text = format!(
+        "{}: {} error(s), {} warning(s)",
+        checker.name(),
+        errors,
+        warnings
+    );
+    if diagnostics.len() > limit {
+        text.push_str(&format!(" (showing the first {limit})"));
+    }
+    for diagnostic in diagnostics.iter().take(limit) {
+        let column = diagnostic
+            .column
+            .map(|column| format!(":{column}"))
+            .unwrap_or_default();
+        let code = diagnostic
+            .code
+            .as_ref()
+            .map(|code| format!("[{code}]"))
+            .unwrap_or_default();
+        text.push_str(&format!(
+            "\n{}:{}{} {}{}: {}",
+            diagnostic.file,
+            diagnostic.line,
+            column,
+            diagnostic.severity.label(),
+            code,
+            diagnostic.message
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo() {
+        let output = r#"{"reason":"compiler-artifact","target":{"name":"demo"}}
+{"reason":"compiler-message","message":{"level":"error","message":"mismatched types","code":{"code":"E0308"},"spans":[{"file_name":"src/main.rs","line_start":4,"column_start":18,"is_primary":true,"label":"expected `u32`, found `&str`"}]}}
+{"reason":"compiler-message","message":{"level":"error","message":"aborting due to 1 previous error","code":null,"spans":[]}}
+"#;
+        assert_eq!(
+            parse_cargo(output),
+            vec![Diagnostic {
+                file: "src/main.rs".to_string(),
+                line: 4,
+                column: Some(18),
+                severity: Severity::Error,
+                code: Some("E0308".to_string()),
+                message: "mismatched types (expected `u32`, found `&str`)".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_tsc_and_mypy_lines() {
+        let tsc = parse_tsc_line(
+            "src/app.ts(12,5): error TS2322: Type 'string' is not assignable to type 'number'.",
+        )
+        .unwrap();
+        assert_eq!(tsc.file, "src/app.ts");
+        assert_eq!((tsc.line, tsc.column), (12, Some(5)));
+        assert_eq!(tsc.code.as_deref(), Some("TS2322"));
+
+        let mypy = parse_mypy_line(
+            "app/models.py:30:9: error: Incompatible return value type (got \"int\", expected \"str\")  [return-value]",
+        )
+        .unwrap();
+        assert_eq!(mypy.file, "app/models.py");
+        assert_eq!(mypy.code.as_deref(), Some("return-value"));
+        assert!(mypy.message.ends_with("expected \"str\")"));
+        assert_eq!(
+            parse_mypy_line("app/models.py:31: note: See the docs"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_dedupe_and_format() {
+        let warning = Diagnostic {
+            file: "src/lib.rs".to_string(),
+            line: 3,
+            column: Some(9),
+            severity: Severity::Warning,
+            code: Some("unused_imports".to_string()),
+            message: "unused import: `std::fmt`".to_string(),
+        };
+        let error = Diagnostic {
+            file: "src/main.rs".to_string(),
+            line: 10,
+            column: None,
+            severity: Severity::Error,
+            code: None,
+            message: "cannot find value `x` in this scope".to_string(),
+        };
+        let diagnostics = dedupe(vec![warning.clone(), error, warning]);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            format_diagnostics(Checker::Cargo, diagnostics, 1),
+            "cargo check: 1 error(s), 1 warning(s) (showing the first 1)\n\
+             src/main.rs:10 error: cannot find value `x` in this scope"
+        );
+    }
+}
@@ -0,0 +1,625 @@
+//! Structured editing of YAML, TOML and JSON configuration files
+//!
+//! The `config-edit` tool addresses values with the query expressions of
+//! [`crate::jsonpath`] and applies set/remove operations without rewriting the
+//! rest of the file:
+//! - TOML files are edited with `toml_edit`, keeping comments and formatting
+//! - YAML files are edited line by line when only mapping keys and scalar values
+//!   are involved; other edits fall back to re-serializing the document
+//! - JSON files keep their indentation
+//!
+//! Usage:
+//! - `config-edit get FILE QUERY`
+//! - `config-edit set FILE QUERY` with the new value (YAML or JSON syntax) in the body
+//! - `config-edit remove FILE QUERY`
+
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
+use crate::jsonpath::{self, Location};
+use crate::tools::ToolResult;
+use serde_json::Value;
+use std::path::Path;
+
+/// Supported configuration file formats
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ConfigFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ConfigFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+
+    /// Parse a document into its JSON data model
+    fn parse(self, content: &str) -> Result<Value, String> {
+        match self {
+            Self::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+            Self::Yaml => serde_yaml::from_str(content).map_err(|e| e.to_string()),
+            Self::Toml => toml_edit::de::from_str(content).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// A modification applied at every matching location
+enum Edit<'a> {
+    Set(&'a Value),
+    Remove,
+}
+
+/// Execute the config-edit tool
+pub async fn execute_config_edit(args: &str, body: &str, silent_mode: bool) -> ToolResult {
+    match run(args, body).await {
+        Ok((summary, output)) => {
+            if !silent_mode {
+                bprintln !(tool: "config-edit",
+                    "{FORMAT_BOLD}🛠️ Config: {summary}{FORMAT_RESET}\n{FORMAT_GRAY}{}{FORMAT_RESET}",
+                    output.lines().take(10).collect::<Vec<_>>().join("\n")
+                );
+            }
+            ToolResult::success(output)
+        }
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            ToolResult::error(error_msg)
+        }
+    }
+}
+
+/// Run the requested action, returning a short summary and the tool output
+async fn run(args: &str, body: &str) -> Result<(String, String), String> {
+    let mut parts = args.trim().splitn(3, char::is_whitespace);
+    let action = parts.next().unwrap_or("").to_lowercase();
+    let filename = parts.next().unwrap_or("").trim();
+    let query = parts.next().unwrap_or("").trim();
+
+    if action.is_empty() || filename.is_empty() || query.is_empty() {
+        return Err("Usage: config-edit get|set|remove FILE QUERY".to_string());
+    }
+
+    let path = crate::tools::path_utils::validate_path(filename)
+        .map_err(|e| format!("Security error for file '{filename}': {e}"))?;
+    let format = ConfigFormat::from_path(&path).ok_or_else(|| {
+        format!("Unsupported config file '{filename}': expected .yaml, .yml, .toml or .json")
+    })?;
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Error reading '{filename}': {e}"))?;
+    let data = format
+        .parse(&content)
+        .map_err(|e| format!("Failed to parse '{filename}': {e}"))?;
+
+    let (edited, preserved, count, verb) = match action.as_str() {
+        "get" => {
+            let locations = jsonpath::query_locations(&data, query).map_err(|e| e.to_string())?;
+            return Ok((
+                format!("{} match(es) for {query} in {filename}", locations.len()),
+                crate::tools::json::format_matches(&data, &locations),
+            ));
+        }
+        "set" => {
+            let value = parse_value(body)?;
+            let locations = jsonpath::write_locations(&data, query).map_err(|e| e.to_string())?;
+            let mut expected = data.clone();
+            jsonpath::set_all(&mut expected, query, value.clone()).map_err(|e| e.to_string())?;
+            let (edited, preserved) =
+                apply(format, &content, &locations, Edit::Set(&value), &expected)?;
+            (edited, preserved, locations.len(), "Set")
+        }
+        "remove" | "delete" => {
+            let locations = jsonpath::query_locations(&data, query).map_err(|e| e.to_string())?;
+            if locations.is_empty() {
+                return Err(format!("No values match '{query}' in {filename}"));
+            }
+            let mut expected = data.clone();
+            jsonpath::delete_all(&mut expected, query).map_err(|e| e.to_string())?;
+            let (edited, preserved) = apply(format, &content, &locations, Edit::Remove, &expected)?;
+            (edited, preserved, locations.len(), "Removed")
+        }
+        other => {
+            return Err(format!(
+                "Unknown config-edit action '{other}'. Use get, set or remove"
+            ))
+        }
+    };
+
+    tokio::fs::write(&path, edited)
+        .await
+        .map_err(|e| format!("Error writing '{filename}': {e}"))?;
+
+    let mut summary = format!("{verb} {count} value(s) at {query} in {filename}");
+    if !preserved {
+        summary.push_str(" (file was re-serialized; comments and formatting were not preserved)");
+    }
+    Ok((summary.clone(), summary))
+}
+
+/// Parse the new value from the body; YAML syntax also accepts plain JSON
+fn parse_value(body: &str) -> Result<Value, String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err("The new value is required in the body".to_string());
+    }
+    serde_yaml::from_str(body).map_err(|e| format!("Invalid value in body: {e}"))
+}
+
+/// Apply an edit to the document text, returning the new text and whether
+/// comments and formatting were preserved
+fn apply(
+    format: ConfigFormat,
+    content: &str,
+    locations: &[Vec<Location>],
+    edit: Edit,
+    expected: &Value,
+) -> Result<(String, bool), String> {
+    if locations.iter().any(|location| location.is_empty()) {
+        return Err("Cannot replace or remove the whole document".to_string());
+    }
+
+    // Edit later array elements first so earlier indices stay valid
+    let mut locations = locations.to_vec();
+    locations.sort();
+    locations.dedup();
+    locations.reverse();
+
+    match format {
+        ConfigFormat::Json => Ok((crate::tools::json::serialize_like(expected, content)?, true)),
+        ConfigFormat::Toml => Ok((toml_apply(content, &locations, &edit)?, true)),
+        ConfigFormat::Yaml => {
+            if let Some(edited) = yaml_apply_in_place(content, &locations, &edit) {
+                // Only keep the line-based edit if it produces exactly the expected data
+                if serde_yaml::from_str::<Value>(&edited).ok().as_ref() == Some(expected) {
+                    return Ok((edited, true));
+                }
+            }
+            serde_yaml::to_string(expected)
+                .map(|yaml| (yaml, false))
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// TOML
+// ---------------------------------------------------------------------------
+
+fn toml_apply(content: &str, locations: &[Vec<Location>], edit: &Edit) -> Result<String, String> {
+    let mut doc: toml_edit::DocumentMut = content.parse().map_err(|e| format!("{e}"))?;
+
+    for location in locations {
+        let (last, parent) = location.split_last().unwrap();
+        let parent_item = parent
+            .iter()
+            .try_fold(doc.as_item_mut(), |item, step| match step {
+                Location::Key(key) => item.get_mut(key.as_str()),
+                Location::Index(index) => item.get_mut(*index),
+            })
+            .ok_or_else(|| format!("Path {} not found", jsonpath::format_location(parent)))?;
+
+        match edit {
+            Edit::Set(value) => toml_set(parent_item, last, value)?,
+            Edit::Remove => toml_remove(parent_item, last)?,
+        }
+    }
+
+    Ok(doc.to_string())
+}
+
+fn toml_set(parent: &mut toml_edit::Item, last: &Location, value: &Value) -> Result<(), String> {
+    match last {
+        Location::Key(key) => {
+            let table = parent
+                .as_table_like_mut()
+                .ok_or_else(|| format!("Cannot set '{key}': parent is not a table"))?;
+            match table.get_mut(key) {
+                Some(existing) => {
+                    let new_item = if existing.is_table() && value.is_object() {
+                        toml_edit::Item::Table(json_to_toml_table(value)?)
+                    } else {
+                        toml_edit::Item::Value(json_to_toml(value)?)
+                    };
+                    replace_keeping_decor(existing, new_item);
+                }
+                None => {
+                    table.insert(key, toml_edit::Item::Value(json_to_toml(value)?));
+                }
+            }
+            Ok(())
+        }
+        Location::Index(index) => {
+            if let Some(array) = parent.as_array_mut() {
+                if *index < array.len() {
+                    array.replace(*index, json_to_toml(value)?);
+                    return Ok(());
+                }
+            } else if let Some(tables) = parent.as_array_of_tables_mut() {
+                if let Some(table) = tables.get_mut(*index) {
+                    *table = json_to_toml_table(value)?;
+                    return Ok(());
+                }
+            }
+            Err(format!("Index {index} not found"))
+        }
+    }
+}
+
+fn toml_remove(parent: &mut toml_edit::Item, last: &Location) -> Result<(), String> {
+    let removed = match last {
+        Location::Key(key) => parent
+            .as_table_like_mut()
+            .and_then(|table| table.remove(key))
+            .is_some(),
+        Location::Index(index) => {
+            if let Some(array) = parent.as_array_mut().filter(|a| *index < a.len()) {
+                let removed = array.remove(*index);
+                // The new first element takes over the leading whitespace
+                if *index == 0 {
+                    if let Some(first) = array.get_mut(0) {
+                        *first.decor_mut() = removed.decor().clone();
+                    }
+                }
+                true
+            } else if let Some(tables) =
+                parent.as_array_of_tables_mut().filter(|t| *index < t.len())
+            {
+                tables.remove(*index);
+                true
+            } else {
+                false
+            }
+        }
+    };
+
+    if removed {
+        Ok(())
+    } else {
+        Err(format!("{last:?} not found"))
+    }
+}
+
+/// Replace an item, keeping the comments and whitespace around the old value
+fn replace_keeping_decor(existing: &mut toml_edit::Item, new_item: toml_edit::Item) {
+    match (existing, new_item) {
+        (toml_edit::Item::Value(old), toml_edit::Item::Value(mut new)) => {
+            *new.decor_mut() = old.decor().clone();
+            *old = new;
+        }
+        (toml_edit::Item::Table(old), toml_edit::Item::Table(mut new)) => {
+            *new.decor_mut() = old.decor().clone();
+            *old = new;
+        }
+        (existing, new_item) => *existing = new_item,
+    }
+}
+
+fn json_to_toml(value: &Value) -> Result<toml_edit::Value, String> {
+    Ok(match value {
+        Value::Null => return Err("TOML does not support null values".to_string()),
+        Value::Bool(b) => (*b).into(),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.into(),
+            None => n.as_f64().unwrap_or_default().into(),
+        },
+        Value::String(s) => s.as_str().into(),
+        Value::Array(items) => {
+            let mut array = toml_edit::Array::new();
+            for item in items {
+                array.push(json_to_toml(item)?);
+            }
+            toml_edit::Value::Array(array)
+        }
+        Value::Object(map) => {
+            let mut table = toml_edit::InlineTable::new();
+            for (key, item) in map {
+                table.insert(key.as_str(), json_to_toml(item)?);
+            }
+            toml_edit::Value::InlineTable(table)
+        }
+    })
+}
+
+fn json_to_toml_table(value: &Value) -> Result<toml_edit::Table, String> {
+    let map = value
+        .as_object()
+        .ok_or("Expected an object for a TOML table")?;
+    let mut table = toml_edit::Table::new();
+    for (key, item) in map {
+        table.insert(key, toml_edit::Item::Value(json_to_toml(item)?));
+    }
+    Ok(table)
+}
+
+// ---------------------------------------------------------------------------
+// YAML
+// ---------------------------------------------------------------------------
+
+/// Apply the edit line by line, or return None if the edit needs a full rewrite
+///
+/// Only paths made of mapping keys in block style and single-line scalar values
+/// are handled here.
+fn yaml_apply_in_place(content: &str, locations: &[Vec<Location>], edit: &Edit) -> Option<String> {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+
+    let scalar = match edit {
+        Edit::Set(value) if value.is_object() || value.is_array() => return None,
+        Edit::Set(value) => {
+            let text = serde_yaml::to_string(value).ok()?;
+            let text = text.trim_end();
+            if text.contains('\n') {
+                return None;
+            }
+            Some(text.to_string())
+        }
+        Edit::Remove => None,
+    };
+
+    for location in locations {
+        let keys: Vec<&str> = location
+            .iter()
+            .map(|step| match step {
+                Location::Key(key) => Some(key.as_str()),
+                Location::Index(_) => None,
+            })
+            .collect::<Option<_>>()?;
+
+        match (&scalar, yaml_locate(&lines, &keys)) {
+            (Some(scalar), Some((line, _))) => {
+                lines[line] = yaml_replace_scalar(&lines[line], scalar)?;
+            }
+            (Some(scalar), None) => yaml_insert_key(&mut lines, &keys, scalar)?,
+            (None, Some((line, block_end))) => {
+                // Keep trailing blank lines and comments that belong to the next key
+                let last_content = (line..block_end)
+                    .rev()
+                    .find(|i| is_yaml_content(&lines[*i]))
+                    .unwrap_or(line);
+                lines.drain(line..=last_content);
+            }
+            (None, None) => return None,
+        }
+    }
+
+    let mut edited = lines.join("\n");
+    if content.ends_with('\n') {
+        edited.push('\n');
+    }
+    Some(edited)
+}
+
+fn yaml_indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Whether a line carries content (not blank, a comment or a document marker)
+fn is_yaml_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('#') && trimmed != "---" && trimmed != "..."
+}
+
+/// Split a block mapping line into its key and the text after the colon
+fn yaml_key(line: &str) -> Option<(String, &str)> {
+    let trimmed = line.trim_start();
+    if let Some(quote) = trimmed.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let close = trimmed[1..].find(quote)? + 1;
+        let rest = trimmed[close + 1..].strip_prefix(':')?;
+        return Some((trimmed[1..close].to_string(), rest));
+    }
+    if trimmed.starts_with("- ") || trimmed == "-" {
+        return None;
+    }
+
+    let colon = trimmed
+        .char_indices()
+        .find(|(i, c)| {
+            *c == ':' && (trimmed[i + 1..].is_empty() || trimmed[i + 1..].starts_with(' '))
+        })
+        .map(|(i, _)| i)?;
+    Some((
+        trimmed[..colon].trim_end().to_string(),
+        &trimmed[colon + 1..],
+    ))
+}
+
+/// Find the line of a key path and the end of its child block
+fn yaml_locate(lines: &[String], keys: &[&str]) -> Option<(usize, usize)> {
+    let mut start = 0;
+    let mut end = lines.len();
+    let mut found = None;
+
+    for key in keys {
+        let block_indent = (start..end)
+            .find(|i| is_yaml_content(&lines[*i]))
+            .map(|i| yaml_indent(&lines[i]))?;
+        let line = (start..end).find(|i| {
+            is_yaml_content(&lines[*i])
+                && yaml_indent(&lines[*i]) == block_indent
+                && yaml_key(&lines[*i]).is_some_and(|(k, _)| k == *key)
+        })?;
+
+        start = line + 1;
+        end = (start..end)
+            .find(|i| is_yaml_content(&lines[*i]) && yaml_indent(&lines[*i]) <= block_indent)
+            .unwrap_or(end);
+        found = Some((line, end));
+    }
+
+    found
+}
+
+/// Replace the scalar value on a `key: value  # comment` line
+fn yaml_replace_scalar(line: &str, scalar: &str) -> Option<String> {
+    let (_, rest) = yaml_key(line)?;
+    let value = rest.trim_start();
+    if value.is_empty() || value.starts_with(['|', '>', '&', '*', '!', '{', '[']) {
+        return None;
+    }
+
+    // Locate a trailing comment outside of quotes
+    let comment_start = if value.starts_with(['"', '\'']) {
+        let quote = value.chars().next()?;
+        let close = value[1..].find(quote)? + 1;
+        value[close + 1..].find(" #").map(|i| close + 1 + i)
+    } else {
+        value.find(" #")
+    };
+    let comment = comment_start.map(|i| &value[i..]).unwrap_or("");
+    let value_trailing = value[..comment_start.unwrap_or(value.len())].len()
+        - value[..comment_start.unwrap_or(value.len())]
+            .trim_end()
+            .len();
+
+    let prefix_len = line.len() - value.len();
+    Some(format!(
+        "{}{}{}{}",
+        &line[..prefix_len],
+        scalar,
+        " ".repeat(value_trailing),
+        comment
+    ))
+}
+
+/// Insert `key: scalar` into the mapping addressed by all but the last key
+fn yaml_insert_key(lines: &mut Vec<String>, keys: &[&str], scalar: &str) -> Option<()> {
+    let (key, parent_keys) = keys.split_last()?;
+
+    let (insert_at, indent) = if parent_keys.is_empty() {
+        let indent = lines
+            .iter()
+            .find(|line| is_yaml_content(line))
+            .map(|line| yaml_indent(line))
+            .unwrap_or(0);
+        let last_content = lines.iter().rposition(|line| is_yaml_content(line));
+        (last_content.map_or(lines.len(), |i| i + 1), indent)
+    } else {
+        let (parent_line, block_end) = yaml_locate(lines, parent_keys)?;
+        let (_, rest) = yaml_key(&lines[parent_line])?;
+        // The parent must be a block mapping (nothing after the colon)
+        if !rest.trim().is_empty() && !rest.trim_start().starts_with('#') {
+            return None;
+        }
+        let children: Vec<usize> = (parent_line + 1..block_end)
+            .filter(|i| is_yaml_content(&lines[*i]))
+            .collect();
+        match (children.first(), children.last()) {
+            (Some(first), Some(last)) => (last + 1, yaml_indent(&lines[*first])),
+            _ => (parent_line + 1, yaml_indent(&lines[parent_line]) + 2),
+        }
+    };
+
+    let needs_quotes =
+        key.is_empty() || key.contains([':', '#', '"', '\'']) || key.starts_with(' ');
+    let key_text = if needs_quotes {
+        serde_json::to_string(key).ok()?
+    } else {
+        key.to_string()
+    };
+    lines.insert(
+        insert_at,
+        format!("{}{}: {}", " ".repeat(indent), key_text, scalar),
+    );
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn edit(
+        format: ConfigFormat,
+        content: &str,
+        query: &str,
+        value: Option<Value>,
+    ) -> (String, bool) {
+        let data = format.parse(content).unwrap();
+        let mut expected = data.clone();
+        match &value {
+            Some(value) => {
+                let locations = jsonpath::write_locations(&data, query).unwrap();
+                jsonpath::set_all(&mut expected, query, value.clone()).unwrap();
+                apply(format, content, &locations, Edit::Set(value), &expected).unwrap()
+            }
+            None => {
+                let locations = jsonpath::query_locations(&data, query).unwrap();
+                jsonpath::delete_all(&mut expected, query).unwrap();
+                apply(format, content, &locations, Edit::Remove, &expected).unwrap()
+            }
+        }
+    }
+
+    #[test]
+    fn test_toml_edit_keeps_comments() {
+        let content = "# Server settings\n[server]\nport = 8080 # public port\nhost = \"0.0.0.0\"\n\n[features]\nlist = [\"a\", \"b\"]\n";
+
+        let (edited, preserved) = edit(
+            ConfigFormat::Toml,
+            content,
+            "$.server.port",
+            Some(json!(9090)),
+        );
+        assert!(preserved);
+        assert!(edited.contains("# Server settings"));
+        assert!(edited.contains("port = 9090 # public port"));
+
+        let (edited, _) = edit(
+            ConfigFormat::Toml,
+            content,
+            "$.server.debug",
+            Some(json!(true)),
+        );
+        assert!(edited.contains("debug = true"));
+
+        let (edited, _) = edit(ConfigFormat::Toml, content, "$.features.list[0]", None);
+        assert!(edited.contains("list = [\"b\"]"));
+    }
+
+    #[test]
+    fn test_yaml_edit_in_place() {
+        let content = "# App config\nserver:\n  port: 8080  # public port\n  host: localhost\nlogging:\n  level: info\n";
+
+        let (edited, preserved) = edit(
+            ConfigFormat::Yaml,
+            content,
+            "$.server.port",
+            Some(json!(9090)),
+        );
+        assert!(preserved);
+        assert_eq!(
+            edited,
+            "# App config\nserver:\n  port: 9090  # public port\n  host: localhost\nlogging:\n  level: info\n"
+        );
+
+        let (edited, preserved) = edit(
+            ConfigFormat::Yaml,
+            content,
+            "$.logging.format",
+            Some(json!("json")),
+        );
+        assert!(preserved);
+        assert!(edited.ends_with("logging:\n  level: info\n  format: json\n"));
+
+        let (edited, preserved) = edit(ConfigFormat::Yaml, content, "$.server.host", None);
+        assert!(preserved);
+        assert!(!edited.contains("host"));
+        assert!(edited.contains("# App config"));
+    }
+
+    #[test]
+    fn test_yaml_falls_back_to_rewrite() {
+        let content = "items:\n  - a\n  - b\n";
+        let (edited, preserved) = edit(ConfigFormat::Yaml, content, "$.items[0]", Some(json!("c")));
+        assert!(!preserved);
+        assert_eq!(
+            serde_yaml::from_str::<Value>(&edited).unwrap(),
+            json!({"items": ["c", "b"]})
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Text extraction for binary document formats (PDF and DOCX)
+//!
+//! Used by the read and fetch tools so that documents come back as readable
+//! text instead of binary data. PDF text is split into pages with
+//! `--- Page N ---` markers so agents can refer to page numbers.
+
+use docx_rs::{
+    DocumentChild, ParagraphChild, RunChild, TableCellContent, TableChild, TableRowChild,
+};
+
+/// Document formats that can be converted to text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DocumentKind {
+    Pdf,
+    Docx,
+}
+
+impl DocumentKind {
+    /// Detect the document kind from a file extension
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "pdf" => Some(Self::Pdf),
+            "docx" => Some(Self::Docx),
+            _ => None,
+        }
+    }
+
+    /// Detect the document kind from a HTTP content type, falling back to the URL path
+    pub fn detect(content_type: &str, url: &str) -> Option<Self> {
+        let content_type = content_type.to_lowercase();
+        if content_type.contains("application/pdf") {
+            return Some(Self::Pdf);
+        }
+        if content_type.contains("wordprocessingml.document") {
+            return Some(Self::Docx);
+        }
+
+        // Servers often send documents as application/octet-stream
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        path.rsplit_once('.')
+            .and_then(|(_, extension)| Self::from_extension(extension))
+    }
+
+    /// Extract the text of a document
+    pub fn extract_text(self, bytes: &[u8]) -> Result<String, String> {
+        match self {
+            Self::Pdf => extract_pdf_text(bytes),
+            Self::Docx => extract_docx_text(bytes),
+        }
+    }
+}
+
+/// Extract text from a PDF, one `--- Page N ---` section per page
+pub fn extract_pdf_text(bytes: &[u8]) -> Result<String, String> {
+    let pages = pdf_extract::extract_text_from_mem_by_pages(bytes)
+        .map_err(|e| format!("Failed to extract text from PDF: {e}"))?;
+
+    if pages.iter().all(|page| page.trim().is_empty()) {
+        return Err("The PDF contains no extractable text (it may be a scanned image)".to_string());
+    }
+
+    Ok(format_pages(&pages))
+}
+
+fn format_pages(pages: &[String]) -> String {
+    pages
+        .iter()
+        .enumerate()
+        .map(|(index, page)| format!("--- Page {} ---\n{}", index + 1, page.trim()))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Extract paragraph and table text from a DOCX document
+pub fn extract_docx_text(bytes: &[u8]) -> Result<String, String> {
+    let docx =
+        docx_rs::read_docx(bytes).map_err(|e| format!("Failed to read DOCX document: {e}"))?;
+
+    let mut lines = Vec::new();
+    for child in &docx.document.children {
+        match child {
+            DocumentChild::Paragraph(paragraph) => lines.push(paragraph_text(&paragraph.children)),
+            DocumentChild::Table(table) => {
+                for TableChild::TableRow(row) in &table.rows {
+                    let cells: Vec<String> = row
+                        .cells
+                        .iter()
+                        .map(|TableRowChild::TableCell(cell)| {
+                            cell.children
+                                .iter()
+                                .filter_map(|content| match content {
+                                    TableCellContent::Paragraph(paragraph) => {
+                                        Some(paragraph_text(&paragraph.children))
+                                    }
+                                    _ => None,
+                                })
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        })
+                        .collect();
+                    lines.push(format!("| {} |", cells.join(" | ")));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
+
+fn paragraph_text(children: &[ParagraphChild]) -> String {
+    let mut text = String::new();
+    for child in children {
+        let runs: Vec<&docx_rs::Run> = match child {
+            ParagraphChild::Run(run) => vec![run.as_ref()],
+            ParagraphChild::Hyperlink(link) => link
+                .children
+                .iter()
+                .filter_map(|child| match child {
+                    ParagraphChild::Run(run) => Some(run.as_ref()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        for run in runs {
+            for run_child in &run.children {
+                match run_child {
+                    RunChild::Text(t) => text.push_str(&t.text),
+                    RunChild::Tab(_) => text.push('\t'),
+                    RunChild::Break(_) => text.push('\n'),
+                    _ => {}
+                }
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_document_kind() {
+        assert_eq!(DocumentKind::from_extension("PDF"), Some(DocumentKind::Pdf));
+        assert_eq!(DocumentKind::from_extension("txt"), None);
+        assert_eq!(
+            DocumentKind::detect("application/pdf", "https://example.com/download"),
+            Some(DocumentKind::Pdf)
+        );
+        assert_eq!(
+            DocumentKind::detect(
+                "application/octet-stream",
+                "https://example.com/spec.docx?v=2"
+            ),
+            Some(DocumentKind::Docx)
+        );
+        assert_eq!(
+            DocumentKind::detect("text/html", "https://example.com/"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_format_pages() {
+        let pages = vec!["First page\n".to_string(), "  Second page".to_string()];
+        assert_eq!(
+            format_pages(&pages),
+            "--- Page 1 ---\nFirst page\n\n--- Page 2 ---\nSecond page"
+        );
+    }
+
+    #[test]
+    fn test_extract_docx_text() {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        docx_rs::Docx::new()
+            .add_paragraph(
+                docx_rs::Paragraph::new().add_run(docx_rs::Run::new().add_text("Hello world")),
+            )
+            .build()
+            .pack(&mut buffer)
+            .unwrap();
+
+        let text = extract_docx_text(buffer.get_ref()).unwrap();
+        assert_eq!(text.trim(), "Hello world");
+    }
+}
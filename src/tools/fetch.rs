@@ -1,5 +1,5 @@
 use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
-use crate::tools::ToolResult;
+use crate::tools::{fetch_cache, network, ToolResult};
  // Already present, but good to ensure
 use scraper::{Html, Selector}; // Import scraper types
  // For timing if needed later
@@ -188,6 +188,22 @@ mod scraper_tests {
         assert_eq!(extract_text_with_scraper(html), expected);
     }
 
+    #[test]
+    fn test_parse_fetch_args() {
+        let parsed =
+            parse_fetch_args("https://example.com format=text links=false --fresh").unwrap();
+        assert_eq!(parsed.url, "https://example.com");
+        assert_eq!(parsed.format, FetchFormat::Text);
+        assert!(!parsed.keep_links);
+        assert!(parsed.fresh);
+
+        let parsed = parse_fetch_args("https://example.com").unwrap();
+        assert_eq!(parsed.format, FetchFormat::Markdown);
+        assert!(parsed.keep_links);
+
+        assert!(parse_fetch_args("https://example.com format=pdf").is_err());
+    }
+
      #[test]
     fn test_complex_structure() {
         let html = r#"
@@ -206,76 +222,227 @@ mod scraper_tests {
      }
 }
 
-/// Extract URL from arguments
-fn parse_fetch_args(args: &str) -> String {
-    // Just take the first non-empty argument as the URL
-    args.split_whitespace().next().unwrap_or("").to_string()
+/// How fetched HTML pages are returned
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FetchFormat {
+    /// Main content converted to markdown
+    Markdown,
+    /// Plain text of the whole page
+    Text,
+    /// The unprocessed HTML
+    Raw,
 }
 
-pub async fn execute_fetch(args: &str, _body: &str, silent_mode: bool) -> ToolResult {
-    // Parse arguments - just get the URL
-    let url = parse_fetch_args(args);
+/// Parsed arguments for the fetch tool
+#[derive(Debug, PartialEq)]
+struct FetchArgs {
+    url: String,
+    format: FetchFormat,
+    keep_links: bool,
+    /// Bypass the fetch cache
+    fresh: bool,
+}
 
-    // Check if URL is provided and valid
-    if url.is_empty() {
-        let error_msg = "Error: No URL provided. Usage: fetch https://example.com".to_string();
+/// Parse `URL [format=markdown|text|raw] [links=true|false] [--fresh]`
+fn parse_fetch_args(args: &str) -> Result<FetchArgs, String> {
+    let mut fetch_args = FetchArgs {
+        url: String::new(),
+        format: FetchFormat::Markdown,
+        keep_links: true,
+        fresh: false,
+    };
 
-        if !silent_mode {
-            bprintln !(error:"{error_msg}");
+    for part in args.split_whitespace() {
+        if let Some(format) = part.strip_prefix("format=") {
+            fetch_args.format = match format {
+                "markdown" | "md" => FetchFormat::Markdown,
+                "text" => FetchFormat::Text,
+                "raw" | "html" => FetchFormat::Raw,
+                other => {
+                    return Err(format!(
+                        "Unknown format '{other}'. Use format=markdown, format=text or format=raw"
+                    ))
+                }
+            };
+        } else if let Some(links) = part.strip_prefix("links=") {
+            fetch_args.keep_links = match links {
+                "true" | "yes" | "keep" => true,
+                "false" | "no" | "drop" => false,
+                other => {
+                    return Err(format!(
+                        "Invalid links value '{other}'. Use links=true or links=false"
+                    ))
+                }
+            };
+        } else if part == "--fresh" {
+            fetch_args.fresh = true;
+        } else if fetch_args.url.is_empty() {
+            fetch_args.url = part.to_string();
         }
-
-        return ToolResult::error(error_msg);
     }
 
-    // Make the request using reqwest
-    let client = reqwest::Client::new();
-    let response = match client.get(&url).send().await {
-        Ok(response) => response,
-        Err(err) => {
-            if !silent_mode {
-                bprintln !(error:"Error fetching URL: {err}");
-            }
+    Ok(fetch_args)
+}
 
-            return ToolResult::error(format!("Error fetching URL: {err}"));
-        }
+/// A response body with its content type
+struct FetchedPage {
+    content_type: String,
+    body: Vec<u8>,
+    from_cache: bool,
+}
+
+/// Download a URL, using and updating the fetch cache unless `fresh` is set
+async fn fetch_page(url: &str, fresh: bool) -> Result<FetchedPage, String> {
+    use reqwest::header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+
+    network::check_url("fetch", url).await?;
+
+    let cached = if fetch_cache::is_enabled() && !fresh {
+        fetch_cache::load(url)
+    } else {
+        None
     };
+    if let Some(page) = cached.as_ref().filter(|page| page.is_fresh()) {
+        return Ok(FetchedPage {
+            content_type: page.content_type.clone(),
+            body: page.body.clone(),
+            from_cache: true,
+        });
+    }
 
-    // Check status code
-    if !response.status().is_success() {
-        if !silent_mode {
-            bprintln !(error:"Error fetching URL: HTTP status {}", response.status());
+    // Revalidate stale entries instead of downloading them again
+    let client = network::client();
+    let mut request = client.get(url);
+    if let Some(page) = &cached {
+        if let Some(etag) = &page.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &page.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
         }
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|err| format!("Error fetching URL: {err}"))?;
+
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some(page) = cached.filter(|page| page.can_revalidate()) {
+            fetch_cache::touch(url);
+            return Ok(FetchedPage {
+                content_type: page.content_type,
+                body: page.body,
+                from_cache: true,
+            });
+        }
+    }
 
-        return ToolResult::error(format!(
+    if !response.status().is_success() {
+        return Err(format!(
             "Error fetching URL: HTTP status {}",
             response.status()
         ));
     }
 
-    // Try to get content type
-    let content_type = response
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("text/html")
-        .to_string();
-
-    // Get text content
-    let text = match response.text().await {
-        Ok(text) => text,
-        Err(err) => {
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let content_type = header(CONTENT_TYPE).unwrap_or_else(|| "text/html".to_string());
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+
+    let body = network::read_body(response).await?;
+
+    if fetch_cache::is_enabled() {
+        fetch_cache::store(url, &content_type, etag, last_modified, &body);
+    }
+
+    Ok(FetchedPage {
+        content_type,
+        body,
+        from_cache: false,
+    })
+}
+
+pub async fn execute_fetch(args: &str, _body: &str, silent_mode: bool) -> ToolResult {
+    let FetchArgs {
+        url,
+        format,
+        keep_links,
+        fresh,
+    } = match parse_fetch_args(args) {
+        Ok(fetch_args) => fetch_args,
+        Err(error_msg) => {
             if !silent_mode {
-                bprintln !(error:"Error reading response: {err}");
+                bprintln !(error:"{error_msg}");
             }
+            return ToolResult::error(error_msg);
+        }
+    };
+
+    // Check if URL is provided and valid
+    if url.is_empty() {
+        let error_msg = "Error: No URL provided. Usage: fetch https://example.com".to_string();
+
+        if !silent_mode {
+            bprintln !(error:"{error_msg}");
+        }
+
+        return ToolResult::error(error_msg);
+    }
 
-            return ToolResult::error(format!("Error reading response: {err}"));
+    let page = match fetch_page(&url, fresh).await {
+        Ok(page) => page,
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln !(error:"{error_msg}");
+            }
+            return ToolResult::error(error_msg);
         }
     };
+    let content_type = page.content_type;
+    let source = if page.from_cache { " (cached)" } else { "" };
+
+    // Documents are converted to text with page markers
+    if let Some(kind) = crate::tools::documents::DocumentKind::detect(&content_type, &url) {
+        let body = page.body;
+        let extracted = tokio::task::spawn_blocking(move || kind.extract_text(&body))
+            .await
+            .unwrap_or_else(|e| Err(format!("Text extraction failed: {e}")));
+
+        return match extracted {
+            Ok(text) => {
+                if !silent_mode {
+                    bprintln !(tool: "fetch",
+                        "{FORMAT_BOLD}🌐 Fetch:{FORMAT_RESET} {url} - Extracted text from {kind:?} document{source}"
+                    );
+                    bprintln !(dev: "{FORMAT_GRAY}{text}{FORMAT_RESET}");
+                }
+                ToolResult::success(format!("Fetched from {url}:\n\n{text}"))
+            }
+            Err(error_msg) => {
+                if !silent_mode {
+                    bprintln !(error:"{error_msg}");
+                }
+                ToolResult::error(error_msg)
+            }
+        };
+    }
+
+    let text = String::from_utf8_lossy(&page.body).into_owned();
 
     // Process text based on content type
     let processed_text = if content_type.contains("text/html") || content_type.contains("html") {
-        // Use the new scraper-based function
-        extract_text_with_scraper(&text)
+        match format {
+            FetchFormat::Markdown => super::html::html_to_markdown(&text, Some(&url), keep_links),
+            FetchFormat::Text => extract_text_with_scraper(&text),
+            FetchFormat::Raw => text,
+        }
     } else {
         // For plain text, JSON, or other formats, use as-is
         text
@@ -287,7 +454,7 @@ pub async fn execute_fetch(args: &str, _body: &str, silent_mode: bool) -> ToolRe
     // Return the fetched content
     if !silent_mode {
         bprintln !(tool: "fetch",
-            "{FORMAT_BOLD}🌐 Fetch:{FORMAT_RESET} {url} - Content fetched successfully"
+            "{FORMAT_BOLD}🌐 Fetch:{FORMAT_RESET} {url} - Content fetched successfully{source}"
         );
         bprintln !(dev: "{FORMAT_GRAY}{user_text}{FORMAT_RESET}");
     }
@@ -0,0 +1,201 @@
+//! Disk cache for pages retrieved by the fetch tool
+//!
+//! Responses are stored under `.termineer/cache/fetch/`, keyed by a hash of the
+//! URL: a `.json` file with the headers needed for revalidation and a `.body`
+//! file with the raw response. Entries younger than the TTL are served without
+//! a request; older entries are revalidated with `If-None-Match` /
+//! `If-Modified-Since` so unchanged pages are not downloaded again.
+//!
+//! The cache is enabled by default with a 1 hour TTL; `--no-fetch-cache`
+//! disables it, `--fetch-cache-ttl` changes the TTL and `fetch URL --fresh`
+//! bypasses it for a single request.
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Directory where fetched pages are stored, relative to the working directory
+pub const CACHE_DIR: &str = ".termineer/cache/fetch";
+
+/// Default time-to-live before a cached page is revalidated
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Global cache settings
+struct CacheSettings {
+    enabled: bool,
+    ttl: Duration,
+}
+
+lazy_static! {
+    static ref CACHE_SETTINGS: RwLock<CacheSettings> = RwLock::new(CacheSettings {
+        enabled: true,
+        ttl: DEFAULT_TTL,
+    });
+}
+
+/// Configure the fetch cache
+pub fn configure(enabled: bool, ttl: Option<Duration>) {
+    let mut settings = CACHE_SETTINGS.write().unwrap();
+    settings.enabled = enabled;
+    settings.ttl = ttl.unwrap_or(DEFAULT_TTL);
+}
+
+/// Whether the fetch cache is enabled
+pub fn is_enabled() -> bool {
+    CACHE_SETTINGS.read().unwrap().enabled
+}
+
+/// Metadata of a cached response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheMetadata {
+    url: String,
+    /// Unix timestamp (seconds) when the entry was written or last revalidated
+    created_at: u64,
+    content_type: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// A response loaded from the cache
+#[derive(Debug, Clone)]
+pub struct CachedPage {
+    pub content_type: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: Vec<u8>,
+    created_at: u64,
+}
+
+impl CachedPage {
+    /// Whether the entry can be used without revalidating it
+    pub fn is_fresh(&self) -> bool {
+        now_secs().saturating_sub(self.created_at) < CACHE_SETTINGS.read().unwrap().ttl.as_secs()
+    }
+
+    /// Whether the server gave us anything to revalidate the entry with
+    pub fn can_revalidate(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Paths of the metadata and body files for a URL
+fn entry_paths(url: &str) -> (PathBuf, PathBuf) {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    let digest = format!("{:016x}", hasher.finish());
+    let dir = PathBuf::from(CACHE_DIR);
+    (
+        dir.join(format!("{digest}.json")),
+        dir.join(format!("{digest}.body")),
+    )
+}
+
+/// Load the cached response for a URL, if any (expired entries are returned too)
+pub fn load(url: &str) -> Option<CachedPage> {
+    let (metadata_path, body_path) = entry_paths(url);
+    let metadata: CacheMetadata =
+        serde_json::from_str(&std::fs::read_to_string(metadata_path).ok()?).ok()?;
+    if metadata.url != url {
+        return None;
+    }
+    let body = std::fs::read(body_path).ok()?;
+
+    Some(CachedPage {
+        content_type: metadata.content_type,
+        etag: metadata.etag,
+        last_modified: metadata.last_modified,
+        body,
+        created_at: metadata.created_at,
+    })
+}
+
+/// Store a response, reporting but otherwise ignoring failures
+pub fn store(
+    url: &str,
+    content_type: &str,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: &[u8],
+) {
+    let (metadata_path, body_path) = entry_paths(url);
+    let metadata = CacheMetadata {
+        url: url.to_string(),
+        created_at: now_secs(),
+        content_type: content_type.to_string(),
+        etag,
+        last_modified,
+    };
+
+    let result = std::fs::create_dir_all(CACHE_DIR)
+        .and_then(|_| std::fs::write(&body_path, body))
+        .and_then(|_| write_metadata(&metadata_path, &metadata));
+    if let Err(e) = result {
+        bprintln!(debug: "Failed to write fetch cache entry for {}: {}", url, e);
+    }
+}
+
+/// Mark a cached entry as fresh again after the server answered 304 Not Modified
+pub fn touch(url: &str) {
+    let (metadata_path, _) = entry_paths(url);
+    let Some(mut metadata) = std::fs::read_to_string(&metadata_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<CacheMetadata>(&content).ok())
+    else {
+        return;
+    };
+
+    metadata.created_at = now_secs();
+    if let Err(e) = write_metadata(&metadata_path, &metadata) {
+        bprintln!(debug: "Failed to update fetch cache entry for {}: {}", url, e);
+    }
+}
+
+fn write_metadata(path: &std::path::Path, metadata: &CacheMetadata) -> std::io::Result<()> {
+    let json = serde_json::to_string(metadata)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    std::fs::write(path, json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_paths_depend_on_url() {
+        let (json_a, body_a) = entry_paths("https://example.com/a");
+        let (json_b, _) = entry_paths("https://example.com/b");
+        assert_ne!(json_a, json_b);
+        assert_eq!(json_a.with_extension("body"), body_a);
+        assert!(json_a.starts_with(CACHE_DIR));
+    }
+
+    #[test]
+    fn test_freshness() {
+        let page = CachedPage {
+            content_type: "text/html".to_string(),
+            etag: None,
+            last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".to_string()),
+            body: Vec::new(),
+            created_at: now_secs(),
+        };
+        assert!(page.is_fresh());
+        assert!(page.can_revalidate());
+
+        let stale = CachedPage {
+            created_at: 0,
+            ..page
+        };
+        assert!(!stale.is_fresh());
+    }
+}
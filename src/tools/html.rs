@@ -0,0 +1,459 @@
+//! HTML to markdown conversion for the fetch tool
+//!
+//! Web pages are reduced to their main content (the `<main>` element, a single
+//! `<article>` or the body) with navigation, scripts and other page chrome removed,
+//! and rendered as markdown: headings, lists, links, emphasis, tables and fenced
+//! code blocks. This keeps fetched pages readable while using far fewer tokens
+//! than raw HTML.
+
+use reqwest::Url;
+use scraper::{ElementRef, Html, Node, Selector};
+
+/// Elements that never contribute useful content
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "script", "style", "noscript", "nav", "aside", "footer", "form", "button", "svg", "iframe",
+    "template", "select", "input", "canvas", "head",
+];
+
+/// Elements rendered as separate paragraphs
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "header",
+    "figure",
+    "figcaption",
+    "address",
+    "details",
+    "summary",
+    "center",
+    "dl",
+];
+
+/// Convert an HTML page to markdown
+///
+/// With `keep_links` links and images are kept as markdown links (relative URLs are
+/// resolved against `base_url`); otherwise only their text is kept.
+pub fn html_to_markdown(html: &str, base_url: Option<&str>, keep_links: bool) -> String {
+    let document = Html::parse_document(html);
+    let mut writer = Writer::new(keep_links, base_url.and_then(|url| Url::parse(url).ok()));
+    writer.children(main_content(&document));
+    writer.finish()
+}
+
+/// Pick the element holding the main content of the page
+fn main_content(document: &Html) -> ElementRef<'_> {
+    for selector in ["main", "[role=main]", "article"] {
+        let selector = Selector::parse(selector).unwrap();
+        let mut matches = document.select(&selector);
+        if let (Some(element), None) = (matches.next(), matches.next()) {
+            if element.text().any(|text| !text.trim().is_empty()) {
+                return element;
+            }
+        }
+    }
+
+    let body = Selector::parse("body").unwrap();
+    document
+        .select(&body)
+        .next()
+        .unwrap_or(document.root_element())
+}
+
+/// Incremental markdown writer tracking line prefixes for lists and quotes
+struct Writer {
+    out: String,
+    /// Prefixes applied at the start of every line (list indentation, `> ` for quotes)
+    prefix: Vec<&'static str>,
+    /// Whether nothing has been written on the current line yet
+    line_empty: bool,
+    /// Set right after a list marker so nested blocks start on the marker line
+    item_started: bool,
+    list_depth: usize,
+    keep_links: bool,
+    base_url: Option<Url>,
+}
+
+impl Writer {
+    fn new(keep_links: bool, base_url: Option<Url>) -> Self {
+        Self {
+            out: String::new(),
+            prefix: Vec::new(),
+            line_empty: true,
+            item_started: false,
+            list_depth: 0,
+            keep_links,
+            base_url,
+        }
+    }
+
+    /// Write text verbatim on the current line
+    fn write(&mut self, text: &str) {
+        if self.line_empty {
+            for prefix in &self.prefix {
+                self.out.push_str(prefix);
+            }
+            self.line_empty = false;
+        }
+        if !text.is_empty() {
+            self.item_started = false;
+        }
+        self.out.push_str(text);
+    }
+
+    /// Write inline text, collapsing whitespace like a browser would
+    fn text(&mut self, raw: &str) {
+        let mut collapsed = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+        if raw.starts_with(char::is_whitespace) {
+            collapsed.insert(0, ' ');
+        }
+        if raw.ends_with(char::is_whitespace) && !collapsed.ends_with(' ') {
+            collapsed.push(' ');
+        }
+
+        let text = if self.line_empty || self.item_started || self.out.ends_with(' ') {
+            collapsed.trim_start()
+        } else {
+            collapsed.as_str()
+        };
+        if !text.is_empty() {
+            self.write(text);
+        }
+    }
+
+    fn newline(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        self.out.push('\n');
+        self.line_empty = true;
+    }
+
+    /// End the current line if anything was written on it
+    fn line_break(&mut self) {
+        if !self.line_empty && !self.item_started {
+            self.newline();
+        }
+    }
+
+    /// Separate blocks with an empty line
+    fn block_break(&mut self) {
+        if self.item_started {
+            return;
+        }
+        self.line_break();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            let prefix = self.prefix.concat();
+            self.out.push_str(prefix.trim_end());
+            self.newline();
+        }
+    }
+
+    /// Render an element's children into a separate single-line string
+    fn render_inline(&self, element: ElementRef) -> String {
+        let mut writer = Writer::new(self.keep_links, self.base_url.clone());
+        writer.children(element);
+        writer.out.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    fn children(&mut self, element: ElementRef) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => self.text(text),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.element(child);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn element(&mut self, element: ElementRef) {
+        let tag = element.value().name().to_lowercase();
+        let tag = tag.as_str();
+
+        if SKIPPED_ELEMENTS.contains(&tag) || element.value().attr("hidden").is_some() {
+            return;
+        }
+
+        match tag {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let text = self.render_inline(element);
+                if !text.is_empty() {
+                    let level = tag[1..].parse().unwrap_or(1);
+                    self.block_break();
+                    self.write(&format!("{} {text}", "#".repeat(level)));
+                    self.block_break();
+                }
+            }
+            "br" => self.newline(),
+            "hr" => {
+                self.block_break();
+                self.write("---");
+                self.block_break();
+            }
+            "pre" => self.code_block(element),
+            "blockquote" => {
+                self.block_break();
+                self.prefix.push("> ");
+                self.children(element);
+                self.line_break();
+                self.prefix.pop();
+                self.block_break();
+            }
+            "ul" | "ol" => self.list(element, tag == "ol"),
+            "table" => self.table(element),
+            "dt" | "dd" | "tr" | "li" => {
+                self.line_break();
+                self.children(element);
+                self.line_break();
+            }
+            "a" => self.link(element),
+            "img" => self.image(element),
+            "code" | "kbd" | "samp" => {
+                let code = element.text().collect::<String>();
+                let code = code.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !code.is_empty() {
+                    let fence = if code.contains('`') { "``" } else { "`" };
+                    self.inline_markup(element, &format!("{fence}{code}{fence}"));
+                }
+            }
+            "strong" | "b" => self.emphasis(element, "**"),
+            "em" | "i" => self.emphasis(element, "*"),
+            "del" | "s" => self.emphasis(element, "~~"),
+            _ if BLOCK_ELEMENTS.contains(&tag) => {
+                self.block_break();
+                self.children(element);
+                self.block_break();
+            }
+            _ => self.children(element),
+        }
+    }
+
+    /// Write inline markup, keeping the whitespace around the element
+    fn inline_markup(&mut self, element: ElementRef, markup: &str) {
+        let text = element.text().collect::<String>();
+        if text.starts_with(char::is_whitespace) {
+            self.text(" ");
+        }
+        self.write(markup);
+        if text.ends_with(char::is_whitespace) {
+            self.text(" ");
+        }
+    }
+
+    fn emphasis(&mut self, element: ElementRef, marker: &str) {
+        let text = self.render_inline(element);
+        if !text.is_empty() {
+            self.inline_markup(element, &format!("{marker}{text}{marker}"));
+        }
+    }
+
+    fn resolve(&self, url: &str) -> String {
+        match &self.base_url {
+            Some(base) => base
+                .join(url)
+                .map(|url| url.to_string())
+                .unwrap_or_else(|_| url.to_string()),
+            None => url.to_string(),
+        }
+    }
+
+    fn link(&mut self, element: ElementRef) {
+        let text = self.render_inline(element);
+        let href = element.value().attr("href").unwrap_or("").trim();
+        let linkable = self.keep_links
+            && !href.is_empty()
+            && !href.starts_with('#')
+            && !href.starts_with("javascript:");
+
+        if linkable && !text.is_empty() {
+            let markup = format!("[{text}]({})", self.resolve(href));
+            self.inline_markup(element, &markup);
+        } else if !text.is_empty() {
+            self.inline_markup(element, &text);
+        }
+    }
+
+    fn image(&mut self, element: ElementRef) {
+        let alt = element.value().attr("alt").unwrap_or("").trim();
+        let src = element.value().attr("src").unwrap_or("").trim();
+        if self.keep_links && !src.is_empty() && !src.starts_with("data:") {
+            let markup = format!("![{alt}]({})", self.resolve(src));
+            self.write(&markup);
+        } else if !alt.is_empty() {
+            self.text(alt);
+        }
+    }
+
+    fn code_block(&mut self, element: ElementRef) {
+        let code_selector = Selector::parse("code").unwrap();
+        let language = std::iter::once(element)
+            .chain(element.select(&code_selector))
+            .filter_map(|el| el.value().attr("class"))
+            .flat_map(|classes| classes.split_whitespace())
+            .find_map(|class| {
+                class
+                    .strip_prefix("language-")
+                    .or_else(|| class.strip_prefix("lang-"))
+            })
+            .unwrap_or("")
+            .to_string();
+
+        let code = element.text().collect::<String>();
+        let code = code.strip_prefix('\n').unwrap_or(&code).trim_end();
+        let fence = if code.contains("```") { "````" } else { "```" };
+
+        self.block_break();
+        self.write(&format!("{fence}{language}"));
+        for line in code.lines() {
+            self.newline();
+            self.write(line);
+        }
+        self.newline();
+        self.write(fence);
+        self.block_break();
+    }
+
+    fn list(&mut self, element: ElementRef, ordered: bool) {
+        if self.list_depth == 0 {
+            self.block_break();
+        } else {
+            self.line_break();
+        }
+        self.list_depth += 1;
+
+        let items = element
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|item| item.value().name().eq_ignore_ascii_case("li"));
+        for (index, item) in items.enumerate() {
+            self.line_break();
+            self.item_started = false;
+            let marker = if ordered {
+                format!("{}. ", index + 1)
+            } else {
+                "- ".to_string()
+            };
+            self.write(&marker);
+            self.item_started = true;
+            self.prefix.push(if ordered { "   " } else { "  " });
+            self.children(item);
+            self.item_started = false;
+            self.prefix.pop();
+            self.line_break();
+        }
+
+        self.list_depth -= 1;
+        if self.list_depth == 0 {
+            self.block_break();
+        }
+    }
+
+    fn table(&mut self, element: ElementRef) {
+        let row_selector = Selector::parse("tr").unwrap();
+        let rows: Vec<Vec<String>> = element
+            .select(&row_selector)
+            .map(|row| {
+                row.children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                    .map(|cell| self.render_inline(cell).replace('|', "\\|"))
+                    .collect::<Vec<_>>()
+            })
+            .filter(|cells| !cells.is_empty())
+            .collect();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+
+        self.block_break();
+        for (index, row) in rows.iter().enumerate() {
+            let mut cells = row.clone();
+            cells.resize(columns, String::new());
+            self.write(&format!("| {} |", cells.join(" | ")));
+            self.newline();
+            if index == 0 {
+                self.write(&format!("|{}", " --- |".repeat(columns)));
+                self.newline();
+            }
+        }
+        self.block_break();
+    }
+
+    fn finish(self) -> String {
+        let mut result = String::new();
+        let mut blank = true;
+        for line in self.out.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                if !blank {
+                    result.push('\n');
+                }
+                blank = true;
+            } else {
+                result.push_str(line);
+                result.push('\n');
+                blank = false;
+            }
+        }
+        result.trim_end().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headings_paragraphs_and_emphasis() {
+        let html = "<html><body><nav><a href=\"/\">Home</a></nav><h1>Title</h1>\
+            <p>Some <strong>bold</strong> and <em>italic</em> text.</p>\
+            <p>Second paragraph with <code>inline()</code> code.</p>\
+            <footer>Copyright</footer></body></html>";
+        assert_eq!(
+            html_to_markdown(html, None, true),
+            "# Title\n\nSome **bold** and *italic* text.\n\nSecond paragraph with `inline()` code."
+        );
+    }
+
+    #[test]
+    fn test_links_and_images() {
+        let html =
+            "<p>See <a href=\"/docs/intro\">the docs</a> <img src=\"logo.png\" alt=\"Logo\"></p>";
+        assert_eq!(
+            html_to_markdown(html, Some("https://example.com/guide/"), true),
+            "See [the docs](https://example.com/docs/intro) ![Logo](https://example.com/guide/logo.png)"
+        );
+        assert_eq!(
+            html_to_markdown(html, Some("https://example.com/"), false),
+            "See the docs Logo"
+        );
+    }
+
+    #[test]
+    fn test_lists_and_code_blocks() {
+        let html = "<ul><li>One</li><li>Two<ul><li>Nested</li></ul></li></ul>\
+            <ol><li>First</li><li>Second</li></ol>\
+            <pre><code class=\"language-rust\">fn main() {\n    println!(\"hi\");\n}\n</code></pre>";
+        assert_eq!(
+            html_to_markdown(html, None, true),
+            "- One\n- Two\n  - Nested\n\n1. First\n2. Second\n\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```"
+        );
+    }
+
+    #[test]
+    fn test_main_content_and_tables() {
+        let html = "<body><div>Sidebar noise</div><main><h2>Data</h2>\
+            <table><tr><th>Name</th><th>Value</th></tr><tr><td>a</td><td>1</td></tr></table>\
+            </main></body>";
+        assert_eq!(
+            html_to_markdown(html, None, true),
+            "## Data\n\n| Name | Value |\n| --- | --- |\n| a | 1 |"
+        );
+    }
+}
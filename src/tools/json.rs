@@ -0,0 +1,176 @@
+//! JSON tool for querying and editing JSON files with JSONPath expressions
+//!
+//! Usage:
+//! - `json get FILE QUERY` - print all values matching the query
+//! - `json set FILE QUERY` - set all matching values to the JSON value in the body
+//! - `json delete FILE QUERY` - remove all matching values
+//!
+//! Queries use the syntax of [`crate::jsonpath::query`], e.g.
+//! `$.dependencies[?(@.version =~ /^0\./)]`.
+
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
+use crate::jsonpath;
+use crate::tools::ToolResult;
+use serde_json::Value;
+use std::path::Path;
+
+/// Execute the json tool
+pub async fn execute_json(args: &str, body: &str, silent_mode: bool) -> ToolResult {
+    match run(args, body).await {
+        Ok((summary, output)) => {
+            if !silent_mode {
+                bprintln !(tool: "json",
+                    "{FORMAT_BOLD}🧩 JSON: {summary}{FORMAT_RESET}\n{FORMAT_GRAY}{}{FORMAT_RESET}",
+                    output.lines().take(10).collect::<Vec<_>>().join("\n")
+                );
+            }
+            ToolResult::success(output)
+        }
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            ToolResult::error(error_msg)
+        }
+    }
+}
+
+/// Run the requested action, returning a short summary and the tool output
+async fn run(args: &str, body: &str) -> Result<(String, String), String> {
+    let mut parts = args.trim().splitn(3, char::is_whitespace);
+    let action = parts.next().unwrap_or("").to_lowercase();
+    let filename = parts.next().unwrap_or("").trim();
+    let query = parts.next().unwrap_or("").trim();
+
+    if action.is_empty() || filename.is_empty() || query.is_empty() {
+        return Err("Usage: json get|set|delete FILE QUERY".to_string());
+    }
+
+    let path = crate::tools::path_utils::validate_path(filename)
+        .map_err(|e| format!("Security error for file '{filename}': {e}"))?;
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Error reading '{filename}': {e}"))?;
+    let mut json: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("'{filename}' is not valid JSON: {e}"))?;
+
+    match action.as_str() {
+        "get" => {
+            let locations = jsonpath::query_locations(&json, query).map_err(|e| e.to_string())?;
+            Ok((
+                format!("{} match(es) for {query} in {filename}", locations.len()),
+                format_matches(&json, &locations),
+            ))
+        }
+        "set" => {
+            let value: Value = serde_json::from_str(body.trim()).map_err(|e| {
+                format!("The body must be a JSON value (quote strings, e.g. \"text\"): {e}")
+            })?;
+            let count = jsonpath::set_all(&mut json, query, value).map_err(|e| e.to_string())?;
+            write_json(&path, &json, &content).await?;
+            let summary = format!("Set {count} value(s) at {query} in {filename}");
+            Ok((summary.clone(), summary))
+        }
+        "delete" | "remove" => {
+            let removed = jsonpath::delete_all(&mut json, query).map_err(|e| e.to_string())?;
+            if removed.is_empty() {
+                return Err(format!("No values match '{query}' in {filename}"));
+            }
+            write_json(&path, &json, &content).await?;
+            let summary = format!(
+                "Removed {} value(s) at {query} from {filename}",
+                removed.len()
+            );
+            Ok((summary.clone(), summary))
+        }
+        other => Err(format!(
+            "Unknown json action '{other}'. Use get, set or delete"
+        )),
+    }
+}
+
+/// Format matches as `location: value` lines, or just the value for a single match
+pub(crate) fn format_matches(json: &Value, locations: &[Vec<jsonpath::Location>]) -> String {
+    let values: Vec<(String, &Value)> = locations
+        .iter()
+        .filter_map(|location| {
+            let path = jsonpath::format_location(location);
+            jsonpath::query(json, &path)
+                .ok()
+                .and_then(|values| values.into_iter().next())
+                .map(|value| (path, value))
+        })
+        .collect();
+
+    match values.as_slice() {
+        [] => "No matches".to_string(),
+        [(_, value)] => serde_json::to_string_pretty(value).unwrap_or_default(),
+        values => values
+            .iter()
+            .map(|(path, value)| format!("{path}: {value}"))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// Write JSON back, keeping the indentation width and trailing newline of the original
+async fn write_json(path: &Path, json: &Value, original: &str) -> Result<(), String> {
+    let output = serialize_like(json, original)?;
+    tokio::fs::write(path, output)
+        .await
+        .map_err(|e| format!("Error writing '{}': {e}", path.display()))
+}
+
+/// Pretty-print JSON with the indentation width and trailing newline of the original text
+pub(crate) fn serialize_like(json: &Value, original: &str) -> Result<String, String> {
+    let indent = detect_indent(original);
+    let mut serialized = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut serialized, formatter);
+    serde::Serialize::serialize(json, &mut serializer).map_err(|e| e.to_string())?;
+
+    let mut output = String::from_utf8(serialized).map_err(|e| e.to_string())?;
+    if original.ends_with('\n') {
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Indentation of the first indented line, defaulting to two spaces
+fn detect_indent(content: &str) -> String {
+    content
+        .lines()
+        .skip(1)
+        .map(|line| {
+            line.chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect::<String>()
+        })
+        .find(|indent| !indent.is_empty())
+        .unwrap_or_else(|| "  ".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_matches() {
+        let data = json!({"a": [1, {"b": 2}]});
+        let single = jsonpath::query_locations(&data, "$.a[1]").unwrap();
+        assert_eq!(format_matches(&data, &single), "{\n  \"b\": 2\n}");
+
+        let many = jsonpath::query_locations(&data, "$.a[*]").unwrap();
+        assert_eq!(
+            format_matches(&data, &many),
+            "$['a'][0]: 1\n$['a'][1]: {\"b\":2}"
+        );
+    }
+
+    #[test]
+    fn test_detect_indent() {
+        assert_eq!(detect_indent("{\n    \"a\": 1\n}"), "    ");
+        assert_eq!(detect_indent("{\"a\": 1}"), "  ");
+    }
+}
@@ -0,0 +1,146 @@
+//! Knowledge base tool searching documentation added with `termineer kb add`
+//!
+//! Usage:
+//! - `kb QUERY` searches every knowledge base of the workspace
+//! - `kb [name=NAME] [top=N] QUERY` searches one knowledge base and returns N passages
+//!
+//! The query may also be given in the body.
+
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
+use crate::tools::ToolResult;
+
+/// Passages returned when no `top` is given
+const DEFAULT_TOP: usize = 5;
+
+/// Lines of each passage included in the result
+const MAX_HIT_LINES: usize = 30;
+
+/// Parsed arguments of the kb tool
+#[derive(Debug, PartialEq)]
+struct KbArgs {
+    name: Option<String>,
+    top: usize,
+    query: String,
+}
+
+pub async fn execute_kb(args: &str, body: &str, silent_mode: bool) -> ToolResult {
+    let KbArgs { name, top, query } = match parse_args(args, body) {
+        Ok(parsed) => parsed,
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+    };
+
+    if !silent_mode {
+        bprintln !(tool: "kb",
+            "{FORMAT_BOLD}📖 Knowledge base{}:{FORMAT_RESET} {FORMAT_GRAY}{}{FORMAT_RESET}",
+            name.as_deref().map(|name| format!(" {name}")).unwrap_or_default(),
+            query
+        );
+    }
+
+    let hits = match crate::index::kb::search(name.as_deref(), &query, top).await {
+        Ok(hits) => hits,
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+    };
+
+    if hits.is_empty() {
+        return ToolResult::success("No matching passages".to_string());
+    }
+
+    let mut result = String::new();
+    for hit in &hits {
+        result.push_str(&format!(
+            "[{}] {} (lines {}-{}, score {:.2})\n",
+            hit.kb, hit.source, hit.start_line, hit.end_line, hit.score
+        ));
+        let lines: Vec<&str> = hit.text.lines().collect();
+        for line in lines.iter().take(MAX_HIT_LINES) {
+            result.push_str(line);
+            result.push('\n');
+        }
+        if lines.len() > MAX_HIT_LINES {
+            result.push_str(&format!(
+                "... {} more line(s)\n",
+                lines.len() - MAX_HIT_LINES
+            ));
+        }
+        result.push('\n');
+    }
+
+    if !silent_mode {
+        bprintln !(tool: "kb",
+            "{FORMAT_GRAY}{} passage(s), best: {}{FORMAT_RESET}",
+            hits.len(),
+            hits[0].source
+        );
+    }
+    ToolResult::success(result.trim_end().to_string())
+}
+
+/// Parse `[name=NAME] [top=N] query`, with the query in the args or the body
+fn parse_args(args: &str, body: &str) -> Result<KbArgs, String> {
+    let mut name = None;
+    let mut top = DEFAULT_TOP;
+    let mut rest = args.trim();
+    loop {
+        let (word, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        if let Some(value) = word.strip_prefix("name=") {
+            name = Some(value.to_string());
+        } else if let Some(value) = word.strip_prefix("top=") {
+            top = value
+                .parse()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| format!("Invalid result count: top={value}"))?;
+        } else {
+            break;
+        }
+        rest = after.trim_start();
+    }
+
+    let query = if rest.is_empty() { body.trim() } else { rest };
+    if query.is_empty() {
+        return Err("Missing search query: use `kb [name=NAME] [top=N] QUERY`".to_string());
+    }
+    Ok(KbArgs {
+        name,
+        top,
+        query: query.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args("name=stripe top=3 refund webhooks", ""),
+            Ok(KbArgs {
+                name: Some("stripe".to_string()),
+                top: 3,
+                query: "refund webhooks".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_args("", "how are tokens refreshed"),
+            Ok(KbArgs {
+                name: None,
+                top: DEFAULT_TOP,
+                query: "how are tokens refreshed".to_string(),
+            })
+        );
+        assert!(parse_args("top=x query", "").is_err());
+        assert!(parse_args("name=docs", "").is_err());
+    }
+}
@@ -0,0 +1,502 @@
+//! Log analysis tool for log files too large to read
+//!
+//! Lines are clustered into templates in the manner of Drain: tokens with
+//! digits (ids, counts, addresses, times) become `<*>`, and a line joins the
+//! most similar cluster of lines with as many tokens, turning the tokens they
+//! differ in into `<*>` too. Timestamps at the start of lines (ISO 8601, e.g.
+//! `2024-05-01T12:00:00Z` or `2024-05-01 12:00:00,123`) give the timeline.
+//!
+//! Usage:
+//! - `logs PATH [top=N]` summarizes a log: line count, time range and the most
+//!   and least frequent clusters
+//! - `logs timeline PATH [CLUSTER] [buckets=N]` counts the lines (of a cluster)
+//!   over time
+//! - `logs show PATH CLUSTER [offset=N] [limit=N]` lists the lines of a cluster
+//!
+//! Clusters are numbered in the order they first appear. The analysis of the
+//! last log is kept, so drilling down does not read the file again unless it
+//! changed.
+
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
+use crate::tools::ToolResult;
+use chrono::NaiveDateTime;
+use lazy_static::lazy_static;
+use std::collections::{BTreeMap, HashMap};
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Token standing for the variable parts of lines
+const WILDCARD: &str = "<*>";
+
+/// Share of equal tokens for a line to join a cluster
+const SIMILARITY: f64 = 0.5;
+
+/// Clusters kept; once reached, lines join the most similar cluster
+const MAX_CLUSTERS: usize = 5000;
+
+/// Clusters of the summary when no `top` is given
+const DEFAULT_TOP: usize = 30;
+
+/// Least frequent clusters listed after the most frequent ones
+const RARE_CLUSTERS: usize = 10;
+
+/// Buckets of the timeline when no `buckets` is given
+const DEFAULT_BUCKETS: usize = 12;
+
+/// Lines of a cluster listed when no `limit` is given
+const DEFAULT_LIMIT: usize = 20;
+
+/// Characters of a line or template shown
+const MAX_LINE_CHARS: usize = 300;
+
+/// Width of the longest timeline bar
+const BAR_WIDTH: usize = 40;
+
+lazy_static! {
+    /// Analysis of the last log, with the size and modification time it had
+    static ref LAST_ANALYSIS: Mutex<Option<(PathBuf, u64, SystemTime, Arc<Analysis>)>> =
+        Mutex::new(None);
+}
+
+/// Lines sharing a template
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    pub template: Vec<String>,
+    pub count: usize,
+    /// Line number of the first line
+    pub first_line: usize,
+    /// Lines per minute since the epoch, for lines with a timestamp
+    pub minutes: BTreeMap<i64, usize>,
+}
+
+/// Clusters of a log
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub lines: usize,
+    pub clusters: Vec<Cluster>,
+    /// Clusters by token count and first constant token
+    groups: HashMap<(usize, String), Vec<usize>>,
+    pub first_time: Option<NaiveDateTime>,
+    pub last_time: Option<NaiveDateTime>,
+}
+
+impl Analysis {
+    /// Add the next line, returning the index of its cluster (None for blank lines)
+    pub fn add(&mut self, line: &str) -> Option<usize> {
+        self.lines += 1;
+        let tokens = tokenize(line);
+        if tokens.is_empty() {
+            return None;
+        }
+        let key = (
+            tokens.len(),
+            tokens
+                .iter()
+                .find(|token| *token != WILDCARD)
+                .cloned()
+                .unwrap_or_default(),
+        );
+        let group = self.groups.entry(key).or_default();
+        let best = group
+            .iter()
+            .map(|&index| (index, similarity(&self.clusters[index].template, &tokens)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        let index = match best {
+            Some((index, score)) if score >= SIMILARITY || self.clusters.len() >= MAX_CLUSTERS => {
+                let template = &mut self.clusters[index].template;
+                for (slot, token) in template.iter_mut().zip(&tokens) {
+                    if slot != token {
+                        *slot = WILDCARD.to_string();
+                    }
+                }
+                index
+            }
+            _ => {
+                group.push(self.clusters.len());
+                self.clusters.push(Cluster {
+                    template: tokens,
+                    count: 0,
+                    first_line: self.lines,
+                    minutes: BTreeMap::new(),
+                });
+                self.clusters.len() - 1
+            }
+        };
+
+        let cluster = &mut self.clusters[index];
+        cluster.count += 1;
+        if let Some(time) = parse_timestamp(line) {
+            *cluster
+                .minutes
+                .entry(time.and_utc().timestamp().div_euclid(60))
+                .or_default() += 1;
+            self.first_time = Some(self.first_time.map_or(time, |first| first.min(time)));
+            self.last_time = Some(self.last_time.map_or(time, |last| last.max(time)));
+        }
+        Some(index)
+    }
+}
+
+/// Tokens of a line, with the variable ones replaced by the wildcard
+fn tokenize(line: &str) -> Vec<String> {
+    line.split_whitespace()
+        .map(|token| {
+            if token.chars().any(|c| c.is_ascii_digit()) {
+                WILDCARD.to_string()
+            } else {
+                token.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Share of the tokens of a line equal to those of a template
+fn similarity(template: &[String], tokens: &[String]) -> f64 {
+    let equal = template.iter().zip(tokens).filter(|(a, b)| a == b).count();
+    equal as f64 / tokens.len().max(1) as f64
+}
+
+/// Timestamp at the start of a line, e.g. `2024-05-01T12:00:00.123Z`,
+/// `[2024-05-01 12:00:00,123]`; fractions and time zones are ignored
+pub fn parse_timestamp(line: &str) -> Option<NaiveDateTime> {
+    let line = line.trim_start().trim_start_matches('[');
+    let stamp = line.get(..19)?.replacen('T', " ", 1);
+    NaiveDateTime::parse_from_str(&stamp, "%Y-%m-%d %H:%M:%S").ok()
+}
+
+/// Read a log, calling `visit` with the number, text and cluster of each line
+fn analyze(
+    path: &Path,
+    mut visit: impl FnMut(usize, &str, Option<usize>),
+) -> std::io::Result<Analysis> {
+    let file = std::fs::File::open(path)?;
+    let mut reader = std::io::BufReader::with_capacity(1 << 20, file);
+    let mut analysis = Analysis::default();
+    let mut bytes = Vec::new();
+    loop {
+        bytes.clear();
+        if reader.read_until(b'\n', &mut bytes)? == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&bytes);
+        let line = line.trim_end_matches(['\n', '\r']);
+        let cluster = analysis.add(line);
+        visit(analysis.lines, line, cluster);
+    }
+    Ok(analysis)
+}
+
+/// Analysis of a log, reused while the file is unchanged
+fn cached_analysis(path: &Path) -> std::io::Result<Arc<Analysis>> {
+    let metadata = std::fs::metadata(path)?;
+    let (len, modified) = (metadata.len(), metadata.modified()?);
+    if let Some((cached_path, cached_len, cached_modified, analysis)) =
+        &*LAST_ANALYSIS.lock().unwrap()
+    {
+        if cached_path == path && *cached_len == len && *cached_modified == modified {
+            return Ok(analysis.clone());
+        }
+    }
+    let analysis = Arc::new(analyze(path, |_, _, _| {})?);
+    *LAST_ANALYSIS.lock().unwrap() = Some((path.to_path_buf(), len, modified, analysis.clone()));
+    Ok(analysis)
+}
+
+/// Text shortened to the characters shown
+fn shorten(text: &str) -> String {
+    match text.char_indices().nth(MAX_LINE_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn format_cluster(id: usize, cluster: &Cluster) -> String {
+    format!(
+        "#{:<5} {:>9}  line {:<9} {}",
+        id + 1,
+        cluster.count,
+        cluster.first_line,
+        shorten(&cluster.template.join(" "))
+    )
+}
+
+/// Line count, time range and the most and least frequent clusters
+pub fn summary(path: &str, analysis: &Analysis, top: usize) -> String {
+    let mut result = format!(
+        "{path}: {} lines, {} clusters",
+        analysis.lines,
+        analysis.clusters.len()
+    );
+    if let (Some(first), Some(last)) = (analysis.first_time, analysis.last_time) {
+        result.push_str(&format!(", from {first} to {last}"));
+    }
+
+    let mut order: Vec<usize> = (0..analysis.clusters.len()).collect();
+    order.sort_by(|&a, &b| analysis.clusters[b].count.cmp(&analysis.clusters[a].count));
+    result.push_str("\n\nMost frequent (cluster, lines, first line, template):\n");
+    for &id in order.iter().take(top) {
+        result.push_str(&format_cluster(id, &analysis.clusters[id]));
+        result.push('\n');
+    }
+    // Rare lines are often the errors worth looking at
+    let rare: Vec<usize> = order
+        .iter()
+        .skip(top)
+        .rev()
+        .take(RARE_CLUSTERS)
+        .copied()
+        .collect();
+    if !rare.is_empty() {
+        result.push_str(&format!(
+            "\nLeast frequent ({} more clusters not shown):\n",
+            order.len() - top - rare.len()
+        ));
+        for id in rare {
+            result.push_str(&format_cluster(id, &analysis.clusters[id]));
+            result.push('\n');
+        }
+    }
+    result.push_str(
+        "\nUse `logs show PATH CLUSTER` for the lines of a cluster \
+         and `logs timeline PATH [CLUSTER]` for their times.",
+    );
+    result
+}
+
+/// Lines per time bucket, of one cluster or of all
+pub fn timeline(analysis: &Analysis, cluster: Option<usize>, buckets: usize) -> String {
+    let (Some(first), Some(last)) = (analysis.first_time, analysis.last_time) else {
+        return "No timestamps found at the start of the lines".to_string();
+    };
+    let start = first.and_utc().timestamp().div_euclid(60);
+    let end = last.and_utc().timestamp().div_euclid(60);
+    let width = ((end - start) / buckets.max(1) as i64 + 1).max(1);
+
+    let mut counts = vec![0usize; ((end - start) / width + 1) as usize];
+    let clusters = match cluster {
+        Some(index) => std::slice::from_ref(&analysis.clusters[index]),
+        None => &analysis.clusters[..],
+    };
+    for minutes in clusters.iter().map(|cluster| &cluster.minutes) {
+        for (minute, count) in minutes {
+            counts[((minute - start) / width) as usize] += count;
+        }
+    }
+
+    let max = counts.iter().copied().max().unwrap_or_default().max(1);
+    let mut result = format!("Lines per {width} minute(s):\n");
+    for (index, count) in counts.iter().enumerate() {
+        let minute = start + index as i64 * width;
+        let time = chrono::DateTime::from_timestamp(minute * 60, 0)
+            .map(|time| time.naive_utc().format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let bar = "█".repeat((count * BAR_WIDTH).div_ceil(max));
+        result.push_str(&format!("{time}  {bar} {count}\n"));
+    }
+    result.trim_end().to_string()
+}
+
+/// Parsed arguments of the logs tool
+#[derive(Debug, PartialEq)]
+struct LogsArgs {
+    action: String,
+    path: String,
+    /// Cluster number as shown, starting at 1
+    cluster: Option<usize>,
+    options: HashMap<String, usize>,
+}
+
+/// Parse `[summary|timeline|show] PATH [CLUSTER] [key=N]...`
+fn parse_args(args: &str) -> Result<LogsArgs, String> {
+    let mut words = Vec::new();
+    let mut options = HashMap::new();
+    for word in args.split_whitespace() {
+        match word.split_once('=') {
+            Some((key, value)) if ["top", "buckets", "offset", "limit"].contains(&key) => {
+                let value = value
+                    .parse()
+                    .map_err(|_| format!("Invalid number: {word}"))?;
+                options.insert(key.to_string(), value);
+            }
+            _ => words.push(word),
+        }
+    }
+    let action = match words.first() {
+        Some(&action) if ["summary", "timeline", "show"].contains(&action) => {
+            words.remove(0);
+            action.to_string()
+        }
+        _ => "summary".to_string(),
+    };
+    let usage = "Usage: `logs PATH`, `logs timeline PATH [CLUSTER]` or `logs show PATH CLUSTER`";
+    let path = match words.first() {
+        Some(path) => path.to_string(),
+        None => return Err(format!("Missing log file. {usage}")),
+    };
+    let cluster = match words.get(1) {
+        Some(word) => Some(
+            word.trim_start_matches('#')
+                .parse()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| format!("Invalid cluster: {word}"))?,
+        ),
+        None if action == "show" => return Err(format!("Missing cluster. {usage}")),
+        None => None,
+    };
+    Ok(LogsArgs {
+        action,
+        path,
+        cluster,
+        options,
+    })
+}
+
+/// Execute the logs tool
+pub async fn execute_logs(args: &str, _body: &str, silent_mode: bool) -> ToolResult {
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+    };
+
+    if !silent_mode {
+        bprintln !(tool: "logs",
+            "{FORMAT_BOLD}📜 Logs {}:{FORMAT_RESET} {FORMAT_GRAY}{}{}{FORMAT_RESET}",
+            args.action,
+            args.path,
+            args.cluster.map(|n| format!(" #{n}")).unwrap_or_default()
+        );
+    }
+
+    // Logs may be gigabytes; reading them must not hold up other agents
+    let result = tokio::task::spawn_blocking(move || run(&args))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+    match result {
+        Ok(output) => ToolResult::success(output),
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            ToolResult::error(error_msg)
+        }
+    }
+}
+
+fn run(args: &LogsArgs) -> Result<String, String> {
+    let path = Path::new(&args.path);
+    let read_error = |e: std::io::Error| format!("Failed to read {}: {e}", args.path);
+    let analysis = cached_analysis(path).map_err(read_error)?;
+    let index = match args.cluster {
+        Some(n) if n > analysis.clusters.len() => {
+            return Err(format!(
+                "No cluster #{n}: {} has {} clusters",
+                args.path,
+                analysis.clusters.len()
+            ))
+        }
+        Some(n) => Some(n - 1),
+        None => None,
+    };
+    let option = |key: &str, default: usize| args.options.get(key).copied().unwrap_or(default);
+
+    match (args.action.as_str(), index) {
+        ("timeline", index) => Ok(timeline(
+            &analysis,
+            index,
+            option("buckets", DEFAULT_BUCKETS),
+        )),
+        ("show", Some(index)) => {
+            let offset = option("offset", 0);
+            let limit = option("limit", DEFAULT_LIMIT);
+            let cluster = &analysis.clusters[index];
+            let mut result = format!(
+                "Cluster #{} ({} lines): {}\n",
+                index + 1,
+                cluster.count,
+                shorten(&cluster.template.join(" "))
+            );
+            // Clustering the log again assigns every line as the first time
+            let mut seen = 0;
+            analyze(path, |number, line, cluster| {
+                if cluster == Some(index) {
+                    if seen >= offset && seen < offset + limit {
+                        result.push_str(&format!("{number:>8} | {}\n", shorten(line)));
+                    }
+                    seen += 1;
+                }
+            })
+            .map_err(read_error)?;
+            if seen > offset + limit {
+                result.push_str(&format!(
+                    "[{} more lines; use offset={}]",
+                    seen - offset - limit,
+                    offset + limit
+                ));
+            }
+            Ok(result.trim_end().to_string())
+        }
+        _ => Ok(summary(&args.path, &analysis, option("top", DEFAULT_TOP))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "2024-05-01T12:00:00Z INFO request 17 served in 12ms\n\
+                       2024-05-01T12:00:30Z INFO request 18 served in 9ms\n\
+                       2024-05-01T12:05:00Z ERROR connection to db-1 refused\n\
+                       \n\
+                       2024-05-01T12:10:00Z INFO request 19 served in 11ms\n";
+
+    #[test]
+    fn test_clusters() {
+        let mut analysis = Analysis::default();
+        let clusters: Vec<Option<usize>> = LOG.lines().map(|line| analysis.add(line)).collect();
+        assert_eq!(clusters, vec![Some(0), Some(0), Some(1), None, Some(0)]);
+        assert_eq!(analysis.lines, 5);
+        assert_eq!(
+            analysis.clusters[0].template.join(" "),
+            "<*> INFO request <*> served in <*>"
+        );
+        assert_eq!(analysis.clusters[1].first_line, 3);
+
+        let text = timeline(&analysis, None, 2);
+        assert_eq!(
+            text.lines().collect::<Vec<_>>(),
+            vec![
+                "Lines per 6 minute(s):",
+                "2024-05-01 12:00  ████████████████████████████████████████ 3",
+                "2024-05-01 12:06  ██████████████ 1",
+            ]
+        );
+
+        assert_eq!(
+            parse_timestamp("[2024-05-01 12:00:00,123] WARN"),
+            NaiveDateTime::parse_from_str("2024-05-01 12:00:00", "%Y-%m-%d %H:%M:%S").ok()
+        );
+        assert_eq!(parse_timestamp("May  1 12:00:00 host sshd"), None);
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse_args("show app.log #3 limit=5").unwrap();
+        assert_eq!(
+            (args.action.as_str(), args.path.as_str(), args.cluster),
+            ("show", "app.log", Some(3))
+        );
+        assert_eq!(args.options["limit"], 5);
+        assert_eq!(parse_args("app.log").unwrap().action, "summary");
+        assert!(parse_args("show app.log").is_err());
+    }
+}
@@ -0,0 +1,442 @@
+//! Code navigation tools answered by the language server
+//!
+//! Positions are given as `PATH:LINE[:COLUMN]` with 1-based numbers, optionally
+//! followed by the symbol on that line, so the model doesn't have to count
+//! columns:
+//! - `definition src/main.rs:42 parse_args`
+//! - `references src/config.rs:10:12`
+//! - `hover src/main.rs:42 parse_args`
+//! - `rename src/config.rs:10 Config Settings`
+
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
+use crate::lsp::{self, LspClient};
+use crate::tools::ToolResult;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// References listed in the output
+const MAX_REFERENCES: usize = 50;
+
+/// A position in a document, in LSP coordinates (0-based line, UTF-16 column)
+#[derive(Debug, PartialEq)]
+struct Target {
+    path: PathBuf,
+    line: u32,
+    character: u32,
+}
+
+impl Target {
+    fn params(&self) -> Value {
+        json!({
+            "textDocument": { "uri": lsp::path_to_uri(&self.path) },
+            "position": { "line": self.line, "character": self.character },
+        })
+    }
+}
+
+/// A text replacement from a workspace edit
+#[derive(Debug, Clone, PartialEq)]
+struct TextEdit {
+    start: (u32, u32),
+    end: (u32, u32),
+    new_text: String,
+}
+
+/// Execute one of the language server tools
+pub async fn execute_lsp_tool(tool_name: &str, args: &str, silent_mode: bool) -> ToolResult {
+    let result = match tool_name {
+        "definition" => definition(args).await,
+        "references" => references(args).await,
+        "hover" => hover(args).await,
+        "rename" => rename(args).await,
+        other => Err(format!("Unknown language server tool '{other}'")),
+    };
+
+    match result {
+        Ok(output) => {
+            if !silent_mode {
+                let first_line = output.lines().next().unwrap_or("");
+                bprintln !(tool: tool_name,
+                    "{FORMAT_BOLD}🧭 {} {}:{FORMAT_RESET} {FORMAT_GRAY}{}{FORMAT_RESET}",
+                    tool_name,
+                    args.trim(),
+                    first_line
+                );
+            }
+            ToolResult::success(output)
+        }
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            ToolResult::error(error_msg)
+        }
+    }
+}
+
+async fn definition(args: &str) -> Result<String, String> {
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    let target = parse_target(&tokens, 2)?;
+    let client = prepare(&target).await?;
+    let result = client
+        .request("textDocument/definition", target.params())
+        .await?;
+    let locations = locations(&result);
+    if locations.is_empty() {
+        return Ok(not_found("No definition found"));
+    }
+    Ok(format_locations(&locations))
+}
+
+async fn references(args: &str) -> Result<String, String> {
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    let target = parse_target(&tokens, 2)?;
+    let client = prepare(&target).await?;
+    let mut params = target.params();
+    params["context"] = json!({ "includeDeclaration": true });
+    let result = client.request("textDocument/references", params).await?;
+    let locations = locations(&result);
+    if locations.is_empty() {
+        return Ok(not_found("No references found"));
+    }
+
+    let mut output = format!("{} reference(s)\n", locations.len());
+    output.push_str(&format_locations(
+        &locations[..locations.len().min(MAX_REFERENCES)],
+    ));
+    if locations.len() > MAX_REFERENCES {
+        output.push_str(&format!(
+            "\n... and {} more",
+            locations.len() - MAX_REFERENCES
+        ));
+    }
+    Ok(output)
+}
+
+async fn hover(args: &str) -> Result<String, String> {
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    let target = parse_target(&tokens, 2)?;
+    let client = prepare(&target).await?;
+    let result = client
+        .request("textDocument/hover", target.params())
+        .await?;
+    let text = result.get("contents").map(hover_text).unwrap_or_default();
+    if text.trim().is_empty() {
+        return Ok(not_found("No information available"));
+    }
+    Ok(text.trim().to_string())
+}
+
+async fn rename(args: &str) -> Result<String, String> {
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    let (new_name, position) = match tokens.split_last() {
+        Some((new_name, position)) if !position.is_empty() => (*new_name, position),
+        _ => return Err("Usage: rename PATH:LINE[:COLUMN] [SYMBOL] NEW_NAME".to_string()),
+    };
+    let target = parse_target(position, 2)?;
+    let client = prepare(&target).await?;
+    let mut params = target.params();
+    params["newName"] = json!(new_name);
+    let result = client.request("textDocument/rename", params).await?;
+    if result.is_null() {
+        return Err("The language server cannot rename the symbol at this position".to_string());
+    }
+
+    // Compute every new file before writing any, so a bad edit changes nothing
+    let mut updated = Vec::new();
+    for (path, edits) in workspace_edits(&result) {
+        let path = crate::tools::path_utils::validate_path(&path.to_string_lossy())
+            .map_err(|e| format!("Refusing to edit {}: {e}", path.display()))?;
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let new_text = apply_edits(&text, &edits)
+            .ok_or_else(|| format!("Invalid edit range in {}", path.display()))?;
+        updated.push((path, new_text, edits.len()));
+    }
+    if updated.is_empty() {
+        return Ok(not_found("Nothing to rename"));
+    }
+
+    let mut output = format!("Renamed to '{new_name}' in {} file(s)", updated.len());
+    for (path, new_text, count) in &updated {
+        std::fs::write(path, new_text)
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+        client.sync_document(path)?;
+        output.push_str(&format!("\n{} ({count} edit(s))", display_path(path)));
+    }
+    Ok(output)
+}
+
+/// Start the server if needed and send it the current content of the document
+async fn prepare(target: &Target) -> Result<std::sync::Arc<LspClient>, String> {
+    let client = lsp::client().await?;
+    client.sync_document(&target.path)?;
+    Ok(client)
+}
+
+/// Message for empty results, which servers also return while still indexing
+fn not_found(message: &str) -> String {
+    format!("{message} (if the server has just started, it may still be indexing the project)")
+}
+
+/// Parse `PATH:LINE[:COLUMN] [SYMBOL]` from the first `max_tokens` tokens
+fn parse_target(tokens: &[&str], max_tokens: usize) -> Result<Target, String> {
+    let usage = "Expected PATH:LINE[:COLUMN] [SYMBOL]";
+    if tokens.is_empty() || tokens.len() > max_tokens {
+        return Err(usage.to_string());
+    }
+    let (path, line, column) = split_location(tokens[0]).ok_or(usage)?;
+    let symbol = tokens.get(1).copied();
+
+    let path = crate::tools::path_utils::validate_path(path)
+        .map_err(|e| format!("Invalid path '{path}': {e}"))?;
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let line_text = text
+        .lines()
+        .nth(line - 1)
+        .ok_or_else(|| format!("Line {line} is past the end of {}", display_path(&path)))?;
+
+    let offset = match (symbol, column) {
+        (Some(symbol), _) => find_symbol(line_text, symbol)
+            .ok_or_else(|| format!("'{symbol}' does not appear on line {line}"))?,
+        (None, Some(column)) => line_text
+            .char_indices()
+            .nth(column - 1)
+            .map_or(line_text.len(), |(offset, _)| offset),
+        (None, None) => line_text.len() - line_text.trim_start().len(),
+    };
+    Ok(Target {
+        path,
+        line: line as u32 - 1,
+        character: lsp::utf16_column(line_text, offset),
+    })
+}
+
+/// Split `PATH:LINE[:COLUMN]` into its parts (line and column are 1-based)
+fn split_location(location: &str) -> Option<(&str, usize, Option<usize>)> {
+    let number = |text: &str| text.parse::<usize>().ok().filter(|n| *n > 0);
+    let (rest, last) = location.rsplit_once(':')?;
+    let last = number(last)?;
+    match rest.rsplit_once(':') {
+        Some((path, line)) if number(line).is_some() && !path.is_empty() => {
+            Some((path, number(line)?, Some(last)))
+        }
+        _ if !rest.is_empty() => Some((rest, last, None)),
+        _ => None,
+    }
+}
+
+/// Byte offset of the symbol on the line, preferring whole-word matches
+fn find_symbol(line_text: &str, symbol: &str) -> Option<usize> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    line_text
+        .match_indices(symbol)
+        .map(|(offset, _)| offset)
+        .find(|&offset| {
+            let before = line_text[..offset].chars().next_back();
+            let after = line_text[offset + symbol.len()..].chars().next();
+            !before.is_some_and(is_word) && !after.is_some_and(is_word)
+        })
+        .or_else(|| line_text.find(symbol))
+}
+
+/// Locations of a definition or references result (Location, LocationLink or lists of them)
+fn locations(result: &Value) -> Vec<(PathBuf, u32, u32)> {
+    let items = match result {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(_) => vec![result],
+        _ => Vec::new(),
+    };
+    items
+        .into_iter()
+        .filter_map(|item| {
+            let (uri, range) = match item.get("targetUri") {
+                Some(uri) => (uri, item.get("targetSelectionRange")?),
+                None => (item.get("uri")?, item.get("range")?),
+            };
+            let path = lsp::uri_to_path(uri.as_str()?)?;
+            let start = range.get("start")?;
+            Some((
+                path,
+                start.get("line")?.as_u64()? as u32,
+                start.get("character")?.as_u64()? as u32,
+            ))
+        })
+        .collect()
+}
+
+/// One line per location with the source line for context
+fn format_locations(locations: &[(PathBuf, u32, u32)]) -> String {
+    let mut files: HashMap<&Path, Vec<String>> = HashMap::new();
+    locations
+        .iter()
+        .map(|(path, line, character)| {
+            let lines = files.entry(path.as_path()).or_insert_with(|| {
+                std::fs::read_to_string(path)
+                    .map(|text| text.lines().map(str::to_string).collect())
+                    .unwrap_or_default()
+            });
+            let source = lines.get(*line as usize).map_or("", |l| l.trim());
+            format!(
+                "{}:{}:{}: {}",
+                display_path(path),
+                line + 1,
+                character + 1,
+                source
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Text of hover contents (MarkupContent, MarkedString or a list of MarkedStrings)
+fn hover_text(contents: &Value) -> String {
+    match contents {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(hover_text)
+            .filter(|text| !text.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        Value::Object(object) => {
+            let value = object.get("value").and_then(|v| v.as_str()).unwrap_or("");
+            match object.get("language").and_then(|l| l.as_str()) {
+                Some(language) => format!("```{language}\n{value}\n```"),
+                None => value.to_string(),
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+/// Text edits of a workspace edit by file; resource operations are ignored
+fn workspace_edits(edit: &Value) -> BTreeMap<PathBuf, Vec<TextEdit>> {
+    let mut by_file: BTreeMap<PathBuf, Vec<TextEdit>> = BTreeMap::new();
+    let mut add = |uri: &Value, edits: &Value| {
+        let Some(path) = uri.as_str().and_then(lsp::uri_to_path) else {
+            return;
+        };
+        let edits = edits.as_array().into_iter().flatten().filter_map(text_edit);
+        by_file.entry(path).or_default().extend(edits);
+    };
+
+    if let Some(changes) = edit.get("changes").and_then(|c| c.as_object()) {
+        for (uri, edits) in changes {
+            add(&Value::String(uri.clone()), edits);
+        }
+    }
+    for change in edit
+        .get("documentChanges")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+    {
+        if let (Some(document), Some(edits)) = (change.get("textDocument"), change.get("edits")) {
+            add(document.get("uri").unwrap_or(&Value::Null), edits);
+        }
+    }
+    by_file
+}
+
+fn text_edit(edit: &Value) -> Option<TextEdit> {
+    let range = edit.get("range")?;
+    let position = |name: &str| -> Option<(u32, u32)> {
+        let position = range.get(name)?;
+        Some((
+            position.get("line")?.as_u64()? as u32,
+            position.get("character")?.as_u64()? as u32,
+        ))
+    };
+    Some(TextEdit {
+        start: position("start")?,
+        end: position("end")?,
+        new_text: edit.get("newText")?.as_str()?.to_string(),
+    })
+}
+
+/// Apply non-overlapping edits to a text
+fn apply_edits(text: &str, edits: &[TextEdit]) -> Option<String> {
+    let mut ranges = Vec::with_capacity(edits.len());
+    for edit in edits {
+        let start = lsp::position_to_offset(text, edit.start.0, edit.start.1)?;
+        let end = lsp::position_to_offset(text, edit.end.0, edit.end.1)?;
+        if end < start {
+            return None;
+        }
+        ranges.push((start, end, edit.new_text.as_str()));
+    }
+    // Apply from the end so earlier offsets stay valid
+    ranges.sort_by(|a, b| b.0.cmp(&a.0));
+    let mut result = text.to_string();
+    for (start, end, new_text) in ranges {
+        result.replace_range(start..end, new_text);
+    }
+    Some(result)
+}
+
+/// Path relative to the current directory when it is inside it
+fn display_path(path: &Path) -> String {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let canonical_cwd = cwd.canonicalize().unwrap_or_else(|_| cwd.clone());
+    path.strip_prefix(&canonical_cwd)
+        .or_else(|_| path.strip_prefix(&cwd))
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_location() {
+        assert_eq!(
+            split_location("src/main.rs:42"),
+            Some(("src/main.rs", 42, None))
+        );
+        assert_eq!(
+            split_location("src/main.rs:42:7"),
+            Some(("src/main.rs", 42, Some(7)))
+        );
+        assert_eq!(split_location("src/main.rs"), None);
+        assert_eq!(split_location("src/main.rs:0"), None);
+        assert_eq!(split_location(":3"), None);
+    }
+
+    #[test]
+    fn test_find_symbol() {
+        let line = "    let config_path = config::path(&config);";
+        assert_eq!(find_symbol(line, "config"), Some(22));
+        assert_eq!(find_symbol(line, "path"), Some(30));
+        assert_eq!(find_symbol(line, "missing"), None);
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let text = "let old = 1;\nprint(old);\n";
+        let edit = |line, start, end| TextEdit {
+            start: (line, start),
+            end: (line, end),
+            new_text: "new".to_string(),
+        };
+        assert_eq!(
+            apply_edits(text, &[edit(0, 4, 7), edit(1, 6, 9)]),
+            Some("let new = 1;\nprint(new);\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_hover_text() {
+        let contents = json!([{ "language": "rust", "value": "fn main()" }, "Entry point"]);
+        assert_eq!(
+            hover_text(&contents),
+            "```rust\nfn main()\n```\n\nEntry point"
+        );
+        let markup = json!({ "kind": "markdown", "value": "**Config**" });
+        assert_eq!(hover_text(&markup), "**Config**");
+    }
+}
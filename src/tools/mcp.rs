@@ -7,6 +7,51 @@ use crate::mcp::protocol::content::McpContent;
 use crate::tools::{AgentStateChange, ToolResult};
 use serde_json::Value;
 
+/// Execute the `mcp` meta-tool
+///
+/// `mcp list-tools <server>` returns the detailed documentation of a server's
+/// tools, which the system prompt only summarizes in one line per server.
+pub async fn execute_mcp_meta_tool(args: &str, _body: &str, silent_mode: bool) -> ToolResult {
+    let args: Vec<&str> = args.split_whitespace().collect();
+
+    let server_name = match args.as_slice() {
+        ["list-tools", server_name] => *server_name,
+        _ => {
+            let servers = crate::mcp::get_provider_names().join(", ");
+            if !silent_mode {
+                bprintln!(error: "Invalid mcp command: {}", args.join(" "));
+            }
+            return ToolResult::error(format!(
+                "Invalid mcp command. Usage: mcp list-tools <server>. Available servers: {servers}"
+            ));
+        }
+    };
+
+    match crate::mcp::manager::describe_server_tools(server_name) {
+        Some(description) => {
+            if !silent_mode {
+                bprintln!(
+                    tool: "mcp",
+                    "{}🔌 Listed tools of {}{}",
+                    crate::constants::FORMAT_BOLD,
+                    server_name,
+                    crate::constants::FORMAT_RESET
+                );
+            }
+            ToolResult::success(description)
+        }
+        None => {
+            let servers = crate::mcp::get_provider_names().join(", ");
+            if !silent_mode {
+                bprintln!(error: "MCP server not found: {}", server_name);
+            }
+            ToolResult::error(format!(
+                "MCP server '{server_name}' is not available. Available servers: {servers}"
+            ))
+        }
+    }
+}
+
 /// Execute a dynamic MCP tool
 ///
 /// This handles tool invocations where the tool name is an MCP server name
@@ -47,6 +92,27 @@ pub async fn execute_dynamic_mcp_tool(
         }
     };
 
+    // Fail fast while the server is down instead of waiting for a timeout
+    if let Some(status) = crate::mcp::server_status(server_name) {
+        let unavailable = match &status.health {
+            crate::mcp::ServerHealth::Healthy => None,
+            crate::mcp::ServerHealth::Restarting => Some("it is restarting".to_string()),
+            crate::mcp::ServerHealth::Unhealthy(reason) => {
+                Some(format!("{reason}; it will be restarted automatically"))
+            }
+        };
+        if let Some(reason) = unavailable {
+            if !silent_mode {
+                bprintln!(error: "MCP server '{}' is unavailable: {}", server_name, reason);
+            }
+
+            return ToolResult::error(format!(
+                "MCP server '{}' is currently unavailable: {}. Try again later or continue without it.",
+                server_name, reason
+            ));
+        }
+    }
+
     // Parse tool arguments from body
     let arguments: Value = match serde_json::from_str(body) {
         Ok(value) => value,
@@ -62,6 +128,28 @@ pub async fn execute_dynamic_mcp_tool(
         }
     };
 
+    // Check the arguments against the tool's input schema before calling the
+    // server, so the model can correct them from a single descriptive error
+    if let Some(tool) = provider.get_tool(tool_name) {
+        if let Err(problems) = tool.input_schema.validate(&arguments) {
+            if !silent_mode {
+                bprintln!(error: "Invalid arguments for {}.{}: {}", server_name, tool_name, problems.join("; "));
+            }
+
+            return ToolResult::error(format!(
+                "Invalid arguments for MCP tool {}.{}:\n{}\n\nExpected arguments:\n{}",
+                server_name,
+                tool_name,
+                problems
+                    .iter()
+                    .map(|problem| format!("- {problem}"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                tool.input_schema.describe()
+            ));
+        }
+    }
+
     // Format invocation message similar to the read tool style
     if !silent_mode {
         // Bold invocation message with icon and MCP tool details
@@ -281,6 +369,16 @@ pub async fn execute_dynamic_mcp_tool(
             }
         }
         Err(err) => {
+            // A lost connection means the server process died; let the monitor restart it
+            if matches!(
+                err,
+                crate::mcp::error::McpError::ServerDisconnected
+                    | crate::mcp::error::McpError::ResponseNotReceived
+                    | crate::mcp::error::McpError::ConnectionError(_)
+            ) {
+                crate::mcp::report_failure(server_name, &err.to_string());
+            }
+
             // Format error message for the agent in a read-tool-like format
             let error_msg = format!(
                 "Error: {}.{} - {}\n\nThe MCP tool execution failed. Please check the tool name and parameters.",
@@ -1,13 +1,39 @@
 pub mod agent;
+pub mod browser;
+pub mod check;
+pub mod config_edit;
+pub mod documents;
 pub mod done;
 pub mod fetch;
+pub mod fetch_cache;
+pub mod guard;
+pub mod html;
+pub mod json;
+pub mod kb;
+pub mod logs;
+pub mod lsp;
 pub mod mcp;
+pub mod network;
 pub mod patch;
 pub mod path_utils;
+pub mod permissions;
+pub mod plan;
+pub mod progress;
+pub mod proposals;
 pub mod read;
+pub mod remind;
+pub mod runbook;
+pub mod scratchpad;
 pub mod search;
+pub mod semsearch;
 pub mod shell;
+pub mod shell_session;
+pub mod sys;
 pub mod task;
+pub mod test_runner;
+pub mod todo;
+pub mod truncate;
+pub mod visual_diff;
 #[cfg(target_os = "macos")]
 pub mod ui;
 pub mod wait;
@@ -15,20 +41,40 @@ pub mod write;
 
 // Re-export all tool functions
 pub use agent::execute_agent_tool;
+pub use browser::execute_browser;
+pub use check::execute_check;
+pub use config_edit::execute_config_edit;
 pub use done::execute_done;
 pub use fetch::execute_fetch;
-pub use mcp::execute_dynamic_mcp_tool;
+pub use json::execute_json;
+pub use kb::execute_kb;
+pub use logs::execute_logs;
+pub use lsp::execute_lsp_tool;
+pub use mcp::{execute_dynamic_mcp_tool, execute_mcp_meta_tool};
 pub use patch::execute_patch;
 pub use read::execute_read;
+pub use remind::execute_remind;
+pub use runbook::execute_runbook;
+pub use scratchpad::execute_scratchpad;
 pub use search::execute_search;
+pub use semsearch::execute_semsearch;
 pub use shell::InterruptData;
+pub use shell_session::execute_shell_session;
+pub use sys::execute_sys;
 pub use task::execute_task;
+pub use test_runner::execute_test;
+pub use todo::execute_plan;
+#[cfg(target_os = "macos")]
+pub use ui::computer::execute_computer;
 #[cfg(target_os = "macos")]
 pub use ui::input::execute_input;
 #[cfg(target_os = "macos")]
+pub use ui::record::execute_record;
+#[cfg(target_os = "macos")]
 pub use ui::screendump::execute_screendump;
 #[cfg(target_os = "macos")]
 pub use ui::screenshot::execute_screenshot;
+pub use visual_diff::execute_visual_diff;
 pub use wait::execute_wait;
 pub use write::execute_write;
 
@@ -169,6 +215,7 @@ impl ToolResult {
 
 // Use macros for output instead of direct functions
 
+use crate::agent::budget::SubAgentCost;
 use crate::agent::AgentId;
 
 /// Handles tool execution with consistent processing
@@ -181,6 +228,12 @@ pub struct ToolExecutor {
     agent_id: Option<AgentId>,
     /// List of tools that are specifically disabled
     disabled_tools: Vec<String>,
+    /// Collected write/patch proposals when running read-only in propose mode
+    proposals: Option<std::sync::Mutex<proposals::ChangeProposals>>,
+    /// Channel receiving the progress reports of tool calls
+    progress: Option<progress::ProgressSender>,
+    /// Cost limit passed on to sub-agents started by the task tool
+    sub_agent_cost: SubAgentCost,
 }
 
 impl ToolExecutor {
@@ -191,6 +244,9 @@ impl ToolExecutor {
             silent_mode,
             agent_id: None,
             disabled_tools: Vec::new(),
+            proposals: None,
+            progress: None,
+            sub_agent_cost: SubAgentCost::default(),
         }
     }
 
@@ -201,6 +257,9 @@ impl ToolExecutor {
             silent_mode,
             agent_id: Some(agent_id),
             disabled_tools: Vec::new(),
+            proposals: None,
+            progress: None,
+            sub_agent_cost: SubAgentCost::default(),
         }
     }
 
@@ -209,6 +268,31 @@ impl ToolExecutor {
         self.disabled_tools = disabled_tools;
     }
 
+    /// Collect write/patch calls as proposed changes instead of rejecting them in read-only mode
+    pub fn enable_proposals(&mut self) {
+        self.proposals = Some(std::sync::Mutex::new(proposals::ChangeProposals::new()));
+    }
+
+    /// Send the progress reports of tool calls to a channel
+    pub fn set_progress_reporter(&mut self, reporter: progress::ProgressSender) {
+        self.progress = Some(reporter);
+    }
+
+    /// Set the cost limit of sub-agents and where they add what they spend
+    pub fn set_sub_agent_cost(&mut self, cost: SubAgentCost) {
+        self.sub_agent_cost = cost;
+    }
+
+    /// Save collected proposals and return their summary, if any changes were
+    /// proposed since the last save
+    pub fn finalize_proposals(&self) -> Option<String> {
+        let mut proposals = self.proposals.as_ref()?.lock().unwrap();
+        if proposals.is_empty() {
+            return None;
+        }
+        proposals.finalize(self.silent_mode)
+    }
+
     /// Check if executor is in silent mode
     pub fn is_silent(&self) -> bool {
         self.silent_mode
@@ -237,6 +321,29 @@ impl ToolExecutor {
             ));
         }
 
+        // In propose mode, write and patch are recorded as proposed changes
+        if self.readonly_mode {
+            if let Some(proposals) = &self.proposals {
+                match tool_name.as_str() {
+                    "write" => {
+                        return proposals.lock().unwrap().propose_write(
+                            args,
+                            body,
+                            self.silent_mode,
+                        )
+                    }
+                    "patch" => {
+                        return proposals.lock().unwrap().propose_patch(
+                            args,
+                            body,
+                            self.silent_mode,
+                        )
+                    }
+                    _ => {}
+                }
+            }
+        }
+
         // In readonly mode, only allow read-only tools (and task which will create readonly subagents)
         if self.readonly_mode && !self.is_readonly_tool(&tool_name) {
             if !self.silent_mode {
@@ -249,48 +356,123 @@ impl ToolExecutor {
             ));
         }
 
-        // Execute the appropriate tool with silent mode flag. Shell handled externally
-        let mut result = match tool_name.as_str() {
-            "agent" => execute_agent_tool(args, body, self.silent_mode, self.agent_id).await,
-            "read" => execute_read(args, body, self.silent_mode).await,
-            "write" => execute_write(args, body, self.silent_mode).await,
-            "patch" => execute_patch(args, body, self.silent_mode).await,
-            "fetch" => execute_fetch(args, body, self.silent_mode).await,
-            "search" => execute_search(args, body, self.silent_mode).await,
-            #[cfg(target_os = "macos")]
-            "screenshot" => execute_screenshot(args, body, self.silent_mode).await,
-            #[cfg(target_os = "macos")]
-            "input" => execute_input(args, body, self.silent_mode).await,
-            "done" => execute_done(args, body, self.silent_mode),
-            "task" => execute_task(args, body, self.silent_mode, self.agent_id).await,
-            #[cfg(target_os = "macos")]
-            "screendump" => execute_screendump(args, body, self.silent_mode).await,
-            "wait" => execute_wait(args, body, self.silent_mode),
-            _ => {
-                // Check if tool_name is an MCP server name
-                if crate::mcp::has_provider(&tool_name) {
-                    // In readonly mode, MCP tools are not available for safety
-                    if self.readonly_mode {
-                        if !self.silent_mode {
-                            bprintln!(error: "MCP tool '{}' is not available in read-only mode", tool_name);
+        // The sys tool only inspects the system in read-only mode
+        if self.readonly_mode && tool_name == "sys" && sys::is_mutating(args) {
+            let error_msg = "sys kill is not available in read-only mode";
+            if !self.silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+
+        // The browser tool only reads pages in read-only mode
+        if self.readonly_mode && tool_name == "browser" && browser::is_mutating(args) {
+            let error_msg = "browser click and fill are not available in read-only mode";
+            if !self.silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+
+        // Execute the appropriate tool with silent mode flag, passing its progress
+        // reports on. Shell handled externally
+        let call = async {
+            match tool_name.as_str() {
+                "agent" => execute_agent_tool(args, body, self.silent_mode, self.agent_id).await,
+                "read" => execute_read(args, body, self.silent_mode).await,
+                "write" => execute_write(args, body, self.silent_mode).await,
+                "patch" => execute_patch(args, body, self.silent_mode).await,
+                "json" => execute_json(args, body, self.silent_mode).await,
+                "config-edit" => execute_config_edit(args, body, self.silent_mode).await,
+                "fetch" => execute_fetch(args, body, self.silent_mode).await,
+                "search" => execute_search(args, body, self.silent_mode).await,
+                "browser" => execute_browser(args, body, self.silent_mode).await,
+                "shell-session" => execute_shell_session(args, body, self.silent_mode).await,
+                #[cfg(target_os = "macos")]
+                "screenshot" => execute_screenshot(args, body, self.silent_mode).await,
+                #[cfg(target_os = "macos")]
+                "input" => execute_input(args, body, self.silent_mode).await,
+                "done" => execute_done(args, body, self.silent_mode),
+                "task" => {
+                    execute_task(
+                        args,
+                        body,
+                        self.silent_mode,
+                        self.agent_id,
+                        &self.sub_agent_cost,
+                    )
+                    .await
+                }
+                #[cfg(target_os = "macos")]
+                "screendump" => execute_screendump(args, body, self.silent_mode).await,
+                #[cfg(target_os = "macos")]
+                "record" => execute_record(args, body, self.silent_mode).await,
+                #[cfg(target_os = "macos")]
+                "computer" => execute_computer(args, body, self.silent_mode, self.agent_id).await,
+                "visual-diff" => execute_visual_diff(args, body, self.silent_mode).await,
+                "wait" => execute_wait(args, body, self.silent_mode).await,
+                "remind" => execute_remind(args, body, self.silent_mode, self.agent_id),
+                "plan" => execute_plan(args, body, self.silent_mode, self.agent_id),
+                "scratchpad" => {
+                    execute_scratchpad(args, body, self.silent_mode, self.agent_id).await
+                }
+                "test" => execute_test(args, body, self.silent_mode).await,
+                "check" => execute_check(args, body, self.silent_mode).await,
+                "semsearch" => execute_semsearch(args, body, self.silent_mode).await,
+                "kb" => execute_kb(args, body, self.silent_mode).await,
+                "runbook" => execute_runbook(args, body, self.silent_mode).await,
+                "logs" => execute_logs(args, body, self.silent_mode).await,
+                "sys" => execute_sys(args, body, self.silent_mode).await,
+                "definition" | "references" | "hover" | "rename" => {
+                    execute_lsp_tool(&tool_name, args, self.silent_mode).await
+                }
+                "mcp" => execute_mcp_meta_tool(args, body, self.silent_mode).await,
+                _ => {
+                    // Check if tool_name is an MCP server name, a namespaced MCP tool or an alias
+                    if crate::mcp::is_mcp_tool(&tool_name) {
+                        // In readonly mode, MCP tools are not available for safety
+                        if self.readonly_mode {
+                            if !self.silent_mode {
+                                bprintln!(error: "MCP tool '{}' is not available in read-only mode", tool_name);
+                            }
+                            return ToolResult::error(format!(
+                                "MCP tool '{}' is not available in read-only mode",
+                                tool_name
+                            ));
                         }
-                        return ToolResult::error(format!(
-                            "MCP tool '{}' is not available in read-only mode",
-                            tool_name
-                        ));
-                    }
 
-                    // It's an MCP server name, so handle it as a dynamic MCP tool
-                    execute_dynamic_mcp_tool(&tool_name, args, body, self.silent_mode).await
-                } else {
-                    if !self.silent_mode {
-                        // Always use buffer-based printing with direct formatting
-                        bprintln !(error:"Unknown tool: {:?}, args:{}, body:{}", tool_name, args, body);
+                        if crate::mcp::has_provider(&tool_name) {
+                            // Server name with the tool name as the first argument
+                            execute_dynamic_mcp_tool(&tool_name, args, body, self.silent_mode).await
+                        } else {
+                            // `server.tool` or an alias, resolved to its server. The JSON
+                            // arguments may start on the header line
+                            let (server_name, mcp_tool_name) =
+                                crate::mcp::resolve_tool(&tool_name).unwrap_or_default();
+                            let arguments = if args.trim().is_empty() {
+                                body.to_string()
+                            } else {
+                                format!("{args}\n{body}")
+                            };
+                            execute_dynamic_mcp_tool(
+                                &server_name,
+                                &mcp_tool_name,
+                                &arguments,
+                                self.silent_mode,
+                            )
+                            .await
+                        }
+                    } else {
+                        if !self.silent_mode {
+                            // Always use buffer-based printing with direct formatting
+                            bprintln !(error:"Unknown tool: {:?}, args:{}, body:{}", tool_name, args, body);
+                        }
+                        ToolResult::error(format!("Unknown tool: {:?}", tool_name))
                     }
-                    ToolResult::error(format!("Unknown tool: {:?}", tool_name))
                 }
             }
         };
+        let mut result = progress::scope(self.progress.clone(), call).await;
 
         // Apply UTF-8 safe truncation to long text outputs
         for i in 0..result.content.len() {
@@ -332,6 +514,11 @@ impl ToolExecutor {
             }
         }
 
+        // Mark content from external sources as untrusted before the model sees it
+        if guard::is_untrusted_tool(&tool_name) {
+            guard::protect(&tool_name, &mut result.content, self.silent_mode).await;
+        }
+
         result
     }
 
@@ -343,13 +530,30 @@ impl ToolExecutor {
                 | "shell"
                 | "fetch"
                 | "search"
+                | "browser"
                 | "screenshot"
                 | "screendump"
+                | "record"
+                | "visual-diff"
                 | "done"
                 | "task"
                 | "agent"
                 | "wait"
-                | "computer" // Note: input is NOT read-only as it modifies application state
+                | "remind"
+                | "scratchpad"
+                | "plan"
+                // Like shell, test and check run project code but edit no files through
+                // a tool; they ask for shell permission and are planned in plan mode instead
+                | "test"
+                | "check"
+                | "semsearch"
+                | "kb"
+                | "runbook"
+                | "logs"
+                | "sys"
+                | "definition"
+                | "references"
+                | "hover" // Note: input and computer are NOT read-only as they modify application state
         )
     }
 }
@@ -494,4 +698,31 @@ mod tests {
         // Verify UTF-8 validity
         assert!(std::str::from_utf8(truncated_mixed.as_bytes()).is_ok());
     }
+
+    #[test]
+    fn test_success_from_mcp() {
+        let content: Vec<crate::mcp::protocol::content::Content> =
+            serde_json::from_value(serde_json::json!([
+                { "type": "resource", "resource":
+                    { "uri": "file:///report.pdf", "mimeType": "application/pdf", "blob": "JVBERi0=" } },
+                { "type": "resource", "resource":
+                    { "uri": "file:///data.bin", "blob": "AAAA" } },
+            ]))
+            .unwrap();
+        let result = ToolResult::success_from_mcp(content);
+        assert_eq!(
+            result.content[0],
+            crate::llm::Content::Document {
+                source: crate::llm::DocumentSource::Base64 {
+                    media_type: "application/pdf".to_string(),
+                    data: "JVBERi0=".to_string(),
+                },
+                title: Some("file:///report.pdf".to_string()),
+            }
+        );
+        assert!(matches!(
+            &result.content[1],
+            crate::llm::Content::Text { text } if text.starts_with("Resource file:///data.bin")
+        ));
+    }
 }
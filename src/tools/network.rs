@@ -0,0 +1,390 @@
+//! Network policy for tools that access the web
+//!
+//! The fetch, search and browser tools consult this policy before making a request:
+//! - blocked domains are rejected, and with an allowlist only listed domains are reachable,
+//!   also as the target of a redirect or of a page navigation
+//! - responses larger than the maximum download size are cut off with an error
+//! - optionally, URLs disallowed by the site's robots.txt are not fetched
+//!
+//! In interactive mode a request to a rejected domain asks the user instead of
+//! failing outright (see [`crate::tools::permissions::request_domain_access`]).
+//!
+//! Domain patterns match the domain itself and all of its subdomains
+//! (`example.com` matches `docs.example.com`); `*.example.com` matches only
+//! subdomains.
+
+use crate::tools::progress;
+use lazy_static::lazy_static;
+use reqwest::Url;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+/// User agent token looked up in robots.txt files
+const ROBOTS_USER_AGENT: &str = "termineer";
+
+/// Timeout for downloading robots.txt
+const ROBOTS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Redirects followed per request, as by reqwest's default policy
+const MAX_REDIRECTS: usize = 10;
+
+/// Network access settings
+#[derive(Debug, Clone, Default)]
+pub struct NetworkPolicy {
+    /// Only these domains may be accessed (empty = all domains)
+    pub allowed_domains: Vec<String>,
+    /// Domains that may never be accessed without asking
+    pub blocked_domains: Vec<String>,
+    /// Largest response body in bytes (None = unlimited)
+    pub max_download_size: Option<u64>,
+    /// Whether robots.txt rules are respected
+    pub respect_robots_txt: bool,
+}
+
+lazy_static! {
+    static ref POLICY: RwLock<NetworkPolicy> = RwLock::new(NetworkPolicy::default());
+    /// Parsed robots.txt rules per origin (scheme://host:port)
+    static ref ROBOTS_CACHE: Mutex<HashMap<String, RobotsRules>> = Mutex::new(HashMap::new());
+}
+
+/// Set the network policy for all tools
+pub fn configure(policy: NetworkPolicy) {
+    *POLICY.write().unwrap() = policy;
+}
+
+/// Whether a host matches a domain pattern
+fn domain_matches(host: &str, pattern: &str) -> bool {
+    let host = host.trim_end_matches('.').to_lowercase();
+    let pattern = pattern.trim().trim_end_matches('.').to_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(parent) => host.ends_with(&format!(".{parent}")),
+        None => host == pattern || host.ends_with(&format!(".{pattern}")),
+    }
+}
+
+/// Check a host against the allow and block lists, returning the reason for rejection
+fn check_domain(policy: &NetworkPolicy, host: &str) -> Result<(), String> {
+    if let Some(pattern) = policy
+        .blocked_domains
+        .iter()
+        .find(|pattern| domain_matches(host, pattern))
+    {
+        return Err(format!(
+            "Access to '{host}' is blocked by the network policy ({pattern})"
+        ));
+    }
+
+    if !policy.allowed_domains.is_empty()
+        && !policy
+            .allowed_domains
+            .iter()
+            .any(|pattern| domain_matches(host, pattern))
+    {
+        return Err(format!(
+            "Access to '{host}' is not allowed by the network policy (allowed: {})",
+            policy.allowed_domains.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check whether a tool may access a URL, asking the user for rejected domains
+pub async fn check_url(tool_name: &str, url: &str) -> Result<(), String> {
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid URL '{url}': {e}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Unsupported URL scheme '{}'", parsed.scheme()));
+    }
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| format!("URL '{url}' has no host"))?
+        .to_string();
+
+    let policy = POLICY.read().unwrap().clone();
+    if let Err(reason) = check_domain(&policy, &host) {
+        crate::tools::permissions::request_domain_access(tool_name, &host, &reason).await?;
+    }
+
+    if policy.respect_robots_txt && !robots_allowed(&parsed).await {
+        return Err(format!(
+            "Fetching '{url}' is disallowed by the site's robots.txt"
+        ));
+    }
+
+    Ok(())
+}
+
+/// An HTTP client for tool requests that checks every redirect against the
+/// policy, so an allowed site cannot forward a request to a rejected domain
+///
+/// A redirect cannot wait for the user, so it only passes a rejected domain the
+/// user already allowed.
+pub fn client() -> reqwest::Client {
+    crate::net::client_builder()
+        .redirect(reqwest::redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error(format!("more than {MAX_REDIRECTS} redirects"));
+            }
+            match check_redirect(attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(reason) => attempt.error(reason),
+            }
+        }))
+        .build()
+        .expect("Failed to create HTTP client")
+}
+
+/// Check the target of a redirect or navigation without asking the user
+pub fn check_redirect(url: &Url) -> Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!(
+            "Redirect to unsupported URL scheme '{}'",
+            url.scheme()
+        ));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("Redirect to '{url}' without a host"))?;
+
+    let policy = POLICY.read().unwrap().clone();
+    match check_domain(&policy, host) {
+        Err(_) if crate::tools::permissions::is_domain_allowed(host) => Ok(()),
+        result => result,
+    }
+}
+
+/// Read a response body, failing once it exceeds the maximum download size
+pub async fn read_body(mut response: reqwest::Response) -> Result<Vec<u8>, String> {
+    let limit = POLICY.read().unwrap().max_download_size;
+    if let (Some(limit), Some(length)) = (limit, response.content_length()) {
+        if length > limit {
+            return Err(download_too_large(limit));
+        }
+    }
+
+    let length = response.content_length().filter(|length| *length > 0);
+    let mut reported = 0;
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|err| format!("Error reading response: {err}"))?
+    {
+        body.extend_from_slice(&chunk);
+        if let Some(limit) = limit.filter(|limit| body.len() as u64 > *limit) {
+            return Err(download_too_large(limit));
+        }
+        report_download(body.len() as u64, length, &mut reported);
+    }
+    Ok(body)
+}
+
+/// Report the progress of a download each time another percent arrived, or
+/// another megabyte when the length is unknown
+fn report_download(received: u64, length: Option<u64>, reported: &mut u64) {
+    const MEGABYTE: u64 = 1_000_000;
+
+    let mark = match length {
+        Some(length) => received * 100 / length,
+        None => received / MEGABYTE,
+    };
+    if mark <= *reported {
+        return;
+    }
+    *reported = mark;
+
+    let megabytes = received as f64 / MEGABYTE as f64;
+    match length {
+        Some(length) => progress::report(
+            Some(received as f64 / length as f64),
+            format!("{megabytes:.1}/{:.1} MB", length as f64 / MEGABYTE as f64),
+        ),
+        None => progress::report(None, format!("{megabytes:.1} MB")),
+    }
+}
+
+fn download_too_large(limit: u64) -> String {
+    format!("Response exceeds the maximum download size of {limit} bytes")
+}
+
+/// Allow and disallow rules from robots.txt that apply to us
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RobotsRules {
+    /// (allowed, path pattern)
+    rules: Vec<(bool, String)>,
+}
+
+impl RobotsRules {
+    /// Parse the group for our user agent, falling back to the `*` group
+    fn parse(content: &str) -> Self {
+        let mut specific = Vec::new();
+        let mut wildcard = Vec::new();
+        let mut agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        let mut has_specific_group = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let field = field.trim().to_lowercase();
+            let value = value.trim();
+
+            match field.as_str() {
+                "user-agent" => {
+                    // A user-agent line after rules starts a new group
+                    if in_rules {
+                        agents.clear();
+                        in_rules = false;
+                    }
+                    agents.push(value.to_lowercase());
+                    has_specific_group |= value.eq_ignore_ascii_case(ROBOTS_USER_AGENT);
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    // An empty Disallow allows everything
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let rule = (field == "allow", value.to_string());
+                    if agents.iter().any(|agent| agent == ROBOTS_USER_AGENT) {
+                        specific.push(rule.clone());
+                    }
+                    if agents.iter().any(|agent| agent == "*") {
+                        wildcard.push(rule);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            rules: if has_specific_group {
+                specific
+            } else {
+                wildcard
+            },
+        }
+    }
+
+    /// Whether a path is allowed; the longest matching rule wins, Allow on ties
+    fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| robots_pattern_matches(pattern, path))
+            .max_by_key(|(allowed, pattern)| (pattern.len(), *allowed))
+            .map(|(allowed, _)| *allowed)
+            .unwrap_or(true)
+    }
+}
+
+/// Match a robots.txt path pattern supporting `*` wildcards and a `$` end anchor
+fn robots_pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    for (index, part) in parts.iter().enumerate() {
+        let is_last = index == parts.len() - 1;
+        if is_last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+
+    !anchored || rest.is_empty()
+}
+
+/// Whether robots.txt of the URL's site allows fetching it
+async fn robots_allowed(url: &Url) -> bool {
+    let origin = url.origin().ascii_serialization();
+    let cached = ROBOTS_CACHE.lock().unwrap().get(&origin).cloned();
+    let rules = match cached {
+        Some(rules) => rules,
+        None => {
+            let rules = download_robots(&origin).await;
+            ROBOTS_CACHE
+                .lock()
+                .unwrap()
+                .insert(origin.clone(), rules.clone());
+            rules
+        }
+    };
+
+    let mut path = url.path().to_string();
+    if let Some(query) = url.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    rules.allows(&path)
+}
+
+/// Download and parse robots.txt; a missing or unreadable file allows everything
+async fn download_robots(origin: &str) -> RobotsRules {
+    let client = match crate::net::client_builder().timeout(ROBOTS_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(_) => return RobotsRules::default(),
+    };
+    match client.get(format!("{origin}/robots.txt")).send().await {
+        Ok(response) if response.status().is_success() => response
+            .text()
+            .await
+            .map(|content| RobotsRules::parse(&content))
+            .unwrap_or_default(),
+        _ => RobotsRules::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_policy() {
+        assert!(domain_matches("docs.rs", "docs.rs"));
+        assert!(domain_matches("api.github.com", "github.com"));
+        assert!(!domain_matches("notgithub.com", "github.com"));
+        assert!(domain_matches("api.github.com", "*.github.com"));
+        assert!(!domain_matches("github.com", "*.github.com"));
+
+        let policy = NetworkPolicy {
+            allowed_domains: vec!["rust-lang.org".to_string(), "docs.rs".to_string()],
+            blocked_domains: vec!["internal.rust-lang.org".to_string()],
+            ..Default::default()
+        };
+        assert!(check_domain(&policy, "doc.rust-lang.org").is_ok());
+        assert!(check_domain(&policy, "internal.rust-lang.org").is_err());
+        assert!(check_domain(&policy, "example.com").is_err());
+        assert!(check_domain(&NetworkPolicy::default(), "example.com").is_ok());
+    }
+
+    #[test]
+    fn test_robots_rules() {
+        let rules = RobotsRules::parse(
+            "User-agent: *\nDisallow: /private/\nAllow: /private/public\nDisallow: /*.pdf$\n\n\
+             User-agent: other\nDisallow: /\n",
+        );
+        assert!(rules.allows("/docs/index.html"));
+        assert!(!rules.allows("/private/secret"));
+        assert!(rules.allows("/private/public/page"));
+        assert!(!rules.allows("/files/report.pdf"));
+        assert!(rules.allows("/files/report.pdf?download=1"));
+
+        let specific =
+            RobotsRules::parse("User-agent: *\nDisallow:\n\nUser-agent: Termineer\nDisallow: /\n");
+        assert!(!specific.allows("/anything"));
+    }
+}
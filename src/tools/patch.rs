@@ -62,114 +62,29 @@ pub async fn execute_patch(args: &str, body: &str, silent_mode: bool) -> ToolRes
     };
 
     // Parse the patch content
-    let before_delimiter = match patch_content.find(PATCH_DELIMITER_BEFORE) {
-        Some(pos) => pos,
-        None => {
+    let (before_text, after_text) = match parse_patch_content(patch_content) {
+        Ok(parts) => parts,
+        Err(error_msg) => {
             if !silent_mode {
-                bprintln !(error:"Missing '{PATCH_DELIMITER_BEFORE}' delimiter in patch");
-            }
-
-            return ToolResult::error(format!(
-                "Missing '{PATCH_DELIMITER_BEFORE}' delimiter in patch"
-            ));
-        }
-    };
-
-    bprintln!(dev: "rest: {}", &patch_content[before_delimiter..]);
-
-    let after_delimiter = match patch_content[before_delimiter..].find(PATCH_DELIMITER_AFTER) {
-        Some(pos) => before_delimiter + pos,
-        None => {
-            if !silent_mode {
-                bprintln !(error:"Missing '{PATCH_DELIMITER_AFTER}' delimiter in patch");
+                bprintln !(error:"{}", error_msg);
             }
 
-            return ToolResult::error(format!(
-                "Missing '{PATCH_DELIMITER_AFTER}' delimiter in patch"
-            ));
+            return ToolResult::error(error_msg);
         }
     };
 
-    let end_delimiter = match patch_content[after_delimiter..].find(PATCH_DELIMITER_END) {
-        Some(pos) => after_delimiter + pos,
-        None => {
+    let new_content = match apply_replacement(&file_content, before_text, after_text) {
+        Ok(content) => content,
+        Err(error_msg) => {
             if !silent_mode {
-                bprintln !(error:"Missing '{PATCH_DELIMITER_END}' delimiter in patch");
+                // Use buffer-based printing directly
+                bprintln !(error:"{}", error_msg);
             }
 
-            return ToolResult::error(format!(
-                "Missing '{PATCH_DELIMITER_END}' delimiter in patch"
-            ));
+            return ToolResult::error(error_msg);
         }
     };
 
-    // Extract the before and after text
-    // Skip the delimiter line itself by finding the next newline
-    let before_start =
-        match patch_content[before_delimiter + PATCH_DELIMITER_BEFORE.len()..].find('\n') {
-            Some(pos) => before_delimiter + PATCH_DELIMITER_BEFORE.len() + pos + 1,
-            None => before_delimiter + PATCH_DELIMITER_BEFORE.len(),
-        };
-
-    let after_start =
-        match patch_content[after_delimiter + PATCH_DELIMITER_AFTER.len()..].find('\n') {
-            Some(pos) => after_delimiter + PATCH_DELIMITER_AFTER.len() + pos + 1,
-            None => after_delimiter + PATCH_DELIMITER_AFTER.len(),
-        };
-
-    // Ensure indices are in bounds
-    if before_start >= after_delimiter || after_start >= end_delimiter {
-        let error_msg = "Invalid patch format: delimiter positions are invalid".to_string();
-
-        if !silent_mode {
-            // Use buffer-based printing
-            bprintln !(error:"{error_msg} {patch_content:?}");
-        }
-
-        return ToolResult::error(error_msg);
-    }
-
-    let before_text = patch_content[before_start..after_delimiter].trim();
-    let after_text = patch_content[after_start..end_delimiter].trim();
-
-    // Count occurrences of the before_text in file_content
-    let mut count = 0;
-    let mut start_index = 0;
-    while let Some(index) = file_content[start_index..].find(before_text) {
-        count += 1;
-        start_index += index + 1;
-        
-        // Early exit if we've already found multiple occurrences
-        if count > 1 {
-            break;
-        }
-    }
-
-    if count == 0 {
-        if !silent_mode {
-            // Use buffer-based printing directly
-            bprintln !(error:"Text to replace not found in the file: '{before_text}'");
-        }
-
-        return ToolResult::error(format!(
-            "Text to replace not found in the file: '{before_text}'"
-        ));
-    }
-
-    // Check if the text appears multiple times in the file
-    if count > 1 {
-        if !silent_mode {
-            // Use buffer-based printing directly
-            bprintln !(error:"Patch failed: Text to replace occurs multiple times ({count} occurrences) in the file. Please provide more context to make the patch unique.");
-        }
-
-        return ToolResult::error(format!(
-            "Ambiguous patch: Text to replace occurs multiple times ({count} occurrences) in the file. Please provide more context to make the patch unique."
-        ));
-    }
-
-    let new_content = file_content.replace(before_text, after_text);
-
     // Get a safe display path for output messages
     let safe_display_path = validated_path.to_string_lossy();
 
@@ -327,6 +242,84 @@ pub async fn execute_patch(args: &str, body: &str, silent_mode: bool) -> ToolRes
     }
 }
 
+/// Split patch content into the text to replace and its replacement
+pub(crate) fn parse_patch_content(patch_content: &str) -> Result<(&str, &str), String> {
+    let before_delimiter = patch_content
+        .find(PATCH_DELIMITER_BEFORE)
+        .ok_or_else(|| format!("Missing '{PATCH_DELIMITER_BEFORE}' delimiter in patch"))?;
+
+    let after_delimiter = patch_content[before_delimiter..]
+        .find(PATCH_DELIMITER_AFTER)
+        .map(|pos| before_delimiter + pos)
+        .ok_or_else(|| format!("Missing '{PATCH_DELIMITER_AFTER}' delimiter in patch"))?;
+
+    let end_delimiter = patch_content[after_delimiter..]
+        .find(PATCH_DELIMITER_END)
+        .map(|pos| after_delimiter + pos)
+        .ok_or_else(|| format!("Missing '{PATCH_DELIMITER_END}' delimiter in patch"))?;
+
+    // Extract the before and after text
+    // Skip the delimiter line itself by finding the next newline
+    let before_start =
+        match patch_content[before_delimiter + PATCH_DELIMITER_BEFORE.len()..].find('\n') {
+            Some(pos) => before_delimiter + PATCH_DELIMITER_BEFORE.len() + pos + 1,
+            None => before_delimiter + PATCH_DELIMITER_BEFORE.len(),
+        };
+
+    let after_start =
+        match patch_content[after_delimiter + PATCH_DELIMITER_AFTER.len()..].find('\n') {
+            Some(pos) => after_delimiter + PATCH_DELIMITER_AFTER.len() + pos + 1,
+            None => after_delimiter + PATCH_DELIMITER_AFTER.len(),
+        };
+
+    // Ensure indices are in bounds
+    if before_start >= after_delimiter || after_start >= end_delimiter {
+        return Err("Invalid patch format: delimiter positions are invalid".to_string());
+    }
+
+    Ok((
+        patch_content[before_start..after_delimiter].trim(),
+        patch_content[after_start..end_delimiter].trim(),
+    ))
+}
+
+/// Replace the single occurrence of `before_text` in `file_content`
+///
+/// Fails if the text is missing or occurs more than once.
+pub(crate) fn apply_replacement(
+    file_content: &str,
+    before_text: &str,
+    after_text: &str,
+) -> Result<String, String> {
+    // Count occurrences of the before_text in file_content
+    let mut count = 0;
+    let mut start_index = 0;
+    while let Some(index) = file_content[start_index..].find(before_text) {
+        count += 1;
+        start_index += index + 1;
+
+        // Early exit if we've already found multiple occurrences
+        if count > 1 {
+            break;
+        }
+    }
+
+    if count == 0 {
+        return Err(format!(
+            "Text to replace not found in the file: '{before_text}'"
+        ));
+    }
+
+    // Check if the text appears multiple times in the file
+    if count > 1 {
+        return Err(format!(
+            "Ambiguous patch: Text to replace occurs multiple times ({count} occurrences) in the file. Please provide more context to make the patch unique."
+        ));
+    }
+
+    Ok(file_content.replace(before_text, after_text))
+}
+
 // Helper function to compute the Longest Common Subsequence
 fn longest_common_subsequence<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<(usize, usize)> {
     let m = a.len();
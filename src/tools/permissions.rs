@@ -0,0 +1,465 @@
+//! Interactive permission prompts per tool category
+//!
+//! The first use of each tool category in a session asks the user to allow or
+//! deny it. Prompts are queued here and rendered by the TUI; decisions marked
+//! "always" are persisted per project in `.termineer/permissions.json`.
+//!
+//! Prompts are only issued once `enable_prompts` has been called (interactive
+//! mode). Non-interactive runs keep the previous behavior and allow all tools.
+//!
+//! The network policy also uses these prompts to ask before accessing a domain
+//! that is blocked or not on the allowlist; those decisions are kept per domain.
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+/// Location of the persisted permission decisions, relative to the project root
+pub const PERMISSIONS_FILE: &str = ".termineer/permissions.json";
+
+/// Categories of tools that require user permission
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolCategory {
+    /// Tools that modify files (write, patch, rename)
+    FileWrite,
+    /// Shell command execution, and tools running project code (test, check)
+    ShellExec,
+    /// Outbound network access (fetch, search, browser, kb)
+    Network,
+    /// Synthetic keyboard and mouse input (input, computer)
+    UiInput,
+    /// Tools provided by MCP servers
+    McpServer,
+    /// Signalling processes (sys kill)
+    ProcessControl,
+}
+
+impl ToolCategory {
+    /// Determine the category of a tool, if it requires permission
+    pub fn for_tool(tool_name: &str) -> Option<Self> {
+        match tool_name {
+            "write" | "patch" | "json" | "config-edit" | "rename" => Some(Self::FileWrite),
+            // Test runners, build checks and linters run project code
+            "shell" | "shell-session" | "test" | "check" => Some(Self::ShellExec),
+            // kb embeds its query with the remote embedding model
+            "fetch" | "search" | "browser" | "kb" => Some(Self::Network),
+            "input" => Some(Self::UiInput),
+            name if crate::mcp::is_mcp_tool(name) => Some(Self::McpServer),
+            _ => None,
+        }
+    }
+
+    /// Determine the category of a tool call, taking its arguments into account
+    pub fn for_invocation(tool_name: &str, args: &str) -> Option<Self> {
+        match tool_name {
+            // Inspecting the system needs no permission, signalling processes does
+            "sys" => crate::tools::sys::is_mutating(args).then_some(Self::ProcessControl),
+            // Screenshots of the computer tool only look
+            "computer" => (!args.trim_start().starts_with("screenshot")).then_some(Self::UiInput),
+            _ => Self::for_tool(tool_name),
+        }
+    }
+
+    /// Human-readable name of the category
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::FileWrite => "file write",
+            Self::ShellExec => "shell exec",
+            Self::Network => "network",
+            Self::UiInput => "UI input",
+            Self::McpServer => "MCP server",
+            Self::ProcessControl => "process control",
+        }
+    }
+}
+
+/// User response to a permission prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionDecision {
+    /// Allow the category for the rest of this session
+    Allow,
+    /// Deny the category for the rest of this session
+    Deny,
+    /// Allow the category now and in future sessions for this project
+    Always,
+}
+
+/// A permission prompt waiting for the user's decision
+pub struct PermissionPrompt {
+    /// Category being requested
+    pub category: ToolCategory,
+    /// Tool that triggered the request
+    pub tool_name: String,
+    /// Short description of the invocation
+    pub summary: String,
+    /// Domain being requested, for network policy prompts
+    pub domain: Option<String>,
+}
+
+/// Pending request together with the channel used to deliver the decision
+struct PendingRequest {
+    prompt: PermissionPrompt,
+    responder: oneshot::Sender<PermissionDecision>,
+}
+
+/// On-disk format of the permissions file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PermissionFile {
+    #[serde(default)]
+    always_allow: Vec<ToolCategory>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    allowed_domains: Vec<String>,
+}
+
+/// Session-wide permission state
+struct PermissionManager {
+    /// Whether interactive prompts are enabled
+    enabled: bool,
+    /// Decisions made in this session (true = allowed)
+    session: HashMap<ToolCategory, bool>,
+    /// Decisions about domains outside the network policy (true = allowed)
+    domains: HashMap<String, bool>,
+    /// Requests waiting for the user
+    pending: VecDeque<PendingRequest>,
+}
+
+lazy_static! {
+    static ref PERMISSION_MANAGER: Mutex<PermissionManager> = Mutex::new(PermissionManager {
+        enabled: false,
+        session: HashMap::new(),
+        domains: HashMap::new(),
+        pending: VecDeque::new(),
+    });
+}
+
+/// Enable interactive permission prompts and load persisted decisions
+pub fn enable_prompts() {
+    let persisted = load_permission_file(Path::new(PERMISSIONS_FILE));
+
+    let mut manager = PERMISSION_MANAGER.lock().unwrap();
+    manager.enabled = true;
+    for category in persisted.always_allow {
+        manager.session.insert(category, true);
+    }
+    for domain in persisted.allowed_domains {
+        manager.domains.insert(domain, true);
+    }
+}
+
+/// Ask for permission to run a tool, waiting for the user if needed
+///
+/// Returns an error message suitable for the agent when the category is denied.
+pub async fn request_permission(tool_name: &str, args: &str) -> Result<(), String> {
+    let Some(category) = ToolCategory::for_invocation(tool_name, args) else {
+        return Ok(());
+    };
+
+    let receiver = {
+        let mut manager = PERMISSION_MANAGER.lock().unwrap();
+        if !manager.enabled {
+            return Ok(());
+        }
+
+        match manager.session.get(&category) {
+            Some(true) => return Ok(()),
+            Some(false) => return Err(denied_message(category)),
+            None => {}
+        }
+
+        let (responder, receiver) = oneshot::channel();
+        manager.pending.push_back(PendingRequest {
+            prompt: PermissionPrompt {
+                category,
+                tool_name: tool_name.to_string(),
+                summary: args.trim().chars().take(120).collect(),
+                domain: None,
+            },
+            responder,
+        });
+        receiver
+    };
+
+    match receiver.await {
+        Ok(PermissionDecision::Allow) | Ok(PermissionDecision::Always) => Ok(()),
+        Ok(PermissionDecision::Deny) | Err(_) => Err(denied_message(category)),
+    }
+}
+
+/// Whether the user already allowed a domain rejected by the network policy
+pub fn is_domain_allowed(domain: &str) -> bool {
+    PERMISSION_MANAGER.lock().unwrap().domains.get(domain) == Some(&true)
+}
+
+/// Ask the user whether a tool may access a domain rejected by the network policy
+///
+/// Without interactive prompts the policy decision stands and `reason` is returned.
+pub async fn request_domain_access(
+    tool_name: &str,
+    domain: &str,
+    reason: &str,
+) -> Result<(), String> {
+    let receiver = {
+        let mut manager = PERMISSION_MANAGER.lock().unwrap();
+        if !manager.enabled {
+            return Err(reason.to_string());
+        }
+
+        match manager.domains.get(domain) {
+            Some(true) => return Ok(()),
+            Some(false) => return Err(reason.to_string()),
+            None => {}
+        }
+
+        let (responder, receiver) = oneshot::channel();
+        manager.pending.push_back(PendingRequest {
+            prompt: PermissionPrompt {
+                category: ToolCategory::Network,
+                tool_name: tool_name.to_string(),
+                summary: reason.to_string(),
+                domain: Some(domain.to_string()),
+            },
+            responder,
+        });
+        receiver
+    };
+
+    match receiver.await {
+        Ok(PermissionDecision::Allow) | Ok(PermissionDecision::Always) => Ok(()),
+        Ok(PermissionDecision::Deny) | Err(_) => Err(reason.to_string()),
+    }
+}
+
+/// Format the prompt currently waiting for a decision, if any
+///
+/// Returns the popup title and body for the TUI temporary output.
+pub fn pending_prompt() -> Option<(String, String)> {
+    let manager = PERMISSION_MANAGER.lock().unwrap();
+    manager.pending.front().map(|request| {
+        let prompt = &request.prompt;
+        if let Some(domain) = &prompt.domain {
+            let title = format!("🌐 Network access: {domain}");
+            let body = format!(
+                "The agent wants to access '{}' with the '{}' tool:\n  {}\n\n[y] allow for this session   [n/Enter] deny   [a] always allow this domain for this project",
+                domain, prompt.tool_name, prompt.summary
+            );
+            return (title, body);
+        }
+
+        let title = format!("🔐 Permission required: {}", prompt.category.display_name());
+        let body = format!(
+            "The agent wants to use the '{}' tool ({} category){}\n\n[y] allow for this session   [n/Enter] deny   [a] always allow for this project",
+            prompt.tool_name,
+            prompt.category.display_name(),
+            if prompt.summary.is_empty() {
+                String::new()
+            } else {
+                format!(":\n  {}", prompt.summary)
+            }
+        );
+        (title, body)
+    })
+}
+
+/// Deliver the user's decision for the oldest pending prompt
+///
+/// Other queued requests for the same category are resolved with the same decision.
+pub fn respond(decision: PermissionDecision) {
+    let mut manager = PERMISSION_MANAGER.lock().unwrap();
+    let Some(request) = manager.pending.pop_front() else {
+        return;
+    };
+
+    let category = request.prompt.category;
+    let domain = request.prompt.domain.clone();
+    let allowed = decision != PermissionDecision::Deny;
+    match &domain {
+        Some(domain) => {
+            manager.domains.insert(domain.clone(), allowed);
+        }
+        None => {
+            manager.session.insert(category, allowed);
+        }
+    }
+
+    if decision == PermissionDecision::Always {
+        let result = match &domain {
+            Some(domain) => persist_allowed_domain(domain),
+            None => persist_always_allow(category),
+        };
+        if let Err(e) = result {
+            bprintln!(error: "Failed to save permissions: {}", e);
+        }
+    }
+
+    let _ = request.responder.send(decision);
+
+    // Resolve any other waiters for the same category (or domain)
+    let (same, rest): (VecDeque<_>, VecDeque<_>) = manager
+        .pending
+        .drain(..)
+        .partition(|r| r.prompt.category == category && r.prompt.domain == domain);
+    manager.pending = rest;
+    for waiting in same {
+        let _ = waiting.responder.send(decision);
+    }
+}
+
+fn denied_message(category: ToolCategory) -> String {
+    format!(
+        "Permission denied: the user did not allow {} tools in this session",
+        category.display_name()
+    )
+}
+
+fn load_permission_file(path: &Path) -> PermissionFile {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn persist_always_allow(category: ToolCategory) -> std::io::Result<()> {
+    let path = Path::new(PERMISSIONS_FILE);
+    let mut file = load_permission_file(path);
+    if !file.always_allow.contains(&category) {
+        file.always_allow.push(category);
+    }
+    write_permission_file(path, &file)
+}
+
+fn persist_allowed_domain(domain: &str) -> std::io::Result<()> {
+    let path = Path::new(PERMISSIONS_FILE);
+    let mut file = load_permission_file(path);
+    if !file.allowed_domains.iter().any(|d| d == domain) {
+        file.allowed_domains.push(domain.to_string());
+    }
+    write_permission_file(path, &file)
+}
+
+fn write_permission_file(path: &Path, file: &PermissionFile) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(file)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    std::fs::write(path, json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_write_category() {
+        for tool in ["write", "patch", "json", "config-edit", "rename"] {
+            assert_eq!(
+                ToolCategory::for_tool(tool),
+                Some(ToolCategory::FileWrite),
+                "{tool}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_shell_exec_category() {
+        for tool in ["shell", "shell-session", "test", "check"] {
+            assert_eq!(
+                ToolCategory::for_tool(tool),
+                Some(ToolCategory::ShellExec),
+                "{tool}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_network_and_input_categories() {
+        for tool in ["fetch", "search", "browser", "kb"] {
+            assert_eq!(
+                ToolCategory::for_tool(tool),
+                Some(ToolCategory::Network),
+                "{tool}"
+            );
+        }
+        assert_eq!(ToolCategory::for_tool("input"), Some(ToolCategory::UiInput));
+    }
+
+    #[test]
+    fn test_read_only_tools_need_no_permission() {
+        for tool in ["read", "hover", "references", "sys", "done"] {
+            assert_eq!(ToolCategory::for_tool(tool), None, "{tool}");
+        }
+    }
+
+    #[test]
+    fn test_sys_category() {
+        assert_eq!(
+            ToolCategory::for_invocation("sys", "kill 1234"),
+            Some(ToolCategory::ProcessControl)
+        );
+        assert_eq!(ToolCategory::for_invocation("sys", "ports"), None);
+        assert_eq!(ToolCategory::for_invocation("sys", ""), None);
+    }
+
+    #[test]
+    fn test_computer_category() {
+        assert_eq!(
+            ToolCategory::for_invocation("computer", "click 10 20"),
+            Some(ToolCategory::UiInput)
+        );
+        assert_eq!(
+            ToolCategory::for_invocation("computer", "type hello"),
+            Some(ToolCategory::UiInput)
+        );
+        assert_eq!(
+            ToolCategory::for_invocation("computer", " screenshot"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_mcp_category() {
+        let aliases = HashMap::from([(
+            "permission_test_alias".to_string(),
+            "remote_tool".to_string(),
+        )]);
+        crate::mcp::manager::register_aliases("permission_test_server", &aliases);
+
+        assert_eq!(
+            ToolCategory::for_tool("permission_test_alias"),
+            Some(ToolCategory::McpServer)
+        );
+        assert_eq!(ToolCategory::for_tool("permission_test_unknown"), None);
+    }
+
+    #[test]
+    fn test_prompt_offers_enter_as_deny() {
+        let mut manager = PERMISSION_MANAGER.lock().unwrap();
+        let (responder, _receiver) = oneshot::channel();
+        manager.pending.push_back(PendingRequest {
+            prompt: PermissionPrompt {
+                category: ToolCategory::ShellExec,
+                tool_name: "shell".to_string(),
+                summary: "ls".to_string(),
+                domain: None,
+            },
+            responder,
+        });
+        drop(manager);
+
+        let (_, body) = pending_prompt().unwrap();
+        assert!(body.contains("[n/Enter] deny"));
+        respond(PermissionDecision::Deny);
+        assert_eq!(
+            PERMISSION_MANAGER
+                .lock()
+                .unwrap()
+                .session
+                .get(&ToolCategory::ShellExec),
+            Some(&false)
+        );
+    }
+}
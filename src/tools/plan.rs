@@ -0,0 +1,390 @@
+//! Plan-only (dry-run) mode support
+//!
+//! When an agent runs with `--plan`, mutating tool invocations are not executed.
+//! They are recorded as planned actions instead, and the final response includes
+//! the ordered plan. The plan is saved to disk so it can be reviewed and later
+//! executed with `--apply`.
+
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_GREEN, FORMAT_RESET};
+use crate::tools::shell::{execute_shell, ShellOutput};
+use crate::tools::{InterruptData, ToolExecutor, ToolResult};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Default location of the plan file, relative to the working directory
+pub const DEFAULT_PLAN_FILE: &str = ".termineer/plan.json";
+
+/// Shell commands that are considered read-only and still run in plan mode
+const SHELL_ALLOWLIST: &[&str] = &[
+    "ls", "cat", "head", "tail", "grep", "rg", "find", "pwd", "wc", "echo", "which", "file",
+    "stat", "tree", "du", "df", "diff", "sort", "uniq", "cut",
+];
+
+/// Git subcommands that are considered read-only
+const GIT_ALLOWLIST: &[&str] = &[
+    "status", "diff", "log", "show", "branch", "blame", "ls-files",
+];
+
+/// Options of find that run commands or write files
+const FIND_WRITE_OPTIONS: &[&str] = &[
+    "-exec", "-execdir", "-ok", "-okdir", "-delete", "-fls", "-fprint", "-fprint0", "-fprintf",
+];
+
+/// Options of git branch that only list branches; anything else creates,
+/// deletes, renames or configures them
+const GIT_BRANCH_LIST_OPTIONS: &[&str] = &[
+    "-a",
+    "--all",
+    "-r",
+    "--remotes",
+    "-v",
+    "-vv",
+    "--verbose",
+    "-l",
+    "--list",
+    "--show-current",
+    "--merged",
+    "--no-merged",
+    "--contains",
+    "--no-contains",
+    "--color",
+    "--no-color",
+];
+
+/// Instructions appended to the system prompt when plan mode is active
+pub const PLAN_MODE_PROMPT: &str = r#"
+
+# PLAN MODE
+You are running in plan-only mode. Mutating actions (write, patch, rename, input,
+test and check runs, and shell commands that modify state) are NOT executed - they
+are recorded as planned actions and will be reviewed by the user before being
+applied.
+- Investigate using read-only tools as usual.
+- Issue each change exactly as you would if it were executed.
+- Do not expect planned actions to have any effect on later reads.
+- When the plan is complete, call the done tool with a short summary of the plan."#;
+
+/// A single mutating tool invocation recorded in plan mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedAction {
+    /// Name of the tool (write, patch, shell, input)
+    pub tool: String,
+    /// Arguments as provided by the LLM
+    pub args: String,
+    /// Tool body as provided by the LLM
+    pub body: String,
+}
+
+impl PlannedAction {
+    /// One-line description of this action for summaries
+    pub fn describe(&self) -> String {
+        let args = self.args.trim();
+        match self.tool.as_str() {
+            "write" => format!("write {} ({} lines)", args, self.body.lines().count()),
+            "patch" => format!("patch {}", args),
+            "shell" if args.is_empty() => {
+                let first_line = self.body.lines().next().unwrap_or("").trim();
+                format!("shell {}", first_line)
+            }
+            _ => format!("{} {}", self.tool, args),
+        }
+    }
+}
+
+/// Ordered list of planned actions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActionPlan {
+    /// Actions in the order they were issued
+    pub actions: Vec<PlannedAction>,
+}
+
+impl ActionPlan {
+    /// Create an empty plan
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a new action, returning its 1-based position in the plan
+    pub fn record(&mut self, tool: &str, args: &str, body: &str) -> usize {
+        self.actions.push(PlannedAction {
+            tool: tool.to_string(),
+            args: args.to_string(),
+            body: body.to_string(),
+        });
+        self.actions.len()
+    }
+
+    /// Whether the plan contains no actions
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Format the ordered plan as plain text for the final response
+    pub fn format_summary(&self) -> String {
+        if self.actions.is_empty() {
+            return "Planned actions: none".to_string();
+        }
+
+        let mut summary = format!("Planned actions ({}):", self.actions.len());
+        for (i, action) in self.actions.iter().enumerate() {
+            summary.push_str(&format!("\n{}. {}", i + 1, action.describe()));
+        }
+        summary
+    }
+
+    /// Save the plan as JSON, creating parent directories as needed
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        std::fs::write(path, json)
+    }
+
+    /// Load a plan from a JSON file
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Check whether a tool invocation must be recorded instead of executed in plan mode
+pub fn should_plan(tool_name: &str, args: &str, body: &str) -> bool {
+    match tool_name {
+        "write" | "patch" | "input" | "shell-session" | "rename" => true,
+        // Test runners, build checks and linters run project code
+        "test" | "check" => true,
+        "json" | "config-edit" => !args.trim_start().starts_with("get"),
+        "computer" => !args.trim_start().starts_with("screenshot"),
+        "sys" => crate::tools::sys::is_mutating(args),
+        "browser" => crate::tools::browser::is_mutating(args),
+        // The shell runs the arguments and the body as one script
+        "shell" => !is_allowlisted_shell_command(&format!("{args}\n{body}")),
+        _ => false,
+    }
+}
+
+/// Check whether every segment of a shell command is on the read-only allowlist
+pub fn is_allowlisted_shell_command(command: &str) -> bool {
+    let command = command.trim();
+    if command.is_empty() {
+        return false;
+    }
+
+    // Redirections and substitutions can hide writes
+    if command.contains('>') || command.contains('`') || command.contains("$(") {
+        return false;
+    }
+
+    command
+        .split(|c| c == '|' || c == ';' || c == '&' || c == '\n')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .all(|segment| {
+            let words: Vec<&str> = segment.split_whitespace().collect();
+            match words.as_slice() {
+                ["git", sub, args @ ..] => {
+                    GIT_ALLOWLIST.contains(sub) && is_readonly_git(sub, args)
+                }
+                [program, args @ ..] => {
+                    SHELL_ALLOWLIST.contains(program) && is_readonly_invocation(program, args)
+                }
+                [] => true,
+            }
+        })
+}
+
+/// Check the arguments of an allowlisted program for options that write files
+/// or run other commands
+fn is_readonly_invocation(program: &str, args: &[&str]) -> bool {
+    match program {
+        "find" => !args.iter().any(|arg| FIND_WRITE_OPTIONS.contains(arg)),
+        "sort" => {
+            !has_output_option(args)
+                && !args.iter().any(|arg| arg.starts_with("--compress-program"))
+        }
+        "tree" => !has_output_option(args),
+        // A second operand is the output file
+        "uniq" => args.iter().filter(|arg| !arg.starts_with('-')).count() <= 1,
+        // A preprocessor is run on every file searched
+        "rg" => !args.iter().any(|arg| arg.starts_with("--pre")),
+        _ => true,
+    }
+}
+
+/// Check for an `-o FILE` or `--output` option, which writes to a file
+///
+/// `-o` may be combined with other short options, as in `-no FILE`.
+fn has_output_option(args: &[&str]) -> bool {
+    args.iter().any(|arg| {
+        arg.starts_with("--output")
+            || (arg.starts_with('-') && !arg.starts_with("--") && arg.contains('o'))
+    })
+}
+
+/// Check the arguments of an allowlisted git subcommand for writes
+fn is_readonly_git(sub: &str, args: &[&str]) -> bool {
+    // diff, log and show can write their output to a file
+    if args.iter().any(|arg| arg.starts_with("--output")) {
+        return false;
+    }
+    match sub {
+        "branch" => args.iter().all(|arg| {
+            GIT_BRANCH_LIST_OPTIONS.contains(arg)
+                || arg.starts_with("--sort=")
+                || arg.starts_with("--format=")
+        }),
+        _ => true,
+    }
+}
+
+/// Execute all actions of an approved plan in order
+///
+/// Stops at the first failing action. Returns the number of actions applied.
+pub async fn apply_plan(plan: &ActionPlan, silent_mode: bool) -> Result<usize, String> {
+    let executor = ToolExecutor::new(false, silent_mode);
+
+    for (i, action) in plan.actions.iter().enumerate() {
+        if !silent_mode {
+            bprintln!(
+                "{FORMAT_BOLD}▶ [{}/{}] {}{FORMAT_RESET}",
+                i + 1,
+                plan.actions.len(),
+                action.describe()
+            );
+        }
+
+        let result = if action.tool == "shell" {
+            run_shell_action(action, silent_mode).await
+        } else {
+            executor
+                .execute_with_parts(&action.tool, &action.args, &action.body)
+                .await
+        };
+
+        if !result.success {
+            return Err(format!(
+                "Action {} ({}) failed: {}",
+                i + 1,
+                action.describe(),
+                result.to_text().trim()
+            ));
+        }
+    }
+
+    if !silent_mode {
+        bprintln!(
+            "{FORMAT_GREEN}✅ Applied {} planned action(s){FORMAT_RESET}",
+            plan.actions.len()
+        );
+    }
+
+    Ok(plan.actions.len())
+}
+
+/// Run a planned shell action to completion and return its final result
+async fn run_shell_action(action: &PlannedAction, silent_mode: bool) -> ToolResult {
+    let interrupt_data = Arc::new(Mutex::new(InterruptData::new()));
+    let mut rx = match execute_shell(&action.args, &action.body, interrupt_data, silent_mode).await
+    {
+        Ok(rx) => rx,
+        Err(e) => return ToolResult::error(format!("Shell execution error: {e}")),
+    };
+
+    while let Some(output) = rx.recv().await {
+        match output {
+            ShellOutput::Stdout(line) | ShellOutput::Stderr(line) => {
+                if !silent_mode {
+                    bprintln!("{FORMAT_GRAY}{line}{FORMAT_RESET}");
+                }
+            }
+            ShellOutput::Complete(result, _) => return result,
+        }
+    }
+
+    ToolResult::error("Shell command ended without a result")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_allowlist() {
+        assert!(is_allowlisted_shell_command("ls -la"));
+        assert!(is_allowlisted_shell_command("git status && git diff HEAD"));
+        assert!(is_allowlisted_shell_command("cat foo.rs | grep fn"));
+        assert!(!is_allowlisted_shell_command("rm -rf target"));
+        assert!(!is_allowlisted_shell_command("echo hi > file.txt"));
+        assert!(!is_allowlisted_shell_command("git commit -m test"));
+        assert!(!is_allowlisted_shell_command("ls; cargo build"));
+        assert!(!is_allowlisted_shell_command(""));
+
+        // Allowlisted programs with options that write or run commands
+        assert!(!is_allowlisted_shell_command("env rm -rf target"));
+        assert!(is_allowlisted_shell_command("find . -name '*.rs'"));
+        assert!(!is_allowlisted_shell_command("find . -name '*.rs' -delete"));
+        assert!(!is_allowlisted_shell_command("find . -exec rm {} +"));
+        assert!(is_allowlisted_shell_command("sort -rn counts.txt"));
+        assert!(!is_allowlisted_shell_command(
+            "sort -o counts.txt counts.txt"
+        ));
+        assert!(!is_allowlisted_shell_command("sort -no out.txt in.txt"));
+        assert!(!is_allowlisted_shell_command(
+            "sort --output=out.txt in.txt"
+        ));
+        assert!(is_allowlisted_shell_command("tree -L 2 src"));
+        assert!(!is_allowlisted_shell_command("tree -o tree.txt"));
+        assert!(!is_allowlisted_shell_command("tree -ao tree.txt src"));
+        assert!(is_allowlisted_shell_command("sort data.txt | uniq -c"));
+        assert!(!is_allowlisted_shell_command("uniq in.txt out.txt"));
+        assert!(is_allowlisted_shell_command("rg -n fn src"));
+        assert!(!is_allowlisted_shell_command("rg --pre ./run.sh fn"));
+        assert!(!is_allowlisted_shell_command("rg --pre=./run.sh fn"));
+        assert!(is_allowlisted_shell_command("git branch -a -v"));
+        assert!(!is_allowlisted_shell_command("git branch -D main"));
+        assert!(!is_allowlisted_shell_command("git branch new-feature"));
+        assert!(!is_allowlisted_shell_command(
+            "git diff --output=patch.diff"
+        ));
+    }
+
+    #[test]
+    fn test_should_plan() {
+        assert!(should_plan("write", "a.txt", "content"));
+        assert!(should_plan("patch", "a.txt", "content"));
+        assert!(should_plan("shell", "cargo build", ""));
+        assert!(!should_plan("shell", "", "ls -la"));
+        assert!(!should_plan("shell", "ls", "cat Cargo.toml"));
+        assert!(should_plan("shell", "rm -rf target", "ls"));
+        assert!(should_plan("shell", "ls", "ls\ncargo build"));
+        assert!(!should_plan("read", "a.txt", ""));
+        assert!(should_plan("rename", "src/main.rs:10 run start", ""));
+        assert!(!should_plan("hover", "src/main.rs:10", ""));
+        assert!(should_plan("test", "cargo parser", ""));
+        assert!(should_plan("check", "", ""));
+        assert!(should_plan("sys", "kill 42", ""));
+        assert!(!should_plan("sys", "ports 8080", ""));
+        assert!(should_plan("browser", "click #submit", ""));
+        assert!(should_plan("computer", "click 10 20 Safari:1", ""));
+        assert!(!should_plan("computer", "screenshot Safari:1", ""));
+        assert!(!should_plan("browser", "text main", ""));
+    }
+
+    #[test]
+    fn test_plan_summary_order() {
+        let mut plan = ActionPlan::new();
+        assert_eq!(plan.record("write", "a.txt", "one\ntwo"), 1);
+        assert_eq!(plan.record("shell", "cargo fmt", ""), 2);
+
+        let summary = plan.format_summary();
+        assert!(summary.starts_with("Planned actions (2):"));
+        assert!(summary.contains("1. write a.txt (2 lines)"));
+        assert!(summary.contains("2. shell cargo fmt"));
+    }
+}
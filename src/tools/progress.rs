@@ -0,0 +1,110 @@
+//! Progress reports of long-running tools
+//!
+//! Tools that take a while, such as downloads, indexing and batches, call
+//! [`report`] as they go instead of staying silent until they finish. The tool
+//! executor routes the reports of a call to the channel of the agent running
+//! it, where the TUI shows the latest one in its status bar; each report is
+//! also logged as a debug line. Outside a tool call reports are ignored, so
+//! shared code such as the embeddings index can report unconditionally.
+
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// How far a running tool is
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolProgress {
+    /// Completed fraction from 0 to 1, when the total is known
+    pub fraction: Option<f64>,
+    /// What the tool is doing, e.g. "12/40 chunks embedded"
+    pub step: String,
+}
+
+impl fmt::Display for ToolProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.fraction {
+            Some(fraction) => write!(f, "{:.0}% {}", fraction * 100.0, self.step),
+            None => write!(f, "{}", self.step),
+        }
+    }
+}
+
+/// Sending side of an agent's progress channel, shared by its tool calls
+pub type ProgressSender = Arc<watch::Sender<Option<ToolProgress>>>;
+
+/// Receiving side of an agent's progress channel; `None` while no tool reports
+pub type ProgressReceiver = watch::Receiver<Option<ToolProgress>>;
+
+tokio::task_local! {
+    /// Channel of the tool call running in this task
+    static REPORTER: ProgressSender;
+}
+
+/// Create a progress channel
+pub fn channel() -> (ProgressSender, ProgressReceiver) {
+    let (sender, receiver) = watch::channel(None);
+    (Arc::new(sender), receiver)
+}
+
+/// Run a tool call with its reports sent to `sender`, clearing the progress
+/// when the call ends
+pub async fn scope<F: Future>(sender: Option<ProgressSender>, call: F) -> F::Output {
+    let Some(sender) = sender else {
+        return call.await;
+    };
+    let output = REPORTER.scope(sender.clone(), call).await;
+    sender.send_replace(None);
+    output
+}
+
+/// Report the progress of the running tool call
+pub fn report(fraction: Option<f64>, step: impl Into<String>) {
+    let progress = ToolProgress {
+        fraction: fraction.map(|fraction| fraction.clamp(0.0, 1.0)),
+        step: step.into(),
+    };
+    let _ = REPORTER.try_with(|sender| {
+        bprintln!(debug: "Progress: {}", progress);
+        sender.send_replace(Some(progress));
+    });
+}
+
+/// Report `done` of `total` items, e.g. files or pages
+pub fn report_count(done: usize, total: usize, items: &str) {
+    let fraction = (total > 0).then(|| done as f64 / total as f64);
+    report(fraction, format!("{done}/{total} {items}"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope() {
+        // Without a tool call there is nobody to report to
+        report(Some(0.5), "ignored");
+
+        // Reports are logged to the output buffer of the agent's task
+        let buffer = crate::output::SharedBuffer::new(10);
+        let (sender, receiver) = channel();
+        let seen = crate::output::CURRENT_BUFFER
+            .scope(
+                buffer,
+                scope(Some(sender), async {
+                    report_count(3, 4, "files");
+                    receiver.borrow().clone()
+                }),
+            )
+            .await;
+        assert_eq!(
+            seen,
+            Some(ToolProgress {
+                fraction: Some(0.75),
+                step: "3/4 files".to_string(),
+            })
+        );
+        assert_eq!(seen.unwrap().to_string(), "75% 3/4 files");
+        assert_eq!(*receiver.borrow(), None);
+    }
+}
@@ -0,0 +1,306 @@
+//! "Propose changes" support for read-only agents
+//!
+//! In propose mode the agent runs read-only, but write and patch calls are not
+//! rejected. They are applied to an in-memory copy of each file instead, and the
+//! accumulated changes are saved as a unified diff under `.termineer/proposals/`
+//! when the agent finishes. Each saved patch only holds the changes made since
+//! the previous one, so the patches apply in order.
+
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
+use crate::tools::patch::{apply_replacement, parse_patch_content};
+use crate::tools::ToolResult;
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Directory where proposal patch files are written
+pub const PROPOSALS_DIR: &str = ".termineer/proposals";
+
+/// Instructions appended to the system prompt when propose mode is active
+pub const PROPOSE_MODE_PROMPT: &str = r#"
+
+# PROPOSE CHANGES MODE
+You are running in read-only mode, but you may still use the write and patch tools.
+Changes are NOT applied to disk - they are collected into a patch file that the user
+will review. Later reads return the original file contents, while later patches
+build on your earlier proposed changes. When finished, call the done tool with a
+summary of the proposed changes."#;
+
+/// Original and proposed content of a single file
+struct ProposedFile {
+    /// Content on disk when first touched (None if the file did not exist)
+    original: Option<String>,
+    /// Content after applying all proposed changes
+    proposed: String,
+}
+
+/// Accumulated file changes proposed by a read-only agent
+#[derive(Default)]
+pub struct ChangeProposals {
+    files: IndexMap<String, ProposedFile>,
+    /// Proposed content of each file as of the last saved patch
+    saved: HashMap<String, String>,
+}
+
+impl ChangeProposals {
+    /// Create an empty proposal set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether any changes were proposed
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Whether any file changed since the last saved patch
+    pub fn has_unsaved(&self) -> bool {
+        self.files
+            .iter()
+            .any(|(path, file)| self.saved.get(path) != Some(&file.proposed))
+    }
+
+    /// Record a proposed write of the full file content
+    pub fn propose_write(
+        &mut self,
+        filename: &str,
+        content: &str,
+        silent_mode: bool,
+    ) -> ToolResult {
+        let display_path = match self.resolve(filename) {
+            Ok(path) => path,
+            Err(error_msg) => return report_error(error_msg, silent_mode),
+        };
+
+        self.entry(&display_path).proposed = content.to_string();
+
+        if !silent_mode {
+            bprintln!(tool: "write",
+                "{FORMAT_BOLD}📝 Proposed write: {display_path} ({} lines){FORMAT_RESET}",
+                content.lines().count()
+            );
+        }
+
+        ToolResult::success(format!(
+            "Proposed write to '{display_path}' recorded ({} lines). The file on disk was NOT modified.",
+            content.lines().count()
+        ))
+    }
+
+    /// Record a proposed patch, applied on top of earlier proposals for the same file
+    pub fn propose_patch(&mut self, filename: &str, body: &str, silent_mode: bool) -> ToolResult {
+        let display_path = match self.resolve(filename) {
+            Ok(path) => path,
+            Err(error_msg) => return report_error(error_msg, silent_mode),
+        };
+
+        let (before_text, after_text) = match parse_patch_content(body) {
+            Ok(parts) => parts,
+            Err(error_msg) => return report_error(error_msg, silent_mode),
+        };
+
+        let current = match self.files.get(&display_path) {
+            Some(file) => file.proposed.clone(),
+            None => match std::fs::read_to_string(&display_path) {
+                Ok(content) => content,
+                Err(e) => {
+                    return report_error(
+                        format!("Error reading file '{display_path}': {e}"),
+                        silent_mode,
+                    )
+                }
+            },
+        };
+
+        let patched = match apply_replacement(&current, before_text, after_text) {
+            Ok(content) => content,
+            Err(error_msg) => return report_error(error_msg, silent_mode),
+        };
+
+        self.entry(&display_path).proposed = patched;
+
+        if !silent_mode {
+            bprintln!(tool: "patch",
+                "{FORMAT_BOLD}📝 Proposed patch: {display_path} (-{} lines, +{} lines){FORMAT_RESET}",
+                before_text.lines().count(),
+                after_text.lines().count()
+            );
+        }
+
+        ToolResult::success(format!(
+            "Proposed patch to '{display_path}' recorded. The file on disk was NOT modified."
+        ))
+    }
+
+    /// Render the changes made since the last saved patch as a single unified
+    /// diff, relative to the last saved content of each file
+    pub fn unified_diff(&self) -> String {
+        let mut output = String::new();
+        for (path, file) in &self.files {
+            let base = match self.saved.get(path) {
+                Some(saved) if *saved == file.proposed => continue,
+                Some(saved) => Some(saved.as_str()),
+                None => file.original.as_deref(),
+            };
+            let old_header = if base.is_some() {
+                format!("a/{path}")
+            } else {
+                "/dev/null".to_string()
+            };
+            let diff = similar::TextDiff::from_lines(base.unwrap_or(""), file.proposed.as_str());
+            output.push_str(
+                &diff
+                    .unified_diff()
+                    .context_radius(3)
+                    .header(&old_header, &format!("b/{path}"))
+                    .to_string(),
+            );
+        }
+        output
+    }
+
+    /// Save the changes since the last saved patch to
+    /// `.termineer/proposals/<timestamp>.patch`
+    pub fn save(&mut self) -> std::io::Result<PathBuf> {
+        let dir = Path::new(PROPOSALS_DIR);
+        std::fs::create_dir_all(dir)?;
+
+        let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let path = dir.join(format!("{timestamp}.patch"));
+        std::fs::write(&path, self.unified_diff())?;
+        self.mark_saved();
+        Ok(path)
+    }
+
+    /// Remember the current proposals as saved
+    fn mark_saved(&mut self) {
+        for (path, file) in &self.files {
+            self.saved.insert(path.clone(), file.proposed.clone());
+        }
+    }
+
+    /// Short plain-text summary of the proposed changes
+    pub fn summary(&self) -> String {
+        let mut summary = format!("Proposed changes ({} files):", self.files.len());
+        for (path, file) in &self.files {
+            let status = if file.original.is_none() {
+                "new file"
+            } else {
+                "modified"
+            };
+            summary.push_str(&format!("\n- {path} ({status})"));
+        }
+        summary
+    }
+
+    /// Save the changes since the last saved patch and return a summary for
+    /// the final response, or `None` if nothing changed since then
+    pub fn finalize(&mut self, silent_mode: bool) -> Option<String> {
+        if !self.has_unsaved() {
+            return None;
+        }
+
+        let mut summary = self.summary();
+        match self.save() {
+            Ok(path) => {
+                summary.push_str(&format!(
+                    "\n\nPatch saved to {}. Apply it with: git apply {}",
+                    path.display(),
+                    path.display()
+                ));
+                if !silent_mode {
+                    bprintln!(
+                        "{FORMAT_GRAY}Proposed changes saved to {}{FORMAT_RESET}",
+                        path.display()
+                    );
+                }
+            }
+            Err(e) => {
+                bprintln!(error: "Failed to save proposed changes: {}", e);
+            }
+        }
+        Some(summary)
+    }
+
+    /// Validate a path and convert it to a path relative to the working directory
+    fn resolve(&self, filename: &str) -> Result<String, String> {
+        let filename = filename.trim();
+        if filename.is_empty() {
+            return Err("A filename is required as an argument".to_string());
+        }
+
+        let validated = crate::tools::path_utils::validate_path(filename)
+            .map_err(|e| format!("Security error for file '{filename}': {e}"))?;
+        let base = std::env::current_dir()
+            .and_then(|dir| dir.canonicalize())
+            .map_err(|e| format!("Failed to resolve working directory: {e}"))?;
+
+        Ok(validated
+            .strip_prefix(&base)
+            .unwrap_or(&validated)
+            .to_string_lossy()
+            .to_string())
+    }
+
+    /// Get the entry for a file, capturing its original content on first use
+    fn entry(&mut self, display_path: &str) -> &mut ProposedFile {
+        self.files
+            .entry(display_path.to_string())
+            .or_insert_with(|| {
+                let original = std::fs::read_to_string(display_path).ok();
+                ProposedFile {
+                    proposed: original.clone().unwrap_or_default(),
+                    original,
+                }
+            })
+    }
+}
+
+fn report_error(error_msg: String, silent_mode: bool) -> ToolResult {
+    if !silent_mode {
+        bprintln!(error: "{}", error_msg);
+    }
+    ToolResult::error(error_msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn propose(
+        proposals: &mut ChangeProposals,
+        path: &str,
+        original: Option<&str>,
+        proposed: &str,
+    ) {
+        proposals
+            .files
+            .entry(path.to_string())
+            .or_insert_with(|| ProposedFile {
+                original: original.map(String::from),
+                proposed: String::new(),
+            })
+            .proposed = proposed.to_string();
+    }
+
+    #[test]
+    fn test_only_unsaved_changes_are_diffed() {
+        let mut proposals = ChangeProposals::new();
+        propose(&mut proposals, "a.txt", Some("one\n"), "two\n");
+        propose(&mut proposals, "b.txt", None, "new\n");
+        assert!(proposals.has_unsaved());
+        let diff = proposals.unified_diff();
+        assert!(diff.contains("+two") && diff.contains("--- /dev/null"));
+
+        proposals.mark_saved();
+        assert!(!proposals.has_unsaved());
+        assert_eq!(proposals.unified_diff(), "");
+
+        // A later change is diffed against the saved content, not the original
+        propose(&mut proposals, "b.txt", None, "newer\n");
+        assert!(proposals.has_unsaved());
+        let diff = proposals.unified_diff();
+        assert!(!diff.contains("a.txt"));
+        assert!(diff.contains("--- a/b.txt") && diff.contains("-new") && diff.contains("+newer"));
+    }
+}
@@ -269,8 +269,23 @@ async fn read_file_content(
         return read_image_file(&validated_path, safe_display_path.to_string(), silent_mode).await;
     }
 
-    // Regular text file handling
-    match fs::read_to_string(&validated_path).await {
+    // Documents are converted to text, everything else is read as text directly
+    let document_kind = extension
+        .as_deref()
+        .and_then(crate::tools::documents::DocumentKind::from_extension);
+    let content = match document_kind {
+        Some(kind) => match fs::read(&validated_path).await {
+            Ok(bytes) => tokio::task::spawn_blocking(move || kind.extract_text(&bytes))
+                .await
+                .unwrap_or_else(|e| Err(format!("Text extraction failed: {e}"))),
+            Err(e) => Err(e.to_string()),
+        },
+        None => fs::read_to_string(&validated_path)
+            .await
+            .map_err(|e| e.to_string()),
+    };
+
+    match content {
         Ok(content) => {
             // Split content into lines
             let lines: Vec<&str> = content.lines().collect();
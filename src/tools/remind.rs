@@ -0,0 +1,236 @@
+//! Remind tool for scheduling timed messages to agents
+//!
+//! Agents can ask to be reminded later ("check the CI again in 10 minutes").
+//! The reminder is delivered as a regular message by the agent manager's
+//! scheduler, which wakes a waiting agent or queues behind current work.
+//!
+//! Usage:
+//! - `remind in=10m [agent=NAME|ID]` with the message in the body
+//! - `remind at=14:30` or `remind at=2025-06-01T09:00:00+02:00`
+//! - `remind list`
+//! - `remind cancel ID`
+
+use crate::agent::{AgentId, Reminder};
+use crate::constants::{FORMAT_BOLD, FORMAT_RESET};
+use crate::tools::ToolResult;
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+use std::time::Duration;
+
+/// Longest delay a reminder may be scheduled with
+const MAX_DELAY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Execute the remind tool
+pub fn execute_remind(
+    args: &str,
+    body: &str,
+    silent_mode: bool,
+    agent_id: Option<AgentId>,
+) -> ToolResult {
+    let mut tokens = args.split_whitespace();
+    let result = match tokens.clone().next() {
+        Some("list") => Ok(list_reminders(agent_id)),
+        Some("cancel") => {
+            tokens.next();
+            cancel_reminder(tokens.next(), agent_id)
+        }
+        _ => schedule_reminder(args, body, agent_id),
+    };
+
+    match result {
+        Ok(output) => {
+            if !silent_mode {
+                bprintln !(tool: "remind", "{FORMAT_BOLD}⏰ Remind:{FORMAT_RESET} {}", output);
+            }
+            ToolResult::success(output)
+        }
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            ToolResult::error(error_msg)
+        }
+    }
+}
+
+/// Parse arguments and schedule a new reminder
+fn schedule_reminder(args: &str, body: &str, agent_id: Option<AgentId>) -> Result<String, String> {
+    let mut delay = None;
+    let mut target = None;
+
+    for arg in args.split_whitespace() {
+        if let Some(value) = arg.strip_prefix("in=") {
+            delay = Some(parse_delay(value)?);
+        } else if let Some(value) = arg.strip_prefix("at=") {
+            delay = Some(delay_until(value, Local::now())?);
+        } else if let Some(value) = arg.strip_prefix("agent=") {
+            target = Some(resolve_agent(value)?);
+        } else {
+            return Err(format!(
+                "Unknown remind argument '{arg}'. Use in=DURATION, at=TIME or agent=NAME"
+            ));
+        }
+    }
+
+    let delay = delay.ok_or("A reminder requires in=DURATION or at=TIME")?;
+    if delay > MAX_DELAY {
+        return Err("Reminders can be scheduled at most 7 days ahead".to_string());
+    }
+
+    let message = body.trim();
+    if message.is_empty() {
+        return Err("Reminder message is required in the body".to_string());
+    }
+
+    let target = target
+        .or(agent_id)
+        .ok_or("No agent to remind; specify agent=NAME")?;
+
+    let reminder = crate::agent::schedule_reminder(target, agent_id, delay, message.to_string())
+        .map_err(|e| format!("Failed to schedule reminder: {e}"))?;
+
+    Ok(format!(
+        "Reminder {} scheduled for {} (agent {})",
+        reminder.id,
+        reminder.due.format("%Y-%m-%d %H:%M:%S"),
+        reminder.target
+    ))
+}
+
+/// List pending reminders for the calling agent
+fn list_reminders(agent_id: Option<AgentId>) -> String {
+    let reminders = crate::agent::get_reminders(agent_id);
+    if reminders.is_empty() {
+        return "No pending reminders".to_string();
+    }
+
+    let mut listing = String::from("Pending reminders:");
+    for reminder in reminders {
+        listing.push_str(&format!("\n{}", describe(&reminder)));
+    }
+    listing
+}
+
+/// Cancel a reminder by ID, if the calling agent scheduled or receives it
+fn cancel_reminder(id: Option<&str>, agent_id: Option<AgentId>) -> Result<String, String> {
+    let id: u64 = id
+        .ok_or("cancel requires a reminder ID")?
+        .parse()
+        .map_err(|_| "Reminder ID must be a number".to_string())?;
+    let reminder = crate::agent::cancel_reminder(id, agent_id).map_err(|e| e.to_string())?;
+    Ok(format!("Cancelled {}", describe(&reminder)))
+}
+
+fn describe(reminder: &Reminder) -> String {
+    let first_line = reminder.message.lines().next().unwrap_or("");
+    format!(
+        "#{} at {} for agent {}: {}",
+        reminder.id,
+        reminder.due.format("%Y-%m-%d %H:%M:%S"),
+        reminder.target,
+        first_line
+    )
+}
+
+/// Resolve an agent by name or numeric ID
+fn resolve_agent(value: &str) -> Result<AgentId, String> {
+    if let Ok(id) = value.parse::<u64>() {
+        let id = AgentId(id);
+        if crate::agent::get_agents()
+            .iter()
+            .any(|(agent, _)| *agent == id)
+        {
+            return Ok(id);
+        }
+    }
+    crate::agent::get_agent_id_by_name(value).ok_or_else(|| format!("Agent '{value}' not found"))
+}
+
+/// Parse a delay such as `90`, `45s`, `10m`, `2h` or `1h30m`
+fn parse_delay(value: &str) -> Result<Duration, String> {
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
+    }
+
+    let invalid = || format!("Invalid duration '{value}'. Use e.g. 90s, 10m, 2h or 1h30m");
+    let mut total = 0u64;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let amount: u64 = number.parse().map_err(|_| invalid())?;
+        number.clear();
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            _ => return Err(invalid()),
+        };
+        total = amount
+            .checked_mul(unit)
+            .and_then(|seconds| total.checked_add(seconds))
+            .ok_or_else(invalid)?;
+    }
+    if !number.is_empty() || total == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// Delay until a local time of day (`HH:MM[:SS]`, next occurrence) or an RFC 3339 timestamp
+fn delay_until(value: &str, now: DateTime<Local>) -> Result<Duration, String> {
+    let due = if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        time.with_timezone(&Local)
+    } else {
+        let time = NaiveTime::parse_from_str(value, "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+            .map_err(|_| format!("Invalid time '{value}'. Use HH:MM or an RFC 3339 timestamp"))?;
+        let today = now.date_naive().and_time(time);
+        let mut due = Local
+            .from_local_datetime(&today)
+            .earliest()
+            .ok_or_else(|| format!("Time '{value}' does not exist today"))?;
+        if due <= now {
+            due += chrono::Duration::days(1);
+        }
+        due
+    };
+
+    (due - now)
+        .to_std()
+        .map_err(|_| format!("Time '{value}' is in the past"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_delay() {
+        assert_eq!(parse_delay("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_delay("10m").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_delay("1h30m").unwrap(), Duration::from_secs(5400));
+        assert!(parse_delay("10x").is_err());
+        assert!(parse_delay("m").is_err());
+        assert!(parse_delay("5m3").is_err());
+        assert!(parse_delay("9999999999999999999d").is_err());
+        assert!(parse_delay("18446744073709551615s1s").is_err());
+    }
+
+    #[test]
+    fn test_delay_until() {
+        let now = Local.with_ymd_and_hms(2025, 6, 10, 12, 0, 0).unwrap();
+        assert_eq!(
+            delay_until("12:30", now).unwrap(),
+            Duration::from_secs(30 * 60)
+        );
+        // Times earlier today roll over to tomorrow
+        assert_eq!(
+            delay_until("11:00", now).unwrap(),
+            Duration::from_secs(23 * 3600)
+        );
+        assert!(delay_until("2000-01-01T00:00:00Z", now).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! Runbook tool giving agents the team's operational procedures
+//!
+//! Runbooks are the Markdown files in `.termineer/runbooks/` of the workspace,
+//! named by their path without the extension (e.g. `db/failover`).
+//!
+//! Usage:
+//! - `runbook` lists the runbooks with their titles and summaries
+//! - `runbook NAME` returns a runbook
+//! - `runbook search QUERY` finds the runbooks mentioning the words of the
+//!   query, e.g. an alert name, with the lines that match
+
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
+use crate::tools::ToolResult;
+use std::path::{Path, PathBuf};
+
+/// Directory of the runbooks, relative to the workspace
+pub const RUNBOOK_DIR: &str = ".termineer/runbooks";
+
+/// Runbooks returned by a search
+const MAX_RESULTS: usize = 5;
+
+/// Matching lines shown for each runbook found
+const MAX_MATCHED_LINES: usize = 5;
+
+/// Words of queries that would match every runbook
+const STOP_WORDS: &[&str] = &[
+    "and", "the", "for", "with", "from", "when", "how", "what", "why",
+];
+
+/// A runbook of the workspace
+#[derive(Debug, Clone, PartialEq)]
+pub struct Runbook {
+    /// Path in the runbook directory without the extension
+    pub name: String,
+    /// First heading, or the name
+    pub title: String,
+    /// First paragraph after the heading
+    pub summary: String,
+    pub content: String,
+}
+
+impl Runbook {
+    fn parse(name: String, content: String) -> Self {
+        let title = content
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .and_then(|line| line.strip_prefix("# "))
+            .map_or_else(|| name.clone(), |title| title.trim().to_string());
+        let summary = content
+            .lines()
+            .map(str::trim)
+            .skip_while(|line| line.is_empty() || line.starts_with('#'))
+            .take_while(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            name,
+            title,
+            summary,
+            content,
+        }
+    }
+}
+
+/// Runbooks of a directory, sorted by name
+pub fn load(dir: &Path) -> Vec<Runbook> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files);
+    let mut runbooks: Vec<Runbook> = files
+        .into_iter()
+        .filter_map(|path| {
+            let content = std::fs::read_to_string(&path).ok()?;
+            let name = path.strip_prefix(dir).ok()?.with_extension("");
+            let name = name.to_string_lossy().replace('\\', "/");
+            Some(Runbook::parse(name, content))
+        })
+        .collect();
+    runbooks.sort_by(|a, b| a.name.cmp(&b.name));
+    runbooks
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            files.push(path);
+        }
+    }
+}
+
+/// Whether the workspace has runbooks; the tool is only offered then
+pub fn available() -> bool {
+    !load(Path::new(RUNBOOK_DIR)).is_empty()
+}
+
+/// Runbooks mentioning the words of a query, best first, with their matching lines
+pub fn search<'a>(runbooks: &'a [Runbook], query: &str) -> Vec<(&'a Runbook, Vec<String>)> {
+    let words: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
+        .map(str::to_lowercase)
+        .filter(|word| word.len() > 2 && !STOP_WORDS.contains(&word.as_str()))
+        .collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut found: Vec<(usize, &Runbook, Vec<String>)> = runbooks
+        .iter()
+        .filter_map(|runbook| {
+            let title = runbook.title.to_lowercase();
+            let mut score: usize = words.iter().filter(|w| title.contains(*w)).count() * 3;
+            let mut lines = Vec::new();
+            for (number, line) in runbook.content.lines().enumerate() {
+                let lower = line.to_lowercase();
+                let hits = words.iter().filter(|w| lower.contains(*w)).count();
+                if hits > 0 {
+                    score += hits;
+                    if lines.len() < MAX_MATCHED_LINES {
+                        lines.push(format!("{:>5} | {}", number + 1, line.trim_end()));
+                    }
+                }
+            }
+            (score > 0).then_some((score, runbook, lines))
+        })
+        .collect();
+    found.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.name.cmp(&b.1.name)));
+    found
+        .into_iter()
+        .take(MAX_RESULTS)
+        .map(|(_, runbook, lines)| (runbook, lines))
+        .collect()
+}
+
+/// Execute the runbook tool
+pub async fn execute_runbook(args: &str, body: &str, silent_mode: bool) -> ToolResult {
+    let runbooks = load(Path::new(RUNBOOK_DIR));
+    let args = args.trim();
+
+    if args.is_empty() {
+        if !silent_mode {
+            bprintln !(tool: "runbook", "{FORMAT_BOLD}📕 Runbooks{FORMAT_RESET}");
+        }
+        if runbooks.is_empty() {
+            return ToolResult::success(format!("No runbooks in {RUNBOOK_DIR}"));
+        }
+        let list: Vec<String> = runbooks
+            .iter()
+            .map(|runbook| {
+                if runbook.summary.is_empty() {
+                    format!("{} - {}", runbook.name, runbook.title)
+                } else {
+                    format!("{} - {}: {}", runbook.name, runbook.title, runbook.summary)
+                }
+            })
+            .collect();
+        return ToolResult::success(list.join("\n"));
+    }
+
+    if let Some(query) = args
+        .strip_prefix("search")
+        .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+    {
+        let query = if query.trim().is_empty() {
+            body.trim()
+        } else {
+            query.trim()
+        };
+        if !silent_mode {
+            bprintln !(tool: "runbook",
+                "{FORMAT_BOLD}📕 Runbook search:{FORMAT_RESET} {FORMAT_GRAY}{}{FORMAT_RESET}",
+                query
+            );
+        }
+        let found = search(&runbooks, query);
+        if found.is_empty() {
+            return ToolResult::success("No matching runbooks".to_string());
+        }
+        let mut result = String::new();
+        for (runbook, lines) in found {
+            result.push_str(&format!("{} - {}\n", runbook.name, runbook.title));
+            for line in lines {
+                result.push_str(&line);
+                result.push('\n');
+            }
+            result.push('\n');
+        }
+        return ToolResult::success(result.trim_end().to_string());
+    }
+
+    if !silent_mode {
+        bprintln !(tool: "runbook",
+            "{FORMAT_BOLD}📕 Runbook:{FORMAT_RESET} {FORMAT_GRAY}{}{FORMAT_RESET}",
+            args
+        );
+    }
+    match runbooks.iter().find(|runbook| runbook.name == args) {
+        Some(runbook) => ToolResult::success(runbook.content.clone()),
+        None => {
+            let error_msg = format!(
+                "No runbook named '{args}': use `runbook` to list them or `runbook search QUERY`"
+            );
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            ToolResult::error(error_msg)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runbooks() {
+        let dir = std::env::temp_dir().join(format!("termineer-runbooks-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("db")).unwrap();
+        std::fs::write(
+            dir.join("db/failover.md"),
+            "# Database failover\n\nPromote the replica when the primary is down.\n\n## Steps\n1. Check replication lag\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("latency.md"),
+            "Alert HighLatency: check the load balancer\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a runbook").unwrap();
+
+        let runbooks = load(&dir);
+        assert_eq!(runbooks.len(), 2);
+        assert_eq!(runbooks[0].name, "db/failover");
+        assert_eq!(runbooks[0].title, "Database failover");
+        assert_eq!(
+            runbooks[0].summary,
+            "Promote the replica when the primary is down."
+        );
+        assert_eq!(runbooks[1].title, "latency");
+
+        let found = search(&runbooks, "replica lag of the primary");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0.name, "db/failover");
+        assert_eq!(
+            found[0].1,
+            vec![
+                "    3 | Promote the replica when the primary is down.",
+                "    6 | 1. Check replication lag"
+            ]
+        );
+        assert_eq!(search(&runbooks, "highlatency")[0].0.name, "latency");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
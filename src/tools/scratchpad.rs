@@ -0,0 +1,199 @@
+//! Scratchpad tool giving each agent a private markdown file
+//!
+//! Models can keep plans, findings and open questions in the scratchpad instead
+//! of repeating them in the conversation. Each agent has its own file at
+//! `.termineer/scratch/<agent>/scratchpad.md`; when an agent with the same name
+//! starts again, the head of its scratchpad is included in the conversation so
+//! it can pick up where it left off.
+//!
+//! Usage:
+//! - `scratchpad read`
+//! - `scratchpad append` with the text in the body
+//! - `scratchpad replace` with the new content in the body
+
+use crate::agent::AgentId;
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
+use crate::tools::ToolResult;
+use std::path::{Path, PathBuf};
+
+/// Directory holding the scratchpads of all agents
+pub const SCRATCH_DIR: &str = ".termineer/scratch";
+
+/// Lines of the scratchpad included when an agent starts
+const HEAD_LINES: usize = 40;
+
+/// Execute the scratchpad tool
+pub async fn execute_scratchpad(
+    args: &str,
+    body: &str,
+    silent_mode: bool,
+    agent_id: Option<AgentId>,
+) -> ToolResult {
+    let path = scratchpad_path(&agent_name(agent_id));
+    let operation = args.split_whitespace().next().unwrap_or("read");
+
+    let result = match operation {
+        "read" => read(&path).await,
+        "append" => append(&path, body).await,
+        "replace" => replace(&path, body).await,
+        other => Err(format!(
+            "Unknown scratchpad operation '{other}'. Use read, append or replace"
+        )),
+    };
+
+    match result {
+        Ok(output) => {
+            if !silent_mode {
+                let first_line = output.lines().next().unwrap_or("");
+                bprintln !(tool: "scratchpad",
+                    "{FORMAT_BOLD}📝 Scratchpad {}:{FORMAT_RESET} {FORMAT_GRAY}{}{FORMAT_RESET}",
+                    operation,
+                    first_line
+                );
+            }
+            ToolResult::success(output)
+        }
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            ToolResult::error(error_msg)
+        }
+    }
+}
+
+/// Name of the calling agent, used as the scratchpad directory
+fn agent_name(agent_id: Option<AgentId>) -> String {
+    agent_id
+        .and_then(|id| {
+            crate::agent::get_agents()
+                .into_iter()
+                .find(|(agent, _)| *agent == id)
+                .map(|(_, name)| name)
+        })
+        .unwrap_or_else(|| "main".to_string())
+}
+
+/// Path of an agent's scratchpad
+pub fn scratchpad_path(agent_name: &str) -> PathBuf {
+    PathBuf::from(SCRATCH_DIR)
+        .join(directory_name(agent_name))
+        .join("scratchpad.md")
+}
+
+/// Agent name reduced to characters that are safe in a directory name
+fn directory_name(agent_name: &str) -> String {
+    let name: String = agent_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.trim_matches('_').is_empty() {
+        "agent".to_string()
+    } else {
+        name
+    }
+}
+
+async fn read(path: &Path) -> Result<String, String> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) if !content.trim().is_empty() => Ok(content),
+        Ok(_) => Ok("The scratchpad is empty".to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Ok("The scratchpad is empty".to_string())
+        }
+        Err(e) => Err(format!("Failed to read the scratchpad: {e}")),
+    }
+}
+
+async fn append(path: &Path, text: &str) -> Result<String, String> {
+    if text.trim().is_empty() {
+        return Err("Text to append is required in the body".to_string());
+    }
+    let mut content = tokio::fs::read_to_string(path).await.unwrap_or_default();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(text.trim_end());
+    content.push('\n');
+    write(path, &content).await?;
+    Ok(format!(
+        "Appended {} line(s); the scratchpad has {} line(s)",
+        text.trim_end().lines().count(),
+        content.lines().count()
+    ))
+}
+
+async fn replace(path: &Path, text: &str) -> Result<String, String> {
+    let mut content = text.trim_end().to_string();
+    content.push('\n');
+    write(path, &content).await?;
+    Ok(format!(
+        "Replaced the scratchpad with {} line(s)",
+        content.lines().count()
+    ))
+}
+
+async fn write(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    tokio::fs::write(path, content)
+        .await
+        .map_err(|e| format!("Failed to write the scratchpad: {e}"))
+}
+
+/// First lines of an agent's scratchpad, if it has one
+pub fn scratchpad_head(agent_name: &str) -> Option<String> {
+    let content = std::fs::read_to_string(scratchpad_path(agent_name)).ok()?;
+    head(&content, HEAD_LINES)
+}
+
+/// First `max_lines` lines of the content, noting how many were left out
+fn head(content: &str, max_lines: usize) -> Option<String> {
+    if content.trim().is_empty() {
+        return None;
+    }
+    let total = content.lines().count();
+    let mut head = content
+        .lines()
+        .take(max_lines)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if total > max_lines {
+        head.push_str(&format!(
+            "\n... ({} more line(s), use `scratchpad read` to see them)",
+            total - max_lines
+        ));
+    }
+    Some(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_name() {
+        assert_eq!(directory_name("task_fix-build"), "task_fix-build");
+        assert_eq!(directory_name("../etc"), "___etc");
+        assert_eq!(directory_name("//"), "agent");
+    }
+
+    #[test]
+    fn test_head() {
+        assert_eq!(head("  \n", 3), None);
+        assert_eq!(head("a\nb\n", 3), Some("a\nb".to_string()));
+        assert_eq!(
+            head("a\nb\nc\nd\n", 2),
+            Some("a\nb\n... (2 more line(s), use `scratchpad read` to see them)".to_string())
+        );
+    }
+}
@@ -3,10 +3,8 @@
 use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
 use crate::tools::ToolResult;
 use lazy_static::lazy_static;
-use reqwest::Client;
 use scraper::{Html, Selector};
 use serde::Deserialize;
-use std::env;
 use std::time::Instant;
 
 // Google Search API response structures
@@ -58,8 +56,15 @@ async fn execute_duckduckgo_search(query: &str, silent_mode: bool) -> ToolResult
     let encoded_query = urlencoding::encode(query);
     let url = format!("https://html.duckduckgo.com/html/?q={encoded_query}");
 
+    if let Err(error_msg) = crate::tools::network::check_url("search", &url).await {
+        if !silent_mode {
+            bprintln !(error:"{}", error_msg);
+        }
+        return ToolResult::error(error_msg);
+    }
+
     // Send the request
-    let client = Client::new();
+    let client = crate::tools::network::client();
     let response = match client
         .get(&url)
         .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36")
@@ -246,8 +251,8 @@ async fn execute_duckduckgo_search(query: &str, silent_mode: bool) -> ToolResult
 /// Execute the search tool using Google Custom Search API
 /// Falls back to DuckDuckGo search if Google API key is not available
 pub async fn execute_search(args: &str, _body: &str, silent_mode: bool) -> ToolResult {
-    // Get the Google API key from environment
-    let api_key = match env::var("GOOGLE_API_KEY") {
+    // Get the Google API key from the keychain or environment
+    let api_key = match crate::credentials::api_key("google") {
         Ok(key) => key,
         Err(_) => {
             if !silent_mode {
@@ -293,8 +298,15 @@ pub async fn execute_search(args: &str, _body: &str, silent_mode: bool) -> ToolR
         api_key, &*SEARCH_ENGINE_ID, encoded_query
     );
 
+    if let Err(error_msg) = crate::tools::network::check_url("search", &url).await {
+        if !silent_mode {
+            bprintln !(error:"{}", error_msg);
+        }
+        return ToolResult::error(error_msg);
+    }
+
     // Execute the search request
-    let client = Client::new();
+    let client = crate::tools::network::client();
     let response = match client.get(&url).send().await {
         Ok(response) => {
             if !response.status().is_success() {
@@ -0,0 +1,123 @@
+//! Semantic search tool finding code by meaning rather than exact text
+//!
+//! Usage:
+//! - `semsearch QUERY` returns the 5 chunks of the workspace closest to the query
+//! - `semsearch top=N QUERY` returns N chunks
+//!
+//! The query may also be given in the body. The index in `.termineer/index/` is
+//! updated before searching, so the first search in a workspace embeds every file.
+
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
+use crate::tools::ToolResult;
+
+/// Chunks returned when no `top` is given
+const DEFAULT_TOP: usize = 5;
+
+/// Lines of each chunk included in the result
+const MAX_HIT_LINES: usize = 15;
+
+pub async fn execute_semsearch(args: &str, body: &str, silent_mode: bool) -> ToolResult {
+    let (top, query) = match parse_args(args, body) {
+        Ok(parsed) => parsed,
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+    };
+
+    if !silent_mode {
+        bprintln !(tool: "semsearch",
+            "{FORMAT_BOLD}🧭 Semantic search:{FORMAT_RESET} {FORMAT_GRAY}{}{FORMAT_RESET}",
+            query
+        );
+    }
+
+    let hits = match crate::index::search(&query, top).await {
+        Ok(hits) => hits,
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+    };
+
+    if hits.is_empty() {
+        return ToolResult::success("The index is empty: no text files to search".to_string());
+    }
+
+    let mut result = String::new();
+    for hit in &hits {
+        result.push_str(&format!(
+            "{}:{}-{} (score {:.2})\n",
+            hit.path, hit.start_line, hit.end_line, hit.score
+        ));
+        let lines: Vec<&str> = hit.text.lines().collect();
+        for (offset, line) in lines.iter().take(MAX_HIT_LINES).enumerate() {
+            result.push_str(&format!("{:>5} | {}\n", hit.start_line + offset, line));
+        }
+        if lines.len() > MAX_HIT_LINES {
+            result.push_str(&format!(
+                "      ... {} more line(s)\n",
+                lines.len() - MAX_HIT_LINES
+            ));
+        }
+        result.push('\n');
+    }
+
+    if !silent_mode {
+        bprintln !(tool: "semsearch",
+            "{FORMAT_GRAY}{} result(s), best: {}:{}{FORMAT_RESET}",
+            hits.len(),
+            hits[0].path,
+            hits[0].start_line
+        );
+    }
+    ToolResult::success(result.trim_end().to_string())
+}
+
+/// Split `[top=N] query` (or a query in the body) into the count and the query
+fn parse_args(args: &str, body: &str) -> Result<(usize, String), String> {
+    let mut top = DEFAULT_TOP;
+    let mut query = args.trim();
+    if let Some(rest) = query.strip_prefix("top=") {
+        let (count, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        top = count
+            .parse()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| format!("Invalid result count: top={count}"))?;
+        query = rest.trim();
+    }
+
+    let query = if query.is_empty() { body.trim() } else { query };
+    if query.is_empty() {
+        return Err("Missing search query: use `semsearch [top=N] QUERY`".to_string());
+    }
+    Ok((top, query.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args("where are retries handled", ""),
+            Ok((DEFAULT_TOP, "where are retries handled".to_string()))
+        );
+        assert_eq!(
+            parse_args("top=10 config loading", ""),
+            Ok((10, "config loading".to_string()))
+        );
+        assert_eq!(
+            parse_args("top=3", "token refresh\n"),
+            Ok((3, "token refresh".to_string()))
+        );
+        assert!(parse_args("top=0 x", "").is_err());
+        assert!(parse_args("", "  ").is_err());
+    }
+}
@@ -1,11 +1,313 @@
 use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
 use crate::tools::ToolResult;
-use std::sync::{Arc, Mutex};
+use lazy_static::lazy_static;
+use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::sync::mpsc;
 use tokio::time::{sleep, Duration};
 
+/// Terminal settings for PTY-based shell execution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PtySettings {
+    /// Whether commands run inside a pseudo-terminal (falls back to pipes on failure)
+    pub enabled: bool,
+    /// Terminal width in columns
+    pub cols: u16,
+    /// Terminal height in rows
+    pub rows: u16,
+}
+
+impl Default for PtySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cols: 120,
+            rows: 40,
+        }
+    }
+}
+
+lazy_static! {
+    static ref PTY_SETTINGS: RwLock<PtySettings> = RwLock::new(PtySettings::default());
+}
+
+/// Configure PTY usage for all subsequent shell commands
+pub fn configure_pty(settings: PtySettings) {
+    *PTY_SETTINGS.write().unwrap() = settings;
+}
+
+/// Current PTY settings
+pub fn pty_settings() -> PtySettings {
+    *PTY_SETTINGS.read().unwrap()
+}
+
+/// Parse a terminal size in the form `COLSxROWS` (e.g. `120x40`)
+pub fn parse_pty_size(value: &str) -> Result<(u16, u16), String> {
+    let (cols, rows) = value
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("Invalid terminal size '{value}', expected COLSxROWS"))?;
+    let cols = cols
+        .trim()
+        .parse::<u16>()
+        .map_err(|_| format!("Invalid column count in '{value}'"))?;
+    let rows = rows
+        .trim()
+        .parse::<u16>()
+        .map_err(|_| format!("Invalid row count in '{value}'"))?;
+    if cols == 0 || rows == 0 {
+        return Err(format!("Terminal size must be non-zero: '{value}'"));
+    }
+    Ok((cols, rows))
+}
+
+/// Collapse carriage-return redraws (progress bars) to the final visible text
+///
+/// Programs running in a terminal redraw a line by emitting `\r` followed by the
+/// new content. Only the last non-empty segment is meaningful once the line is
+/// stored, so earlier frames are dropped.
+pub fn collapse_carriage_returns(line: &str) -> String {
+    let line = line.trim_end_matches('\r');
+    if !line.contains('\r') {
+        return line.to_string();
+    }
+    line.rsplit('\r')
+        .find(|segment| !segment.is_empty())
+        .unwrap_or("")
+        .to_string()
+}
+
+/// Per-invocation working directory and environment for a shell command
+///
+/// Given as leading arguments of the shell tool:
+/// `shell --cwd src --env RUST_LOG=debug cargo test`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShellOptions {
+    /// Working directory (validated to be inside the project)
+    pub cwd: Option<PathBuf>,
+    /// Additional environment variables
+    pub env: Vec<(String, String)>,
+}
+
+impl ShellOptions {
+    /// Parse leading `--cwd PATH` / `--env KEY=VALUE` options from the shell arguments
+    ///
+    /// Returns the options and the remaining command text.
+    pub fn parse(args: &str) -> Result<(Self, String), String> {
+        let mut options = Self::default();
+        let mut rest = args.trim_start();
+
+        loop {
+            let (token, after) = split_first_token(rest);
+            let (flag, inline_value) = match token.split_once('=') {
+                Some((flag, value)) if flag == "--cwd" || flag == "--env" => (flag, Some(value)),
+                _ => (token, None),
+            };
+
+            if flag != "--cwd" && flag != "--env" {
+                break;
+            }
+
+            let (value, after) = match inline_value {
+                Some(value) => (value, after),
+                None => split_first_token(after),
+            };
+            if value.is_empty() {
+                return Err(format!("Missing value for shell option {flag}"));
+            }
+
+            if flag == "--cwd" {
+                let dir = crate::tools::path_utils::validate_directory(value)
+                    .map_err(|e| format!("Invalid --cwd '{value}': {e}"))?;
+                if !dir.is_dir() {
+                    return Err(format!("Invalid --cwd '{value}': not a directory"));
+                }
+                options.cwd = Some(dir);
+            } else {
+                let (key, env_value) = value
+                    .split_once('=')
+                    .ok_or_else(|| format!("Invalid --env '{value}', expected KEY=VALUE"))?;
+                if !is_valid_env_key(key) {
+                    return Err(format!("Invalid environment variable name '{key}'"));
+                }
+                options.env.push((key.to_string(), env_value.to_string()));
+            }
+
+            rest = after;
+        }
+
+        Ok((options, rest.to_string()))
+    }
+
+    /// Working directory the command runs in
+    pub fn effective_cwd(&self) -> std::io::Result<PathBuf> {
+        match &self.cwd {
+            Some(dir) => Ok(dir.clone()),
+            None => std::env::current_dir(),
+        }
+    }
+
+    /// Header recorded in the tool result so the model knows where the command ran
+    pub fn header(&self) -> String {
+        let cwd = self
+            .effective_cwd()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_else(|_| "unknown".to_string());
+        let mut header = format!("[cwd: {cwd}]");
+        if !self.env.is_empty() {
+            let env: Vec<String> = self.env.iter().map(|(k, v)| format!("{k}={v}")).collect();
+            header.push_str(&format!(" [env: {}]", env.join(" ")));
+        }
+        header
+    }
+}
+
+/// Split off the first whitespace-delimited token
+fn split_first_token(text: &str) -> (&str, &str) {
+    let text = text.trim_start();
+    match text.find(char::is_whitespace) {
+        Some(pos) => (&text[..pos], text[pos..].trim_start()),
+        None => (text, ""),
+    }
+}
+
+fn is_valid_env_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Exit information of a finished shell process
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ExitInfo {
+    success: bool,
+    /// Exit code (None when terminated by a signal)
+    code: Option<i32>,
+}
+
+/// A running shell process, either attached to a PTY or to pipes
+enum ShellChild {
+    Piped(tokio::process::Child),
+    Pty {
+        child: Box<dyn portable_pty::Child + Send + Sync>,
+        /// Kept alive for the lifetime of the process; dropping it hangs up the terminal
+        _master: Box<dyn MasterPty + Send>,
+    },
+}
+
+impl ShellChild {
+    /// Check whether the process has exited, returning its exit information
+    fn try_wait(&mut self) -> std::io::Result<Option<ExitInfo>> {
+        match self {
+            ShellChild::Piped(child) => Ok(child.try_wait()?.map(|status| ExitInfo {
+                success: status.success(),
+                code: status.code(),
+            })),
+            ShellChild::Pty { child, .. } => Ok(child.try_wait()?.map(|status| ExitInfo {
+                success: status.success(),
+                code: Some(status.exit_code() as i32),
+            })),
+        }
+    }
+
+    /// Kill the process
+    async fn kill(&mut self) -> std::io::Result<()> {
+        match self {
+            ShellChild::Piped(child) => child.kill().await,
+            ShellChild::Pty { child, .. } => child.kill(),
+        }
+    }
+}
+
+/// Environment that keeps programs from opening an interactive pager, such as
+/// `git log` or `man` do when their output is a terminal, which would wait for
+/// keys until the command times out. `LESS` covers programs that run `less`
+/// directly: it quits at the end of short output and keeps colors.
+const PTY_PAGER_ENV: &[(&str, &str)] = &[
+    ("PAGER", "cat"),
+    ("GIT_PAGER", "cat"),
+    ("MANPAGER", "cat"),
+    ("LESS", "-FRX"),
+];
+
+/// Spawn a command inside a pseudo-terminal
+///
+/// Output is read on a dedicated thread (the PTY reader is blocking) and delivered
+/// line by line through the returned channel.
+fn spawn_pty(
+    shell: &str,
+    shell_arg: &str,
+    command_str: &str,
+    options: &ShellOptions,
+    settings: PtySettings,
+    output_bytes: &Arc<OutputBytes>,
+) -> Result<(ShellChild, mpsc::Receiver<String>), Box<dyn std::error::Error>> {
+    let pair = native_pty_system().openpty(PtySize {
+        rows: settings.rows,
+        cols: settings.cols,
+        pixel_width: 0,
+        pixel_height: 0,
+    })?;
+
+    let mut cmd = CommandBuilder::new(shell);
+    cmd.arg(shell_arg);
+    cmd.arg(command_str);
+    cmd.cwd(options.effective_cwd()?);
+    cmd.env("TERM", "xterm-256color");
+    cmd.env("COLUMNS", settings.cols.to_string());
+    cmd.env("LINES", settings.rows.to_string());
+    for (key, value) in PTY_PAGER_ENV {
+        cmd.env(key, value);
+    }
+    for (key, value) in &options.env {
+        cmd.env(key, value);
+    }
+
+    let child = pair.slave.spawn_command(cmd)?;
+    // Close our copy of the slave so the reader sees EOF when the process exits
+    drop(pair.slave);
+
+    let mut reader = pair.master.try_clone_reader()?;
+    let (line_sender, line_receiver) = mpsc::channel(100);
+    let output_bytes = Arc::clone(output_bytes);
+
+    std::thread::spawn(move || {
+        let mut pending: Vec<u8> = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    output_bytes.stdout.fetch_add(n, Ordering::Relaxed);
+                    pending.extend_from_slice(&chunk[..n]);
+                    while let Some(pos) = pending.iter().position(|b| *b == b'\n') {
+                        let line: Vec<u8> = pending.drain(..=pos).collect();
+                        let text = String::from_utf8_lossy(&line[..line.len() - 1]).to_string();
+                        if line_sender.blocking_send(text).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+        if !pending.is_empty() {
+            let _ = line_sender.blocking_send(String::from_utf8_lossy(&pending).to_string());
+        }
+    });
+
+    Ok((
+        ShellChild::Pty {
+            child,
+            _master: pair.master,
+        },
+        line_receiver,
+    ))
+}
+
 /// Data structure for managing interruption with reason
 pub struct InterruptData {
     /// Flag indicating whether the process should be interrupted
@@ -46,15 +348,36 @@ pub enum ShellOutput {
     Stdout(String),
     /// Line from standard error
     Stderr(String),
-    /// Completion signal with final result
-    Complete(ToolResult),
+    /// Completion signal with final result and how the process ended
+    Complete(ToolResult, ShellExit),
+}
+
+/// How a shell command ended
+#[derive(Debug, Clone, Copy)]
+pub struct ShellExit {
+    /// Exit code (None if killed or unknown)
+    pub code: Option<i32>,
+    /// Whether the command ran in a PTY, whose output combines stdout and
+    /// stderr into the `Stdout` lines
+    pub terminal: bool,
+    /// Raw bytes the command wrote to stdout (all of its output under a PTY)
+    pub stdout_bytes: usize,
+    /// Raw bytes the command wrote to stderr
+    pub stderr_bytes: usize,
+}
+
+/// Raw bytes read from a command's output streams, before any cleanup
+#[derive(Default)]
+struct OutputBytes {
+    stdout: AtomicUsize,
+    stderr: AtomicUsize,
 }
 
 /// Execute shell command with streaming output and interruption capability
 /// Returns a receiver to consume streaming output
 ///
 /// # Arguments
-/// * `command_to_run` - Command to execute
+/// * `command_to_run` - Command to execute, optionally preceded by `--cwd`/`--env` options
 /// * `body` - Optional script body (overrides command if not empty)
 /// * `interrupt_data` - Shared data for interruption coordination
 /// * `silent_mode` - Whether to suppress console output
@@ -67,6 +390,10 @@ pub async fn execute_shell(
     interrupt_data: Arc<Mutex<InterruptData>>,
     silent_mode: bool,
 ) -> Result<mpsc::Receiver<ShellOutput>, Box<dyn std::error::Error>> {
+    // Split off per-invocation working directory and environment options
+    let (options, command_to_run) = ShellOptions::parse(command_to_run)?;
+    let command_to_run = command_to_run.as_str();
+
     // Combine args and body for multiline scripts if both are provided
     let command_str = if !body.is_empty() {
         if !command_to_run.is_empty() {
@@ -109,93 +436,75 @@ pub async fn execute_shell(
     // Clone the interrupt data for thread use
     let thread_interrupt_data = Arc::clone(&interrupt_data);
 
-    // Start the actual command
-    let mut child = Command::new(shell)
-        .arg(shell_arg)
-        .arg(&command_str)
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()?;
-
-    // Take the stdout and stderr handles
-    let stdout = child.stdout.take().expect("Failed to capture stdout");
-    let stderr = child.stderr.take().expect("Failed to capture stderr");
-
     // Command status (using tokio::sync::Mutex now)
     let command_running = Arc::new(tokio::sync::Mutex::new(true));
-
-    // Interrupt data clone for checking
-    let interrupt_data_clone = Arc::clone(&thread_interrupt_data);
-
-    // Stdout reader task
-    let stdout_sender = sender.clone();
-    let stdout_running_clone = Arc::clone(&command_running);
-    let stdout_silent = silent_mode;
-
-    crate::output::spawn(async move {
-        let mut reader = BufReader::new(stdout).lines();
-        let mut _line_count = 0;
-
-        while let Ok(Some(line)) = reader.next_line().await {
-            // Display line if not in silent mode
-            if !stdout_silent {
-                // Use output buffer for shell output
-                bprintln !(tool: "shell", "{}{}{}", FORMAT_GRAY, line, FORMAT_RESET);
-                _line_count += 1;
-            }
-
-            // Send the line through the channel
-            if stdout_sender
-                .send(ShellOutput::Stdout(line.clone()))
-                .await
-                .is_err()
-            {
-                break;
-            }
-
-            // Check if we should exit
-            if !*stdout_running_clone.lock().await {
-                break;
+    let output_bytes = Arc::new(OutputBytes::default());
+
+    // Prefer a PTY so programs produce colored, line-buffered output; fall back to pipes
+    let settings = pty_settings();
+    let pty = if settings.enabled {
+        match spawn_pty(
+            shell,
+            shell_arg,
+            &command_str,
+            &options,
+            settings,
+            &output_bytes,
+        ) {
+            Ok(spawned) => Some(spawned),
+            Err(e) => {
+                bprintln!(debug: "PTY unavailable, falling back to pipes: {}", e);
+                None
             }
         }
+    } else {
+        None
+    };
 
-        // No need to manually flush output when using buffer system
-    });
+    let terminal = pty.is_some();
+    let mut child = if let Some((child, mut lines)) = pty {
+        // PTY output combines stdout and stderr into a single stream
+        let pty_sender = sender.clone();
+        let pty_running_clone = Arc::clone(&command_running);
+        let pty_silent = silent_mode;
 
-    // Stderr reader task
-    let stderr_sender = sender.clone();
-    let stderr_running_clone = Arc::clone(&command_running);
-    let stderr_silent = silent_mode;
+        crate::output::spawn(async move {
+            while let Some(raw_line) = lines.recv().await {
+                let line = collapse_carriage_returns(&raw_line);
 
-    crate::output::spawn(async move {
-        let mut reader = BufReader::new(stderr).lines();
-        let mut _line_count = 0;
+                // Display line if not in silent mode
+                if !pty_silent {
+                    bprintln !(tool: "shell", "{}{}{}", FORMAT_GRAY, line, FORMAT_RESET);
+                }
 
-        while let Ok(Some(line)) = reader.next_line().await {
-            // Display line if not in silent mode
-            if !stderr_silent {
-                // Use output buffer for shell stderr output
-                bprintln !(tool: "shell", "{}{}{}", FORMAT_GRAY, line, FORMAT_RESET);
-                _line_count += 1;
-            }
+                // Send the line through the channel
+                if pty_sender.send(ShellOutput::Stdout(line)).await.is_err() {
+                    break;
+                }
 
-            // Send the line through the channel
-            if stderr_sender
-                .send(ShellOutput::Stderr(line.clone()))
-                .await
-                .is_err()
-            {
-                break;
+                // Check if we should exit
+                if !*pty_running_clone.lock().await {
+                    break;
+                }
             }
+        });
 
-            // Check if we should exit
-            if !*stderr_running_clone.lock().await {
-                break;
-            }
-        }
+        child
+    } else {
+        spawn_piped(
+            shell,
+            shell_arg,
+            &command_str,
+            &options,
+            &sender,
+            &command_running,
+            &output_bytes,
+            silent_mode,
+        )?
+    };
 
-        // No need to manually flush output when using buffer system
-    });
+    // Interrupt data clone for checking
+    let interrupt_data_clone = Arc::clone(&thread_interrupt_data);
 
     // No longer handling keyboard interruptions directly in the shell tool
     // Interrupts now come from the UI layer through the InterruptData
@@ -218,9 +527,9 @@ pub async fn execute_shell(
         loop {
             // Check if process has completed on its own
             match child.try_wait() {
-                Ok(Some(status)) => {
+                Ok(Some(exit_info)) => {
                     // Store the exit status for later use
-                    exit_status = Some(status);
+                    exit_status = Some(exit_info);
                     *command_running.lock().await = false;
                     break;
                 }
@@ -296,10 +605,18 @@ pub async fn execute_shell(
 
                     // Send error completion
                     let _ = main_sender
-                        .send(ShellOutput::Complete(ToolResult::default(
-                            false,
-                            format!("Error monitoring process status: {e}"),
-                        )))
+                        .send(ShellOutput::Complete(
+                            ToolResult::default(
+                                false,
+                                format!("Error monitoring process status: {e}"),
+                            ),
+                            ShellExit {
+                                code: None,
+                                terminal,
+                                stdout_bytes: output_bytes.stdout.load(Ordering::Relaxed),
+                                stderr_bytes: output_bytes.stderr.load(Ordering::Relaxed),
+                            },
+                        ))
                         .await;
                     return;
                 }
@@ -317,7 +634,7 @@ pub async fn execute_shell(
             true // Interruption is successful
         } else {
             // Use the stored exit status
-            exit_status.is_some_and(|status| status.success())
+            exit_status.is_some_and(|exit| exit.success)
         };
 
         // Combined output
@@ -328,7 +645,10 @@ pub async fn execute_shell(
         } else if success {
             format!("Command '{main_command_str}' finished with success")
         } else {
-            format!("Command '{main_command_str}' finished with error")
+            let code = exit_status
+                .and_then(|exit| exit.code)
+                .map_or("unknown".to_string(), |code| code.to_string());
+            format!("Command '{main_command_str}' finished with error (exit code {code})")
         };
 
         // Log execution time
@@ -365,13 +685,179 @@ pub async fn execute_shell(
 
         // Send final completion message with result
         let _ = main_sender
-            .send(ShellOutput::Complete(ToolResult::default(
-                success,
-                agent_output,
-            )))
+            .send(ShellOutput::Complete(
+                ToolResult::default(success, agent_output),
+                ShellExit {
+                    code: exit_status.and_then(|exit| exit.code),
+                    terminal,
+                    stdout_bytes: output_bytes.stdout.load(Ordering::Relaxed),
+                    stderr_bytes: output_bytes.stderr.load(Ordering::Relaxed),
+                },
+            ))
             .await;
     });
 
     // Return the receiver for streaming output
     Ok(receiver)
 }
+
+/// Spawn a command with piped stdout/stderr and stream both line by line
+#[allow(clippy::too_many_arguments)]
+fn spawn_piped(
+    shell: &str,
+    shell_arg: &str,
+    command_str: &str,
+    options: &ShellOptions,
+    sender: &mpsc::Sender<ShellOutput>,
+    command_running: &Arc<tokio::sync::Mutex<bool>>,
+    output_bytes: &Arc<OutputBytes>,
+    silent_mode: bool,
+) -> Result<ShellChild, Box<dyn std::error::Error>> {
+    let mut child = Command::new(shell)
+        .arg(shell_arg)
+        .arg(command_str)
+        .current_dir(options.effective_cwd()?)
+        .envs(options.env.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+
+    // Take the stdout and stderr handles
+    let stdout = child.stdout.take().expect("Failed to capture stdout");
+    let stderr = child.stderr.take().expect("Failed to capture stderr");
+
+    // Stdout reader task
+    let stdout_sender = sender.clone();
+    let stdout_running_clone = Arc::clone(command_running);
+    let stdout_silent = silent_mode;
+    let stdout_bytes = Arc::clone(output_bytes);
+
+    crate::output::spawn(async move {
+        let mut reader = BufReader::new(stdout);
+        let mut raw_line = Vec::new();
+
+        while let Ok(n) = reader.read_until(b'\n', &mut raw_line).await {
+            if n == 0 {
+                break;
+            }
+            stdout_bytes.stdout.fetch_add(n, Ordering::Relaxed);
+            let line = collapse_carriage_returns(&read_line_text(&raw_line));
+            raw_line.clear();
+
+            // Display line if not in silent mode
+            if !stdout_silent {
+                // Use output buffer for shell output
+                bprintln !(tool: "shell", "{}{}{}", FORMAT_GRAY, line, FORMAT_RESET);
+            }
+
+            // Send the line through the channel
+            if stdout_sender.send(ShellOutput::Stdout(line)).await.is_err() {
+                break;
+            }
+
+            // Check if we should exit
+            if !*stdout_running_clone.lock().await {
+                break;
+            }
+        }
+    });
+
+    // Stderr reader task
+    let stderr_sender = sender.clone();
+    let stderr_running_clone = Arc::clone(command_running);
+    let stderr_silent = silent_mode;
+    let stderr_bytes = Arc::clone(output_bytes);
+
+    crate::output::spawn(async move {
+        let mut reader = BufReader::new(stderr);
+        let mut raw_line = Vec::new();
+
+        while let Ok(n) = reader.read_until(b'\n', &mut raw_line).await {
+            if n == 0 {
+                break;
+            }
+            stderr_bytes.stderr.fetch_add(n, Ordering::Relaxed);
+            let line = collapse_carriage_returns(&read_line_text(&raw_line));
+            raw_line.clear();
+
+            // Display line if not in silent mode
+            if !stderr_silent {
+                // Use output buffer for shell stderr output
+                bprintln !(tool: "shell", "{}{}{}", FORMAT_GRAY, line, FORMAT_RESET);
+            }
+
+            // Send the line through the channel
+            if stderr_sender.send(ShellOutput::Stderr(line)).await.is_err() {
+                break;
+            }
+
+            // Check if we should exit
+            if !*stderr_running_clone.lock().await {
+                break;
+            }
+        }
+    });
+
+    Ok(ShellChild::Piped(child))
+}
+
+/// Text of a line read with `read_until`, without its line ending
+fn read_line_text(raw_line: &[u8]) -> String {
+    let line = raw_line.strip_suffix(b"\n").unwrap_or(raw_line);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    String::from_utf8_lossy(line).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collapse_carriage_returns() {
+        assert_eq!(collapse_carriage_returns("plain line\r"), "plain line");
+        assert_eq!(
+            collapse_carriage_returns("10%\r50%\r100% done\r"),
+            "100% done"
+        );
+        assert_eq!(collapse_carriage_returns("done\r"), "done");
+    }
+
+    #[test]
+    fn test_read_line_text() {
+        assert_eq!(read_line_text(b"done\r\n"), "done");
+        assert_eq!(read_line_text(b"50%\r100%\n"), "50%\r100%");
+        assert_eq!(read_line_text(b"last"), "last");
+        assert_eq!(read_line_text(b"caf\xc3\xa9\n"), "caf\u{e9}");
+    }
+
+    #[test]
+    fn test_parse_shell_options() {
+        let (options, command) =
+            ShellOptions::parse("--env RUST_LOG=debug --env=A=1 cargo test --env X").unwrap();
+        assert_eq!(command, "cargo test --env X");
+        assert_eq!(
+            options.env,
+            vec![
+                ("RUST_LOG".to_string(), "debug".to_string()),
+                ("A".to_string(), "1".to_string())
+            ]
+        );
+        assert!(options.cwd.is_none());
+        assert!(options.header().contains("[env: RUST_LOG=debug A=1]"));
+
+        let (options, command) = ShellOptions::parse("ls -la").unwrap();
+        assert_eq!(options, ShellOptions::default());
+        assert_eq!(command, "ls -la");
+
+        assert!(ShellOptions::parse("--env 1BAD=x ls").is_err());
+        assert!(ShellOptions::parse("--cwd /etc ls").is_err());
+    }
+
+    #[test]
+    fn test_parse_pty_size() {
+        assert_eq!(parse_pty_size("120x40"), Ok((120, 40)));
+        assert_eq!(parse_pty_size("80X24"), Ok((80, 24)));
+        assert!(parse_pty_size("80").is_err());
+        assert!(parse_pty_size("0x24").is_err());
+    }
+}
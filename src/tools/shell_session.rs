@@ -0,0 +1,470 @@
+//! Persistent interactive shell sessions
+//!
+//! The `shell-session` tool keeps a program (bash, python, node, a database
+//! shell, ...) running in a PTY across turns. The agent can send input, read
+//! output incrementally and close the session when done, which keeps state such
+//! as an activated virtualenv or an open database connection between commands.
+
+use crate::ansi_converter::strip_ansi_sequences;
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET, MAX_TOOL_OUTPUT_LENGTH};
+use crate::tools::shell::{collapse_carriage_returns, pty_settings};
+use crate::tools::ToolResult;
+use lazy_static::lazy_static;
+use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Session name used when none is given
+const DEFAULT_SESSION: &str = "default";
+
+/// Program started when none is given
+const DEFAULT_PROGRAM: &str = "bash";
+
+/// Default time to wait for output after sending input
+const DEFAULT_WAIT_SECONDS: u64 = 5;
+
+/// Output is considered complete after this much silence
+const QUIET_PERIOD: Duration = Duration::from_millis(400);
+
+/// Most unread output kept per session; older output is dropped so a chatty
+/// program nobody reads from cannot grow the buffer without bound
+const MAX_UNREAD_BYTES: usize = 4 * MAX_TOOL_OUTPUT_LENGTH;
+
+/// Marker in front of unread output whose beginning was dropped
+const DROPPED_MARKER: &str = "[... earlier output dropped ...]\n";
+
+/// A running interactive program attached to a PTY
+struct ShellSession {
+    program: String,
+    child: Box<dyn portable_pty::Child + Send + Sync>,
+    writer: Box<dyn Write + Send>,
+    /// Kept alive for the lifetime of the session; dropping it hangs up the terminal
+    _master: Box<dyn MasterPty + Send>,
+    /// Output received but not yet returned to the agent
+    unread: Arc<Mutex<String>>,
+    /// Time the last output chunk arrived
+    last_output: Arc<Mutex<Instant>>,
+}
+
+lazy_static! {
+    static ref SESSIONS: Mutex<HashMap<String, ShellSession>> = Mutex::new(HashMap::new());
+}
+
+/// Parsed tool arguments
+struct SessionArguments {
+    action: String,
+    name: String,
+    program: Option<String>,
+    wait: Duration,
+}
+
+fn parse_arguments(args: &str) -> SessionArguments {
+    let mut action = String::new();
+    let mut name = DEFAULT_SESSION.to_string();
+    let mut program = None;
+    let mut wait = Duration::from_secs(DEFAULT_WAIT_SECONDS);
+
+    for arg in args.split_whitespace() {
+        if let Some(value) = arg.strip_prefix("name=") {
+            name = value.to_string();
+        } else if let Some(value) = arg.strip_prefix("program=") {
+            program = Some(value.to_string());
+        } else if let Some(value) = arg.strip_prefix("wait=") {
+            if let Some(seconds) = value.parse::<f64>().ok().filter(|s| s.is_finite()) {
+                wait = Duration::from_secs_f64(seconds.clamp(0.0, 120.0));
+            }
+        } else if action.is_empty() {
+            action = arg.to_lowercase();
+        }
+    }
+
+    SessionArguments {
+        action,
+        name,
+        program,
+        wait,
+    }
+}
+
+/// Execute the shell-session tool
+pub async fn execute_shell_session(args: &str, body: &str, silent_mode: bool) -> ToolResult {
+    let arguments = parse_arguments(args);
+
+    let result = match arguments.action.as_str() {
+        "open" => open_session(&arguments).await,
+        "send" => send_input(&arguments, body).await,
+        "read" => read_session(&arguments).await,
+        "close" => close_session(&arguments.name),
+        "list" => Ok(list_sessions()),
+        "" => Err("An action is required: open, send, read, close or list".to_string()),
+        other => Err(format!(
+            "Unknown shell-session action '{other}'. Use open, send, read, close or list"
+        )),
+    };
+
+    match result {
+        Ok(output) => {
+            if !silent_mode {
+                bprintln!(tool: "shell-session",
+                    "{FORMAT_BOLD}🖥️ Session '{}' {}:{FORMAT_RESET}\n{FORMAT_GRAY}{}{FORMAT_RESET}",
+                    arguments.name,
+                    arguments.action,
+                    output.trim_end()
+                );
+            }
+            ToolResult::success(output)
+        }
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            ToolResult::error(error_msg)
+        }
+    }
+}
+
+/// Start a new session running the requested program
+async fn open_session(arguments: &SessionArguments) -> Result<String, String> {
+    if SESSIONS.lock().unwrap().contains_key(&arguments.name) {
+        return Err(format!(
+            "Session '{}' is already open. Close it first or choose another name=",
+            arguments.name
+        ));
+    }
+
+    let program = arguments
+        .program
+        .clone()
+        .unwrap_or_else(|| DEFAULT_PROGRAM.to_string());
+    let session = spawn_session(&program).map_err(|e| {
+        format!(
+            "Failed to start '{program}' for session '{}': {e}",
+            arguments.name
+        )
+    })?;
+
+    let unread = Arc::clone(&session.unread);
+    let last_output = Arc::clone(&session.last_output);
+    SESSIONS
+        .lock()
+        .unwrap()
+        .insert(arguments.name.clone(), session);
+
+    let output = collect_output(
+        &unread,
+        &last_output,
+        arguments.wait.min(Duration::from_secs(2)),
+    )
+    .await;
+    Ok(format!(
+        "Session '{}' started ({program}).\n{}",
+        arguments.name,
+        format_output(&output)
+    ))
+}
+
+/// Send input to a session and return the output it produces
+async fn send_input(arguments: &SessionArguments, body: &str) -> Result<String, String> {
+    let (unread, last_output) = {
+        let mut sessions = SESSIONS.lock().unwrap();
+        let session = sessions
+            .get_mut(&arguments.name)
+            .ok_or_else(|| missing_session(&arguments.name))?;
+
+        if let Ok(Some(status)) = session.child.try_wait() {
+            return Err(format!(
+                "Session '{}' has exited ({:?}). Close it and open a new one",
+                arguments.name, status
+            ));
+        }
+
+        let mut input = body.trim_end_matches('\n').to_string();
+        input.push('\n');
+        session
+            .writer
+            .write_all(input.as_bytes())
+            .and_then(|_| session.writer.flush())
+            .map_err(|e| format!("Failed to send input to session '{}': {e}", arguments.name))?;
+
+        (
+            Arc::clone(&session.unread),
+            Arc::clone(&session.last_output),
+        )
+    };
+
+    let output = collect_output(&unread, &last_output, arguments.wait).await;
+    Ok(format_output(&output))
+}
+
+/// Return any output received since the last call
+async fn read_session(arguments: &SessionArguments) -> Result<String, String> {
+    let (unread, last_output, exited) = {
+        let mut sessions = SESSIONS.lock().unwrap();
+        let session = sessions
+            .get_mut(&arguments.name)
+            .ok_or_else(|| missing_session(&arguments.name))?;
+        let exited = matches!(session.child.try_wait(), Ok(Some(_)));
+        (
+            Arc::clone(&session.unread),
+            Arc::clone(&session.last_output),
+            exited,
+        )
+    };
+
+    let output = collect_output(&unread, &last_output, arguments.wait).await;
+    let mut result = format_output(&output);
+    if exited {
+        result.push_str("\n[SESSION HAS EXITED]");
+    }
+    Ok(result)
+}
+
+/// Terminate a session and return its remaining output
+fn close_session(name: &str) -> Result<String, String> {
+    let mut session = SESSIONS
+        .lock()
+        .unwrap()
+        .remove(name)
+        .ok_or_else(|| missing_session(name))?;
+
+    let _ = session.child.kill();
+    let remaining = std::mem::take(&mut *session.unread.lock().unwrap());
+    Ok(format!(
+        "Session '{name}' ({}) closed.\n{}",
+        session.program,
+        format_output(&remaining)
+    ))
+}
+
+/// List open sessions
+fn list_sessions() -> String {
+    let mut sessions = SESSIONS.lock().unwrap();
+    if sessions.is_empty() {
+        return "No open sessions".to_string();
+    }
+
+    let mut names: Vec<String> = sessions.keys().cloned().collect();
+    names.sort();
+    let mut listing = String::from("Open sessions:");
+    for name in names {
+        let session = sessions.get_mut(&name).unwrap();
+        let status = match session.child.try_wait() {
+            Ok(Some(_)) => "exited",
+            _ => "running",
+        };
+        listing.push_str(&format!("\n- {name}: {} ({status})", session.program));
+    }
+    listing
+}
+
+/// Close all sessions (called on shutdown)
+pub fn close_all_sessions() {
+    let mut sessions = SESSIONS.lock().unwrap();
+    for (_, mut session) in sessions.drain() {
+        let _ = session.child.kill();
+    }
+}
+
+fn missing_session(name: &str) -> String {
+    format!("No open session named '{name}'. Use 'shell-session open name={name}' first")
+}
+
+/// Start a program in a new PTY with a background output reader
+fn spawn_session(program: &str) -> Result<ShellSession, Box<dyn std::error::Error>> {
+    let settings = pty_settings();
+    let pair = native_pty_system().openpty(PtySize {
+        rows: settings.rows,
+        cols: settings.cols,
+        pixel_width: 0,
+        pixel_height: 0,
+    })?;
+
+    let mut parts = program.split_whitespace();
+    let executable = parts.next().unwrap_or(DEFAULT_PROGRAM);
+    let mut cmd = CommandBuilder::new(executable);
+    cmd.args(parts);
+    cmd.cwd(std::env::current_dir()?);
+    cmd.env("TERM", "xterm-256color");
+
+    let child = pair.slave.spawn_command(cmd)?;
+    drop(pair.slave);
+
+    let mut reader = pair.master.try_clone_reader()?;
+    let writer = pair.master.take_writer()?;
+
+    let unread = Arc::new(Mutex::new(String::new()));
+    let last_output = Arc::new(Mutex::new(Instant::now()));
+
+    let reader_unread = Arc::clone(&unread);
+    let reader_last_output = Arc::clone(&last_output);
+    std::thread::spawn(move || {
+        let mut chunk = [0u8; 4096];
+        // Bytes of a character split across reads, kept for the next read
+        let mut pending = Vec::new();
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    pending.extend_from_slice(&chunk[..n]);
+                    let text = decode_utf8(&mut pending);
+                    let mut unread = reader_unread.lock().unwrap();
+                    unread.push_str(&text);
+                    cap_unread(&mut unread);
+                    drop(unread);
+                    *reader_last_output.lock().unwrap() = Instant::now();
+                }
+            }
+        }
+        // A sequence cut off by the end of the output is kept as U+FFFD
+        if !pending.is_empty() {
+            let mut unread = reader_unread.lock().unwrap();
+            unread.push_str(&String::from_utf8_lossy(&pending));
+            cap_unread(&mut unread);
+        }
+    });
+
+    Ok(ShellSession {
+        program: program.to_string(),
+        child,
+        writer,
+        _master: pair.master,
+        unread,
+        last_output,
+    })
+}
+
+/// Wait until the output goes quiet (or the timeout expires) and take it
+async fn collect_output(
+    unread: &Arc<Mutex<String>>,
+    last_output: &Arc<Mutex<Instant>>,
+    timeout: Duration,
+) -> String {
+    let started = Instant::now();
+    loop {
+        let has_output = !unread.lock().unwrap().is_empty();
+        let quiet = last_output.lock().unwrap().elapsed() >= QUIET_PERIOD;
+        if (has_output && quiet) || started.elapsed() >= timeout {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    std::mem::take(&mut *unread.lock().unwrap())
+}
+
+/// Decode the complete characters of `pending`, leaving an incomplete
+/// trailing sequence in place; invalid bytes become U+FFFD
+fn decode_utf8(pending: &mut Vec<u8>) -> String {
+    let mut text = String::new();
+    loop {
+        match std::str::from_utf8(pending) {
+            Ok(valid) => {
+                text.push_str(valid);
+                pending.clear();
+                return text;
+            }
+            Err(e) => {
+                let valid_up_to = e.valid_up_to();
+                text.push_str(&String::from_utf8_lossy(&pending[..valid_up_to]));
+                match e.error_len() {
+                    Some(len) => {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        pending.drain(..valid_up_to + len);
+                    }
+                    None => {
+                        pending.drain(..valid_up_to);
+                        return text;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Drop the oldest unread output beyond `MAX_UNREAD_BYTES`
+fn cap_unread(unread: &mut String) {
+    if unread.len() <= MAX_UNREAD_BYTES {
+        return;
+    }
+    let mut start = unread.len() - MAX_UNREAD_BYTES;
+    while !unread.is_char_boundary(start) {
+        start += 1;
+    }
+    *unread = format!("{DROPPED_MARKER}{}", &unread[start..]);
+}
+
+/// Clean raw terminal output for the conversation, truncating it the way the
+/// shell tool truncates long command output
+fn format_output(raw: &str) -> String {
+    let cleaned: Vec<String> = strip_ansi_sequences(raw)
+        .split('\n')
+        .map(collapse_carriage_returns)
+        .collect();
+    let cleaned = cleaned.join("\n");
+    if cleaned.trim().is_empty() {
+        "[NO NEW OUTPUT]".to_string()
+    } else if cleaned.len() > MAX_TOOL_OUTPUT_LENGTH {
+        crate::tools::truncate::truncate_shell_output(cleaned.trim_end())
+    } else {
+        cleaned.trim_end().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_arguments() {
+        let arguments = parse_arguments("send name=db wait=2.5");
+        assert_eq!(arguments.action, "send");
+        assert_eq!(arguments.name, "db");
+        assert_eq!(arguments.wait, Duration::from_millis(2500));
+
+        let arguments = parse_arguments("read wait=NaN");
+        assert_eq!(arguments.wait, Duration::from_secs(DEFAULT_WAIT_SECONDS));
+
+        let arguments = parse_arguments("OPEN program=python3");
+        assert_eq!(arguments.action, "open");
+        assert_eq!(arguments.name, DEFAULT_SESSION);
+        assert_eq!(arguments.program.as_deref(), Some("python3"));
+    }
+
+    #[test]
+    fn test_format_output() {
+        assert_eq!(format_output("\x1b[32m>>> \x1b[0m1\r\n2\r\n"), ">>> 1\n2");
+        assert_eq!(format_output("\r\n"), "[NO NEW OUTPUT]");
+    }
+
+    #[test]
+    fn test_decode_utf8() {
+        // "é€" split in the middle of both characters
+        let bytes = "é€".as_bytes();
+        let mut pending = bytes[..1].to_vec();
+        assert_eq!(decode_utf8(&mut pending), "");
+        pending.extend_from_slice(&bytes[1..3]);
+        assert_eq!(decode_utf8(&mut pending), "é");
+        assert_eq!(pending, bytes[2..3]);
+        pending.extend_from_slice(&bytes[3..]);
+        assert_eq!(decode_utf8(&mut pending), "€");
+        assert!(pending.is_empty());
+
+        let mut pending = b"a\xffb".to_vec();
+        assert_eq!(decode_utf8(&mut pending), "a\u{fffd}b");
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_cap_unread() {
+        let mut unread = "é".repeat(MAX_UNREAD_BYTES / 2);
+        cap_unread(&mut unread);
+        assert!(!unread.starts_with(DROPPED_MARKER));
+
+        unread.push_str("xyz");
+        cap_unread(&mut unread);
+        assert!(unread.starts_with(DROPPED_MARKER));
+        assert!(unread.ends_with("éxyz"));
+        assert!(unread.len() <= MAX_UNREAD_BYTES + DROPPED_MARKER.len());
+    }
+}
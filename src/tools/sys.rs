@@ -0,0 +1,531 @@
+//! System inspection tool: processes, ports, CPU, memory and disks
+//!
+//! Answers questions like "what is eating my CPU" or "what is bound to port
+//! 8080" the same way on every platform, without shell one-liners whose flags
+//! differ between `ps`, `lsof`, `netstat` and `ss`.
+//!
+//! Usage:
+//! - `sys` gives an overview: host, uptime, load, CPU, memory and the busiest
+//!   processes
+//! - `sys ps [FILTER] [sort=cpu|mem] [limit=N]` lists processes, optionally
+//!   those whose name or command line contains FILTER
+//! - `sys ports [PORT]` lists listening TCP and bound UDP sockets with their
+//!   processes, or every socket of a port
+//! - `sys disk` lists the mounted disks with their usage
+//! - `sys kill PID [term|int|hup|kill]` signals a process
+//!
+//! Everything but `kill` only reads; `kill` is refused in read-only mode and,
+//! like the other tools that change state, asks for permission first.
+
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
+use crate::tools::ToolResult;
+use netstat2::{AddressFamilyFlags, ProtocolFlags, ProtocolSocketInfo, TcpState};
+use std::collections::HashMap;
+use sysinfo::{Disks, Pid, Process, ProcessesToUpdate, Signal, System};
+
+/// Processes listed when no `limit` is given
+const DEFAULT_LIMIT: usize = 25;
+
+/// Busiest processes listed in the overview
+const OVERVIEW_PROCESSES: usize = 5;
+
+/// Characters of a command line shown
+const MAX_COMMAND_CHARS: usize = 120;
+
+/// Whether a call of the sys tool changes the system
+pub fn is_mutating(args: &str) -> bool {
+    args.split_whitespace().next() == Some("kill")
+}
+
+/// Order of process lists
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SortBy {
+    Cpu,
+    Memory,
+}
+
+#[derive(Debug, PartialEq)]
+enum SysArgs {
+    Overview,
+    Processes {
+        filter: Option<String>,
+        sort: SortBy,
+        limit: usize,
+    },
+    Ports {
+        port: Option<u16>,
+    },
+    Disks,
+    Kill {
+        pid: u32,
+        signal: Signal,
+    },
+}
+
+fn parse_args(args: &str) -> Result<SysArgs, String> {
+    let mut words = args.split_whitespace();
+    let action = words.next().unwrap_or("overview");
+    let rest: Vec<&str> = words.collect();
+    match action {
+        "overview" | "cpu" | "mem" | "memory" => Ok(SysArgs::Overview),
+        "ps" | "processes" => {
+            let mut filter = Vec::new();
+            let mut sort = SortBy::Cpu;
+            let mut limit = DEFAULT_LIMIT;
+            for word in rest {
+                match word.split_once('=') {
+                    Some(("sort", "cpu")) => sort = SortBy::Cpu,
+                    Some(("sort", "mem" | "memory")) => sort = SortBy::Memory,
+                    Some(("sort", other)) => {
+                        return Err(format!("Invalid sort: {other}; use cpu or mem"))
+                    }
+                    Some(("limit", value)) => {
+                        limit = value
+                            .parse()
+                            .map_err(|_| format!("Invalid number: {word}"))?
+                    }
+                    _ => filter.push(word),
+                }
+            }
+            Ok(SysArgs::Processes {
+                filter: (!filter.is_empty()).then(|| filter.join(" ")),
+                sort,
+                limit,
+            })
+        }
+        "ports" => {
+            let port = match rest.first() {
+                Some(word) => Some(
+                    word.trim_start_matches(':')
+                        .parse()
+                        .map_err(|_| format!("Invalid port: {word}"))?,
+                ),
+                None => None,
+            };
+            Ok(SysArgs::Ports { port })
+        }
+        "disk" | "disks" => Ok(SysArgs::Disks),
+        "kill" => {
+            let pid = rest
+                .first()
+                .and_then(|word| word.parse().ok())
+                .ok_or("Usage: `sys kill PID [term|int|hup|kill]`")?;
+            let signal = match rest
+                .get(1)
+                .map(|s| s.trim_start_matches("SIG").to_lowercase())
+            {
+                None => Signal::Term,
+                Some(name) => match name.as_str() {
+                    "term" | "15" => Signal::Term,
+                    "int" | "2" => Signal::Interrupt,
+                    "hup" | "1" => Signal::Hangup,
+                    "kill" | "9" => Signal::Kill,
+                    _ => return Err(format!("Unsupported signal: {name}")),
+                },
+            };
+            Ok(SysArgs::Kill { pid, signal })
+        }
+        other => Err(format!(
+            "Unknown action '{other}': use ps, ports, disk or kill, or no action for an overview"
+        )),
+    }
+}
+
+/// Execute the sys tool
+pub async fn execute_sys(args: &str, _body: &str, silent_mode: bool) -> ToolResult {
+    let parsed = match parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+    };
+
+    if !silent_mode {
+        bprintln !(tool: "sys",
+            "{FORMAT_BOLD}🖥️  System:{FORMAT_RESET} {FORMAT_GRAY}{}{FORMAT_RESET}",
+            if args.trim().is_empty() { "overview" } else { args.trim() }
+        );
+    }
+
+    // CPU usage is measured over an interval, and socket tables can be large
+    let result = tokio::task::spawn_blocking(move || run(parsed))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+    match result {
+        Ok(output) => ToolResult::success(output),
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            ToolResult::error(error_msg)
+        }
+    }
+}
+
+fn run(args: SysArgs) -> Result<String, String> {
+    match args {
+        SysArgs::Overview => Ok(overview(&snapshot())),
+        SysArgs::Processes {
+            filter,
+            sort,
+            limit,
+        } => Ok(processes(&snapshot(), filter.as_deref(), sort, limit)),
+        SysArgs::Ports { port } => ports(port),
+        SysArgs::Disks => Ok(disks()),
+        SysArgs::Kill { pid, signal } => kill(pid, signal),
+    }
+}
+
+/// System state with CPU usage measured since the first refresh
+fn snapshot() -> System {
+    let mut system = System::new();
+    system.refresh_cpu_usage();
+    system.refresh_processes(ProcessesToUpdate::All, true);
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_cpu_usage();
+    system.refresh_memory();
+    system.refresh_processes(ProcessesToUpdate::All, true);
+    system
+}
+
+fn overview(system: &System) -> String {
+    let load = System::load_average();
+    let mut text = format!(
+        "Host: {} ({} {})\nUptime: {}\n",
+        System::host_name().unwrap_or_default(),
+        System::name().unwrap_or_default(),
+        System::os_version().unwrap_or_default(),
+        format_uptime(System::uptime())
+    );
+    text.push_str(&format!(
+        "CPU: {:.1}% of {} cores, load {:.2} {:.2} {:.2}\n",
+        system.global_cpu_usage(),
+        system.cpus().len(),
+        load.one,
+        load.five,
+        load.fifteen
+    ));
+    text.push_str(&format!(
+        "Memory: {} of {} used, swap {} of {}\n\n",
+        format_bytes(system.used_memory()),
+        format_bytes(system.total_memory()),
+        format_bytes(system.used_swap()),
+        format_bytes(system.total_swap())
+    ));
+    text.push_str("Busiest processes:\n");
+    text.push_str(&processes(system, None, SortBy::Cpu, OVERVIEW_PROCESSES));
+    text
+}
+
+fn processes(system: &System, filter: Option<&str>, sort: SortBy, limit: usize) -> String {
+    let filter = filter.map(str::to_lowercase);
+    let mut list: Vec<&Process> = system
+        .processes()
+        .values()
+        // Threads are listed as processes on Linux
+        .filter(|process| process.thread_kind().is_none())
+        .filter(|process| {
+            filter.as_ref().map_or(true, |filter| {
+                process
+                    .name()
+                    .to_string_lossy()
+                    .to_lowercase()
+                    .contains(filter)
+                    || command_line(process).to_lowercase().contains(filter)
+            })
+        })
+        .collect();
+    match sort {
+        SortBy::Cpu => list.sort_by(|a, b| b.cpu_usage().total_cmp(&a.cpu_usage())),
+        SortBy::Memory => list.sort_by_key(|process| std::cmp::Reverse(process.memory())),
+    }
+
+    if list.is_empty() {
+        return "No matching processes".to_string();
+    }
+    let mut text = format!(
+        "{:>7} {:>7} {:>6} {:>9}  COMMAND\n",
+        "PID", "PPID", "CPU%", "MEM"
+    );
+    for process in list.iter().take(limit) {
+        text.push_str(&format!(
+            "{:>7} {:>7} {:>6.1} {:>9}  {}\n",
+            process.pid(),
+            process
+                .parent()
+                .map(|pid| pid.to_string())
+                .unwrap_or_default(),
+            process.cpu_usage(),
+            format_bytes(process.memory()),
+            command_line(process)
+        ));
+    }
+    if list.len() > limit {
+        text.push_str(&format!("... {} more\n", list.len() - limit));
+    }
+    text
+}
+
+/// Command line of a process, or its name when the command line is hidden
+fn command_line(process: &Process) -> String {
+    let command: Vec<String> = process
+        .cmd()
+        .iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    let command = if command.is_empty() {
+        process.name().to_string_lossy().into_owned()
+    } else {
+        command.join(" ")
+    };
+    if command.chars().count() > MAX_COMMAND_CHARS {
+        let short: String = command.chars().take(MAX_COMMAND_CHARS).collect();
+        format!("{short}…")
+    } else {
+        command
+    }
+}
+
+/// A socket with the processes owning it
+#[derive(Debug, Clone, PartialEq)]
+struct Socket {
+    protocol: &'static str,
+    local: String,
+    local_port: u16,
+    remote: Option<String>,
+    state: Option<String>,
+    pids: Vec<u32>,
+}
+
+fn ports(port: Option<u16>) -> Result<String, String> {
+    let sockets = netstat2::get_sockets_info(
+        AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6,
+        ProtocolFlags::TCP | ProtocolFlags::UDP,
+    )
+    .map_err(|e| format!("Failed to list sockets: {e}"))?;
+    let sockets: Vec<Socket> = sockets
+        .into_iter()
+        .map(|info| match info.protocol_socket_info {
+            ProtocolSocketInfo::Tcp(tcp) => Socket {
+                protocol: "tcp",
+                local: format!("{}:{}", tcp.local_addr, tcp.local_port),
+                local_port: tcp.local_port,
+                remote: (tcp.state != TcpState::Listen)
+                    .then(|| format!("{}:{}", tcp.remote_addr, tcp.remote_port)),
+                state: Some(tcp.state.to_string()),
+                pids: info.associated_pids,
+            },
+            ProtocolSocketInfo::Udp(udp) => Socket {
+                protocol: "udp",
+                local: format!("{}:{}", udp.local_addr, udp.local_port),
+                local_port: udp.local_port,
+                remote: None,
+                state: None,
+                pids: info.associated_pids,
+            },
+        })
+        .collect();
+
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::All, true);
+    let names: HashMap<u32, String> = system
+        .processes()
+        .iter()
+        .map(|(pid, process)| (pid.as_u32(), process.name().to_string_lossy().into_owned()))
+        .collect();
+    Ok(format_sockets(&sockets, port, &names))
+}
+
+/// Sockets of a port, or the listening and bound ones, as a table
+fn format_sockets(sockets: &[Socket], port: Option<u16>, names: &HashMap<u32, String>) -> String {
+    let mut selected: Vec<&Socket> = sockets
+        .iter()
+        .filter(|socket| match port {
+            Some(port) => socket.local_port == port,
+            None => socket.remote.is_none(),
+        })
+        .collect();
+    selected.sort_by(|a, b| {
+        (a.local_port, a.protocol, &a.local).cmp(&(b.local_port, b.protocol, &b.local))
+    });
+    selected.dedup();
+    if selected.is_empty() {
+        return match port {
+            Some(port) => format!("Nothing uses port {port}"),
+            None => "No listening sockets".to_string(),
+        };
+    }
+
+    let mut text = String::new();
+    for socket in selected {
+        let processes: Vec<String> = socket
+            .pids
+            .iter()
+            .map(|pid| match names.get(pid) {
+                Some(name) => format!("{name} ({pid})"),
+                None => pid.to_string(),
+            })
+            .collect();
+        let processes = if processes.is_empty() {
+            // Sockets of other users' processes need elevated privileges
+            "unknown process".to_string()
+        } else {
+            processes.join(", ")
+        };
+        let mut line = format!("{} {}", socket.protocol, socket.local);
+        if let Some(remote) = &socket.remote {
+            line.push_str(&format!(" -> {remote}"));
+        }
+        if let Some(state) = &socket.state {
+            line.push_str(&format!(" {state}"));
+        }
+        text.push_str(&format!("{line}  {processes}\n"));
+    }
+    text
+}
+
+fn disks() -> String {
+    let disks = Disks::new_with_refreshed_list();
+    if disks.list().is_empty() {
+        return "No disks found".to_string();
+    }
+    let mut text = String::new();
+    for disk in disks.list() {
+        let total = disk.total_space();
+        let used = total.saturating_sub(disk.available_space());
+        let percent = if total == 0 {
+            0.0
+        } else {
+            used as f64 * 100.0 / total as f64
+        };
+        text.push_str(&format!(
+            "{} ({}, {}): {} of {} used ({percent:.0}%), {} free\n",
+            disk.mount_point().display(),
+            disk.name().to_string_lossy(),
+            disk.file_system().to_string_lossy(),
+            format_bytes(used),
+            format_bytes(total),
+            format_bytes(disk.available_space())
+        ));
+    }
+    text
+}
+
+fn kill(pid: u32, signal: Signal) -> Result<String, String> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    let process = system
+        .process(pid)
+        .ok_or_else(|| format!("No process with PID {pid}"))?;
+    match process.kill_with(signal) {
+        Some(true) => Ok(format!(
+            "Sent {signal:?} to {} ({pid})",
+            process.name().to_string_lossy()
+        )),
+        Some(false) => Err(format!("Failed to signal process {pid}")),
+        None => Err(format!("{signal:?} is not supported on this platform")),
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+fn format_uptime(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+    if days > 0 {
+        format!("{days}d {hours}h {minutes}m")
+    } else {
+        format!("{hours}h {minutes}m")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args("").unwrap(), SysArgs::Overview);
+        assert_eq!(
+            parse_args("ps node sort=mem limit=5").unwrap(),
+            SysArgs::Processes {
+                filter: Some("node".to_string()),
+                sort: SortBy::Memory,
+                limit: 5
+            }
+        );
+        assert_eq!(
+            parse_args("ports :8080").unwrap(),
+            SysArgs::Ports { port: Some(8080) }
+        );
+        assert_eq!(
+            parse_args("kill 42 SIGKILL").unwrap(),
+            SysArgs::Kill {
+                pid: 42,
+                signal: Signal::Kill
+            }
+        );
+        assert!(parse_args("kill").is_err());
+        assert!(parse_args("reboot").is_err());
+        assert!(is_mutating("kill 42"));
+        assert!(!is_mutating("ps kill"));
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(3 * 1024 * 1024 / 2), "1.5 MB");
+    }
+
+    #[test]
+    fn test_format_sockets() {
+        let socket = |protocol, port, remote: Option<&str>, pids: Vec<u32>| Socket {
+            protocol,
+            local: format!("0.0.0.0:{port}"),
+            local_port: port,
+            remote: remote.map(str::to_string),
+            state: (protocol == "tcp").then(|| {
+                if remote.is_some() {
+                    "ESTABLISHED"
+                } else {
+                    "LISTEN"
+                }
+                .to_string()
+            }),
+            pids,
+        };
+        let sockets = vec![
+            socket("tcp", 8080, None, vec![7]),
+            socket("tcp", 8080, Some("10.0.0.2:51000"), vec![7]),
+            socket("udp", 53, None, vec![]),
+        ];
+        let names = HashMap::from([(7, "server".to_string())]);
+
+        assert_eq!(
+            format_sockets(&sockets, None, &names),
+            "udp 0.0.0.0:53  unknown process\ntcp 0.0.0.0:8080 LISTEN  server (7)\n"
+        );
+        assert_eq!(
+            format_sockets(&sockets, Some(8080), &names),
+            "tcp 0.0.0.0:8080 LISTEN  server (7)\n\
+             tcp 0.0.0.0:8080 -> 10.0.0.2:51000 ESTABLISHED  server (7)\n"
+        );
+        assert_eq!(
+            format_sockets(&sockets, Some(9), &names),
+            "Nothing uses port 9"
+        );
+    }
+}
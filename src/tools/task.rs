@@ -1,5 +1,7 @@
 //! Task tool implementation for creating and running subtasks
 
+use crate::agent::budget::{AgentBudget, SubAgentCost};
+use crate::agent::types::StateReceiver;
 use crate::agent::{AgentId, AgentMessage, AgentState};
 use crate::config::Config;
 use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
@@ -8,22 +10,36 @@ use crate::tools::ToolResult;
 use std::fs::File;
 use std::io::{self, Read};
 use std::path::Path;
-use std::time::{Duration, Instant};
-use tokio::time::sleep;
+use std::time::Duration;
+
+/// Line separating multiple task bodies for parallel fan-out
+pub const TASK_SEPARATOR: &str = "<<<<TASK";
+
+/// Longest a sub-agent may work on its task
+const TASK_TIMEOUT: Duration = Duration::from_secs(300);
 
 /// Execute the task tool - create and run a subtask with its own agent
 pub async fn execute_task(
     args: &str,
     body: &str,
     silent_mode: bool,
-    _parent_agent_id: Option<AgentId>,
+    parent_agent_id: Option<AgentId>,
+    cost: &SubAgentCost,
 ) -> ToolResult {
-    // Parse arguments to extract task name, kind, and includes
-    let (task_name, kind_name, includes) = parse_task_arguments(args);
+    // Parse arguments to extract task name, kind, includes, parallelism and budgets
+    let TaskArguments {
+        name: task_name,
+        kind: kind_name,
+        includes,
+        parallel,
+        budget,
+    } = parse_task_arguments(args);
+
+    // Split the body into individual task instructions
+    let task_bodies = split_task_bodies(body);
 
     // Validate task instructions
-    let task_instructions = body.trim();
-    if task_instructions.is_empty() {
+    if task_bodies.is_empty() {
         let error_msg = "Error: Task requires instructions in the body".to_string();
         if !silent_mode {
             bprintln!(error:"{}", error_msg);
@@ -31,6 +47,25 @@ pub async fn execute_task(
         return ToolResult::error(error_msg);
     }
 
+    // Multiple bodies or an explicit parallel= parameter fan out to readonly sub-agents
+    if task_bodies.len() > 1 || parallel.is_some() {
+        let cost = cost.split(task_bodies.len());
+        return execute_parallel_tasks(
+            &task_name,
+            kind_name,
+            &includes,
+            task_bodies,
+            parallel,
+            budget,
+            cost,
+            parent_agent_id,
+            silent_mode,
+        )
+        .await;
+    }
+
+    let task_instructions = task_bodies[0].as_str();
+
     // Log task start information
     if !silent_mode {
         let kind_info = if let Some(kind) = &kind_name {
@@ -57,36 +92,192 @@ pub async fn execute_task(
         );
     }
 
+    let result = match run_subtask(
+        format!("task_{task_name}"),
+        kind_name,
+        &includes,
+        task_instructions,
+        false,
+        budget,
+        cost,
+        parent_agent_id,
+        silent_mode,
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error:"{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+    };
+
+    // Log task completion
+    if !silent_mode {
+        bprintln!(tool: "task",
+            "\n{}✅ Subtask Completed:{} {}\n{}",
+            FORMAT_BOLD,
+            FORMAT_RESET,
+            task_name,
+            result,
+        );
+    }
+
+    // Return the result
+    ToolResult::success(result)
+}
+
+/// Run several readonly subtasks concurrently and aggregate their results
+#[allow(clippy::too_many_arguments)]
+async fn execute_parallel_tasks(
+    task_name: &str,
+    kind_name: Option<String>,
+    includes: &[String],
+    task_bodies: Vec<String>,
+    parallel: Option<usize>,
+    budget: AgentBudget,
+    cost: SubAgentCost,
+    parent_agent_id: Option<AgentId>,
+    silent_mode: bool,
+) -> ToolResult {
+    use futures::stream::{self, StreamExt};
+
+    let task_count = task_bodies.len();
+    let concurrency = parallel.unwrap_or(task_count).clamp(1, task_count);
+
+    if !silent_mode {
+        bprintln!(tool: "task",
+            "\n{}🔀 Parallel Subtasks Started:{} {} ({} tasks, up to {} at once)",
+            FORMAT_BOLD,
+            FORMAT_RESET,
+            task_name,
+            task_count,
+            concurrency
+        );
+    }
+
+    // `buffered` keeps results in the original task order
+    let cost = &cost;
+    let results: Vec<Result<String, String>> = stream::iter(task_bodies.iter().enumerate())
+        .map(|(i, instructions)| {
+            let agent_name = format!("task_{}_{}", task_name, i + 1);
+            let kind_name = kind_name.clone();
+            let budget = budget.clone();
+            async move {
+                run_subtask(
+                    agent_name,
+                    kind_name,
+                    includes,
+                    instructions,
+                    true,
+                    budget,
+                    cost,
+                    parent_agent_id,
+                    silent_mode,
+                )
+                .await
+            }
+        })
+        .buffered(concurrency)
+        .collect()
+        .await;
+
+    let failed = results.iter().filter(|r| r.is_err()).count();
+
+    // Combine the results into per-task sections
+    let combined = results
+        .iter()
+        .zip(task_bodies.iter())
+        .enumerate()
+        .map(|(i, (result, instructions))| {
+            let objective = instructions.lines().next().unwrap_or("").trim();
+            match result {
+                Ok(output) => format!(
+                    "## Task {} of {}: {}\n\n{}",
+                    i + 1,
+                    task_count,
+                    objective,
+                    output.trim()
+                ),
+                Err(e) => format!(
+                    "## Task {} of {}: {} (FAILED)\n\n{}",
+                    i + 1,
+                    task_count,
+                    objective,
+                    e
+                ),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    if !silent_mode {
+        bprintln!(tool: "task",
+            "\n{}✅ Parallel Subtasks Completed:{} {} ({} succeeded, {} failed)",
+            FORMAT_BOLD,
+            FORMAT_RESET,
+            task_name,
+            task_count - failed,
+            failed
+        );
+    }
+
+    if failed == task_count {
+        ToolResult::error(combined)
+    } else {
+        ToolResult::success(combined)
+    }
+}
+
+/// Create a sub-agent, send it the instructions and wait for its result
+#[allow(clippy::too_many_arguments)]
+async fn run_subtask(
+    agent_name: String,
+    kind_name: Option<String>,
+    includes: &[String],
+    task_instructions: &str,
+    readonly: bool,
+    budget: AgentBudget,
+    cost: &SubAgentCost,
+    parent_agent_id: Option<AgentId>,
+    silent_mode: bool,
+) -> Result<String, String> {
     // Create config for the subtask agent
     let mut config = Config::new();
+    config.readonly = readonly;
+    config.budget = budget;
+    // The sub-agent may spend what is left of the parent's cost limit, and its cost counts there
+    config.budget.max_cost = cost.ceiling;
+    config.parent_cost = Some(cost.spent.clone());
 
     // Set up the system prompt based on the kind
-    let enabled_tools = prompts::ALL_TOOLS;
+    let enabled_tools = if readonly {
+        prompts::READONLY_TOOLS.to_vec()
+    } else {
+        prompts::kinds::default_tools(kind_name.as_deref())
+    };
     let grammar = prompts::select_grammar_for_model("claude-3"); // Default to Claude grammar
 
     // Generate the system prompt
-    if let Ok(system_prompt) = prompts::generate_system_prompt(
-        enabled_tools,
+    match prompts::generate_system_prompt(
+        &enabled_tools,
         false,
         kind_name.as_deref(),
         grammar.clone(),
         Some(&config.disabled_tools), // Pass inherited disabled tools
     ) {
-        config.system_prompt = Some(system_prompt);
-    } else {
-        let error_msg = format!("Failed to generate system prompt for task agent");
-        if !silent_mode {
-            bprintln!(error:"{}", error_msg);
+        Ok(system_prompt) => {
+            config.system_prompt = Some(system_prompt.text);
+            config.stop_sequences = system_prompt.stop_sequences;
         }
-        return ToolResult::error(error_msg);
+        Err(_) => return Err("Failed to generate system prompt for task agent".to_string()),
     }
 
     // Set the kind parameter in the config
     config.kind = kind_name;
 
-    // Create the subtask agent with a unique name
-    let agent_name = format!("task_{task_name}");
-
     // Make a note of disabled tools for clarity in output
     if !config.disabled_tools.is_empty() && !silent_mode {
         bprintln!(
@@ -98,20 +289,14 @@ pub async fn execute_task(
         );
     }
 
-    let subtask_agent_id = match crate::agent::create_agent(agent_name, config) {
-        Ok(id) => id,
-        Err(e) => {
-            let error_msg = format!("Failed to create task agent: {e}");
-            if !silent_mode {
-                bprintln!(error:"{}", error_msg);
-            }
-            return ToolResult::error(error_msg);
-        }
-    };
+    // Each sub-agent gets its own output buffer; depth and loop limits are enforced here
+    let subtask_agent_id =
+        crate::agent::create_child_agent(agent_name, config, parent_agent_id, task_instructions)
+            .map_err(|e| format!("Failed to create task agent: {e}"))?;
 
     // Process file includes and combine with task instructions
     let combined_instructions = if !includes.is_empty() {
-        let context_content = process_includes(&includes, silent_mode);
+        let context_content = process_includes(includes, silent_mode);
         if !context_content.is_empty() {
             // Combine file context with task instructions
             format!(
@@ -125,41 +310,57 @@ pub async fn execute_task(
         task_instructions.to_string()
     };
 
-    // Send the combined instructions (context + task) to the agent
-    if let Err(e) = crate::agent::send_message(
-        subtask_agent_id,
-        AgentMessage::UserInput(combined_instructions),
-    ) {
-        let error_msg = format!("Failed to send task to agent: {e}");
-        if !silent_mode {
-            bprintln!(error:"{}", error_msg);
-        }
-        return ToolResult::error(error_msg);
-    }
+    // Send the combined instructions (context + task) to the agent and wait for its result
+    let result = send_and_wait(subtask_agent_id, combined_instructions, silent_mode).await;
 
-    // Wait for the agent to complete its task
-    let result = wait_for_agent_completion(subtask_agent_id, silent_mode).await;
+    // Remove the agent once its result is collected, so the next task may reuse its name
+    let _ = crate::agent::terminate_agent(subtask_agent_id).await;
+    result
+}
 
-    // Log task completion
-    if !silent_mode {
-        bprintln!(tool: "task",
-            "\n{}✅ Subtask Completed:{} {}\n{}",
-            FORMAT_BOLD,
-            FORMAT_RESET,
-            task_name,
-            result,
-        );
+/// Split the task body into separate task instructions on `TASK_SEPARATOR` lines
+fn split_task_bodies(body: &str) -> Vec<String> {
+    let mut bodies = Vec::new();
+    let mut current = String::new();
+
+    for line in body.lines() {
+        if line.trim() == TASK_SEPARATOR {
+            bodies.push(std::mem::take(&mut current));
+        } else {
+            current.push_str(line);
+            current.push('\n');
+        }
     }
+    bodies.push(current);
 
-    // Return the result
-    ToolResult::success(result)
+    bodies
+        .into_iter()
+        .map(|b| b.trim().to_string())
+        .filter(|b| !b.is_empty())
+        .collect()
+}
+
+/// Parsed parameters of a task tool invocation
+struct TaskArguments {
+    /// Name of the task (non-parameter words)
+    name: String,
+    /// Agent kind to use for the sub-agent(s)
+    kind: Option<String>,
+    /// Include patterns for file context
+    includes: Vec<String>,
+    /// Maximum number of concurrent sub-agents for fan-out
+    parallel: Option<usize>,
+    /// Resource limits applied to each sub-agent
+    budget: AgentBudget,
 }
 
-/// Parse task arguments to extract task name, kind, and includes
-fn parse_task_arguments(args: &str) -> (String, Option<String>, Vec<String>) {
+/// Parse task arguments to extract task name, kind, includes, parallelism and budgets
+fn parse_task_arguments(args: &str) -> TaskArguments {
     let args_string = args.trim().to_string();
     let mut kind_name = None;
     let mut includes = Vec::new();
+    let mut parallel = None;
+    let mut budget = AgentBudget::default();
     let mut task_name_parts = Vec::new();
 
     // Split the args by spaces to check for parameters
@@ -176,6 +377,23 @@ fn parse_task_arguments(args: &str) -> (String, Option<String>, Vec<String>) {
             if let Some(value) = part.strip_prefix("include=") {
                 includes.push(value.to_string());
             }
+        } else if let Some((key @ ("parallel" | "max_tokens" | "max_time" | "max_tools"), value)) =
+            part.split_once('=')
+        {
+            // Extract numeric parameters (parallelism and per-agent budgets)
+            let number = match value.parse::<usize>() {
+                Ok(n) if n > 0 => n,
+                _ => {
+                    bprintln!(warn: "Invalid {key} value: {value}. Ignoring.");
+                    continue;
+                }
+            };
+            match key {
+                "parallel" => parallel = Some(number),
+                "max_tokens" => budget.max_tokens = Some(number),
+                "max_time" => budget.max_duration = Some(Duration::from_secs(number as u64)),
+                _ => budget.max_tool_calls = Some(number),
+            }
         } else {
             // This is part of the task name
             task_name_parts.push(part);
@@ -189,7 +407,13 @@ fn parse_task_arguments(args: &str) -> (String, Option<String>, Vec<String>) {
         task_name_parts.join(" ")
     };
 
-    (task_name, kind_name, includes)
+    TaskArguments {
+        name: task_name,
+        kind: kind_name,
+        includes,
+        parallel,
+        budget,
+    }
 }
 
 /// Process include files and return their contents
@@ -254,70 +478,57 @@ fn read_file<P: AsRef<Path>>(path: P) -> io::Result<String> {
     Ok(content)
 }
 
-/// Wait for agent to complete its task and return the final result
-async fn wait_for_agent_completion(agent_id: AgentId, silent_mode: bool) -> String {
-    let timeout = Duration::from_secs(300); // 5 minute timeout
-    let start_time = Instant::now();
-    let mut last_polling_time = Instant::now();
-    let polling_interval = Duration::from_millis(500);
-
-    let mut result = String::new();
-    let mut done = false;
-
-    // Keep checking the agent state until it's done or timeout
-    while !done && start_time.elapsed() < timeout {
-        // Only poll at the specified interval
-        if last_polling_time.elapsed() >= polling_interval {
-            last_polling_time = Instant::now();
-
-            // Get the agent state
-            let state = crate::agent::get_agent_state(agent_id).ok();
-
-            match state {
-                // Agent is done, get the result
-                Some(AgentState::Done(response)) => {
-                    // Extract the final response
-                    if let Some(content) = response {
-                        result = content;
-                    } else {
-                        // If no explicit done response, get the buffer content
-                        let buffer_content = extract_final_output(agent_id);
-                        result = buffer_content;
-                    }
-
-                    done = true;
-                }
-
-                // Agent is terminated, consider as done
-                Some(AgentState::Terminated) => {
-                    if !silent_mode {
-                        bprintln!(warn: "Task agent was terminated before completion");
-                    }
-                    result = "Task was terminated before completion".to_string();
-                    done = true;
-                }
+/// Send the task to its agent and wait for the result
+async fn send_and_wait(
+    agent_id: AgentId,
+    instructions: String,
+    silent_mode: bool,
+) -> Result<String, String> {
+    // Follow the state from before the task is sent, so no change is missed
+    let mut state = crate::agent::subscribe_agent_state(agent_id)
+        .map_err(|e| format!("Failed to follow task agent: {e}"))?;
+    state.borrow_and_update();
+    crate::agent::send_message(agent_id, AgentMessage::UserInput(instructions))
+        .map_err(|e| format!("Failed to send task to agent: {e}"))?;
+    Ok(wait_for_agent_completion(agent_id, state, silent_mode).await)
+}
 
-                // Other states - keep waiting
+/// Wait for agent to complete its task and return the final result
+async fn wait_for_agent_completion(
+    agent_id: AgentId,
+    mut state: StateReceiver,
+    silent_mode: bool,
+) -> String {
+    // Wait for the agent to finish, to be terminated or for its task to end
+    let completion = async {
+        while state.changed().await.is_ok() {
+            let current = state.borrow_and_update().clone();
+            match current {
+                AgentState::Done(Some(response)) => return Some(response),
+                // Without an explicit done response, use the agent's output
+                AgentState::Done(None) => return Some(extract_final_output(agent_id)),
+                AgentState::Terminated => break,
                 _ => {}
             }
         }
+        None
+    };
 
-        // Small sleep to avoid tight loop
-        sleep(Duration::from_millis(50)).await;
-    }
-
-    // If we reached timeout
-    if !done {
-        if !silent_mode {
-            bprintln!(warn: "Task timed out after {} seconds", timeout.as_secs());
+    match tokio::time::timeout(TASK_TIMEOUT, completion).await {
+        Ok(Some(result)) => result,
+        Ok(None) => {
+            if !silent_mode {
+                bprintln!(warn: "Task agent was terminated before completion");
+            }
+            "Task was terminated before completion".to_string()
+        }
+        Err(_) => {
+            if !silent_mode {
+                bprintln!(warn: "Task timed out after {} seconds", TASK_TIMEOUT.as_secs());
+            }
+            format!("Task timed out after {} seconds", TASK_TIMEOUT.as_secs())
         }
-        result = format!("Task timed out after {} seconds", timeout.as_secs());
-
-        // Terminate the agent
-        let _ = crate::agent::terminate_agent(agent_id).await;
     }
-
-    result
 }
 
 /// Extract the final output from the agent's buffer
@@ -369,3 +580,37 @@ fn extract_final_output(agent_id: AgentId) -> String {
 
     "Unable to retrieve agent output".to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_task_bodies() {
+        let body = "First task\nmore details\n<<<<TASK\nSecond task\n<<<<TASK\n\n";
+        let bodies = split_task_bodies(body);
+        assert_eq!(bodies, vec!["First task\nmore details", "Second task"]);
+
+        assert_eq!(split_task_bodies("Single task"), vec!["Single task"]);
+        assert!(split_task_bodies("  \n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_parallel_argument() {
+        let parsed = parse_task_arguments("kind=researcher parallel=3 compare libraries");
+        assert_eq!(parsed.name, "compare libraries");
+        assert_eq!(parsed.kind.as_deref(), Some("researcher"));
+        assert!(parsed.includes.is_empty());
+        assert_eq!(parsed.parallel, Some(3));
+        assert!(!parsed.budget.is_limited());
+    }
+
+    #[test]
+    fn test_parse_budget_arguments() {
+        let parsed = parse_task_arguments("max_tokens=50000 max_time=120 max_tools=10 audit");
+        assert_eq!(parsed.name, "audit");
+        assert_eq!(parsed.budget.max_tokens, Some(50000));
+        assert_eq!(parsed.budget.max_duration, Some(Duration::from_secs(120)));
+        assert_eq!(parsed.budget.max_tool_calls, Some(10));
+    }
+}
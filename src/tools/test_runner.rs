@@ -0,0 +1,636 @@
+//! Test tool running common test runners and summarizing their failures
+//!
+//! Raw test output is mostly passing tests, progress lines and backtraces. The
+//! test tool runs the project's runner, parses its output into failures (test
+//! name, location and message) and returns a compact report, falling back to the
+//! end of the raw output when nothing could be parsed (e.g. build errors).
+//!
+//! Usage:
+//! - `test` detects the runner from the project files
+//! - `test cargo|pytest|jest|go [ARGS...]` picks the runner and passes the
+//!   remaining arguments to it (filters, paths, packages)
+
+use crate::constants::{FORMAT_BOLD, FORMAT_GRAY, FORMAT_RESET};
+use crate::tools::ToolResult;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tokio::time::Duration;
+
+/// Longest a test or check run may take
+const TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Failures included in the report
+const MAX_FAILURES: usize = 20;
+
+/// Lines of each failure message included in the report
+const MAX_MESSAGE_LINES: usize = 10;
+
+/// Lines of raw output included when no failures were recognized
+const RAW_TAIL_LINES: usize = 40;
+
+lazy_static! {
+    static ref CARGO_RESULT: Regex = Regex::new(r"^test (.+?) \.\.\. (ok|FAILED|ignored)").unwrap();
+    static ref CARGO_SECTION: Regex = Regex::new(r"^---- (.+) stdout ----$").unwrap();
+    static ref CARGO_PANIC: Regex =
+        Regex::new(r"panicked at (?:'(.*)', )?([^\s:]+):(\d+):\d+:?$").unwrap();
+    static ref PYTEST_SUMMARY: Regex = Regex::new(r"^(FAILED|ERROR) (\S+)(?: - (.*))?$").unwrap();
+    static ref PYTEST_SECTION: Regex = Regex::new(r"^_{3,} (.+?) _{3,}$").unwrap();
+    static ref PYTEST_LOCATION: Regex = Regex::new(r"^(\S+\.py):(\d+): ").unwrap();
+    static ref PYTEST_COUNT: Regex = Regex::new(r"(\d+) (passed|failed|errors?)\b").unwrap();
+    static ref GO_LOCATION: Regex = Regex::new(r"^\s*(\S+\.go):(\d+): ").unwrap();
+    static ref JS_LOCATION: Regex = Regex::new(r"\(?([^\s()]+\.[jt]sx?):(\d+):\d+\)?").unwrap();
+}
+
+/// Supported test runners
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Runner {
+    Cargo,
+    Pytest,
+    Jest,
+    Go,
+}
+
+impl Runner {
+    fn name(self) -> &'static str {
+        match self {
+            Runner::Cargo => "cargo test",
+            Runner::Pytest => "pytest",
+            Runner::Jest => "jest",
+            Runner::Go => "go test",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "cargo" => Some(Runner::Cargo),
+            "pytest" => Some(Runner::Pytest),
+            "jest" => Some(Runner::Jest),
+            "go" => Some(Runner::Go),
+            _ => None,
+        }
+    }
+
+    /// Pick the runner from the files in the project directory
+    fn detect(dir: &Path) -> Option<Self> {
+        let has = |file: &str| dir.join(file).exists();
+        if has("Cargo.toml") {
+            Some(Runner::Cargo)
+        } else if has("go.mod") {
+            Some(Runner::Go)
+        } else if has("package.json") {
+            Some(Runner::Jest)
+        } else if [
+            "pytest.ini",
+            "pyproject.toml",
+            "setup.py",
+            "setup.cfg",
+            "tox.ini",
+        ]
+        .iter()
+        .any(|file| has(file))
+        {
+            Some(Runner::Pytest)
+        } else {
+            None
+        }
+    }
+
+    /// Program and arguments; jest writes its JSON report to `report`
+    fn command(self, args: &[&str], report: &Path) -> (&'static str, Vec<String>) {
+        let args = args.iter().map(|arg| arg.to_string());
+        match self {
+            Runner::Cargo => (
+                "cargo",
+                ["test".to_string()].into_iter().chain(args).collect(),
+            ),
+            Runner::Pytest => (
+                "pytest",
+                ["-q", "-rfE", "--tb=short", "--color=no"]
+                    .map(String::from)
+                    .into_iter()
+                    .chain(args)
+                    .collect(),
+            ),
+            Runner::Jest => (
+                "npx",
+                vec![
+                    "jest".to_string(),
+                    "--ci".to_string(),
+                    "--json".to_string(),
+                    format!("--outputFile={}", report.display()),
+                ]
+                .into_iter()
+                .chain(args)
+                .collect(),
+            ),
+            Runner::Go => {
+                let mut command: Vec<String> = vec!["test".to_string(), "-json".to_string()];
+                let before = command.len();
+                command.extend(args);
+                if command.len() == before {
+                    command.push("./...".to_string());
+                }
+                ("go", command)
+            }
+        }
+    }
+}
+
+/// A failed test
+#[derive(Debug, Clone, PartialEq)]
+struct Failure {
+    name: String,
+    /// `file:line` of the failure, if it could be determined
+    location: Option<String>,
+    message: String,
+}
+
+/// Parsed outcome of a test run
+#[derive(Debug, Default, PartialEq)]
+struct TestReport {
+    passed: usize,
+    failed: usize,
+    failures: Vec<Failure>,
+}
+
+/// Execute the test tool
+pub async fn execute_test(args: &str, _body: &str, silent_mode: bool) -> ToolResult {
+    let mut tokens: Vec<&str> = args.split_whitespace().collect();
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+
+    let explicit = tokens.first().and_then(|token| Runner::parse(token));
+    let runner = match explicit.or_else(|| Runner::detect(&cwd)) {
+        Some(runner) => runner,
+        None => {
+            let error_msg = "Could not detect the test runner; use `test cargo`, `test pytest`, \
+                             `test jest` or `test go`"
+                .to_string();
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+    };
+    if explicit.is_some() {
+        tokens.remove(0);
+    }
+
+    let report_file =
+        std::env::temp_dir().join(format!("termineer-jest-{}.json", uuid::Uuid::new_v4()));
+    let (program, command_args) = runner.command(&tokens, &report_file);
+
+    if !silent_mode {
+        bprintln !(tool: "test",
+            "{FORMAT_BOLD}🧪 Running {}:{FORMAT_RESET} {FORMAT_GRAY}{} {}{FORMAT_RESET}",
+            runner.name(),
+            program,
+            command_args.join(" ")
+        );
+    }
+
+    let output = match run_with_timeout(program, &command_args).await {
+        Ok(output) => output,
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+    };
+
+    let raw = crate::ansi_converter::strip_ansi_sequences(&format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    ));
+    let report = match runner {
+        Runner::Cargo => parse_cargo(&raw),
+        Runner::Pytest => parse_pytest(&raw),
+        Runner::Go => parse_go(&raw),
+        Runner::Jest => {
+            let json = tokio::fs::read_to_string(&report_file)
+                .await
+                .unwrap_or_default();
+            let _ = tokio::fs::remove_file(&report_file).await;
+            parse_jest(&json, &cwd)
+        }
+    };
+
+    let summary = format_report(runner, &report, output.status.success(), &raw);
+    if !silent_mode {
+        let headline = summary.lines().next().unwrap_or("");
+        bprintln !(tool: "test", "{FORMAT_GRAY}{}{FORMAT_RESET}", headline);
+    }
+    // Failing tests are a result, not a failure of the tool
+    ToolResult::success(summary)
+}
+
+/// Run a program, giving up after the test timeout
+pub(crate) async fn run_with_timeout(
+    program: &str,
+    args: &[String],
+) -> Result<std::process::Output, String> {
+    let mut command = Command::new(program);
+    command.args(args).kill_on_drop(true);
+    match tokio::time::timeout(TIMEOUT, command.output()).await {
+        Ok(Ok(output)) => Ok(output),
+        Ok(Err(e)) => Err(format!("Failed to run {program}: {e}")),
+        Err(_) => Err(format!(
+            "{program} did not finish within {} seconds",
+            TIMEOUT.as_secs()
+        )),
+    }
+}
+
+/// Parse the output of `cargo test`
+fn parse_cargo(output: &str) -> TestReport {
+    let mut report = TestReport::default();
+    let mut failed_names = Vec::new();
+    let mut sections: HashMap<String, Vec<&str>> = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for line in output.lines() {
+        if let Some(captures) = CARGO_RESULT.captures(line) {
+            match &captures[2] {
+                "ok" => report.passed += 1,
+                "FAILED" => failed_names.push(captures[1].to_string()),
+                _ => {}
+            }
+            continue;
+        }
+        if let Some(captures) = CARGO_SECTION.captures(line) {
+            current = Some(captures[1].to_string());
+            continue;
+        }
+        if line == "failures:" || line.starts_with("test result:") {
+            current = None;
+            continue;
+        }
+        if let Some(name) = &current {
+            sections.entry(name.clone()).or_default().push(line);
+        }
+    }
+
+    report.failed = failed_names.len();
+    for name in failed_names {
+        let lines = sections.remove(&name).unwrap_or_default();
+        let mut location = None;
+        let mut message = Vec::new();
+        for line in lines {
+            if let Some(captures) = CARGO_PANIC.captures(line) {
+                location = Some(format!("{}:{}", &captures[2], &captures[3]));
+                if let Some(inline) = captures.get(1) {
+                    message.push(inline.as_str());
+                }
+            } else if !line.trim().is_empty() && !line.starts_with("note: run with") {
+                message.push(line);
+            }
+        }
+        report.failures.push(Failure {
+            name,
+            location,
+            message: message.join("\n"),
+        });
+    }
+    report
+}
+
+/// Parse the output of `pytest -rfE --tb=short`
+fn parse_pytest(output: &str) -> TestReport {
+    let mut report = TestReport::default();
+
+    // Location of the last traceback entry of each section
+    let mut locations: HashMap<String, String> = HashMap::new();
+    let mut section: Option<String> = None;
+    for line in output.lines() {
+        if let Some(captures) = PYTEST_SECTION.captures(line) {
+            section = Some(captures[1].to_string());
+        } else if let (Some(name), Some(captures)) = (&section, PYTEST_LOCATION.captures(line)) {
+            locations.insert(name.clone(), format!("{}:{}", &captures[1], &captures[2]));
+        }
+    }
+
+    for line in output.lines() {
+        if let Some(captures) = PYTEST_SUMMARY.captures(line) {
+            let node = captures[2].to_string();
+            let test_name = node.rsplit("::").next().unwrap_or(&node).to_string();
+            let location = locations
+                .iter()
+                .find(|(section, _)| section.ends_with(&test_name))
+                .map(|(_, location)| location.clone())
+                .or_else(|| node.split("::").next().map(str::to_string));
+            report.failures.push(Failure {
+                name: node,
+                location,
+                message: captures.get(3).map_or("", |m| m.as_str()).to_string(),
+            });
+        }
+    }
+
+    // The final line counts all tests, e.g. "2 failed, 10 passed in 0.31s"
+    if let Some(last) = output
+        .lines()
+        .rev()
+        .find(|line| PYTEST_COUNT.is_match(line))
+    {
+        for captures in PYTEST_COUNT.captures_iter(last) {
+            let count: usize = captures[1].parse().unwrap_or(0);
+            match &captures[2] {
+                "passed" => report.passed += count,
+                _ => report.failed += count,
+            }
+        }
+    }
+    report.failed = report.failed.max(report.failures.len());
+    report
+}
+
+/// Parse the event stream of `go test -json`
+fn parse_go(output: &str) -> TestReport {
+    let mut report = TestReport::default();
+    let mut outputs: HashMap<(String, String), Vec<String>> = HashMap::new();
+    let mut failed_packages = Vec::new();
+
+    for line in output.lines() {
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        let field = |name: &str| event.get(name).and_then(|v| v.as_str()).unwrap_or("");
+        let key = (field("Package").to_string(), field("Test").to_string());
+        match field("Action") {
+            "output" => outputs
+                .entry(key)
+                .or_default()
+                .push(field("Output").trim_end().to_string()),
+            "pass" if !key.1.is_empty() => report.passed += 1,
+            "fail" if key.1.is_empty() => failed_packages.push(key.0),
+            "fail" => {
+                let lines = outputs.remove(&key).unwrap_or_default();
+                report
+                    .failures
+                    .push(go_failure(format!("{}.{}", key.0, key.1), &lines));
+            }
+            _ => {}
+        }
+    }
+
+    // Packages that failed without a failing test did not build
+    for package in failed_packages {
+        let prefix = format!("{package}.");
+        if report.failures.iter().any(|f| f.name.starts_with(&prefix)) {
+            continue;
+        }
+        let lines = outputs
+            .remove(&(package.clone(), String::new()))
+            .unwrap_or_default();
+        report.failures.push(go_failure(package, &lines));
+    }
+
+    report.failed = report.failures.len();
+    report
+}
+
+/// Failure from the output lines of a Go test or package
+fn go_failure(name: String, lines: &[String]) -> Failure {
+    let location = lines.iter().find_map(|line| {
+        GO_LOCATION
+            .captures(line)
+            .map(|captures| format!("{}:{}", &captures[1], &captures[2]))
+    });
+    let message = lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| {
+            !line.is_empty()
+                && ![
+                    "=== RUN",
+                    "=== PAUSE",
+                    "=== CONT",
+                    "--- FAIL",
+                    "FAIL",
+                    "ok ",
+                ]
+                .iter()
+                .any(|prefix| line.starts_with(prefix))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Failure {
+        name,
+        location,
+        message,
+    }
+}
+
+/// Parse the JSON report of `jest --json`
+fn parse_jest(json: &str, cwd: &Path) -> TestReport {
+    let mut report = TestReport::default();
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(json) else {
+        return report;
+    };
+    let count = |name: &str| value.get(name).and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+    report.passed = count("numPassedTests");
+    report.failed = count("numFailedTests");
+
+    let suites = value.get("testResults").and_then(|v| v.as_array());
+    for suite in suites.into_iter().flatten() {
+        let file = suite.get("name").and_then(|v| v.as_str()).unwrap_or("");
+        let file = Path::new(file)
+            .strip_prefix(cwd)
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|_| file.to_string());
+
+        let mut suite_failed = false;
+        let assertions = suite.get("assertionResults").and_then(|v| v.as_array());
+        for assertion in assertions.into_iter().flatten() {
+            if assertion.get("status").and_then(|v| v.as_str()) != Some("failed") {
+                continue;
+            }
+            suite_failed = true;
+            let message = assertion
+                .get("failureMessages")
+                .and_then(|v| v.as_array())
+                .map(|messages| {
+                    messages
+                        .iter()
+                        .filter_map(|m| m.as_str())
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .unwrap_or_default();
+            report.failures.push(Failure {
+                name: assertion
+                    .get("fullName")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+                location: js_location(&message, &file),
+                message: crate::ansi_converter::strip_ansi_sequences(&message)
+                    .lines()
+                    .filter(|line| !line.trim_start().starts_with("at "))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            });
+        }
+
+        // Suites that failed to run (syntax errors, missing modules) have no assertions
+        let suite_status = suite.get("status").and_then(|v| v.as_str());
+        if suite_status == Some("failed") && !suite_failed {
+            let message = suite.get("message").and_then(|v| v.as_str()).unwrap_or("");
+            report.failures.push(Failure {
+                name: file.clone(),
+                location: js_location(message, &file),
+                message: crate::ansi_converter::strip_ansi_sequences(message),
+            });
+            report.failed += 1;
+        }
+    }
+    report
+}
+
+/// First stack frame in the test file
+fn js_location(message: &str, file: &str) -> Option<String> {
+    JS_LOCATION.captures_iter(message).find_map(|captures| {
+        captures[1]
+            .ends_with(file)
+            .then(|| format!("{}:{}", file, &captures[2]))
+    })
+}
+
+/// Compact report for the model
+fn format_report(runner: Runner, report: &TestReport, success: bool, raw: &str) -> String {
+    if success && report.failures.is_empty() {
+        return format!("{}: all {} test(s) passed", runner.name(), report.passed);
+    }
+
+    let mut text = format!(
+        "{}: {} passed, {} failed",
+        runner.name(),
+        report.passed,
+        report.failed
+    );
+    if report.failures.is_empty() {
+        let lines: Vec<&str> = raw.lines().filter(|line| !line.trim().is_empty()).collect();
+        let tail = &lines[lines.len().saturating_sub(RAW_TAIL_LINES)..];
+        text.push_str(&format!(
+            "\nThe run failed but no test failures were recognized. Last lines of output:\n{}",
+            tail.join("\n")
+        ));
+        return text;
+    }
+
+    for (i, failure) in report.failures.iter().take(MAX_FAILURES).enumerate() {
+        text.push_str(&format!("\n\n{}. {}", i + 1, failure.name));
+        if let Some(location) = &failure.location {
+            text.push_str(&format!(" ({location})"));
+        }
+        let lines: Vec<&str> = failure.message.lines().collect();
+        for line in lines.iter().take(MAX_MESSAGE_LINES) {
+            text.push_str(&format!("\n   {line}"));
+        }
+        if lines.len() > MAX_MESSAGE_LINES {
+            text.push_str(&format!(
+                "\n   ... ({} more line(s))",
+                lines.len() - MAX_MESSAGE_LINES
+            ));
+        }
+    }
+    if report.failures.len() > MAX_FAILURES {
+        text.push_str(&format!(
+            "\n\n... and {} more failure(s)",
+            report.failures.len() - MAX_FAILURES
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo() {
+        let output = "\
+running 2 tests
+test parser::tests::empty ... ok
+test parser::tests::dates ... FAILED
+
+failures:
+
+---- parser::tests::dates stdout ----
+thread 'parser::tests::dates' panicked at src/parser.rs:42:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    parser::tests::dates
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out
+";
+        let report = parse_cargo(output);
+        assert_eq!(report.passed, 1);
+        assert_eq!(report.failed, 1);
+        assert_eq!(
+            report.failures,
+            vec![Failure {
+                name: "parser::tests::dates".to_string(),
+                location: Some("src/parser.rs:42".to_string()),
+                message: "assertion `left == right` failed\n  left: 1\n right: 2".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_pytest() {
+        let output = "\
+.F.                                                                      [100%]
+=================================== FAILURES ===================================
+_________________________________ test_dates ___________________________________
+tests/test_parse.py:12: in test_dates
+    assert parse(\"2024\") == 2025
+E   AssertionError: assert 2024 == 2025
+=========================== short test summary info ============================
+FAILED tests/test_parse.py::test_dates - AssertionError: assert 2024 == 2025
+1 failed, 2 passed in 0.05s
+";
+        let report = parse_pytest(output);
+        assert_eq!(report.passed, 2);
+        assert_eq!(report.failed, 1);
+        assert_eq!(
+            report.failures,
+            vec![Failure {
+                name: "tests/test_parse.py::test_dates".to_string(),
+                location: Some("tests/test_parse.py:12".to_string()),
+                message: "AssertionError: assert 2024 == 2025".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_go() {
+        let output = r#"{"Action":"run","Package":"example/parse","Test":"TestDates"}
+{"Action":"output","Package":"example/parse","Test":"TestDates","Output":"=== RUN   TestDates\n"}
+{"Action":"output","Package":"example/parse","Test":"TestDates","Output":"    parse_test.go:12: got 2024, want 2025\n"}
+{"Action":"output","Package":"example/parse","Test":"TestDates","Output":"--- FAIL: TestDates (0.00s)\n"}
+{"Action":"fail","Package":"example/parse","Test":"TestDates"}
+{"Action":"pass","Package":"example/parse","Test":"TestEmpty"}
+{"Action":"fail","Package":"example/parse"}
+"#;
+        let report = parse_go(output);
+        assert_eq!(report.passed, 1);
+        assert_eq!(
+            report.failures,
+            vec![Failure {
+                name: "example/parse.TestDates".to_string(),
+                location: Some("parse_test.go:12".to_string()),
+                message: "parse_test.go:12: got 2024, want 2025".to_string(),
+            }]
+        );
+    }
+}
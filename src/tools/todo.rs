@@ -0,0 +1,235 @@
+//! Plan tool for tracking a checklist of steps
+//!
+//! The agent registers the steps of its task and marks them as it works
+//! through them. The checklist is shown in a panel of the interactive UI and a
+//! compact form of it is appended to the system prompt on every turn, so the
+//! model keeps track of where it is in long tasks.
+//!
+//! Usage:
+//! - `plan set` with one step per line in the body (replaces the checklist)
+//! - `plan add` with more steps in the body
+//! - `plan start N` / `plan done N` to update step N (1-based; several allowed)
+//! - `plan show`
+//! - `plan clear`
+
+use crate::agent::AgentId;
+use crate::constants::{FORMAT_BOLD, FORMAT_RESET};
+use crate::tools::ToolResult;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Progress of a single step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepStatus {
+    Pending,
+    InProgress,
+    Done,
+}
+
+impl StepStatus {
+    /// Marker used in the tool output and the system prompt
+    fn marker(self) -> &'static str {
+        match self {
+            StepStatus::Pending => "[ ]",
+            StepStatus::InProgress => "[>]",
+            StepStatus::Done => "[x]",
+        }
+    }
+}
+
+/// A step of the checklist
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub text: String,
+    pub status: StepStatus,
+}
+
+lazy_static! {
+    /// Checklist of each agent
+    static ref CHECKLISTS: Mutex<HashMap<AgentId, Vec<Step>>> = Mutex::new(HashMap::new());
+}
+
+/// Agent used for checklists of tool executors without an agent
+const NO_AGENT: AgentId = AgentId(0);
+
+/// Execute the plan tool
+pub fn execute_plan(
+    args: &str,
+    body: &str,
+    silent_mode: bool,
+    agent_id: Option<AgentId>,
+) -> ToolResult {
+    let agent_id = agent_id.unwrap_or(NO_AGENT);
+    let mut tokens = args.split_whitespace();
+    let operation = tokens.next().unwrap_or("show");
+    let numbers: Vec<&str> = tokens.collect();
+
+    let result = {
+        let mut checklists = CHECKLISTS.lock().unwrap();
+        let steps = checklists.entry(agent_id).or_default();
+        let outcome = match operation {
+            "set" => {
+                *steps = parse_steps(body);
+                Ok(())
+            }
+            "add" => {
+                steps.extend(parse_steps(body));
+                Ok(())
+            }
+            "start" => mark(steps, &numbers, StepStatus::InProgress),
+            "done" => mark(steps, &numbers, StepStatus::Done),
+            "show" => Ok(()),
+            "clear" => {
+                steps.clear();
+                Ok(())
+            }
+            other => Err(format!(
+                "Unknown plan operation '{other}'. Use set, add, start, done, show or clear"
+            )),
+        };
+        outcome.map(|_| format_steps(steps))
+    };
+
+    match result {
+        Ok(output) => {
+            if !silent_mode {
+                bprintln !(tool: "plan", "{FORMAT_BOLD}🗒️ Plan:{FORMAT_RESET}\n{}", output);
+            }
+            ToolResult::success(output)
+        }
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            ToolResult::error(error_msg)
+        }
+    }
+}
+
+/// Parse one step per line, dropping list and checkbox markers
+fn parse_steps(body: &str) -> Vec<Step> {
+    body.lines()
+        .filter_map(|line| {
+            let mut text = line.trim();
+            text = text
+                .strip_prefix("- ")
+                .or_else(|| text.strip_prefix("* "))
+                .unwrap_or(text);
+            // Numbered items such as "1." or "2)"
+            let digits = text.chars().take_while(char::is_ascii_digit).count();
+            if digits > 0 && matches!(text[digits..].chars().next(), Some('.') | Some(')')) {
+                text = text[digits + 1..].trim_start();
+            }
+            let mut status = StepStatus::Pending;
+            for (marker, marked) in [
+                ("[ ]", StepStatus::Pending),
+                ("[>]", StepStatus::InProgress),
+                ("[x]", StepStatus::Done),
+                ("[X]", StepStatus::Done),
+            ] {
+                if let Some(rest) = text.strip_prefix(marker) {
+                    text = rest.trim_start();
+                    status = marked;
+                }
+            }
+            (!text.is_empty()).then(|| Step {
+                text: text.to_string(),
+                status,
+            })
+        })
+        .collect()
+}
+
+/// Set the status of the given 1-based step numbers
+fn mark(steps: &mut [Step], numbers: &[&str], status: StepStatus) -> Result<(), String> {
+    if numbers.is_empty() {
+        return Err("A step number is required, e.g. `plan done 2`".to_string());
+    }
+    for number in numbers {
+        let index = number
+            .parse::<usize>()
+            .ok()
+            .filter(|n| (1..=steps.len()).contains(n))
+            .ok_or_else(|| {
+                format!(
+                    "Invalid step number '{number}'; the plan has {} step(s)",
+                    steps.len()
+                )
+            })?;
+        steps[index - 1].status = status;
+    }
+    Ok(())
+}
+
+/// Numbered checklist with status markers
+fn format_steps(steps: &[Step]) -> String {
+    if steps.is_empty() {
+        return "The plan is empty".to_string();
+    }
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| format!("{} {}. {}", step.status.marker(), i + 1, step.text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Current checklist of an agent
+pub fn steps(agent_id: AgentId) -> Vec<Step> {
+    CHECKLISTS
+        .lock()
+        .unwrap()
+        .get(&agent_id)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Checklist section appended to the system prompt, if the agent has a plan
+pub fn format_for_prompt(agent_id: AgentId) -> Option<String> {
+    let steps = steps(agent_id);
+    if steps.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "# Current plan\n{}\nKeep it up to date with the plan tool as you work.",
+        format_steps(&steps)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_steps() {
+        let steps = parse_steps("- [x] Reproduce\n2. Fix parser\n\n* [>] Run tests\n");
+        assert_eq!(
+            steps,
+            vec![
+                Step {
+                    text: "Reproduce".to_string(),
+                    status: StepStatus::Done
+                },
+                Step {
+                    text: "Fix parser".to_string(),
+                    status: StepStatus::Pending
+                },
+                Step {
+                    text: "Run tests".to_string(),
+                    status: StepStatus::InProgress
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mark_and_format() {
+        let mut steps = parse_steps("one\ntwo\nthree");
+        mark(&mut steps, &["1", "2"], StepStatus::Done).unwrap();
+        mark(&mut steps, &["3"], StepStatus::InProgress).unwrap();
+        assert_eq!(format_steps(&steps), "[x] 1. one\n[x] 2. two\n[>] 3. three");
+        assert!(mark(&mut steps, &["4"], StepStatus::Done).is_err());
+        assert!(mark(&mut steps, &[], StepStatus::Done).is_err());
+    }
+}
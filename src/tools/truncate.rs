@@ -0,0 +1,180 @@
+//! Content-aware truncation for long shell output
+//!
+//! Plain start/end truncation often cuts out the single failing test or compiler
+//! error in the middle of a long log. This truncation keeps the start and end of
+//! the output like before, and additionally preserves lines in the middle that
+//! match error/failure/warning patterns, with a little surrounding context.
+
+use crate::constants::{MAX_TOOL_OUTPUT_LENGTH, PRESERVED_END_LENGTH, PRESERVED_START_LENGTH};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::sync::RwLock;
+
+/// Patterns for lines worth keeping when output is truncated
+const DEFAULT_PRIORITY_PATTERNS: &[&str] = &[
+    r"(?i)\berror\b",
+    r"(?i)\bfail(ed|ure|ures|ing)?\b",
+    r"(?i)\bwarning\b",
+    r"(?i)\bpanicked\b",
+    r"(?i)\bexception\b",
+    r"(?i)^traceback",
+    r"(?i)assert",
+    r"^\s*-->\s",
+];
+
+/// Lines of context kept around each matching line
+const CONTEXT_LINES: usize = 2;
+
+lazy_static! {
+    static ref PRIORITY_PATTERNS: RwLock<Vec<Regex>> = RwLock::new(
+        DEFAULT_PRIORITY_PATTERNS
+            .iter()
+            .map(|pattern| Regex::new(pattern).expect("valid default truncation pattern"))
+            .collect()
+    );
+}
+
+/// Add user-provided patterns to the default priority patterns
+pub fn configure_priority_patterns(extra: &[String]) -> Result<(), String> {
+    let mut compiled = Vec::with_capacity(extra.len());
+    for pattern in extra {
+        compiled.push(
+            Regex::new(pattern)
+                .map_err(|e| format!("Invalid truncation pattern '{pattern}': {e}"))?,
+        );
+    }
+    PRIORITY_PATTERNS.write().unwrap().extend(compiled);
+    Ok(())
+}
+
+/// Truncate shell output, preserving the start, the end and important lines in between
+pub fn truncate_shell_output(content: &str) -> String {
+    let patterns = PRIORITY_PATTERNS.read().unwrap();
+    truncate_with_patterns(
+        content,
+        &patterns,
+        MAX_TOOL_OUTPUT_LENGTH,
+        PRESERVED_START_LENGTH,
+        PRESERVED_END_LENGTH,
+    )
+}
+
+/// Line-based truncation keeping head, tail and lines matching any pattern
+pub fn truncate_with_patterns(
+    content: &str,
+    patterns: &[Regex],
+    max_length: usize,
+    start_length: usize,
+    end_length: usize,
+) -> String {
+    if content.len() <= max_length {
+        return content.to_string();
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut keep = vec![false; lines.len()];
+
+    // Keep whole lines from the start up to the start budget
+    let mut used = 0;
+    let mut head_end = 0;
+    while head_end < lines.len() && used + lines[head_end].len() < start_length {
+        used += lines[head_end].len() + 1;
+        keep[head_end] = true;
+        head_end += 1;
+    }
+
+    // Keep whole lines from the end up to the end budget
+    let mut tail_used = 0;
+    let mut tail_start = lines.len();
+    while tail_start > head_end && tail_used + lines[tail_start - 1].len() < end_length {
+        tail_start -= 1;
+        tail_used += lines[tail_start].len() + 1;
+        keep[tail_start] = true;
+    }
+    used += tail_used;
+
+    // Fill the remaining budget with matching lines (and context) from the middle
+    let budget = max_length.saturating_sub(used);
+    let mut middle_used = 0;
+    for index in head_end..tail_start {
+        if !patterns
+            .iter()
+            .any(|pattern| pattern.is_match(lines[index]))
+        {
+            continue;
+        }
+
+        let from = index.saturating_sub(CONTEXT_LINES).max(head_end);
+        let to = (index + CONTEXT_LINES + 1).min(tail_start);
+        let cost: usize = (from..to)
+            .filter(|i| !keep[*i])
+            .map(|i| lines[i].len() + 1)
+            .sum();
+        if middle_used + cost > budget {
+            break;
+        }
+        middle_used += cost;
+        for flag in &mut keep[from..to] {
+            *flag = true;
+        }
+    }
+
+    // Assemble the kept lines, marking each omitted run
+    let mut result = String::with_capacity(max_length);
+    let mut omitted = 0;
+    for (line, kept) in lines.iter().zip(&keep) {
+        if *kept {
+            if omitted > 0 {
+                result.push_str(&format!("[... {omitted} lines omitted ...]\n"));
+                omitted = 0;
+            }
+            result.push_str(line);
+            result.push('\n');
+        } else {
+            omitted += 1;
+        }
+    }
+    if omitted > 0 {
+        result.push_str(&format!("[... {omitted} lines omitted ...]\n"));
+    }
+
+    // Single huge lines can still exceed the limit; fall back to byte truncation
+    if result.len() > max_length {
+        return super::truncate_utf8_content(&result, Some(max_length), None, None, None);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_failures_from_the_middle() {
+        let mut lines: Vec<String> = (0..1000).map(|i| format!("test case_{i} ... ok")).collect();
+        lines[500] = "test case_500 ... FAILED".to_string();
+        let content = lines.join("\n");
+
+        let patterns: Vec<Regex> = DEFAULT_PRIORITY_PATTERNS
+            .iter()
+            .map(|p| Regex::new(p).unwrap())
+            .collect();
+        let truncated = truncate_with_patterns(&content, &patterns, 2000, 500, 500);
+
+        assert!(truncated.len() <= 2000);
+        assert!(truncated.starts_with("test case_0 ... ok"));
+        assert!(truncated.contains("test case_500 ... FAILED"));
+        assert!(truncated.contains("test case_498 ... ok"));
+        assert!(truncated.contains("test case_999 ... ok"));
+        assert!(truncated.contains("lines omitted"));
+    }
+
+    #[test]
+    fn test_short_content_unchanged() {
+        assert_eq!(
+            truncate_with_patterns("error: short", &[], 100, 10, 10),
+            "error: short"
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Computer use: act on a window and see the result in one step
+//!
+//! The `computer` tool joins the UI tools into the loop of computer use: the
+//! agent looks at a window, sends one input and gets a screenshot of the window
+//! taken after the input, so every action is verified before the next one.
+//! - `computer screenshot WINDOW_ID` captures a window
+//! - `computer click|type|key|sequence ...` takes the arguments of the input
+//!   tool, sends the input and returns the window as it looks afterwards
+//!
+//! Guardrails limit what an agent can do on its own: each agent may send at
+//! most `--computer-max-actions` inputs (a sequence counts each of its clicks,
+//! texts and keys), and `--computer-app APP` restricts input and screenshots
+//! to the windows of the listed applications.
+
+use crate::agent::AgentId;
+use crate::llm::{Content, ImageSource};
+use crate::tools::ui::input::{self, InputAction, InputCommand};
+use crate::tools::ToolResult;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+/// Inputs an agent may send when no limit is configured
+pub const DEFAULT_MAX_ACTIONS: usize = 50;
+
+/// Time given to the application to react before the screenshot
+const SETTLE_TIME: Duration = Duration::from_millis(700);
+
+/// Actions that send input, with the syntax of the input tool
+const INPUT_ACTIONS: &[&str] = &["click", "type", "key", "sequence"];
+
+/// Limits of the computer tool
+#[derive(Debug, Clone)]
+pub struct ComputerPolicy {
+    /// Inputs each agent may send
+    pub max_actions: usize,
+    /// Applications whose windows may be used (empty = all)
+    pub allowed_apps: Vec<String>,
+}
+
+impl Default for ComputerPolicy {
+    fn default() -> Self {
+        Self {
+            max_actions: DEFAULT_MAX_ACTIONS,
+            allowed_apps: Vec::new(),
+        }
+    }
+}
+
+lazy_static! {
+    static ref POLICY: RwLock<ComputerPolicy> = RwLock::new(ComputerPolicy::default());
+    /// Inputs sent by each agent so far
+    static ref ACTIONS: Mutex<HashMap<Option<AgentId>, usize>> = Mutex::new(HashMap::new());
+}
+
+/// Set the limits of the computer tool
+pub fn configure(policy: ComputerPolicy) {
+    *POLICY.write().unwrap() = policy;
+}
+
+/// Execute the computer tool
+pub async fn execute_computer(
+    args: &str,
+    body: &str,
+    silent_mode: bool,
+    agent_id: Option<AgentId>,
+) -> ToolResult {
+    let policy = POLICY.read().unwrap().clone();
+    let mut words = args.split_whitespace();
+    let result = match words.next().unwrap_or_default() {
+        "screenshot" => {
+            let window_id = words.next().unwrap_or_default();
+            if !silent_mode {
+                crate::bprintln!("🖥️ Looking at window '{}'...", window_id);
+            }
+            check_window(window_id, &policy.allowed_apps).and_then(|_| observe(window_id))
+        }
+        action if INPUT_ACTIONS.contains(&action) => {
+            act(args, body, silent_mode, agent_id, &policy).await
+        }
+        "" => Err("An action is required: screenshot, click, type, key or sequence".to_string()),
+        other => Err(format!(
+            "Unknown computer action '{other}'. Use screenshot, click, type, key or sequence"
+        )),
+    };
+
+    match result {
+        Ok(content) => ToolResult::success_with_content(content),
+        Err(error_message) => {
+            if !silent_mode {
+                crate::bprintln!(error: "{}", error_message);
+            }
+            ToolResult::error(error_message)
+        }
+    }
+}
+
+/// Send an input within the guardrails, then capture the window
+async fn act(
+    args: &str,
+    body: &str,
+    silent_mode: bool,
+    agent_id: Option<AgentId>,
+    policy: &ComputerPolicy,
+) -> Result<Vec<Content>, String> {
+    let command = input::parse_command(args, body);
+    let window_id = window_id(&command).to_string();
+    check_window(&window_id, &policy.allowed_apps)?;
+    let left = reserve(agent_id, action_count(&command), policy.max_actions)?;
+
+    let result = crate::tools::ui::macos::input::execute_macos_input(command, silent_mode).await;
+    let report = result
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            Content::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if !result.success {
+        return Err(report);
+    }
+
+    tokio::time::sleep(SETTLE_TIME).await;
+    let mut content = vec![Content::Text {
+        text: format!("{report}\nWindow '{window_id}' after the input ({left} actions left):"),
+    }];
+    content.extend(observe(&window_id)?);
+    Ok(content)
+}
+
+/// Screenshot of a window
+fn observe(window_id: &str) -> Result<Vec<Content>, String> {
+    let image = tokio::task::block_in_place(|| {
+        crate::tools::ui::macos::screenshot::capture_window(window_id).map_err(|e| e.to_string())
+    })
+    .map_err(|e| format!("Failed to capture window '{window_id}': {e}"))?;
+    Ok(vec![Content::Image {
+        source: ImageSource::Base64 {
+            media_type: "image/jpeg".to_string(),
+            data: image,
+        },
+    }])
+}
+
+fn window_id(command: &InputCommand) -> &str {
+    match command {
+        InputCommand::Click { window_id, .. }
+        | InputCommand::Type { window_id, .. }
+        | InputCommand::KeyPress { window_id, .. }
+        | InputCommand::Sequence { window_id, .. } => window_id,
+    }
+}
+
+/// Inputs sent by a command; waits are free
+fn action_count(command: &InputCommand) -> usize {
+    match command {
+        InputCommand::Sequence { actions, .. } => actions
+            .iter()
+            .filter(|action| !matches!(action, InputAction::Wait { .. }))
+            .count(),
+        _ => 1,
+    }
+}
+
+/// Check that a window may be used: it must be given, and belong to one of
+/// the allowed applications if there are any
+fn check_window(window_id: &str, allowed_apps: &[String]) -> Result<(), String> {
+    if window_id.is_empty() {
+        return Err("A window ID is required; take it from `screendump`".to_string());
+    }
+    if allowed_apps.is_empty() {
+        return Ok(());
+    }
+
+    let apps = allowed_apps.join(", ");
+    // Window indexes do not tell the application
+    if window_id.parse::<usize>().is_ok() {
+        return Err(format!(
+            "Only windows of {apps} may be used; give their App:Index IDs from `screendump`"
+        ));
+    }
+    let app = window_id.split(':').next().unwrap_or_default();
+    if allowed_apps
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(app))
+    {
+        Ok(())
+    } else {
+        Err(format!(
+            "Window '{window_id}' belongs to {app}, but only windows of {apps} may be used"
+        ))
+    }
+}
+
+/// Count `count` inputs against an agent's budget, returning the inputs left
+fn reserve(agent_id: Option<AgentId>, count: usize, max: usize) -> Result<usize, String> {
+    let mut actions = ACTIONS.lock().unwrap();
+    let used = actions.entry(agent_id).or_default();
+    if *used + count > max {
+        return Err(format!(
+            "The action budget does not allow {count} more inputs ({used} of {max} sent). \
+             Stop here and report what was done and what remains."
+        ));
+    }
+    *used += count;
+    Ok(max - *used)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guardrails() {
+        let allowed = vec!["Safari".to_string(), "TextEdit".to_string()];
+        assert!(check_window("safari:1", &allowed).is_ok());
+        assert!(check_window("TextEdit", &allowed).is_ok());
+        assert!(check_window("Terminal:1", &allowed).is_err());
+        assert!(check_window("3", &allowed).is_err());
+        assert!(check_window("3", &[]).is_ok());
+        assert!(check_window("", &[]).is_err());
+
+        let agent = Some(AgentId(9_000));
+        assert_eq!(reserve(agent, 3, 5), Ok(2));
+        assert!(reserve(agent, 3, 5).is_err());
+        assert_eq!(reserve(agent, 2, 5), Ok(0));
+        assert_eq!(reserve(Some(AgentId(9_001)), 1, 5), Ok(4));
+
+        let sequence = input::parse_command("sequence Safari:1", "click 10 20\nwait 100\n");
+        assert_eq!(window_id(&sequence), "Safari:1");
+        assert_eq!(action_count(&sequence), 1);
+    }
+}
@@ -21,6 +21,8 @@ pub enum InputAction {
         y: i32,
         button: MouseButtonType,
         double: bool,
+        /// Whether the coordinates are in points rather than screenshot pixels
+        points: bool,
     },
     /// Type text
     Type { text: String },
@@ -40,6 +42,8 @@ pub enum InputCommand {
         window_id: String,
         button: MouseButtonType,
         double: bool,
+        /// Whether the coordinates are in points rather than screenshot pixels
+        points: bool,
     },
     /// Type text into a window
     Type { text: String, window_id: String },
@@ -71,7 +75,7 @@ pub fn parse_command(args: &str, body: &str) -> InputCommand {
     match parts[0].to_lowercase().as_str() {
         "click" => {
             // Format: input click x y [window_id] [options]
-            // Options: --right --middle --double
+            // Options: --right --middle --double --points
             if parts.len() < 3 {
                 return InputCommand::Click {
                     x: 0,
@@ -79,6 +83,7 @@ pub fn parse_command(args: &str, body: &str) -> InputCommand {
                     window_id: String::new(),
                     button: MouseButtonType::Left,
                     double: false,
+                    points: false,
                 };
             }
 
@@ -90,6 +95,7 @@ pub fn parse_command(args: &str, body: &str) -> InputCommand {
             let mut window_id = String::new();
             let mut button = MouseButtonType::Left;
             let mut double = false;
+            let mut points = false;
 
             // Parse remaining arguments
             for i in 3..parts.len() {
@@ -97,6 +103,7 @@ pub fn parse_command(args: &str, body: &str) -> InputCommand {
                     "--right" => button = MouseButtonType::Right,
                     "--middle" => button = MouseButtonType::Middle,
                     "--double" => double = true,
+                    "--points" => points = true,
                     id => {
                         // Assume it's the window ID if not an option
                         if !id.starts_with("--") {
@@ -112,6 +119,7 @@ pub fn parse_command(args: &str, body: &str) -> InputCommand {
                 window_id,
                 button,
                 double,
+                points,
             }
         }
         "type" => {
@@ -227,12 +235,14 @@ pub fn parse_action_sequence(body: &str) -> Vec<InputAction> {
                 let y = parts[2].parse::<i32>().unwrap_or(0);
                 let mut button = MouseButtonType::Left;
                 let mut double = false;
+                let mut points = false;
 
                 for i in 3..parts.len() {
                     match parts[i] {
                         "--right" => button = MouseButtonType::Right,
                         "--middle" => button = MouseButtonType::Middle,
                         "--double" => double = true,
+                        "--points" => points = true,
                         _ => {}
                     }
                 }
@@ -242,6 +252,7 @@ pub fn parse_action_sequence(body: &str) -> Vec<InputAction> {
                     y,
                     button,
                     double,
+                    points,
                 });
             }
             "type" => {
@@ -3,7 +3,9 @@
 //! This module provides macOS-specific implementation for sending
 //! mouse and keyboard inputs using macOS APIs.
 
+use super::permissions::{self, Permission};
 use crate::tools::ui::input::{InputAction, InputCommand, MouseButtonType};
+use crate::tools::ui::screen_access::{self, Rect};
 use crate::tools::ui::screendump;
 use crate::tools::ToolResult;
 use enigo::{Button, Coordinate, Direction, Enigo, Key, Keyboard, Mouse};
@@ -20,6 +22,7 @@ pub async fn execute_macos_input(command: InputCommand, silent_mode: bool) -> To
                 window_id,
                 button,
                 double,
+                ..
             } => {
                 let button_str = match button {
                     MouseButtonType::Left => "left",
@@ -67,6 +70,13 @@ pub async fn execute_macos_input(command: InputCommand, silent_mode: bool) -> To
         }
     }
 
+    if let Err(guidance) = permissions::require(&[Permission::Accessibility]) {
+        if !silent_mode {
+            crate::bprintln!(error: "{}", guidance);
+        }
+        return ToolResult::error(guidance);
+    }
+
     match command {
         InputCommand::Click {
             x,
@@ -74,7 +84,8 @@ pub async fn execute_macos_input(command: InputCommand, silent_mode: bool) -> To
             window_id,
             button,
             double,
-        } => match send_mouse_click(x, y, &window_id, button, double).await {
+            points,
+        } => match send_mouse_click(x, y, &window_id, button, double, points).await {
             Ok(msg) => ToolResult::success(msg),
             Err(err) => ToolResult::error(err),
         },
@@ -152,7 +163,7 @@ fn parse_modifier(modifier: &str) -> Option<Key> {
 }
 
 /// Get the window position and size from its ID
-async fn get_window_position(window_id: &str) -> Result<(i32, i32), String> {
+async fn get_window_bounds(window_id: &str) -> Result<Rect, String> {
     crate::bprintln!(dev: "💻 INPUT: Getting position for window '{}'", window_id);
 
     // Get window information from screendump module
@@ -168,9 +179,10 @@ async fn get_window_position(window_id: &str) -> Result<(i32, i32), String> {
         }
     }
 
-    let (_, _, x, y, _, _) = result.map_err(|e| format!("Failed to get window position: {e}"))?;
+    let (_, _, x, y, width, height) =
+        result.map_err(|e| format!("Failed to get window position: {e}"))?;
 
-    Ok((x, y))
+    Ok(Rect::new(x, y, width, height))
 }
 
 /// Activate a window by its ID
@@ -231,19 +243,29 @@ async fn send_mouse_click(
     window_id: &str,
     button: MouseButtonType,
     double: bool,
+    points: bool,
 ) -> Result<String, String> {
     crate::bprintln!(dev: "💻 INPUT: Sending mouse click: x={}, y={}, window='{}', button={:?}, double={}", 
               x, y, window_id, button, double);
 
+    // Coordinates read off a screenshot of the window are in its pixels, which
+    // differ from points on Retina displays and when the screenshot was scaled down
+    let scale = screen_access::window_scale(window_id).filter(|scale| !points && *scale != 1.0);
+    let (point_x, point_y) = match scale {
+        Some(scale) => screen_access::pixels_to_points(x, y, scale),
+        None => (x, y),
+    };
+
     // First activate the window
     activate_window(window_id).await?;
 
     // Get the window position
-    let (win_x, win_y) = get_window_position(window_id).await?;
+    let window = get_window_bounds(window_id).await?;
 
-    // Calculate absolute screen coordinates
-    let abs_x = win_x + x;
-    let abs_y = win_y + y;
+    // Calculate global coordinates, which are negative on displays left of or
+    // above the primary one
+    let displays = screen_access::displays()?;
+    let (abs_x, abs_y) = screen_access::window_to_global(&displays, &window, point_x, point_y)?;
     crate::bprintln!(dev: "💻 INPUT: Calculated absolute coordinates: ({}, {})", abs_x, abs_y);
 
     crate::bprintln!(dev: "💻 INPUT: Executing mouse click with Enigo (blocking)");
@@ -280,6 +302,11 @@ async fn send_mouse_click(
 
     result?;
 
+    if scale.is_some() {
+        return Ok(format!(
+            "Clicked at screenshot coordinates ({x}, {y}), ({point_x}, {point_y}) in points, in window '{window_id}'"
+        ));
+    }
     Ok(format!(
         "Clicked at coordinates ({x}, {y}) in window '{window_id}'"
     ))
@@ -400,7 +427,8 @@ async fn execute_action_sequence(actions: &[InputAction], window_id: &str) -> To
                 y,
                 button,
                 double,
-            } => send_mouse_click(*x, *y, window_id, *button, *double).await,
+                points,
+            } => send_mouse_click(*x, *y, window_id, *button, *double, *points).await,
             InputAction::Type { text } => send_keyboard_text(text, window_id).await,
             InputAction::KeyPress { key, modifiers } => {
                 send_keyboard_shortcut(key, modifiers, window_id).await
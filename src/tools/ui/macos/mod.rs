@@ -4,8 +4,11 @@
 //! - screenshot: Capture screenshots using macOS APIs
 //! - screendump: Extract UI structure using macOS Accessibility APIs
 //! - input: Send mouse and keyboard inputs using macOS APIs
+//!
+//! `permissions` checks the privacy permissions these need.
 
 pub mod input;
+pub mod permissions;
 pub mod screendump;
 pub mod screenshot;
 pub mod xml_helpers;
@@ -0,0 +1,100 @@
+//! macOS privacy permissions needed by the UI tools
+//!
+//! Reading other applications' UI and sending input needs Accessibility
+//! access, capturing other applications' windows needs Screen Recording
+//! access. macOS grants both to the terminal application that runs termineer.
+//! Without them the system APIs return empty or blank results instead of
+//! errors, so the tools check before they start.
+
+use accessibility_sys_ng::{
+    kAXTrustedCheckOptionPrompt, AXIsProcessTrusted, AXIsProcessTrustedWithOptions,
+};
+use core_foundation::base::TCFType;
+use core_foundation::boolean::CFBoolean;
+use core_foundation::dictionary::CFDictionary;
+use core_foundation::string::CFString;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGPreflightScreenCaptureAccess() -> bool;
+    fn CGRequestScreenCaptureAccess() -> bool;
+}
+
+/// A privacy permission of macOS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Reading the UI of other applications and sending them input
+    Accessibility,
+    /// Capturing the contents of other applications' windows
+    ScreenRecording,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 2] = [Permission::Accessibility, Permission::ScreenRecording];
+
+    /// Name as shown in System Settings
+    pub fn name(&self) -> &'static str {
+        match self {
+            Permission::Accessibility => "Accessibility",
+            Permission::ScreenRecording => "Screen Recording",
+        }
+    }
+
+    /// Tools that need the permission
+    pub fn tools(&self) -> &'static str {
+        match self {
+            Permission::Accessibility => "screendump, input",
+            Permission::ScreenRecording => "screenshot, record",
+        }
+    }
+
+    /// Whether the permission is granted, without asking the user
+    pub fn granted(&self) -> bool {
+        unsafe {
+            match self {
+                Permission::Accessibility => AXIsProcessTrusted(),
+                Permission::ScreenRecording => CGPreflightScreenCaptureAccess(),
+            }
+        }
+    }
+
+    /// Show the system prompt asking the user to grant the permission, if it
+    /// is missing; returns whether it is granted now
+    pub fn request(&self) -> bool {
+        unsafe {
+            match self {
+                Permission::Accessibility => {
+                    let options = CFDictionary::from_CFType_pairs(&[(
+                        CFString::wrap_under_get_rule(kAXTrustedCheckOptionPrompt as _),
+                        CFBoolean::true_value(),
+                    )]);
+                    AXIsProcessTrustedWithOptions(options.as_concrete_TypeRef() as _)
+                }
+                Permission::ScreenRecording => CGRequestScreenCaptureAccess(),
+            }
+        }
+    }
+
+    /// How to grant the permission
+    pub fn guidance(&self) -> String {
+        format!(
+            "{name} access is not granted to this terminal. Open System Settings > \
+             Privacy & Security > {name}, enable it for the terminal application running \
+             termineer, then restart the terminal. `termineer doctor --prompt` asks macOS \
+             to show the request.",
+            name = self.name()
+        )
+    }
+}
+
+/// Check that the permissions are granted, failing with guidance for the
+/// first missing one
+pub fn require(permissions: &[Permission]) -> Result<(), String> {
+    match permissions.iter().find(|permission| !permission.granted()) {
+        Some(permission) => Err(format!(
+            "{} Ask the user to grant it; the tool cannot work until then.",
+            permission.guidance()
+        )),
+        None => Ok(()),
+    }
+}
@@ -5,12 +5,14 @@
 //! This module provides macOS-specific implementation for the screendump tool
 //! using the macOS Accessibility APIs.
 
+use super::permissions::{self, Permission};
 use super::xml_helpers;
+use crate::tools::ui::screen_access::{self, Display, Rect};
 use crate::tools::ui::screendump::ScreendumpCommand;
 use crate::tools::ToolResult;
 
 use accessibility_ng::{AXUIElement, AXUIElementAttributes};
-use accessibility_sys_ng::{pid_t, AXIsProcessTrusted};
+use accessibility_sys_ng::pid_t;
 use core_graphics_types::geometry::{CGPoint, CGSize};
 use objc2::rc::Retained;
 use objc2::runtime::NSObject;
@@ -46,10 +48,11 @@ pub async fn execute_macos_screendump(args: &str, _body: &str, silent_mode: bool
     }
 
     // Check accessibility permissions
-    unsafe {
-        if !AXIsProcessTrusted() {
-            return ToolResult::error("Accessibility access is not enabled for this application. Please enable it in System Preferences > Security & Privacy > Privacy > Accessibility");
+    if let Err(guidance) = permissions::require(&[Permission::Accessibility]) {
+        if !silent_mode {
+            crate::bprintln!(error: "{}", guidance);
         }
+        return ToolResult::error(guidance);
     }
 
     match command {
@@ -60,10 +63,21 @@ pub async fn execute_macos_screendump(args: &str, _body: &str, silent_mode: bool
                         return ToolResult::success("<UITree><Windows/></UITree>");
                     }
 
-                    // Create a list of all windows in XML format
+                    // Create a list of the displays and all windows in XML format
+                    let displays = screen_access::displays().unwrap_or_default();
                     let mut xml_output = String::from(
-                        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<UITree>\n  <Windows>\n",
+                        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<UITree>\n  <Displays>\n",
                     );
+                    for display in &displays {
+                        xml_output.push_str(&format!(
+                            "    <Display index=\"{}\" id=\"{}\" name=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" scale=\"{}\" primary=\"{}\"/>\n",
+                            display.index, display.id, display.name,
+                            display.bounds.x, display.bounds.y,
+                            display.bounds.width, display.bounds.height,
+                            display.scale, display.primary
+                        ));
+                    }
+                    xml_output.push_str("  </Displays>\n  <Windows>\n");
 
                     for window in windows {
                         // Print window dimensions for debugging
@@ -75,12 +89,15 @@ pub async fn execute_macos_screendump(args: &str, _body: &str, silent_mode: bool
                         );
 
                         xml_output.push_str(&format!(
-                            "    <Window id=\"{}:{}\" app=\"{}\" title=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+                            "    <Window id=\"{}:{}\" app=\"{}\" title=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{}/>\n",
                             window.app_name, window.index,
                             window.app_name,
                             window.window_title,
                             window.position.0, window.position.1,
-                            window.size.0, window.size.1
+                            window.size.0, window.size.1,
+                            window_display(&displays, &window)
+                                .map(|index| format!(" display=\"{index}\""))
+                                .unwrap_or_default()
                         ));
                     }
 
@@ -140,7 +157,7 @@ fn get_window_details_xml(window: &MacOSWindow) -> Result<String, String> {
     );
 
     // Create a structured UIWindow using our helper
-    let ui_window = xml_helpers::create_ui_window_from_macos_window(
+    let mut ui_window = xml_helpers::create_ui_window_from_macos_window(
         window.app_name.clone(),
         window.window_title.clone(),
         window.position,
@@ -148,6 +165,10 @@ fn get_window_details_xml(window: &MacOSWindow) -> Result<String, String> {
         &window.element,
     );
 
+    if let Ok(displays) = screen_access::displays() {
+        ui_window.display = window_display(&displays, window);
+    }
+
     // Convert to XML
     let xml_result = ui_window.to_xml();
 
@@ -161,6 +182,17 @@ fn get_window_details_xml(window: &MacOSWindow) -> Result<String, String> {
     xml_result
 }
 
+/// Index of the display showing most of a window
+fn window_display(displays: &[Display], window: &MacOSWindow) -> Option<usize> {
+    let bounds = Rect::new(
+        window.position.0,
+        window.position.1,
+        window.size.0,
+        window.size.1,
+    );
+    screen_access::display_for_rect(displays, &bounds).map(|display| display.index)
+}
+
 /// List all windows on the system
 fn list_all_windows() -> Result<Vec<MacOSWindow>, String> {
     // Get running application PIDs
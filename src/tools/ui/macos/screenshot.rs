@@ -3,8 +3,10 @@
 //! This module provides macOS-specific implementation for the screenshot tool
 //! using the xcap crate for cross-platform screen capture.
 
+use super::permissions::{self, Permission};
 use crate::llm::Content;
 use crate::llm::ImageSource;
+use crate::tools::ui::screen_access::{self, Image, Rect};
 use crate::tools::ui::screendump;
 use crate::tools::ToolResult;
 use base64::{engine::general_purpose, Engine as _};
@@ -35,6 +37,13 @@ pub async fn execute_macos_screenshot(args: &str, _body: &str, silent_mode: bool
         }
     }
 
+    if let Err(guidance) = permissions::require(&[Permission::ScreenRecording]) {
+        if !silent_mode {
+            crate::bprintln!(error: "{}", guidance);
+        }
+        return ToolResult::error(guidance);
+    }
+
     // Attempt to capture screenshots
     match capture_screenshots(command) {
         Ok(images) => {
@@ -209,6 +218,12 @@ fn capture_all_screens() -> Result<Vec<String>, Box<dyn std::error::Error>> {
 
 /// Capture a single screen by index
 fn capture_single_screen(index: usize) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let image = capture_screen_image(index)?;
+    Ok(vec![process_image(image.image)?])
+}
+
+/// Capture a screen by index as an image
+pub(crate) fn capture_screen_image(index: usize) -> Result<Image, Box<dyn std::error::Error>> {
     // Get all monitors
     let monitors = Monitor::all()?;
 
@@ -244,16 +259,25 @@ fn capture_single_screen(index: usize) -> Result<Vec<String>, Box<dyn std::error
 
     // Convert to DynamicImage
     // The image from xcap is already an RgbaImage from the image crate
-    let dynamic_image = DynamicImage::ImageRgba8(image);
-
-    // Process the image
-    let base64_image = process_image(dynamic_image)?;
-
-    Ok(vec![base64_image])
+    // The monitor size is in points, the image in pixels
+    let origin = (monitor.x()?, monitor.y()?);
+    Ok(Image::new(
+        DynamicImage::ImageRgba8(image),
+        origin,
+        width as i32,
+    ))
 }
 
 /// Capture a specific window
-fn capture_window(window_id: &str) -> Result<String, Box<dyn std::error::Error>> {
+pub(crate) fn capture_window(window_id: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let image = capture_window_image(window_id)?.fit();
+    // The input tool converts clicks given in pixels of this screenshot
+    screen_access::remember_window_scale(window_id, image.scale);
+    process_image(image.image)
+}
+
+/// Capture a window, by index, title or screendump ID, as an image
+pub(crate) fn capture_window_image(window_id: &str) -> Result<Image, Box<dyn std::error::Error>> {
     crate::bprintln!(dev: "Capturing window: {}", window_id);
 
     // First try using xcap's Window functions if the window_id is a numeric ID
@@ -268,9 +292,11 @@ fn capture_window(window_id: &str) -> Result<String, Box<dyn std::error::Error>>
             let image = window.capture_image()?;
 
             // Convert to DynamicImage - image is already an RgbaImage
-            let dynamic_image = DynamicImage::ImageRgba8(image);
-
-            return process_image(dynamic_image);
+            return Ok(Image::new(
+                DynamicImage::ImageRgba8(image),
+                (window.x()?, window.y()?),
+                window.width()? as i32,
+            ));
         }
     }
 
@@ -285,9 +311,11 @@ fn capture_window(window_id: &str) -> Result<String, Box<dyn std::error::Error>>
                 let image = window.capture_image()?;
 
                 // Convert to DynamicImage - image is already an RgbaImage
-                let dynamic_image = DynamicImage::ImageRgba8(image);
-
-                return process_image(dynamic_image);
+                return Ok(Image::new(
+                    DynamicImage::ImageRgba8(image),
+                    (window.x()?, window.y()?),
+                    window.width()? as i32,
+                ));
             }
         }
     }
@@ -302,71 +330,54 @@ fn capture_window(window_id: &str) -> Result<String, Box<dyn std::error::Error>>
     crate::bprintln!(dev: "Found window '{}' of app '{}' at {}x{} size {}x{}",
         window_title, app_name, x, y, width, height);
 
-    // Capture the region using monitor.capture_area
-    let monitors = Monitor::all()?;
-
-    // Find which monitor contains this window
-    let mut found_monitor = None;
-    for monitor in &monitors {
-        let monitor_x = monitor.x()?;
-        let monitor_y = monitor.y()?;
-        let monitor_width = monitor.width()? as i32;
-        let monitor_height = monitor.height()? as i32;
-
-        // Check if the window is at least partially on this monitor
-        if x < monitor_x + monitor_width
-            && x + width > monitor_x
-            && y < monitor_y + monitor_height
-            && y + height > monitor_y
-        {
-            found_monitor = Some(monitor);
-            break;
-        }
-    }
-
-    let monitor = found_monitor.ok_or_else(|| "Window not on any screen".to_string())?;
-
-    // Calculate coordinates relative to the monitor
-    let rel_x = (x - monitor.x()?).max(0);
-    let rel_y = (y - monitor.y()?).max(0);
+    // Capture the display showing most of the window
+    let displays = screen_access::displays()?;
+    let window = Rect::new(x, y, width, height);
+    let display = screen_access::display_for_rect(&displays, &window)
+        .ok_or_else(|| "Window not on any screen".to_string())?;
+
+    // The window bounds are in points, the captured image in pixels of the display
+    let visible = display
+        .bounds
+        .intersection(&window)
+        .ok_or_else(|| "Window not on any screen".to_string())?;
+    let area = display
+        .to_pixels(&visible)
+        .ok_or_else(|| "Window not on any screen".to_string())?;
+    crate::bprintln!(dev: "Cropping {:?} from display {} (scale {})", area, display.index, display.scale);
 
-    // Ensure width and height stay within monitor bounds
-    let width_pixels = monitor.width()? as i32;
-    let height_pixels = monitor.height()? as i32;
-
-    let cap_width = (width as u32).min((width_pixels - rel_x) as u32);
-    let cap_height = (height as u32).min((height_pixels - rel_y) as u32);
-
-    // Capture the entire monitor and crop the region
+    let monitors = Monitor::all()?;
+    let monitor = monitors
+        .get(display.index)
+        .ok_or_else(|| "The displays changed during the capture".to_string())?;
     let full_image = monitor.capture_image()?;
 
-    // Crop the image to the desired area
+    // Crop the image to the window
     let cropped_image = image::imageops::crop_imm(
         &full_image,
-        rel_x as u32,
-        rel_y as u32,
-        cap_width,
-        cap_height,
+        area.x as u32,
+        area.y as u32,
+        area.width as u32,
+        area.height as u32,
     )
     .to_image();
 
     // Convert to DynamicImage
-    let dynamic_image = DynamicImage::ImageRgba8(cropped_image);
-
-    // Process the image
-    process_image(dynamic_image)
+    Ok(Image::new(
+        DynamicImage::ImageRgba8(cropped_image),
+        (visible.x, visible.y),
+        visible.width,
+    ))
 }
 
 /// Process the image (resize if needed, convert to JPEG)
-fn process_image(img: DynamicImage) -> Result<String, Box<dyn std::error::Error>> {
+pub(crate) fn process_image(img: DynamicImage) -> Result<String, Box<dyn std::error::Error>> {
     // Check if image dimensions are too large (Claude has limits)
     let (width, height) = img.dimensions();
-    let resized_img = if width > 1600 || height > 1200 {
+    let resized_img = if let Some((new_width, new_height)) =
+        screen_access::fitted_size(width, height)
+    {
         // Scale down to reasonable dimensions while preserving aspect ratio
-        let scale_factor = f32::min(1600.0 / width as f32, 1200.0 / height as f32);
-        let new_width = (width as f32 * scale_factor) as u32;
-        let new_height = (height as f32 * scale_factor) as u32;
-
         crate::bprintln!(dev: "Resizing image from {}x{} to {}x{}", width, height, new_width, new_height);
         img.resize(new_width, new_height, image::imageops::FilterType::Lanczos3)
     } else {
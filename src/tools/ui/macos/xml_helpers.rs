@@ -22,6 +22,7 @@ pub fn create_ui_window_from_macos_window(
         window_title: window_title.clone(),
         position,
         size,
+        display: None,
         ui_tree: None,
     };
 
@@ -4,8 +4,15 @@
 //! - screenshot: Capture screenshots of windows and screens
 //! - screendump: Extract UI structure from applications
 //! - input: Send mouse and keyboard inputs to applications
+//! - record: Record screens or windows and return their key frames
+//! - computer: Send an input and return a screenshot of the result
+//!
+//! `screen_access` holds the display geometry they share.
 
+pub mod computer;
 pub mod input;
+pub mod record;
+pub mod screen_access;
 pub mod screendump;
 pub mod screenshot;
 pub mod structure;
@@ -0,0 +1,459 @@
+//! Record tool module
+//!
+//! This tool records a screen or window while the agent drives it, so that it
+//! can check what its input actually did:
+//! - `record start [screen N | window ID] [fps=N] [max=SECONDS]` starts
+//!   capturing frames in the background
+//! - `record stop [frames=N]` ends the recording and returns its key frames
+//!
+//! Frames equal to the previous one are dropped while recording. Key frames
+//! are the first and the last frame and those that differ visibly from the
+//! key frame before them, so a recording of a click that opens a dialog comes
+//! back as "before", "dialog" and "after" rather than dozens of images.
+
+use crate::llm::{Content, ImageSource};
+use crate::tools::ToolResult;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use lazy_static::lazy_static;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Frames per second when no `fps` is given, and the most allowed
+const DEFAULT_FPS: u32 = 4;
+const MAX_FPS: u32 = 10;
+
+/// Seconds after which a recording stops by itself when no `max` is given,
+/// and the longest allowed
+const DEFAULT_MAX_SECONDS: u64 = 60;
+const MAX_SECONDS: u64 = 300;
+
+/// Key frames returned when no `frames` is given, and the most allowed
+const DEFAULT_KEY_FRAMES: usize = 6;
+const MAX_KEY_FRAMES: usize = 20;
+
+/// Side of the grayscale thumbnails frames are compared by
+const SIGNATURE_SIZE: u32 = 32;
+
+/// Mean difference to the previous key frame, as a share of the brightness
+/// range, for a frame to become a key frame; blinking cursors stay below it
+const KEY_FRAME_CHANGE: f64 = 0.004;
+
+lazy_static! {
+    static ref RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+}
+
+/// What is recorded
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    Screen(usize),
+    Window(String),
+}
+
+#[derive(Debug, PartialEq)]
+enum RecordCommand {
+    Start {
+        target: Target,
+        fps: u32,
+        max_seconds: u64,
+    },
+    Stop {
+        frames: usize,
+    },
+}
+
+/// A captured frame
+struct Frame {
+    /// Time since the start of the recording
+    at: Duration,
+    /// Grayscale thumbnail the frame is compared by
+    signature: Vec<u8>,
+    /// Base64 JPEG of the frame
+    image: String,
+}
+
+/// Frames of a finished recording
+struct Captured {
+    frames: Vec<Frame>,
+    /// Frames captured, including those equal to the previous one
+    captures: usize,
+    duration: Duration,
+    /// Why the recording ended early, if a capture failed
+    error: Option<String>,
+}
+
+/// A running recording
+struct Recording {
+    target: Target,
+    stop: Arc<AtomicBool>,
+    task: tokio::task::JoinHandle<Captured>,
+}
+
+fn parse_command(args: &str) -> Result<RecordCommand, String> {
+    let usage = "Usage: `record start [screen N | window ID] [fps=N] [max=SECONDS]` or `record stop [frames=N]`";
+    let mut words = args.split_whitespace();
+    let action = words.next().unwrap_or_default().to_lowercase();
+    let mut rest = Vec::new();
+    let mut options = std::collections::HashMap::new();
+    for word in words {
+        match word.split_once('=') {
+            Some((key, value)) if ["fps", "max", "frames"].contains(&key) => {
+                let value: u64 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid number: {word}"))?;
+                options.insert(key, value.max(1));
+            }
+            _ => rest.push(word),
+        }
+    }
+
+    match action.as_str() {
+        "start" => {
+            let target = match rest.first().map(|word| word.to_lowercase()) {
+                None => Target::Screen(0),
+                Some(word) if word == "screen" => match rest.get(1) {
+                    Some(index) => Target::Screen(
+                        index
+                            .parse()
+                            .map_err(|_| format!("Invalid screen index: {index}"))?,
+                    ),
+                    None => Target::Screen(0),
+                },
+                Some(word) if word == "window" || word == "win" => {
+                    if rest.len() < 2 {
+                        return Err(format!("Missing window ID. {usage}"));
+                    }
+                    Target::Window(rest[1..].join(" "))
+                }
+                Some(_) => Target::Window(rest.join(" ")),
+            };
+            Ok(RecordCommand::Start {
+                target,
+                fps: options
+                    .get("fps")
+                    .map_or(DEFAULT_FPS, |fps| *fps as u32)
+                    .min(MAX_FPS),
+                max_seconds: options
+                    .get("max")
+                    .copied()
+                    .unwrap_or(DEFAULT_MAX_SECONDS)
+                    .min(MAX_SECONDS),
+            })
+        }
+        "stop" => Ok(RecordCommand::Stop {
+            frames: options
+                .get("frames")
+                .map_or(DEFAULT_KEY_FRAMES, |frames| *frames as usize)
+                .clamp(2, MAX_KEY_FRAMES),
+        }),
+        _ => Err(usage.to_string()),
+    }
+}
+
+/// Execute the record tool
+pub async fn execute_record(args: &str, _body: &str, silent_mode: bool) -> ToolResult {
+    let result = match parse_command(args) {
+        Ok(RecordCommand::Start {
+            target,
+            fps,
+            max_seconds,
+        }) => {
+            if !silent_mode {
+                crate::bprintln!("🎬 Recording {}...", describe(&target));
+            }
+            start(target, fps, max_seconds)
+        }
+        Ok(RecordCommand::Stop { frames }) => {
+            if !silent_mode {
+                crate::bprintln!("🎬 Stopping the recording...");
+            }
+            stop(frames).await
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(content) => ToolResult::success_with_content(content),
+        Err(error_message) => {
+            if !silent_mode {
+                crate::bprintln!(error: "{}", error_message);
+            }
+            ToolResult::error(error_message)
+        }
+    }
+}
+
+fn describe(target: &Target) -> String {
+    match target {
+        Target::Screen(index) => format!("screen {index}"),
+        Target::Window(id) => format!("window '{id}'"),
+    }
+}
+
+fn start(target: Target, fps: u32, max_seconds: u64) -> Result<Vec<Content>, String> {
+    let mut recording = RECORDING.lock().unwrap();
+    if let Some(running) = recording.as_ref() {
+        if !running.task.is_finished() {
+            return Err(format!(
+                "Already recording {}: stop that recording first",
+                describe(&running.target)
+            ));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    crate::tools::ui::macos::permissions::require(&[
+        crate::tools::ui::macos::permissions::Permission::ScreenRecording,
+    ])?;
+
+    // The first capture shows right away whether the target can be recorded
+    let first = tokio::task::block_in_place(|| capture(&target))?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let task = crate::output::spawn(record(
+        target.clone(),
+        first,
+        Duration::from_millis(1000 / fps as u64),
+        Duration::from_secs(max_seconds),
+        stop.clone(),
+    ));
+    *recording = Some(Recording {
+        target: target.clone(),
+        stop,
+        task,
+    });
+
+    Ok(vec![Content::Text {
+        text: format!(
+            "Recording {} at {fps} frames per second for up to {max_seconds} seconds. \
+             Send the input now, then use `record stop` to get the key frames.",
+            describe(&target)
+        ),
+    }])
+}
+
+/// Capture frames until stopped or the maximum duration is reached
+async fn record(
+    target: Target,
+    first: DynamicImage,
+    interval: Duration,
+    max_duration: Duration,
+    stop: Arc<AtomicBool>,
+) -> Captured {
+    let started = Instant::now();
+    let mut captured = Captured {
+        frames: Vec::new(),
+        captures: 0,
+        duration: Duration::ZERO,
+        error: None,
+    };
+    let mut image = Ok(first);
+    loop {
+        match image.and_then(|image| Ok((signature(&image), encode(image)?))) {
+            Ok((signature, encoded)) => {
+                captured.captures += 1;
+                let unchanged = captured
+                    .frames
+                    .last()
+                    .is_some_and(|last| last.signature == signature);
+                if !unchanged {
+                    captured.frames.push(Frame {
+                        at: started.elapsed(),
+                        signature,
+                        image: encoded,
+                    });
+                }
+            }
+            Err(e) => {
+                captured.error = Some(e);
+                break;
+            }
+        }
+
+        tokio::time::sleep(interval).await;
+        if stop.load(Ordering::Relaxed) || started.elapsed() >= max_duration {
+            break;
+        }
+        image = tokio::task::block_in_place(|| capture(&target));
+    }
+    captured.duration = started.elapsed();
+    captured
+}
+
+async fn stop(max_frames: usize) -> Result<Vec<Content>, String> {
+    let recording = RECORDING
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or("No recording is running: use `record start` first")?;
+    recording.stop.store(true, Ordering::Relaxed);
+    let captured = recording
+        .task
+        .await
+        .map_err(|e| format!("Recording failed: {e}"))?;
+
+    let signatures: Vec<&[u8]> = captured
+        .frames
+        .iter()
+        .map(|frame| frame.signature.as_slice())
+        .collect();
+    let key_frames = select_key_frames(&signatures, max_frames);
+
+    let mut summary = format!(
+        "Recorded {} for {:.1}s: {} frames, {} distinct, {} key frames",
+        describe(&recording.target),
+        captured.duration.as_secs_f64(),
+        captured.captures,
+        captured.frames.len(),
+        key_frames.len()
+    );
+    if let Some(error) = &captured.error {
+        summary.push_str(&format!("\nThe recording ended early: {error}"));
+    }
+    let mut content = vec![Content::Text { text: summary }];
+    for (n, index) in key_frames.into_iter().enumerate() {
+        let frame = &captured.frames[index];
+        content.push(Content::Text {
+            text: format!("Key frame {} at +{:.1}s", n + 1, frame.at.as_secs_f64()),
+        });
+        content.push(Content::Image {
+            source: ImageSource::Base64 {
+                media_type: "image/jpeg".to_string(),
+                data: frame.image.clone(),
+            },
+        });
+    }
+    Ok(content)
+}
+
+/// Indices of the key frames: the first and the last frame, and the frames
+/// that differ visibly from the key frame before them. When there are more
+/// than `max`, those with the largest changes are kept.
+fn select_key_frames(signatures: &[&[u8]], max: usize) -> Vec<usize> {
+    if signatures.len() <= 1 {
+        return (0..signatures.len()).collect();
+    }
+    let last = signatures.len() - 1;
+
+    let mut changes = Vec::new();
+    let mut previous = 0;
+    for (index, signature) in signatures.iter().enumerate().take(last).skip(1) {
+        let change = difference(signatures[previous], signature);
+        if change >= KEY_FRAME_CHANGE {
+            changes.push((index, change));
+            previous = index;
+        }
+    }
+    changes.sort_by(|a, b| b.1.total_cmp(&a.1));
+    changes.truncate(max.saturating_sub(2));
+
+    let mut selected: Vec<usize> = changes.into_iter().map(|(index, _)| index).collect();
+    selected.push(0);
+    selected.push(last);
+    selected.sort_unstable();
+    selected
+}
+
+/// Mean difference of two signatures as a share of the brightness range
+fn difference(a: &[u8], b: &[u8]) -> f64 {
+    if a.is_empty() || a.len() != b.len() {
+        return 1.0;
+    }
+    let total: u64 = a.iter().zip(b).map(|(a, b)| a.abs_diff(*b) as u64).sum();
+    total as f64 / (a.len() as f64 * 255.0)
+}
+
+fn signature(image: &DynamicImage) -> Vec<u8> {
+    image
+        .resize_exact(SIGNATURE_SIZE, SIGNATURE_SIZE, FilterType::Triangle)
+        .to_luma8()
+        .into_raw()
+}
+
+fn capture(target: &Target) -> Result<DynamicImage, String> {
+    #[cfg(target_os = "macos")]
+    {
+        use crate::tools::ui::macos::screenshot;
+        let image = match target {
+            Target::Screen(index) => screenshot::capture_screen_image(*index),
+            Target::Window(id) => screenshot::capture_window_image(id),
+        };
+        image
+            .map(|image| image.image)
+            .map_err(|e| format!("Failed to capture {}: {e}", describe(target)))
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    Err(format!(
+        "Recording {} is not implemented for {} platform",
+        describe(target),
+        std::env::consts::OS
+    ))
+}
+
+fn encode(image: DynamicImage) -> Result<String, String> {
+    #[cfg(target_os = "macos")]
+    {
+        crate::tools::ui::macos::screenshot::process_image(image)
+            .map_err(|e| format!("Failed to encode a frame: {e}"))
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = image;
+        Err("Recording is not implemented for this platform".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("start").unwrap(),
+            RecordCommand::Start {
+                target: Target::Screen(0),
+                fps: DEFAULT_FPS,
+                max_seconds: DEFAULT_MAX_SECONDS
+            }
+        );
+        assert_eq!(
+            parse_command("start window Safari:1 fps=30 max=10").unwrap(),
+            RecordCommand::Start {
+                target: Target::Window("Safari:1".to_string()),
+                fps: MAX_FPS,
+                max_seconds: 10
+            }
+        );
+        assert_eq!(
+            parse_command("stop frames=3").unwrap(),
+            RecordCommand::Stop { frames: 3 }
+        );
+        assert!(parse_command("start screen one").is_err());
+        assert!(parse_command("pause").is_err());
+    }
+
+    #[test]
+    fn test_select_key_frames() {
+        let frame = |value: u8| vec![value; 16];
+        // Idle, a dialog fades in over three frames, idle, the dialog closes
+        let frames = [
+            frame(10),
+            frame(10),
+            frame(11),
+            frame(40),
+            frame(80),
+            frame(80),
+            frame(10),
+            frame(10),
+        ];
+        let signatures: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+
+        assert_eq!(select_key_frames(&signatures, 10), vec![0, 3, 4, 6, 7]);
+        assert_eq!(select_key_frames(&signatures, 3), vec![0, 6, 7]);
+        assert_eq!(select_key_frames(&signatures[..1], 10), vec![0]);
+        assert_eq!(difference(&frame(0), &frame(255)), 1.0);
+    }
+}
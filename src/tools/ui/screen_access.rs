@@ -0,0 +1,299 @@
+//! Display geometry shared by the UI tools
+//!
+//! All displays share one global coordinate space measured in points, with
+//! the origin at the top-left corner of the primary display; displays left of
+//! or above it have negative coordinates. Window positions and mouse events
+//! use this space, while the captured image of a display has `scale` pixels
+//! per point (2 on Retina displays).
+//!
+//! Screenshots carry their scale, which also counts any downscaling for the
+//! model, and the scale of the last screenshot of each window is remembered so
+//! the input tool can turn coordinates read off it back into points.
+
+use image::DynamicImage;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use xcap::Monitor;
+
+/// Largest images sent to models; larger captures are scaled down
+pub const MAX_IMAGE_WIDTH: u32 = 1600;
+pub const MAX_IMAGE_HEIGHT: u32 = 1200;
+
+lazy_static! {
+    /// Image pixels per point of the last screenshot of each window
+    static ref WINDOW_SCALES: Mutex<HashMap<String, f32>> = Mutex::new(HashMap::new());
+}
+
+/// A rectangle in global points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Whether a point lies inside the rectangle
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// The part shared with another rectangle, if any
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        (right > left && bottom > top).then(|| Rect::new(left, top, right - left, bottom - top))
+    }
+
+    fn area(&self) -> i64 {
+        self.width as i64 * self.height as i64
+    }
+}
+
+/// A connected display
+#[derive(Debug, Clone, PartialEq)]
+pub struct Display {
+    /// Position in the display list, as used by `screenshot screen N`
+    pub index: usize,
+    /// Display ID of the system
+    pub id: u32,
+    pub name: String,
+    /// Bounds in global points
+    pub bounds: Rect,
+    /// Pixels per point in captured images
+    pub scale: f32,
+    pub primary: bool,
+}
+
+impl Display {
+    /// The part of a rectangle on this display, in pixels of its captured image
+    pub fn to_pixels(&self, rect: &Rect) -> Option<Rect> {
+        let visible = self.bounds.intersection(rect)?;
+        let scale = |value: i32| (value as f32 * self.scale).round() as i32;
+        Some(Rect::new(
+            scale(visible.x - self.bounds.x),
+            scale(visible.y - self.bounds.y),
+            scale(visible.width),
+            scale(visible.height),
+        ))
+    }
+}
+
+/// A captured image with what is needed to map its pixels back to points
+pub struct Image {
+    pub image: DynamicImage,
+    /// Global position of the top-left corner in points
+    pub origin: (i32, i32),
+    /// Image pixels per point
+    pub scale: f32,
+}
+
+impl Image {
+    /// Image of an area `width` points wide with its top-left corner at `origin`
+    pub fn new(image: DynamicImage, origin: (i32, i32), width: i32) -> Self {
+        let scale = if width > 0 {
+            image.width() as f32 / width as f32
+        } else {
+            1.0
+        };
+        Self {
+            image,
+            origin,
+            scale,
+        }
+    }
+
+    /// Scale the image down to the size sent to models, keeping the scale in step
+    pub fn fit(self) -> Self {
+        let Some((width, height)) = fitted_size(self.image.width(), self.image.height()) else {
+            return self;
+        };
+        let scale = self.scale * width as f32 / self.image.width() as f32;
+        Self {
+            image: self
+                .image
+                .resize(width, height, image::imageops::FilterType::Lanczos3),
+            origin: self.origin,
+            scale,
+        }
+    }
+}
+
+/// Size of an image scaled down to fit the limits for models, or None when
+/// it fits already
+pub fn fitted_size(width: u32, height: u32) -> Option<(u32, u32)> {
+    if width <= MAX_IMAGE_WIDTH && height <= MAX_IMAGE_HEIGHT {
+        return None;
+    }
+    // Preserve the aspect ratio
+    let factor = f32::min(
+        MAX_IMAGE_WIDTH as f32 / width as f32,
+        MAX_IMAGE_HEIGHT as f32 / height as f32,
+    );
+    Some((
+        (width as f32 * factor) as u32,
+        (height as f32 * factor) as u32,
+    ))
+}
+
+/// Remember the scale of a screenshot of a window
+pub fn remember_window_scale(window_id: &str, scale: f32) {
+    WINDOW_SCALES
+        .lock()
+        .unwrap()
+        .insert(window_id.to_string(), scale);
+}
+
+/// Image pixels per point of the last screenshot of a window, if any
+pub fn window_scale(window_id: &str) -> Option<f32> {
+    WINDOW_SCALES.lock().unwrap().get(window_id).copied()
+}
+
+/// Points of a position given in pixels of an image with `scale` pixels per point
+pub fn pixels_to_points(x: i32, y: i32, scale: f32) -> (i32, i32) {
+    if scale <= 0.0 {
+        return (x, y);
+    }
+    (
+        (x as f32 / scale).round() as i32,
+        (y as f32 / scale).round() as i32,
+    )
+}
+
+/// The connected displays, in the order of `screenshot screen N`
+pub fn displays() -> Result<Vec<Display>, String> {
+    let error = |e: xcap::XCapError| format!("Failed to read the displays: {e}");
+    let monitors = Monitor::all().map_err(error)?;
+    monitors
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| {
+            Ok(Display {
+                index,
+                id: monitor.id().map_err(error)?,
+                name: monitor.name().unwrap_or_default(),
+                bounds: Rect::new(
+                    monitor.x().map_err(error)?,
+                    monitor.y().map_err(error)?,
+                    monitor.width().map_err(error)? as i32,
+                    monitor.height().map_err(error)? as i32,
+                ),
+                scale: monitor.scale_factor().unwrap_or(1.0),
+                primary: monitor.is_primary().unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+/// The display showing the largest part of a rectangle
+pub fn display_for_rect<'a>(displays: &'a [Display], rect: &Rect) -> Option<&'a Display> {
+    displays
+        .iter()
+        .filter_map(|display| Some((display, display.bounds.intersection(rect)?.area())))
+        .max_by_key(|(_, area)| *area)
+        .map(|(display, _)| display)
+}
+
+/// The display containing a global point
+pub fn display_at(displays: &[Display], x: i32, y: i32) -> Option<&Display> {
+    displays
+        .iter()
+        .find(|display| display.bounds.contains(x, y))
+}
+
+/// Global point of a point relative to a window's top-left corner; fails when
+/// the point is on no display, where input would be lost or land elsewhere
+pub fn window_to_global(
+    displays: &[Display],
+    window: &Rect,
+    x: i32,
+    y: i32,
+) -> Result<(i32, i32), String> {
+    let global = (window.x + x, window.y + y);
+    if display_at(displays, global.0, global.1).is_none() {
+        return Err(format!(
+            "Point ({x}, {y}) of the window is at ({}, {}), which is on no display",
+            global.0, global.1
+        ));
+    }
+    Ok(global)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn display(index: usize, bounds: Rect, scale: f32) -> Display {
+        Display {
+            index,
+            id: index as u32 + 1,
+            name: format!("Display {index}"),
+            bounds,
+            scale,
+            primary: index == 0,
+        }
+    }
+
+    #[test]
+    fn test_display_geometry() {
+        // A Retina laptop with an external display to its left
+        let displays = vec![
+            display(0, Rect::new(0, 0, 1512, 982), 2.0),
+            display(1, Rect::new(-1920, -98, 1920, 1080), 1.0),
+        ];
+
+        // Mostly on the external display
+        let window = Rect::new(-1000, 100, 1200, 600);
+        assert_eq!(display_for_rect(&displays, &window).unwrap().index, 1);
+        assert_eq!(
+            displays[1].to_pixels(&window),
+            Some(Rect::new(920, 198, 1000, 600))
+        );
+        assert_eq!(
+            displays[0].to_pixels(&window),
+            Some(Rect::new(0, 200, 400, 1200))
+        );
+        assert_eq!(
+            display_for_rect(&displays, &Rect::new(5000, 0, 10, 10)),
+            None
+        );
+
+        assert_eq!(
+            window_to_global(&displays, &window, 10, 20),
+            Ok((-990, 120))
+        );
+        assert_eq!(
+            window_to_global(&displays, &window, 1100, 20),
+            Ok((100, 120))
+        );
+        assert!(window_to_global(&displays, &window, 10, -300).is_err());
+    }
+
+    #[test]
+    fn test_image_scale() {
+        // A Retina window capture, too wide for models
+        let image = Image::new(DynamicImage::new_rgba8(3200, 1000), (40, 30), 1600);
+        assert_eq!(image.scale, 2.0);
+        let image = image.fit();
+        assert_eq!((image.image.width(), image.image.height()), (1600, 500));
+        assert_eq!(image.scale, 1.0);
+
+        assert_eq!(fitted_size(1600, 1200), None);
+        assert_eq!(fitted_size(3024, 1964), Some((1600, 1039)));
+        assert_eq!(pixels_to_points(300, 151, 2.0), (150, 76));
+        assert_eq!(pixels_to_points(300, 150, 1.058), (284, 142));
+    }
+}
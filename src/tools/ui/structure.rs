@@ -212,6 +212,9 @@ pub struct UIWindow {
     /// The window size (width, height)
     pub size: (i32, i32),
 
+    /// Index of the display showing most of the window
+    pub display: Option<usize>,
+
     /// The window's UI element tree
     pub ui_tree: Option<UIElement>,
 }
@@ -243,6 +246,9 @@ impl UIWindow {
         window_elem.push_attribute(("y", self.position.1.to_string().as_str()));
         window_elem.push_attribute(("width", self.size.0.to_string().as_str()));
         window_elem.push_attribute(("height", self.size.1.to_string().as_str()));
+        if let Some(display) = self.display {
+            window_elem.push_attribute(("display", display.to_string().as_str()));
+        }
 
         writer
             .write_event(Event::Start(window_elem))
@@ -0,0 +1,455 @@
+//! Visual diff tool for visual regression checks
+//!
+//! Compares two screenshots, or a screenshot with a baseline stored in
+//! `.termineer/baselines/`, and returns a similarity score with an image
+//! marking the changed pixels in red:
+//! - `visual-diff A B` compares two images
+//! - `visual-diff A baseline=NAME` compares with a baseline, storing A as the
+//!   baseline when there is none yet; `update` replaces it with A
+//! - `threshold=0.99` sets the pixel similarity needed to pass
+//!
+//! Images are PNG or JPEG files in the working directory or, on macOS, fresh
+//! captures given as `screen:N` or `window:ID`. The score is the share of
+//! pixels that match within a small tolerance, which absorbs compression noise;
+//! a difference hash of both images tells whether they look alike overall.
+
+use crate::llm::{Content, ImageSource};
+use crate::tools::ToolResult;
+use base64::{engine::general_purpose, Engine as _};
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+use std::path::PathBuf;
+
+/// Directory of the stored baselines
+pub const BASELINE_DIR: &str = ".termineer/baselines";
+
+/// Pixel similarity needed to pass when no `threshold` is given
+const DEFAULT_THRESHOLD: f64 = 0.99;
+
+/// Largest channel difference of pixels counted as unchanged
+const PIXEL_TOLERANCE: u8 = 24;
+
+/// Hash distance (of 64 bits) up to which images look alike
+const SIMILAR_HASH_DISTANCE: u32 = 10;
+
+/// Largest diff images sent to models; larger ones are scaled down
+const MAX_IMAGE_WIDTH: u32 = 1600;
+const MAX_IMAGE_HEIGHT: u32 = 1200;
+
+/// An image to compare
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    File(String),
+    Screen(usize),
+    Window(String),
+}
+
+impl Source {
+    fn parse(text: &str) -> Result<Self, String> {
+        if let Some(index) = text.strip_prefix("screen:") {
+            let index = index
+                .parse()
+                .map_err(|_| format!("Invalid screen index '{index}'"))?;
+            Ok(Source::Screen(index))
+        } else if let Some(id) = text.strip_prefix("window:") {
+            Ok(Source::Window(id.to_string()))
+        } else {
+            Ok(Source::File(text.to_string()))
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Source::File(path) => path.clone(),
+            Source::Screen(index) => format!("screen {index}"),
+            Source::Window(id) => format!("window '{id}'"),
+        }
+    }
+}
+
+/// What the first image is compared with
+#[derive(Debug, Clone, PartialEq)]
+enum Reference {
+    Image(Source),
+    Baseline { name: String, update: bool },
+}
+
+#[derive(Debug, PartialEq)]
+struct DiffArgs {
+    image: Source,
+    reference: Reference,
+    threshold: f64,
+}
+
+fn parse_args(args: &str) -> Result<DiffArgs, String> {
+    let usage = "Usage: visual-diff IMAGE IMAGE [threshold=0.99] or \
+                 visual-diff IMAGE baseline=NAME [update] [threshold=0.99]";
+    let mut sources = Vec::new();
+    let mut baseline = None;
+    let mut update = false;
+    let mut threshold = DEFAULT_THRESHOLD;
+
+    for word in args.split_whitespace() {
+        if let Some(value) = word.strip_prefix("threshold=") {
+            threshold = value
+                .parse::<f64>()
+                .ok()
+                .filter(|value| (0.0..=1.0).contains(value))
+                .ok_or_else(|| format!("Invalid threshold '{value}': expected 0 to 1"))?;
+        } else if let Some(name) = word.strip_prefix("baseline=") {
+            baseline = Some(name.to_string());
+        } else if word == "update" {
+            update = true;
+        } else {
+            sources.push(Source::parse(word)?);
+        }
+    }
+
+    let mut sources = sources.into_iter();
+    let image = sources.next().ok_or(usage)?;
+    let reference = match (baseline, sources.next()) {
+        (Some(name), None) => {
+            validate_baseline_name(&name)?;
+            Reference::Baseline { name, update }
+        }
+        (None, Some(other)) if !update => Reference::Image(other),
+        _ => return Err(usage.to_string()),
+    };
+    if sources.next().is_some() {
+        return Err(usage.to_string());
+    }
+
+    Ok(DiffArgs {
+        image,
+        reference,
+        threshold,
+    })
+}
+
+/// Baseline names become file names, so they may not leave the directory
+fn validate_baseline_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid baseline name '{name}': use letters, digits, '-', '_' and '.'"
+        ))
+    }
+}
+
+fn baseline_path(name: &str) -> PathBuf {
+    PathBuf::from(BASELINE_DIR).join(format!("{name}.png"))
+}
+
+/// Execute the visual-diff tool
+pub async fn execute_visual_diff(args: &str, _body: &str, silent_mode: bool) -> ToolResult {
+    let result = match parse_args(args) {
+        // Captures print through the task's output buffer, so stay on this task
+        Ok(args) => tokio::task::block_in_place(|| run(&args)),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(content) => {
+            if !silent_mode {
+                if let Some(Content::Text { text }) = content.first() {
+                    crate::bprintln!(tool: "visual-diff", "🔍 {}", text);
+                }
+            }
+            ToolResult::success_with_content(content)
+        }
+        Err(error_message) => {
+            if !silent_mode {
+                crate::bprintln!(error: "{}", error_message);
+            }
+            ToolResult::error(error_message)
+        }
+    }
+}
+
+fn run(args: &DiffArgs) -> Result<Vec<Content>, String> {
+    let image = load(&args.image)?;
+
+    let (reference, reference_name) = match &args.reference {
+        Reference::Image(source) => (load(source)?, source.describe()),
+        Reference::Baseline { name, update } => {
+            let path = baseline_path(name);
+            if *update || !path.exists() {
+                let existed = path.exists();
+                save_baseline(&image, &path)?;
+                let verb = if existed { "Updated" } else { "Created" };
+                return Ok(vec![Content::Text {
+                    text: format!(
+                        "{verb} baseline '{name}' ({}x{}) from {}",
+                        image.width(),
+                        image.height(),
+                        args.image.describe()
+                    ),
+                }]);
+            }
+            let baseline =
+                image::open(&path).map_err(|e| format!("Failed to read baseline '{name}': {e}"))?;
+            (baseline, format!("baseline '{name}'"))
+        }
+    };
+
+    let (comparison, diff) = compare(&image, &reference);
+    let mut content = vec![Content::Text {
+        text: comparison.report(&args.image.describe(), &reference_name, args.threshold),
+    }];
+    if comparison.changed > 0 {
+        content.push(Content::Image {
+            source: ImageSource::Base64 {
+                media_type: "image/png".to_string(),
+                data: encode(diff)?,
+            },
+        });
+    }
+    Ok(content)
+}
+
+fn load(source: &Source) -> Result<DynamicImage, String> {
+    match source {
+        Source::File(path) => {
+            let path = crate::tools::path_utils::validate_path(path)
+                .map_err(|e| format!("Cannot read {path}: {e}"))?;
+            image::open(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))
+        }
+        #[cfg(target_os = "macos")]
+        Source::Screen(index) => crate::tools::ui::macos::screenshot::capture_screen_image(*index)
+            .map(|image| image.image)
+            .map_err(|e| format!("Failed to capture {}: {e}", source.describe())),
+        #[cfg(target_os = "macos")]
+        Source::Window(id) => crate::tools::ui::macos::screenshot::capture_window_image(id)
+            .map(|image| image.image)
+            .map_err(|e| format!("Failed to capture {}: {e}", source.describe())),
+        #[cfg(not(target_os = "macos"))]
+        Source::Screen(_) | Source::Window(_) => Err(format!(
+            "Capturing {} is not implemented for {} platform; compare image files instead",
+            source.describe(),
+            std::env::consts::OS
+        )),
+    }
+}
+
+fn save_baseline(image: &DynamicImage, path: &std::path::Path) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    image
+        .save_with_format(path, image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Result of comparing two images
+#[derive(Debug, PartialEq)]
+struct Comparison {
+    /// Size of the first image; the second is scaled to it when they differ
+    size: (u32, u32),
+    /// Size of the second image
+    reference_size: (u32, u32),
+    /// Pixels differing by more than the tolerance
+    changed: u64,
+    /// Smallest rectangle (x, y, width, height) containing the changed pixels
+    bounds: Option<(u32, u32, u32, u32)>,
+    /// Bits differing between the difference hashes, of 64
+    hash_distance: u32,
+}
+
+impl Comparison {
+    /// Share of pixels that match
+    fn similarity(&self) -> f64 {
+        let total = self.size.0 as u64 * self.size.1 as u64;
+        if total == 0 {
+            return 1.0;
+        }
+        1.0 - self.changed as f64 / total as f64
+    }
+
+    fn report(&self, image: &str, reference: &str, threshold: f64) -> String {
+        let similarity = self.similarity();
+        let verdict = if similarity >= threshold {
+            "PASS"
+        } else {
+            "FAIL"
+        };
+        let mut report = format!(
+            "{verdict}: {image} matches {reference} to {:.2}% (threshold {:.2}%)",
+            similarity * 100.0,
+            threshold * 100.0
+        );
+        if self.size != self.reference_size {
+            report.push_str(&format!(
+                "\nSizes differ: {}x{} and {}x{}; the second image was scaled for the comparison",
+                self.size.0, self.size.1, self.reference_size.0, self.reference_size.1
+            ));
+        }
+        match self.bounds {
+            Some((x, y, width, height)) => report.push_str(&format!(
+                "\n{} pixels changed within x={x} y={y} width={width} height={height} (red in the diff image)",
+                self.changed
+            )),
+            None => report.push_str("\nNo pixels changed"),
+        }
+        let look = if self.hash_distance == 0 {
+            "identical"
+        } else if self.hash_distance <= SIMILAR_HASH_DISTANCE {
+            "similar"
+        } else {
+            "different"
+        };
+        report.push_str(&format!(
+            "\nPerceptual hash distance {}/64: the images look {look} overall",
+            self.hash_distance
+        ));
+        report
+    }
+}
+
+/// Compare two images pixel by pixel, returning the comparison and the diff
+/// image: the first image faded to gray with changed pixels in red
+fn compare(image: &DynamicImage, reference: &DynamicImage) -> (Comparison, RgbaImage) {
+    let (width, height) = image.dimensions();
+    let reference_pixels = if reference.dimensions() == (width, height) {
+        reference.to_rgba8()
+    } else {
+        reference
+            .resize_exact(width, height, FilterType::Triangle)
+            .to_rgba8()
+    };
+    let pixels = image.to_rgba8();
+
+    let mut diff = RgbaImage::new(width, height);
+    let mut changed = 0;
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for (x, y, pixel) in pixels.enumerate_pixels() {
+        let other = reference_pixels.get_pixel(x, y);
+        let differs = pixel
+            .0
+            .iter()
+            .zip(other.0.iter())
+            .any(|(a, b)| a.abs_diff(*b) > PIXEL_TOLERANCE);
+        if differs {
+            changed += 1;
+            bounds = Some(match bounds {
+                None => (x, y, x, y),
+                Some((left, top, right, bottom)) => {
+                    (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                }
+            });
+            diff.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+        } else {
+            let [r, g, b, _] = pixel.0;
+            let luma = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000;
+            let faded = (128 + luma / 2) as u8;
+            diff.put_pixel(x, y, Rgba([faded, faded, faded, 255]));
+        }
+    }
+
+    let comparison = Comparison {
+        size: (width, height),
+        reference_size: reference.dimensions(),
+        changed,
+        bounds: bounds
+            .map(|(left, top, right, bottom)| (left, top, right - left + 1, bottom - top + 1)),
+        hash_distance: (difference_hash(image) ^ difference_hash(reference)).count_ones(),
+    };
+    (comparison, diff)
+}
+
+/// 64-bit difference hash: whether each pixel of a 9x8 grayscale thumbnail is
+/// brighter than its right neighbour
+fn difference_hash(image: &DynamicImage) -> u64 {
+    let thumbnail = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if thumbnail.get_pixel(x, y).0[0] > thumbnail.get_pixel(x + 1, y).0[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+/// Encode a diff image as PNG, scaled down to the size sent to models
+fn encode(diff: RgbaImage) -> Result<String, String> {
+    let mut image = DynamicImage::ImageRgba8(diff);
+    if image.width() > MAX_IMAGE_WIDTH || image.height() > MAX_IMAGE_HEIGHT {
+        // Nearest neighbour keeps isolated changed pixels red
+        image = image.resize(MAX_IMAGE_WIDTH, MAX_IMAGE_HEIGHT, FilterType::Nearest);
+    }
+    let mut png = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode the diff image: {e}"))?;
+    Ok(general_purpose::STANDARD.encode(png))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args("before.png window:Safari:1 threshold=0.95").unwrap(),
+            DiffArgs {
+                image: Source::File("before.png".to_string()),
+                reference: Reference::Image(Source::Window("Safari:1".to_string())),
+                threshold: 0.95,
+            }
+        );
+        assert_eq!(
+            parse_args("screen:0 baseline=home update").unwrap(),
+            DiffArgs {
+                image: Source::Screen(0),
+                reference: Reference::Baseline {
+                    name: "home".to_string(),
+                    update: true
+                },
+                threshold: DEFAULT_THRESHOLD,
+            }
+        );
+        assert!(parse_args("a.png").is_err());
+        assert!(parse_args("a.png b.png update").is_err());
+        assert!(parse_args("a.png baseline=../secrets").is_err());
+        assert!(parse_args("a.png b.png threshold=2").is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let mut first = RgbaImage::from_pixel(100, 50, Rgba([255, 255, 255, 255]));
+        let second = DynamicImage::ImageRgba8(first.clone());
+        // Compression noise stays within the tolerance
+        first.put_pixel(0, 0, Rgba([250, 245, 255, 255]));
+        for x in 10..20 {
+            for y in 5..10 {
+                first.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+            }
+        }
+        let first = DynamicImage::ImageRgba8(first);
+
+        let (comparison, diff) = compare(&first, &second);
+        assert_eq!(comparison.changed, 50);
+        assert_eq!(comparison.bounds, Some((10, 5, 10, 5)));
+        assert_eq!(comparison.similarity(), 0.99);
+        assert_eq!(diff.get_pixel(12, 7), &Rgba([255, 0, 0, 255]));
+        assert_ne!(diff.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+        assert!(comparison
+            .report("after.png", "baseline 'home'", 0.99)
+            .starts_with("PASS: after.png matches baseline 'home' to 99.00%"));
+
+        let (same, _) = compare(&second, &second);
+        assert_eq!(same.changed, 0);
+        assert_eq!(same.hash_distance, 0);
+        assert!(same.report("a", "b", 1.0).contains("No pixels changed"));
+    }
+}
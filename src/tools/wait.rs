@@ -2,20 +2,257 @@
 //!
 //! This tool allows agents to signal they are ready to wait for messages
 //! from users or other agents, pausing their operation until input is received.
+//!
+//! With wake conditions (`--for-file <glob>`, `--for-pid <pid>`, `--seconds <n>`)
+//! the tool instead blocks until the condition is met and then resumes the agent
+//! automatically, which lets agents monitor builds or deployments.
 
 use crate::tools::ToolResult;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often wake conditions are checked
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Timeout for file/process conditions when `--seconds` is not given
+const DEFAULT_CONDITION_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Upper bound for any conditional wait
+const MAX_WAIT: Duration = Duration::from_secs(3600);
+
+/// Parsed wake conditions
+#[derive(Debug, Default, PartialEq)]
+struct WakeConditions {
+    file_pattern: Option<String>,
+    pid: Option<u32>,
+    seconds: Option<f64>,
+    reason: String,
+}
+
+impl WakeConditions {
+    fn is_conditional(&self) -> bool {
+        self.file_pattern.is_some() || self.pid.is_some() || self.seconds.is_some()
+    }
+
+    fn timeout(&self) -> Duration {
+        match self.seconds {
+            Some(seconds) => Duration::from_secs_f64(seconds.min(MAX_WAIT.as_secs_f64())),
+            None => DEFAULT_CONDITION_TIMEOUT,
+        }
+    }
+}
+
+/// Parse leading `--for-file`, `--for-pid` and `--seconds` options; the rest is the reason
+fn parse_conditions(args: &str) -> Result<WakeConditions, String> {
+    let mut conditions = WakeConditions::default();
+    let mut tokens = args.split_whitespace().peekable();
+
+    while let Some(token) = tokens.peek().copied() {
+        let (flag, inline_value) = match token.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (token, None),
+        };
+        if !matches!(flag, "--for-file" | "--for-pid" | "--seconds") {
+            break;
+        }
+        tokens.next();
+
+        let value = match inline_value {
+            Some(value) => value,
+            None => tokens
+                .next()
+                .map(str::to_string)
+                .ok_or_else(|| format!("Option {flag} requires a value"))?,
+        };
+
+        match flag {
+            "--for-file" => conditions.file_pattern = Some(value),
+            "--for-pid" => {
+                conditions.pid = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid process id '{value}'"))?,
+                )
+            }
+            _ => {
+                let seconds: f64 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid number of seconds '{value}'"))?;
+                if !seconds.is_finite() || seconds < 0.0 {
+                    return Err(format!("Invalid number of seconds '{value}'"));
+                }
+                conditions.seconds = Some(seconds);
+            }
+        }
+    }
+
+    conditions.reason = tokens.collect::<Vec<_>>().join(" ");
+    Ok(conditions)
+}
+
+/// Modification time and size of every file matching a glob
+fn snapshot_files(pattern: &str) -> Result<BTreeMap<String, (Option<SystemTime>, u64)>, String> {
+    let paths =
+        glob::glob(pattern).map_err(|e| format!("Invalid file pattern '{pattern}': {e}"))?;
+    let mut snapshot = BTreeMap::new();
+    for path in paths.flatten() {
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            snapshot.insert(
+                path.display().to_string(),
+                (metadata.modified().ok(), metadata.len()),
+            );
+        }
+    }
+    Ok(snapshot)
+}
+
+/// Paths that were added, removed or modified between two snapshots
+fn changed_files(
+    before: &BTreeMap<String, (Option<SystemTime>, u64)>,
+    after: &BTreeMap<String, (Option<SystemTime>, u64)>,
+) -> Vec<String> {
+    let mut changed: Vec<String> = after
+        .iter()
+        .filter(|(path, state)| before.get(*path) != Some(state))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.extend(
+        before
+            .keys()
+            .filter(|path| !after.contains_key(*path))
+            .map(|path| format!("{path} (removed)")),
+    );
+    changed
+}
+
+/// Whether a process with the given id is still running
+fn process_alive(pid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        std::path::Path::new(&format!("/proc/{pid}")).exists()
+    }
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        std::process::Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+            .unwrap_or(false)
+    }
+}
+
+/// Block until one of the conditions is met or the timeout expires
+async fn wait_for_conditions(conditions: &WakeConditions) -> Result<String, String> {
+    let started = Instant::now();
+    let timeout = conditions.timeout();
+
+    let initial_files = match &conditions.file_pattern {
+        Some(pattern) => Some(snapshot_files(pattern)?),
+        None => None,
+    };
+    if let Some(pid) = conditions.pid {
+        if !process_alive(pid) {
+            return Ok(format!("Process {pid} is not running"));
+        }
+    }
+
+    loop {
+        if let (Some(pattern), Some(initial)) = (&conditions.file_pattern, &initial_files) {
+            let changed = changed_files(initial, &snapshot_files(pattern)?);
+            if !changed.is_empty() {
+                return Ok(format!(
+                    "Files matching '{pattern}' changed after {:.1}s:\n{}",
+                    started.elapsed().as_secs_f64(),
+                    changed.join("\n")
+                ));
+            }
+        }
+
+        if let Some(pid) = conditions.pid {
+            if !process_alive(pid) {
+                return Ok(format!(
+                    "Process {pid} exited after {:.1}s",
+                    started.elapsed().as_secs_f64()
+                ));
+            }
+        }
+
+        let elapsed = started.elapsed();
+        if elapsed >= timeout {
+            return Ok(
+                if conditions.file_pattern.is_some() || conditions.pid.is_some() {
+                    format!(
+                        "Timed out after {:.1}s without the condition being met",
+                        elapsed.as_secs_f64()
+                    )
+                } else {
+                    format!("Waited {:.1}s", elapsed.as_secs_f64())
+                },
+            );
+        }
+
+        tokio::time::sleep(POLL_INTERVAL.min(timeout - elapsed)).await;
+    }
+}
 
 /// Execute the wait tool
-pub fn execute_wait(args: &str, body: &str, silent_mode: bool) -> ToolResult {
+pub async fn execute_wait(args: &str, body: &str, silent_mode: bool) -> ToolResult {
+    let conditions = match parse_conditions(args) {
+        Ok(conditions) => conditions,
+        Err(error_msg) => {
+            if !silent_mode {
+                bprintln!(error: "{}", error_msg);
+            }
+            return ToolResult::error(error_msg);
+        }
+    };
+
     // Get the reason for waiting from args (optional) or body
-    let wait_reason = if !args.trim().is_empty() {
-        args.trim().to_string()
+    let wait_reason = if !conditions.reason.is_empty() {
+        conditions.reason.clone()
     } else if !body.trim().is_empty() {
         body.trim().to_string()
     } else {
         "No specific reason provided".to_string()
     };
 
+    if conditions.is_conditional() {
+        if !silent_mode {
+            bprintln !(tool: "wait",
+                "{}⏳ Waiting:{} until condition is met (timeout {:.0}s): {}",
+                crate::constants::FORMAT_BOLD,
+                crate::constants::FORMAT_RESET,
+                conditions.timeout().as_secs_f64(),
+                wait_reason
+            );
+        }
+
+        return match wait_for_conditions(&conditions).await {
+            Ok(outcome) => {
+                if !silent_mode {
+                    bprintln !(tool: "wait", "▶️ Resumed: {}", outcome);
+                }
+                ToolResult::success(format!("Resumed: {outcome}"))
+            }
+            Err(error_msg) => {
+                if !silent_mode {
+                    bprintln!(error: "{}", error_msg);
+                }
+                ToolResult::error(error_msg)
+            }
+        };
+    }
+
     if !silent_mode {
         bprintln !(tool: "wait",
             "{}⏸️ Waiting:{} Agent will wait for messages: {}",
@@ -28,3 +265,46 @@ pub fn execute_wait(args: &str, body: &str, silent_mode: bool) -> ToolResult {
     // Return a tool result that puts the agent in waiting state
     ToolResult::wait(wait_reason)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_conditions() {
+        let conditions =
+            parse_conditions("--for-file target/*.log --seconds=30 build logs").unwrap();
+        assert_eq!(conditions.file_pattern.as_deref(), Some("target/*.log"));
+        assert_eq!(conditions.seconds, Some(30.0));
+        assert_eq!(conditions.reason, "build logs");
+        assert_eq!(conditions.timeout(), Duration::from_secs(30));
+
+        let conditions = parse_conditions("waiting for the user").unwrap();
+        assert!(!conditions.is_conditional());
+        assert_eq!(conditions.reason, "waiting for the user");
+
+        assert!(parse_conditions("--for-pid abc").is_err());
+        assert!(parse_conditions("--seconds").is_err());
+        assert!(parse_conditions("--seconds inf").is_err());
+        assert!(parse_conditions("--seconds NaN").is_err());
+
+        // Values too large for a Duration are clamped before conversion
+        let conditions = parse_conditions("--seconds 1e300").unwrap();
+        assert_eq!(conditions.timeout(), MAX_WAIT);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_exited_process() {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+
+        let conditions = WakeConditions {
+            pid: Some(pid),
+            seconds: Some(5.0),
+            ..Default::default()
+        };
+        let outcome = wait_for_conditions(&conditions).await.unwrap();
+        assert!(outcome.contains(&pid.to_string()));
+    }
+}
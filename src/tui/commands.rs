@@ -2,6 +2,7 @@
 
 use crate::agent::types::AgentCommand;
 use crate::agent::{AgentId, AgentMessage};
+use crate::llm::sampling::SamplingTask;
 use crate::tui::state::TuiState;
 
 /// Process slash commands
@@ -15,24 +16,7 @@ pub async fn process_command(state: &mut TuiState, input: &str) -> anyhow::Resul
     match command {
         "help" => {
             // Show help information
-            let help_text = obfstr::obfstring!(
-                "\
-            Available commands:
-            /help - Show this help information
-            /exit, /quit - Exit the application
-            /interrupt - Interrupt the current agent
-            /model MODEL - Set the model (e.g., claude-3-haiku-20240307)
-            /tools on|off - Enable or disable tools
-            /system TEXT - Set the system prompt
-            /reset - Reset the conversation
-            /thinking NUMBER - Set thinking budget in tokens (e.g., 10000)
-
-            Agent selection:
-            #ID or #NAME - Switch to agent by ID or name
-            "
-            );
-
-            show_command_result(state, "Help".to_string(), help_text);
+            show_command_result(state, t!("tui.title.help"), t!("tui.help"));
         }
 
         "exit" | "quit" => {
@@ -50,11 +34,7 @@ pub async fn process_command(state: &mut TuiState, input: &str) -> anyhow::Resul
 
         "model" => {
             if args.is_empty() {
-                show_command_result(
-                    state,
-                    "Error".to_string(),
-                    "Model name is required".to_string(),
-                );
+                show_command_result(state, t!("tui.title.error"), t!("tui.model_required"));
                 return Ok(());
             }
 
@@ -70,11 +50,7 @@ pub async fn process_command(state: &mut TuiState, input: &str) -> anyhow::Resul
                 "on" | "true" | "yes" | "1" => true,
                 "off" | "false" | "no" | "0" => false,
                 _ => {
-                    show_command_result(
-                        state,
-                        "Error".to_string(),
-                        "Invalid argument. Use 'on' or 'off'".to_string(),
-                    );
+                    show_command_result(state, t!("tui.title.error"), t!("tui.tools_invalid"));
                     return Ok(());
                 }
             };
@@ -86,16 +62,29 @@ pub async fn process_command(state: &mut TuiState, input: &str) -> anyhow::Resul
             crate::agent::send_message(state.selected_agent_id, AgentMessage::Command(cmd))?;
         }
 
-        "system" => {
-            if args.is_empty() {
+        "enable-tool" | "disable-tool" => {
+            if args.is_empty() || args.contains(char::is_whitespace) {
                 show_command_result(
                     state,
-                    "Error".to_string(),
-                    "System prompt is required".to_string(),
+                    t!("tui.title.error"),
+                    t!("tui.tool_name_required", command = command),
                 );
                 return Ok(());
             }
 
+            let cmd = AgentCommand::SetToolEnabled {
+                tool: args.to_string(),
+                enabled: command == "enable-tool",
+            };
+            crate::agent::send_message(state.selected_agent_id, AgentMessage::Command(cmd))?;
+        }
+
+        "system" => {
+            if args.is_empty() {
+                show_command_result(state, t!("tui.title.error"), t!("tui.system_required"));
+                return Ok(());
+            }
+
             // Create SetSystemPrompt command
             let cmd = AgentCommand::SetSystemPrompt(args.to_string());
 
@@ -114,22 +103,15 @@ pub async fn process_command(state: &mut TuiState, input: &str) -> anyhow::Resul
         "thinking" => {
             // Parse the thinking budget argument
             if args.is_empty() {
-                show_command_result(
-                    state,
-                    "Error".to_string(),
-                    "Thinking budget (number of tokens) is required".to_string(),
-                );
+                show_command_result(state, t!("tui.title.error"), t!("tui.thinking_required"));
                 return Ok(());
             }
 
             let budget = match args.parse::<usize>() {
                 Ok(value) => value,
+                Err(_) if args == "off" => 0,
                 Err(_) => {
-                    show_command_result(
-                        state,
-                        "Error".to_string(),
-                        "Invalid number format".to_string(),
-                    );
+                    show_command_result(state, t!("tui.title.error"), t!("tui.invalid_number"));
                     return Ok(());
                 }
             };
@@ -142,15 +124,266 @@ pub async fn process_command(state: &mut TuiState, input: &str) -> anyhow::Resul
             )?;
         }
 
+        "maxtokens" => {
+            let max_tokens = match args {
+                "" => {
+                    show_command_result(state, t!("tui.title.error"), t!("tui.maxtokens_required"));
+                    return Ok(());
+                }
+                "off" => None,
+                _ => match args.parse::<usize>() {
+                    Ok(value) if value > 0 => Some(value),
+                    _ => {
+                        show_command_result(state, t!("tui.title.error"), t!("tui.invalid_number"));
+                        return Ok(());
+                    }
+                },
+            };
+
+            crate::agent::send_message(
+                state.selected_agent_id,
+                AgentMessage::Command(AgentCommand::SetMaxTokens(max_tokens)),
+            )?;
+        }
+
+        "temp" => {
+            let mut parts = args.split_whitespace().collect::<Vec<_>>();
+            let Some(value) = parts.pop() else {
+                show_command_result(state, t!("tui.title.error"), t!("tui.temp_required"));
+                return Ok(());
+            };
+            let task = match parts.as_slice() {
+                [] => None,
+                [class] => match class.parse::<SamplingTask>() {
+                    Ok(task) => Some(task),
+                    Err(e) => {
+                        show_command_result(state, t!("tui.title.error"), e);
+                        return Ok(());
+                    }
+                },
+                _ => {
+                    show_command_result(state, t!("tui.title.error"), t!("tui.temp_usage"));
+                    return Ok(());
+                }
+            };
+            let temperature = match value {
+                "off" => None,
+                _ => match crate::llm::sampling::parse_temperature(value) {
+                    Ok(temperature) => Some(temperature),
+                    Err(e) => {
+                        show_command_result(state, t!("tui.title.error"), e);
+                        return Ok(());
+                    }
+                },
+            };
+
+            crate::agent::send_message(
+                state.selected_agent_id,
+                AgentMessage::Command(AgentCommand::SetTemperature { task, temperature }),
+            )?;
+        }
+
+        "export" => {
+            // Parse the file name and optional flags
+            let mut preserve_colors = false;
+            let mut conversation = false;
+            let mut path = None;
+            for arg in args.split_whitespace() {
+                match arg {
+                    "--color" | "--ansi" => preserve_colors = true,
+                    "--conversation" => conversation = true,
+                    _ => path = Some(arg),
+                }
+            }
+
+            let Some(path) = path else {
+                show_command_result(state, t!("tui.title.error"), t!("tui.export_file_required"));
+                return Ok(());
+            };
+
+            // The agent holds the conversation and writes it itself
+            if conversation {
+                let cmd = AgentCommand::ExportConversation(path.to_string());
+                crate::agent::send_message(state.selected_agent_id, AgentMessage::Command(cmd))?;
+                return Ok(());
+            }
+
+            let transcript = state.agent_buffer.export_transcript(preserve_colors);
+            let message = match std::fs::write(path, transcript) {
+                Ok(()) => t!("tui.transcript_saved", path = path),
+                Err(e) => t!("tui.transcript_failed", path = path, error = e),
+            };
+            show_command_result(state, t!("tui.title.export"), message);
+        }
+
+        "fork" => {
+            if args.is_empty() || args.contains(char::is_whitespace) {
+                show_command_result(state, t!("tui.title.error"), t!("tui.fork_name_required"));
+                return Ok(());
+            }
+            if crate::agent::get_agent_id_by_name(args).is_some() {
+                show_command_result(
+                    state,
+                    t!("tui.title.error"),
+                    t!("tui.agent_exists", name = args),
+                );
+                return Ok(());
+            }
+
+            // The agent clones itself once it has finished its current step
+            let cmd = AgentCommand::Fork(args.to_string());
+            crate::agent::send_message(state.selected_agent_id, AgentMessage::Command(cmd))?;
+            show_command_result(state, t!("tui.title.fork"), t!("tui.forking", name = args));
+        }
+
+        "handoff" => {
+            let mut parts = args.split_whitespace();
+            let (Some(kind), model) = (parts.next(), parts.next()) else {
+                show_command_result(
+                    state,
+                    t!("tui.title.error"),
+                    t!("tui.handoff_kind_required"),
+                );
+                return Ok(());
+            };
+            if !crate::prompts::is_valid_kind(kind) {
+                show_command_result(
+                    state,
+                    t!("tui.title.error"),
+                    t!("tui.unknown_kind", kind = kind),
+                );
+                return Ok(());
+            }
+
+            // Name the new agent after its kind, numbered if the name is taken
+            let base_name = kind.rsplit('/').next().unwrap_or(kind).to_string();
+            let mut name = base_name.clone();
+            let mut suffix = 2;
+            while crate::agent::get_agent_id_by_name(&name).is_some() {
+                name = format!("{base_name}-{suffix}");
+                suffix += 1;
+            }
+
+            let cmd = AgentCommand::Handoff {
+                name: name.clone(),
+                kind: kind.to_string(),
+                model: model.map(str::to_string),
+            };
+            crate::agent::send_message(state.selected_agent_id, AgentMessage::Command(cmd))?;
+
+            // Switch to the new agent once the summary is written and it is created
+            state.pending_selection = Some(name);
+        }
+
+        "export-workflow" => {
+            if args.is_empty() || args.contains(char::is_whitespace) {
+                show_command_result(
+                    state,
+                    t!("tui.title.error"),
+                    t!("tui.workflow_name_required"),
+                );
+                return Ok(());
+            }
+
+            // The agent drafts the workflow from what it recorded during the session
+            let cmd = AgentCommand::ExportWorkflow(args.to_string());
+            crate::agent::send_message(state.selected_agent_id, AgentMessage::Command(cmd))?;
+        }
+
+        "timings" => {
+            let cmd = AgentCommand::ShowTimings;
+            crate::agent::send_message(state.selected_agent_id, AgentMessage::Command(cmd))?;
+        }
+
+        "start" => {
+            let (name, details) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+            if name.is_empty() {
+                show_command_result(
+                    state,
+                    t!("tui.title.starters"),
+                    crate::starters::format_list(&crate::starters::list()),
+                );
+                return Ok(());
+            }
+            let starter = match crate::starters::load(name) {
+                Ok(starter) => starter,
+                Err(e) => {
+                    show_command_result(state, t!("tui.title.error"), e);
+                    return Ok(());
+                }
+            };
+            let prompt = starter.prompt_with(details);
+
+            match starter.kind {
+                Some(kind) => {
+                    if !crate::prompts::is_valid_kind(&kind) {
+                        show_command_result(
+                            state,
+                            t!("tui.title.error"),
+                            t!("tui.starter_unknown_kind", kind = kind, name = name),
+                        );
+                        return Ok(());
+                    }
+
+                    // The kind sets the system prompt, so the starter runs in a new agent
+                    let mut agent_name = starter.name.clone();
+                    let mut suffix = 2;
+                    while crate::agent::get_agent_id_by_name(&agent_name).is_some() {
+                        agent_name = format!("{}-{suffix}", starter.name);
+                        suffix += 1;
+                    }
+                    let cmd = AgentCommand::Start {
+                        name: agent_name.clone(),
+                        kind,
+                        model: starter.model,
+                        prompt,
+                    };
+                    crate::agent::send_message(
+                        state.selected_agent_id,
+                        AgentMessage::Command(cmd),
+                    )?;
+                    state.pending_selection = Some(agent_name);
+                }
+                None => {
+                    if let Some(model) = starter.model {
+                        let cmd = AgentCommand::SetModel(model);
+                        crate::agent::send_message(
+                            state.selected_agent_id,
+                            AgentMessage::Command(cmd),
+                        )?;
+                    }
+                    crate::agent::send_message(
+                        state.selected_agent_id,
+                        crate::share::user_message(prompt),
+                    )?;
+                }
+            }
+        }
+
+        "mcp" => match args {
+            "" | "status" => {
+                show_command_result(
+                    state,
+                    t!("tui.title.mcp"),
+                    crate::mcp::manager::format_status(),
+                );
+            }
+            _ => {
+                show_command_result(
+                    state,
+                    t!("tui.title.error"),
+                    t!("tui.unknown_mcp_command", command = args),
+                );
+            }
+        },
+
         // Unknown command
         _ => {
-            // Log error message to buffer
-            state
-                .agent_buffer
-                .stdout(&format!(
-                    "Unknown command: '{input}'. Type /help for available commands."
-                ))
-                .unwrap();
+            show_command_result(
+                state,
+                t!("tui.title.error"),
+                t!("tui.unknown_command", command = input),
+            );
         }
     }
 
@@ -160,7 +393,7 @@ pub async fn process_command(state: &mut TuiState, input: &str) -> anyhow::Resul
 /// Handle pound command for agent switching
 pub async fn handle_pound_command(state: &mut TuiState, cmd: &str) -> anyhow::Result<()> {
     // Create popup for command result
-    let command_title = format!("Agent Selection: {cmd}");
+    let command_title = t!("tui.title.agent_selection", command = cmd);
     let mut result = String::new();
 
     // Parse the agent number from the command
@@ -187,14 +420,14 @@ pub async fn handle_pound_command(state: &mut TuiState, cmd: &str) -> anyhow::Re
                     .iter()
                     .find(|(id, _)| *id == agent_id)
                     .map(|(_, name)| name.clone())
-                    .unwrap_or_else(|| "Unknown".to_string());
+                    .unwrap_or_else(|| t!("tui.unknown_agent"));
 
-                result.push_str(&format!("Switched to agent {agent_name} [{agent_id}]"));
+                result.push_str(&t!("tui.switched", name = agent_name, id = agent_id));
             } else {
-                result.push_str(&format!("Failed to get buffer for agent {agent_id}"));
+                result.push_str(&t!("tui.buffer_failed", agent = agent_id));
             }
         } else {
-            result.push_str(&format!("Agent with ID {agent_id} not found"));
+            result.push_str(&t!("tui.agent_id_not_found", id = agent_id));
         }
     } else {
         // Try to find agent by name
@@ -205,12 +438,12 @@ pub async fn handle_pound_command(state: &mut TuiState, cmd: &str) -> anyhow::Re
             // Update buffer to show the selected agent's output
             if let Ok(buffer) = crate::agent::get_agent_buffer(agent_id) {
                 state.agent_buffer = buffer;
-                result.push_str(&format!("Switched to agent {agent_str} [{agent_id}]"));
+                result.push_str(&t!("tui.switched", name = agent_str, id = agent_id));
             } else {
-                result.push_str(&format!("Failed to get buffer for agent {agent_str}"));
+                result.push_str(&t!("tui.buffer_failed", agent = agent_str));
             }
         } else {
-            result.push_str(&format!("Agent '{agent_str}' not found"));
+            result.push_str(&t!("tui.agent_not_found", name = agent_str));
         }
     }
 
@@ -220,7 +453,8 @@ pub async fn handle_pound_command(state: &mut TuiState, cmd: &str) -> anyhow::Re
     Ok(())
 }
 
-/// Show a command result in the temporary output
+/// Show a command result in the temporary output, which scrolls and searches
+/// results longer than the window
 pub fn show_command_result(state: &mut TuiState, title: String, content: String) {
     state.temp_output.show(title, content);
 }
@@ -1,31 +1,39 @@
 //! Event handling for the Terminal UI
 
-use crate::agent::AgentMessage;
+use crate::tools::permissions::PermissionDecision;
 use crate::tui::{commands, state::TuiState};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
 use std::time::{Duration, Instant};
 
 /// Handle key events
 pub async fn handle_key_event(state: &mut TuiState, key: KeyEvent) -> anyhow::Result<()> {
+    // A pending permission prompt captures the keyboard until answered
+    if state.permission_prompt_active {
+        handle_permission_key(state, key);
+        return Ok(());
+    }
+
+    // The temporary output captures the keyboard while shown, except for Ctrl+C
+    if state.temp_output.visible
+        && !(key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+    {
+        handle_output_key(state, key);
+        return Ok(());
+    }
+
     match key.code {
         // Multi-level interrupt with Ctrl+C
         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
             handle_ctrl_c_interrupt(state).await?;
         }
 
+        // Collapse or expand the plan panel with Ctrl+P
+        KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            state.plan_expanded = !state.plan_expanded;
+        }
+
         // Submit on Enter or insert newline with Shift+Enter
         KeyCode::Enter => {
-            // If temporary output is visible, dismiss it, reset state, and return
-            if state.temp_output.visible {
-                state.temp_output.hide();
-                // Clear input and hide suggestions when dismissing output
-                state.input.clear();
-                state.cursor_position = 0;
-                state.command_mode = false;
-                state.command_suggestions.hide();
-                return Ok(());
-            }
-
             // If Shift is held, insert a newline instead of submitting
             if key.modifiers.contains(KeyModifiers::SHIFT) {
                 state.input.insert(state.cursor_position, '\n');
@@ -73,7 +81,7 @@ pub async fn handle_key_event(state: &mut TuiState, key: KeyEvent) -> anyhow::Re
                     // Send to selected agent
                     crate::agent::send_message(
                         state.selected_agent_id,
-                        AgentMessage::UserInput(input),
+                        crate::share::user_message(input),
                     )?;
                 }
             }
@@ -81,11 +89,6 @@ pub async fn handle_key_event(state: &mut TuiState, key: KeyEvent) -> anyhow::Re
 
         // Backspace
         KeyCode::Backspace => {
-            // Ignore if temporary output is visible
-            if state.temp_output.visible {
-                return Ok(());
-            }
-
             if state.cursor_position > 0 {
                 state.input.remove(state.cursor_position - 1);
                 state.cursor_position -= 1;
@@ -100,11 +103,6 @@ pub async fn handle_key_event(state: &mut TuiState, key: KeyEvent) -> anyhow::Re
 
         // Delete
         KeyCode::Delete => {
-            // Ignore if temporary output is visible
-            if state.temp_output.visible {
-                return Ok(());
-            }
-
             if state.cursor_position < state.input.len() {
                 state.input.remove(state.cursor_position);
                 state.update_command_mode();
@@ -118,11 +116,6 @@ pub async fn handle_key_event(state: &mut TuiState, key: KeyEvent) -> anyhow::Re
 
         // Left arrow (with modifiers for macOS conventions)
         KeyCode::Left => {
-            // Ignore if temporary output is visible
-            if state.temp_output.visible {
-                return Ok(());
-            }
-
             // Command + Left: Move to beginning of line (macOS convention)
             if key.modifiers.contains(KeyModifiers::META) {
                 state.cursor_position = 0;
@@ -156,11 +149,6 @@ pub async fn handle_key_event(state: &mut TuiState, key: KeyEvent) -> anyhow::Re
 
         // Right arrow (with modifiers for macOS conventions)
         KeyCode::Right => {
-            // Ignore if temporary output is visible
-            if state.temp_output.visible {
-                return Ok(());
-            }
-
             // Command + Right: Move to end of line (macOS convention)
             if key.modifiers.contains(KeyModifiers::META) {
                 state.cursor_position = state.input.len();
@@ -196,9 +184,8 @@ pub async fn handle_key_event(state: &mut TuiState, key: KeyEvent) -> anyhow::Re
             if key.modifiers.contains(KeyModifiers::SHIFT) {
                 // Shift+Home: Scroll to top/oldest messages (offset = 0)
                 state.scroll_offset = 0;
-            } else if !state.temp_output.visible {
+            } else {
                 // Regular Home: Move cursor to start of input
-                // Only if temporary output is not visible
                 state.cursor_position = 0;
             }
         }
@@ -208,20 +195,14 @@ pub async fn handle_key_event(state: &mut TuiState, key: KeyEvent) -> anyhow::Re
             if key.modifiers.contains(KeyModifiers::SHIFT) {
                 // Shift+End: Scroll to bottom/newest messages (offset = max)
                 state.scroll_to_bottom();
-            } else if !state.temp_output.visible {
+            } else {
                 // Regular End: Move cursor to end of input
-                // Only if temporary output is not visible
                 state.cursor_position = state.input.len();
             }
         }
 
         // Regular character input
         KeyCode::Char(c) => {
-            // Ignore if temporary output is visible
-            if state.temp_output.visible {
-                return Ok(());
-            }
-
             // Handle Option+Right (commonly produces 'f' character in macOS terminal - "forward")
             if c == 'f' && key.modifiers.contains(KeyModifiers::ALT) {
                 // Move one word right
@@ -279,11 +260,6 @@ pub async fn handle_key_event(state: &mut TuiState, key: KeyEvent) -> anyhow::Re
 
         // Tab key for command completion
         KeyCode::Tab => {
-            // Ignore if temporary output is visible
-            if state.temp_output.visible {
-                return Ok(());
-            }
-
             // Only handle Tab in command mode with visible suggestions
             if state.command_mode && state.command_suggestions.visible {
                 // Get the currently selected command
@@ -306,11 +282,9 @@ pub async fn handle_key_event(state: &mut TuiState, key: KeyEvent) -> anyhow::Re
             }
         }
 
-        // Escape either dismisses temp output, hides suggestions, or clears the input
+        // Escape either hides suggestions or clears the input
         KeyCode::Esc => {
-            if state.temp_output.visible {
-                state.temp_output.hide();
-            } else if state.command_suggestions.visible {
+            if state.command_suggestions.visible {
                 state.command_suggestions.hide();
             } else {
                 // Clear input and reset history navigation
@@ -337,11 +311,6 @@ pub async fn handle_key_event(state: &mut TuiState, key: KeyEvent) -> anyhow::Re
 
         // Up arrow handling - navigate suggestions, history, or scroll
         KeyCode::Up => {
-            // Ignore if temporary output is visible
-            if state.temp_output.visible {
-                return Ok(());
-            }
-
             // If command suggestions are visible, navigate up through them
             if state.command_mode
                 && state.command_suggestions.visible
@@ -385,11 +354,6 @@ pub async fn handle_key_event(state: &mut TuiState, key: KeyEvent) -> anyhow::Re
 
         // Down arrow handling - navigate suggestions, history, or scroll
         KeyCode::Down => {
-            // Ignore if temporary output is visible
-            if state.temp_output.visible {
-                return Ok(());
-            }
-
             // If command suggestions are visible, navigate down through them
             if state.command_mode
                 && state.command_suggestions.visible
@@ -436,8 +400,92 @@ pub async fn handle_key_event(state: &mut TuiState, key: KeyEvent) -> anyhow::Re
     Ok(())
 }
 
+/// Handle a key press while a permission prompt is shown
+fn handle_permission_key(state: &mut TuiState, key: KeyEvent) {
+    let Some(decision) = permission_key_decision(key) else {
+        return;
+    };
+
+    crate::tools::permissions::respond(decision);
+    state.temp_output.hide();
+    state.permission_prompt_active = false;
+}
+
+/// Map a key press to a decision for the pending permission prompt
+fn permission_key_decision(key: KeyEvent) -> Option<PermissionDecision> {
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Char('Y') => Some(PermissionDecision::Allow),
+        // Enter denies, so a keystroke meant for the input cannot approve a tool call
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Enter => {
+            Some(PermissionDecision::Deny)
+        }
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(PermissionDecision::Deny)
+        }
+        KeyCode::Char('a') | KeyCode::Char('A') => Some(PermissionDecision::Always),
+        _ => None,
+    }
+}
+
+/// Handle a key press while the temporary output is shown: scroll, search,
+/// save or dismiss it
+fn handle_output_key(state: &mut TuiState, key: KeyEvent) {
+    let output = &mut state.temp_output;
+
+    // Typing a search query after `/`
+    if let Some(query) = output.search_input.as_mut() {
+        match key.code {
+            KeyCode::Enter => output.confirm_search(),
+            KeyCode::Esc => output.search_input = None,
+            KeyCode::Backspace => {
+                query.pop();
+            }
+            KeyCode::Char(c) => query.push(c),
+            _ => {}
+        }
+        return;
+    }
+
+    let page = output.page() as isize;
+    match key.code {
+        KeyCode::Up => output.scroll_by(-1),
+        KeyCode::Down => output.scroll_by(1),
+        KeyCode::PageUp => output.scroll_by(-page),
+        KeyCode::PageDown | KeyCode::Char(' ') => output.scroll_by(page),
+        KeyCode::Home => output.scroll = 0,
+        KeyCode::End => output.scroll = output.max_scroll(),
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => output.save(),
+        KeyCode::Char('/') => {
+            output.search_input = Some(String::new());
+            output.status = None;
+        }
+        KeyCode::Char('n') => output.find(true),
+        KeyCode::Char('N') => output.find(false),
+        KeyCode::Esc | KeyCode::Char('q') => output.hide(),
+        // Enter dismisses the output and starts over with a clear input
+        KeyCode::Enter => {
+            output.hide();
+            state.input.clear();
+            state.cursor_position = 0;
+            state.command_mode = false;
+            state.command_suggestions.hide();
+        }
+        _ => {}
+    }
+}
+
 /// Handle mouse events
 pub async fn handle_mouse_event(state: &mut TuiState, mouse: MouseEvent) -> anyhow::Result<()> {
+    // The wheel scrolls the temporary output while it is shown
+    if state.temp_output.visible {
+        match mouse.kind {
+            MouseEventKind::ScrollDown => state.temp_output.scroll_by(3),
+            MouseEventKind::ScrollUp => state.temp_output.scroll_by(-3),
+            _ => {}
+        }
+        return Ok(());
+    }
+
     // Simple mouse wheel scrolling implementation
     match mouse.kind {
         MouseEventKind::ScrollDown => {
@@ -455,7 +503,7 @@ pub async fn handle_mouse_event(state: &mut TuiState, mouse: MouseEvent) -> anyh
 }
 
 /// Handle Ctrl+C interrupt with multi-level behavior
-async fn handle_ctrl_c_interrupt(state: &mut TuiState) -> anyhow::Result<()> {
+pub async fn handle_ctrl_c_interrupt(state: &mut TuiState) -> anyhow::Result<()> {
     // Define the double-press window (3 seconds)
     const DOUBLE_PRESS_WINDOW: Duration = Duration::from_secs(3);
 
@@ -468,8 +516,8 @@ async fn handle_ctrl_c_interrupt(state: &mut TuiState) -> anyhow::Result<()> {
         if !state.last_interrupt_was_process && now.duration_since(last_time) < DOUBLE_PRESS_WINDOW
         {
             // This is a double-press, exit the application
-            let popup_title = "Exiting Application".to_string();
-            let popup_content = "Received second Ctrl+C. Exiting application...".to_string();
+            let popup_title = t!("tui.title.exiting");
+            let popup_content = t!("tui.exiting");
             commands::show_command_result(state, popup_title, popup_content);
 
             state.should_quit = true;
@@ -480,7 +528,7 @@ async fn handle_ctrl_c_interrupt(state: &mut TuiState) -> anyhow::Result<()> {
     // Get current agent state
     let agent_state = crate::agent::get_agent_state(state.selected_agent_id).ok();
 
-    let popup_title = "Interrupt".to_string();
+    let popup_title = t!("tui.title.interrupt");
     let popup_content;
 
     match agent_state {
@@ -504,7 +552,7 @@ async fn handle_ctrl_c_interrupt(state: &mut TuiState) -> anyhow::Result<()> {
 
         // If agent is waiting for input (idle or done), start the double-press timer
         _ => {
-            popup_content = "Press Ctrl+C again within 3 seconds to exit application.".to_string();
+            popup_content = t!("tui.press_again");
 
             // Start the double-press timer for exiting the application
             state.last_interrupt_time = Some(now);
@@ -517,3 +565,34 @@ async fn handle_ctrl_c_interrupt(state: &mut TuiState) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_keys() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(
+            permission_key_decision(key(KeyCode::Char('y'))),
+            Some(PermissionDecision::Allow)
+        );
+        assert_eq!(
+            permission_key_decision(key(KeyCode::Char('a'))),
+            Some(PermissionDecision::Always)
+        );
+        assert_eq!(
+            permission_key_decision(key(KeyCode::Enter)),
+            Some(PermissionDecision::Deny)
+        );
+        assert_eq!(
+            permission_key_decision(key(KeyCode::Esc)),
+            Some(PermissionDecision::Deny)
+        );
+        assert_eq!(
+            permission_key_decision(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(PermissionDecision::Deny)
+        );
+        assert_eq!(permission_key_decision(key(KeyCode::Char('x'))), None);
+    }
+}
@@ -67,12 +67,23 @@ impl TuiInterface {
             }
 
             // Ensure we have a valid agent selected before drawing
+            self.state.select_pending_agent();
             self.state.ensure_selected_agent_valid();
+            crate::control::set_active_agent(self.state.selected_agent_id);
+            self.state.update_activity();
+
+            // Surface permission requests from agents
+            if !self.state.permission_prompt_active {
+                if let Some((title, body)) = crate::tools::permissions::pending_prompt() {
+                    self.state.temp_output.show(title, body);
+                    self.state.permission_prompt_active = true;
+                }
+            }
 
             // Draw the UI after processing all pending events
             self.terminal.draw(|f| {
                 // Update visible height based on frame size
-                let content_height = f.size().height.saturating_sub(6) as usize; // Account for headers and borders
+                let content_height = f.size().height.saturating_sub(7) as usize; // Account for headers, status bar and borders
                 self.state.visible_height = content_height;
                 self.state.update_scroll();
                 rendering::render_ui(&self.state, f);
@@ -6,9 +6,11 @@
 mod commands;
 mod events;
 mod interface;
+mod plain;
 mod popup;
 mod rendering;
 mod state;
 
 // Re-export the main interface
 pub use interface::TuiInterface;
+pub use plain::PlainInterface;
@@ -0,0 +1,163 @@
+//! Plain line-based interface for screen readers and basic terminals
+//!
+//! Offers the same commands as the full-screen interface without the
+//! alternate screen: agent output is printed as plain lines without colors,
+//! input is read a line at a time, and changes of the selected agent's state
+//! are announced on lines of their own.
+
+use crate::agent::AgentId;
+use crate::tools::permissions::{self, PermissionDecision};
+use crate::tui::{commands, events, state::TuiState};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// How often the agent's state and permission requests are checked
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Line-based interface for the Termineer application
+pub struct PlainInterface {
+    /// Application state, shared with the commands of the full-screen interface
+    state: TuiState,
+}
+
+impl PlainInterface {
+    /// Create a new plain interface
+    pub fn new(main_agent_id: AgentId) -> Self {
+        let buffer = crate::agent::get_agent_buffer(main_agent_id).unwrap();
+        Self {
+            state: TuiState::new(main_agent_id, buffer),
+        }
+    }
+
+    /// Run the plain interface until the user exits
+    pub async fn run(&mut self) -> anyhow::Result<()> {
+        let mut input = BufReader::new(tokio::io::stdin()).lines();
+        let mut output = self.state.agent_buffer.reader(usize::MAX);
+        let mut output_agent = self.state.selected_agent_id;
+        let mut announced = String::new();
+        let mut poll = tokio::time::interval(POLL_INTERVAL);
+
+        println!("{}", t!("tui.plain.intro"));
+
+        while !self.state.should_quit {
+            tokio::select! {
+                lines = output.next_lines() => {
+                    for line in lines {
+                        println!("{}", crate::ansi_converter::strip_ansi_sequences(&line.content));
+                    }
+                }
+                line = input.next_line() => match line? {
+                    Some(line) => self.submit(line.trim_end().to_string()).await?,
+                    // End of input (Ctrl+D) exits like /exit
+                    None => self.state.should_quit = true,
+                },
+                _ = tokio::signal::ctrl_c() => {
+                    events::handle_ctrl_c_interrupt(&mut self.state).await?;
+                    self.print_command_result();
+                }
+                _ = poll.tick() => {}
+            }
+
+            // Follow the output of the agent switched to
+            self.state.select_pending_agent();
+            self.state.ensure_selected_agent_valid();
+            crate::control::set_active_agent(self.state.selected_agent_id);
+            if self.state.selected_agent_id != output_agent {
+                output_agent = self.state.selected_agent_id;
+                output = self.state.agent_buffer.reader(0);
+            }
+
+            // Announce state changes such as "Thinking..." and "Task completed"
+            let state = self.state.get_agent_state_string();
+            if state != announced {
+                let name = crate::agent::get_agents()
+                    .into_iter()
+                    .find_map(|(id, name)| (id == output_agent).then_some(name))
+                    .unwrap_or_else(|| t!("tui.unknown_agent"));
+                println!("{}", t!("tui.plain.state", name = name, state = state));
+                announced = state;
+            }
+
+            // Ask permission requests of agents on their own lines
+            if !self.state.permission_prompt_active {
+                if let Some((title, body)) = permissions::pending_prompt() {
+                    println!("{title}\n{body}");
+                    self.state.permission_prompt_active = true;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle a line of input the way the full-screen interface handles Enter
+    async fn submit(&mut self, input: String) -> anyhow::Result<()> {
+        if self.state.permission_prompt_active {
+            match permission_decision(&input) {
+                Some(decision) => {
+                    permissions::respond(decision);
+                    self.state.permission_prompt_active = false;
+                }
+                None => println!("{}", t!("tui.plain.permission_answer")),
+            }
+            return Ok(());
+        }
+
+        if input.is_empty() {
+            return Ok(());
+        }
+        if input.starts_with('/') {
+            commands::process_command(&mut self.state, &input).await?;
+        } else if input.starts_with('#') {
+            commands::handle_pound_command(&mut self.state, &input).await?;
+        } else {
+            crate::agent::send_message(
+                self.state.selected_agent_id,
+                crate::share::user_message(input),
+            )?;
+        }
+        self.print_command_result();
+        Ok(())
+    }
+
+    /// Print the output a command showed, as the popup of the full-screen interface
+    fn print_command_result(&mut self) {
+        let output = &mut self.state.temp_output;
+        if output.visible {
+            println!("{}", output.title);
+            for line in &output.content {
+                println!("{line}");
+            }
+            output.hide();
+        }
+    }
+}
+
+/// Decision of an answer to a permission request, the keys of the full-screen prompt
+fn permission_decision(answer: &str) -> Option<PermissionDecision> {
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Some(PermissionDecision::Allow),
+        // A bare Enter must not approve a tool call by accident
+        "" | "n" | "no" => Some(PermissionDecision::Deny),
+        "a" | "always" => Some(PermissionDecision::Always),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_answers() {
+        assert_eq!(permission_decision("y"), Some(PermissionDecision::Allow));
+        assert_eq!(
+            permission_decision(" Always "),
+            Some(PermissionDecision::Always)
+        );
+        assert_eq!(permission_decision("no"), Some(PermissionDecision::Deny));
+        // A bare Enter denies
+        assert_eq!(permission_decision(""), Some(PermissionDecision::Deny));
+        assert_eq!(permission_decision("maybe"), None);
+    }
+}
@@ -17,6 +17,8 @@ pub struct CommandSuggestionsPopup {
     pub filtered_commands: Vec<CommandSuggestion>,
     /// Currently selected command index
     pub selected_index: usize,
+    /// The project's starters, suggested after `/start `
+    pub starters: Vec<CommandSuggestion>,
     /// Whether the popup is visible
     pub visible: bool,
 }
@@ -28,39 +30,63 @@ impl CommandSuggestionsPopup {
         let all_commands = vec![
             CommandSuggestion {
                 name: "/help".to_string(),
-                description: "Show available commands".to_string(),
+                description: t!("tui.popup.help"),
             },
             CommandSuggestion {
                 name: "/exit".to_string(),
-                description: "Exit the application".to_string(),
+                description: t!("tui.popup.exit"),
             },
             CommandSuggestion {
                 name: "/quit".to_string(),
-                description: "Exit the application".to_string(),
+                description: t!("tui.popup.exit"),
             },
             CommandSuggestion {
                 name: "/interrupt".to_string(),
-                description: "Interrupt the current agent".to_string(),
+                description: t!("tui.popup.interrupt"),
             },
             CommandSuggestion {
                 name: "/model".to_string(),
-                description: "Set the model for the current agent".to_string(),
+                description: t!("tui.popup.model"),
             },
             CommandSuggestion {
                 name: "/tools".to_string(),
-                description: "Enable or disable tools".to_string(),
+                description: t!("tui.popup.tools"),
             },
             CommandSuggestion {
                 name: "/system".to_string(),
-                description: "Set the system prompt".to_string(),
+                description: t!("tui.popup.system"),
             },
             CommandSuggestion {
                 name: "/reset".to_string(),
-                description: "Reset the conversation".to_string(),
+                description: t!("tui.popup.reset"),
             },
             CommandSuggestion {
                 name: "/thinking".to_string(),
-                description: "Set the thinking budget in tokens".to_string(),
+                description: t!("tui.popup.thinking"),
+            },
+            CommandSuggestion {
+                name: "/export".to_string(),
+                description: t!("tui.popup.export"),
+            },
+            CommandSuggestion {
+                name: "/mcp".to_string(),
+                description: t!("tui.popup.mcp"),
+            },
+            CommandSuggestion {
+                name: "/fork".to_string(),
+                description: t!("tui.popup.fork"),
+            },
+            CommandSuggestion {
+                name: "/handoff".to_string(),
+                description: t!("tui.popup.handoff"),
+            },
+            CommandSuggestion {
+                name: "/start".to_string(),
+                description: t!("tui.popup.start"),
+            },
+            CommandSuggestion {
+                name: "/timings".to_string(),
+                description: t!("tui.popup.timings"),
             },
         ];
 
@@ -68,6 +94,7 @@ impl CommandSuggestionsPopup {
             all_commands: all_commands.clone(),
             filtered_commands: all_commands,
             selected_index: 0,
+            starters: Vec::new(),
             visible: false,
         }
     }
@@ -75,6 +102,14 @@ impl CommandSuggestionsPopup {
     /// Show the suggestions popup and filter based on current input
     pub fn show(&mut self, current_input: &str) {
         self.visible = true;
+        // Starters may have been added since the popup was last shown
+        self.starters = crate::starters::list()
+            .into_iter()
+            .map(|starter| CommandSuggestion {
+                name: format!("/start {}", starter.name),
+                description: starter.description,
+            })
+            .collect();
         self.update_suggestions(current_input);
     }
 
@@ -95,9 +130,15 @@ impl CommandSuggestionsPopup {
             return;
         }
 
+        // After `/start ` the starters are suggested instead of commands
+        let candidates = if search_text.starts_with("start ") {
+            &self.starters
+        } else {
+            &self.all_commands
+        };
+
         // Filter commands that match the input prefix
-        self.filtered_commands = self
-            .all_commands
+        self.filtered_commands = candidates
             .iter()
             .filter(|cmd| cmd.name.trim_start_matches('/').starts_with(search_text))
             .cloned()
@@ -4,4 +4,4 @@ mod commands;
 mod temporary;
 
 pub use commands::CommandSuggestionsPopup;
-pub use temporary::TemporaryOutput;
+pub use temporary::{match_ranges, TemporaryOutput};
@@ -1,5 +1,12 @@
 //! Temporary output window component
 
+use std::cell::Cell;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Directory where outputs are saved with Ctrl+S
+pub const SAVE_DIR: &str = ".termineer/output";
+
 /// Temporary output window that overlays the input area and can grow upward
 pub struct TemporaryOutput {
     /// Title of the output window
@@ -8,6 +15,17 @@ pub struct TemporaryOutput {
     pub content: Vec<String>,
     /// Whether the output is visible
     pub visible: bool,
+    /// Index of the first content line shown
+    pub scroll: usize,
+    /// Query being typed after `/`, while search input is active
+    pub search_input: Option<String>,
+    /// Last confirmed search query
+    pub query: String,
+    /// Result of the last search or save, shown at the bottom of the window
+    pub status: Option<String>,
+    /// Text width and height of the window at the last render, which sets
+    /// the page size and how far the content can scroll
+    viewport: Cell<(u16, usize)>,
 }
 
 impl TemporaryOutput {
@@ -17,6 +35,11 @@ impl TemporaryOutput {
             title: String::new(),
             content: Vec::new(),
             visible: false,
+            scroll: 0,
+            search_input: None,
+            query: String::new(),
+            status: None,
+            viewport: Cell::new((0, 0)),
         }
     }
 
@@ -24,17 +47,7 @@ impl TemporaryOutput {
     pub fn count_lines(&self, width: u16) -> usize {
         self.content
             .iter()
-            .map(|line| {
-                // Calculate how many display lines this content line will take
-                // with wrapping at the specified width
-                let chars = line.chars().count();
-                if chars == 0 {
-                    1 // Empty line still takes one line
-                } else {
-                    // Number of full lines plus one for any partial line
-                    (chars / width as usize) + if chars % width as usize > 0 { 1 } else { 0 }
-                }
-            })
+            .map(|line| wrapped_lines(line, width))
             .sum()
     }
 
@@ -48,5 +61,138 @@ impl TemporaryOutput {
         self.title = title;
         self.content = content.lines().map(String::from).collect();
         self.visible = true;
+        self.scroll = 0;
+        self.search_input = None;
+        self.query.clear();
+        self.status = None;
+    }
+
+    /// Remember the size of the window, called when it is rendered
+    pub fn set_viewport(&self, width: u16, height: usize) {
+        self.viewport.set((width, height));
+    }
+
+    /// Number of lines shown at once
+    pub fn page(&self) -> usize {
+        self.viewport.get().1.max(1)
+    }
+
+    /// First line to show so that the end of the content fills the window
+    pub fn max_scroll(&self) -> usize {
+        let (width, height) = self.viewport.get();
+        if width == 0 {
+            return self.content.len().saturating_sub(1);
+        }
+
+        let mut rows = 0;
+        for (index, line) in self.content.iter().enumerate().rev() {
+            rows += wrapped_lines(line, width);
+            if rows > height {
+                return index + 1;
+            }
+        }
+        0
+    }
+
+    /// Scroll by a number of lines, up when negative
+    pub fn scroll_by(&mut self, lines: isize) {
+        let max = self.max_scroll() as isize;
+        self.scroll = (self.scroll as isize + lines).clamp(0, max) as usize;
+    }
+
+    /// Search for the typed query from the first line shown
+    pub fn confirm_search(&mut self) {
+        let Some(query) = self.search_input.take() else {
+            return;
+        };
+        self.query = query;
+        self.status = None;
+        if !self.query.is_empty() {
+            self.jump_to_match(self.scroll, true);
+        }
+    }
+
+    /// Move to the next or previous match of the last query
+    pub fn find(&mut self, forward: bool) {
+        if self.query.is_empty() || self.content.is_empty() {
+            return;
+        }
+        let start = if forward {
+            self.scroll + 1
+        } else {
+            self.scroll + self.content.len() - 1
+        };
+        self.jump_to_match(start, forward);
+    }
+
+    /// Scroll to the first line matching the query, searching from `start`
+    /// and wrapping around the end of the content
+    fn jump_to_match(&mut self, start: usize, forward: bool) {
+        let matches: Vec<usize> = (0..self.content.len())
+            .filter(|&index| !match_ranges(&self.content[index], &self.query).is_empty())
+            .collect();
+
+        let len = self.content.len().max(1);
+        let found = (0..len)
+            .map(|step| {
+                if forward {
+                    (start + step) % len
+                } else {
+                    (start + len - step) % len
+                }
+            })
+            .find(|index| matches.contains(index));
+
+        self.status = Some(match found {
+            Some(line) => {
+                self.scroll = line.min(self.max_scroll());
+                let number = matches.iter().position(|&index| index == line).unwrap_or(0) + 1;
+                t!(
+                    "tui.output_match",
+                    index = number,
+                    count = matches.len(),
+                    query = self.query,
+                )
+            }
+            None => t!("tui.output_no_match", query = self.query),
+        });
+    }
+
+    /// Write the title and content to a file in the save directory
+    pub fn save(&mut self) {
+        let path = PathBuf::from(SAVE_DIR).join(format!(
+            "output-{}.txt",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let text = format!("{}\n\n{}\n", self.title, self.content.join("\n"));
+        let result = std::fs::create_dir_all(SAVE_DIR).and_then(|_| std::fs::write(&path, text));
+
+        self.status = Some(match result {
+            Ok(()) => t!("tui.output_saved", path = path.display()),
+            Err(e) => t!("tui.output_save_failed", path = path.display(), error = e),
+        });
+    }
+}
+
+/// Number of lines a content line takes when wrapped at a width
+fn wrapped_lines(line: &str, width: u16) -> usize {
+    let width = (width as usize).max(1);
+    let chars = line.chars().count();
+    if chars == 0 {
+        1 // Empty line still takes one line
+    } else {
+        // Number of full lines plus one for any partial line
+        chars.div_ceil(width)
+    }
+}
+
+/// Byte ranges of the occurrences of a query in a line, ignoring ASCII case
+pub fn match_ranges(line: &str, query: &str) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
     }
+    line.to_ascii_lowercase()
+        .match_indices(&query.to_ascii_lowercase())
+        .map(|(start, found)| start..start + found.len())
+        .collect()
 }
@@ -1,13 +1,20 @@
 //! Rendering functions for the Terminal UI components
 
+use crate::agent::AgentState;
+use crate::tools::todo::{Step, StepStatus};
+use crate::tui::popup::match_ranges;
 use crate::tui::state::TuiState;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+    widgets::{
+        block::{Position, Title},
+        Block, BorderType, Borders, Clear, Paragraph, Wrap,
+    },
     Frame,
 };
+use std::time::Duration;
 
 /// Rendering functions for the TUI
 pub fn render_ui(state: &TuiState, f: &mut Frame) {
@@ -28,6 +35,7 @@ pub fn render_ui(state: &TuiState, f: &mut Frame) {
         .constraints([
             Constraint::Length(3),            // Header
             Constraint::Min(1),               // Content (flexible)
+            Constraint::Length(1),            // Status bar
             Constraint::Length(input_height), // Dynamic-height input
         ])
         .split(size);
@@ -36,13 +44,35 @@ pub fn render_ui(state: &TuiState, f: &mut Frame) {
     f.render_widget(Clear, chunks[0]);
     render_header(state, f, chunks[0]);
 
-    // Render the content area with conversation history
-    f.render_widget(Clear, chunks[1]);
-    render_content(state, f, chunks[1]);
+    // Render the content area with conversation history, with the plan of the
+    // selected agent below it when there is one
+    let steps = crate::tools::todo::steps(state.selected_agent_id);
+    let content_area = if steps.is_empty() {
+        chunks[1]
+    } else {
+        let plan_height = if state.plan_expanded {
+            (steps.len() as u16 + 2).min(chunks[1].height / 2)
+        } else {
+            3
+        };
+        let content_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(plan_height)])
+            .split(chunks[1]);
+        f.render_widget(Clear, content_chunks[1]);
+        render_plan(state, &steps, f, content_chunks[1]);
+        content_chunks[0]
+    };
+    f.render_widget(Clear, content_area);
+    render_content(state, f, content_area);
 
-    // Render the input prompt
+    // Render the status bar with the selected agent's activity
     f.render_widget(Clear, chunks[2]);
-    render_input(state, f, chunks[2]);
+    render_status_bar(state, f, chunks[2]);
+
+    // Render the input prompt
+    f.render_widget(Clear, chunks[3]);
+    render_input(state, f, chunks[3]);
 
     // Render the command suggestions popup if in command mode and temp output is not visible
     if state.command_mode && !state.temp_output.visible {
@@ -51,7 +81,135 @@ pub fn render_ui(state: &TuiState, f: &mut Frame) {
 
     // Render the temporary output window if visible
     if state.temp_output.visible {
-        render_temp_output(state, f, chunks[2], chunks[1]);
+        render_temp_output(state, f, chunks[3], chunks[1]);
+    }
+}
+
+/// Render the selected agent's plan as a checklist, or a summary line when collapsed
+pub fn render_plan(state: &TuiState, steps: &[Step], f: &mut Frame, area: Rect) {
+    let done = steps
+        .iter()
+        .filter(|step| step.status == StepStatus::Done)
+        .count();
+    let title = t!("tui.plan", done = done, total = steps.len());
+
+    let step_line = |(index, step): (usize, &Step)| {
+        let (marker, style) = match step.status {
+            StepStatus::Done => ("✔", Style::default().fg(Color::Green)),
+            StepStatus::InProgress => (
+                "▶",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            StepStatus::Pending => ("○", Style::default().fg(Color::Gray)),
+        };
+        Line::from(Span::styled(
+            format!(" {marker} {}. {}", index + 1, step.text),
+            style,
+        ))
+    };
+
+    let lines: Vec<Line> = if state.plan_expanded {
+        steps.iter().enumerate().map(step_line).collect()
+    } else {
+        // Show the step in progress, or else the next pending one
+        steps
+            .iter()
+            .enumerate()
+            .find(|(_, step)| step.status == StepStatus::InProgress)
+            .or_else(|| {
+                steps
+                    .iter()
+                    .enumerate()
+                    .find(|(_, step)| step.status == StepStatus::Pending)
+            })
+            .map(step_line)
+            .into_iter()
+            .collect()
+    };
+
+    let plan = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(title),
+    );
+    f.render_widget(plan, area);
+}
+
+/// Render the status bar: what the selected agent is doing, with a spinner, the
+/// progress its tool reports and the elapsed time while it works, and the
+/// tokens it has used
+pub fn render_status_bar(state: &TuiState, f: &mut Frame, area: Rect) {
+    const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+    let Some((agent_id, agent_state, since)) = &state.activity else {
+        return;
+    };
+    let elapsed = since.elapsed();
+    let busy = matches!(
+        agent_state,
+        AgentState::Processing | AgentState::RunningTool { .. }
+    );
+
+    let indicator = if busy {
+        SPINNER[(elapsed.as_millis() / 100) as usize % SPINNER.len()]
+    } else {
+        TuiState::get_state_indicator(agent_state)
+    };
+    let activity = match agent_state {
+        AgentState::RunningTool { tool, detail, .. } if !detail.is_empty() => {
+            format!("{tool}: {detail}")
+        }
+        _ => agent_state.as_display_string(),
+    };
+
+    let mut parts = vec![format!("{indicator} {activity}")];
+    if let Ok(Some(progress)) = crate::agent::get_agent_progress(*agent_id) {
+        parts.push(progress.to_string());
+    }
+    if busy {
+        parts.push(t!("tui.status.elapsed", time = format_elapsed(elapsed)));
+    }
+    let usage = crate::agent::get_agent_usage(*agent_id).unwrap_or_default();
+    parts.push(t!(
+        "tui.status.tokens",
+        tokens = format_tokens(usage.input_tokens + usage.output_tokens),
+    ));
+
+    let style = if busy {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::Gray)
+    };
+    f.render_widget(
+        Paragraph::new(format!(" {}", parts.join(" — "))).style(style),
+        area,
+    );
+}
+
+/// Elapsed time as minutes and seconds, with hours once it reaches one
+fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// Token count shortened to thousands or millions
+fn format_tokens(tokens: usize) -> String {
+    match tokens {
+        0..=999 => tokens.to_string(),
+        1_000..=999_999 => format!("{:.1}k", tokens as f64 / 1_000.0),
+        _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
     }
 }
 
@@ -77,9 +235,42 @@ pub fn render_temp_output(state: &TuiState, f: &mut Frame, input_area: Rect, con
     // Clear the area
     f.render_widget(Clear, output_area);
 
+    // Show the content from the scroll position, with matches of the search highlighted
+    let output = &state.temp_output;
+    output.set_viewport(
+        output_area.width.saturating_sub(2),
+        output_area.height.saturating_sub(2) as usize,
+    );
+    let match_style = Style::default()
+        .fg(Color::Black)
+        .bg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let lines: Vec<Line> = output
+        .content
+        .iter()
+        .skip(output.scroll)
+        .map(|line| {
+            let mut spans = Vec::new();
+            let mut end = 0;
+            for range in match_ranges(line, &output.query) {
+                spans.push(Span::raw(line[end..range.start].to_string()));
+                spans.push(Span::styled(line[range.clone()].to_string(), match_style));
+                end = range.end;
+            }
+            spans.push(Span::raw(line[end..].to_string()));
+            Line::from(spans)
+        })
+        .collect();
+
+    // The bottom border shows the search being typed, the last search or save, or the keys
+    let footer = match (&output.search_input, &output.status) {
+        (Some(query), _) => format!("/{query}"),
+        (None, Some(status)) => status.clone(),
+        (None, None) => t!("tui.output_keys"),
+    };
+
     // Create the temporary output widget with dark orange styling
-    let content_text = state.temp_output.content.join("\n");
-    let output_widget = Paragraph::new(content_text)
+    let output_widget = Paragraph::new(lines)
         .style(
             Style::default()
                 .fg(Color::LightCyan) // More visible cyan text instead of white
@@ -90,15 +281,18 @@ pub fn render_temp_output(state: &TuiState, f: &mut Frame, input_area: Rect, con
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .border_style(Style::default().fg(Color::Rgb(255, 140, 0))) // Brighter orange border
-                .title(format!(
-                    "{} (Press ESC or Enter to dismiss)",
-                    state.temp_output.title
+                .title(t!(
+                    "tui.output_title",
+                    title = output.title,
+                    line = (output.scroll + 1).min(output.content.len()),
+                    total = output.content.len(),
                 ))
                 .title_style(
                     Style::default()
                         .fg(Color::Yellow)
                         .add_modifier(Modifier::BOLD),
-                ),
+                )
+                .title(Title::from(footer).position(Position::Bottom)),
         )
         .wrap(Wrap { trim: true });
 
@@ -204,7 +398,7 @@ pub fn render_command_suggestions(state: &TuiState, f: &mut Frame) {
     let suggestions_widget = Paragraph::new(content_lines).block(
         Block::default()
             .borders(Borders::ALL)
-            .title("Commands (TAB to complete)"),
+            .title(t!("tui.commands")),
     );
 
     // Render the suggestions
@@ -256,7 +450,7 @@ pub fn render_header(state: &TuiState, f: &mut Frame, area: Rect) {
         Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .title("Agents"),
+            .title(t!("tui.agents")),
     );
 
     f.render_widget(header, area);
@@ -315,20 +509,26 @@ pub fn render_content(state: &TuiState, f: &mut Frame, area: Rect) {
     // Create title with scroll info and most recent messages indicator
     let scroll_info = if total_lines > visible_height {
         let latest_indicator = if state.scroll_offset == state.max_scroll_offset {
-            " (Most Recent ↓)"
+            t!("tui.most_recent")
         } else {
-            ""
+            String::new()
         };
 
-        format!(
-            " | Scroll: {}/{}{}",
-            state.scroll_offset, state.max_scroll_offset, latest_indicator
+        t!(
+            "tui.scroll",
+            offset = state.scroll_offset,
+            max = state.max_scroll_offset,
+            latest = latest_indicator,
         )
     } else {
         String::new()
     };
 
-    let title = format!("Conversation ({} lines{})", total_lines, scroll_info);
+    let title = t!(
+        "tui.conversation",
+        lines = total_lines,
+        scroll = scroll_info
+    );
 
     let conversation = Paragraph::new(items).block(
         Block::default()
@@ -364,12 +564,14 @@ pub fn render_input(state: &TuiState, f: &mut Frame, area: Rect) {
                 None
             }
         })
-        .unwrap_or_else(|| "Unknown".to_string());
+        .unwrap_or_else(|| t!("tui.unknown_agent"));
 
     // Create title with agent state
-    let title = format!(
-        "Input [{} [{}] | {}]",
-        agent_name, state.selected_agent_id, agent_state_str
+    let title = t!(
+        "tui.input",
+        name = agent_name,
+        id = state.selected_agent_id,
+        state = agent_state_str,
     );
 
     // Create the input widget with text wrapping enabled
@@ -35,6 +35,9 @@ pub struct TuiState {
     pub max_scroll_offset: usize,
     /// Visible content height in lines
     pub visible_height: usize,
+    /// Agent whose output the scroll offset refers to, and the lines dropped
+    /// from the front of its history then
+    scroll_anchor: (AgentId, u64),
     /// Temporary output window that grows upward from the input area
     pub temp_output: TemporaryOutput,
     /// Command suggestions popup for auto-completion
@@ -45,6 +48,14 @@ pub struct TuiState {
     pub history_index: isize,
     /// Current input before history navigation began
     pub current_input: Option<String>,
+    /// Whether the temporary output currently shows a permission prompt
+    pub permission_prompt_active: bool,
+    /// Name of an agent that is being created and should be selected once it exists
+    pub pending_selection: Option<String>,
+    /// Whether the plan panel lists all steps or only a one-line summary
+    pub plan_expanded: bool,
+    /// State of the selected agent shown in the status bar, and when it began
+    pub activity: Option<(AgentId, AgentState, Instant)>,
 }
 
 impl TuiState {
@@ -63,11 +74,16 @@ impl TuiState {
             scroll_offset: 0,
             max_scroll_offset: 0,
             visible_height: 0,
+            scroll_anchor: (selected_agent_id, 0),
             temp_output: TemporaryOutput::new(),
             command_suggestions: CommandSuggestionsPopup::new(),
             command_history: Vec::new(),
             history_index: -1,
             current_input: None,
+            permission_prompt_active: false,
+            pending_selection: None,
+            plan_expanded: true,
+            activity: None,
         }
     }
 
@@ -116,10 +132,34 @@ impl TuiState {
         }
     }
 
+    /// Select the pending agent as soon as it has been created
+    pub fn select_pending_agent(&mut self) {
+        let Some(name) = &self.pending_selection else {
+            return;
+        };
+        let Some(agent_id) = crate::agent::get_agent_id_by_name(name) else {
+            return;
+        };
+        if let Ok(buffer) = crate::agent::get_agent_buffer(agent_id) {
+            self.selected_agent_id = agent_id;
+            self.agent_buffer = buffer;
+            self.pending_selection = None;
+        }
+    }
+
     /// Update scroll bounds based on current content and visible area
     pub fn update_scroll(&mut self) {
         let total_lines = self.agent_buffer.lines().len();
 
+        // Keep the same lines in view when old lines are dropped from the history
+        let dropped = self.agent_buffer.dropped();
+        let (anchor_agent, anchor_dropped) = self.scroll_anchor;
+        if anchor_agent == self.selected_agent_id && self.scroll_offset < self.max_scroll_offset {
+            let newly_dropped = dropped.saturating_sub(anchor_dropped) as usize;
+            self.scroll_offset = self.scroll_offset.saturating_sub(newly_dropped);
+        }
+        self.scroll_anchor = (self.selected_agent_id, dropped);
+
         // Calculate new max_scroll_offset
         let new_max_scroll_offset = if total_lines > self.visible_height {
             total_lines - self.visible_height
@@ -195,11 +235,11 @@ impl TuiState {
     /// Get a string representation of the selected agent's state
     pub fn get_agent_state_string(&self) -> String {
         if self.command_mode {
-            return "Command Mode".to_string();
+            return t!("tui.state.command_mode");
         }
 
         if self.pound_command_mode {
-            return "Agent Selection Mode".to_string();
+            return t!("tui.state.agent_selection");
         }
 
         // Try to get the state from the agent manager
@@ -208,7 +248,23 @@ impl TuiState {
         }
 
         // Fallback if we can't get the state
-        "Ready".to_string()
+        t!("tui.state.ready")
+    }
+
+    /// Follow the state of the selected agent, restarting the elapsed time of
+    /// the status bar when it changes
+    pub fn update_activity(&mut self) {
+        let Ok(agent_state) = crate::agent::get_agent_state(self.selected_agent_id) else {
+            self.activity = None;
+            return;
+        };
+        let changed = match &self.activity {
+            Some((id, state, _)) => *id != self.selected_agent_id || *state != agent_state,
+            None => true,
+        };
+        if changed {
+            self.activity = Some((self.selected_agent_id, agent_state, Instant::now()));
+        }
     }
 
     /// Get an emoji indicator for agent state
@@ -90,7 +90,7 @@ pub async fn check_for_updates() -> Result<(bool, String, Option<String>)> {
 
 /// Fetch the latest version from NPM registry
 async fn fetch_latest_version() -> Result<String> {
-    let client = reqwest::Client::builder()
+    let client = crate::net::client_builder()
         .timeout(Duration::from_secs(5))
         .build()?;
 
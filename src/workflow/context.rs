@@ -42,6 +42,9 @@ pub enum WorkflowError {
 
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
+
+    #[error("Output extraction failed: {0}")]
+    ExtractionError(String),
 }
 
 /// Context for workflow execution
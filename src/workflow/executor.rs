@@ -124,6 +124,10 @@ impl WorkflowExecutor {
 
         // Store output if specified
         if let Some(var_name) = &step.store_output {
+            let output = match &step.extract {
+                Some(query) => extract_output(&output, query)?,
+                None => output,
+            };
             context.set_variable(var_name.clone(), output.clone());
             println!("✅ Command output stored in variable: {}", var_name);
 
@@ -179,7 +183,7 @@ impl WorkflowExecutor {
         step: &Step,
         context: &mut WorkflowContext,
     ) -> Result<(), WorkflowError> {
-        use std::time::{Duration, Instant};
+        use std::time::Duration;
         use tokio::time::sleep;
 
         // Verify required fields
@@ -226,12 +230,13 @@ impl WorkflowExecutor {
         let new_agent_id = crate::agent::create_agent(agent_name, agent_config)
             .map_err(|e| WorkflowError::AgentError(format!("Failed to create agent: {}", e)))?;
 
-        // Set up buffer streaming for real-time feedback
-        let mut last_line_count = 0;
-        let mut buffer_check_time = Instant::now();
-        let buffer_check_interval = Duration::from_millis(100);
-        let state_check_interval = Duration::from_millis(500);
-        let mut state_check_time = Instant::now();
+        // Stream the agent's output for real-time feedback, and follow its state
+        let mut output = crate::agent::get_agent_buffer(new_agent_id)
+            .ok()
+            .map(|buffer| buffer.reader(usize::MAX));
+        let mut state = crate::agent::subscribe_agent_state(new_agent_id)
+            .map_err(|e| WorkflowError::AgentError(format!("Failed to follow agent: {}", e)))?;
+        state.borrow_and_update();
 
         // Send the message to the agent
         crate::agent::send_message(new_agent_id, AgentMessage::UserInput(rendered_prompt))
@@ -242,105 +247,57 @@ impl WorkflowExecutor {
 
         // Use a custom timeout of 5 minutes (300 seconds)
         let timeout_seconds = 300;
-        let timeout = Duration::from_secs(timeout_seconds);
-        let start_time = Instant::now();
-        let mut last_activity_time = Instant::now();
-
-        // Use a manual approach that combines buffer streaming and state checking
-        let mut response = String::new();
-        let mut done = false;
-
-        // Keep checking until we're done or reach timeout
-        while !done && start_time.elapsed() < timeout {
-            let mut had_activity = false;
-
-            // Sleep briefly to avoid tight polling
-            sleep(Duration::from_millis(50)).await;
-
-            // 1. Stream buffer updates only at certain intervals
-            if buffer_check_time.elapsed() >= buffer_check_interval {
-                buffer_check_time = Instant::now();
-
-                if let Ok(buffer) = crate::agent::get_agent_buffer(new_agent_id) {
-                    let lines = buffer.lines();
-                    let current_count = lines.len();
-
-                    // Check if we have new lines
-                    if current_count > last_line_count {
-                        had_activity = true;
-
-                        // Print new lines with a subtle prefix
-                        for i in last_line_count..current_count {
-                            if let Some(line) = lines.get(i) {
-                                // Filter out certain system messages for cleaner output
-                                if !line.content.starts_with("🤖")
-                                    && !line.content.contains("Token usage:")
-                                {
-                                    println!("│ {}", line.content);
-                                }
-                            }
-                        }
-                        last_line_count = current_count;
-                    }
+        let deadline = sleep(Duration::from_secs(timeout_seconds));
+        tokio::pin!(deadline);
+
+        // Stream output until the agent is done, is terminated or times out
+        let response = loop {
+            let new_lines = async {
+                match &mut output {
+                    Some(reader) => reader.next_lines().await,
+                    None => std::future::pending().await,
                 }
-            }
-
-            // 2. Check if agent is done (less frequently than buffer checks)
-            if state_check_time.elapsed() >= state_check_interval {
-                state_check_time = Instant::now();
-
-                if let Ok(state) = crate::agent::get_agent_state(new_agent_id) {
-                    match state {
-                        crate::agent::AgentState::Done(Some(content)) => {
-                            // Agent is done with a response
-                            response = content;
-                            done = true;
-                            break;
-                        }
-                        crate::agent::AgentState::Terminated => {
-                            // Agent was terminated
-                            return Err(WorkflowError::AgentError(
+            };
+            tokio::select! {
+                lines = new_lines => print_agent_output(lines),
+                changed = state.changed() => {
+                    let current = changed.ok().map(|_| state.borrow_and_update().clone());
+                    match current {
+                        // Agent is done with a response
+                        Some(crate::agent::AgentState::Done(Some(content))) => break Ok(content),
+                        // Agent was terminated, or its task ended
+                        Some(crate::agent::AgentState::Terminated) | None => {
+                            break Err(WorkflowError::AgentError(
                                 "Agent was terminated".to_string(),
                             ));
                         }
-                        crate::agent::AgentState::Processing
-                        | crate::agent::AgentState::RunningTool { .. } => {
-                            // These are active states, update activity timestamp
-                            had_activity = true;
-                        }
                         _ => {}
                     }
                 }
+                _ = &mut deadline => {
+                    break Err(WorkflowError::AgentError(format!(
+                        "Agent did not complete within {} seconds",
+                        timeout_seconds
+                    )));
+                }
             }
-
-            // Update the last activity time if we saw activity
-            if had_activity {
-                last_activity_time = Instant::now();
-            }
-
-            // Additional check: if no activity for 30 seconds, check more aggressively
-            if last_activity_time.elapsed() > Duration::from_secs(30) {
-                // Force check agent state at next iteration
-                state_check_time = Instant::now() - state_check_interval;
-            }
-        }
-
-        // If we reached here and we're not done, we timed out
-        if !done {
-            // Try to terminate the agent cleanly
-            let _ = crate::agent::send_message(new_agent_id, AgentMessage::Terminate);
-
-            return Err(WorkflowError::AgentError(format!(
-                "Agent did not complete within {} seconds",
-                timeout_seconds
-            )));
+        };
+        if let Some(reader) = &mut output {
+            print_agent_output(reader.pending());
         }
 
+        // Remove the agent, so running the workflow again may reuse its name
+        let _ = crate::agent::terminate_agent(new_agent_id).await;
+        let response = response?;
         println!("{}", "-".repeat(40));
         println!("✅ Agent task completed!");
 
         // Store response in the specified variable
         if let Some(var_name) = &step.into {
+            let response = match &step.extract {
+                Some(query) => extract_output(&response, query)?,
+                None => response,
+            };
             context.set_variable(var_name.clone(), response.clone());
             println!("Response stored in variable: {}", var_name);
 
@@ -364,12 +321,41 @@ impl WorkflowExecutor {
             );
         }
 
-        // Send a terminate message to the agent
-        if let Err(e) = crate::agent::send_message(new_agent_id, AgentMessage::Terminate) {
-            println!("Warning: Failed to send terminate message to agent: {}", e);
+        Ok(())
+    }
+}
+
+/// Print the output of a step's agent with a subtle prefix, filtering out
+/// certain system messages for cleaner output
+fn print_agent_output(lines: Vec<crate::output::OutputLine>) {
+    for line in lines {
+        if !line.content.starts_with("🤖") && !line.content.contains("Token usage:") {
+            println!("│ {}", line.content);
         }
+    }
+}
 
-        Ok(())
+/// Extract values from JSON step output with a JSONPath query
+///
+/// A single string match is stored as-is, any other single match as compact
+/// JSON, and multiple matches as a JSON array.
+fn extract_output(output: &str, query: &str) -> Result<String, WorkflowError> {
+    let json: serde_json::Value = serde_json::from_str(output.trim())
+        .map_err(|e| WorkflowError::ExtractionError(format!("output is not valid JSON: {}", e)))?;
+
+    let matches = crate::jsonpath::query(&json, query)
+        .map_err(|e| WorkflowError::ExtractionError(e.to_string()))?;
+
+    match matches.as_slice() {
+        [] => Err(WorkflowError::ExtractionError(format!(
+            "query '{}' matched nothing",
+            query
+        ))),
+        [serde_json::Value::String(text)] => Ok(text.clone()),
+        [value] => Ok(value.to_string()),
+        values => {
+            Ok(serde_json::Value::Array(values.iter().map(|v| (*v).clone()).collect()).to_string())
+        }
     }
 }
 
@@ -0,0 +1,253 @@
+//! Drafting workflows from interactive sessions
+//!
+//! An agent records the requests it receives and the tool calls that
+//! succeeded while handling them. `/export-workflow NAME` turns this record
+//! into a workflow draft in `.termineer/workflows/NAME.yaml`:
+//!
+//! - each request becomes an agent step whose prompt lists the tool calls
+//!   that worked in the session, and receives the previous step's result
+//! - the first request is replaced by `{{query}}`, so the workflow runs on a
+//!   new task given on the command line
+//! - shell commands run after the last edit of a request (builds, tests,
+//!   linters) become shell steps that check the result
+//!
+//! The draft is meant to be reviewed and edited before it is run.
+
+use crate::workflow::context::WorkflowError;
+use serde_yaml::{Mapping, Value};
+use std::path::PathBuf;
+
+/// Longest tool call summary listed in a prompt
+const MAX_ACTION_LENGTH: usize = 120;
+
+/// A successful tool call
+#[derive(Debug, Clone)]
+struct Action {
+    tool: String,
+    args: String,
+    body: String,
+}
+
+impl Action {
+    /// Command run by a shell call
+    fn command(&self) -> String {
+        match (self.args.trim(), self.body.trim()) {
+            (args, "") => args.to_string(),
+            ("", body) => body.to_string(),
+            (args, body) => format!("{args}\n{body}"),
+        }
+    }
+
+    /// One-line summary of the call for a prompt
+    fn summary(&self) -> String {
+        let detail = if self.tool == "shell" {
+            self.command()
+        } else if self.args.trim().is_empty() {
+            self.body.trim().to_string()
+        } else {
+            self.args.trim().to_string()
+        };
+        let detail = detail.lines().next().unwrap_or("").to_string();
+        let summary = format!("{} {}", self.tool, detail);
+        if summary.chars().count() > MAX_ACTION_LENGTH {
+            let cut: String = summary.chars().take(MAX_ACTION_LENGTH).collect();
+            format!("{cut}...")
+        } else {
+            summary.trim_end().to_string()
+        }
+    }
+}
+
+/// A request and the tool calls that succeeded while handling it
+#[derive(Debug, Clone)]
+struct Turn {
+    request: String,
+    actions: Vec<Action>,
+}
+
+/// Requests and successful tool calls of a session, in order
+#[derive(Debug, Clone, Default)]
+pub struct SessionRecord {
+    turns: Vec<Turn>,
+}
+
+impl SessionRecord {
+    /// Start recording the handling of a new request
+    pub fn request(&mut self, request: &str) {
+        self.turns.push(Turn {
+            request: request.trim().to_string(),
+            actions: Vec::new(),
+        });
+    }
+
+    /// Record a tool call that succeeded
+    pub fn succeeded(&mut self, tool: &str, args: &str, body: &str) {
+        // The final answer is not a step to repeat
+        if tool == "done" {
+            return;
+        }
+        if let Some(turn) = self.turns.last_mut() {
+            turn.actions.push(Action {
+                tool: tool.to_string(),
+                args: args.to_string(),
+                body: body.to_string(),
+            });
+        }
+    }
+
+    /// Forget the session, when the conversation is reset
+    pub fn clear(&mut self) {
+        self.turns.clear();
+    }
+
+    /// Workflow YAML drafted from the session
+    pub fn to_yaml(&self, name: &str, kind: Option<&str>) -> Result<String, WorkflowError> {
+        let first = self.turns.first().ok_or_else(|| {
+            WorkflowError::InvalidConfig("The session has no requests yet".to_string())
+        })?;
+
+        let mut steps = Vec::new();
+        for (index, turn) in self.turns.iter().enumerate() {
+            let number = index + 1;
+            let mut prompt = if index == 0 {
+                "{{query}}".to_string()
+            } else {
+                format!(
+                    "{}\n\nResult of the previous step:\n{{{{step{index}}}}}",
+                    turn.request
+                )
+            };
+            if !turn.actions.is_empty() {
+                prompt.push_str("\n\nSteps that worked when this was done before:\n");
+                for (i, action) in turn.actions.iter().enumerate() {
+                    prompt.push_str(&format!("{}. {}\n", i + 1, action.summary()));
+                }
+            }
+
+            let mut step = Mapping::new();
+            step.insert("agent".into(), first_line(&turn.request).into());
+            if let Some(kind) = kind {
+                step.insert("kind".into(), kind.into());
+            }
+            step.insert("prompt".into(), prompt.trim_end().into());
+            step.insert("into".into(), format!("step{number}").into());
+            steps.push(Value::Mapping(step));
+
+            for (check, command) in checks(&turn.actions).into_iter().enumerate() {
+                let mut step = Mapping::new();
+                step.insert(
+                    "shell".into(),
+                    format!("Check {number}.{}", check + 1).into(),
+                );
+                step.insert("command".into(), command.into());
+                steps.push(Value::Mapping(step));
+            }
+        }
+
+        let mut workflow = Mapping::new();
+        workflow.insert("name".into(), name.into());
+        workflow.insert(
+            "description".into(),
+            format!("Drafted from a session: {}", first_line(&first.request)).into(),
+        );
+        workflow.insert("steps".into(), Value::Sequence(steps));
+
+        let yaml = serde_yaml::to_string(&workflow)?;
+        Ok(format!(
+            "# Drafted with /export-workflow; review the prompts and commands before running:\n\
+             #   termineer workflow {name} \"<task>\"\n\
+             # The first request of the session is replaced by {{{{query}}}}, the task given when it runs.\n\
+             {yaml}"
+        ))
+    }
+}
+
+/// Shell commands run after the last edit of a request, which check its result
+fn checks(actions: &[Action]) -> Vec<String> {
+    let Some(last_edit) = actions
+        .iter()
+        .rposition(|action| matches!(action.tool.as_str(), "write" | "patch"))
+    else {
+        return Vec::new();
+    };
+    let mut commands: Vec<String> = Vec::new();
+    for action in &actions[last_edit + 1..] {
+        let command = action.command();
+        if action.tool == "shell" && !command.is_empty() && !commands.contains(&command) {
+            commands.push(command);
+        }
+    }
+    commands
+}
+
+/// First line of a request, shortened to name a step
+fn first_line(text: &str) -> String {
+    let line = text.lines().next().unwrap_or("").trim();
+    if line.chars().count() > 60 {
+        let cut: String = line.chars().take(60).collect();
+        format!("{}...", cut.trim_end())
+    } else {
+        line.to_string()
+    }
+}
+
+/// Save a drafted workflow as `.termineer/workflows/NAME.yaml`, never overwriting one
+pub fn save(name: &str, yaml: &str) -> Result<PathBuf, WorkflowError> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(WorkflowError::InvalidConfig(format!(
+            "Invalid workflow name: '{name}'"
+        )));
+    }
+    let dir = PathBuf::from(".termineer").join("workflows");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{name}.yaml"));
+    if path.exists() {
+        return Err(WorkflowError::InvalidConfig(format!(
+            "{} already exists; choose another name",
+            path.display()
+        )));
+    }
+    std::fs::write(&path, yaml)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::types::{StepType, Workflow};
+
+    #[test]
+    fn test_to_yaml() {
+        let mut record = SessionRecord::default();
+        assert!(record.to_yaml("fix", None).is_err());
+
+        record.request("Fix the flaky login test");
+        record.succeeded("read", "tests/login.rs", "");
+        record.succeeded("shell", "cargo test login", "");
+        record.succeeded("patch", "tests/login.rs", "...");
+        record.succeeded("shell", "cargo test login", "");
+        record.succeeded("done", "", "Fixed");
+        record.request("Add a changelog entry");
+        record.succeeded("write", "CHANGELOG.md", "- Fix flaky login test");
+
+        let yaml = record.to_yaml("fix-flaky-test", Some("coder")).unwrap();
+        let workflow: Workflow = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(workflow.name, "fix-flaky-test");
+
+        let types: Vec<StepType> = workflow.steps.iter().map(|s| s.get_type()).collect();
+        assert_eq!(types, [StepType::Agent, StepType::Shell, StepType::Agent]);
+
+        let first = workflow.steps[0].prompt.as_deref().unwrap();
+        assert!(first.starts_with("{{query}}"));
+        assert!(first.contains("4. shell cargo test login"));
+        assert!(!first.contains("done"));
+        assert_eq!(
+            workflow.steps[1].command.as_deref(),
+            Some("cargo test login")
+        );
+
+        let second = workflow.steps[2].prompt.as_deref().unwrap();
+        assert!(second.contains("Add a changelog entry"));
+        assert!(second.contains("{{step1}}"));
+    }
+}
@@ -7,6 +7,7 @@
 pub mod cli;
 pub mod context;
 pub mod executor;
+pub mod export;
 pub mod loader;
 pub mod types;
 
@@ -83,6 +83,9 @@ pub struct Step {
     pub prompt: Option<String>,
     pub into: Option<String>,
 
+    /// JSONPath query applied to the step output (parsed as JSON) before it is stored
+    pub extract: Option<String>,
+
     /// Keep fields for message, file, output, and wait steps to maintain deserializing
     /// compatibility with existing workflow files, even though we don't use them
     #[serde(rename = "message")]